@@ -1,7 +1,9 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use uuid::Uuid;
-use xlink::core::types::{ChannelType, DeviceId};
+use xlink::core::types::{ChannelType, DeviceId, GroupId, MessagePayload};
+use xlink::crypto::context::EncryptionContext;
 use xlink::crypto::engine::CryptoEngine;
+use xlink::crypto::treekem::TreeKemEngine;
 use xlink::router::predictor::RoutePredictor;
 
 fn crypto_benchmark(c: &mut Criterion) {
@@ -9,6 +11,8 @@ fn crypto_benchmark(c: &mut Criterion) {
     let device_id = DeviceId(Uuid::new_v4());
     let data = vec![0u8; 1024]; // 1KB data
 
+    let context = EncryptionContext::direct(DeviceId(Uuid::new_v4()), device_id);
+
     // 预先建立会话，否则加密会失败
     let other_public_key = x25519_dalek::PublicKey::from(
         &x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng),
@@ -20,12 +24,54 @@ fn crypto_benchmark(c: &mut Criterion) {
     c.bench_function("encrypt_1kb", |b| {
         b.iter(|| {
             engine
-                .encrypt(black_box(&device_id), black_box(&data))
+                .encrypt(black_box(&device_id), black_box(&data), &context)
                 .unwrap()
         })
     });
 }
 
+fn group_crypto_benchmark(c: &mut Criterion) {
+    const MESSAGES_PER_EPOCH: usize = 10_000;
+
+    let sender = DeviceId(Uuid::new_v4());
+    let engine = TreeKemEngine::new(sender);
+    let group_id = GroupId(Uuid::new_v4());
+    engine.create_group(group_id, vec![sender]).unwrap();
+    let payload = MessagePayload::Binary(vec![0u8; 256]);
+
+    let mut group = c.benchmark_group("group_10k_messages_one_epoch");
+    group.sample_size(10);
+    // 缓存命中：同一纪元内复用消息密钥
+    group.bench_function("cached_key", |b| {
+        b.iter(|| {
+            for _ in 0..MESSAGES_PER_EPOCH {
+                let encrypted = engine
+                    .encrypt_group_message(group_id, sender, black_box(&payload))
+                    .unwrap();
+                engine
+                    .decrypt_group_message(group_id, sender, &encrypted)
+                    .unwrap();
+            }
+        })
+    });
+    // 对照：每次加解密前丢弃缓存，相当于逐条重新初始化密钥
+    group.bench_function("uncached_key", |b| {
+        b.iter(|| {
+            for _ in 0..MESSAGES_PER_EPOCH {
+                engine.invalidate_message_key(group_id);
+                let encrypted = engine
+                    .encrypt_group_message(group_id, sender, black_box(&payload))
+                    .unwrap();
+                engine.invalidate_message_key(group_id);
+                engine
+                    .decrypt_group_message(group_id, sender, &encrypted)
+                    .unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
 fn predictor_benchmark(c: &mut Criterion) {
     let predictor = RoutePredictor::new();
     let device_id = DeviceId(Uuid::new_v4());
@@ -45,5 +91,10 @@ fn predictor_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, crypto_benchmark, predictor_benchmark);
+criterion_group!(
+    benches,
+    crypto_benchmark,
+    group_crypto_benchmark,
+    predictor_benchmark
+);
 criterion_main!(benches);
@@ -66,6 +66,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             data_cost_sensitive: false,
         });
 
+    // Register their public keys (obtained via key exchange in a real deployment)
+    for member_id in [bob_id, carol_id] {
+        let secret = x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng);
+        sdk.register_device_key(member_id, x25519_dalek::PublicKey::from(&secret))?;
+    }
+
     // 3. Create Group
     log::info!("Creating group with Bob and Carol...");
     let group_members = vec![bob_id, carol_id];
@@ -0,0 +1,215 @@
+//! SDK 构建器
+//!
+//! `XLinkBuilder` 在创建实例前集中设置存储后端、合规、限流、压缩、时钟与初始插件，
+//! 并在 `build` 时校验互斥的选项。`XLink::new`、`with_storage` 等构造函数均委托给它。
+
+use crate::core::clock::{system_clock, Clock};
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::{Channel, Plugin, Storage};
+use crate::core::types::{
+    AppQueueConfig, ComplianceConfig, CompressionConfig, DeviceCapabilities, RateLimitConfig,
+};
+use crate::storage::file_store::FileStorage;
+use crate::XLink;
+use std::sync::Arc;
+
+/// 未指定存储后端时使用的文件存储目录
+const DEFAULT_STORAGE_PATH: &str = "storage";
+
+/// 存储后端的来源
+enum StorageChoice {
+    /// 以指定目录打开文件存储
+    Path(String),
+    /// 调用方提供的存储实现
+    Custom(Arc<dyn Storage>),
+}
+
+/// 以链式调用配置并创建 [`XLink`] 实例
+///
+/// ```ignore
+/// let sdk = XLink::builder(capabilities)
+///     .channels(channels)
+///     .storage_path("data")
+///     .encrypted_storage(true)
+///     .storage_key([7u8; 32])
+///     .rate_limit(RateLimitConfig { per_sender_per_sec: 50, ..Default::default() })
+///     .build()
+///     .await?;
+/// ```
+pub struct XLinkBuilder {
+    capabilities: DeviceCapabilities,
+    channels: Vec<Arc<dyn Channel>>,
+    storage: Vec<StorageChoice>,
+    encrypted: bool,
+    encryption_key: Option<[u8; 32]>,
+    app_queue: AppQueueConfig,
+    compliance: Option<ComplianceConfig>,
+    rate_limit: Option<RateLimitConfig>,
+    compression: Option<CompressionConfig>,
+    plugins: Vec<Arc<dyn Plugin>>,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl XLinkBuilder {
+    pub fn new(capabilities: DeviceCapabilities) -> Self {
+        Self {
+            capabilities,
+            channels: Vec::new(),
+            storage: Vec::new(),
+            encrypted: false,
+            encryption_key: None,
+            app_queue: AppQueueConfig::default(),
+            compliance: None,
+            rate_limit: None,
+            compression: None,
+            plugins: Vec::new(),
+            clock: None,
+        }
+    }
+
+    /// 追加通信通道
+    pub fn channels(mut self, channels: impl IntoIterator<Item = Arc<dyn Channel>>) -> Self {
+        self.channels.extend(channels);
+        self
+    }
+
+    /// 追加单个通信通道
+    pub fn channel(mut self, channel: Arc<dyn Channel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// 使用指定目录下的文件存储，未设置存储时默认使用 `storage` 目录
+    pub fn storage_path(mut self, path: impl Into<String>) -> Self {
+        self.storage.push(StorageChoice::Path(path.into()));
+        self
+    }
+
+    /// 使用自定义存储实现，与 [`storage_path`](Self::storage_path) 互斥
+    pub fn storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage.push(StorageChoice::Custom(storage));
+        self
+    }
+
+    /// 加密文件存储中的消息记录，需同时以 [`storage_key`](Self::storage_key) 提供密钥
+    pub fn encrypted_storage(mut self, encrypted: bool) -> Self {
+        self.encrypted = encrypted;
+        self
+    }
+
+    /// 文件存储的加密密钥
+    pub fn storage_key(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// 应用接收队列配置
+    pub fn app_queue(mut self, config: AppQueueConfig) -> Self {
+        self.app_queue = config;
+        self
+    }
+
+    /// 合规性配置（隐私模式、保留天数、加密等级）
+    pub fn compliance(mut self, config: ComplianceConfig) -> Self {
+        self.compliance = Some(config);
+        self
+    }
+
+    /// 收发限流配置
+    pub fn rate_limit(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limit = Some(config);
+        self
+    }
+
+    /// 消息压缩配置
+    pub fn compression(mut self, config: CompressionConfig) -> Self {
+        self.compression = Some(config);
+        self
+    }
+
+    /// 创建后按顺序注册的插件
+    pub fn plugin(mut self, plugin: Arc<dyn Plugin>) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    /// 心跳、流会话、群组、限流与消息时间戳使用的时钟，默认读取系统时间；测试可注入 `MockClock`
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// 校验配置、打开存储并创建 SDK 实例
+    ///
+    /// 同时设置多个存储后端、加密自定义存储、启用加密但未提供密钥，
+    /// 或提供密钥但未启用加密时返回 `invalid_input`
+    pub async fn build(mut self) -> Result<XLink> {
+        if self.storage.len() > 1 {
+            return Err(XLinkError::invalid_input(
+                "storage",
+                "storage_path and storage are mutually exclusive and may be set once",
+                file!(),
+            ));
+        }
+        let choice = self
+            .storage
+            .pop()
+            .unwrap_or_else(|| StorageChoice::Path(DEFAULT_STORAGE_PATH.to_string()));
+
+        if self.encrypted && matches!(choice, StorageChoice::Custom(_)) {
+            return Err(XLinkError::invalid_input(
+                "encrypted_storage",
+                "only file storage can be encrypted; custom backends manage their own encryption",
+                file!(),
+            ));
+        }
+        let key = match (self.encrypted, self.encryption_key) {
+            (true, None) => {
+                return Err(XLinkError::invalid_input(
+                    "storage_key",
+                    "encrypted storage requires a key",
+                    file!(),
+                ))
+            }
+            (false, Some(_)) => {
+                return Err(XLinkError::invalid_input(
+                    "storage_key",
+                    "a storage key was given but storage encryption is not enabled",
+                    file!(),
+                ))
+            }
+            (_, key) => key,
+        };
+
+        let storage: Arc<dyn Storage> = match choice {
+            StorageChoice::Custom(storage) => storage,
+            StorageChoice::Path(path) => match key {
+                Some(key) => Arc::new(FileStorage::new_encrypted(&path, key).await?),
+                None => Arc::new(FileStorage::new(&path).await?),
+            },
+        };
+
+        let clock = self.clock.unwrap_or_else(system_clock);
+        let mut sdk = XLink::assemble(
+            self.capabilities,
+            self.channels,
+            storage,
+            self.app_queue,
+            clock,
+        )
+        .await?;
+        if let Some(compliance) = self.compliance {
+            sdk.compliance = Arc::new(compliance);
+        }
+        if let Some(config) = self.rate_limit {
+            sdk.set_rate_limit_config(config);
+        }
+        if let Some(config) = self.compression {
+            sdk.set_compression_config(config);
+        }
+        for plugin in self.plugins {
+            sdk.register_plugin(plugin)?;
+        }
+        Ok(sdk)
+    }
+}
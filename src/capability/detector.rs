@@ -1,5 +1,5 @@
 use crate::capability::manager::CapabilityManager;
-use crate::core::types::{ChannelType, DeviceCapabilities, DeviceType};
+use crate::core::types::{ChannelType, DeviceCapabilities, DeviceType, NetworkType};
 use log::{debug, info};
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -70,6 +70,32 @@ impl LocalCapabilityDetector {
         );
 
         self.manager.update_local_capabilities(new_caps);
+        self.manager
+            .update_local_network_type(self.detect_network_type());
+    }
+
+    /// 根据活动网络接口的名称推断本地网络类型，WiFi 优先于蜂窝与有线
+    fn detect_network_type(&self) -> NetworkType {
+        let names: Vec<String> = pnet_datalink::interfaces()
+            .into_iter()
+            .filter(|iface| !iface.is_loopback() && iface.is_up() && !iface.ips.is_empty())
+            .map(|iface| iface.name.to_lowercase())
+            .collect();
+        let any = |prefixes: &[&str]| {
+            names
+                .iter()
+                .any(|name| prefixes.iter().any(|prefix| name.starts_with(prefix)))
+        };
+
+        if any(&["wl", "wifi"]) {
+            NetworkType::WiFi
+        } else if any(&["rmnet", "wwan", "ccmni", "pdp_ip"]) {
+            NetworkType::Cellular4G
+        } else if any(&["eth", "en"]) {
+            NetworkType::Ethernet
+        } else {
+            NetworkType::Unknown
+        }
     }
 
     fn detect_lan_support(&self) -> bool {
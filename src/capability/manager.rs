@@ -1,7 +1,18 @@
-use crate::core::types::{ChannelState, ChannelType, DeviceCapabilities, DeviceId};
+use crate::core::error::{Result, XLinkError};
+use crate::core::types::{
+    ChannelState, ChannelType, DeviceCapabilities, DeviceId, MessagePriority, NetworkType,
+    PresenceHint, MAX_PRESENCE_HINT_CHANNELS,
+};
+use crate::router::breaker::{BreakerState, CircuitBreaker, CircuitBreakerConfig};
+use crate::router::scoring::Scorer;
 use dashmap::DashMap;
-use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// 每个设备-通道保留的最近失败记录上限
+const MAX_FAILURE_RECORDS: usize = 64;
 
 /// 能力变化事件类型
 #[derive(Debug, Clone)]
@@ -30,6 +41,20 @@ pub enum CapabilityChange {
     },
 }
 
+/// 本地设备状态变化事件，仅在值确实改变时发出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityEvent {
+    /// 电量变化（百分比）
+    BatteryChanged(u8),
+    /// 充电状态变化
+    ChargingChanged(bool),
+    /// 本地网络类型变化
+    NetworkTypeChanged(NetworkType),
+}
+
+/// 每个本地状态事件订阅者最多积压的事件数，超出后最旧的事件被丢弃
+pub const CAPABILITY_EVENT_CAPACITY: usize = 64;
+
 /// 能力变化监听器
 pub type CapabilityChangeHandler = Box<dyn Fn(CapabilityChange) + Send + Sync>;
 
@@ -42,6 +67,15 @@ pub struct CapabilityManager {
     remote_caps: Arc<DashMap<DeviceId, DeviceCapabilities>>,
     // 能力变化监听器列表
     change_handlers: Arc<dashmap::DashMap<String, CapabilityChangeHandler>>,
+    // 通道失败时间记录，用于按时间窗口衰减的失败惩罚
+    failure_log: Arc<DashMap<(DeviceId, ChannelType), VecDeque<Instant>>>,
+    // 本地网络类型，由能力检测器更新
+    local_network: Arc<RwLock<NetworkType>>,
+    // 本地状态事件广播
+    local_events: broadcast::Sender<CapabilityEvent>,
+    // 通道熔断记录与配置
+    breaker: Arc<Mutex<CircuitBreaker>>,
+    breaker_config: Arc<RwLock<CircuitBreakerConfig>>,
 }
 
 impl CapabilityManager {
@@ -51,7 +85,50 @@ impl CapabilityManager {
             remote_states: Arc::new(DashMap::new()),
             remote_caps: Arc::new(DashMap::new()),
             change_handlers: Arc::new(dashmap::DashMap::new()),
+            failure_log: Arc::new(DashMap::new()),
+            local_network: Arc::new(RwLock::new(NetworkType::Unknown)),
+            local_events: broadcast::channel(CAPABILITY_EVENT_CAPACITY).0,
+            breaker: Arc::new(Mutex::new(CircuitBreaker::default())),
+            breaker_config: Arc::new(RwLock::new(CircuitBreakerConfig::default())),
+        }
+    }
+
+    /// 订阅本地设备状态事件（电量、充电状态、网络类型）
+    ///
+    /// 只投递订阅之后发生的变化；订阅者消费过慢时最旧的事件被丢弃，`recv` 返回 `Lagged`
+    pub fn subscribe(&self) -> broadcast::Receiver<CapabilityEvent> {
+        self.local_events.subscribe()
+    }
+
+    /// 当前的本地网络类型
+    pub fn local_network_type(&self) -> NetworkType {
+        *self
+            .local_network
+            .read()
+            .expect("Failed to acquire read lock for local_network")
+    }
+
+    /// 更新本地网络类型，类型确实改变时发出事件并返回 true
+    pub fn update_local_network_type(&self, network_type: NetworkType) -> bool {
+        {
+            let mut current = self
+                .local_network
+                .write()
+                .expect("Failed to acquire write lock for local_network");
+            if *current == network_type {
+                return false;
+            }
+            *current = network_type;
         }
+
+        let _ = self
+            .local_events
+            .send(CapabilityEvent::NetworkTypeChanged(network_type));
+        self.notify_capability_change(CapabilityChange::NetworkTypeChanged {
+            device_id: self.get_local_caps().device_id,
+            network_type,
+        });
+        true
     }
 
     pub fn get_local_caps(&self) -> DeviceCapabilities {
@@ -81,6 +158,131 @@ impl CapabilityManager {
             .and_then(|map| map.get(channel).map(|v| v.clone()))
     }
 
+    /// 记录一次通道失败（发送失败或心跳超时）
+    pub fn record_channel_failure(&self, device: DeviceId, channel: ChannelType) {
+        let mut entry = self.failure_log.entry((device, channel)).or_default();
+        entry.push_back(Instant::now());
+        if entry.len() > MAX_FAILURE_RECORDS {
+            entry.pop_front();
+        }
+    }
+
+    /// 记录一次经通道发送的结果
+    ///
+    /// 失败时累加通道状态的 `failure_count` 并记入失败惩罚，成功时清零；
+    /// 连续失败达到熔断阈值后路由在冷却期内跳过该通道
+    pub fn record_channel_result(&self, device: DeviceId, channel: ChannelType, success: bool) {
+        if !success {
+            self.record_channel_failure(device, channel);
+        }
+        let failure_count = self
+            .remote_states
+            .get(&device)
+            .and_then(|states| {
+                states.get_mut(&channel).map(|mut state| {
+                    state.failure_count = if success {
+                        0
+                    } else {
+                        state.failure_count.saturating_add(1)
+                    };
+                    state.failure_count
+                })
+            })
+            .unwrap_or(0);
+        let config = self.circuit_breaker_config();
+        self.breaker
+            .lock()
+            .expect("Failed to acquire lock for breaker")
+            .record(device, channel, success, failure_count, &config);
+    }
+
+    /// 对端通道当前的熔断状态，没有通道状态时视为闭合
+    pub fn breaker_state(&self, device: &DeviceId, channel: &ChannelType) -> BreakerState {
+        let Some(state) = self.get_channel_state(device, channel) else {
+            return BreakerState::Closed;
+        };
+        let config = self.circuit_breaker_config();
+        self.breaker
+            .lock()
+            .expect("Failed to acquire lock for breaker")
+            .state(*device, *channel, state.failure_count, &config)
+    }
+
+    /// 标记半开通道的探测发送已开始
+    pub fn begin_breaker_probe(&self, device: DeviceId, channel: ChannelType) {
+        self.breaker
+            .lock()
+            .expect("Failed to acquire lock for breaker")
+            .begin_probe(device, channel);
+    }
+
+    /// 更新熔断配置
+    pub fn set_circuit_breaker_config(&self, config: CircuitBreakerConfig) {
+        *self
+            .breaker_config
+            .write()
+            .expect("Failed to acquire write lock for breaker_config") = config;
+    }
+
+    /// 当前的熔断配置
+    pub fn circuit_breaker_config(&self) -> CircuitBreakerConfig {
+        *self
+            .breaker_config
+            .read()
+            .expect("Failed to acquire read lock for breaker_config")
+    }
+
+    /// 时间窗口内的失败次数
+    pub fn recent_failures(
+        &self,
+        device: &DeviceId,
+        channel: &ChannelType,
+        window: Duration,
+    ) -> usize {
+        self.failure_log
+            .get(&(*device, *channel))
+            .map(|log| log.iter().filter(|at| at.elapsed() < window).count())
+            .unwrap_or(0)
+    }
+
+    /// 时间窗口内失败的衰减加权和
+    ///
+    /// 刚发生的失败权重为 1，随时间线性衰减，到窗口边界时降为 0
+    pub fn recent_failure_weight(
+        &self,
+        device: &DeviceId,
+        channel: &ChannelType,
+        window: Duration,
+    ) -> f64 {
+        if window.is_zero() {
+            return 0.0;
+        }
+        self.failure_log
+            .get(&(*device, *channel))
+            .map(|log| {
+                log.iter()
+                    .map(|at| 1.0 - at.elapsed().as_secs_f64() / window.as_secs_f64())
+                    .filter(|weight| *weight > 0.0)
+                    .sum()
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// 获取指定远程设备的全部通道状态
+    pub fn get_channel_states(&self, device: &DeviceId) -> Vec<(ChannelType, ChannelState)> {
+        self.remote_states
+            .get(device)
+            .map(|map| map.iter().map(|r| (*r.key(), r.value().clone())).collect())
+            .unwrap_or_default()
+    }
+
+    /// 获取所有已知远程设备 ID（已注册能力或存在通道状态）
+    pub fn get_known_devices(&self) -> Vec<DeviceId> {
+        let mut devices: HashSet<DeviceId> = self.remote_caps.iter().map(|r| *r.key()).collect();
+        devices.extend(self.remote_states.iter().map(|r| *r.key()));
+        devices.into_iter().collect()
+    }
+
     /// 清理所有远程设备信息，防止内存泄漏
     pub fn clear_remote_devices(&self) {
         // Remove remote_states entries one by one to avoid fragmentation
@@ -100,12 +302,87 @@ impl CapabilityManager {
             &self.change_handlers,
             crate::utils::get_all_keys(&self.change_handlers),
         );
+
+        crate::utils::remove_keys(
+            &self.failure_log,
+            crate::utils::get_all_keys(&self.failure_log),
+        );
+
+        if let Ok(mut breaker) = self.breaker.lock() {
+            breaker.clear();
+        }
+    }
+
+    /// 使指定远程设备的全部能力与通道状态失效
+    ///
+    /// 失效后路由不再考虑该设备的任何通道，直到重新注册或更新通道状态
+    pub fn invalidate_device(&self, device_id: DeviceId) {
+        let removed_states = self.remote_states.remove(&device_id).is_some();
+        let removed_caps = self.remote_caps.remove(&device_id).is_some();
+        if let Ok(mut breaker) = self.breaker.lock() {
+            breaker.forget_device(device_id);
+        }
+        if removed_states || removed_caps {
+            log::info!("Invalidated cached capabilities for device {}", device_id);
+        }
+    }
+
+    /// 使指定远程设备的单个通道失效
+    ///
+    /// 移除该通道的状态并从设备支持的通道集合中剔除，路由随即不再选择该通道
+    pub fn invalidate_channel(&self, device_id: DeviceId, channel: ChannelType) {
+        let removed_state = self
+            .remote_states
+            .get(&device_id)
+            .map(|states| states.remove(&channel).is_some())
+            .unwrap_or(false);
+        if let Some(mut caps) = self.remote_caps.get_mut(&device_id) {
+            caps.supported_channels.remove(&channel);
+        }
+
+        if removed_state {
+            log::info!("Invalidated channel {:?} for device {}", channel, device_id);
+            self.notify_capability_change(CapabilityChange::ChannelSupportChanged {
+                device_id,
+                channel,
+                supported: false,
+            });
+        }
     }
 
     pub fn register_remote_device(&self, caps: DeviceCapabilities) {
         self.remote_caps.insert(caps.device_id, caps);
     }
 
+    /// 用心跳携带的能力摘要更新已注册的远程设备，返回是否有变化
+    ///
+    /// 未注册的设备无法从摘要构造完整能力，直接忽略
+    pub fn apply_presence_hint(&self, device_id: DeviceId, hint: &PresenceHint) -> bool {
+        let Some(old) = self.get_remote_device(device_id) else {
+            return false;
+        };
+        let new = DeviceCapabilities {
+            battery_level: hint.battery_level,
+            is_charging: hint.is_charging,
+            supported_channels: hint
+                .supported_channels
+                .iter()
+                .take(MAX_PRESENCE_HINT_CHANNELS)
+                .copied()
+                .collect(),
+            ..old.clone()
+        };
+        let changes = self.detect_capability_changes(&old, &new);
+        if changes.is_empty() {
+            return false;
+        }
+        self.remote_caps.insert(device_id, new);
+        for change in changes {
+            self.notify_capability_change(change);
+        }
+        true
+    }
+
     /// 获取指定远程设备的能力
     pub fn get_remote_device(&self, device_id: DeviceId) -> Option<DeviceCapabilities> {
         self.remote_caps
@@ -113,6 +390,40 @@ impl CapabilityManager {
             .map(|entry| entry.value().clone())
     }
 
+    /// 协商本地与对端共同支持的通道，按当前评分从高到低排列
+    ///
+    /// 没有通道状态的通道评分为 0，同分时功耗低的通道优先。对端未注册能力时返回
+    /// `capability_mismatch`，以区别于双方确实没有共同通道（返回空列表）
+    pub fn negotiate_channels(&self, peer: DeviceId) -> Result<Vec<ChannelType>> {
+        let remote = self.get_remote_device(peer).ok_or_else(|| {
+            XLinkError::capability_mismatch(
+                peer.to_string(),
+                "registered capabilities".to_string(),
+                "none".to_string(),
+                file!(),
+            )
+        })?;
+        let local = self.get_local_caps();
+
+        let mut common: Vec<(ChannelType, f64)> = local
+            .supported_channels
+            .intersection(&remote.supported_channels)
+            .map(|ctype| {
+                let score = self
+                    .get_channel_state(&peer, ctype)
+                    .map(|state| Scorer::score(*ctype, &state, &local, MessagePriority::Normal))
+                    .unwrap_or(0.0);
+                (*ctype, score)
+            })
+            .collect();
+        common.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| a.0.power_cost().cmp(&b.0.power_cost()))
+                .then_with(|| (a.0 as u8).cmp(&(b.0 as u8)))
+        });
+        Ok(common.into_iter().map(|(ctype, _)| ctype).collect())
+    }
+
     /// 获取所有远程设备 ID
     pub fn get_all_remote_devices(&self) -> Vec<DeviceId> {
         self.remote_caps.iter().map(|r| *r.key()).collect()
@@ -196,6 +507,21 @@ impl CapabilityManager {
         for change in changes {
             self.notify_capability_change(change);
         }
+
+        // 本地状态事件：电量未知时不发出电量事件
+        if let Some(level) = new_capabilities
+            .battery_level
+            .filter(|level| current_capabilities.battery_level != Some(*level))
+        {
+            let _ = self
+                .local_events
+                .send(CapabilityEvent::BatteryChanged(level));
+        }
+        if current_capabilities.is_charging != new_capabilities.is_charging {
+            let _ = self.local_events.send(CapabilityEvent::ChargingChanged(
+                new_capabilities.is_charging,
+            ));
+        }
     }
 
     /// 检测能力变化
@@ -18,6 +18,7 @@ pub struct MemoryChannel {
     latency_ms: u64,
     should_fail: Arc<Mutex<bool>>,
     sent_messages: Arc<Mutex<Vec<Message>>>,
+    ordered: Option<bool>,
 }
 
 impl MemoryChannel {
@@ -29,6 +30,7 @@ impl MemoryChannel {
             latency_ms,
             should_fail: Arc::new(Mutex::new(false)),
             sent_messages: Arc::new(Mutex::new(Vec::new())),
+            ordered: None,
         }
     }
 
@@ -37,6 +39,12 @@ impl MemoryChannel {
         self
     }
 
+    /// Override whether this channel reports ordered delivery
+    pub fn with_ordering(mut self, ordered: bool) -> Self {
+        self.ordered = Some(ordered);
+        self
+    }
+
     pub fn set_failure(&self, fail: bool) {
         if let Ok(mut guard) = self.should_fail.try_lock() {
             *guard = fail;
@@ -66,6 +74,11 @@ impl Channel for MemoryChannel {
         self.channel_type
     }
 
+    fn is_ordered(&self) -> bool {
+        self.ordered
+            .unwrap_or_else(|| self.channel_type.is_ordered())
+    }
+
     async fn send(&self, message: Message) -> Result<()> {
         if *self.should_fail.lock().await {
             return Err(crate::core::error::XLinkError::channel_disconnected(
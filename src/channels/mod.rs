@@ -4,5 +4,8 @@ pub mod dummy;
 pub mod lan;
 pub mod memory;
 pub mod mesh;
+#[cfg(feature = "quic")]
+pub mod quic;
 pub mod remote;
+pub mod websocket;
 pub mod wifi;
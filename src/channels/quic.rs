@@ -0,0 +1,672 @@
+//! QUIC 公网通道
+//!
+//! 本地端点同时接受入站连接，并可连接到一个中继地址。每个连接上的每条
+//! [`Message`] 单独占用一条单向流（JSON 编码），互不阻塞。中继连接断开后按指数
+//! 退避自动重连，并尝试 0-RTT 恢复会话；断开期间 `send` 返回
+//! `channel_disconnected`，消息由 SDK 转入待发送队列等待恢复。
+//!
+//! 连接建立后，发起方在一条双向流上发送本机设备 ID 及其身份密钥对该 TLS 会话
+//! 导出密钥材料的签名。接收方按该设备已固定的身份公钥校验通过后，才将此入站
+//! 连接绑定到该设备，之后发往它的消息优先经此连接发出；未认证的对端只经由
+//! 中继连接送达。入站连接在握手完成后才处理其数据，0-RTT 数据不会被重放处理。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::{Channel, ChannelIdentity, MessageHandler};
+use crate::core::types::{ChannelState, ChannelType, DeviceId, Message, NetworkType};
+use crate::crypto::engine::CryptoEngine;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use quinn::{ClientConfig, Connection, Endpoint, ServerConfig, TransportConfig, ZeroRttAccepted};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// 首次重连前的等待时间
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// 重连等待时间上限
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// `reconnect` 等待中继连接恢复的最长时间，超时后由调用方按退避再次请求
+const RECONNECT_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+/// 单条消息编码后的最大字节数，超过时丢弃该流
+const MAX_MESSAGE_BYTES: usize = 8 * 1024 * 1024;
+/// 单条消息的最大负载字节数；JSON 编码中每个负载字节最多占 4 字节
+const MAX_PAYLOAD_BYTES: usize = MAX_MESSAGE_BYTES / 4;
+/// 对端在一个连接上可同时打开的单向流数，限制接收缓冲占用的内存
+const MAX_CONCURRENT_STREAMS: u32 = 16;
+/// 身份认证帧的最大字节数
+const MAX_AUTH_BYTES: usize = 1024;
+/// 导出身份认证所签名的会话密钥材料时使用的标签
+const AUTH_EXPORT_LABEL: &[u8] = b"xlink-quic-peer-auth";
+/// 自签名证书与不校验模式下使用的服务器名称
+const INSECURE_SERVER_NAME: &str = "localhost";
+
+/// QUIC 通道的 TLS 配置
+///
+/// 证书与私钥用于接受入站连接；连接中继时按 `trusted_roots` 校验服务器证书。
+/// `insecure_for_test` 生成自签名证书且不校验对端，只能用于测试。
+#[derive(Debug)]
+pub struct QuicTlsConfig {
+    cert_chain: Vec<CertificateDer<'static>>,
+    private_key: PrivateKeyDer<'static>,
+    trusted_roots: Vec<CertificateDer<'static>>,
+    server_name: String,
+    insecure: bool,
+}
+
+impl QuicTlsConfig {
+    /// 使用 DER 编码的证书链与 PKCS#8 私钥，`server_name` 为中继证书上的名称
+    pub fn new(
+        cert_chain: Vec<Vec<u8>>,
+        private_key: Vec<u8>,
+        server_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            cert_chain: cert_chain.into_iter().map(CertificateDer::from).collect(),
+            private_key: PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(private_key)),
+            trusted_roots: Vec::new(),
+            server_name: server_name.into(),
+            insecure: false,
+        }
+    }
+
+    /// 添加一个用于校验中继证书的根证书（DER 编码）
+    pub fn with_trusted_root(mut self, root: Vec<u8>) -> Self {
+        self.trusted_roots.push(CertificateDer::from(root));
+        self
+    }
+
+    /// 生成自签名证书且不校验对端证书，仅用于测试
+    pub fn insecure_for_test() -> Result<Self> {
+        let certified = rcgen::generate_simple_self_signed(vec![INSECURE_SERVER_NAME.to_string()])
+            .map_err(|e| XLinkError::channel_init_failed(e.to_string(), file!()))?;
+        Ok(Self {
+            cert_chain: vec![certified.cert.der().clone()],
+            private_key: PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+                certified.key_pair.serialize_der(),
+            )),
+            trusted_roots: Vec::new(),
+            server_name: INSECURE_SERVER_NAME.to_string(),
+            insecure: true,
+        })
+    }
+
+    fn crypto_provider() -> Arc<rustls::crypto::CryptoProvider> {
+        Arc::new(rustls::crypto::ring::default_provider())
+    }
+
+    fn server_config(&self) -> Result<ServerConfig> {
+        let init_failed = |e: String| XLinkError::channel_init_failed(e, file!());
+        let mut tls = rustls::ServerConfig::builder_with_provider(Self::crypto_provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(|e| init_failed(e.to_string()))?
+            .with_no_client_auth()
+            .with_single_cert(self.cert_chain.clone(), self.private_key.clone_key())
+            .map_err(|e| init_failed(e.to_string()))?;
+        // 允许客户端以 0-RTT 发送首批数据；入站连接在握手完成后才读取，
+        // 重放的 0-RTT 数据无法完成握手，因而不会被处理
+        tls.max_early_data_size = u32::MAX;
+        let crypto = QuicServerConfig::try_from(tls).map_err(|e| init_failed(e.to_string()))?;
+        let mut config = ServerConfig::with_crypto(Arc::new(crypto));
+        config.transport_config(Self::transport_config());
+        Ok(config)
+    }
+
+    fn client_config(&self) -> Result<ClientConfig> {
+        let init_failed = |e: String| XLinkError::channel_init_failed(e, file!());
+        let builder = rustls::ClientConfig::builder_with_provider(Self::crypto_provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(|e| init_failed(e.to_string()))?;
+        let mut tls = if self.insecure {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipServerVerification(
+                    Self::crypto_provider(),
+                )))
+                .with_no_client_auth()
+        } else {
+            let mut roots = rustls::RootCertStore::empty();
+            for root in &self.trusted_roots {
+                roots
+                    .add(root.clone())
+                    .map_err(|e| init_failed(e.to_string()))?;
+            }
+            builder.with_root_certificates(roots).with_no_client_auth()
+        };
+        tls.enable_early_data = true;
+        let crypto = QuicClientConfig::try_from(tls).map_err(|e| init_failed(e.to_string()))?;
+        let mut config = ClientConfig::new(Arc::new(crypto));
+        config.transport_config(Self::transport_config());
+        Ok(config)
+    }
+
+    /// 限制对端可同时打开的流数：单向流承载消息，双向流仅用于一次身份认证
+    fn transport_config() -> Arc<TransportConfig> {
+        let mut transport = TransportConfig::default();
+        transport
+            .max_concurrent_uni_streams(MAX_CONCURRENT_STREAMS.into())
+            .max_concurrent_bidi_streams(1u32.into());
+        Arc::new(transport)
+    }
+}
+
+/// 不校验服务器证书的校验器，仅由 `QuicTlsConfig::insecure_for_test` 使用
+#[derive(Debug)]
+struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// 连接建立后发起方发送的身份认证帧
+#[derive(Serialize, Deserialize)]
+struct PeerAuth {
+    device_id: DeviceId,
+    // 身份密钥对 `session_material` 的 Ed25519 签名
+    signature: Vec<u8>,
+}
+
+/// 从连接的 TLS 会话导出与 `device_id` 绑定的密钥材料；签名因此无法在其他连接上重放
+fn session_material(connection: &Connection, device_id: &DeviceId) -> Result<[u8; 32]> {
+    let mut material = [0u8; 32];
+    connection
+        .export_keying_material(
+            &mut material,
+            AUTH_EXPORT_LABEL,
+            device_id.to_string().as_bytes(),
+        )
+        .map_err(|e| XLinkError::channel_disconnected(format!("{:?}", e), file!()))?;
+    Ok(material)
+}
+
+/// 收发任务共享的连接状态
+struct Shared {
+    endpoint: Endpoint,
+    relay: Option<SocketAddr>,
+    server_name: String,
+    // 当前的中继连接，未连接时为 None
+    relay_connection: parking_lot::RwLock<Option<Connection>>,
+    // 已认证的设备 -> 其最近一次入站连接，回复时优先使用
+    peers: DashMap<DeviceId, Connection>,
+    handler: Mutex<Option<Arc<dyn MessageHandler>>>,
+    // 本机身份与已固定的对端公钥，未设置时既不认证自身也不绑定入站连接
+    identity: parking_lot::RwLock<Option<ChannelIdentity>>,
+    // 唤醒中继循环立即重连，跳过剩余的退避等待
+    reconnect_now: Notify,
+    // 中继连接建立时通知等待中的 `reconnect`
+    relay_connected: Notify,
+}
+
+/// QUIC 通道实现
+pub struct QuicChannel {
+    shared: Arc<Shared>,
+    // 当前的连接与接收任务，重新启动或通道销毁时终止
+    task: parking_lot::Mutex<Option<AbortHandle>>,
+}
+
+impl QuicChannel {
+    /// 在 `bind_addr` 上创建端点；`relay` 为 None 时只接受入站连接
+    ///
+    /// 端点立即绑定（需在 Tokio 运行时内调用），调用 `start` 或 `start_with_handler`
+    /// 后才连接中继并接收消息
+    pub fn new(
+        bind_addr: SocketAddr,
+        relay: Option<SocketAddr>,
+        tls: QuicTlsConfig,
+    ) -> Result<Self> {
+        let mut endpoint = Endpoint::server(tls.server_config()?, bind_addr)
+            .map_err(|e| XLinkError::channel_init_failed(e.to_string(), file!()))?;
+        endpoint.set_default_client_config(tls.client_config()?);
+        Ok(Self {
+            shared: Arc::new(Shared {
+                endpoint,
+                relay,
+                server_name: tls.server_name,
+                relay_connection: parking_lot::RwLock::new(None),
+                peers: DashMap::new(),
+                handler: Mutex::new(None),
+                identity: parking_lot::RwLock::new(None),
+                reconnect_now: Notify::new(),
+                relay_connected: Notify::new(),
+            }),
+            task: parking_lot::Mutex::new(None),
+        })
+    }
+
+    /// 端点实际绑定的本地地址
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.shared
+            .endpoint
+            .local_addr()
+            .map_err(|e| XLinkError::channel_init_failed(e.to_string(), file!()))
+    }
+
+    /// 当前是否已与中继建立连接
+    pub fn is_connected(&self) -> bool {
+        self.shared.relay_connection().is_some()
+    }
+
+    /// 同时接受入站连接并保持中继连接，终止该任务即停止两者
+    fn spawn_connection_task(&self) -> JoinHandle<()> {
+        let shared = self.shared.clone();
+        tokio::spawn(async move {
+            let relay = async {
+                if let Some(relay) = shared.relay {
+                    shared.clone().relay_loop(relay).await;
+                }
+            };
+            tokio::join!(shared.clone().accept_loop(), relay);
+        })
+    }
+}
+
+impl Shared {
+    fn relay_connection(&self) -> Option<Connection> {
+        self.relay_connection
+            .read()
+            .as_ref()
+            .filter(|conn| conn.close_reason().is_none())
+            .cloned()
+    }
+
+    /// 接受入站连接，每个连接独立接收消息
+    async fn accept_loop(self: Arc<Self>) {
+        while let Some(incoming) = self.endpoint.accept().await {
+            let shared = self.clone();
+            tokio::spawn(async move {
+                let connecting = match incoming.accept() {
+                    Ok(connecting) => connecting,
+                    Err(e) => {
+                        log::warn!("[QUIC] Failed to accept connection: {}", e);
+                        return;
+                    }
+                };
+                // 等待握手完成后再读取数据，携带的 0-RTT 数据此时已不可能是重放
+                let connection = match connecting.await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        log::warn!("[QUIC] Handshake failed: {}", e);
+                        return;
+                    }
+                };
+                log::info!(
+                    "[QUIC] Accepted connection from {}",
+                    connection.remote_address()
+                );
+                let authenticate = async {
+                    match shared.authenticate_peer(&connection).await {
+                        Ok(device_id) => {
+                            log::info!(
+                                "[QUIC] Authenticated {} from {}",
+                                device_id,
+                                connection.remote_address()
+                            );
+                            shared.peers.insert(device_id, connection.clone());
+                        }
+                        Err(e) => log::warn!(
+                            "[QUIC] Connection from {} not authenticated: {}",
+                            connection.remote_address(),
+                            e
+                        ),
+                    }
+                };
+                tokio::join!(shared.run_connection(connection.clone()), authenticate);
+            });
+        }
+    }
+
+    /// 保持与中继的连接，断开后按指数退避重连；`reconnect_now` 被通知时立即重连
+    async fn relay_loop(self: Arc<Self>, relay: SocketAddr) {
+        let mut delay = RECONNECT_BASE_DELAY;
+        loop {
+            let attempt = tokio::select! {
+                attempt = self.connect(relay) => attempt,
+                // 仍在握手的尝试可能卡在不可达的旧路径上，放弃后重新发起
+                _ = self.reconnect_now.notified() => {
+                    delay = RECONNECT_BASE_DELAY;
+                    continue;
+                }
+            };
+            match attempt {
+                Ok((connection, zero_rtt)) => {
+                    log::info!("[QUIC] Connected to {}", relay);
+                    delay = RECONNECT_BASE_DELAY;
+                    *self.relay_connection.write() = Some(connection.clone());
+                    self.relay_connected.notify_waiters();
+                    let authenticate = async {
+                        if let Err(e) = self.authenticate(&connection, zero_rtt).await {
+                            log::warn!("[QUIC] Failed to authenticate to {}: {}", relay, e);
+                        }
+                    };
+                    tokio::join!(self.run_connection(connection.clone()), authenticate);
+                    *self.relay_connection.write() = None;
+                    log::warn!("[QUIC] Connection to {} closed", relay);
+                }
+                Err(e) => log::warn!("[QUIC] Failed to connect to {}: {}", relay, e),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => delay = (delay * 2).min(RECONNECT_MAX_DELAY),
+                _ = self.reconnect_now.notified() => delay = RECONNECT_BASE_DELAY,
+            }
+        }
+    }
+
+    /// 连接中继；以 0-RTT 建立时同时返回握手完成的通知
+    async fn connect(&self, relay: SocketAddr) -> Result<(Connection, Option<ZeroRttAccepted>)> {
+        let connecting = self
+            .endpoint
+            .connect(relay, &self.server_name)
+            .map_err(|e| XLinkError::channel_disconnected(e.to_string(), file!()))?;
+        // 持有先前会话的票据时以 0-RTT 立即可用，否则等待完整握手
+        match connecting.into_0rtt() {
+            Ok((connection, accepted)) => Ok((connection, Some(accepted))),
+            Err(connecting) => connecting
+                .await
+                .map(|connection| (connection, None))
+                .map_err(|e| XLinkError::channel_disconnected(e.to_string(), file!())),
+        }
+    }
+
+    /// 向对端证明本机身份，未设置身份时跳过
+    async fn authenticate(
+        &self,
+        connection: &Connection,
+        zero_rtt: Option<ZeroRttAccepted>,
+    ) -> Result<()> {
+        let Some(identity) = self.identity.read().clone() else {
+            return Ok(());
+        };
+        // 会话密钥材料在握手完成后才可导出
+        if let Some(accepted) = zero_rtt {
+            accepted.await;
+        }
+        let material = session_material(connection, &identity.device_id)?;
+        let auth = PeerAuth {
+            device_id: identity.device_id,
+            signature: identity.signer.sign(&material),
+        };
+        let disconnected = |e: String| XLinkError::channel_disconnected(e, file!());
+        let (mut stream, _) = connection
+            .open_bi()
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        stream
+            .write_all(&serde_json::to_vec(&auth)?)
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        stream.finish().map_err(|e| disconnected(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 读取对端的身份认证帧，按其设备已固定的身份公钥校验后返回该设备
+    async fn authenticate_peer(&self, connection: &Connection) -> Result<DeviceId> {
+        let disconnected = |e: String| XLinkError::channel_disconnected(e, file!());
+        let (_, mut stream) = connection
+            .accept_bi()
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        let data = stream
+            .read_to_end(MAX_AUTH_BYTES)
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        let auth: PeerAuth = serde_json::from_slice(&data)?;
+        let pinned_key = self
+            .identity
+            .read()
+            .as_ref()
+            .and_then(|identity| identity.pinned_keys.get(&auth.device_id).map(|key| *key));
+        let Some(pinned_key) = pinned_key else {
+            return Err(XLinkError::signature_verification_failed(
+                "Ed25519".to_string(),
+                format!("No pinned identity key for {}", auth.device_id),
+                file!(),
+            ));
+        };
+        let material = session_material(connection, &auth.device_id)?;
+        CryptoEngine::verify_with_key(&pinned_key, &material, &auth.signature)?;
+        Ok(auth.device_id)
+    }
+
+    /// 接收一个连接上的单向流，直到连接关闭
+    async fn run_connection(&self, connection: Connection) {
+        loop {
+            let mut stream = match connection.accept_uni().await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::debug!(
+                        "[QUIC] Connection {} ended: {}",
+                        connection.remote_address(),
+                        e
+                    );
+                    break;
+                }
+            };
+            let data = match stream.read_to_end(MAX_MESSAGE_BYTES).await {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("[QUIC] Failed to read stream: {}", e);
+                    continue;
+                }
+            };
+            self.deliver(&data).await;
+        }
+        self.peers
+            .retain(|_, conn| conn.stable_id() != connection.stable_id());
+    }
+
+    async fn deliver(&self, data: &[u8]) {
+        let msg = match serde_json::from_slice::<Message>(data) {
+            Ok(msg) => msg,
+            Err(e) => {
+                log::error!("[QUIC] Failed to deserialize message: {}", e);
+                return;
+            }
+        };
+        let handler = self.handler.lock().await.clone();
+        match handler {
+            Some(handler) => {
+                if let Err(e) = handler.handle_message(msg).await {
+                    log::error!("[QUIC] Error handling message: {}", e);
+                }
+            }
+            None => log::warn!(
+                "[QUIC] Dropping message {} received without a handler",
+                msg.id
+            ),
+        }
+    }
+
+    /// 优先使用已认证对端自己的连接，否则使用中继连接
+    fn connection_for(&self, target: &DeviceId) -> Option<Connection> {
+        let peer = self
+            .peers
+            .get(target)
+            .map(|conn| conn.clone())
+            .filter(|conn| conn.close_reason().is_none());
+        peer.or_else(|| self.relay_connection())
+    }
+}
+
+impl Drop for QuicChannel {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.lock().take() {
+            task.abort();
+        }
+        self.shared.endpoint.close(0u32.into(), b"channel dropped");
+    }
+}
+
+#[async_trait]
+impl Channel for QuicChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Internet
+    }
+
+    fn max_payload(&self) -> Option<usize> {
+        Some(MAX_PAYLOAD_BYTES)
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        let Some(connection) = self.shared.connection_for(&message.recipient) else {
+            return Err(XLinkError::channel_disconnected(
+                format!("QUIC has no connection to {}", message.recipient),
+                file!(),
+            ));
+        };
+        let data = serde_json::to_vec(&message)?;
+        let disconnected = |e: String| {
+            XLinkError::channel_disconnected(
+                format!("QUIC send to {} failed: {}", connection.remote_address(), e),
+                file!(),
+            )
+        };
+        let mut stream = connection
+            .open_uni()
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        stream
+            .write_all(&data)
+            .await
+            .map_err(|e| disconnected(e.to_string()))?;
+        stream.finish().map_err(|e| disconnected(e.to_string()))?;
+        log::debug!(
+            "[QUIC] Sent message {} via {}",
+            message.id,
+            connection.remote_address()
+        );
+        Ok(())
+    }
+
+    async fn check_state(&self, target: &DeviceId) -> Result<ChannelState> {
+        let connection = self.shared.connection_for(target);
+        let available = connection.is_some();
+        // RTT 取 QUIC 拥塞控制维护的平滑估计值
+        let rtt_ms = connection
+            .map(|conn| conn.rtt().as_millis().min(u32::MAX as u128) as u32)
+            .unwrap_or(0);
+
+        Ok(ChannelState {
+            available,
+            rtt_ms,
+            jitter_ms: 0,
+            packet_loss_rate: 0.0,
+            bandwidth_bps: 10_000_000,
+            signal_strength: None,
+            distance_meters: None,
+            network_type: NetworkType::Unknown,
+            failure_count: if available { 0 } else { 1 },
+            last_heartbeat: 0,
+        })
+    }
+
+    async fn start(&self) -> Result<()> {
+        // 已在运行时不重复启动，避免同一端点上出现两个接收循环
+        let mut task = self.task.lock();
+        if task.as_ref().is_some_and(|task| !task.is_finished()) {
+            return Ok(());
+        }
+        *task = Some(self.spawn_connection_task().abort_handle());
+        Ok(())
+    }
+
+    async fn start_with_handler(
+        &self,
+        handler: Arc<dyn MessageHandler>,
+    ) -> Result<Option<JoinHandle<()>>> {
+        *self.shared.handler.lock().await = Some(handler);
+        log::info!("[QUIC] Starting channel on {:?}", self.local_addr().ok());
+        let task = self.spawn_connection_task();
+        if let Some(previous) = self.task.lock().replace(task.abort_handle()) {
+            previous.abort();
+        }
+        Ok(Some(task))
+    }
+
+    fn set_identity(&self, identity: ChannelIdentity) {
+        *self.shared.identity.write() = Some(identity);
+    }
+
+    async fn clear_handler(&self) -> Result<()> {
+        *self.shared.handler.lock().await = None;
+        Ok(())
+    }
+
+    /// 立即唤醒中继循环重连并等待连接恢复
+    ///
+    /// 未配置中继时只接受入站连接，无需重连；连接任务已退出时直接返回，由调用方经
+    /// `start_with_handler` 重新启动
+    async fn reconnect(&self) -> Result<()> {
+        let Some(relay) = self.shared.relay else {
+            return Ok(());
+        };
+        let running = self
+            .task
+            .lock()
+            .as_ref()
+            .is_some_and(|task| !task.is_finished());
+        if !running {
+            return Ok(());
+        }
+
+        let connected = self.shared.relay_connected.notified();
+        tokio::pin!(connected);
+        connected.as_mut().enable();
+        if self.is_connected() {
+            return Ok(());
+        }
+        self.shared.reconnect_now.notify_one();
+        tokio::time::timeout(RECONNECT_WAIT_TIMEOUT, connected)
+            .await
+            .map_err(|_| {
+                XLinkError::channel_disconnected(
+                    format!("QUIC relay {} still unreachable", relay),
+                    file!(),
+                )
+            })
+    }
+}
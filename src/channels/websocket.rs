@@ -0,0 +1,311 @@
+//! WebSocket 公网通道
+//!
+//! 连接到可配置的 WebSocket 中继地址，每条 [`Message`] 以一个 JSON 文本帧收发。
+//! 连接断开后接收任务按指数退避自动重连，`reconnect` 可跳过剩余的退避立即重连；
+//! 断开期间 `send` 返回 `channel_disconnected`，消息由 SDK 转入待发送队列等待恢复。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::{Channel, MessageHandler};
+use crate::core::types::{ChannelState, ChannelType, DeviceId, Message, NetworkType};
+use async_trait::async_trait;
+use futures::stream::SplitSink;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSink = SplitSink<WsStream, WsMessage>;
+
+/// 首次重连前的等待时间
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// 重连等待时间上限
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// 等待 pong 的超时时间，超时视为连接不可用
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// `reconnect` 等待连接恢复的最长时间，超时后由调用方按退避再次请求
+const RECONNECT_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 收发任务共享的连接状态
+struct Shared {
+    url: String,
+    // 当前连接的写端，未连接时为 None
+    sink: Mutex<Option<WsSink>>,
+    handler: Mutex<Option<Arc<dyn MessageHandler>>>,
+    // ping 序号 -> 等待对应 pong 的发送端
+    pending_pings: parking_lot::Mutex<HashMap<u64, oneshot::Sender<()>>>,
+    next_ping: AtomicU64,
+    last_rtt_ms: AtomicU32,
+    // 唤醒接收任务立即重连，跳过剩余的退避等待
+    reconnect_now: Notify,
+    // 连接建立时通知等待中的 `reconnect`
+    connected: Notify,
+}
+
+/// WebSocket 通道实现
+pub struct WebSocketChannel {
+    shared: Arc<Shared>,
+    // 当前的接收任务，重新启动或通道销毁时终止
+    task: parking_lot::Mutex<Option<AbortHandle>>,
+}
+
+impl WebSocketChannel {
+    /// 创建连接到 `url`（`ws://` 或 `wss://`）的通道，调用 `start` 后才建立连接
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                url: url.into(),
+                sink: Mutex::new(None),
+                handler: Mutex::new(None),
+                pending_pings: parking_lot::Mutex::new(HashMap::new()),
+                next_ping: AtomicU64::new(0),
+                last_rtt_ms: AtomicU32::new(0),
+                reconnect_now: Notify::new(),
+                connected: Notify::new(),
+            }),
+            task: parking_lot::Mutex::new(None),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.shared.url
+    }
+
+    /// 当前是否已与服务器建立连接
+    pub async fn is_connected(&self) -> bool {
+        self.shared.sink.lock().await.is_some()
+    }
+
+    fn spawn_connection_task(&self) -> JoinHandle<()> {
+        let shared = self.shared.clone();
+        tokio::spawn(async move {
+            let mut delay = RECONNECT_BASE_DELAY;
+            loop {
+                let attempt = tokio::select! {
+                    attempt = connect_async(shared.url.as_str()) => attempt,
+                    // 仍在进行的连接尝试可能卡在不可达的旧路径上，放弃后重新发起
+                    _ = shared.reconnect_now.notified() => {
+                        delay = RECONNECT_BASE_DELAY;
+                        continue;
+                    }
+                };
+                match attempt {
+                    Ok((stream, _)) => {
+                        log::info!("[WebSocket] Connected to {}", shared.url);
+                        delay = RECONNECT_BASE_DELAY;
+                        shared.run_connection(stream).await;
+                        log::warn!("[WebSocket] Connection to {} closed", shared.url);
+                    }
+                    Err(e) => {
+                        log::warn!("[WebSocket] Failed to connect to {}: {}", shared.url, e);
+                    }
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => delay = (delay * 2).min(RECONNECT_MAX_DELAY),
+                    _ = shared.reconnect_now.notified() => delay = RECONNECT_BASE_DELAY,
+                }
+            }
+        })
+    }
+
+    /// 启动接收任务并终止先前的任务，避免同一通道上出现两个连接
+    fn replace_connection_task(&self) -> JoinHandle<()> {
+        let task = self.spawn_connection_task();
+        if let Some(previous) = self.task.lock().replace(task.abort_handle()) {
+            previous.abort();
+        }
+        task
+    }
+}
+
+impl Shared {
+    /// 处理一条连接上的入站帧，直到连接关闭
+    async fn run_connection(&self, stream: WsStream) {
+        let (sink, mut source) = stream.split();
+        *self.sink.lock().await = Some(sink);
+        self.connected.notify_waiters();
+
+        while let Some(frame) = source.next().await {
+            match frame {
+                Ok(WsMessage::Text(text)) => self.deliver(text.as_bytes()).await,
+                Ok(WsMessage::Binary(data)) => self.deliver(&data).await,
+                Ok(WsMessage::Pong(data)) => {
+                    if let Ok(bytes) = <[u8; 8]>::try_from(data.as_slice()) {
+                        let id = u64::from_be_bytes(bytes);
+                        if let Some(waiter) = self.pending_pings.lock().remove(&id) {
+                            let _ = waiter.send(());
+                        }
+                    }
+                }
+                Ok(WsMessage::Close(_)) => break,
+                // ping 由协议层自动回复 pong
+                Ok(_) => {}
+                Err(e) => {
+                    log::warn!("[WebSocket] Receive error from {}: {}", self.url, e);
+                    break;
+                }
+            }
+        }
+
+        *self.sink.lock().await = None;
+        self.pending_pings.lock().clear();
+    }
+
+    async fn deliver(&self, data: &[u8]) {
+        let msg = match serde_json::from_slice::<Message>(data) {
+            Ok(msg) => msg,
+            Err(e) => {
+                log::error!("[WebSocket] Failed to deserialize message: {}", e);
+                return;
+            }
+        };
+        let handler = self.handler.lock().await.clone();
+        match handler {
+            Some(handler) => {
+                if let Err(e) = handler.handle_message(msg).await {
+                    log::error!("[WebSocket] Error handling message: {}", e);
+                }
+            }
+            None => log::warn!(
+                "[WebSocket] Dropping message {} received without a handler",
+                msg.id
+            ),
+        }
+    }
+
+    /// 发送一帧，失败时丢弃当前连接等待重连
+    async fn send_frame(&self, frame: WsMessage) -> Result<()> {
+        let mut sink = self.sink.lock().await;
+        let Some(ws) = sink.as_mut() else {
+            return Err(XLinkError::channel_disconnected(
+                format!("WebSocket {} is not connected", self.url),
+                file!(),
+            ));
+        };
+        if let Err(e) = ws.send(frame).await {
+            *sink = None;
+            return Err(XLinkError::channel_disconnected(
+                format!("WebSocket {} send failed: {}", self.url, e),
+                file!(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WebSocketChannel {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.lock().take() {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl Channel for WebSocketChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Internet
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        let data = serde_json::to_string(&message)?;
+        self.shared.send_frame(WsMessage::Text(data)).await?;
+        log::debug!(
+            "[WebSocket] Sent message {} via {}",
+            message.id,
+            self.shared.url
+        );
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> Result<ChannelState> {
+        // 所有对端都经由同一中继可达，状态取决于与服务器的连接
+        let id = self.shared.next_ping.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.shared.pending_pings.lock().insert(id, tx);
+
+        let started = Instant::now();
+        let answered = match self
+            .shared
+            .send_frame(WsMessage::Ping(id.to_be_bytes().to_vec()))
+            .await
+        {
+            Ok(()) => matches!(tokio::time::timeout(PING_TIMEOUT, rx).await, Ok(Ok(()))),
+            Err(_) => false,
+        };
+        self.shared.pending_pings.lock().remove(&id);
+
+        if answered {
+            let rtt_ms = started.elapsed().as_millis().min(u32::MAX as u128) as u32;
+            self.shared.last_rtt_ms.store(rtt_ms, Ordering::Relaxed);
+        }
+
+        Ok(ChannelState {
+            available: answered,
+            rtt_ms: self.shared.last_rtt_ms.load(Ordering::Relaxed),
+            jitter_ms: 0,
+            packet_loss_rate: 0.0,
+            bandwidth_bps: 10_000_000,
+            signal_strength: None,
+            distance_meters: None,
+            network_type: NetworkType::Unknown,
+            failure_count: if answered { 0 } else { 1 },
+            last_heartbeat: 0,
+        })
+    }
+
+    async fn start(&self) -> Result<()> {
+        self.replace_connection_task();
+        Ok(())
+    }
+
+    async fn start_with_handler(
+        &self,
+        handler: Arc<dyn MessageHandler>,
+    ) -> Result<Option<JoinHandle<()>>> {
+        *self.shared.handler.lock().await = Some(handler);
+        log::info!("[WebSocket] Starting channel for {}", self.shared.url);
+        Ok(Some(self.replace_connection_task()))
+    }
+
+    async fn clear_handler(&self) -> Result<()> {
+        *self.shared.handler.lock().await = None;
+        Ok(())
+    }
+
+    /// 立即唤醒接收任务重连并等待连接恢复
+    ///
+    /// 接收任务已退出时直接返回，由调用方经 `start_with_handler` 重新启动
+    async fn reconnect(&self) -> Result<()> {
+        let running = self
+            .task
+            .lock()
+            .as_ref()
+            .is_some_and(|task| !task.is_finished());
+        if !running {
+            return Ok(());
+        }
+
+        let connected = self.shared.connected.notified();
+        tokio::pin!(connected);
+        connected.as_mut().enable();
+        if self.is_connected().await {
+            return Ok(());
+        }
+        self.shared.reconnect_now.notify_one();
+        tokio::time::timeout(RECONNECT_WAIT_TIMEOUT, connected)
+            .await
+            .map_err(|_| {
+                XLinkError::channel_disconnected(
+                    format!("WebSocket {} still unreachable", self.shared.url),
+                    file!(),
+                )
+            })
+    }
+}
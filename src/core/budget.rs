@@ -0,0 +1,116 @@
+//! 计量流量预算
+//!
+//! [`TrafficBudget`] 累计经计量网络（蜂窝）发出的字节数，并通过存储元数据跨重启保留。
+//! 开启省流模式且用量达到 `UserTrafficPreferences::monthly_data_limit_mb` 后，
+//! SDK 拒绝计量网络上非 `Critical` 的发送；WiFi、LAN 与近场通道的流量不计入预算。
+//! 点对点发送、流式分片与群组扇出都经路由器检查并累计同一份预算。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::Storage;
+use crate::core::types::{ChannelType, MessagePriority, NetworkType};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 持久化预算用量所用的存储元数据键
+const TRAFFIC_BUDGET_KEY: &str = "traffic_budget";
+
+/// 1 MB 对应的字节数
+pub const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// 经该通道、在该本地网络下发送的流量是否计入预算
+///
+/// 只有经互联网通道且本地处于蜂窝网络时计费，局域网与近场通道不经运营商网络
+pub fn is_metered(channel: ChannelType, network: NetworkType) -> bool {
+    channel == ChannelType::Internet
+        && matches!(network, NetworkType::Cellular4G | NetworkType::Cellular5G)
+}
+
+/// 计量网络的累计用量
+#[derive(Debug, Default)]
+pub struct TrafficBudget {
+    used_bytes: AtomicU64,
+    /// 因扇出策略改走待发送队列、未经计量网络发出的字节数
+    avoided_bytes: AtomicU64,
+    /// 省流模式下的月流量上限（MB），未开启省流模式时为 None
+    limit_mb: RwLock<Option<u64>>,
+}
+
+impl TrafficBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前计费周期内的累计用量（字节）
+    pub fn used_bytes(&self) -> u64 {
+        self.used_bytes.load(Ordering::Relaxed)
+    }
+
+    /// 累加一次计量网络发送，返回是否跨过了整 MB 边界（调用方据此决定是否持久化）
+    pub fn record(&self, bytes: u64) -> bool {
+        let before = self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+        before / BYTES_PER_MB != (before + bytes) / BYTES_PER_MB
+    }
+
+    /// 当前计费周期内因扇出策略避免的计量网络流量（字节）
+    pub fn avoided_bytes(&self) -> u64 {
+        self.avoided_bytes.load(Ordering::Relaxed)
+    }
+
+    /// 累加一次被避免的计量网络发送
+    pub fn record_avoided(&self, bytes: u64) {
+        self.avoided_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// 设置省流模式下的月流量上限（MB），None 表示未开启省流模式、不拦截发送
+    pub fn set_limit_mb(&self, limit_mb: Option<u64>) {
+        *self.limit_mb.write() = limit_mb;
+    }
+
+    /// 计量网络上的发送是否放行：开启省流模式且用量达到上限时拒绝非 `Critical` 的发送
+    pub fn admit(&self, priority: MessagePriority) -> Result<()> {
+        let limit_mb = *self.limit_mb.read();
+        match limit_mb {
+            Some(limit_mb) if priority != MessagePriority::Critical && self.exhausted(limit_mb) => {
+                Err(XLinkError::resource_exhausted(
+                    "metered data budget (MB)",
+                    self.used_bytes() / BYTES_PER_MB,
+                    limit_mb,
+                    file!(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 用量是否已达到 `limit_mb`
+    pub fn exhausted(&self, limit_mb: u64) -> bool {
+        self.used_bytes() >= limit_mb.saturating_mul(BYTES_PER_MB)
+    }
+
+    /// 距 `limit_mb` 剩余的额度（MB），已超出时为 0
+    pub fn remaining_mb(&self, limit_mb: u64) -> u64 {
+        limit_mb.saturating_sub(self.used_bytes().div_ceil(BYTES_PER_MB))
+    }
+
+    /// 开始新的计费周期，用量归零
+    pub fn reset(&self) {
+        self.used_bytes.store(0, Ordering::Relaxed);
+        self.avoided_bytes.store(0, Ordering::Relaxed);
+    }
+
+    /// 将累计用量写入存储
+    pub async fn persist(&self, storage: &dyn Storage) -> Result<()> {
+        let data = serde_json::to_vec(&self.used_bytes()).map_err(Into::<XLinkError>::into)?;
+        storage.save_metadata(TRAFFIC_BUDGET_KEY, data).await
+    }
+
+    /// 从存储恢复累计用量，覆盖当前计数
+    pub async fn load(&self, storage: &dyn Storage) -> Result<()> {
+        let Some(data) = storage.load_metadata(TRAFFIC_BUDGET_KEY).await? else {
+            return Ok(());
+        };
+        let used: u64 = serde_json::from_slice(&data).map_err(Into::<XLinkError>::into)?;
+        self.used_bytes.store(used, Ordering::Relaxed);
+        Ok(())
+    }
+}
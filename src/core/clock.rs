@@ -0,0 +1,96 @@
+//! 可替换的时钟
+//!
+//! 超时、保留期与限流窗口通过 [`Clock`] 取当前时间。默认的 [`SystemClock`] 直接读取系统时间；
+//! 测试可注入 [`MockClock`] 并手动推进，无需真实等待即可触发超时与清理。
+
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 时间来源
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// 当前 Unix 时间（毫秒）
+    fn now_unix_millis(&self) -> u64;
+
+    /// 当前 Unix 时间（秒）
+    fn now_unix_secs(&self) -> u64 {
+        self.now_unix_millis() / 1000
+    }
+
+    /// 当前单调时间点，用于计算时间窗口
+    fn instant_now(&self) -> Instant;
+}
+
+/// 读取系统时间的时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_unix_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+
+    fn instant_now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 默认时钟
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// 只在调用 [`advance`](MockClock::advance) 时前进的时钟
+#[derive(Debug)]
+pub struct MockClock {
+    start_unix_millis: u64,
+    start_instant: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl MockClock {
+    /// 以当前系统时间为起点
+    pub fn new() -> Self {
+        Self::starting_at(SystemClock.now_unix_millis())
+    }
+
+    /// 以指定的 Unix 时间（毫秒）为起点
+    pub fn starting_at(unix_millis: u64) -> Self {
+        Self {
+            start_unix_millis: unix_millis,
+            start_instant: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// 推进时钟
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock() += duration;
+    }
+
+    /// 自起点以来推进的总时长
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now_unix_millis(&self) -> u64 {
+        self.start_unix_millis
+            .saturating_add(self.elapsed().as_millis() as u64)
+    }
+
+    fn instant_now(&self) -> Instant {
+        self.start_instant + self.elapsed()
+    }
+}
@@ -0,0 +1,96 @@
+//! 大负载压缩
+//!
+//! 发送端按 [`CompressionConfig`] 压缩超过阈值的 `Binary` 负载，以
+//! `MessagePayload::Compressed` 发出；经流式传输时由 `StreamChunk` 的 `compression`
+//! 字段标记。接收端在交付应用或重组完成后解压，应用始终收到原始 `Binary`。
+//! 已压缩的数据（如图片、视频、压缩包）字节分布接近均匀，按采样熵判断后跳过，避免浪费 CPU。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::types::{CompressionAlgorithm, CompressionConfig};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::io::{Read, Write};
+
+/// 熵估计的采样字节数
+const ENTROPY_SAMPLE_BYTES: usize = 4096;
+/// 采样熵高于此值（比特/字节）视为不可压缩
+const INCOMPRESSIBLE_ENTROPY_BITS: f64 = 7.5;
+/// 解压结果上限，防止压缩炸弹耗尽内存
+pub const MAX_DECOMPRESSED_BYTES: usize = 256 * 1024 * 1024;
+
+/// 按配置压缩负载，未启用、低于阈值、判定为不可压缩或压缩后未变小时返回 None
+pub fn compress_payload(
+    data: &[u8],
+    config: &CompressionConfig,
+) -> Option<(CompressionAlgorithm, Vec<u8>)> {
+    let algorithm = config.algorithm?;
+    if data.len() < config.min_size_bytes {
+        return None;
+    }
+    if config.skip_incompressible && looks_incompressible(data) {
+        log::debug!("Skipping compression of {} high-entropy bytes", data.len());
+        return None;
+    }
+    match compress(data, algorithm, config.level) {
+        Ok(compressed) if compressed.len() < data.len() => Some((algorithm, compressed)),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Compression failed, sending uncompressed: {}", e);
+            None
+        }
+    }
+}
+
+pub fn compress(data: &[u8], algorithm: CompressionAlgorithm, level: u32) -> Result<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithm::Deflate => {
+            let mut encoder =
+                DeflateEncoder::new(Vec::new(), flate2::Compression::new(level.min(9)));
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+/// 解压负载，结果超过 [`MAX_DECOMPRESSED_BYTES`] 或数据损坏时返回错误
+pub fn decompress(data: &[u8], algorithm: CompressionAlgorithm) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let read = match algorithm {
+        CompressionAlgorithm::Deflate => DeflateDecoder::new(data)
+            .take(MAX_DECOMPRESSED_BYTES as u64 + 1)
+            .read_to_end(&mut output),
+    };
+    read.map_err(|e| {
+        XLinkError::invalid_protocol_message("Compressed".to_string(), e.to_string(), file!())
+    })?;
+    if output.len() > MAX_DECOMPRESSED_BYTES {
+        return Err(XLinkError::invalid_protocol_message(
+            "Compressed".to_string(),
+            format!("decompressed size exceeds {} bytes", MAX_DECOMPRESSED_BYTES),
+            file!(),
+        ));
+    }
+    Ok(output)
+}
+
+/// 按开头采样的字节熵判断数据是否已压缩或加密
+pub fn looks_incompressible(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(ENTROPY_SAMPLE_BYTES)];
+    if sample.is_empty() {
+        return false;
+    }
+    let mut counts = [0u32; 256];
+    for byte in sample {
+        counts[*byte as usize] += 1;
+    }
+    let total = sample.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = f64::from(*count) / total;
+            -p * p.log2()
+        })
+        .sum();
+    entropy > INCOMPRESSIBLE_ENTROPY_BITS
+}
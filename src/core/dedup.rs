@@ -0,0 +1,86 @@
+//! 接收端消息去重
+//!
+//! 群组广播可能同时经直连与中继到达同一设备，[`DedupCache`] 按到达顺序记录最近收到的
+//! (发送方, 消息 ID)，容量与有效期由 [`DedupConfig`] 决定：超出容量淘汰最早的记录，
+//! 过期记录在下次访问时清理。
+
+use crate::core::types::{DedupConfig, DeviceId};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+type DedupKey = (DeviceId, Uuid);
+
+/// 最近收到消息的有界记录
+#[derive(Debug, Default)]
+pub struct DedupCache {
+    config: DedupConfig,
+    seen: HashMap<DedupKey, Instant>,
+    // 按记录时间排列，队首最早
+    order: VecDeque<(DedupKey, Instant)>,
+}
+
+impl DedupCache {
+    pub fn new(config: DedupConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> DedupConfig {
+        self.config
+    }
+
+    /// 更新配置，容量调小时立即淘汰多余的记录
+    pub fn set_config(&mut self, config: DedupConfig) {
+        self.config = config;
+        self.evict(Instant::now());
+    }
+
+    /// 记录一条消息，有效期内已记录过时返回 false
+    pub fn insert(&mut self, sender: DeviceId, message_id: Uuid) -> bool {
+        if self.config.capacity == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        self.evict(now);
+        let key = (sender, message_id);
+        if self.seen.contains_key(&key) {
+            return false;
+        }
+        self.seen.insert(key, now);
+        self.order.push_back((key, now));
+        self.evict(now);
+        true
+    }
+
+    /// 撤销一条记录，消息未能交付时让重发的副本仍可被接收
+    pub fn forget(&mut self, sender: DeviceId, message_id: Uuid) {
+        self.seen.remove(&(sender, message_id));
+    }
+
+    /// 当前记录的消息数
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn evict(&mut self, now: Instant) {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        while let Some(&(key, recorded)) = self.order.front() {
+            let stale = self.seen.get(&key) != Some(&recorded);
+            let expired = now.saturating_duration_since(recorded) >= ttl;
+            if !(stale || expired || self.seen.len() > self.config.capacity) {
+                break;
+            }
+            self.order.pop_front();
+            if !stale {
+                self.seen.remove(&key);
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! 结构化数据的 SHA-256 摘要
+//!
+//! 以紧凑的自描述二进制编码把任意 `Serialize` 值直接写入哈希：每个值带类型标记，
+//! 整数按小端定长编码，字符串、字节与序列带长度前缀。编码不在内存中成形，
+//! 字节数组逐字节写入缓冲区后批量哈希，大负载的摘要开销接近对原始字节做一次哈希。
+//! 映射按迭代顺序编码，含 `HashMap` 的值须自行保证顺序确定。
+
+use serde::ser::{self, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+const BUFFER_BYTES: usize = 64 * 1024;
+
+/// 计算 `value` 的摘要，逻辑相等的值总是得到相同的摘要
+pub fn digest<T: Serialize + ?Sized>(value: &T) -> [u8; 32] {
+    let mut serializer = DigestSerializer {
+        hasher: Sha256::new(),
+        buffer: Vec::with_capacity(BUFFER_BYTES),
+    };
+    value
+        .serialize(&mut serializer)
+        .expect("digest serialization never fails");
+    serializer.flush();
+    serializer.hasher.finalize().into()
+}
+
+#[derive(Debug)]
+pub struct DigestError(String);
+
+impl fmt::Display for DigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DigestError {}
+
+impl ser::Error for DigestError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        DigestError(msg.to_string())
+    }
+}
+
+// 类型标记
+const TAG_BOOL: u8 = 1;
+const TAG_INT: u8 = 2;
+const TAG_FLOAT: u8 = 3;
+const TAG_CHAR: u8 = 4;
+const TAG_STR: u8 = 5;
+const TAG_BYTES: u8 = 6;
+const TAG_NONE: u8 = 7;
+const TAG_SOME: u8 = 8;
+const TAG_UNIT: u8 = 9;
+const TAG_VARIANT: u8 = 10;
+const TAG_SEQ: u8 = 11;
+const TAG_MAP: u8 = 12;
+const TAG_STRUCT: u8 = 13;
+const TAG_END: u8 = 14;
+
+struct DigestSerializer {
+    hasher: Sha256,
+    buffer: Vec<u8>,
+}
+
+impl DigestSerializer {
+    fn write(&mut self, bytes: &[u8]) {
+        if self.buffer.len() + bytes.len() > BUFFER_BYTES {
+            self.flush();
+        }
+        if bytes.len() >= BUFFER_BYTES {
+            self.hasher.update(bytes);
+        } else {
+            self.buffer.extend_from_slice(bytes);
+        }
+    }
+
+    fn flush(&mut self) {
+        self.hasher.update(&self.buffer);
+        self.buffer.clear();
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.write(&[tag]);
+    }
+
+    fn len(&mut self, len: Option<usize>) {
+        // 长度未知时以结束标记收尾
+        match len {
+            Some(len) => {
+                self.tag(1);
+                self.write(&(len as u64).to_le_bytes());
+            }
+            None => self.tag(0),
+        }
+    }
+
+    fn int(&mut self, value: i128) {
+        self.tag(TAG_INT);
+        self.write(&value.to_le_bytes());
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut DigestSerializer {
+    type Ok = ();
+    type Error = DigestError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), DigestError> {
+        self.write(&[TAG_BOOL, u8::from(v)]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), DigestError> {
+        self.int(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), DigestError> {
+        // 字节数组的元素：不带类型标记直接写入缓冲区，保持编码紧凑
+        if self.buffer.len() == BUFFER_BYTES {
+            self.flush();
+        }
+        self.buffer.push(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), DigestError> {
+        self.int(v.into());
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), DigestError> {
+        self.tag(TAG_INT);
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), DigestError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), DigestError> {
+        self.tag(TAG_FLOAT);
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), DigestError> {
+        self.tag(TAG_CHAR);
+        self.write(&u32::from(v).to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), DigestError> {
+        self.tag(TAG_STR);
+        self.len(Some(v.len()));
+        self.write(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), DigestError> {
+        self.tag(TAG_BYTES);
+        self.len(Some(v.len()));
+        self.write(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), DigestError> {
+        self.tag(TAG_NONE);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), DigestError> {
+        self.tag(TAG_SOME);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), DigestError> {
+        self.tag(TAG_UNIT);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), DigestError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), DigestError> {
+        self.tag(TAG_VARIANT);
+        self.write(&variant_index.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), DigestError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), DigestError> {
+        self.tag(TAG_VARIANT);
+        self.write(&variant_index.to_le_bytes());
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a>, DigestError> {
+        self.tag(TAG_SEQ);
+        self.len(len);
+        Ok(Compound(self))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, DigestError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, DigestError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, DigestError> {
+        self.tag(TAG_VARIANT);
+        self.write(&variant_index.to_le_bytes());
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a>, DigestError> {
+        self.tag(TAG_MAP);
+        self.len(len);
+        Ok(Compound(self))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, DigestError> {
+        self.tag(TAG_STRUCT);
+        self.len(Some(len));
+        Ok(Compound(self))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, DigestError> {
+        self.tag(TAG_VARIANT);
+        self.write(&variant_index.to_le_bytes());
+        self.serialize_struct("", len)
+    }
+}
+
+/// 复合值（序列、映射、结构体）的元素写入器，结束时写入结束标记
+pub struct Compound<'a>(&'a mut DigestSerializer);
+
+impl Compound<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        value.serialize(&mut *self.0)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        self.0.tag(TAG_END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), DigestError> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = DigestError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), DigestError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), DigestError> {
+        Compound::end(self)
+    }
+}
@@ -92,4 +92,42 @@ impl XLinkError {
             base_delay_ms: 2000,
         })
     }
+
+    /// 无可用有序通道 (0205)
+    ///
+    /// 当消息要求有序交付但目标设备没有可用的有序通道时返回此错误
+    #[inline]
+    pub fn no_ordered_channel<S: Into<String>>(target: S, location: &'static str) -> Self {
+        Self::new_internal(
+            ErrorCode(205),
+            ErrorCategory::Channel,
+            "无可用有序通道".to_string(),
+            &format!("No ordered channel available to reach {}", target.into()),
+            location,
+        )
+    }
+
+    /// 消息超出所有可用通道的负载上限 (0206)
+    ///
+    /// 当目标设备的可用通道单条负载上限都小于消息大小时返回此错误
+    #[inline]
+    pub fn payload_too_large_for_channels<S: Into<String>>(
+        target: S,
+        payload_size: usize,
+        max_payload: usize,
+        location: &'static str,
+    ) -> Self {
+        Self::new_internal(
+            ErrorCode(206),
+            ErrorCategory::Channel,
+            "消息超出通道负载上限".to_string(),
+            &format!(
+                "Message of {} bytes exceeds the {} byte limit of every channel to {}",
+                payload_size,
+                max_payload,
+                target.into()
+            ),
+            location,
+        )
+    }
 }
@@ -117,4 +117,20 @@ impl XLinkError {
             base_delay_ms: 500,
         })
     }
+
+    /// 状态无效 (0106)
+    ///
+    /// 当操作与当前运行状态冲突时返回此错误（例如重复启动）
+    #[inline]
+    pub fn invalid_state<S: Into<String>>(operation: S, reason: S, location: &'static str) -> Self {
+        let operation_str = operation.into();
+        let reason_str = reason.into();
+        Self::new_internal(
+            ErrorCode(106),
+            ErrorCategory::System,
+            "当前状态不允许此操作".to_string(),
+            &format!("Invalid state for {}: {}", operation_str, reason_str),
+            location,
+        )
+    }
 }
@@ -1,6 +1,10 @@
 //! 群组相关错误 (04xx)
 
 use crate::core::error::{ErrorCategory, ErrorCode, RetrySuggestion, XLinkError};
+use crate::core::types::DeviceId;
+
+/// `debug_info` 中记录缺少公钥成员的字段
+const MISSING_MEMBER_KEYS_FIELD: &str = "missing_member_keys";
 
 impl XLinkError {
     /// 群组不存在 (0401)
@@ -132,4 +136,60 @@ impl XLinkError {
             base_delay_ms: 500,
         })
     }
+
+    /// 群组权限不足 (0407)
+    ///
+    /// 当非管理员成员尝试执行仅限管理员的操作时返回此错误
+    #[inline]
+    pub fn group_permission_denied<S: Into<String>>(
+        group_id: S,
+        user_id: S,
+        action: S,
+        location: &'static str,
+    ) -> Self {
+        let group_id_str = group_id.into();
+        let user_id_str = user_id.into();
+        let action_str = action.into();
+        Self::new_internal(
+            ErrorCode(407),
+            ErrorCategory::Group,
+            "群组权限不足".to_string(),
+            &format!(
+                "User {} is not allowed to {} in group {}",
+                user_id_str, action_str, group_id_str
+            ),
+            location,
+        )
+        .with_group_id(group_id_str)
+    }
+
+    /// 成员缺少公钥 (0408)
+    ///
+    /// 创建群组时部分成员未注册 TreeKEM 公钥，缺少公钥的设备记录在 `debug_info` 中，
+    /// 可通过 [`missing_member_keys`](Self::missing_member_keys) 取回
+    #[inline]
+    pub fn group_member_keys_missing(device_ids: &[DeviceId], location: &'static str) -> Self {
+        let listed: Vec<String> = device_ids.iter().map(ToString::to_string).collect();
+        Self::new_internal(
+            ErrorCode(408),
+            ErrorCategory::Group,
+            "群组成员缺少公钥".to_string(),
+            &format!(
+                "No registered public key for members: {}",
+                listed.join(", ")
+            ),
+            location,
+        )
+        .with_debug_info(serde_json::json!({ MISSING_MEMBER_KEYS_FIELD: device_ids }))
+    }
+
+    /// 缺少公钥的成员列表，仅对 `group_member_keys_missing` 错误非空
+    pub fn missing_member_keys(&self) -> Vec<DeviceId> {
+        self.context
+            .debug_info
+            .as_ref()
+            .and_then(|info| info.get(MISSING_MEMBER_KEYS_FIELD))
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
 }
@@ -700,6 +700,13 @@ impl<'a> Iterator for SourceIter<'a> {
 impl From<std::io::Error> for XLinkError {
     #[inline]
     fn from(error: std::io::Error) -> Self {
+        // 磁盘或配额耗尽（ENOSPC/EDQUOT）单独报告为存储空间不足，调用方据此回收空间
+        if matches!(
+            error.kind(),
+            std::io::ErrorKind::StorageFull | std::io::ErrorKind::QuotaExceeded
+        ) {
+            return Self::storage_full(error.to_string(), file!());
+        }
         Self::new_internal(
             ErrorCode(101),
             ErrorCategory::System,
@@ -746,6 +753,12 @@ impl From<DetailedError> for XLinkError {
 /// 便捷类型别名
 pub type Result<T> = std::result::Result<T, XLinkError>;
 
+/// 错误时间线最多保留的记录数，超出后丢弃最旧的记录
+const MAX_ERROR_TIMELINE: usize = 10_000;
+
+/// 按速率告警时统计的时间窗口
+const ERROR_RATE_WINDOW: chrono::Duration = chrono::Duration::seconds(60);
+
 /// 错误统计信息
 ///
 /// 用于收集和报告错误统计数据
@@ -779,8 +792,34 @@ impl ErrorStatistics {
         let category_name = error.category.name().to_string();
         *self.category_counts.entry(category_name).or_insert(0) += 1;
 
-        self.last_error_time = Some(chrono::Utc::now());
-        self.error_timeline.push((chrono::Utc::now(), code));
+        let now = chrono::Utc::now();
+        self.last_error_time = Some(now);
+        self.error_timeline.push((now, code));
+        if self.error_timeline.len() > MAX_ERROR_TIMELINE {
+            let excess = self.error_timeline.len() - MAX_ERROR_TIMELINE;
+            self.error_timeline.drain(..excess);
+        }
+    }
+
+    /// 导出统计快照：前 `top_n` 个常见错误码、按类别计数与最近一分钟各错误码的次数
+    pub fn snapshot(&self, top_n: usize) -> ErrorStatsSnapshot {
+        let since = chrono::Utc::now() - ERROR_RATE_WINDOW;
+        let mut last_minute = std::collections::HashMap::new();
+        for (_, code) in self
+            .error_timeline
+            .iter()
+            .rev()
+            .take_while(|(at, _)| *at >= since)
+        {
+            *last_minute.entry(*code).or_insert(0) += 1;
+        }
+        ErrorStatsSnapshot {
+            total: self.total_count(),
+            most_common: self.get_most_common(top_n),
+            by_category: self.category_counts.clone(),
+            last_minute,
+            last_error: self.last_error_time,
+        }
     }
 
     /// 获取错误总数
@@ -852,6 +891,21 @@ impl ErrorStatistics {
     }
 }
 
+/// 错误统计快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorStatsSnapshot {
+    /// 错误总数
+    pub total: u64,
+    /// 最常见的错误码及次数，按次数降序排列
+    pub most_common: Vec<(u16, u64)>,
+    /// 按类别名称统计的次数
+    pub by_category: std::collections::HashMap<String, u64>,
+    /// 最近一分钟内各错误码的次数，可用于按速率告警
+    pub last_minute: std::collections::HashMap<u16, u64>,
+    /// 最后一次错误发生时间
+    pub last_error: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// 错误日志格式化辅助函数
 ///
 /// 生成适合日志系统的格式化错误字符串
@@ -65,4 +65,37 @@ impl XLinkError {
             location,
         )
     }
+
+    /// 存储空间不足 (0704)
+    ///
+    /// 当存储设备空间耗尽，或清理存储后仍无法写入数据时返回此错误，需要用户释放设备空间
+    #[inline]
+    pub fn storage_full<S: Into<String>>(reason: S, location: &'static str) -> Self {
+        Self::new_internal(
+            ErrorCode(704),
+            ErrorCategory::Storage,
+            "存储空间不足".to_string(),
+            &format!("Storage is full: {}", reason.into()),
+            location,
+        )
+        .with_retry_suggestion(RetrySuggestion::ManualIntervention)
+    }
+
+    /// 存储迁移失败 (0705)
+    ///
+    /// 当迁移后目标存储中的数据与源存储不一致时返回此错误
+    #[inline]
+    pub fn storage_migration_failed<S: Into<String>>(reason: S, location: &'static str) -> Self {
+        Self::new_internal(
+            ErrorCode(705),
+            ErrorCategory::Storage,
+            "存储迁移失败".to_string(),
+            &format!("Storage migration failed: {}", reason.into()),
+            location,
+        )
+        .with_retry_suggestion(RetrySuggestion::Retryable {
+            max_attempts: 3,
+            base_delay_ms: 100,
+        })
+    }
 }
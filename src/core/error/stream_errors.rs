@@ -95,4 +95,31 @@ impl XLinkError {
         )
         .with_retry_suggestion(RetrySuggestion::ManualIntervention)
     }
+
+    /// 流数据校验失败 (0605)
+    ///
+    /// 当流分片的 CRC32 或重组后数据的 SHA-256 与发送方声明的不一致时返回此错误
+    #[inline]
+    pub fn stream_integrity_failed<S: Into<String>>(
+        stream_id: S,
+        reason: S,
+        location: &'static str,
+    ) -> Self {
+        let stream_id_str = stream_id.into();
+        let reason_str = reason.into();
+        Self::new_internal(
+            ErrorCode(605),
+            ErrorCategory::Stream,
+            "流数据校验失败".to_string(),
+            &format!(
+                "Integrity check failed for stream {}: {}",
+                stream_id_str, reason_str
+            ),
+            location,
+        )
+        .with_retry_suggestion(RetrySuggestion::Retryable {
+            max_attempts: 3,
+            base_delay_ms: 1000,
+        })
+    }
 }
@@ -0,0 +1,121 @@
+//! SDK 统一事件总线
+//!
+//! 将消息收发、能力变化、流媒体与群组事件汇聚为一个 [`SdkEvent`] 枚举，集成方只需订阅一次并按变体匹配。
+//! 各模块原有的专用订阅接口（能力变化监听器、流事件处理器）保持可用。
+//!
+//! # 投递语义
+//!
+//! 事件总线基于 `tokio::sync::broadcast`，投递是有损的：
+//! - 只投递订阅之后发生的事件；
+//! - 订阅者消费过慢、积压超过 [`EVENT_BUS_CAPACITY`] 条时，最旧的事件被丢弃，
+//!   订阅流记录告警后从仍保留的最旧事件继续；
+//! - 同一发布方发布的事件保持发布顺序。
+
+use crate::capability::manager::CapabilityChange;
+use crate::core::types::{ChannelType, DeviceId, ReorderOverflowPolicy};
+use crate::group::manager::GroupEvent;
+use crate::media::stream_manager::StreamEvent;
+use futures::stream::Stream;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// 每个订阅者最多积压的事件数
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+/// SDK 事件
+#[derive(Debug, Clone)]
+pub enum SdkEvent {
+    /// 已为待发送消息选定通道
+    ChannelSelected {
+        message_id: Uuid,
+        recipient: DeviceId,
+        channel: ChannelType,
+    },
+    /// 消息已通过通道发出
+    MessageSent {
+        message_id: Uuid,
+        recipient: DeviceId,
+        channel: ChannelType,
+    },
+    /// 消息经通道发送失败
+    MessageSendFailed {
+        message_id: Uuid,
+        recipient: DeviceId,
+        reason: String,
+    },
+    /// 收到消息并已交付给应用
+    MessageReceived { message_id: Uuid, sender: DeviceId },
+    /// 收到超过最大年龄的消息；`delivered` 表示是否仍交付给了应用
+    StaleMessageReceived {
+        message_id: Uuid,
+        sender: DeviceId,
+        age_secs: u64,
+        delivered: bool,
+    },
+    /// 发送方的重排缓冲区已满；策略为 `RequestRetransmit` 时 SDK 已向发送方请求重传 `missing` 区间
+    /// 与被丢弃的消息
+    ReorderBufferOverflow {
+        sender: DeviceId,
+        missing: std::ops::Range<u64>,
+        policy: ReorderOverflowPolicy,
+    },
+    /// 等待发送方缺失序号超时，跳过 `missing` 区间交付其后已缓冲的消息
+    ReorderGapSkipped {
+        sender: DeviceId,
+        missing: std::ops::Range<u64>,
+    },
+    /// 存储空间不足，清理后仍无法保存待发送消息，应提示用户释放空间
+    StorageFull { message_id: Uuid, reason: String },
+    /// 发现的设备通过挑战-应答校验
+    DeviceVerified { device_id: DeviceId },
+    /// 发现的设备未通过校验，未被标记为可路由
+    DeviceRejected { device_id: DeviceId, reason: String },
+    /// 发现的设备因超出发现对端上限被淘汰，已不再可路由
+    DeviceLost { device_id: DeviceId },
+    /// 设备能力变化
+    Capability(CapabilityChange),
+    /// 流媒体事件
+    Stream(StreamEvent),
+    /// 群组事件
+    Group(GroupEvent),
+}
+
+/// 事件总线，克隆后共享同一广播通道
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<SdkEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { tx }
+    }
+
+    /// 发布事件，没有订阅者时直接丢弃
+    pub fn publish(&self, event: SdkEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    /// 订阅事件流
+    pub fn subscribe(&self) -> impl Stream<Item = SdkEvent> + Send + 'static {
+        let rx = self.tx.subscribe();
+        futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("SDK event subscriber lagged, {} events dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
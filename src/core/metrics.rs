@@ -1,5 +1,9 @@
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::Storage;
 use crate::core::types::{ChannelType, DeviceId};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
@@ -8,13 +12,24 @@ pub struct MetricsCollector {
     messages_received: AtomicU64,
     bytes_sent: AtomicU64,
     bytes_received: AtomicU64,
+    stale_messages: AtomicU64,
+    ephemeral_sent: AtomicU64,
+    ephemeral_dropped: AtomicU64,
+    reorder_overflows: AtomicU64,
+    streamed_sends: AtomicU64,
+    chunked_sends: AtomicU64,
 
     // 按通道统计
     channel_usage: DashMap<ChannelType, AtomicU64>,
+    channel_bytes_sent: DashMap<ChannelType, AtomicU64>,
 
     // 延迟统计 (ms)
     last_rtt: DashMap<DeviceId, u32>,
 
+    // 按对端统计的在途发送数及其峰值
+    in_flight_sends: DashMap<DeviceId, u64>,
+    peak_in_flight_sends: DashMap<DeviceId, u64>,
+
     start_time: Instant,
 }
 
@@ -25,8 +40,17 @@ impl Default for MetricsCollector {
             messages_received: AtomicU64::new(0),
             bytes_sent: AtomicU64::new(0),
             bytes_received: AtomicU64::new(0),
+            stale_messages: AtomicU64::new(0),
+            ephemeral_sent: AtomicU64::new(0),
+            ephemeral_dropped: AtomicU64::new(0),
+            reorder_overflows: AtomicU64::new(0),
+            streamed_sends: AtomicU64::new(0),
+            chunked_sends: AtomicU64::new(0),
             channel_usage: DashMap::new(),
+            channel_bytes_sent: DashMap::new(),
             last_rtt: DashMap::new(),
+            in_flight_sends: DashMap::new(),
+            peak_in_flight_sends: DashMap::new(),
             start_time: Instant::now(),
         }
     }
@@ -45,6 +69,10 @@ impl MetricsCollector {
             .entry(channel)
             .or_insert_with(|| AtomicU64::new(0))
             .fetch_add(1, Ordering::Relaxed);
+        self.channel_bytes_sent
+            .entry(channel)
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(bytes, Ordering::Relaxed);
     }
 
     pub fn record_receive(&self, bytes: u64) {
@@ -52,6 +80,67 @@ impl MetricsCollector {
         self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
     }
 
+    /// 记录一条超过最大年龄的入站消息
+    pub fn record_stale(&self) {
+        self.stale_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一条成功发出的尽力发送（不持久化）消息
+    pub fn record_ephemeral_send(&self) {
+        self.ephemeral_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一条发送失败后直接丢弃的尽力发送消息
+    pub fn record_ephemeral_dropped(&self) {
+        self.ephemeral_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一条超过自动流式阈值、改经流式传输发出的消息
+    pub fn record_streamed_send(&self) {
+        self.streamed_sends.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一条关闭自动流式传输后、经可靠分片发出并被接收方确认的消息
+    pub fn record_chunked_send(&self) {
+        self.chunked_sends.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一次接收端重排缓冲区溢出
+    pub fn record_reorder_overflow(&self) {
+        self.reorder_overflows.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录对端的一次发送开始占用在途名额
+    pub fn record_send_started(&self, device: DeviceId) {
+        let current = {
+            let mut entry = self.in_flight_sends.entry(device).or_insert(0);
+            *entry += 1;
+            *entry
+        };
+        let mut peak = self.peak_in_flight_sends.entry(device).or_insert(0);
+        *peak = (*peak).max(current);
+    }
+
+    /// 记录对端的一次发送结束并释放在途名额
+    pub fn record_send_finished(&self, device: DeviceId) {
+        if let Some(mut entry) = self.in_flight_sends.get_mut(&device) {
+            *entry = entry.saturating_sub(1);
+        }
+    }
+
+    /// 对端当前的在途发送数
+    pub fn in_flight_sends(&self, device: &DeviceId) -> u64 {
+        self.in_flight_sends.get(device).map(|v| *v).unwrap_or(0)
+    }
+
+    /// 对端出现过的最大在途发送数
+    pub fn peak_in_flight_sends(&self, device: &DeviceId) -> u64 {
+        self.peak_in_flight_sends
+            .get(device)
+            .map(|v| *v)
+            .unwrap_or(0)
+    }
+
     pub fn update_rtt(&self, device: DeviceId, rtt_ms: u32) {
         self.last_rtt.insert(device, rtt_ms);
     }
@@ -63,7 +152,119 @@ impl MetricsCollector {
             total_received: self.messages_received.load(Ordering::Relaxed),
             total_bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
             total_bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            total_stale_received: self.stale_messages.load(Ordering::Relaxed),
+            total_ephemeral_sent: self.ephemeral_sent.load(Ordering::Relaxed),
+            total_ephemeral_dropped: self.ephemeral_dropped.load(Ordering::Relaxed),
+            total_reorder_overflows: self.reorder_overflows.load(Ordering::Relaxed),
+            total_streamed_sends: self.streamed_sends.load(Ordering::Relaxed),
+            total_chunked_sends: self.chunked_sends.load(Ordering::Relaxed),
+            in_flight_sends: self
+                .in_flight_sends
+                .iter()
+                .filter(|entry| *entry.value() > 0)
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+        }
+    }
+}
+
+/// 持久化指标所用的存储元数据键
+const METRICS_STORAGE_KEY: &str = "metrics";
+
+/// 跨重启保留的累计计数器（延迟与在途数等瞬时指标不保留）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersistedCounters {
+    messages_sent: u64,
+    messages_received: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    stale_messages: u64,
+    #[serde(default)]
+    ephemeral_sent: u64,
+    #[serde(default)]
+    ephemeral_dropped: u64,
+    #[serde(default)]
+    reorder_overflows: u64,
+    #[serde(default)]
+    streamed_sends: u64,
+    #[serde(default)]
+    chunked_sends: u64,
+    channel_usage: HashMap<ChannelType, u64>,
+    #[serde(default)]
+    channel_bytes_sent: HashMap<ChannelType, u64>,
+}
+
+impl MetricsCollector {
+    /// 将累计计数器写入存储
+    pub async fn persist(&self, storage: &dyn Storage) -> Result<()> {
+        let counters = PersistedCounters {
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            stale_messages: self.stale_messages.load(Ordering::Relaxed),
+            ephemeral_sent: self.ephemeral_sent.load(Ordering::Relaxed),
+            ephemeral_dropped: self.ephemeral_dropped.load(Ordering::Relaxed),
+            reorder_overflows: self.reorder_overflows.load(Ordering::Relaxed),
+            streamed_sends: self.streamed_sends.load(Ordering::Relaxed),
+            chunked_sends: self.chunked_sends.load(Ordering::Relaxed),
+            channel_usage: self
+                .channel_usage
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+                .collect(),
+            channel_bytes_sent: self
+                .channel_bytes_sent
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+                .collect(),
+        };
+        let data = serde_json::to_vec(&counters).map_err(Into::<XLinkError>::into)?;
+        storage.save_metadata(METRICS_STORAGE_KEY, data).await
+    }
+
+    /// 从存储恢复累计计数器，叠加到当前计数之上
+    ///
+    /// 应在每次 `clear` 之后只调用一次，否则已恢复的计数会被重复累加
+    pub async fn load(&self, storage: &dyn Storage) -> Result<()> {
+        let Some(data) = storage.load_metadata(METRICS_STORAGE_KEY).await? else {
+            return Ok(());
+        };
+        let counters: PersistedCounters =
+            serde_json::from_slice(&data).map_err(Into::<XLinkError>::into)?;
+        self.messages_sent
+            .fetch_add(counters.messages_sent, Ordering::Relaxed);
+        self.messages_received
+            .fetch_add(counters.messages_received, Ordering::Relaxed);
+        self.bytes_sent
+            .fetch_add(counters.bytes_sent, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(counters.bytes_received, Ordering::Relaxed);
+        self.stale_messages
+            .fetch_add(counters.stale_messages, Ordering::Relaxed);
+        self.ephemeral_sent
+            .fetch_add(counters.ephemeral_sent, Ordering::Relaxed);
+        self.ephemeral_dropped
+            .fetch_add(counters.ephemeral_dropped, Ordering::Relaxed);
+        self.reorder_overflows
+            .fetch_add(counters.reorder_overflows, Ordering::Relaxed);
+        self.streamed_sends
+            .fetch_add(counters.streamed_sends, Ordering::Relaxed);
+        self.chunked_sends
+            .fetch_add(counters.chunked_sends, Ordering::Relaxed);
+        for (channel, count) in counters.channel_usage {
+            self.channel_usage
+                .entry(channel)
+                .or_insert_with(|| AtomicU64::new(0))
+                .fetch_add(count, Ordering::Relaxed);
+        }
+        for (channel, bytes) in counters.channel_bytes_sent {
+            self.channel_bytes_sent
+                .entry(channel)
+                .or_insert_with(|| AtomicU64::new(0))
+                .fetch_add(bytes, Ordering::Relaxed);
         }
+        Ok(())
     }
 }
 
@@ -73,6 +274,20 @@ pub struct MetricsReport {
     pub total_received: u64,
     pub total_bytes_sent: u64,
     pub total_bytes_received: u64,
+    /// 超过最大年龄的入站消息数（含丢弃与标记交付）
+    pub total_stale_received: u64,
+    /// 成功发出的尽力发送消息数（同时计入 total_sent）
+    pub total_ephemeral_sent: u64,
+    /// 发送失败后直接丢弃的尽力发送消息数
+    pub total_ephemeral_dropped: u64,
+    /// 接收端重排缓冲区溢出次数
+    pub total_reorder_overflows: u64,
+    /// 超过自动流式阈值、经流式传输发出的消息数（不计入 total_sent）
+    pub total_streamed_sends: u64,
+    /// 关闭自动流式传输后经可靠分片发出的消息数（不计入 total_sent）
+    pub total_chunked_sends: u64,
+    /// 各对端当前的在途发送数（仅包含非零项）
+    pub in_flight_sends: std::collections::HashMap<DeviceId, u64>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -85,33 +300,99 @@ pub struct AnalyticsEvent {
 }
 
 impl MetricsCollector {
-    // ... 原有方法保持不变 ...
-
-    /// 导出为 Prometheus 格式
+    /// 导出为 Prometheus 格式，等价于 [`to_prometheus`](Self::to_prometheus)
     pub fn export_prometheus(&self) -> String {
-        let mut report = String::new();
-        report.push_str("# HELP xlink_messages_sent_total Total number of messages sent\n");
-        report.push_str("# TYPE xlink_messages_sent_total counter\n");
-        report.push_str(&format!(
-            "xlink_messages_sent_total {}\n",
-            self.messages_sent.load(Ordering::Relaxed)
-        ));
+        self.to_prometheus()
+    }
 
-        report.push_str("# HELP xlink_bytes_sent_total Total number of bytes sent\n");
-        report.push_str("# TYPE xlink_bytes_sent_total counter\n");
-        report.push_str(&format!(
-            "xlink_bytes_sent_total {}\n",
-            self.bytes_sent.load(Ordering::Relaxed)
-        ));
+    /// 渲染为 Prometheus 文本格式（exposition format）
+    ///
+    /// 每个指标族都带 `# HELP` 与 `# TYPE` 行，按通道统计的指标以 `channel` 标签区分，
+    /// 标签值按规范转义，输出以换行结尾。只读取原子计数，可与 `record_*` 并发调用。
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "xlink_messages_sent_total",
+                "Total number of messages sent",
+                &self.messages_sent,
+            ),
+            (
+                "xlink_messages_received_total",
+                "Total number of messages received",
+                &self.messages_received,
+            ),
+            (
+                "xlink_bytes_received_total",
+                "Total number of bytes received",
+                &self.bytes_received,
+            ),
+            (
+                "xlink_stale_messages_total",
+                "Inbound messages older than the configured max age",
+                &self.stale_messages,
+            ),
+            (
+                "xlink_ephemeral_messages_sent_total",
+                "Best-effort messages sent without persistence",
+                &self.ephemeral_sent,
+            ),
+            (
+                "xlink_ephemeral_messages_dropped_total",
+                "Best-effort messages dropped after a failed send",
+                &self.ephemeral_dropped,
+            ),
+            (
+                "xlink_reorder_buffer_overflows_total",
+                "Receive-side reorder buffer overflows",
+                &self.reorder_overflows,
+            ),
+            (
+                "xlink_streamed_sends_total",
+                "Binary payloads sent through the chunked stream path",
+                &self.streamed_sends,
+            ),
+            (
+                "xlink_chunked_sends_total",
+                "Binary payloads sent through the acknowledged chunked path",
+                &self.chunked_sends,
+            ),
+        ];
+        for (name, help, value) in counters {
+            write_family_header(&mut out, name, help, "counter");
+            out.push_str(&format!("{} {}\n", name, value.load(Ordering::Relaxed)));
+        }
+
+        write_family_header(
+            &mut out,
+            "xlink_bytes_sent_total",
+            "Total number of bytes sent per channel",
+            "counter",
+        );
+        write_channel_samples(&mut out, "xlink_bytes_sent_total", &self.channel_bytes_sent);
 
-        for entry in self.channel_usage.iter() {
-            report.push_str(&format!(
-                "xlink_channel_usage_total{{channel=\"{:?}\"}} {}\n",
-                entry.key(),
-                entry.value().load(Ordering::Relaxed)
+        write_family_header(
+            &mut out,
+            "xlink_channel_usage_total",
+            "Total number of messages sent per channel",
+            "counter",
+        );
+        write_channel_samples(&mut out, "xlink_channel_usage_total", &self.channel_usage);
+
+        write_family_header(
+            &mut out,
+            "xlink_peer_in_flight_sends",
+            "Sends currently in flight per peer",
+            "gauge",
+        );
+        for entry in self.in_flight_sends.iter() {
+            out.push_str(&format!(
+                "xlink_peer_in_flight_sends{{device=\"{}\"}} {}\n",
+                escape_label_value(&entry.key().to_string()),
+                entry.value()
             ));
         }
-        report
+        out
     }
 
     /// 记录高级分析事件
@@ -138,6 +419,59 @@ impl MetricsCollector {
             self.last_rtt.remove(&device_id);
         }
 
+        self.channel_bytes_sent.clear();
+        self.in_flight_sends.clear();
+        self.peak_in_flight_sends.clear();
+
+        // 累计计数器归零，需要跨重启保留时由调用方先 persist
+        self.messages_sent.store(0, Ordering::Relaxed);
+        self.messages_received.store(0, Ordering::Relaxed);
+        self.bytes_sent.store(0, Ordering::Relaxed);
+        self.bytes_received.store(0, Ordering::Relaxed);
+        self.stale_messages.store(0, Ordering::Relaxed);
+        self.ephemeral_sent.store(0, Ordering::Relaxed);
+        self.ephemeral_dropped.store(0, Ordering::Relaxed);
+        self.reorder_overflows.store(0, Ordering::Relaxed);
+        self.streamed_sends.store(0, Ordering::Relaxed);
+        self.chunked_sends.store(0, Ordering::Relaxed);
+
         log::debug!("MetricsCollector: Cleared all metrics data using entry removal");
     }
 }
+
+fn write_family_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    out.push_str(&format!(
+        "# HELP {} {}\n# TYPE {} {}\n",
+        name, help, name, kind
+    ));
+}
+
+/// 按通道名排序输出，保证相同计数得到相同的文本
+fn write_channel_samples(out: &mut String, name: &str, values: &DashMap<ChannelType, AtomicU64>) {
+    let mut samples: Vec<(String, u64)> = values
+        .iter()
+        .map(|entry| {
+            (
+                format!("{:?}", entry.key()),
+                entry.value().load(Ordering::Relaxed),
+            )
+        })
+        .collect();
+    samples.sort();
+    for (channel, value) in samples {
+        out.push_str(&format!(
+            "{}{{channel=\"{}\"}} {}\n",
+            name,
+            escape_label_value(&channel),
+            value
+        ));
+    }
+}
+
+/// 按 Prometheus 文本格式转义标签值：反斜杠、双引号与换行
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
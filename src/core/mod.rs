@@ -4,18 +4,40 @@
 //!
 //! # 模块结构
 //!
+//! - [`budget`] - 计量网络流量预算
+//! - [`clock`] - 可替换的时钟，测试中可手动推进
+//! - [`compression`] - 大负载的透明压缩与解压
+//! - [`dedup`] - 接收端跨通道消息去重
+//! - [`digest`] - 结构化数据的 SHA-256 摘要
 //! - [`error`] - 增强的错误类型定义
+//! - [`events`] - SDK 统一事件总线
 //! - [`metrics`] - 性能指标收集
+//! - [`ordering`] - 有序交付与接收端重排
+//! - [`rate_window`] - 跨优先级的出站速率窗口
+//! - [`receive_pool`] - 按发送方分区的接收工作池
+//! - [`retry`] - 按重试建议执行的指数退避重试
+//! - [`send_handle`] - 可取消发送的句柄与结果
 //! - [`traits`] - 核心 trait 接口
 //! - [`types`] - 核心数据类型
 
+pub mod budget;
+pub mod clock;
+pub mod compression;
+pub mod dedup;
+pub mod digest;
 pub mod error;
+pub mod events;
 pub mod metrics;
+pub mod ordering;
+pub mod rate_window;
+pub mod receive_pool;
+pub mod retry;
+pub mod send_handle;
 pub mod traits;
 pub mod types;
 
 // 重新导出常用类型，便于使用
 pub use error::{
-    ErrorCategory, ErrorCode, ErrorContext, ErrorStatistics, ImpactScope, Result, RetrySuggestion,
-    XLinkError,
+    ErrorCategory, ErrorCode, ErrorContext, ErrorStatistics, ErrorStatsSnapshot, ImpactScope,
+    Result, RetrySuggestion, XLinkError,
 };
@@ -0,0 +1,284 @@
+//! 有序交付支持
+//!
+//! 对设置了 `require_ordered` 或在 `DeliveryMode::Ordered` 下发送的消息，发送端按接收方分配递增序号，
+//! 接收端使用 [`ReorderBuffer`] 缓冲乱序到达的消息，按序号恢复顺序后再交付给应用。
+//! 缓冲区大小与溢出处理由 [`ReorderBufferConfig`] 决定，序号空洞再大也不会无限占用内存；
+//! 缺失序号等待超过 `gap_timeout_ms` 后放弃等待，跳过空洞交付已缓冲的消息。
+//! 被跳过的序号会被记录下来（数量有上限），之后补发到达时仍交付一次，而不是当作重复丢弃。
+//! 应用接收队列持续满载时，已取出但未交付的消息通过 [`ReorderBuffer::requeue`] 放回缓冲区。
+//!
+//! 序号在发送方的每个纪元（`Message::sequence_epoch`，每次启动更新）内从 0 递增。
+//! 收到更新纪元的消息说明发送方已重启：先交付旧纪元仍缓冲的消息，再从新纪元的 0 开始；
+//! 旧纪元迟到的消息不参与排序，直接交付。
+
+use crate::core::types::{Message, ReorderBufferConfig, ReorderOverflowPolicy};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// 每个发送方默认最多缓冲的乱序消息数
+pub const MAX_REORDER_BUFFER: usize = 256;
+
+/// 应答一次重传请求时最多重发的消息数
+pub const MAX_RETRANSMIT_PER_REQUEST: usize = MAX_REORDER_BUFFER;
+
+/// 每个发送方最多记录的已跳过序号区间数，超出时丢弃最早的区间
+pub const MAX_SKIPPED_RANGES: usize = 256;
+
+/// 一次缓冲区溢出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorderOverflow {
+    /// 溢出时仍缺失的序号区间
+    pub missing: Range<u64>,
+    /// 采取的处理方式
+    pub policy: ReorderOverflowPolicy,
+}
+
+/// 接收一条消息的结果
+#[derive(Debug, Default)]
+pub struct ReorderPush {
+    /// 当前可以按序交付的消息（可能为空）
+    pub ready: Vec<Message>,
+    /// 本次接收触发了缓冲区溢出
+    pub overflow: Option<ReorderOverflow>,
+}
+
+/// 一次因等待超时而跳过的序号空洞
+#[derive(Debug, Default)]
+pub struct ReorderGap {
+    /// 放弃等待的序号区间
+    pub missing: Range<u64>,
+    /// 跳过空洞后可以按序交付的消息
+    pub ready: Vec<Message>,
+}
+
+/// 单个发送方的接收端重排缓冲区
+#[derive(Debug, Default)]
+pub struct ReorderBuffer {
+    // 当前跟踪的发送方纪元，不带纪元的消息视为纪元 0
+    epoch: u64,
+    next_expected: u64,
+    pending: BTreeMap<u64, Message>,
+    // 开始等待当前缺失序号的时间，没有缓冲消息时为 None
+    waiting_since: Option<Instant>,
+    // 已放弃等待的序号区间（起点 -> 终点，不含终点），迟到的补发据此交付一次
+    skipped: BTreeMap<u64, u64>,
+}
+
+impl ReorderBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 接收一条消息，返回当前可以按序交付的消息及溢出情况
+    ///
+    /// 不带序号的消息直接返回；落在已跳过区间内的迟到消息直接交付一次，
+    /// 其余序号小于期望值的重复消息被丢弃
+    pub fn push(&mut self, message: Message, config: &ReorderBufferConfig) -> ReorderPush {
+        let Some(sequence) = message.sequence else {
+            return ReorderPush {
+                ready: vec![message],
+                overflow: None,
+            };
+        };
+
+        let epoch = message.sequence_epoch.unwrap_or(0);
+        if epoch < self.epoch {
+            log::debug!(
+                "Delivering ordered message {} from an earlier sender epoch {} unordered",
+                message.id,
+                epoch
+            );
+            return ReorderPush {
+                ready: vec![message],
+                overflow: None,
+            };
+        }
+        // 发送方重启后序号从 0 开始：先按序交出旧纪元缓冲的消息
+        let mut flushed = Vec::new();
+        if epoch > self.epoch {
+            if self.epoch != 0 || self.next_expected != 0 || !self.pending.is_empty() {
+                log::info!(
+                    "Sender epoch changed from {} to {}, resetting sequence numbering",
+                    self.epoch,
+                    epoch
+                );
+            }
+            flushed = std::mem::take(&mut self.pending).into_values().collect();
+            self.skipped.clear();
+            self.epoch = epoch;
+            self.next_expected = 0;
+            self.waiting_since = None;
+        }
+
+        if sequence < self.next_expected && self.take_skipped(sequence) {
+            log::debug!(
+                "Delivering late ordered message {} (sequence {}) after its gap was skipped",
+                message.id,
+                sequence
+            );
+            flushed.push(message);
+            return ReorderPush {
+                ready: flushed,
+                overflow: None,
+            };
+        }
+        if sequence < self.next_expected || self.pending.contains_key(&sequence) {
+            log::debug!(
+                "Dropping duplicate ordered message {} (sequence {}, expecting {})",
+                message.id,
+                sequence,
+                self.next_expected
+            );
+            // 放回缓冲区的消息随重发一并交出
+            flushed.extend(self.take_ready());
+            return ReorderPush {
+                ready: flushed,
+                overflow: None,
+            };
+        }
+
+        let mut overflow = None;
+        if sequence != self.next_expected
+            && self.pending.len() >= config.max_entries_per_sender.max(1)
+        {
+            let first = self.pending.keys().next().copied().unwrap_or(sequence);
+            let missing = self.next_expected..first.min(sequence);
+            log::warn!(
+                "Reorder buffer full, missing sequences {}..{} ({:?})",
+                missing.start,
+                missing.end,
+                config.overflow_policy
+            );
+            overflow = Some(ReorderOverflow {
+                missing: missing.clone(),
+                policy: config.overflow_policy,
+            });
+            match config.overflow_policy {
+                ReorderOverflowPolicy::DeliverOutOfOrder => {
+                    self.record_skipped(missing.clone());
+                    self.next_expected = missing.end;
+                }
+                ReorderOverflowPolicy::Drop | ReorderOverflowPolicy::RequestRetransmit => {
+                    return ReorderPush {
+                        ready: flushed,
+                        overflow,
+                    };
+                }
+            }
+        }
+
+        self.pending.insert(sequence, message);
+
+        let ready = self.drain_ready();
+        if self.pending.is_empty() {
+            self.waiting_since = None;
+        } else if !ready.is_empty() || self.waiting_since.is_none() {
+            // 出现新的空洞，重新开始计时
+            self.waiting_since = Some(Instant::now());
+        }
+        flushed.extend(ready);
+        ReorderPush {
+            ready: flushed,
+            overflow,
+        }
+    }
+
+    /// 缺失序号等待超过 `timeout` 时放弃等待，跳过空洞并返回随后可以按序交付的消息
+    pub fn release_expired(&mut self, timeout: Duration) -> Option<ReorderGap> {
+        if self.waiting_since?.elapsed() < timeout {
+            return None;
+        }
+        let first = *self.pending.keys().next()?;
+        let missing = self.next_expected..first;
+        self.record_skipped(missing.clone());
+        self.next_expected = first;
+        let ready = self.drain_ready();
+        self.waiting_since = (!self.pending.is_empty()).then(Instant::now);
+        Some(ReorderGap { missing, ready })
+    }
+
+    /// 交出当前可以按序交付的消息（例如经 [`requeue`](Self::requeue) 放回的消息）
+    pub fn take_ready(&mut self) -> Vec<Message> {
+        let ready = self.drain_ready();
+        if self.pending.is_empty() {
+            self.waiting_since = None;
+        }
+        ready
+    }
+
+    /// 将已按序取出但未能交付的消息放回缓冲区，返回无法放回的消息
+    ///
+    /// `messages` 须为之前交出的顺序。紧接在期望序号之前的连续消息重新排队，
+    /// 期望序号随之回退；其余当前纪元的消息记为已跳过，补发到达时仍交付一次
+    pub fn requeue(&mut self, messages: Vec<Message>) -> Vec<Message> {
+        let mut rejected = Vec::new();
+        for message in messages.into_iter().rev() {
+            match message.sequence {
+                Some(sequence)
+                    if message.sequence_epoch.unwrap_or(0) == self.epoch
+                        && sequence < self.next_expected =>
+                {
+                    if sequence + 1 == self.next_expected {
+                        self.next_expected = sequence;
+                        self.pending.insert(sequence, message);
+                    } else {
+                        self.record_skipped(sequence..sequence + 1);
+                        rejected.push(message);
+                    }
+                }
+                _ => rejected.push(message),
+            }
+        }
+        if !self.pending.is_empty() && self.waiting_since.is_none() {
+            self.waiting_since = Some(Instant::now());
+        }
+        rejected.reverse();
+        rejected
+    }
+
+    fn record_skipped(&mut self, missing: Range<u64>) {
+        if missing.is_empty() {
+            return;
+        }
+        self.skipped.insert(missing.start, missing.end);
+        while self.skipped.len() > MAX_SKIPPED_RANGES {
+            self.skipped.pop_first();
+        }
+    }
+
+    // 序号落在已跳过区间内时将其移出记录并返回 true，保证每个序号最多补交付一次
+    fn take_skipped(&mut self, sequence: u64) -> bool {
+        let Some((&start, &end)) = self.skipped.range(..=sequence).next_back() else {
+            return false;
+        };
+        if sequence >= end {
+            return false;
+        }
+        self.skipped.remove(&start);
+        if start < sequence {
+            self.skipped.insert(start, sequence);
+        }
+        if sequence + 1 < end {
+            self.skipped.insert(sequence + 1, end);
+        }
+        while self.skipped.len() > MAX_SKIPPED_RANGES {
+            self.skipped.pop_first();
+        }
+        true
+    }
+
+    fn drain_ready(&mut self) -> Vec<Message> {
+        let mut ready = Vec::new();
+        while let Some(next) = self.pending.remove(&self.next_expected) {
+            ready.push(next);
+            self.next_expected += 1;
+        }
+        ready
+    }
+
+    /// 当前缓冲中等待缺失序号的消息数
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+}
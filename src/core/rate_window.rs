@@ -0,0 +1,63 @@
+//! 跨优先级的出站速率窗口
+//!
+//! 按优先级划分的限流各自计数；[`SendRateWindow`] 统计本机所有优先级合计的出站量，
+//! Critical 消息在总量之外另有一小份独立额度，关键消息不会被其他优先级的流量挤占。
+
+use crate::core::types::MessagePriority;
+use std::time::{Duration, Instant};
+
+/// 本机跨优先级的出站计数窗口
+#[derive(Debug, Default)]
+pub struct SendRateWindow {
+    window: Duration,
+    state: parking_lot::Mutex<WindowState>,
+}
+
+#[derive(Debug, Default)]
+struct WindowState {
+    // 当前窗口的开始时间，尚未有发送时为 None
+    started: Option<Instant>,
+    total: u32,
+    critical: u32,
+}
+
+impl SendRateWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            state: parking_lot::Mutex::new(WindowState::default()),
+        }
+    }
+
+    /// 在 `now` 计入一次发送，返回是否仍在总量 `limit` 之内
+    ///
+    /// Critical 消息先占用每个窗口 `critical_reserve` 条的独立额度，用完后与其他优先级共享总量
+    pub fn admit(
+        &self,
+        priority: MessagePriority,
+        limit: u32,
+        critical_reserve: u32,
+        now: Instant,
+    ) -> bool {
+        let mut state = self.state.lock();
+        let expired = state
+            .started
+            .is_none_or(|started| now.saturating_duration_since(started) >= self.window);
+        if expired {
+            *state = WindowState {
+                started: Some(now),
+                ..WindowState::default()
+            };
+        }
+        if priority == MessagePriority::Critical && state.critical < critical_reserve {
+            state.critical += 1;
+            return true;
+        }
+        state.total = state.total.saturating_add(1);
+        state.total <= limit
+    }
+
+    pub fn clear(&self) {
+        *self.state.lock() = WindowState::default();
+    }
+}
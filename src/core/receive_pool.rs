@@ -0,0 +1,77 @@
+//! 接收流水线工作池
+//!
+//! 默认情况下入站消息在通道的接收任务中直接处理，耗时的处理（如解密）会阻塞该通道上的后续消息。
+//! 启用工作池后，消息按发送方哈希分配给固定的工作者：同一发送方的消息始终由同一工作者
+//! 按到达顺序处理，不同发送方的消息可以并行处理。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::MessageHandler;
+use crate::core::types::{DeviceId, Message};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 按发送方分区的有界接收工作池
+pub struct ReceivePool {
+    workers: Vec<mpsc::Sender<Message>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ReceivePool {
+    /// 创建 `workers` 个工作者（最少 1 个），每个工作者的队列最多积压 `queue_capacity` 条消息
+    ///
+    /// `handler` 在工作者任务中以内联方式处理消息，不能再把消息分派回本工作池
+    pub fn new(workers: usize, queue_capacity: usize, handler: Arc<dyn MessageHandler>) -> Self {
+        let (workers, tasks) = (0..workers.max(1))
+            .map(|index| {
+                let (tx, mut rx) = mpsc::channel::<Message>(queue_capacity.max(1));
+                let handler = handler.clone();
+                let task = tokio::spawn(async move {
+                    while let Some(message) = rx.recv().await {
+                        let message_id = message.id;
+                        if let Err(e) = handler.handle_message(message).await {
+                            log::warn!(
+                                "Receive worker {} failed to handle message {}: {}",
+                                index,
+                                message_id,
+                                e
+                            );
+                        }
+                    }
+                });
+                (tx, task)
+            })
+            .unzip();
+        Self { workers, tasks }
+    }
+
+    /// 工作者数量
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// 将消息交给其发送方对应的工作者，队列已满时等待空位（对通道形成背压）
+    pub async fn dispatch(&self, message: Message) -> Result<()> {
+        let worker = &self.workers[self.worker_for(&message.sender)];
+        worker.send(message).await.map_err(|_| {
+            XLinkError::channel_disconnected("receive worker stopped".to_string(), file!())
+        })
+    }
+
+    /// 发送方对应的工作者编号
+    pub fn worker_for(&self, sender: &DeviceId) -> usize {
+        let mut hasher = DefaultHasher::new();
+        sender.hash(&mut hasher);
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+}
+
+impl Drop for ReceivePool {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
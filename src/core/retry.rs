@@ -0,0 +1,95 @@
+//! 按错误的重试建议执行指数退避重试
+//!
+//! 错误携带的 [`RetrySuggestion::Retryable`] 给出最大重试次数与基础延迟，
+//! 第 `n` 次重试前等待 `base_delay_ms * 2^n` 毫秒并叠加至多一半的随机抖动，
+//! 避免多个发送方同时重试。其余重试建议（含未给出建议）立即返回错误。
+//!
+//! 通道重连不限次数，使用 [`ReconnectBackoff`] 按配置翻倍等待并封顶，成功后重置。
+
+use crate::core::error::{Result, RetrySuggestion};
+use crate::core::types::ReconnectConfig;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+/// 退避指数上限，防止基础延迟左移溢出
+const MAX_BACKOFF_SHIFT: u32 = 20;
+
+/// 执行 `op`，遇到可重试错误时按其建议退避重试
+///
+/// 每次失败都以本次错误的建议为准：超过其 `max_attempts` 或错误不可重试时返回该错误
+pub async fn retry_with_suggestion<F, Fut, T>(mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0u32;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let Some(delay) = backoff_delay(e.retry_suggestion(), attempt) else {
+                    return Err(e);
+                };
+                log::debug!(
+                    "Retrying after {:?} (attempt {}): {}",
+                    delay,
+                    attempt + 1,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// 第 `attempt` 次重试（从 0 开始）前的等待时间，不应再重试时返回 None
+pub fn backoff_delay(suggestion: Option<RetrySuggestion>, attempt: u32) -> Option<Duration> {
+    match suggestion {
+        Some(RetrySuggestion::Retryable {
+            max_attempts,
+            base_delay_ms,
+        }) if attempt < max_attempts => {
+            let delay_ms = base_delay_ms.saturating_mul(1u64 << attempt.min(MAX_BACKOFF_SHIFT));
+            let jitter_ms = rand::thread_rng().gen_range(0..=delay_ms / 2);
+            Some(Duration::from_millis(delay_ms.saturating_add(jitter_ms)))
+        }
+        _ => None,
+    }
+}
+
+/// 通道重连的指数退避状态
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    config: ReconnectConfig,
+    attempt: u32,
+}
+
+impl ReconnectBackoff {
+    pub fn new(config: ReconnectConfig) -> Self {
+        Self { config, attempt: 0 }
+    }
+
+    /// 下一次重连前的等待时间：`initial_delay_ms * 2^n` 封顶于 `max_delay_ms`
+    pub fn next_delay(&mut self) -> Duration {
+        let delay_ms = self
+            .config
+            .initial_delay_ms
+            .saturating_mul(1u64 << self.attempt.min(MAX_BACKOFF_SHIFT))
+            .min(self.config.max_delay_ms);
+        self.attempt = self.attempt.saturating_add(1);
+        Duration::from_millis(delay_ms)
+    }
+
+    /// 自上次重置以来已安排的重连次数
+    pub fn attempts(&self) -> u32 {
+        self.attempt
+    }
+
+    /// 重连成功后回到初始等待时间，并采用最新配置
+    pub fn reset(&mut self, config: ReconnectConfig) {
+        self.config = config;
+        self.attempt = 0;
+    }
+}
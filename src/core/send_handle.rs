@@ -0,0 +1,63 @@
+//! 可取消的发送
+//!
+//! `XLink::send_cancellable` 返回发送句柄与发送 future。句柄可在任意任务中取消发送：
+//! 取消时停止尚未发出的流分片，并清除该消息在存储中的持久化副本。
+
+use std::sync::Arc;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// 可取消发送的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// 消息已由通道发出
+    Sent { message_id: Uuid },
+    /// 大消息经流式传输发出了全部分片
+    Streamed { stream_id: Uuid },
+    /// 关闭自动流式传输时，大消息经可靠分片发出，接收方已确认全部分片
+    Chunked { stream_id: Uuid },
+    /// 发送在完成前被取消
+    Cancelled,
+    /// 消息被出站插件丢弃，未发出
+    Dropped,
+}
+
+/// 发送句柄，可克隆后交给其他任务（如界面上的取消按钮）
+#[derive(Debug, Clone)]
+pub struct SendHandle {
+    message_id: Uuid,
+    cancel_tx: Arc<watch::Sender<bool>>,
+}
+
+impl SendHandle {
+    pub(crate) fn new(message_id: Uuid) -> (Self, watch::Receiver<bool>) {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        let handle = Self {
+            message_id,
+            cancel_tx: Arc::new(cancel_tx),
+        };
+        (handle, cancel_rx)
+    }
+
+    /// 本次发送的消息 ID
+    pub fn message_id(&self) -> Uuid {
+        self.message_id
+    }
+
+    /// 取消发送，发送已完成时无效果
+    pub fn cancel(&self) {
+        self.cancel_tx.send_replace(true);
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_tx.borrow()
+    }
+}
+
+/// 等待取消请求；句柄全部丢弃而未取消时永不返回
+pub(crate) async fn cancelled(cancel_rx: &mut watch::Receiver<bool>) {
+    if cancel_rx.wait_for(|cancelled| *cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
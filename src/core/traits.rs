@@ -1,12 +1,42 @@
-use crate::core::error::Result;
-use crate::core::types::{ChannelState, ChannelType, DeviceId, Message};
+use crate::core::error::{Result, XLinkError};
+use crate::core::types::{
+    AuditEntry, AuditEntryPage, AuditFilter, AuditLogPage, ChannelState, ChannelType,
+    DeliveryReceipt, DeviceId, Message, MessagePayload, RecoveryFilter, MAX_AUDIT_QUERY_SCAN_BYTES,
+};
+use crate::crypto::engine::CryptoEngine;
 use async_trait::async_trait;
+use dashmap::DashMap;
+use ed25519_dalek::VerifyingKey;
+use std::sync::Arc;
+
+/// Local identity handed to channels that authenticate peers at the transport level.
+#[derive(Clone)]
+pub struct ChannelIdentity {
+    /// ID of the local device
+    pub device_id: DeviceId,
+    /// Engine holding the local identity key, used to prove the local identity
+    pub signer: Arc<CryptoEngine>,
+    /// Identity keys pinned by the application, used to verify peers
+    pub pinned_keys: Arc<DashMap<DeviceId, VerifyingKey>>,
+}
 
 #[async_trait]
 pub trait Channel: Send + Sync {
     /// Get the type of this channel
     fn channel_type(&self) -> ChannelType;
 
+    /// Whether this channel delivers messages in the order they were sent.
+    /// Defaults to the ordering characteristics of the channel type.
+    fn is_ordered(&self) -> bool {
+        self.channel_type().is_ordered()
+    }
+
+    /// Largest payload in bytes a single message on this channel can carry,
+    /// or `None` when unbounded. Defaults to the limit of the channel type.
+    fn max_payload(&self) -> Option<usize> {
+        self.channel_type().max_payload()
+    }
+
     /// Send a message to a specific device
     async fn send(&self, message: Message) -> Result<()>;
 
@@ -16,20 +46,40 @@ pub trait Channel: Send + Sync {
     /// Start listening for incoming messages
     async fn start(&self) -> Result<()>;
 
+    /// Prepare the outbound path to a target ahead of the first send
+    /// (e.g. open a connection). Defaults to a state check.
+    async fn warm_up(&self, target: &DeviceId) -> Result<()> {
+        self.check_state(target).await.map(|_| ())
+    }
+
     /// Start listening for incoming messages with a handler
     async fn start_with_handler(
         &self,
-        _handler: std::sync::Arc<dyn MessageHandler>,
+        _handler: Arc<dyn MessageHandler>,
     ) -> Result<Option<tokio::task::JoinHandle<()>>> {
         self.start().await?;
         Ok(None)
     }
 
+    /// Provide the local identity and the pinned peer keys. Channels that bind
+    /// replies to inbound connections use it to authenticate the connecting
+    /// device first. Defaults to a no-op for channels that rely on message
+    /// signatures alone.
+    fn set_identity(&self, _identity: ChannelIdentity) {}
+
     /// Clear the message handler (for cleanup to prevent memory leaks)
     async fn clear_handler(&self) -> Result<()> {
         // Default implementation does nothing
         Ok(())
     }
+
+    /// Re-establish the underlying connection after it dropped. Called by the
+    /// SDK with exponential backoff until it succeeds; the listener is then
+    /// restarted through `start_with_handler` if its task had exited.
+    /// Defaults to a no-op for channels without a persistent connection.
+    async fn reconnect(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -42,17 +92,149 @@ pub trait Storage: Send + Sync {
     async fn save_audit_log(&self, log: String) -> Result<()>;
     async fn get_audit_logs(&self, limit: usize) -> Result<Vec<String>>;
 
-    // 数据清理支持
+    /// 分页读取审计日志（从新到旧），读取字节数超过 `max_bytes_scanned` 时返回部分结果
+    ///
+    /// 每页至少返回一条（若存在），保证分页总能向前推进。默认实现基于 `get_audit_logs`，
+    /// 需要读取偏移之前的全部条目，存储后端应尽量提供按偏移直接定位的实现。
+    async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+    ) -> Result<AuditLogPage> {
+        let logs = self
+            .get_audit_logs(offset.saturating_add(limit).saturating_add(1))
+            .await?;
+        Ok(AuditLogPage::collect(
+            offset,
+            limit,
+            max_bytes_scanned,
+            logs.into_iter().skip(offset),
+        ))
+    }
+
+    /// 保存一条结构化审计记录，默认序列化为 JSON 后经 `save_audit_log` 保存
+    async fn save_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.save_audit_log(serde_json::to_string(entry)?).await
+    }
+
+    /// 按条件分页查询审计记录（从新到旧），`page` 从 0 开始
+    ///
+    /// 默认实现经 `get_audit_logs_paged` 分批读取并逐条过滤，最多扫描
+    /// [`MAX_AUDIT_QUERY_SCAN_BYTES`] 字节，超出时返回部分结果；旧格式的纯文本日志按
+    /// [`AuditEntry::parse`] 转换。存储后端应尽量在读取时按条件过滤。
+    async fn query_audit_logs(
+        &self,
+        filter: &AuditFilter,
+        page: usize,
+        page_size: usize,
+    ) -> Result<AuditEntryPage> {
+        const BATCH: usize = 256;
+        let skip = page.saturating_mul(page_size);
+        let mut result = AuditEntryPage::default();
+        let (mut offset, mut scanned) = (0usize, 0u64);
+        loop {
+            let batch = self
+                .get_audit_logs_paged(
+                    offset,
+                    BATCH,
+                    MAX_AUDIT_QUERY_SCAN_BYTES.saturating_sub(scanned),
+                )
+                .await?;
+            for line in &batch.entries {
+                scanned += line.len() as u64;
+                let entry = AuditEntry::parse(line);
+                if !filter.matches(&entry) {
+                    continue;
+                }
+                if result.total >= skip && result.entries.len() < page_size {
+                    result.entries.push(entry);
+                }
+                result.total += 1;
+            }
+            let Some(next) = batch.next_offset else { break };
+            if scanned >= MAX_AUDIT_QUERY_SCAN_BYTES {
+                result.partial = true;
+                break;
+            }
+            offset = next;
+        }
+        Ok(result)
+    }
+
+    // 数据清理支持（审计记录同样按写入时间清理）
     async fn cleanup_old_data(&self, days: u32) -> Result<u64>;
 
-    // 消息队列持久化支持（用于设备崩溃恢复）
+    // 消息队列持久化支持（用于设备崩溃恢复），待发送消息按发送方分组
     async fn save_pending_message(&self, message: &Message) -> Result<()>;
     async fn get_pending_messages_for_recovery(&self, device_id: &DeviceId)
         -> Result<Vec<Message>>;
+    /// 只取满足 `filter` 的待发送消息，默认实现基于全量结果筛选，后端应在读取时过滤
+    async fn get_pending_messages_for_recovery_filtered(
+        &self,
+        device_id: &DeviceId,
+        filter: &RecoveryFilter,
+    ) -> Result<Vec<Message>> {
+        Ok(filter.apply(self.get_pending_messages_for_recovery(device_id).await?))
+    }
     async fn remove_pending_message(&self, message_id: &uuid::Uuid) -> Result<()>;
 
+    // 投递回执，默认实现以元数据保存，后端应覆盖以纳入保留天数清理
+    async fn save_delivery_receipt(&self, receipt: &DeliveryReceipt) -> Result<()> {
+        let key = DeliveryReceipt::metadata_key(&receipt.message_id);
+        self.save_metadata(&key, serde_json::to_vec(receipt)?).await
+    }
+
+    async fn get_delivery_receipt(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> Result<Option<DeliveryReceipt>> {
+        match self
+            .load_metadata(&DeliveryReceipt::metadata_key(message_id))
+            .await?
+        {
+            Some(data) if !data.is_empty() => Ok(Some(serde_json::from_slice(&data)?)),
+            _ => Ok(None),
+        }
+    }
+
+    // 全量枚举（用于存储后端迁移），默认实现返回不支持错误，后端应覆盖以支持迁移
+    async fn list_messages(&self) -> Result<Vec<Message>> {
+        Err(XLinkError::invalid_state(
+            "list_messages",
+            "storage backend does not support enumeration",
+            file!(),
+        ))
+    }
+
+    async fn list_pending_messages(&self) -> Result<Vec<Message>> {
+        Err(XLinkError::invalid_state(
+            "list_pending_messages",
+            "storage backend does not support enumeration",
+            file!(),
+        ))
+    }
+
+    // 键值元数据（如跨重启保留的指标），键只允许字母、数字、`-` 与 `_`
+    //
+    // 默认实现不持久化：保存返回不支持错误，读取视为从未保存
+    async fn save_metadata(&self, _key: &str, _value: Vec<u8>) -> Result<()> {
+        Err(XLinkError::invalid_state(
+            "save_metadata",
+            "storage backend does not support metadata",
+            file!(),
+        ))
+    }
+
+    async fn load_metadata(&self, _key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
     // 存储空间管理
     async fn get_storage_usage(&self) -> Result<u64>;
+    /// 从最早写入的已存消息与审计日志开始删除，直到占用不超过目标大小，返回释放的字节数
+    ///
+    /// 待发送消息与元数据不会被删除
     async fn cleanup_storage(&self, target_size_bytes: u64) -> Result<u64>;
 
     // 索引清理（用于内存泄漏防护）
@@ -67,12 +249,35 @@ pub trait MessageHandler: Send + Sync {
     async fn handle_message(&self, message: Message) -> Result<()>;
 }
 
+/// 插件处理消息后的决定
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginAction {
+    /// 保留消息（可能已被原地修改），交给下一个插件
+    Continue,
+    /// 丢弃消息：出站消息不发出，入站消息不交付给应用，均不视为错误
+    Drop,
+    /// 以新的负载替换消息负载后交给下一个插件
+    Replace(MessagePayload),
+}
+
 /// 插件系统 Trait
+///
+/// 出站钩子在发送端选路之前、入站钩子在接收端交付应用之前按注册顺序调用，
+/// 默认实现不做任何处理
+#[async_trait]
 pub trait Plugin: Send + Sync {
     fn name(&self) -> &str;
     fn version(&self) -> &str;
     fn on_load(&self) -> Result<()>;
     fn on_unload(&self) -> Result<()>;
+
+    async fn on_outbound(&self, _message: &mut Message) -> Result<PluginAction> {
+        Ok(PluginAction::Continue)
+    }
+
+    async fn on_inbound(&self, _message: &mut Message) -> Result<PluginAction> {
+        Ok(PluginAction::Continue)
+    }
 }
 
 /// 自定义通道插件
@@ -95,6 +95,30 @@ impl ChannelType {
             ChannelType::Lan => 2,
         }
     }
+
+    /// 通道是否保证按发送顺序交付
+    ///
+    /// BLE GATT 与 WiFi Direct (TCP) 有序；Mesh 泛洪、基于 UDP 的 LAN 与 ntfy 推送不保证顺序
+    pub fn is_ordered(&self) -> bool {
+        match self {
+            ChannelType::BluetoothLE => true,
+            ChannelType::BluetoothMesh => false,
+            ChannelType::WiFiDirect => true,
+            ChannelType::Internet => false,
+            ChannelType::Lan => false,
+        }
+    }
+
+    /// 单条消息可承载的最大负载字节数，None 表示不限
+    ///
+    /// BLE GATT 单次写入受 ATT MTU 限制，Mesh 接入层 PDU 更小；其余通道基于 IP，不设上限
+    pub fn max_payload(&self) -> Option<usize> {
+        match self {
+            ChannelType::BluetoothLE => Some(512),
+            ChannelType::BluetoothMesh => Some(380),
+            ChannelType::WiFiDirect | ChannelType::Internet | ChannelType::Lan => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -108,7 +132,7 @@ pub enum NetworkType {
     Loopback,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum MessagePriority {
     Low,
     Normal,
@@ -116,7 +140,7 @@ pub enum MessagePriority {
     Critical,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MemberRole {
     Admin,
     Member,
@@ -169,6 +193,704 @@ impl Default for ComplianceConfig {
     }
 }
 
+/// 时间戳超出时钟偏差容忍窗口时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClockSkewAction {
+    /// 将时间戳钳制回容忍窗口内（未来时间戳钳制为本地当前时间）
+    #[default]
+    Clamp,
+    /// 直接拒绝该消息
+    Reject,
+}
+
+/// 接收消息时间戳的时钟偏差容忍配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSkewConfig {
+    /// 允许发送方时钟与本地时钟的最大偏差（秒）
+    pub max_skew_secs: u64,
+    /// 超出窗口时的处理方式
+    pub action: ClockSkewAction,
+}
+
+impl Default for ClockSkewConfig {
+    fn default() -> Self {
+        Self {
+            max_skew_secs: 300,
+            action: ClockSkewAction::Clamp,
+        }
+    }
+}
+
+/// 超过最大消息年龄的入站消息的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StaleMessageAction {
+    /// 丢弃过期消息，不交付给应用
+    #[default]
+    Drop,
+    /// 照常交付，同时发布 `StaleMessageReceived` 事件供应用标记
+    Deliver,
+}
+
+/// 入站消息的最大年龄配置
+///
+/// 按发送方时间戳计算年龄，并额外放宽时钟偏差容忍窗口，避免误判时钟略慢的发送方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MessageAgeConfig {
+    /// 最大消息年龄（秒），None 表示不限制
+    pub max_age_secs: Option<u64>,
+    pub action: StaleMessageAction,
+}
+
+/// 重排缓冲区满时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReorderOverflowPolicy {
+    /// 跳过缺失序号，交付已缓冲的消息（顺序出现空洞）
+    #[default]
+    DeliverOutOfOrder,
+    /// 丢弃新到达的消息，继续等待缺失序号
+    Drop,
+    /// 丢弃新到达的消息，向发送方请求重传缺失区间与该消息；
+    /// 发送方从待发送队列与消息日志中查找并重发
+    RequestRetransmit,
+}
+
+/// 接收端重排缓冲区配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReorderBufferConfig {
+    /// 每个发送方最多缓冲的乱序消息数
+    pub max_entries_per_sender: usize,
+    pub overflow_policy: ReorderOverflowPolicy,
+    /// 等待缺失序号的最长时间（毫秒），超时后跳过空洞交付已缓冲的消息；None 表示一直等待
+    pub gap_timeout_ms: Option<u64>,
+}
+
+impl Default for ReorderBufferConfig {
+    fn default() -> Self {
+        Self {
+            max_entries_per_sender: crate::core::ordering::MAX_REORDER_BUFFER,
+            overflow_policy: ReorderOverflowPolicy::default(),
+            gap_timeout_ms: Some(2000),
+        }
+    }
+}
+
+/// 点对点消息的交付顺序
+///
+/// `Ordered` 为发往同一接收方的每条直接发送的消息分配递增序号，接收端按序号恢复顺序后交付，
+/// 不限制通道；缺失的序号按 [`ReorderBufferConfig`] 的超时与容量放弃等待。
+/// 自动流式传输的大负载不分配序号，也不参与排序。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeliveryMode {
+    #[default]
+    Unordered,
+    Ordered,
+}
+
+/// 接收端消息去重配置
+///
+/// 记录最近收到的 (发送方, 消息 ID)，同一消息经不同通道或中继重复到达时只交付一次
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupConfig {
+    /// 最多记录的消息数，超出时淘汰最早的记录；0 表示关闭去重
+    pub capacity: usize,
+    /// 记录的有效期（秒），超过后同一消息再次到达视为新消息
+    pub ttl_secs: u64,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 4096,
+            ttl_secs: 300,
+        }
+    }
+}
+
+/// 按消息优先级划分的速率限制（每秒每设备消息数）
+///
+/// 每个优先级拥有独立的计数窗口，低优先级流量被限流时不会挤占高优先级的配额。
+/// 出站方向另有跨优先级的总量窗口（见 [`RateLimitConfig`]），`critical_reserve`
+/// 条以内的 Critical 消息不计入总量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriorityRateLimits {
+    pub low: u32,
+    pub normal: u32,
+    pub high: u32,
+    pub critical: u32,
+    #[serde(default = "default_critical_reserve")]
+    pub critical_reserve: u32,
+}
+
+fn default_critical_reserve() -> u32 {
+    10
+}
+
+impl PriorityRateLimits {
+    pub fn limit_for(&self, priority: MessagePriority) -> u32 {
+        match priority {
+            MessagePriority::Low => self.low,
+            MessagePriority::Normal => self.normal,
+            MessagePriority::High => self.high,
+            MessagePriority::Critical => self.critical,
+        }
+    }
+}
+
+impl Default for PriorityRateLimits {
+    fn default() -> Self {
+        Self {
+            low: 100,
+            normal: 100,
+            high: 100,
+            critical: 100,
+            critical_reserve: default_critical_reserve(),
+        }
+    }
+}
+
+/// 收发两个方向的速率限制（每秒每设备消息数）
+///
+/// 入站按发送方计数，出站按本机计数；`burst` 为每个计数窗口在速率之外额外允许的消息数。
+/// 速率为 0 时关闭该方向的限流。每个优先级仍受 [`PriorityRateLimits`] 约束，
+/// 实际上限取两者中较小的速率再加上 `burst`；出站各优先级合计同样不超过速率加 `burst`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub per_sender_per_sec: u32,
+    pub per_device_send_per_sec: u32,
+    pub burst: u32,
+}
+
+impl RateLimitConfig {
+    /// 入站某优先级的窗口上限，`None` 表示不限流
+    pub fn inbound_limit(&self, priority_limit: u32) -> Option<u32> {
+        self.effective_limit(self.per_sender_per_sec, priority_limit)
+    }
+
+    /// 出站某优先级的窗口上限，`None` 表示不限流
+    pub fn outbound_limit(&self, priority_limit: u32) -> Option<u32> {
+        self.effective_limit(self.per_device_send_per_sec, priority_limit)
+    }
+
+    fn effective_limit(&self, rate: u32, priority_limit: u32) -> Option<u32> {
+        (rate > 0).then(|| rate.min(priority_limit).saturating_add(self.burst))
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_sender_per_sec: 100,
+            per_device_send_per_sec: 100,
+            burst: 0,
+        }
+    }
+}
+
+/// 流数据交付配置
+///
+/// 默认由 SDK 缓存 `StreamChunk` 分片并在收齐后以完整 `Binary` 交付，应用实现简单，
+/// 但必须等待全部分片到达且整段数据驻留内存。开启 `deliver_raw_stream_chunks` 后分片
+/// 原样交付给应用，可边收边解码、自行处理乱序与丢片；代价是 SDK 不再重组、不再校验
+/// 分片完整性，应用需要自行按 `stream_id` 与 `chunk_index` 拼装，分片的 `compression`
+/// 不为 None 时拼装结果还需自行解压。`StreamFrame` 始终原样交付。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StreamDeliveryConfig {
+    pub deliver_raw_stream_chunks: bool,
+}
+
+/// 负载压缩算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompressionAlgorithm {
+    Deflate,
+}
+
+/// 发送端负载压缩配置
+///
+/// 只压缩不小于 `min_size_bytes` 的 `Binary` 负载；自动流式传输按压缩后的大小判断。
+/// 接收端总能解压，无需配置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    /// 压缩算法，None 表示不压缩
+    pub algorithm: Option<CompressionAlgorithm>,
+    pub min_size_bytes: usize,
+    /// 压缩级别 0-9，越大压缩率越高、越耗 CPU
+    pub level: u32,
+    /// 采样熵判定为已压缩的数据时跳过压缩
+    pub skip_incompressible: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: None,
+            min_size_bytes: 4 * 1024,
+            level: 6,
+            skip_incompressible: true,
+        }
+    }
+}
+
+/// 自动流式传输配置
+///
+/// `Binary` 负载（压缩后）超过 `auto_stream_threshold_bytes` 且不要求有序、确认、主题或关联时，
+/// 改经流管理器分片发送：分片按码率节奏发出，由接收方逐片确认，可通过 `resume_transfer` 续传，
+/// 但不写入待发送队列、不重试，`send_cancellable` 返回 `SendOutcome::Streamed`。
+/// 不超过阈值时走直接路径：先持久化再发送，失败可重试并遵循 `require_ack`。
+/// 阈值为 None 时关闭自动流式传输：超过单个分片（32KB）的负载经可靠分片发送，发送等待接收方确认
+/// 全部分片、确认超时的分片重发，返回 `SendOutcome::Chunked`；有序、确认等不分片的消息仍走直接路径。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamConfig {
+    pub auto_stream_threshold_bytes: Option<usize>,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            auto_stream_threshold_bytes: Some(32 * 1024),
+        }
+    }
+}
+
+/// 群组密钥配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GroupKeyConfig {
+    /// `create_group` 为未注册公钥的成员生成随机占位公钥
+    ///
+    /// 仅用于测试环境：占位公钥没有对应的私钥，这些成员永远无法解密群组消息。
+    /// 默认关闭，缺少公钥的成员使 `create_group` 返回错误
+    pub placeholder_keys_for_testing: bool,
+}
+
+/// 发送路由配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// 找不到路由时为接收方在全部本地通道上伪造默认通道状态并重试
+    ///
+    /// 仅用于测试环境：开启后对端不可达会被当作可达，默认关闭，找不到路由时直接返回错误
+    pub auto_seed_channel_state: bool,
+}
+
+/// 点对点确认发送的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckStatus {
+    /// 接收方已确认交付
+    Delivered,
+    /// 超时前未收到确认，消息可能仍会到达
+    TimedOut,
+    /// 消息未能发出，或等待确认期间 SDK 已停止
+    Failed,
+}
+
+/// 发送重试配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SendRetryConfig {
+    /// 通道发送失败时按错误的重试建议退避重试（如通道暂时断开），重试耗尽后再放入待发送队列
+    ///
+    /// 默认关闭：失败立即返回错误并放入待发送队列
+    pub retry_transient_failures: bool,
+}
+
+/// 通道断线重连配置
+///
+/// 通道接收任务退出或发送返回 `channel_disconnected` 时，SDK 以指数退避调用
+/// [`Channel::reconnect`](crate::core::traits::Channel::reconnect)，直至成功后重置退避
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconnectConfig {
+    /// 是否自动重连
+    pub enabled: bool,
+    /// 首次重连前的等待时间（毫秒），之后每次失败翻倍
+    pub initial_delay_ms: u64,
+    /// 退避等待的上限（毫秒）
+    pub max_delay_ms: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+/// 指标配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// 停止时将累计计数器写入存储，启动时恢复，使计数跨越重启
+    pub persist_across_restarts: bool,
+}
+
+/// 电量保护策略
+///
+/// 电量低于下限且未充电时拒绝 `Low`/`Normal` 优先级的发送并返回可重试错误，`High`/`Critical` 不受影响；
+/// 开始充电或电量回到下限以上后自动恢复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BatteryPolicy {
+    /// 电量下限（百分比），None 表示不限制
+    pub battery_floor: Option<u8>,
+}
+
+impl BatteryPolicy {
+    /// 当前电量状态下是否限制该优先级的发送，电量未知时不限制
+    pub fn blocks(
+        &self,
+        priority: MessagePriority,
+        battery_level: Option<u8>,
+        is_charging: bool,
+    ) -> bool {
+        let below_floor = match (self.battery_floor, battery_level) {
+            (Some(floor), Some(level)) => level < floor,
+            _ => false,
+        };
+        below_floor
+            && !is_charging
+            && matches!(priority, MessagePriority::Low | MessagePriority::Normal)
+    }
+}
+
+/// SDK 停止原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownReason {
+    /// 应用调用 `stop`
+    Requested,
+    /// 低电量保护触发的关闭
+    LowBattery,
+}
+
+/// 优雅关闭配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// `shutdown` 等待在途发送与存储写入完成的最长时间（毫秒），超时仍未发出的消息转入待发送队列
+    pub grace_period_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_ms: 5_000,
+        }
+    }
+}
+
+/// 正常退出时写入存储的标记，下次启动据此区分正常重启与崩溃
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownMarker {
+    pub reason: ShutdownReason,
+    /// 写入时间（Unix 秒）
+    pub timestamp: u64,
+    /// 退出时仍待重发的消息数，为 0 时启动可跳过恢复
+    pub pending_messages: usize,
+}
+
+/// 崩溃恢复进度，每处理完一条待发送消息上报一次
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryProgress {
+    /// 需要重发的消息总数
+    pub total: usize,
+    /// 已处理的消息数
+    pub processed: usize,
+    /// 重发成功的消息数
+    pub succeeded: usize,
+    /// 重发失败的消息数
+    pub failed: usize,
+}
+
+/// 选择性恢复待发送消息的条件，未设置的条件不做限制
+///
+/// 不满足条件的消息留在待发送队列中，留待之后的完整恢复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RecoveryFilter {
+    /// 只恢复该群组的消息
+    pub group_id: Option<GroupId>,
+    /// 只恢复不低于该优先级的消息
+    pub min_priority: Option<MessagePriority>,
+    /// 最多恢复的消息数，按写入先后取最早的
+    pub max_count: Option<usize>,
+}
+
+impl RecoveryFilter {
+    /// 消息是否满足群组与优先级条件（不考虑 `max_count`）
+    pub fn matches(&self, message: &Message) -> bool {
+        self.group_id.is_none_or(|id| message.group_id == Some(id))
+            && self.min_priority.is_none_or(|p| message.priority >= p)
+    }
+
+    /// 按条件筛选消息并截断到 `max_count`
+    pub fn apply(&self, messages: impl IntoIterator<Item = Message>) -> Vec<Message> {
+        messages
+            .into_iter()
+            .filter(|message| self.matches(message))
+            .take(self.max_count.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// 上一次运行的退出方式，由 `start` 根据退出标记判定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviousExit {
+    /// 正常退出
+    Clean(ShutdownMarker),
+    /// 没有退出标记：上次运行崩溃或未调用 `stop`
+    Crashed,
+}
+
+/// 通道预热配置
+///
+/// 开启后 `start` 会在后台为最近活跃的对端提前建立出站通路，避免启动后首条消息承担连接建立延迟
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelWarmupConfig {
+    /// 是否在 `start` 后自动预热
+    pub enabled: bool,
+    /// 只预热最近一次心跳在该时长（秒）内的对端
+    pub recent_within_secs: u64,
+    /// 最多预热的对端数，按最近活跃时间优先
+    pub max_peers: usize,
+    /// 同时进行的预热数
+    pub max_concurrency: usize,
+    /// 电量低于该值（百分比）且未充电时跳过预热
+    pub min_battery_level: u8,
+}
+
+impl Default for ChannelWarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            recent_within_secs: 24 * 3600,
+            max_peers: 16,
+            max_concurrency: 4,
+            min_battery_level: 20,
+        }
+    }
+}
+
+/// 接收流水线配置
+///
+/// 默认在通道接收任务中逐条内联处理，顺序最简单；开启工作池后同一发送方的消息仍按到达顺序处理，
+/// 不同发送方之间不再相互阻塞
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivePipelineConfig {
+    /// 工作者数量，0 表示内联处理
+    pub workers: usize,
+    /// 每个工作者最多积压的消息数，队列满时对通道形成背压
+    pub queue_capacity: usize,
+}
+
+impl Default for ReceivePipelineConfig {
+    fn default() -> Self {
+        Self {
+            workers: 0,
+            queue_capacity: 256,
+        }
+    }
+}
+
+/// 应用接收队列配置
+///
+/// 队列满时接收端等待应用取走消息，超过等待时间则向通道返回资源耗尽错误，而不是静默丢弃
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppQueueConfig {
+    /// 队列最多积压的消息数
+    pub capacity: usize,
+    /// 队列满时等待应用取走消息的最长时间（毫秒）
+    pub send_timeout_ms: u64,
+}
+
+impl Default for AppQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            send_timeout_ms: 1000,
+        }
+    }
+}
+
+/// 审计日志查询配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditQueryConfig {
+    /// 单次分页查询最多读取的日志字节数，达到上限时提前返回部分结果
+    pub max_bytes_scanned: u64,
+}
+
+impl Default for AuditQueryConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes_scanned: 1024 * 1024,
+        }
+    }
+}
+
+/// 审计日志分页查询结果，条目按从新到旧排列
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuditLogPage {
+    pub entries: Vec<String>,
+    /// 下一页的起始偏移，None 表示已无更多条目
+    pub next_offset: Option<usize>,
+    /// 因扫描字节数达到上限而提前返回，`entries` 少于请求的条数
+    pub partial: bool,
+}
+
+impl AuditLogPage {
+    /// 从已跳过 `offset` 条的日志序列中收集一页，超过字节上限时提前停止（至少收集一条）
+    pub fn collect(
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+        logs: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let mut logs = logs.into_iter().peekable();
+        let mut page = Self::default();
+        let mut scanned = 0u64;
+        while page.entries.len() < limit {
+            let Some(next) = logs.peek() else { break };
+            let size = next.len() as u64;
+            if !page.entries.is_empty() && scanned + size > max_bytes_scanned {
+                page.partial = true;
+                break;
+            }
+            scanned += size;
+            page.entries.extend(logs.next());
+        }
+        if logs.peek().is_some() {
+            page.next_offset = Some(offset + page.entries.len());
+        }
+        page
+    }
+}
+
+/// 结构化审计记录，以 JSON 形式保存在审计日志中
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// 记录时间（Unix 秒）
+    pub timestamp: u64,
+    /// 执行操作的设备，旧格式的纯文本日志为 None
+    pub device_id: Option<DeviceId>,
+    pub action: String,
+    #[serde(default)]
+    pub details: Option<String>,
+}
+
+impl AuditEntry {
+    /// 以当前时间创建一条审计记录
+    pub fn new(device_id: DeviceId, action: impl Into<String>) -> Self {
+        Self {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            device_id: Some(device_id),
+            action: action.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// 解析一行审计日志；旧格式的纯文本日志整行作为 `action`，时间戳为 0
+    pub fn parse(line: &str) -> Self {
+        serde_json::from_str(line).unwrap_or_else(|_| Self {
+            timestamp: 0,
+            device_id: None,
+            action: line.to_string(),
+            details: None,
+        })
+    }
+}
+
+/// 按条件查询审计记录的默认实现最多扫描的日志字节数，超出后返回部分结果
+pub const MAX_AUDIT_QUERY_SCAN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 审计记录查询条件，未设置的条件不做限制
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// 起始时间（Unix 秒，含）
+    pub since: Option<u64>,
+    /// 结束时间（Unix 秒，不含）
+    pub until: Option<u64>,
+    pub device_id: Option<DeviceId>,
+    /// `action` 需包含的子串
+    pub action_contains: Option<String>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+            && self
+                .device_id
+                .is_none_or(|device_id| entry.device_id == Some(device_id))
+            && self
+                .action_contains
+                .as_deref()
+                .is_none_or(|pattern| entry.action.contains(pattern))
+    }
+}
+
+/// 审计记录查询结果，条目按从新到旧排列
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuditEntryPage {
+    pub entries: Vec<AuditEntry>,
+    /// 满足条件的记录总数
+    pub total: usize,
+    /// 扫描量达到上限而提前停止，`total` 只统计已扫描的记录
+    pub partial: bool,
+}
+
+/// 消息投递回执，按消息 ID 持久化，消息本身被删除后仍可查询是否已送达
+///
+/// 回执与其他记录一样受保留天数清理
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliveryReceipt {
+    pub message_id: Uuid,
+    pub recipient: DeviceId,
+    /// 发出消息的通道
+    pub channel: ChannelType,
+    /// 通道发送成功的时间（Unix 秒）
+    pub delivered_at: u64,
+    /// 收到接收方确认的时间（Unix 秒），未要求或尚未收到确认时为 None
+    #[serde(default)]
+    pub acknowledged_at: Option<u64>,
+}
+
+impl DeliveryReceipt {
+    /// 元数据回退存储使用的键
+    pub fn metadata_key(message_id: &Uuid) -> String {
+        format!("receipt_{}", message_id.simple())
+    }
+}
+
+/// 单个对端的并发发送限制
+///
+/// 同一对端的在途发送数达到上限后，新的发送排队等待空闲名额，超过等待时间则返回超时错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerSendConcurrency {
+    /// 每个对端的最大在途发送数
+    pub max_in_flight: usize,
+    /// 排队等待名额的最长时间（毫秒）
+    pub queue_timeout_ms: u64,
+}
+
+impl Default for PeerSendConcurrency {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 16,
+            queue_timeout_ms: 5000,
+        }
+    }
+}
+
+/// SDK 托管的异步任务状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    /// 任务名称（通道接收任务为 `receive_<ChannelType>`）
+    pub name: String,
+    /// 任务是否仍在运行
+    pub alive: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelState {
     pub available: bool,
@@ -219,6 +941,31 @@ pub enum MemberStatus {
     Busy,
 }
 
+/// 心跳携带的能力摘要上限：通道列表最多保留的项数
+pub const MAX_PRESENCE_HINT_CHANNELS: usize = 8;
+
+/// 随心跳发送的在线状态与能力摘要，省去单独的能力通告消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceHint {
+    pub battery_level: Option<u8>,
+    pub is_charging: bool,
+    /// 支持的通道，最多 `MAX_PRESENCE_HINT_CHANNELS` 项
+    pub supported_channels: Vec<ChannelType>,
+}
+
+impl PresenceHint {
+    pub fn from_capabilities(caps: &DeviceCapabilities) -> Self {
+        let mut supported_channels: Vec<_> = caps.supported_channels.iter().copied().collect();
+        supported_channels.sort_by_key(|channel| *channel as u8);
+        supported_channels.truncate(MAX_PRESENCE_HINT_CHANNELS);
+        Self {
+            battery_level: caps.battery_level,
+            is_charging: caps.is_charging,
+            supported_channels,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemberState {
     pub device_id: DeviceId,
@@ -249,8 +996,14 @@ pub enum MessagePayload {
     },
     Ack(Uuid),
 
-    // F6: 心跳包含发送时间戳用于计算 RTT
-    Ping(u64),
+    // 有序交付：接收端重排缓冲溢出时请求发送方重传该纪元内缺失的序号区间
+    RetransmitRequest {
+        epoch: u64,
+        sequences: Vec<std::ops::Range<u64>>,
+    },
+
+    // F6: 心跳包含发送时间戳用于计算 RTT，可选携带在线状态与能力摘要
+    Ping(u64, Option<PresenceHint>),
     Pong(u64),
 
     GroupInvite {
@@ -265,6 +1018,18 @@ pub enum MessagePayload {
         chunk_index: u32,
         data: Vec<u8>,
         sent_at: u64,
+        /// 前向纠错分组大小，为 None 时不含校验分片；序号不小于 `total_chunks` 的分片为校验分片
+        #[serde(default)]
+        fec_group_size: Option<u32>,
+        /// 重组后的数据所用的压缩算法，接收端重组完成后解压
+        #[serde(default)]
+        compression: Option<CompressionAlgorithm>,
+        /// 全部数据分片拼接后的 SHA-256，接收端重组后校验，为 None 时不校验
+        #[serde(default)]
+        checksum: Option<[u8; 32]>,
+        /// 本分片数据的 CRC32，校验失败的分片被丢弃且不确认，续传时重发
+        #[serde(default)]
+        chunk_crc: Option<u32>,
     },
 
     // F8: 媒体帧定义，用于音视频帧重组
@@ -275,6 +1040,12 @@ pub enum MessagePayload {
         timestamp: u64,
     },
 
+    // F8: 分片确认，接收方每收到一个数据分片回送一次，发送方据此记录可续传进度
+    StreamChunkAck {
+        stream_id: Uuid,
+        chunk_index: u32,
+    },
+
     // F8: 流控反馈
     StreamControl {
         stream_id: Uuid,
@@ -288,6 +1059,72 @@ pub enum MessagePayload {
         epoch: u64,
         update_path: Vec<u8>,
     },
+
+    // 群组密钥分发：新纪元的群组密钥以管理员与该成员的一对一会话密钥加密
+    GroupKeyDelivery {
+        group_id: GroupId,
+        epoch: u64,
+        sealed_secret: Vec<u8>,
+    },
+
+    // 申请加入群组，由群组管理员审批
+    JoinRequest {
+        group_id: GroupId,
+    },
+
+    /// 压缩后的 `Binary` 负载，接收端解压后以 `Binary` 交付应用
+    Compressed {
+        algorithm: CompressionAlgorithm,
+        data: Vec<u8>,
+    },
+}
+
+impl MessagePayload {
+    /// 是否为群组控制消息（邀请、ACK、密钥更新与分发、入群申请）
+    ///
+    /// 控制消息不使用群组密钥加密：新成员在处理邀请之前并不持有群组密钥，
+    /// 若邀请本身被群组密钥加密，新成员将无法完成加入。
+    pub fn is_group_control(&self) -> bool {
+        matches!(
+            self,
+            MessagePayload::GroupInvite { .. }
+                | MessagePayload::GroupAck { .. }
+                | MessagePayload::GroupKeyUpdate { .. }
+                | MessagePayload::GroupKeyDelivery { .. }
+                | MessagePayload::JoinRequest { .. }
+        )
+    }
+}
+
+/// 路由策略使用的流量类别，由负载类型与优先级推导
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrafficClass {
+    /// 流媒体分片、帧与流控制
+    Media,
+    /// 心跳、确认与群组控制消息
+    Control,
+    /// 最高优先级的应用消息
+    Urgent,
+    /// 其余应用消息
+    Data,
+}
+
+impl TrafficClass {
+    pub fn of(message: &Message) -> Self {
+        match &message.payload {
+            MessagePayload::StreamChunk { .. }
+            | MessagePayload::StreamFrame { .. }
+            | MessagePayload::StreamControl { .. } => TrafficClass::Media,
+            MessagePayload::Ping(..)
+            | MessagePayload::Pong(_)
+            | MessagePayload::Ack(_)
+            | MessagePayload::RetransmitRequest { .. }
+            | MessagePayload::StreamChunkAck { .. } => TrafficClass::Control,
+            payload if payload.is_group_control() => TrafficClass::Control,
+            _ if message.priority == MessagePriority::Critical => TrafficClass::Urgent,
+            _ => TrafficClass::Data,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -299,7 +1136,32 @@ pub struct Message {
     pub payload: MessagePayload,
     pub priority: MessagePriority,
     pub timestamp: u64,
+    /// 是否要求确认：点对点消息交付给接收方应用后回送 `MessagePayload::Ack`，群组消息回送 `GroupAck`
     pub require_ack: bool,
+    /// 是否要求有序交付：仅经有序通道发送，接收端按序号重排后再交付
+    #[serde(default)]
+    pub require_ordered: bool,
+    /// 有序交付序号（按发送方-接收方对递增，由 SDK 在发送时分配）
+    #[serde(default)]
+    pub sequence: Option<u64>,
+    /// 序号所属的发送方纪元：发送方每次启动取新的纪元，序号随之从 0 重新开始
+    #[serde(default)]
+    pub sequence_epoch: Option<u64>,
+    /// 请求-响应：请求消息携带的关联 ID，响应方据此回复
+    #[serde(default)]
+    pub correlation_id: Option<Uuid>,
+    /// 请求-响应：响应消息所回复的请求关联 ID
+    #[serde(default)]
+    pub in_reply_to: Option<Uuid>,
+    /// 应用主题：接收端将消息分发给该主题的订阅者，无订阅者时回退到普通接收队列
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// 过期时间（Unix 秒），过期后崩溃恢复不再重发；为 None 时永不过期
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// 发送方身份密钥（Ed25519）对 [`Message::signing_bytes`] 的签名，经中继转发后仍可校验
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
 }
 
 impl Message {
@@ -315,7 +1177,15 @@ impl Message {
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
-            require_ack: true,
+            require_ack: false,
+            require_ordered: false,
+            sequence: None,
+            sequence_epoch: None,
+            correlation_id: None,
+            in_reply_to: None,
+            topic: None,
+            expires_at: None,
+            signature: None,
         }
     }
 
@@ -332,6 +1202,75 @@ impl Message {
                 .unwrap_or_default()
                 .as_secs(),
             require_ack: true, // F4: 群组消息现在默认需要 ACK 处理
+            require_ordered: false,
+            sequence: None,
+            sequence_epoch: None,
+            correlation_id: None,
+            in_reply_to: None,
+            topic: None,
+            expires_at: None,
+            signature: None,
         }
     }
+
+    /// 消息在给定时刻（Unix 秒）是否已过期
+    pub fn is_expired(&self, now_secs: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| now_secs >= expires_at)
+    }
+
+    /// 消息的规范字节表示，用于签名、内容哈希与去重
+    ///
+    /// 按字段声明的固定顺序编码为紧凑 JSON，负载以其 [`digest`](crate::core::digest::digest) 代替：
+    /// 逻辑相等的消息总是得到相同的字节，与反序列化来源无关，且长度与负载大小无关
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        self.canonical_encoding(self.signature.as_deref())
+    }
+
+    /// 签名覆盖的字节：不含签名本身的规范字节
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        self.canonical_encoding(None)
+    }
+
+    fn canonical_encoding(&self, signature: Option<&[u8]>) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct CanonicalMessage<'a> {
+            id: &'a Uuid,
+            sender: &'a DeviceId,
+            recipient: &'a DeviceId,
+            group_id: &'a Option<GroupId>,
+            payload_sha256: String,
+            priority: &'a MessagePriority,
+            timestamp: u64,
+            require_ack: bool,
+            require_ordered: bool,
+            sequence: Option<u64>,
+            sequence_epoch: Option<u64>,
+            correlation_id: Option<Uuid>,
+            in_reply_to: Option<Uuid>,
+            topic: Option<&'a str>,
+            expires_at: Option<u64>,
+            signature: Option<&'a [u8]>,
+        }
+
+        serde_json::to_vec(&CanonicalMessage {
+            id: &self.id,
+            sender: &self.sender,
+            recipient: &self.recipient,
+            group_id: &self.group_id,
+            payload_sha256: hex::encode(crate::core::digest::digest(&self.payload)),
+            priority: &self.priority,
+            timestamp: self.timestamp,
+            require_ack: self.require_ack,
+            require_ordered: self.require_ordered,
+            sequence: self.sequence,
+            sequence_epoch: self.sequence_epoch,
+            correlation_id: self.correlation_id,
+            in_reply_to: self.in_reply_to,
+            topic: self.topic.as_deref(),
+            expires_at: self.expires_at,
+            signature,
+        })
+        .expect("Message header is always JSON serializable")
+    }
 }
@@ -0,0 +1,70 @@
+//! 加密上下文（AEAD 关联数据）
+//!
+//! 密文与其所属的发送方、接收方、群组与纪元绑定：关联数据参与认证但不加密，
+//! 解密时必须提供相同的上下文，否则认证失败。这样截获的合法密文无法被挪用到
+//! 其他接收方、其他群组或旧纪元中重放。
+
+use crate::core::types::{DeviceId, GroupId, Message};
+
+/// 关联数据格式版本前缀
+const CONTEXT_AAD_LABEL: &[u8] = b"xLink_AEAD_Context_v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionContext {
+    pub sender: DeviceId,
+    /// 群组消息的同一份密文分发给所有成员，因此不绑定接收方
+    pub recipient: Option<DeviceId>,
+    pub group_id: Option<GroupId>,
+    pub epoch: u64,
+}
+
+impl EncryptionContext {
+    /// 一对一消息上下文
+    pub fn direct(sender: DeviceId, recipient: DeviceId) -> Self {
+        Self {
+            sender,
+            recipient: Some(recipient),
+            group_id: None,
+            epoch: 0,
+        }
+    }
+
+    /// 群组消息上下文
+    pub fn group(sender: DeviceId, group_id: GroupId, epoch: u64) -> Self {
+        Self {
+            sender,
+            recipient: None,
+            group_id: Some(group_id),
+            epoch,
+        }
+    }
+
+    /// 由消息头构造上下文：带群组 ID 的消息按群组上下文处理
+    pub fn for_message(message: &Message, epoch: u64) -> Self {
+        match message.group_id {
+            Some(group_id) => Self::group(message.sender, group_id, epoch),
+            None => Self::direct(message.sender, message.recipient),
+        }
+    }
+
+    /// 编码为定长关联数据，缺省字段以全零占位
+    pub fn associated_data(&self) -> Vec<u8> {
+        let mut aad = Vec::with_capacity(CONTEXT_AAD_LABEL.len() + 16 * 3 + 8);
+        aad.extend_from_slice(CONTEXT_AAD_LABEL);
+        aad.extend_from_slice(self.sender.0.as_bytes());
+        aad.extend_from_slice(
+            self.recipient
+                .map(|id| *id.0.as_bytes())
+                .unwrap_or([0u8; 16])
+                .as_slice(),
+        );
+        aad.extend_from_slice(
+            self.group_id
+                .map(|id| *id.0.as_bytes())
+                .unwrap_or([0u8; 16])
+                .as_slice(),
+        );
+        aad.extend_from_slice(&self.epoch.to_be_bytes());
+        aad
+    }
+}
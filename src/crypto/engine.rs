@@ -1,6 +1,8 @@
 use crate::core::error::{Result, XLinkError};
-use crate::core::types::DeviceId;
-use chacha20poly1305::aead::{Aead, KeyInit};
+use crate::core::types::{DeviceId, Message};
+use crate::crypto::context::EncryptionContext;
+use crate::crypto::mlkem::{self, MlKemKeyPair};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
 use chacha20poly1305::{ChaCha20Poly1305, Nonce};
 use dashmap::DashMap;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
@@ -16,6 +18,23 @@ use zeroize::Zeroize;
 
 type Key = [u8; 32];
 
+/// 混合会话密文的格式版本，位于密文首字节；经典会话沿用无版本的 nonce || 密文格式
+pub const HYBRID_WIRE_VERSION: u8 = 2;
+
+/// 由 x25519 与 ML-KEM 共享密钥派生混合会话的初始共享密钥
+fn hybrid_shared_secret(classic: &Key, post_quantum: &Key) -> Result<Key> {
+    let mut ikm = [0u8; 64];
+    ikm[..32].copy_from_slice(classic);
+    ikm[32..].copy_from_slice(post_quantum);
+    let hk = Hkdf::<Sha256>::new(None, &ikm);
+    ikm.zeroize();
+
+    let mut shared = [0u8; 32];
+    hk.expand(b"xlink-hybrid-v1", &mut shared)
+        .map_err(|e| XLinkError::key_derivation_failed("HKDF-HYBRID", &e.to_string(), file!()))?;
+    Ok(shared)
+}
+
 /// 检查密钥是否为弱密钥或全零密钥
 fn is_weak_key(key: &[u8]) -> bool {
     // 检查是否全为零
@@ -129,6 +148,9 @@ struct SessionState {
     created_at: u64,
     /// 会话过期时间（秒），默认 24 小时
     expires_at: u64,
+    /// 会话密钥同时由 ML-KEM 派生，密文带版本字节
+    #[serde(default)]
+    hybrid: bool,
 }
 
 // 实现 Drop trait 以确保会话结束时安全清理密钥
@@ -144,7 +166,11 @@ impl Drop for SessionState {
 impl SessionState {
     const SESSION_TTL_SECONDS: u64 = 24 * 60 * 60; // 24小时
 
-    fn new(shared_secret: Key, peer_verifying_key: Option<VerifyingKey>) -> Result<Self> {
+    fn new(
+        shared_secret: Key,
+        peer_verifying_key: Option<VerifyingKey>,
+        hybrid: bool,
+    ) -> Result<Self> {
         // 验证共享密钥
         if is_weak_key(&shared_secret) {
             return Err(XLinkError::key_derivation_failed(
@@ -173,6 +199,7 @@ impl SessionState {
             peer_verifying_key,
             created_at: now,
             expires_at: now + Self::SESSION_TTL_SECONDS,
+            hybrid,
         })
     }
 
@@ -227,8 +254,12 @@ mod verifying_key_serde {
 #[derive(Serialize, Deserialize)]
 pub struct CryptoState {
     pub static_secret: [u8; 32],
+    /// 身份签名私钥（Ed25519），迁移后对端固定的身份公钥保持不变
     pub signing_key: [u8; 32],
     pub sessions: Vec<(DeviceId, Vec<u8>)>,
+    /// 混合模式下 ML-KEM 密钥对的种子，经典模式为 None
+    #[serde(default)]
+    pub kem_seed: Option<Vec<u8>>,
 }
 
 pub struct CryptoEngine {
@@ -238,6 +269,8 @@ pub struct CryptoEngine {
     /// 使用 Mutex 替代 RwLock 避免嵌套锁死锁风险
     /// 访问模式：先通过 DashMap 获取条目，再获取 Mutex 锁
     sessions: Arc<DashMap<DeviceId, Mutex<SessionState>>>,
+    /// 混合模式的 ML-KEM 密钥对，经典模式为 None
+    kem: Option<MlKemKeyPair>,
 }
 
 impl Default for CryptoEngine {
@@ -257,9 +290,30 @@ impl CryptoEngine {
             public_key: public,
             signing_key,
             sessions: Arc::new(DashMap::new()),
+            kem: None,
         }
     }
 
+    /// 创建混合模式引擎：会话密钥同时由 x25519 与 ML-KEM-768 派生
+    ///
+    /// 对端不支持 ML-KEM 时会话降级为经典 x25519，见 [`Self::initiate_session`]
+    pub fn new_hybrid() -> Self {
+        Self {
+            kem: Some(MlKemKeyPair::generate()),
+            ..Self::new()
+        }
+    }
+
+    /// 是否为混合模式
+    pub fn is_hybrid(&self) -> bool {
+        self.kem.is_some()
+    }
+
+    /// 本机的 ML-KEM 公钥，随公钥一同广播即表示支持混合会话；经典模式为 None
+    pub fn kem_public_key(&self) -> Option<Vec<u8>> {
+        self.kem.as_ref().map(|kem| kem.public_key().to_vec())
+    }
+
     pub fn public_key(&self) -> PublicKey {
         self.public_key
     }
@@ -282,6 +336,7 @@ impl CryptoEngine {
             static_secret: self.static_secret.to_bytes(),
             signing_key: self.signing_key.to_bytes(),
             sessions: session_data,
+            kem_seed: self.kem.as_ref().map(|kem| kem.seed().to_vec()),
         })
     }
 
@@ -289,6 +344,16 @@ impl CryptoEngine {
         let static_secret = StaticSecret::from(state.static_secret);
         let public_key = PublicKey::from(&static_secret);
         let signing_key = SigningKey::from_bytes(&state.signing_key);
+        let kem = match state.kem_seed {
+            Some(mut seed) => {
+                let bytes: [u8; mlkem::SEED_BYTES] = seed.as_slice().try_into().map_err(|_| {
+                    XLinkError::invalid_input("kem_seed", "Invalid ML-KEM seed length", file!())
+                })?;
+                seed.zeroize();
+                Some(MlKemKeyPair::from_seed(&bytes))
+            }
+            None => None,
+        };
 
         let sessions = Arc::new(DashMap::new());
         for (device_id, serialized) in state.sessions {
@@ -303,6 +368,7 @@ impl CryptoEngine {
             public_key,
             signing_key,
             sessions,
+            kem,
         })
     }
 
@@ -313,9 +379,21 @@ impl CryptoEngine {
         }
     }
 
+    /// 移除与指定设备的会话，之后需重新握手才能加解密，返回会话此前是否存在
+    pub fn remove_session(&self, peer_id: &DeviceId) -> bool {
+        self.sessions.remove(peer_id).is_some()
+    }
+
+    /// 与指定设备的会话是否经过签名密钥认证，无会话时返回 None
+    pub fn is_session_authenticated(&self, peer_id: &DeviceId) -> Option<bool> {
+        self.sessions
+            .get(peer_id)
+            .map(|session| session.lock().peer_verifying_key.is_some())
+    }
+
     pub fn establish_session(&self, peer_id: DeviceId, peer_public: PublicKey) -> Result<()> {
         let shared_secret = self.static_secret.diffie_hellman(&peer_public);
-        let session = SessionState::new(*shared_secret.as_bytes(), None)?;
+        let session = SessionState::new(*shared_secret.as_bytes(), None, false)?;
         // 使用 Mutex 替代 RwLock
         self.sessions.insert(peer_id, Mutex::new(session));
         Ok(())
@@ -328,16 +406,88 @@ impl CryptoEngine {
         peer_verifying_key: VerifyingKey,
     ) -> Result<()> {
         let shared_secret = self.static_secret.diffie_hellman(&peer_public);
-        let session = SessionState::new(*shared_secret.as_bytes(), Some(peer_verifying_key))?;
+        let session =
+            SessionState::new(*shared_secret.as_bytes(), Some(peer_verifying_key), false)?;
         // 使用 Mutex 替代 RwLock
         self.sessions.insert(peer_id, Mutex::new(session));
         Ok(())
     }
 
+    /// 发起会话并按双方能力协商密钥交换方式
+    ///
+    /// 本机为混合模式且对端提供了 ML-KEM 公钥时建立混合会话，返回需发给对端的封装密文，
+    /// 对端以 [`Self::accept_hybrid_session`] 完成握手；否则降级为经典 x25519 会话并返回 None
+    pub fn initiate_session(
+        &self,
+        peer_id: DeviceId,
+        peer_public: PublicKey,
+        peer_kem_public: Option<&[u8]>,
+    ) -> Result<Option<Vec<u8>>> {
+        let Some(peer_kem_public) = peer_kem_public.filter(|_| self.is_hybrid()) else {
+            self.establish_session(peer_id, peer_public)?;
+            return Ok(None);
+        };
+
+        let (mut post_quantum, ciphertext) = mlkem::encapsulate(peer_kem_public)?;
+        let classic = self.static_secret.diffie_hellman(&peer_public);
+        let shared = hybrid_shared_secret(classic.as_bytes(), &post_quantum);
+        post_quantum.zeroize();
+        let session = SessionState::new(shared?, None, true)?;
+        self.sessions.insert(peer_id, Mutex::new(session));
+        Ok(Some(ciphertext))
+    }
+
+    /// 用发起方发来的 ML-KEM 封装密文建立混合会话
+    ///
+    /// 经典模式的引擎无法解封装，返回 `protocol_version_mismatch`
+    pub fn accept_hybrid_session(
+        &self,
+        peer_id: DeviceId,
+        peer_public: PublicKey,
+        kem_ciphertext: &[u8],
+    ) -> Result<()> {
+        let kem = self.kem.as_ref().ok_or_else(|| {
+            XLinkError::protocol_version_mismatch("x25519", "x25519+ml-kem-768", file!())
+        })?;
+        let mut post_quantum = kem.decapsulate(kem_ciphertext)?;
+        let classic = self.static_secret.diffie_hellman(&peer_public);
+        let shared = hybrid_shared_secret(classic.as_bytes(), &post_quantum);
+        post_quantum.zeroize();
+        let session = SessionState::new(shared?, None, true)?;
+        self.sessions.insert(peer_id, Mutex::new(session));
+        Ok(())
+    }
+
+    /// 与指定设备的会话是否为混合会话，无会话时返回 None
+    pub fn is_session_hybrid(&self, peer_id: &DeviceId) -> Option<bool> {
+        self.sessions
+            .get(peer_id)
+            .map(|session| session.lock().hybrid)
+    }
+
+    /// 以本机静态私钥应答发现校验挑战
+    pub fn respond_to_discovery_challenge(
+        &self,
+        device_id: DeviceId,
+        challenge: &crate::discovery::verification::DiscoveryChallenge,
+    ) -> crate::discovery::verification::DiscoveryChallengeResponse {
+        crate::discovery::verification::respond_to_challenge(
+            &self.static_secret,
+            device_id,
+            challenge,
+        )
+    }
+
+    /// 用本机身份密钥（Ed25519）签名，返回 64 字节签名
     pub fn sign(&self, data: &[u8]) -> Vec<u8> {
         self.signing_key.sign(data).to_bytes().to_vec()
     }
 
+    /// 对消息签名，签名写入 `message.signature`；签名后修改消息的任何字段都会导致校验失败
+    pub fn sign_message(&self, message: &mut Message) {
+        message.signature = Some(self.sign(&message.signing_bytes()));
+    }
+
     pub fn verify(&self, peer_id: &DeviceId, data: &[u8], signature_bytes: &[u8]) -> Result<()> {
         let session_guard = self
             .sessions
@@ -362,16 +512,31 @@ impl CryptoEngine {
             XLinkError::invalid_input("verifying_key", "No verifying key for peer", file!())
         })?;
 
+        Self::verify_with_key(&verifying_key, data, signature_bytes)
+    }
+
+    /// 用发送方的身份公钥校验签名，不依赖会话，适用于经中继转发的消息
+    pub fn verify_with_key(
+        sender_key: &VerifyingKey,
+        data: &[u8],
+        signature_bytes: &[u8],
+    ) -> Result<()> {
         let signature = Signature::from_slice(signature_bytes).map_err(|e| {
             XLinkError::signature_verification_failed("Ed25519", &e.to_string(), file!())
         })?;
 
-        verifying_key.verify(data, &signature).map_err(|e| {
+        sender_key.verify(data, &signature).map_err(|e| {
             XLinkError::signature_verification_failed("Ed25519", &e.to_string(), file!())
         })
     }
 
-    pub fn encrypt(&self, peer_id: &DeviceId, plaintext: &[u8]) -> Result<Vec<u8>> {
+    /// 加密发往 `peer_id` 的数据，密文与 `context` 绑定
+    pub fn encrypt(
+        &self,
+        peer_id: &DeviceId,
+        plaintext: &[u8],
+        context: &EncryptionContext,
+    ) -> Result<Vec<u8>> {
         let session_guard = self
             .sessions
             .get(peer_id)
@@ -394,27 +559,44 @@ impl CryptoEngine {
         let (next_ck, msg_key) = secure_kdf_ck(&session.send_chain_key)?;
         session.send_chain_key = next_ck;
         session.send_ratchet_counter += 1;
+        let version = session.hybrid.then_some(HYBRID_WIRE_VERSION);
 
         let cipher = ChaCha20Poly1305::new((&msg_key).into());
         let mut nonce_bytes = [0u8; 12];
         OsRng.fill_bytes(&mut nonce_bytes);
         let nonce = Nonce::from_slice(&nonce_bytes);
 
-        let ciphertext = cipher.encrypt(nonce, plaintext).map_err(|e| {
-            XLinkError::encryption_failed("ChaCha20Poly1305", &e.to_string(), file!())
-        })?;
+        let aad = versioned_aad(context, version);
+        let ciphertext = cipher
+            .encrypt(
+                nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| {
+                XLinkError::encryption_failed("ChaCha20Poly1305", &e.to_string(), file!())
+            })?;
 
         // 安全清理消息密钥
         let mut msg_key_copy = msg_key;
         msg_key_copy.zeroize();
 
-        let mut result = Vec::with_capacity(12 + ciphertext.len());
+        let mut result = Vec::with_capacity(13 + ciphertext.len());
+        result.extend(version);
         result.extend_from_slice(&nonce_bytes);
         result.extend_from_slice(&ciphertext);
         Ok(result)
     }
 
-    pub fn decrypt(&self, peer_id: &DeviceId, ciphertext_data: &[u8]) -> Result<Vec<u8>> {
+    /// 解密来自 `peer_id` 的数据，`context` 必须与加密时一致
+    pub fn decrypt(
+        &self,
+        peer_id: &DeviceId,
+        ciphertext_data: &[u8],
+        context: &EncryptionContext,
+    ) -> Result<Vec<u8>> {
         if ciphertext_data.len() < 12 {
             return Err(XLinkError::invalid_ciphertext(
                 "Ciphertext too short (minimum 12 bytes for nonce)".to_string(),
@@ -441,6 +623,31 @@ impl CryptoEngine {
             ));
         }
 
+        // 混合会话的密文以版本字节开头，版本不符时在推进链密钥之前拒绝
+        let (version, ciphertext_data) = if session.hybrid {
+            match ciphertext_data.split_first() {
+                Some((&HYBRID_WIRE_VERSION, rest)) if rest.len() >= 12 => {
+                    (Some(HYBRID_WIRE_VERSION), rest)
+                }
+                Some((&version, rest)) if rest.len() >= 12 => {
+                    return Err(XLinkError::protocol_version_mismatch(
+                        HYBRID_WIRE_VERSION.to_string(),
+                        version.to_string(),
+                        file!(),
+                    ));
+                }
+                _ => {
+                    return Err(XLinkError::invalid_ciphertext(
+                        "Hybrid ciphertext too short (minimum 13 bytes for version and nonce)"
+                            .to_string(),
+                        file!(),
+                    ));
+                }
+            }
+        } else {
+            (None, ciphertext_data)
+        };
+
         let (next_ck, msg_key) = secure_kdf_ck(&session.recv_chain_key)?;
         session.recv_chain_key = next_ck;
 
@@ -449,9 +656,18 @@ impl CryptoEngine {
 
         let cipher = ChaCha20Poly1305::new((&msg_key).into());
 
-        let plaintext = cipher.decrypt(nonce, ciphertext).map_err(|e| {
-            XLinkError::encryption_failed("ChaCha20Poly1305", &e.to_string(), file!())
-        })?;
+        let aad = versioned_aad(context, version);
+        let plaintext = cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| {
+                XLinkError::encryption_failed("ChaCha20Poly1305", &e.to_string(), file!())
+            })?;
 
         // 安全清理消息密钥
         let mut msg_key_copy = msg_key;
@@ -461,4 +677,11 @@ impl CryptoEngine {
     }
 }
 
+/// 附加认证数据：混合会话额外绑定版本字节，防止被剥离后按其他格式解析
+fn versioned_aad(context: &EncryptionContext, version: Option<u8>) -> Vec<u8> {
+    let mut aad = context.associated_data();
+    aad.extend(version);
+    aad
+}
+
 pub type PublicKeyAlias = PublicKey;
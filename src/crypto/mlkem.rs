@@ -0,0 +1,144 @@
+//! ML-KEM-768 密钥封装（FIPS 203）
+//!
+//! 供 [`crate::crypto::engine::CryptoEngine`] 的混合模式使用：会话密钥同时依赖 x25519
+//! 共享密钥与 ML-KEM 封装出的共享密钥，任一方被攻破都不足以恢复会话密钥。
+//! 算法本身由 RustCrypto 的 `ml-kem` 实现，本模块只负责密钥的保存、导入与长度校验。
+
+use crate::core::error::{Result, XLinkError};
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, EncapsulateDeterministic, EncodedSizeUser, KemCore, MlKem768, B32};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+
+const Q: u16 = 3329;
+const POLY_BYTES: usize = 384;
+const K: usize = 3;
+
+/// 封装公钥长度
+pub const PUBLIC_KEY_BYTES: usize = POLY_BYTES * K + 32;
+/// 解封装私钥长度
+pub const SECRET_KEY_BYTES: usize = 2 * POLY_BYTES * K + 96;
+/// 封装密文长度
+pub const CIPHERTEXT_BYTES: usize = 1088;
+/// 生成密钥对所用种子 (d || z) 的长度
+pub const SEED_BYTES: usize = 64;
+/// 共享密钥长度
+pub const SHARED_SECRET_BYTES: usize = 32;
+
+/// ML-KEM-768 密钥对
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct MlKemKeyPair {
+    seed: [u8; SEED_BYTES],
+    public_key: Vec<u8>,
+    secret_key: Vec<u8>,
+}
+
+impl MlKemKeyPair {
+    /// 随机生成密钥对
+    pub fn generate() -> Self {
+        let mut seed = [0u8; SEED_BYTES];
+        OsRng.fill_bytes(&mut seed);
+        let pair = Self::from_seed(&seed);
+        seed.zeroize();
+        pair
+    }
+
+    /// 由种子 (d || z) 确定性地生成密钥对，用于状态导入
+    pub fn from_seed(seed: &[u8; SEED_BYTES]) -> Self {
+        let d = B32::try_from(&seed[..32]).unwrap_or_default();
+        let z = B32::try_from(&seed[32..]).unwrap_or_default();
+        let (dk, ek) = MlKem768::generate_deterministic(&d, &z);
+        Self {
+            seed: *seed,
+            public_key: ek.as_bytes().to_vec(),
+            secret_key: dk.as_bytes().to_vec(),
+        }
+    }
+
+    /// 生成密钥对的种子，导出后可由 [`Self::from_seed`] 恢复
+    pub fn seed(&self) -> &[u8; SEED_BYTES] {
+        &self.seed
+    }
+
+    /// 封装公钥
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// 解封装，返回共享密钥
+    ///
+    /// 密文被篡改时按标准返回由私钥派生的伪随机密钥（隐式拒绝），后续 AEAD 解密会失败
+    pub fn decapsulate(&self, ciphertext: &[u8]) -> Result<[u8; SHARED_SECRET_BYTES]> {
+        let ciphertext = Ciphertext::<MlKem768>::try_from(ciphertext).map_err(|_| {
+            XLinkError::invalid_ciphertext(
+                format!(
+                    "ML-KEM ciphertext must be {} bytes, got {}",
+                    CIPHERTEXT_BYTES,
+                    ciphertext.len()
+                ),
+                file!(),
+            )
+        })?;
+        let encoded = ml_kem::Encoded::<DecapsulationKey>::try_from(self.secret_key.as_slice())
+            .map_err(|_| {
+                XLinkError::invalid_state("mlkem_decapsulate", "malformed secret key", file!())
+            })?;
+        let shared = DecapsulationKey::from_bytes(&encoded)
+            .decapsulate(&ciphertext)
+            .map_err(|_| {
+                XLinkError::invalid_ciphertext("ML-KEM decapsulation failed".to_string(), file!())
+            })?;
+        Ok(shared.into())
+    }
+}
+
+/// 向 `public_key` 封装一个随机共享密钥，返回 (共享密钥, 密文)
+pub fn encapsulate(public_key: &[u8]) -> Result<([u8; SHARED_SECRET_BYTES], Vec<u8>)> {
+    let (ciphertext, shared) = encapsulation_key(public_key)?
+        .encapsulate(&mut OsRng)
+        .map_err(|_| encapsulation_failed())?;
+    Ok((shared.into(), ciphertext.to_vec()))
+}
+
+/// 以给定的随机数 `m` 封装，结果完全由输入决定，仅用于已知答案测试
+pub fn encapsulate_deterministic(
+    public_key: &[u8],
+    m: &[u8; 32],
+) -> Result<([u8; SHARED_SECRET_BYTES], Vec<u8>)> {
+    let (ciphertext, shared) = encapsulation_key(public_key)?
+        .encapsulate_deterministic(&B32::from(*m))
+        .map_err(|_| encapsulation_failed())?;
+    Ok((shared.into(), ciphertext.to_vec()))
+}
+
+fn encapsulation_failed() -> XLinkError {
+    XLinkError::invalid_input("kem_public_key", "ML-KEM encapsulation failed", file!())
+}
+
+/// 解析封装公钥，须为合法编码：长度正确且每个系数都小于 q（FIPS 203 的模数检查）
+fn encapsulation_key(ek: &[u8]) -> Result<EncapsulationKey> {
+    let encoded = ml_kem::Encoded::<EncapsulationKey>::try_from(ek).map_err(|_| {
+        XLinkError::invalid_input(
+            "kem_public_key",
+            &format!("expected {} bytes, got {}", PUBLIC_KEY_BYTES, ek.len()),
+            file!(),
+        )
+    })?;
+    let in_range = ek[..POLY_BYTES * K].chunks_exact(3).all(|bytes| {
+        let low = u16::from(bytes[0]) | (u16::from(bytes[1] & 0x0F) << 8);
+        let high = u16::from(bytes[1] >> 4) | (u16::from(bytes[2]) << 4);
+        low < Q && high < Q
+    });
+    if !in_range {
+        return Err(XLinkError::invalid_input(
+            "kem_public_key",
+            "coefficient out of range",
+            file!(),
+        ));
+    }
+    Ok(EncapsulationKey::from_bytes(&encoded))
+}
@@ -1,2 +1,5 @@
+pub mod context;
 pub mod engine;
+pub mod mlkem;
+pub mod state_seal;
 pub mod treekem;
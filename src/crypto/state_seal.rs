@@ -0,0 +1,123 @@
+//! 加密状态的静态加密
+//!
+//! 配置状态密钥后，`XLink::export_sdk_state` 与低电量关闭时持久化的加密状态都会以
+//! XChaCha20-Poly1305 封装，磁盘或迁移文件中不再出现明文私钥。
+//!
+//! 密钥来源由 `StateKeyProvider` 决定：
+//! - 移动端应实现该 trait，从系统密钥库（Android Keystore、iOS Keychain 等）取出或派生
+//!   一个 32 字节密钥，密钥本身不离开密钥库管理的范围；
+//! - 桌面端或测试可使用 `StaticStateKey`，由应用自行保管配置的密钥。
+//!
+//! 封装格式：`MAGIC (5 字节) || nonce (24 字节) || 密文`，MAGIC 同时作为关联数据。
+//! `FileStorage::new_encrypted` 复用同一封装格式加密落盘的消息记录，并把记录类型与消息 ID
+//! 追加到关联数据中，密文无法在记录之间互换。
+
+use crate::core::error::{Result, XLinkError};
+use chacha20poly1305::{
+    aead::{Aead, OsRng, Payload},
+    AeadCore, KeyInit, XChaCha20Poly1305, XNonce,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const MAGIC: &[u8; 5] = b"XLSS\x01";
+const NONCE_LEN: usize = 24;
+
+/// 提供加密状态的封装密钥
+pub trait StateKeyProvider: Send + Sync {
+    /// 返回 32 字节封装密钥；密钥库不可用时返回错误，此时不会写出明文状态
+    fn state_key(&self) -> Result<[u8; 32]>;
+}
+
+/// 由应用配置的固定封装密钥
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct StaticStateKey([u8; 32]);
+
+impl StaticStateKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+}
+
+impl StateKeyProvider for StaticStateKey {
+    fn state_key(&self) -> Result<[u8; 32]> {
+        Ok(self.0)
+    }
+}
+
+/// 数据是否为封装后的状态
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 用提供者的密钥封装状态
+pub fn seal(provider: &dyn StateKeyProvider, plaintext: &[u8]) -> Result<Vec<u8>> {
+    seal_with_context(provider, plaintext, &[])
+}
+
+/// 封装数据并把 `context` 绑定进关联数据，解封时须提供相同的 `context`
+pub fn seal_with_context(
+    provider: &dyn StateKeyProvider,
+    plaintext: &[u8],
+    context: &[u8],
+) -> Result<Vec<u8>> {
+    let aad = associated_data(context);
+    let mut key = provider.state_key()?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    key.zeroize();
+
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|e| XLinkError::encryption_failed("XChaCha20Poly1305", &e.to_string(), file!()))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// 解封状态，密钥不匹配或数据被篡改时返回错误
+pub fn open(provider: &dyn StateKeyProvider, sealed: &[u8]) -> Result<Vec<u8>> {
+    open_with_context(provider, sealed, &[])
+}
+
+/// 解封以 [`seal_with_context`] 封装的数据，`context` 不一致时返回错误
+pub fn open_with_context(
+    provider: &dyn StateKeyProvider,
+    sealed: &[u8],
+    context: &[u8],
+) -> Result<Vec<u8>> {
+    if !is_sealed(sealed) || sealed.len() < MAGIC.len() + NONCE_LEN {
+        return Err(XLinkError::invalid_ciphertext(
+            "Sealed state header missing or truncated".to_string(),
+            file!(),
+        ));
+    }
+    let (nonce, ciphertext) = sealed[MAGIC.len()..].split_at(NONCE_LEN);
+
+    let mut key = provider.state_key()?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    key.zeroize();
+
+    cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &associated_data(context),
+            },
+        )
+        .map_err(|e| XLinkError::invalid_ciphertext(e.to_string(), file!()))
+}
+
+/// 关联数据：MAGIC || context，空 context 与未绑定上下文的封装格式一致
+fn associated_data(context: &[u8]) -> Vec<u8> {
+    [MAGIC.as_slice(), context].concat()
+}
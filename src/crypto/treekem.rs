@@ -1,5 +1,5 @@
 use chacha20poly1305::{
-    aead::{Aead, OsRng},
+    aead::{Aead, OsRng, Payload},
     KeyInit, XChaCha20Poly1305,
 };
 use dashmap::DashMap;
@@ -14,6 +14,7 @@ use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
 use crate::core::error::XLinkError;
 use crate::core::types::{DeviceId, GroupId, MessagePayload};
+use crate::crypto::context::EncryptionContext;
 
 type Key = [u8; 32];
 
@@ -62,6 +63,8 @@ pub struct TreeKemEngine {
     pub local_device_id: DeviceId,
     pub local_private_key: StaticSecret,
     pub signing_key: SigningKey,
+    // 每个群组当前纪元的消息密钥缓存：群组 ID -> (纪元, 已初始化的 AEAD 实例)
+    message_keys: DashMap<GroupId, (u64, XChaCha20Poly1305)>,
 }
 
 impl TreeKemEngine {
@@ -75,6 +78,7 @@ impl TreeKemEngine {
             local_device_id,
             local_private_key,
             signing_key,
+            message_keys: DashMap::new(),
         }
     }
 
@@ -96,9 +100,45 @@ impl TreeKemEngine {
             })
     }
 
+    /// 列出已注册的设备公钥
+    pub fn registered_device_keys(&self) -> Vec<(DeviceId, Vec<u8>)> {
+        self.device_public_keys
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// 撤销设备公钥，返回该公钥此前是否存在
+    pub fn revoke_device_key(&self, device_id: DeviceId) -> bool {
+        self.device_public_keys.remove(&device_id).is_some()
+    }
+
     pub fn clear_keys(&self) {
         self.groups.clear();
         self.device_public_keys.clear();
+        self.message_keys.clear();
+    }
+
+    /// 丢弃群组缓存的消息密钥，下次加解密时按当前群组密钥重新初始化
+    ///
+    /// 引擎内部的换钥操作会自动调用；直接修改 `groups` 中的群组密钥后需手动调用
+    pub fn invalidate_message_key(&self, group_id: GroupId) {
+        self.message_keys.remove(&group_id);
+    }
+
+    /// 取群组当前纪元的消息密钥，同一纪元内复用缓存，纪元变化时重新初始化
+    fn message_cipher(&self, group: &TreeKemGroup) -> Result<XChaCha20Poly1305, XLinkError> {
+        if let Some(cached) = self.message_keys.get(&group.group_id) {
+            if cached.0 == group.epoch {
+                return Ok(cached.1.clone());
+            }
+        }
+        let cipher = XChaCha20Poly1305::new_from_slice(&group.group_secret).map_err(|e| {
+            XLinkError::encryption_failed("XChaCha20Poly1305 init", &e.to_string(), file!())
+        })?;
+        self.message_keys
+            .insert(group.group_id, (group.epoch, cipher.clone()));
+        Ok(cipher)
     }
 
     pub fn create_group(
@@ -141,12 +181,15 @@ impl TreeKemEngine {
         };
 
         self.groups.insert(group_id, group.clone());
+        self.invalidate_message_key(group_id);
         Ok(group)
     }
 
+    /// 以群组密钥加密消息，密文与发送方、群组及当前纪元绑定
     pub fn encrypt_group_message(
         &self,
         group_id: GroupId,
+        sender: DeviceId,
         payload: &MessagePayload,
     ) -> Result<MessagePayload, XLinkError> {
         let group = self
@@ -156,26 +199,36 @@ impl TreeKemEngine {
 
         let plaintext = serde_json::to_vec(payload).map_err(Into::<XLinkError>::into)?;
 
+        // 密钥在纪元内复用，每条消息仍使用独立的 192 位随机 nonce
         let mut nonce_bytes = [0u8; 24];
         OsRng.fill_bytes(&mut nonce_bytes);
         let nonce = chacha20poly1305::XNonce::from(nonce_bytes);
 
-        let cipher = XChaCha20Poly1305::new_from_slice(&group.group_secret).map_err(|e| {
-            XLinkError::encryption_failed("XChaCha20Poly1305 init", &e.to_string(), file!())
-        })?;
+        let cipher = self.message_cipher(&group)?;
 
-        let ciphertext = cipher.encrypt(&nonce, &*plaintext).map_err(|e| {
-            XLinkError::encryption_failed("XChaCha20Poly1305 encrypt", &e.to_string(), file!())
-        })?;
+        let aad = EncryptionContext::group(sender, group_id, group.epoch).associated_data();
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| {
+                XLinkError::encryption_failed("XChaCha20Poly1305 encrypt", &e.to_string(), file!())
+            })?;
 
         let mut result = nonce_bytes.to_vec();
         result.extend_from_slice(&ciphertext);
         Ok(MessagePayload::Binary(result))
     }
 
+    /// 解密群组消息，`sender` 为消息头中声明的发送方
     pub fn decrypt_group_message(
         &self,
         group_id: GroupId,
+        sender: DeviceId,
         payload: &MessagePayload,
     ) -> Result<MessagePayload, XLinkError> {
         match payload {
@@ -196,23 +249,25 @@ impl TreeKemEngine {
                 nonce_bytes.copy_from_slice(&ciphertext[0..24]);
                 let nonce = chacha20poly1305::XNonce::from(nonce_bytes);
 
-                let cipher =
-                    XChaCha20Poly1305::new_from_slice(&group.group_secret).map_err(|e| {
+                let cipher = self.message_cipher(&group)?;
+
+                let aad = EncryptionContext::group(sender, group_id, group.epoch).associated_data();
+                let decrypted = cipher
+                    .decrypt(
+                        &nonce,
+                        Payload {
+                            msg: &ciphertext[24..],
+                            aad: &aad,
+                        },
+                    )
+                    .map_err(|e| {
                         XLinkError::encryption_failed(
-                            "XChaCha20Poly1305 init",
+                            "XChaCha20Poly1305 decrypt",
                             &e.to_string(),
                             file!(),
                         )
                     })?;
 
-                let decrypted = cipher.decrypt(&nonce, &ciphertext[24..]).map_err(|e| {
-                    XLinkError::encryption_failed(
-                        "XChaCha20Poly1305 decrypt",
-                        &e.to_string(),
-                        file!(),
-                    )
-                })?;
-
                 let payload: MessagePayload =
                     serde_json::from_slice(&decrypted).map_err(Into::<XLinkError>::into)?;
 
@@ -238,14 +293,45 @@ impl TreeKemEngine {
         let info = b"xLink_TreeKEM_KeyRotation_v1".to_vec();
         let hkdf = Hkdf::<Sha256>::new(Some(&group.group_secret), &new_secret);
         let mut okm = [0u8; 64];
-        hkdf.expand(&info, &mut okm).expect("HKDF key expansion failed");
+        hkdf.expand(&info, &mut okm)
+            .expect("HKDF key expansion failed");
 
         group.group_secret.copy_from_slice(&okm[0..32]);
         group.epoch += 1;
+        self.invalidate_message_key(group_id);
 
         Ok(())
     }
 
+    /// 群组当前的纪元与密钥，供管理员分发给成员
+    pub fn group_secret(&self, group_id: GroupId) -> Result<(u64, Key), XLinkError> {
+        self.groups
+            .get(&group_id)
+            .map(|group| (group.epoch, group.group_secret))
+            .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))
+    }
+
+    /// 安装管理员分发的群组密钥，返回是否生效
+    ///
+    /// 本地没有该群组时以此密钥创建；早于本地纪元的密钥被忽略，防止回滚到旧密钥
+    pub fn install_group_secret(&self, group_id: GroupId, epoch: u64, secret: Key) -> bool {
+        let mut group = self.groups.entry(group_id).or_insert_with(|| TreeKemGroup {
+            group_id,
+            tree: HashMap::new(),
+            epoch,
+            group_secret: secret,
+            member_devices: HashMap::new(),
+        });
+        if epoch < group.epoch {
+            return false;
+        }
+        group.epoch = epoch;
+        group.group_secret = secret;
+        drop(group);
+        self.invalidate_message_key(group_id);
+        true
+    }
+
     pub fn update_group_key(
         &self,
         group_id: GroupId,
@@ -273,6 +359,7 @@ impl TreeKemEngine {
         }
 
         group.epoch = update_path.epoch;
+        self.invalidate_message_key(group_id);
         Ok(())
     }
 
@@ -306,7 +393,8 @@ impl TreeKemEngine {
         let info = b"xLink_TreeKEM_PathSecret";
         let hkdf = Hkdf::<Sha256>::new(None, &secret);
         let mut okm = [0u8; 64];
-        hkdf.expand(info, &mut okm).expect("HKDF path secret expansion failed");
+        hkdf.expand(info, &mut okm)
+            .expect("HKDF path secret expansion failed");
 
         let path_secret = {
             let mut ps = [0u8; 32];
@@ -321,11 +409,17 @@ impl TreeKemEngine {
             if parent_id == 0 {
                 break;
             }
-            let secret = Self::derive_path_secret(path_secrets.last().expect("Path secrets should not be empty"), parent_id);
+            let secret = Self::derive_path_secret(
+                path_secrets
+                    .last()
+                    .expect("Path secrets should not be empty"),
+                parent_id,
+            );
             let info = b"xLink_TreeKEM_PathSecret";
             let hkdf = Hkdf::<Sha256>::new(None, &secret);
             let mut okm = [0u8; 64];
-            hkdf.expand(info, &mut okm).expect("HKDF path secret expansion failed");
+            hkdf.expand(info, &mut okm)
+                .expect("HKDF path secret expansion failed");
 
             let parent_secret = {
                 let mut ps = [0u8; 32];
@@ -382,7 +476,8 @@ impl TreeKemEngine {
 
         let hkdf = Hkdf::<Sha256>::new(Some(secret), secret);
         let mut okm = [0u8; 32];
-        hkdf.expand(&info, &mut okm).expect("HKDF key expansion failed");
+        hkdf.expand(&info, &mut okm)
+            .expect("HKDF key expansion failed");
         okm
     }
 
@@ -0,0 +1,87 @@
+//! BLE 广播过滤
+//!
+//! 拥挤环境中扫描会收到大量无关设备的广播。扫描回调先按
+//! [`DiscoveryConfig::service_uuid`] 与 [`DiscoveryConfig::min_rssi`] 过滤广播，
+//! 再将 RSSI 换算为 `ChannelState` 的信号强度与估算距离，
+//! 信号过弱的对端不会登记，路由也就不会把它当作可用的 BLE 对端评分。
+
+use crate::core::types::{ChannelState, ChannelType, DeviceCapabilities, NetworkType};
+use crate::discovery::verification::{DiscoveredPeer, DiscoveryConfig};
+use uuid::Uuid;
+use x25519_dalek::PublicKey;
+
+/// XLink 设备在 BLE 广播中声明的服务 UUID
+pub const XLINK_BLE_SERVICE_UUID: Uuid = Uuid::from_u128(0x6a1b_0001_7c3e_4f2a_9d5e_3b8c_2f41_a7d0);
+
+/// 广播未携带发射功率时使用的 1 米处参考 RSSI（dBm）
+const DEFAULT_TX_POWER: i8 = -59;
+
+/// 室内环境的路径损耗指数
+const PATH_LOSS_EXPONENT: f32 = 2.0;
+
+/// 扫描到的一条 BLE 广播
+#[derive(Debug, Clone)]
+pub struct BleAdvertisement {
+    /// 从广播服务数据中解出的设备能力
+    pub capabilities: DeviceCapabilities,
+    pub claimed_public_key: PublicKey,
+    /// 广播中声明的服务 UUID 列表
+    pub service_uuids: Vec<Uuid>,
+    /// 接收信号强度（dBm）
+    pub rssi: i16,
+    /// 广播携带的 1 米处发射功率（dBm）
+    pub tx_power: Option<i8>,
+}
+
+impl BleAdvertisement {
+    /// 按服务 UUID 与 RSSI 阈值过滤广播，通过时返回可交给校验器接纳的发现结果
+    pub fn filter(self, config: &DiscoveryConfig) -> Option<DiscoveredPeer> {
+        let device_id = self.capabilities.device_id;
+        if !self.service_uuids.contains(&config.service_uuid) {
+            log::debug!(
+                "Skipping BLE advertisement from {} without service {}",
+                device_id,
+                config.service_uuid
+            );
+            return None;
+        }
+        if self.rssi < config.min_rssi {
+            log::debug!(
+                "Skipping BLE peer {}: RSSI {} dBm below {} dBm",
+                device_id,
+                self.rssi,
+                config.min_rssi
+            );
+            return None;
+        }
+
+        // RTT 尚未测量，由之后的心跳更新
+        let state = ChannelState {
+            available: true,
+            rtt_ms: 0,
+            failure_count: 0,
+            last_heartbeat: 0,
+            signal_strength: Some(self.rssi.clamp(i8::MIN.into(), i8::MAX.into()) as i8),
+            distance_meters: Some(estimate_distance(
+                self.rssi,
+                self.tx_power.unwrap_or(DEFAULT_TX_POWER),
+            )),
+            network_type: NetworkType::Bluetooth,
+            bandwidth_bps: 0,
+            jitter_ms: 0,
+            packet_loss_rate: 0.0,
+        };
+        Some(DiscoveredPeer {
+            capabilities: self.capabilities,
+            claimed_public_key: self.claimed_public_key,
+            channel: ChannelType::BluetoothLE,
+            state,
+        })
+    }
+}
+
+/// 按对数距离路径损耗模型由 RSSI 估算距离（米）
+pub fn estimate_distance(rssi: i16, tx_power: i8) -> f32 {
+    let loss = f32::from(tx_power) - f32::from(rssi);
+    10f32.powf(loss / (10.0 * PATH_LOSS_EXPONENT))
+}
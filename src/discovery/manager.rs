@@ -1,13 +1,15 @@
 use crate::capability::manager::CapabilityManager;
-use crate::core::types::{
-    ChannelState, ChannelType, DeviceCapabilities, DeviceId, DeviceType, NetworkType,
-};
-use mdns_sd::{ServiceDaemon, ServiceEvent};
-use std::collections::HashSet;
+use crate::core::types::{ChannelState, ChannelType, DeviceId, NetworkType};
+use crate::discovery::peers::DiscoveredPeers;
+use crate::discovery::txt;
+use crate::discovery::verification::{DiscoveredPeer, DiscoveryConfig, PeerVerifier};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
-use uuid::Uuid;
+
+const SERVICE_TYPE: &str = "_xlink._tcp.local.";
 
 #[derive(Debug, Clone)]
 struct DiscoveryInfo {
@@ -32,6 +34,9 @@ pub struct DiscoveryManager {
     ble_task: Option<JoinHandle<()>>,
     discovery_cache: Arc<tokio::sync::RwLock<std::collections::HashMap<DeviceId, DiscoveryInfo>>>,
     _start_time: Instant,
+    verifier: Option<Arc<PeerVerifier>>,
+    discovered_peers: Option<Arc<DiscoveredPeers>>,
+    config: DiscoveryConfig,
 }
 
 impl DiscoveryManager {
@@ -42,12 +47,47 @@ impl DiscoveryManager {
             ble_task: None,
             discovery_cache: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
             _start_time: Instant::now(),
+            verifier: None,
+            discovered_peers: None,
+            config: DiscoveryConfig::default(),
         }
     }
 
+    /// 设置发现配置，BLE 广播按其中的服务 UUID 与 RSSI 阈值过滤
+    pub fn set_config(&mut self, config: DiscoveryConfig) {
+        self.config = config;
+    }
+
+    /// 当前的发现配置
+    pub fn config(&self) -> &DiscoveryConfig {
+        &self.config
+    }
+
+    /// 设置发现结果校验器，之后发现的对端需经其接纳才会被标记为可路由
+    pub fn set_verifier(&mut self, verifier: Arc<PeerVerifier>) {
+        self.verifier = Some(verifier);
+    }
+
+    /// 获取当前的发现结果校验器
+    pub fn verifier(&self) -> Option<Arc<PeerVerifier>> {
+        self.verifier.clone()
+    }
+
+    /// 设置发现对端集合，之后发现的对端计入上限并按最久未见淘汰
+    pub fn set_discovered_peers(&mut self, peers: Arc<DiscoveredPeers>) {
+        self.discovered_peers = Some(peers);
+    }
+
+    /// 获取当前的发现对端集合
+    pub fn discovered_peers(&self) -> Option<Arc<DiscoveredPeers>> {
+        self.discovered_peers.clone()
+    }
+
     pub async fn start_discovery(&mut self) -> (Option<JoinHandle<()>>, Option<JoinHandle<()>>) {
         let cap_manager = self.cap_manager.clone();
         let discovery_cache = self.discovery_cache.clone();
+        let verifier = self.verifier.clone();
+        let discovered_peers = self.discovered_peers.clone();
 
         let mdns_task = tokio::spawn(async move {
             log::info!("Starting mDNS discovery with <5s target...");
@@ -59,9 +99,17 @@ impl DiscoveryManager {
                     return;
                 }
             };
-            let service_type = "_xlink._tcp.local.";
+            // 广播本机能力，对端无需连接即可协商通道
+            match Self::local_service_info(&cap_manager) {
+                Ok(info) => {
+                    if let Err(e) = mdns.register(info) {
+                        log::error!("Failed to register mDNS service: {}", e);
+                    }
+                }
+                Err(e) => log::error!("Failed to build mDNS service info: {}", e),
+            }
 
-            let receiver = match mdns.browse(service_type) {
+            let receiver = match mdns.browse(SERVICE_TYPE) {
                 Ok(r) => r,
                 Err(e) => {
                     log::error!("Failed to browse mDNS: {}", e);
@@ -87,27 +135,31 @@ impl DiscoveryManager {
                     let fingerprint = Self::identify_fingerprint(&info);
                     log::debug!("Device fingerprint: {}", fingerprint);
 
-                    if let Some(device_id_str) = info.get_property_val_str("id") {
-                        if let Ok(device_id_uuid) = Uuid::parse_str(device_id_str) {
-                            let device_id = DeviceId(device_id_uuid);
-
-                            let caps = DeviceCapabilities {
-                                device_id,
-                                device_type: match info.get_property_val_str("type") {
-                                    Some("mobile") => DeviceType::Smartphone,
-                                    Some("desktop") => DeviceType::Desktop,
-                                    _ => DeviceType::IoTDevice,
-                                },
-                                device_name: info.get_hostname().to_string(),
-                                supported_channels: HashSet::new(),
-                                battery_level: info
-                                    .get_property_val_str("bat")
-                                    .and_then(|v| v.parse().ok()),
-                                is_charging: false,
-                                data_cost_sensitive: false,
-                            };
-
-                            cap_manager.register_remote_device(caps);
+                    let properties: HashMap<String, String> = info
+                        .get_properties()
+                        .iter()
+                        .map(|property| {
+                            (property.key().to_string(), property.val_str().to_string())
+                        })
+                        .collect();
+                    match txt::decode_capabilities(&properties, info.get_hostname()) {
+                        Err(e) if e.code().0 == 801 => {
+                            log::warn!(
+                                "Ignoring mDNS service {}: protocol_version_mismatch ({})",
+                                info.get_fullname(),
+                                e.original_message()
+                            );
+                        }
+                        Err(e) => {
+                            log::debug!(
+                                "Ignoring mDNS service {}: {}",
+                                info.get_fullname(),
+                                e.original_message()
+                            );
+                        }
+                        Ok(caps) if caps.device_id == cap_manager.get_local_caps().device_id => {}
+                        Ok(caps) => {
+                            let device_id = caps.device_id;
 
                             let distance = Self::estimate_distance_from_network(&info);
                             let state = ChannelState {
@@ -122,11 +174,47 @@ impl DiscoveryManager {
                                 jitter_ms: 0,
                                 packet_loss_rate: 0.0,
                             };
-                            cap_manager.update_channel_state(
-                                device_id,
-                                ChannelType::Internet,
-                                state,
-                            );
+
+                            let manual = discovered_peers
+                                .as_ref()
+                                .is_some_and(|peers| peers.is_manual(&device_id));
+
+                            match &verifier {
+                                Some(verifier) if verifier.config().verify_peers => {
+                                    // 广播中必须携带公钥，供挑战-应答校验
+                                    let Some(claimed_public_key) = info
+                                        .get_property_val_str("pk")
+                                        .and_then(Self::parse_public_key)
+                                    else {
+                                        log::warn!(
+                                            "Ignoring device {} advertised without public key",
+                                            device_id
+                                        );
+                                        continue;
+                                    };
+                                    let peer = DiscoveredPeer {
+                                        capabilities: caps,
+                                        claimed_public_key,
+                                        channel: ChannelType::Internet,
+                                        state,
+                                    };
+                                    if !verifier.admit(peer).await {
+                                        continue;
+                                    }
+                                }
+                                _ => {
+                                    cap_manager.register_remote_device(caps);
+                                    cap_manager.update_channel_state(
+                                        device_id,
+                                        ChannelType::Internet,
+                                        state,
+                                    );
+                                }
+                            }
+
+                            if let Some(peers) = discovered_peers.as_ref().filter(|_| !manual) {
+                                peers.observe(device_id);
+                            }
 
                             let mut cache = discovery_cache.write().await;
                             cache.insert(
@@ -146,8 +234,13 @@ impl DiscoveryManager {
             }
         });
 
+        let (service_uuid, min_rssi) = (self.config.service_uuid, self.config.min_rssi);
         let ble_task = tokio::spawn(async move {
-            log::info!("BLE scanning simulation - would scan for 5 seconds");
+            log::info!(
+                "BLE scanning simulation - would scan for 5 seconds (service {}, min RSSI {} dBm)",
+                service_uuid,
+                min_rssi
+            );
             tokio::time::sleep(Duration::from_secs(5)).await;
             log::info!("BLE discovery completed");
         });
@@ -192,8 +285,33 @@ impl DiscoveryManager {
         fingerprint
     }
 
+    fn parse_public_key(hex_key: &str) -> Option<x25519_dalek::PublicKey> {
+        let bytes: [u8; 32] = hex::decode(hex_key).ok()?.try_into().ok()?;
+        Some(x25519_dalek::PublicKey::from(bytes))
+    }
+
     fn filter_service(info: &mdns_sd::ServiceInfo) -> bool {
-        info.get_fullname().contains("_xlink._tcp.local.")
+        info.get_fullname().contains(SERVICE_TYPE)
+    }
+
+    /// 本机的 mDNS 服务信息，TXT 记录携带设备能力与发现协议版本
+    ///
+    /// 服务仅用于发现，不承载连接，端口固定为 0
+    fn local_service_info(
+        cap_manager: &CapabilityManager,
+    ) -> std::result::Result<ServiceInfo, mdns_sd::Error> {
+        let caps = cap_manager.get_local_caps();
+        let instance = caps.device_id.to_string();
+        let host = format!("{}.local.", instance);
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &host,
+            "",
+            0,
+            txt::encode_capabilities(&caps),
+        )
+        .map(ServiceInfo::enable_addr_auto)
     }
 
     fn estimate_distance_from_network(_info: &mdns_sd::ServiceInfo) -> f32 {
@@ -34,6 +34,9 @@ pub struct DiscoveryManager {
     ble_task: Arc<Mutex<Option<JoinHandle<()>>>>,
     discovery_cache: Arc<tokio::sync::RwLock<std::collections::HashMap<DeviceId, DiscoveryInfo>>>,
     start_time: Instant,
+    verifier: Option<Arc<crate::discovery::verification::PeerVerifier>>,
+    discovered_peers: Option<Arc<crate::discovery::peers::DiscoveredPeers>>,
+    config: crate::discovery::verification::DiscoveryConfig,
 }
 
 impl DiscoveryManager {
@@ -44,9 +47,42 @@ impl DiscoveryManager {
             ble_task: Arc::new(Mutex::new(None)),
             discovery_cache: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
             start_time: Instant::now(),
+            verifier: None,
+            discovered_peers: None,
+            config: Default::default(),
         }
     }
 
+    /// 设置发现配置（测试版本的模拟扫描不经过 BLE 广播过滤）
+    pub fn set_config(&mut self, config: crate::discovery::verification::DiscoveryConfig) {
+        self.config = config;
+    }
+
+    /// 当前的发现配置
+    pub fn config(&self) -> &crate::discovery::verification::DiscoveryConfig {
+        &self.config
+    }
+
+    /// 设置发现结果校验器（测试版本的模拟设备不携带公钥，仅保存校验器）
+    pub fn set_verifier(&mut self, verifier: Arc<crate::discovery::verification::PeerVerifier>) {
+        self.verifier = Some(verifier);
+    }
+
+    /// 获取当前的发现结果校验器
+    pub fn verifier(&self) -> Option<Arc<crate::discovery::verification::PeerVerifier>> {
+        self.verifier.clone()
+    }
+
+    /// 设置发现对端集合，之后发现的对端计入上限并按最久未见淘汰
+    pub fn set_discovered_peers(&mut self, peers: Arc<crate::discovery::peers::DiscoveredPeers>) {
+        self.discovered_peers = Some(peers);
+    }
+
+    /// 获取当前的发现对端集合
+    pub fn discovered_peers(&self) -> Option<Arc<crate::discovery::peers::DiscoveredPeers>> {
+        self.discovered_peers.clone()
+    }
+
     pub async fn start_discovery(&self) -> (Option<JoinHandle<()>>, Option<JoinHandle<()>>) {
         let mdns_task_guard = self.mdns_task.lock().await;
         let ble_task_guard = self.ble_task.lock().await;
@@ -58,6 +94,7 @@ impl DiscoveryManager {
 
         let cap_manager = self.cap_manager.clone();
         let discovery_cache = self.discovery_cache.clone();
+        let discovered_peers = self.discovered_peers.clone();
         let mdns_task_arc = self.mdns_task.clone();
 
         // Test version: Simulate mDNS discovery
@@ -117,6 +154,9 @@ impl DiscoveryManager {
                         ..Default::default()
                     },
                 );
+                if let Some(peers) = &discovered_peers {
+                    peers.observe(device_id);
+                }
 
                 tokio::time::sleep(Duration::from_millis(500)).await;
             }
@@ -133,6 +173,7 @@ impl DiscoveryManager {
         // Test version: Simulate BLE discovery
         let cap_manager_ble = self.cap_manager.clone();
         let discovery_cache_ble = self.discovery_cache.clone();
+        let discovered_peers_ble = self.discovered_peers.clone();
         let ble_task_arc = self.ble_task.clone();
 
         let ble_task = tokio::spawn(async move {
@@ -192,6 +233,9 @@ impl DiscoveryManager {
                             ..Default::default()
                         },
                     );
+                    if let Some(peers) = &discovered_peers_ble {
+                        peers.observe(device_id);
+                    }
                 }
 
                 tokio::time::sleep(Duration::from_millis(700)).await;
@@ -1,5 +1,9 @@
+pub mod ble;
 #[cfg(not(feature = "test_no_external_deps"))]
 pub mod manager;
+pub mod peers;
+pub mod txt;
+pub mod verification;
 
 #[cfg(feature = "test_no_external_deps")]
 pub mod manager_test;
@@ -0,0 +1,107 @@
+//! 发现对端数量上限
+//!
+//! 密集环境中发现机制可能登记数百个对端，撑大路由表与内存。[`DiscoveredPeers`]
+//! 跟踪经发现机制登记的对端，超过 [`DiscoveryConfig::max_peers`](crate::discovery::verification::DiscoveryConfig::max_peers)
+//! 时淘汰最久未见的对端，使其能力失效并发布 `SdkEvent::DeviceLost`。
+//! 应用自行注册的对端（手动对端）不受跟踪，永不被淘汰。
+
+use crate::capability::manager::CapabilityManager;
+use crate::core::events::{EventBus, SdkEvent};
+use crate::core::types::DeviceId;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 经发现机制登记的对端集合，按最近一次被发现的先后淘汰
+pub struct DiscoveredPeers {
+    max_peers: AtomicUsize,
+    // 对端 -> 最近一次被发现的序号，序号越小越久未见
+    last_seen: Mutex<(u64, HashMap<DeviceId, u64>)>,
+    cap_manager: Arc<CapabilityManager>,
+    events: EventBus,
+}
+
+impl DiscoveredPeers {
+    pub fn new(max_peers: usize, cap_manager: Arc<CapabilityManager>, events: EventBus) -> Self {
+        Self {
+            max_peers: AtomicUsize::new(max_peers),
+            last_seen: Mutex::new((0, HashMap::new())),
+            cap_manager,
+            events,
+        }
+    }
+
+    pub fn max_peers(&self) -> usize {
+        self.max_peers.load(Ordering::Relaxed)
+    }
+
+    /// 调整上限，立即淘汰超出部分
+    pub fn set_max_peers(&self, max_peers: usize) {
+        self.max_peers.store(max_peers, Ordering::Relaxed);
+        self.evict_excess();
+    }
+
+    /// 当前跟踪的发现对端数量
+    pub fn len(&self) -> usize {
+        self.last_seen.lock().1.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, device_id: &DeviceId) -> bool {
+        self.last_seen.lock().1.contains_key(device_id)
+    }
+
+    /// 对端是否由应用自行注册：已登记到能力管理器但不是由发现机制带来的
+    pub fn is_manual(&self, device_id: &DeviceId) -> bool {
+        !self.contains(device_id) && self.cap_manager.get_remote_device(*device_id).is_some()
+    }
+
+    /// 记录一次发现，超过上限时淘汰最久未见的对端并返回被淘汰的设备
+    pub fn observe(&self, device_id: DeviceId) -> Vec<DeviceId> {
+        {
+            let mut guard = self.last_seen.lock();
+            let (tick, peers) = &mut *guard;
+            *tick += 1;
+            peers.insert(device_id, *tick);
+        }
+        self.evict_excess()
+    }
+
+    fn evict_excess(&self) -> Vec<DeviceId> {
+        let max_peers = self.max_peers();
+        let evicted: Vec<DeviceId> = {
+            let mut guard = self.last_seen.lock();
+            let peers = &mut guard.1;
+            let mut evicted = Vec::new();
+            while peers.len() > max_peers {
+                let Some(oldest) = peers
+                    .iter()
+                    .min_by_key(|(_, seen)| **seen)
+                    .map(|(id, _)| *id)
+                else {
+                    break;
+                };
+                peers.remove(&oldest);
+                evicted.push(oldest);
+            }
+            evicted
+        };
+
+        for device_id in &evicted {
+            log::info!(
+                "Evicting discovered device {} (max {} peers)",
+                device_id,
+                max_peers
+            );
+            self.cap_manager.invalidate_device(*device_id);
+            self.events.publish(SdkEvent::DeviceLost {
+                device_id: *device_id,
+            });
+        }
+        evicted
+    }
+}
@@ -0,0 +1,143 @@
+//! mDNS TXT 记录中的设备能力广播
+//!
+//! 注册 mDNS 服务时把本机的设备类型、支持的通道与发现协议版本写入 TXT 记录，
+//! 对端解析后即可得到部分 [`DeviceCapabilities`]，无需建立连接就能协商通道。
+//!
+//! | 键    | 内容                                   |
+//! |-------|----------------------------------------|
+//! | `id`  | 设备 ID                                |
+//! | `type`| 设备类型标签（如 `mobile`、`desktop`） |
+//! | `ch`  | 逗号分隔的通道标签（如 `lan,ble`）     |
+//! | `pv`  | 发现协议版本                           |
+//! | `bat` | 电量百分比，可选                       |
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::types::{ChannelType, DeviceCapabilities, DeviceId, DeviceType};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// 当前的发现协议版本，TXT 记录版本不同的对端不予接纳
+pub const DISCOVERY_PROTOCOL_VERSION: u16 = 1;
+
+const KEY_ID: &str = "id";
+const KEY_TYPE: &str = "type";
+const KEY_CHANNELS: &str = "ch";
+const KEY_PROTOCOL_VERSION: &str = "pv";
+const KEY_BATTERY: &str = "bat";
+
+/// 把设备能力编码为 TXT 记录
+pub fn encode_capabilities(caps: &DeviceCapabilities) -> HashMap<String, String> {
+    let mut channels: Vec<&str> = caps.supported_channels.iter().map(channel_tag).collect();
+    channels.sort_unstable();
+
+    let mut txt = HashMap::from([
+        (KEY_ID.to_string(), caps.device_id.to_string()),
+        (
+            KEY_TYPE.to_string(),
+            device_type_tag(&caps.device_type).to_string(),
+        ),
+        (KEY_CHANNELS.to_string(), channels.join(",")),
+        (
+            KEY_PROTOCOL_VERSION.to_string(),
+            DISCOVERY_PROTOCOL_VERSION.to_string(),
+        ),
+    ]);
+    if let Some(battery) = caps.battery_level {
+        txt.insert(KEY_BATTERY.to_string(), battery.to_string());
+    }
+    txt
+}
+
+/// 从 TXT 记录解析部分设备能力，`device_name` 取自 mDNS 主机名
+///
+/// 未携带协议版本的旧版本广播按当前版本处理；版本不同时返回 `protocol_version_mismatch`，
+/// 缺少或无法解析设备 ID 时返回 `invalid_protocol_message`，无法识别的通道标签被忽略
+pub fn decode_capabilities(
+    txt: &HashMap<String, String>,
+    device_name: &str,
+) -> Result<DeviceCapabilities> {
+    if let Some(version) = txt.get(KEY_PROTOCOL_VERSION) {
+        if version.parse::<u16>().ok() != Some(DISCOVERY_PROTOCOL_VERSION) {
+            return Err(XLinkError::protocol_version_mismatch(
+                DISCOVERY_PROTOCOL_VERSION.to_string(),
+                version.clone(),
+                file!(),
+            ));
+        }
+    }
+
+    let device_id = txt
+        .get(KEY_ID)
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .map(DeviceId)
+        .ok_or_else(|| {
+            XLinkError::invalid_protocol_message(
+                "mdns txt".to_string(),
+                "missing or malformed device id".to_string(),
+                file!(),
+            )
+        })?;
+
+    let supported_channels: HashSet<ChannelType> = txt
+        .get(KEY_CHANNELS)
+        .map(|channels| channels.split(',').filter_map(parse_channel_tag).collect())
+        .unwrap_or_default();
+
+    Ok(DeviceCapabilities {
+        device_id,
+        device_type: txt
+            .get(KEY_TYPE)
+            .map(|tag| parse_device_type_tag(tag))
+            .unwrap_or(DeviceType::IoTDevice),
+        device_name: device_name.to_string(),
+        supported_channels,
+        battery_level: txt.get(KEY_BATTERY).and_then(|v| v.parse().ok()),
+        is_charging: false,
+        data_cost_sensitive: false,
+    })
+}
+
+fn channel_tag(channel: &ChannelType) -> &'static str {
+    match channel {
+        ChannelType::BluetoothLE => "ble",
+        ChannelType::BluetoothMesh => "mesh",
+        ChannelType::WiFiDirect => "p2p",
+        ChannelType::Internet => "inet",
+        ChannelType::Lan => "lan",
+    }
+}
+
+fn parse_channel_tag(tag: &str) -> Option<ChannelType> {
+    match tag {
+        "ble" => Some(ChannelType::BluetoothLE),
+        "mesh" => Some(ChannelType::BluetoothMesh),
+        "p2p" => Some(ChannelType::WiFiDirect),
+        "inet" => Some(ChannelType::Internet),
+        "lan" => Some(ChannelType::Lan),
+        _ => None,
+    }
+}
+
+fn device_type_tag(device_type: &DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Smartphone => "mobile",
+        DeviceType::Tablet => "tablet",
+        DeviceType::Laptop => "laptop",
+        DeviceType::Desktop => "desktop",
+        DeviceType::Server => "server",
+        DeviceType::IoTDevice => "iot",
+        DeviceType::DevelopmentBoard => "devboard",
+    }
+}
+
+fn parse_device_type_tag(tag: &str) -> DeviceType {
+    match tag {
+        "mobile" => DeviceType::Smartphone,
+        "tablet" => DeviceType::Tablet,
+        "laptop" => DeviceType::Laptop,
+        "desktop" => DeviceType::Desktop,
+        "server" => DeviceType::Server,
+        "devboard" => DeviceType::DevelopmentBoard,
+        _ => DeviceType::IoTDevice,
+    }
+}
@@ -0,0 +1,232 @@
+//! 发现结果校验
+//!
+//! 广播中声明的 `DeviceId` 与公钥默认被直接信任，攻击者可以冒用他人的设备 ID。
+//! 开启 [`DiscoveryConfig::verify_peers`] 后，对端在被标记为可路由之前必须完成挑战-应答：
+//! 校验方生成临时 X25519 密钥与随机数，对端用其静态私钥与临时公钥协商共享密钥，
+//! 并以 HMAC-SHA256(共享密钥, 随机数 || 设备 ID) 应答，从而证明持有所声明公钥对应的私钥。
+//!
+//! 广播携带的公钥本身不可信：冒充者可以广播他人的设备 ID 与自己的公钥并正确应答。
+//! 因此挑战始终针对该设备 ID 事先固定的公钥进行，广播声明的公钥与之不符或该设备
+//! 尚未固定公钥时直接拒绝。
+
+use crate::capability::manager::CapabilityManager;
+use crate::core::error::{Result, XLinkError};
+use crate::core::events::{EventBus, SdkEvent};
+use crate::core::types::{ChannelState, ChannelType, DeviceCapabilities, DeviceId};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 已固定的对端静态公钥：设备 ID -> X25519 公钥，发现校验据此确认对端身份
+pub type PinnedPublicKeys = Arc<DashMap<DeviceId, PublicKey>>;
+
+/// 发现配置
+#[derive(Debug, Clone)]
+pub struct DiscoveryConfig {
+    /// 是否在标记对端可路由之前执行挑战-应答校验
+    pub verify_peers: bool,
+    /// 等待对端应答的超时时间
+    pub challenge_timeout: Duration,
+    /// 最多保留的发现对端数量，超出时淘汰最久未见的对端（手动注册的对端不计入）
+    pub max_peers: usize,
+    /// BLE 扫描只接受声明该服务 UUID 的广播
+    pub service_uuid: Uuid,
+    /// BLE 广播的最低 RSSI（dBm），信号更弱的对端被跳过
+    pub min_rssi: i16,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            verify_peers: false,
+            challenge_timeout: Duration::from_secs(5),
+            max_peers: 256,
+            service_uuid: crate::discovery::ble::XLINK_BLE_SERVICE_UUID,
+            min_rssi: -90,
+        }
+    }
+}
+
+/// 发送给对端的挑战
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryChallenge {
+    pub ephemeral_public: [u8; 32],
+    pub nonce: [u8; 32],
+}
+
+/// 对端的应答
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryChallengeResponse {
+    pub device_id: DeviceId,
+    pub mac: Vec<u8>,
+}
+
+/// 一次发现结果：对端广播的身份、公钥与可达信息
+#[derive(Debug, Clone)]
+pub struct DiscoveredPeer {
+    pub capabilities: DeviceCapabilities,
+    pub claimed_public_key: PublicKey,
+    pub channel: ChannelType,
+    pub state: ChannelState,
+}
+
+/// 通过发现到的端点向对端发送挑战并取回应答
+#[async_trait]
+pub trait PeerChallenger: Send + Sync {
+    async fn challenge(
+        &self,
+        peer: &DiscoveredPeer,
+        challenge: &DiscoveryChallenge,
+    ) -> Result<DiscoveryChallengeResponse>;
+}
+
+/// 以本机静态私钥应答挑战
+pub fn respond_to_challenge(
+    secret: &StaticSecret,
+    device_id: DeviceId,
+    challenge: &DiscoveryChallenge,
+) -> DiscoveryChallengeResponse {
+    let shared = secret.diffie_hellman(&PublicKey::from(challenge.ephemeral_public));
+    DiscoveryChallengeResponse {
+        device_id,
+        mac: challenge_mac(shared.as_bytes(), &challenge.nonce, device_id)
+            .finalize()
+            .into_bytes()
+            .to_vec(),
+    }
+}
+
+fn challenge_mac(shared: &[u8; 32], nonce: &[u8; 32], device_id: DeviceId) -> HmacSha256 {
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(shared).expect("HMAC accepts keys of any length");
+    mac.update(nonce);
+    mac.update(device_id.0.as_bytes());
+    mac
+}
+
+/// 发现结果校验器：校验通过后才将对端登记到能力管理器
+pub struct PeerVerifier {
+    config: DiscoveryConfig,
+    challenger: Arc<dyn PeerChallenger>,
+    cap_manager: Arc<CapabilityManager>,
+    events: EventBus,
+    pinned_keys: PinnedPublicKeys,
+}
+
+impl PeerVerifier {
+    pub fn new(
+        config: DiscoveryConfig,
+        challenger: Arc<dyn PeerChallenger>,
+        cap_manager: Arc<CapabilityManager>,
+        events: EventBus,
+        pinned_keys: PinnedPublicKeys,
+    ) -> Self {
+        Self {
+            config,
+            challenger,
+            cap_manager,
+            events,
+            pinned_keys,
+        }
+    }
+
+    pub fn config(&self) -> &DiscoveryConfig {
+        &self.config
+    }
+
+    /// 接纳发现结果，返回对端是否被标记为可路由
+    ///
+    /// 未开启校验时直接登记；开启后仅在挑战-应答通过时登记，
+    /// 并发布 `DeviceVerified` / `DeviceRejected` 事件
+    pub async fn admit(&self, peer: DiscoveredPeer) -> bool {
+        let device_id = peer.capabilities.device_id;
+
+        if self.config.verify_peers {
+            if let Err(e) = self.verify(&peer).await {
+                log::warn!("Rejected discovered device {}: {}", device_id, e);
+                self.events.publish(SdkEvent::DeviceRejected {
+                    device_id,
+                    reason: e.to_string(),
+                });
+                return false;
+            }
+            log::info!("Verified discovered device {}", device_id);
+            self.events.publish(SdkEvent::DeviceVerified { device_id });
+        }
+
+        self.cap_manager.register_remote_device(peer.capabilities);
+        self.cap_manager
+            .update_channel_state(device_id, peer.channel, peer.state);
+        true
+    }
+
+    /// 对发现结果执行挑战-应答校验
+    ///
+    /// 挑战针对该设备已固定的公钥，广播声明的公钥须与之一致
+    pub async fn verify(&self, peer: &DiscoveredPeer) -> Result<()> {
+        let device_id = peer.capabilities.device_id;
+        let Some(pinned_key) = self.pinned_keys.get(&device_id).map(|key| *key) else {
+            return Err(XLinkError::signature_verification_failed(
+                "X25519-HMAC".to_string(),
+                format!("No public key pinned for device {}", device_id),
+                file!(),
+            ));
+        };
+        if pinned_key != peer.claimed_public_key {
+            return Err(XLinkError::signature_verification_failed(
+                "X25519-HMAC",
+                "Advertised public key does not match the pinned key",
+                file!(),
+            ));
+        }
+        let ephemeral = StaticSecret::random_from_rng(OsRng);
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+        let challenge = DiscoveryChallenge {
+            ephemeral_public: PublicKey::from(&ephemeral).to_bytes(),
+            nonce,
+        };
+
+        let response = tokio::time::timeout(
+            self.config.challenge_timeout,
+            self.challenger.challenge(peer, &challenge),
+        )
+        .await
+        .map_err(|_| {
+            XLinkError::timeout(
+                format!("Discovery challenge to {}", device_id),
+                self.config.challenge_timeout.as_millis() as u64,
+                file!(),
+            )
+        })??;
+
+        if response.device_id != device_id {
+            return Err(XLinkError::signature_verification_failed(
+                "X25519-HMAC",
+                "Challenge answered by a different device",
+                file!(),
+            ));
+        }
+
+        let shared = ephemeral.diffie_hellman(&pinned_key);
+        challenge_mac(shared.as_bytes(), &nonce, device_id)
+            .verify_slice(&response.mac)
+            .map_err(|_| {
+                XLinkError::signature_verification_failed(
+                    "X25519-HMAC",
+                    "Challenge response does not match claimed public key",
+                    file!(),
+                )
+            })
+    }
+}
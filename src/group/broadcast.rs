@@ -60,11 +60,12 @@ impl BroadcastExecutor {
             let router = self.router.clone();
             let mut msg_to_send = message.clone();
             msg_to_send.recipient = member_id; // 设置具体的接收者
+            router.sign_message(&mut msg_to_send);
 
             futures.push(async move {
                 match router.select_channel(&msg_to_send).await {
                     Ok(channel) => {
-                        if let Err(e) = channel.send(msg_to_send).await {
+                        if let Err(e) = router.send_through(channel.as_ref(), msg_to_send).await {
                             warn!("Failed to send message to member {}: {}", member_id, e);
                             (member_id, false)
                         } else {
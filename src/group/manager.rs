@@ -1,17 +1,22 @@
+use crate::core::clock::{system_clock, Clock};
 use crate::core::error::{Result, XLinkError};
 use crate::core::types::{
     DeviceId, Group, GroupId, GroupMember, MemberRole, MemberStatus, Message, MessagePayload,
     MessagePriority,
 };
+use crate::crypto::engine::CryptoEngine;
 use crate::crypto::treekem::TreeKemEngine;
 use crate::crypto::treekem::UpdatePath;
 use crate::router::selector::Router;
 use dashmap::DashMap;
-use futures::stream::{FuturesUnordered, StreamExt};
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::sync::{mpsc, RwLock};
+use ed25519_dalek::VerifyingKey;
+use futures::stream::{self, StreamExt};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 use x25519_dalek::PublicKey;
 
@@ -28,6 +33,147 @@ pub enum ProximityType {
 
 type AckStats = (HashSet<DeviceId>, HashSet<DeviceId>, HashSet<DeviceId>);
 
+/// 单次广播默认的最大并发发送数
+pub const DEFAULT_BROADCAST_CONCURRENCY: usize = 32;
+
+/// 群组广播的远程扇出策略，用于控制按流量计费网络上的发送量
+/// 群组事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupEvent {
+    /// 本地设备作为管理员收到入群申请，需调用 approve_join / deny_join 处理
+    JoinRequested {
+        group_id: GroupId,
+        device_id: DeviceId,
+    },
+    /// 收到本地未加入群组的消息，`policy` 为处理时采用的策略
+    UnknownGroupMessage {
+        group_id: GroupId,
+        sender: DeviceId,
+        message_id: Uuid,
+        policy: UnknownGroupPolicy,
+    },
+}
+
+pub type GroupEventHandler = Box<dyn Fn(GroupEvent) + Send + Sync>;
+
+/// 群组成员在线状态的一次变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresenceEvent {
+    pub group_id: GroupId,
+    pub device_id: DeviceId,
+    pub previous: MemberStatus,
+    pub status: MemberStatus,
+}
+
+/// 按最后活跃时间判定成员在线状态的阈值
+///
+/// 心跳收到成员的 Ping 或 Pong 时将其标记为在线并刷新 `last_seen`；
+/// 超过 `away_after` 未活跃的在线成员变为 Away，超过 `offline_after` 变为 Offline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceConfig {
+    pub away_after: Duration,
+    pub offline_after: Duration,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        // 与心跳默认最长间隔（60s）的 3 倍离线判定一致
+        Self {
+            away_after: Duration::from_secs(90),
+            offline_after: Duration::from_secs(180),
+        }
+    }
+}
+
+/// 每个在线状态订阅者最多积压的事件数，超出后新事件被丢弃
+pub const PRESENCE_CHANNEL_CAPACITY: usize = 64;
+
+/// 收到本地未加入群组的消息时的处理策略
+///
+/// 邀请与群组消息可能乱序到达，消息先于邀请到达时群组尚不存在
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownGroupPolicy {
+    /// 丢弃并记录日志
+    #[default]
+    Drop,
+    /// 短暂缓存，期间收到该群组的邀请后按到达顺序补交给应用
+    Buffer {
+        ttl: Duration,
+        /// 每个群组最多缓存的消息数，超出时丢弃最旧的消息
+        max_messages: usize,
+    },
+    /// 丢弃消息并向发送方申请加入该群组，仅当发送方为群组管理员时才会被处理
+    RequestJoin,
+}
+
+/// 最多同时缓存多少个未知群组的消息
+const MAX_BUFFERED_UNKNOWN_GROUPS: usize = 64;
+
+/// 同一未知群组两次入群申请之间的最短间隔
+const UNKNOWN_GROUP_JOIN_REQUEST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 群组密钥的自动轮换策略，按时间或消息数触发，先到者生效
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// 两次轮换之间的最长间隔
+    pub every: Duration,
+    /// 自上次轮换起本地广播的消息数达到该值时轮换，为 0 时只按时间轮换
+    pub after_n_messages: u32,
+}
+
+/// 已启用自动轮换的群组的调度状态
+struct RotationSchedule {
+    policy: RotationPolicy,
+    // 自上次轮换起本地广播的（非控制）消息数
+    messages: AtomicU32,
+    // 消息数达到阈值时唤醒轮换任务
+    trigger: Notify,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastFanoutPolicy {
+    /// 单次广播最多直接发送的远程成员数，超出部分的消息放入结果的 `deferred`，None 表示不限制
+    pub max_remote_sends: Option<usize>,
+    /// 本地设备对流量费用敏感时，不通过蜂窝网络直接发送给仅能经蜂窝网络触达的成员
+    pub avoid_cellular_when_cost_sensitive: bool,
+}
+
+/// 群组广播的接收范围，本机始终不在其中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastTargets {
+    /// 全部成员
+    All,
+    /// 只发给在线成员，离线成员的消息放在结果的 `deferred` 中
+    OnlineOnly,
+    /// 指定角色的成员
+    Role(MemberRole),
+    /// 指定的设备，必须都是群组成员
+    Devices(HashSet<DeviceId>),
+}
+
+impl BroadcastTargets {
+    fn includes(&self, device_id: DeviceId, member: &GroupMember) -> bool {
+        match self {
+            BroadcastTargets::All | BroadcastTargets::OnlineOnly => true,
+            BroadcastTargets::Role(role) => member.role == *role,
+            BroadcastTargets::Devices(devices) => devices.contains(&device_id),
+        }
+    }
+
+    /// 参与广播去重的范围标识，全体广播与在线广播不区分
+    fn dedup_scope(&self) -> Vec<u8> {
+        match self {
+            BroadcastTargets::All | BroadcastTargets::OnlineOnly => Vec::new(),
+            BroadcastTargets::Role(role) => format!("role:{:?}", role).into_bytes(),
+            BroadcastTargets::Devices(devices) => {
+                let mut ids: Vec<String> = devices.iter().map(|id| id.to_string()).collect();
+                ids.sort();
+                format!("devices:{}", ids.join(",")).into_bytes()
+            }
+        }
+    }
+}
+
 pub struct GroupManager {
     local_device_id: DeviceId,
     groups: DashMap<GroupId, Group>,
@@ -42,6 +188,37 @@ pub struct GroupManager {
     ack_timeout: Duration,
     // 广播结果通知通道
     broadcast_results: Arc<RwLock<HashMap<Uuid, mpsc::Sender<BroadcastResult>>>>,
+    // 远程扇出策略
+    fanout_policy: parking_lot::RwLock<BroadcastFanoutPolicy>,
+    // 单次广播的最大并发发送数
+    broadcast_concurrency: std::sync::atomic::AtomicUsize,
+    // 发送端广播去重窗口，None 表示关闭
+    dedup_window: parking_lot::RwLock<Option<Duration>>,
+    // 进行中的广播: (GroupId, 负载哈希) -> (MessageId, 开始时间, 扇出结果)
+    inflight_broadcasts: DashMap<BroadcastKey, (Uuid, Instant, SharedBroadcastOutcome)>,
+    // 待审批的入群申请: (GroupId, 申请设备) -> 申请时间戳
+    pending_join_requests: DashMap<(GroupId, DeviceId), u64>,
+    // 群组事件处理器
+    event_handlers: parking_lot::Mutex<Vec<GroupEventHandler>>,
+    // 未知群组消息的处理策略
+    unknown_group_policy: parking_lot::RwLock<UnknownGroupPolicy>,
+    // 等待邀请的未知群组消息: GroupId -> [(缓存时间, 消息)]
+    unknown_group_buffer: DashMap<GroupId, VecDeque<(Instant, Message)>>,
+    // 因未知群组消息发出入群申请的时间，用于限制申请频率
+    unknown_group_join_requests: DashMap<GroupId, Instant>,
+    // 为发出的群组消息签名的身份密钥，None 表示不签名
+    signer: parking_lot::RwLock<Option<Arc<CryptoEngine>>>,
+    // 已固定的成员身份公钥，用于校验明文传输的群组控制消息的签名
+    identity_keys: parking_lot::RwLock<Arc<DashMap<DeviceId, VerifyingKey>>>,
+    // 成员在线状态订阅者: GroupId -> 订阅通道
+    presence_subscribers: DashMap<GroupId, Vec<mpsc::Sender<PresenceEvent>>>,
+    presence_config: parking_lot::RwLock<PresenceConfig>,
+    // 自动轮换调度: GroupId -> 策略与消息计数
+    rotation_schedules: DashMap<GroupId, Arc<RotationSchedule>>,
+    // 纪元闸门：广播在发送期间持有读锁，轮换持有写锁，保证进行中的广播以旧纪元发完且轮换按群组串行
+    epoch_gates: DashMap<GroupId, Arc<RwLock<()>>>,
+    // 成员活跃时间、邀请与缓存过期的时间来源
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,8 +229,108 @@ pub struct BroadcastResult {
     pub total_attempts: usize,
 }
 
+/// 选择性广播的结果，只有 [`BroadcastTargets::OnlineOnly`] 会跳过离线成员
+#[derive(Debug, Clone, Default)]
+pub struct OnlineBroadcast {
+    pub message_id: Uuid,
+    /// 因离线被跳过的成员
+    pub skipped: Vec<DeviceId>,
+    /// 被扇出策略（[`BroadcastFanoutPolicy`]）延后、未直接发送的远程成员
+    pub fanout_deferred: Vec<DeviceId>,
+    /// 发给被跳过与被延后成员的消息，应放入待发送队列
+    pub deferred: Vec<Message>,
+}
+
+/// 广播去重键：(GroupId, 负载与接收范围的哈希)
+type BroadcastKey = (GroupId, [u8; 32]);
+/// 进行中广播的扇出结果，扇出结束前为 None
+type SharedBroadcastOutcome = tokio::sync::watch::Receiver<Option<Result<OnlineBroadcast>>>;
+
+/// 广播去重检查的结果
+enum BroadcastDedup {
+    /// 未开启去重或无法计算负载哈希
+    Untracked,
+    /// 本次广播负责扇出，结束后经 `sender` 把结果交给合并进来的调用方
+    Leader {
+        key: BroadcastKey,
+        sender: tokio::sync::watch::Sender<Option<Result<OnlineBroadcast>>>,
+    },
+    /// 相同的广播正在扇出，等待其结果
+    Coalesced(SharedBroadcastOutcome),
+}
+
+/// 在途广播的登记，扇出结束、失败或被取消时移除
+struct InflightBroadcast<'a> {
+    inflight: &'a DashMap<BroadcastKey, (Uuid, Instant, SharedBroadcastOutcome)>,
+    key: BroadcastKey,
+    message_id: Uuid,
+}
+
+impl Drop for InflightBroadcast<'_> {
+    fn drop(&mut self) {
+        self.inflight
+            .remove_if(&self.key, |_, (id, _, _)| *id == self.message_id);
+    }
+}
+
+/// 分发群组密钥的加密上下文，密文同时绑定管理员、接收成员、群组与纪元
+fn group_key_context(
+    admin: DeviceId,
+    member: DeviceId,
+    group_id: GroupId,
+    epoch: u64,
+) -> crate::crypto::context::EncryptionContext {
+    crate::crypto::context::EncryptionContext {
+        sender: admin,
+        recipient: Some(member),
+        group_id: Some(group_id),
+        epoch,
+    }
+}
+
+/// 构造发给单个成员的群组消息，`timestamp` 为发送时的 Unix 时间（秒）
+#[allow(clippy::too_many_arguments)]
+fn group_message(
+    message_id: Uuid,
+    timestamp: u64,
+    sender: DeviceId,
+    recipient: DeviceId,
+    group_id: GroupId,
+    payload: MessagePayload,
+    priority: MessagePriority,
+    require_ack: bool,
+) -> Message {
+    Message {
+        id: message_id,
+        sender,
+        recipient,
+        group_id: Some(group_id),
+        payload,
+        timestamp,
+        priority,
+        require_ack,
+        require_ordered: false,
+        sequence: None,
+        sequence_epoch: None,
+        correlation_id: None,
+        in_reply_to: None,
+        topic: None,
+        expires_at: None,
+        signature: None,
+    }
+}
+
 impl GroupManager {
     pub fn new(local_device_id: DeviceId, router: Arc<Router>) -> Self {
+        Self::with_clock(local_device_id, router, system_clock())
+    }
+
+    /// 使用指定时钟创建，成员活跃时间、邀请与未知群组消息缓存的过期均以该时钟为准
+    pub fn with_clock(
+        local_device_id: DeviceId,
+        router: Arc<Router>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         let treekem_engine = Arc::new(TreeKemEngine::new(local_device_id));
 
         Self {
@@ -65,7 +342,411 @@ impl GroupManager {
             processed_invites: Arc::new(DashMap::new()),
             ack_timeout: Duration::from_secs(30),
             broadcast_results: Arc::new(RwLock::new(HashMap::new())),
+            fanout_policy: parking_lot::RwLock::new(BroadcastFanoutPolicy::default()),
+            broadcast_concurrency: std::sync::atomic::AtomicUsize::new(
+                DEFAULT_BROADCAST_CONCURRENCY,
+            ),
+            dedup_window: parking_lot::RwLock::new(None),
+            inflight_broadcasts: DashMap::new(),
+            pending_join_requests: DashMap::new(),
+            event_handlers: parking_lot::Mutex::new(Vec::new()),
+            unknown_group_policy: parking_lot::RwLock::new(UnknownGroupPolicy::default()),
+            unknown_group_buffer: DashMap::new(),
+            unknown_group_join_requests: DashMap::new(),
+            signer: parking_lot::RwLock::new(None),
+            identity_keys: parking_lot::RwLock::new(Arc::new(DashMap::new())),
+            presence_subscribers: DashMap::new(),
+            presence_config: parking_lot::RwLock::new(PresenceConfig::default()),
+            rotation_schedules: DashMap::new(),
+            epoch_gates: DashMap::new(),
+            clock,
+        }
+    }
+
+    /// 为群组启用自动密钥轮换并启动轮换任务，仅限管理员
+    ///
+    /// 替换已有策略时旧任务在下一次触发时自行退出；返回的任务句柄由调用方保管，
+    /// 停止时中止即可。群组被移除或策略被清除后任务同样自行退出
+    pub fn set_rotation_policy(
+        self: &Arc<Self>,
+        group_id: GroupId,
+        policy: RotationPolicy,
+    ) -> Result<JoinHandle<()>> {
+        if policy.every.is_zero() {
+            return Err(XLinkError::invalid_input(
+                "every",
+                "rotation interval must be non-zero",
+                file!(),
+            ));
         }
+        {
+            let group = self
+                .groups
+                .get(&group_id)
+                .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+            self.require_admin(&group, "schedule group key rotation")?;
+        }
+        self.rotation_schedules.insert(
+            group_id,
+            Arc::new(RotationSchedule {
+                policy,
+                messages: AtomicU32::new(0),
+                trigger: Notify::new(),
+            }),
+        );
+        Ok(self
+            .spawn_rotation_task(group_id)
+            .expect("rotation schedule was just inserted"))
+    }
+
+    /// 关闭群组的自动密钥轮换，返回此前是否已启用
+    pub fn clear_rotation_policy(&self, group_id: GroupId) -> bool {
+        self.rotation_schedules.remove(&group_id).is_some()
+    }
+
+    /// 获取群组当前的自动轮换策略
+    pub fn rotation_policy(&self, group_id: GroupId) -> Option<RotationPolicy> {
+        self.rotation_schedules
+            .get(&group_id)
+            .map(|schedule| schedule.policy)
+    }
+
+    /// 按已登记的策略启动轮换任务，群组未启用自动轮换时返回 None
+    fn spawn_rotation_task(self: &Arc<Self>, group_id: GroupId) -> Option<JoinHandle<()>> {
+        let schedule = self.rotation_schedules.get(&group_id)?.clone();
+        let manager: Weak<Self> = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            loop {
+                let by_count = tokio::select! {
+                    _ = tokio::time::sleep(schedule.policy.every) => false,
+                    _ = schedule.trigger.notified() => true,
+                };
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                // 策略被替换或清除、群组已移除时退出
+                let current = manager
+                    .rotation_schedules
+                    .get(&group_id)
+                    .is_some_and(|s| Arc::ptr_eq(s.value(), &schedule));
+                if !current || !manager.groups.contains_key(&group_id) {
+                    break;
+                }
+                // 按时间轮换已清零计数时，残留的唤醒不再触发额外的轮换
+                if by_count
+                    && schedule.messages.load(Ordering::Relaxed) < schedule.policy.after_n_messages
+                {
+                    continue;
+                }
+                if let Err(e) = manager.rotate_group_key(group_id).await {
+                    log::warn!(
+                        "Scheduled key rotation for group {} failed: {}",
+                        group_id,
+                        e
+                    );
+                }
+            }
+            log::debug!("Key rotation task for group {} stopped", group_id);
+        }))
+    }
+
+    /// 群组的纪元闸门
+    fn epoch_gate(&self, group_id: GroupId) -> Arc<RwLock<()>> {
+        self.epoch_gates.entry(group_id).or_default().clone()
+    }
+
+    /// 记录一条本地广播，达到轮换策略的消息数时唤醒轮换任务
+    fn count_rotation_message(&self, group_id: GroupId) {
+        let Some(schedule) = self.rotation_schedules.get(&group_id) else {
+            return;
+        };
+        let threshold = schedule.policy.after_n_messages;
+        if threshold > 0 && schedule.messages.fetch_add(1, Ordering::Relaxed) + 1 == threshold {
+            schedule.trigger.notify_one();
+        }
+    }
+
+    /// 设置单次广播的最大并发发送数（最小为 1），避免大群组广播瞬间占满套接字/射频资源
+    pub fn set_broadcast_concurrency(&self, limit: usize) {
+        self.broadcast_concurrency
+            .store(limit.max(1), std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// 设置为广播消息签名的引擎，接收方可凭发送方身份公钥识别中继对密文的篡改
+    pub fn set_message_signer(&self, signer: Option<Arc<CryptoEngine>>) {
+        *self.signer.write() = signer;
+    }
+
+    /// 设置已固定的设备身份公钥表，群组控制消息的签名据此校验
+    ///
+    /// 未在表中的发送方回退到签名引擎中已认证会话的公钥，两者都没有时控制消息被拒绝
+    pub fn set_identity_keys(&self, keys: Arc<DashMap<DeviceId, VerifyingKey>>) {
+        *self.identity_keys.write() = keys;
+    }
+
+    /// 以设置的签名引擎签名消息，未设置时不做处理
+    fn sign_message(&self, message: &mut Message) {
+        if let Some(signer) = self.signer.read().as_ref() {
+            signer.sign_message(message);
+        }
+    }
+
+    /// 获取单次广播的最大并发发送数
+    pub fn broadcast_concurrency(&self) -> usize {
+        self.broadcast_concurrency
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// 设置广播去重窗口（默认关闭）
+    ///
+    /// 开启后，向同一群组发送相同负载的广播在前一次扇出结束前会合并进去，共享其结果；
+    /// 扇出结束（成功或失败）后的广播重新发送。窗口是合并的时间上限，超出窗口仍未结束的
+    /// 扇出不再接受合并
+    pub fn set_broadcast_dedup_window(&self, window: Option<Duration>) {
+        *self.dedup_window.write() = window;
+        if window.is_none() {
+            crate::utils::remove_keys(
+                &self.inflight_broadcasts,
+                crate::utils::get_all_keys(&self.inflight_broadcasts),
+            );
+        }
+    }
+
+    /// 获取当前的广播去重窗口
+    pub fn broadcast_dedup_window(&self) -> Option<Duration> {
+        *self.dedup_window.read()
+    }
+
+    /// 去重检查：已有相同广播正在扇出时返回其结果的接收端，否则登记本次广播
+    fn dedup_broadcast(
+        &self,
+        group_id: GroupId,
+        payload: &MessagePayload,
+        targets: &BroadcastTargets,
+        message_id: Uuid,
+    ) -> BroadcastDedup {
+        use sha2::{Digest, Sha256};
+
+        let Some(window) = *self.dedup_window.read() else {
+            return BroadcastDedup::Untracked;
+        };
+        let Ok(encoded) = serde_json::to_vec(payload) else {
+            return BroadcastDedup::Untracked;
+        };
+        let digest: [u8; 32] = Sha256::new()
+            .chain_update(&encoded)
+            .chain_update(targets.dedup_scope())
+            .finalize()
+            .into();
+        let now = self.clock.instant_now();
+        let key = (group_id, digest);
+
+        match self.inflight_broadcasts.entry(key) {
+            dashmap::mapref::entry::Entry::Occupied(entry)
+                if now.duration_since(entry.get().1) < window =>
+            {
+                BroadcastDedup::Coalesced(entry.get().2.clone())
+            }
+            dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                let (sender, outcome) = tokio::sync::watch::channel(None);
+                entry.insert((message_id, now, outcome));
+                BroadcastDedup::Leader { key, sender }
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let (sender, outcome) = tokio::sync::watch::channel(None);
+                entry.insert((message_id, now, outcome));
+                BroadcastDedup::Leader { key, sender }
+            }
+        }
+    }
+
+    /// 设置群组广播的远程扇出策略
+    pub fn set_fanout_policy(&self, policy: BroadcastFanoutPolicy) {
+        log::info!("Group broadcast fanout policy updated: {:?}", policy);
+        *self.fanout_policy.write() = policy;
+    }
+
+    /// 获取当前的远程扇出策略
+    pub fn fanout_policy(&self) -> BroadcastFanoutPolicy {
+        self.fanout_policy.read().clone()
+    }
+
+    /// 设置未知群组消息的处理策略，切换为非缓存策略时丢弃已缓存的消息
+    pub fn set_unknown_group_policy(&self, policy: UnknownGroupPolicy) {
+        *self.unknown_group_policy.write() = policy;
+        if !matches!(policy, UnknownGroupPolicy::Buffer { .. }) {
+            self.unknown_group_buffer.clear();
+        }
+    }
+
+    /// 获取当前的未知群组消息处理策略
+    pub fn unknown_group_policy(&self) -> UnknownGroupPolicy {
+        *self.unknown_group_policy.read()
+    }
+
+    /// 取出为该群组缓存且未过期的消息（按到达顺序），通常在处理完群组邀请后调用
+    pub fn take_buffered_group_messages(&self, group_id: GroupId) -> Vec<Message> {
+        let Some((_, queue)) = self.unknown_group_buffer.remove(&group_id) else {
+            return Vec::new();
+        };
+        let ttl = match *self.unknown_group_policy.read() {
+            UnknownGroupPolicy::Buffer { ttl, .. } => ttl,
+            _ => return Vec::new(),
+        };
+        let now = self.clock.instant_now();
+        queue
+            .into_iter()
+            .filter(|(buffered_at, _)| now.duration_since(*buffered_at) < ttl)
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    /// 按策略处理发往本地未加入群组的消息
+    async fn handle_unknown_group_message(
+        &self,
+        group_id: GroupId,
+        message: &Message,
+    ) -> Result<()> {
+        let policy = *self.unknown_group_policy.read();
+        match policy {
+            UnknownGroupPolicy::Drop => {
+                log::warn!(
+                    "Dropping message {} from {} for unknown group {}",
+                    message.id,
+                    message.sender,
+                    group_id
+                );
+            }
+            UnknownGroupPolicy::Buffer { ttl, max_messages } => {
+                self.buffer_unknown_group_message(group_id, message, ttl, max_messages);
+            }
+            UnknownGroupPolicy::RequestJoin => {
+                let now = self.clock.instant_now();
+                let recently_requested = self
+                    .unknown_group_join_requests
+                    .get(&group_id)
+                    .is_some_and(|at| {
+                        now.duration_since(*at) < UNKNOWN_GROUP_JOIN_REQUEST_INTERVAL
+                    });
+                if !recently_requested {
+                    self.unknown_group_join_requests.insert(group_id, now);
+                    if let Err(e) = self.request_join(group_id, message.sender).await {
+                        log::warn!(
+                            "Failed to request join for unknown group {} from {}: {}",
+                            group_id,
+                            message.sender,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        self.emit_event(GroupEvent::UnknownGroupMessage {
+            group_id,
+            sender: message.sender,
+            message_id: message.id,
+            policy,
+        });
+        Ok(())
+    }
+
+    fn buffer_unknown_group_message(
+        &self,
+        group_id: GroupId,
+        message: &Message,
+        ttl: Duration,
+        max_messages: usize,
+    ) {
+        let now = self.clock.instant_now();
+        self.unknown_group_buffer.retain(|_, queue| {
+            queue.retain(|(buffered_at, _)| now.duration_since(*buffered_at) < ttl);
+            !queue.is_empty()
+        });
+
+        // 缓存的群组数达到上限时，淘汰最早开始缓存的群组
+        if !self.unknown_group_buffer.contains_key(&group_id)
+            && self.unknown_group_buffer.len() >= MAX_BUFFERED_UNKNOWN_GROUPS
+        {
+            let oldest = self
+                .unknown_group_buffer
+                .iter()
+                .filter_map(|entry| entry.value().front().map(|(at, _)| (*at, *entry.key())))
+                .min_by_key(|(at, _)| *at)
+                .map(|(_, key)| key);
+            if let Some(oldest) = oldest {
+                self.unknown_group_buffer.remove(&oldest);
+            }
+        }
+
+        let mut queue = self.unknown_group_buffer.entry(group_id).or_default();
+        queue.push_back((now, message.clone()));
+        while queue.len() > max_messages.max(1) {
+            queue.pop_front();
+        }
+        log::info!(
+            "Buffered message {} for unknown group {} ({} pending)",
+            message.id,
+            group_id,
+            queue.len()
+        );
+    }
+
+    /// 判断成员是否只能通过蜂窝网络触达
+    fn reachable_only_via_cellular(&self, member_id: DeviceId) -> bool {
+        let cap_manager = self.router.capability_manager();
+        let mut any_available = false;
+        for ctype in self.router.get_channels().keys() {
+            if let Some(state) = cap_manager.get_channel_state(&member_id, ctype) {
+                if !state.available {
+                    continue;
+                }
+                any_available = true;
+                if !matches!(
+                    state.network_type,
+                    crate::core::types::NetworkType::Cellular4G
+                        | crate::core::types::NetworkType::Cellular5G
+                ) {
+                    return false;
+                }
+            }
+        }
+        any_available
+    }
+
+    /// 按扇出策略拆分远程成员：返回 (直接发送, 延后发送, 其中为避开蜂窝网络而延后的成员数)
+    fn apply_fanout_policy(
+        &self,
+        remote_members: Vec<DeviceId>,
+    ) -> (Vec<DeviceId>, Vec<DeviceId>, usize) {
+        let policy = self.fanout_policy();
+        let cost_sensitive = self
+            .router
+            .capability_manager()
+            .get_local_caps()
+            .data_cost_sensitive;
+
+        let mut direct = Vec::new();
+        let mut deferred = Vec::new();
+        let mut avoided_cellular = 0;
+        for member_id in remote_members {
+            let over_cap = policy
+                .max_remote_sends
+                .is_some_and(|max| direct.len() >= max);
+            let avoid_cellular = policy.avoid_cellular_when_cost_sensitive
+                && cost_sensitive
+                && self.reachable_only_via_cellular(member_id);
+
+            if avoid_cellular {
+                avoided_cellular += 1;
+            }
+            if over_cap || avoid_cellular {
+                deferred.push(member_id);
+            } else {
+                direct.push(member_id);
+            }
+        }
+        (direct, deferred, avoided_cellular)
     }
 
     /// 注册设备公钥到 TreeKEM 引擎
@@ -75,6 +756,32 @@ impl GroupManager {
         Ok(())
     }
 
+    /// 返回 `devices` 中尚未注册公钥的设备，保持原有顺序并去重
+    pub fn missing_device_keys(&self, devices: &[DeviceId]) -> Vec<DeviceId> {
+        let mut missing: Vec<DeviceId> = Vec::new();
+        for &device_id in devices {
+            if self
+                .treekem_engine
+                .get_device_public_key(device_id)
+                .is_err()
+                && !missing.contains(&device_id)
+            {
+                missing.push(device_id);
+            }
+        }
+        missing
+    }
+
+    /// 列出已注册到 TreeKEM 引擎的设备公钥
+    pub fn registered_device_keys(&self) -> Vec<(DeviceId, Vec<u8>)> {
+        self.treekem_engine.registered_device_keys()
+    }
+
+    /// 从 TreeKEM 引擎撤销设备公钥，返回该公钥此前是否存在
+    pub fn revoke_device_key(&self, device_id: DeviceId) -> bool {
+        self.treekem_engine.revoke_device_key(device_id)
+    }
+
     /// 智能分类设备邻近性，用于混合拓扑广播
     /// 基于路由器选择的通道类型来判断设备距离
     async fn classify_member_proximity(&self, member_id: DeviceId) -> ProximityType {
@@ -88,6 +795,14 @@ impl GroupManager {
             timestamp: 0,
             priority: crate::core::types::MessagePriority::Normal,
             require_ack: false,
+            require_ordered: false,
+            sequence: None,
+            sequence_epoch: None,
+            correlation_id: None,
+            in_reply_to: None,
+            topic: None,
+            expires_at: None,
+            signature: None,
         };
 
         // 尝试选择通道来判断设备类型
@@ -103,25 +818,52 @@ impl GroupManager {
         }
     }
 
+    /// 创建群组，本地设备为管理员
+    ///
+    /// 任一成员未注册公钥时返回 `group_member_keys_missing`，错误中列出这些设备；
+    /// 只想以已有公钥的成员建群时使用 [`create_group_partial`](Self::create_group_partial)
     pub async fn create_group(
         &self,
         name: String,
         initial_members: Vec<DeviceId>,
     ) -> Result<Group> {
-        let group_id = GroupId::new();
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_else(|_| Duration::from_secs(0))
-            .as_secs();
+        let missing = self.missing_device_keys(&initial_members);
+        if !missing.is_empty() {
+            return Err(XLinkError::group_member_keys_missing(&missing, file!()));
+        }
+        self.create_group_with_keys(name, initial_members)
+    }
 
-        // 初始化 TreeKEM 群组密钥
-        let member_ids: Vec<_> = initial_members
-            .iter()
-            .filter(|&&device_id| self.treekem_engine.get_device_public_key(device_id).is_ok())
-            .cloned()
+    /// 只以已注册公钥的成员创建群组，返回群组与被跳过的成员
+    ///
+    /// 被跳过的成员可在注册公钥后通过 `add_member` 加入
+    pub async fn create_group_partial(
+        &self,
+        name: String,
+        initial_members: Vec<DeviceId>,
+    ) -> Result<(Group, Vec<DeviceId>)> {
+        let skipped = self.missing_device_keys(&initial_members);
+        let members = initial_members
+            .into_iter()
+            .filter(|device_id| !skipped.contains(device_id))
             .collect();
+        if !skipped.is_empty() {
+            log::warn!(
+                "Creating group without {} members lacking public keys",
+                skipped.len()
+            );
+        }
+        let group = self.create_group_with_keys(name, members)?;
+        Ok((group, skipped))
+    }
 
-        if member_ids.is_empty() {
+    /// 以已全部注册公钥的成员创建群组
+    fn create_group_with_keys(
+        &self,
+        name: String,
+        initial_members: Vec<DeviceId>,
+    ) -> Result<Group> {
+        if initial_members.is_empty() {
             return Err(XLinkError::invalid_input(
                 "member_keys",
                 "No valid member keys found for group creation",
@@ -129,7 +871,12 @@ impl GroupManager {
             ));
         }
 
-        self.treekem_engine.create_group(group_id, member_ids)?;
+        let group_id = GroupId::new();
+        let now = self.clock.now_unix_secs();
+
+        // 初始化 TreeKEM 群组密钥
+        self.treekem_engine
+            .create_group(group_id, initial_members.clone())?;
 
         let mut members = HashMap::new();
         for device_id in initial_members {
@@ -174,42 +921,321 @@ impl GroupManager {
         Ok(group)
     }
 
+    /// 添加群组成员，仅限管理员
     pub async fn add_member(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
         let mut group = self
             .groups
             .get_mut(&group_id)
             .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+        self.require_admin(&group, "add members")?;
+
+        let now = self.clock.now_unix_secs();
+
+        // 注册新成员公钥到 TreeKEM (如果已有)
+        if self.treekem_engine.get_device_public_key(device_id).is_ok() {
+            self.treekem_engine.add_member(group_id, device_id)?;
+        } else {
+            use rand::rngs::OsRng;
+            use x25519_dalek::StaticSecret;
+            let secret = StaticSecret::random_from_rng(OsRng);
+            let public = PublicKey::from(&secret);
+            self.treekem_engine.register_device_key(device_id, public);
+            self.treekem_engine.add_member(group_id, device_id)?;
+        }
+
+        group.members.insert(
+            device_id,
+            GroupMember {
+                device_id,
+                role: MemberRole::Member,
+                joined_at: now,
+                last_seen: now,
+                status: MemberStatus::Online,
+            },
+        );
+
+        log::info!("Added member {} to group {}", device_id, group_id);
+        Ok(())
+    }
+
+    /// 移除群组成员并轮换群组密钥，仅限管理员
+    ///
+    /// 成员先从 TreeKEM 树中剔除，随后的密钥更新只广播给剩余成员，被移除的设备
+    /// 无法解密之后的群组消息。移除本地设备等同于 `leave_group`
+    pub async fn remove_member(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
+        if device_id == self.local_device_id {
+            return self.leave_group(group_id).await;
+        }
+
+        {
+            let mut group = self
+                .groups
+                .get_mut(&group_id)
+                .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+            self.require_admin(&group, "remove members")?;
+            if !group.members.contains_key(&device_id) {
+                return Err(XLinkError::not_group_member(
+                    group_id.to_string(),
+                    device_id.to_string(),
+                    file!(),
+                ));
+            }
+
+            self.treekem_engine.remove_member(group_id, device_id)?;
+            group.members.remove(&device_id);
+        }
+
+        log::info!("Removed member {} from group {}", device_id, group_id);
+        self.rotate_group_key(group_id).await
+    }
+
+    /// 将设备从本地担任管理员的所有群组中移除并轮换这些群组的密钥，返回已移除该设备的群组
+    ///
+    /// 本地不是管理员的群组无法轮换密钥，只记录警告，须由该群组的管理员移除
+    pub async fn remove_device_from_groups(&self, device_id: DeviceId) -> Vec<GroupId> {
+        let candidates: Vec<(GroupId, bool)> = self
+            .groups
+            .iter()
+            .filter(|group| group.members.contains_key(&device_id))
+            .map(|group| {
+                (
+                    group.id,
+                    self.require_admin(&group, "remove members").is_ok(),
+                )
+            })
+            .collect();
+
+        let mut removed = Vec::new();
+        for (group_id, is_admin) in candidates {
+            if !is_admin {
+                log::warn!(
+                    "Device {} stays in group {} until its admin removes it",
+                    device_id,
+                    group_id
+                );
+                continue;
+            }
+            if let Err(e) = self.remove_member(group_id, device_id).await {
+                log::warn!(
+                    "Failed to remove device {} from group {}: {}",
+                    device_id,
+                    group_id,
+                    e
+                );
+            }
+            let still_member = self
+                .groups
+                .get(&group_id)
+                .is_some_and(|group| group.members.contains_key(&device_id));
+            if !still_member {
+                removed.push(group_id);
+            }
+        }
+        removed
+    }
+
+    /// 向指定设备发送群组邀请
+    ///
+    /// 邀请属于群组控制消息，以明文点对点发送，受邀设备无需持有群组密钥即可处理
+    pub async fn send_invite(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
+        let name = self
+            .groups
+            .get(&group_id)
+            .map(|g| g.name.clone())
+            .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+
+        let mut message = Message::new(
+            self.local_device_id,
+            device_id,
+            MessagePayload::GroupInvite { group_id, name },
+        );
+        message.group_id = Some(group_id);
+        self.sign_message(&mut message);
+
+        let channel = self.router.select_channel(&message).await?;
+        self.router.send_through(channel.as_ref(), message).await?;
+
+        log::info!("Sent invite for group {} to device {}", group_id, device_id);
+        Ok(())
+    }
+
+    /// 向群组管理员申请加入群组
+    pub async fn request_join(&self, group_id: GroupId, admin: DeviceId) -> Result<()> {
+        let mut message = Message::new(
+            self.local_device_id,
+            admin,
+            MessagePayload::JoinRequest { group_id },
+        );
+        message.group_id = Some(group_id);
+        self.sign_message(&mut message);
+
+        let channel = self.router.select_channel(&message).await?;
+        self.router.send_through(channel.as_ref(), message).await?;
+
+        log::info!("Requested to join group {} via admin {}", group_id, admin);
+        Ok(())
+    }
+
+    /// 批准入群申请：添加成员、发送邀请并轮换群组密钥
+    ///
+    /// 密钥轮换保证新成员无法解密加入之前的群组消息
+    pub async fn approve_join(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
+        if self
+            .pending_join_requests
+            .remove(&(group_id, device_id))
+            .is_none()
+        {
+            return Err(XLinkError::invalid_state(
+                "approve_join",
+                "No pending join request for device",
+                file!(),
+            ));
+        }
+
+        self.add_member(group_id, device_id).await?;
+        if let Err(e) = self.send_invite(group_id, device_id).await {
+            log::warn!(
+                "Failed to send invite for group {} to approved device {}: {}",
+                group_id,
+                device_id,
+                e
+            );
+        }
+        self.rotate_group_key(group_id).await?;
+
+        log::info!(
+            "Approved join request of {} for group {}",
+            device_id,
+            group_id
+        );
+        Ok(())
+    }
+
+    /// 拒绝入群申请
+    pub fn deny_join(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
+        self.pending_join_requests
+            .remove(&(group_id, device_id))
+            .map(|_| {
+                log::info!(
+                    "Denied join request of {} for group {}",
+                    device_id,
+                    group_id
+                )
+            })
+            .ok_or_else(|| {
+                XLinkError::invalid_state(
+                    "deny_join",
+                    "No pending join request for device",
+                    file!(),
+                )
+            })
+    }
+
+    /// 列出群组中待审批的入群申请
+    pub fn pending_join_requests(&self, group_id: GroupId) -> Vec<DeviceId> {
+        self.pending_join_requests
+            .iter()
+            .filter(|entry| entry.key().0 == group_id)
+            .map(|entry| entry.key().1)
+            .collect()
+    }
+
+    /// 注册群组事件处理器
+    pub fn register_event_handler(&self, handler: GroupEventHandler) {
+        self.event_handlers.lock().push(handler);
+    }
+
+    fn emit_event(&self, event: GroupEvent) {
+        for handler in self.event_handlers.lock().iter() {
+            handler(event.clone());
+        }
+    }
+
+    /// 要求本地设备是群组管理员：非成员返回 `not_group_member`，普通成员返回 `group_permission_denied`
+    fn require_admin(&self, group: &Group, action: &str) -> Result<()> {
+        match group.members.get(&self.local_device_id) {
+            Some(member) if member.role == MemberRole::Admin => Ok(()),
+            Some(_) => Err(XLinkError::group_permission_denied(
+                group.id.to_string(),
+                self.local_device_id.to_string(),
+                action.to_string(),
+                file!(),
+            )),
+            None => Err(XLinkError::not_group_member(
+                group.id.to_string(),
+                self.local_device_id.to_string(),
+                file!(),
+            )),
+        }
+    }
+
+    /// 将本地设备的管理员身份移交给另一名成员，本地设备随即降为普通成员
+    ///
+    /// 升降级在同一次群组写锁内完成，群组始终至少有一名管理员
+    pub fn transfer_admin(&self, group_id: GroupId, to: DeviceId) -> Result<()> {
+        let mut group = self
+            .groups
+            .get_mut(&group_id)
+            .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+        self.require_admin(&group, "transfer admin")?;
+        if to == self.local_device_id {
+            return Ok(());
+        }
+
+        let Some(new_admin) = group.members.get_mut(&to) else {
+            return Err(XLinkError::not_group_member(
+                group_id.to_string(),
+                to.to_string(),
+                file!(),
+            ));
+        };
+        new_admin.role = MemberRole::Admin;
+        if let Some(old_admin) = group.members.get_mut(&self.local_device_id) {
+            old_admin.role = MemberRole::Member;
+        }
 
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_else(|_| Duration::from_secs(0))
-            .as_secs();
+        log::info!("Transferred admin of group {} to {}", group_id, to);
+        Ok(())
+    }
 
-        // 注册新成员公钥到 TreeKEM (如果已有)
-        if self.treekem_engine.get_device_public_key(device_id).is_ok() {
-            self.treekem_engine.add_member(group_id, device_id)?;
-        } else {
-            use rand::rngs::OsRng;
-            use x25519_dalek::StaticSecret;
-            let secret = StaticSecret::random_from_rng(OsRng);
-            let public = PublicKey::from(&secret);
-            self.treekem_engine.register_device_key(device_id, public);
-            self.treekem_engine.add_member(group_id, device_id)?;
+    /// 校验控制消息携带发送方有效的身份签名
+    ///
+    /// 控制消息不经群组密钥加密，签名是确认其确实来自 `message.sender` 的唯一依据
+    fn verify_control_signature(&self, message: &Message) -> Result<()> {
+        let Some(signature) = message.signature.as_deref() else {
+            return Err(XLinkError::signature_verification_failed(
+                "Ed25519",
+                "missing identity signature on group control message",
+                file!(),
+            ));
+        };
+        let data = message.signing_bytes();
+        let pinned = self.identity_keys.read().get(&message.sender).map(|k| *k);
+        match pinned {
+            Some(key) => CryptoEngine::verify_with_key(&key, &data, signature),
+            None => match self.signer.read().clone() {
+                Some(engine) => engine.verify(&message.sender, &data, signature),
+                None => Err(XLinkError::signature_verification_failed(
+                    "Ed25519",
+                    "no identity key to verify group control message",
+                    file!(),
+                )),
+            },
         }
+    }
 
-        group.members.insert(
-            device_id,
-            GroupMember {
-                device_id,
-                role: MemberRole::Member,
-                joined_at: now,
-                last_seen: now,
-                status: MemberStatus::Online,
-            },
-        );
+    /// 发送方是否为群组成员；`admin_only` 时要求为管理员
+    fn is_member(&self, group_id: GroupId, device_id: DeviceId, admin_only: bool) -> bool {
+        self.groups.get(&group_id).is_some_and(|group| {
+            group
+                .members
+                .get(&device_id)
+                .is_some_and(|member| !admin_only || member.role == MemberRole::Admin)
+        })
+    }
 
-        log::info!("Added member {} to group {}", device_id, group_id);
-        Ok(())
+    fn is_local_admin(&self, group_id: GroupId) -> bool {
+        self.is_member(group_id, self.local_device_id, true)
     }
 
     pub async fn join_group(&self, group: Group) -> Result<()> {
@@ -258,13 +1284,40 @@ impl GroupManager {
         Ok(())
     }
 
+    /// 离开群组
+    ///
+    /// 本地设备是唯一管理员且群组中仍有其他成员时拒绝离开，需先调用 `transfer_admin`
     pub async fn leave_group(&self, group_id: GroupId) -> Result<()> {
+        if let Some(group) = self.groups.get(&group_id) {
+            let is_admin = |m: &GroupMember| m.role == MemberRole::Admin;
+            let local_is_admin = group
+                .members
+                .get(&self.local_device_id)
+                .is_some_and(is_admin);
+            let other_admins = group
+                .members
+                .values()
+                .filter(|m| m.device_id != self.local_device_id && is_admin(m))
+                .count();
+            if local_is_admin && other_admins == 0 && group.members.len() > 1 {
+                return Err(XLinkError::group_permission_denied(
+                    group_id.to_string(),
+                    self.local_device_id.to_string(),
+                    "leave as the only admin".to_string(),
+                    file!(),
+                ));
+            }
+        }
+
         // 从 TreeKEM 群组中移除
         self.treekem_engine
             .remove_member(group_id, self.local_device_id)?;
 
-        // 从本地群组列表中移除
+        // 从本地群组列表中移除，在线状态订阅与自动轮换随之结束
         self.groups.remove(&group_id);
+        self.presence_subscribers.remove(&group_id);
+        self.rotation_schedules.remove(&group_id);
+        self.epoch_gates.remove(&group_id);
 
         log::info!("Left group {}", group_id);
         Ok(())
@@ -279,6 +1332,9 @@ impl GroupManager {
         let group_keys: Vec<_> = self.groups.iter().map(|entry| *entry.key()).collect();
         for group_id in group_keys {
             self.groups.remove(&group_id);
+            self.presence_subscribers.remove(&group_id);
+            self.rotation_schedules.remove(&group_id);
+            self.epoch_gates.remove(&group_id);
         }
 
         let pending_keys: Vec<_> = self.pending_acks.iter().map(|entry| *entry.key()).collect();
@@ -295,6 +1351,17 @@ impl GroupManager {
             self.processed_invites.remove(&key);
         }
 
+        crate::utils::remove_keys(
+            &self.inflight_broadcasts,
+            crate::utils::get_all_keys(&self.inflight_broadcasts),
+        );
+        crate::utils::remove_keys(
+            &self.pending_join_requests,
+            crate::utils::get_all_keys(&self.pending_join_requests),
+        );
+        self.unknown_group_buffer.clear();
+        self.unknown_group_join_requests.clear();
+
         // TreeKemEngine 可能也需要清理
         self.treekem_engine.clear_keys();
 
@@ -307,29 +1374,152 @@ impl GroupManager {
         );
     }
 
+    /// 向全体成员广播；被扇出策略延后的成员的消息被丢弃，需要投递时使用 [`Self::broadcast_to`]
     pub async fn broadcast(&self, group_id: GroupId, payload: MessagePayload) -> Result<Uuid> {
-        let group = self
-            .groups
-            .get(&group_id)
-            .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+        Ok(self
+            .broadcast_filtered(group_id, payload, &BroadcastTargets::All)
+            .await?
+            .message_id)
+    }
+
+    /// 向部分成员广播，仍使用群组密钥加密，ACK 追踪与 Mesh 中继只针对选中的成员
+    ///
+    /// `Devices` 中含有非群组成员时返回 `not_group_member`，不发送任何消息
+    pub async fn broadcast_to(
+        &self,
+        group_id: GroupId,
+        payload: MessagePayload,
+        targets: BroadcastTargets,
+    ) -> Result<OnlineBroadcast> {
+        self.broadcast_filtered(group_id, payload, &targets).await
+    }
+
+    /// 只向在线成员广播
+    ///
+    /// 状态不是 `Online`、或已知通道全部不可用的成员被跳过，不占用发送与 ACK 追踪名额；
+    /// 发给他们的消息放在返回值的 `deferred` 中，由调用方放入待发送队列
+    pub async fn broadcast_to_online(
+        &self,
+        group_id: GroupId,
+        payload: MessagePayload,
+    ) -> Result<OnlineBroadcast> {
+        self.broadcast_filtered(group_id, payload, &BroadcastTargets::OnlineOnly)
+            .await
+    }
+
+    /// 成员当前是否可达：状态为在线，且不存在“已知通道全部不可用”的在线状态数据
+    fn is_member_online(&self, member: &GroupMember) -> bool {
+        let states = self
+            .router
+            .capability_manager()
+            .get_channel_states(&member.device_id);
+        member.status == MemberStatus::Online
+            && (states.is_empty() || states.iter().any(|(_, state)| state.available))
+    }
+
+    async fn broadcast_filtered(
+        &self,
+        group_id: GroupId,
+        payload: MessagePayload,
+        targets: &BroadcastTargets,
+    ) -> Result<OnlineBroadcast> {
+        // 取成员快照后立即释放群组表的读锁，扇出期间的 await 不会阻塞对群组表的写入
+        let members: Vec<(DeviceId, GroupMember)> = {
+            let group = self
+                .groups
+                .get(&group_id)
+                .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+
+            if let BroadcastTargets::Devices(devices) = targets {
+                if let Some(outsider) = devices.iter().find(|id| !group.members.contains_key(id)) {
+                    return Err(XLinkError::not_group_member(
+                        group_id.to_string(),
+                        outsider.to_string(),
+                        file!(),
+                    ));
+                }
+            }
+            group
+                .members
+                .iter()
+                .map(|(&id, member)| (id, member.clone()))
+                .collect()
+        };
 
         let message_id = Uuid::new_v4();
+        match self.dedup_broadcast(group_id, &payload, targets, message_id) {
+            BroadcastDedup::Untracked => {}
+            BroadcastDedup::Coalesced(mut outcome) => {
+                // 合并进来的调用方共享扇出结果；待发送消息只交给发起扇出的调用方入队一次
+                if let Ok(done) = outcome.wait_for(Option::is_some).await {
+                    if let Some(result) = done.clone() {
+                        log::info!(
+                            "Coalesced duplicate broadcast to group {} into an in-flight fan-out",
+                            group_id
+                        );
+                        return result.map(|broadcast| OnlineBroadcast {
+                            deferred: Vec::new(),
+                            ..broadcast
+                        });
+                    }
+                }
+                // 发起方被取消而未给出结果，本次广播自行扇出
+            }
+            BroadcastDedup::Leader { key, sender } => {
+                let _inflight = InflightBroadcast {
+                    inflight: &self.inflight_broadcasts,
+                    key,
+                    message_id,
+                };
+                let result = self
+                    .fan_out(group_id, members, payload, targets, message_id)
+                    .await;
+                sender.send_replace(Some(result.clone()));
+                return result;
+            }
+        }
+        self.fan_out(group_id, members, payload, targets, message_id)
+            .await
+    }
+
+    /// 加密负载并扇出给选中的成员
+    async fn fan_out(
+        &self,
+        group_id: GroupId,
+        members: Vec<(DeviceId, GroupMember)>,
+        payload: MessagePayload,
+        targets: &BroadcastTargets,
+        message_id: Uuid,
+    ) -> Result<OnlineBroadcast> {
         let mut successful_devices = HashSet::new();
         let mut failed_devices = HashSet::new();
 
-        // 使用 TreeKEM 加密消息
-        let encrypted_payload = match self
-            .treekem_engine
-            .encrypt_group_message(group_id, &payload)
-        {
-            Ok(encrypted) => encrypted,
-            Err(e) => {
-                log::error!("Failed to encrypt group message: {}", e);
-                return Err(XLinkError::encryption_failed(
-                    "TreeKEM",
-                    &e.to_string(),
-                    file!(),
-                ));
+        // 普通消息在整个扇出期间持有纪元闸门，期间开始的轮换等本次广播以旧纪元发完
+        let _epoch_guard = if payload.is_group_control() {
+            None
+        } else {
+            self.count_rotation_message(group_id);
+            Some(self.epoch_gate(group_id).read_owned().await)
+        };
+
+        // 使用 TreeKEM 加密消息（群组控制消息以明文发送，见 MessagePayload::is_group_control）
+        let encrypted_payload = if payload.is_group_control() {
+            payload
+        } else {
+            match self.treekem_engine.encrypt_group_message(
+                group_id,
+                self.local_device_id,
+                &payload,
+            ) {
+                Ok(encrypted) => encrypted,
+                Err(e) => {
+                    log::error!("Failed to encrypt group message: {}", e);
+                    return Err(XLinkError::encryption_failed(
+                        "TreeKEM",
+                        &e.to_string(),
+                        file!(),
+                    ));
+                }
             }
         };
 
@@ -337,9 +1527,15 @@ impl GroupManager {
         let mut nearby_members = Vec::new(); // 近场设备（BLE/WiFi Direct）
         let mut remote_members = Vec::new(); // 远程设备（Internet）
         let mut relay_candidates = Vec::new(); // 可作为中继的设备
+        let mut skipped = Vec::new(); // 离线而跳过的成员
 
-        for &member_id in group.members.keys() {
-            if member_id == self.local_device_id {
+        for (member_id, member) in &members {
+            let member_id = *member_id;
+            if member_id == self.local_device_id || !targets.includes(member_id, member) {
+                continue;
+            }
+            if *targets == BroadcastTargets::OnlineOnly && !self.is_member_online(member) {
+                skipped.push(member_id);
                 continue;
             }
 
@@ -358,11 +1554,28 @@ impl GroupManager {
 
         // IT-GRP-003: 混合拓扑广播 - 根据设备距离选择不同的通信通道
         // 近场设备通过 BLE/WiFi 直连，远程设备通过 ntfy 服务器
+        // 远程成员受扇出策略限制，延后的成员改为待发送消息，以节省计费流量
+        let (remote_members, fanout_deferred, avoided_cellular) =
+            self.apply_fanout_policy(remote_members);
+        if !fanout_deferred.is_empty() {
+            log::info!(
+                "Fanout policy deferred {} remote members of group {} to the pending queue",
+                fanout_deferred.len(),
+                group_id
+            );
+        }
+        if avoided_cellular > 0 {
+            let bytes = crate::router::selector::payload_size(&encrypted_payload) as u64;
+            self.router
+                .record_metered_avoided(bytes * avoided_cellular as u64);
+        }
 
-        // 并行发送消息给所有群组成员
-        let mut futures = FuturesUnordered::new();
+        // 并行发送消息给所有群组成员（并发数受 broadcast_concurrency 限制）
+        let mut sends = Vec::new();
         let router_clone = self.router.clone();
         let local_device_id = self.local_device_id;
+        let signer = self.signer.read().clone();
+        let sent_at = self.clock.now_unix_secs();
 
         // 合并所有成员到一个列表中处理
         let all_members: Vec<(DeviceId, bool)> = nearby_members
@@ -374,8 +1587,9 @@ impl GroupManager {
         for (member_id, is_nearby) in all_members {
             let router = router_clone.clone();
             let encrypted_payload = encrypted_payload.clone();
+            let signer = signer.clone();
 
-            futures.push(async move {
+            sends.push(async move {
                 let priority = if is_nearby {
                     MessagePriority::High
                 } else {
@@ -383,19 +1597,19 @@ impl GroupManager {
                 };
                 let require_ack = !is_nearby; // 远程设备需要ACK确认
 
-                let message = Message {
-                    id: message_id,
-                    sender: local_device_id,
-                    recipient: member_id,
-                    group_id: Some(group_id),
-                    payload: encrypted_payload.clone(),
-                    timestamp: SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap_or_else(|_| Duration::from_secs(0))
-                        .as_secs(),
+                let mut message = group_message(
+                    message_id,
+                    sent_at,
+                    local_device_id,
+                    member_id,
+                    group_id,
+                    encrypted_payload,
                     priority,
                     require_ack,
-                };
+                );
+                if let Some(signer) = &signer {
+                    signer.sign_message(&mut message);
+                }
 
                 // 选择通道并发送消息
                 match router.select_channel(&message).await {
@@ -410,7 +1624,7 @@ impl GroupManager {
 
                         let can_relay = is_nearby_channel; // 只有近场通道可以作为中继
 
-                        match channel.send(message).await {
+                        match router.send_through(channel.as_ref(), message).await {
                             Ok(_) => {
                                 if is_nearby {
                                     log::debug!(
@@ -462,6 +1676,8 @@ impl GroupManager {
             });
         }
 
+        let mut futures = stream::iter(sends).buffer_unordered(self.broadcast_concurrency());
+
         // 等待发送完成，并收集可作为中继节点的成员
         let mut available_relays = Vec::new(); // 可用的中继节点
         while let Some(result) = futures.next().await {
@@ -480,16 +1696,17 @@ impl GroupManager {
 
         // --- F4: Mesh 中继模式实现 ---
         // 如果有成员发送失败，且我们有可用的中继候选者，尝试请求中继
-        if !failed_devices.is_empty() && !available_relays.is_empty() {
+        let relay_targets: Vec<DeviceId> = failed_devices.iter().copied().collect();
+        if !relay_targets.is_empty() && !available_relays.is_empty() {
             log::info!(
-                "Attempting Mesh relay for {} failed devices via {} candidates",
-                failed_devices.len(),
+                "Attempting Mesh relay for {} devices via {} candidates",
+                relay_targets.len(),
                 available_relays.len()
             );
 
             // 模拟 Mesh 中继逻辑：请求已成功的节点转发消息
             // 在真实实现中，这需要定义一种新的 RelayRequest 消息类型
-            for &failed_id in &failed_devices {
+            for &failed_id in &relay_targets {
                 // 简化模拟：假设第一个中继候选者能帮我们触达
                 if let Some(&relay_id) = available_relays.first() {
                     log::info!(
@@ -532,7 +1749,42 @@ impl GroupManager {
             total_attempts
         );
 
-        Ok(message_id)
+        // 跳过的离线成员与被扇出策略延后的成员改为待发送消息，稍后再投递
+        let deferred = skipped
+            .iter()
+            .chain(fanout_deferred.iter())
+            .map(|&member_id| {
+                let mut message = group_message(
+                    message_id,
+                    sent_at,
+                    self.local_device_id,
+                    member_id,
+                    group_id,
+                    encrypted_payload.clone(),
+                    MessagePriority::Normal,
+                    true,
+                );
+                if let Some(signer) = &signer {
+                    signer.sign_message(&mut message);
+                }
+                message
+            })
+            .collect();
+        if !skipped.is_empty() {
+            log::info!(
+                "Broadcast message {} skipped {} offline members of group {}",
+                message_id,
+                skipped.len(),
+                group_id
+            );
+        }
+
+        Ok(OnlineBroadcast {
+            message_id,
+            skipped,
+            fanout_deferred,
+            deferred,
+        })
     }
 
     /// 标记设备为成功接收（收到ACK）
@@ -571,8 +1823,21 @@ impl GroupManager {
         })
     }
 
-    /// 执行群组密钥更新（前向保密性）
+    /// 执行群组密钥更新（前向保密性），仅限管理员
     pub async fn rotate_group_key(&self, group_id: GroupId) -> Result<()> {
+        {
+            let group = self
+                .groups
+                .get(&group_id)
+                .ok_or_else(|| XLinkError::group_not_found(group_id.to_string(), file!()))?;
+            self.require_admin(&group, "rotate the group key")?;
+        }
+        // 同一群组的轮换串行执行，并等待进行中的广播发完；
+        // 密钥更新消息是控制消息，不经过闸门，发出前新纪元的广播不会开始
+        let _epoch_guard = self.epoch_gate(group_id).write_owned().await;
+        if let Some(schedule) = self.rotation_schedules.get(&group_id) {
+            schedule.messages.store(0, Ordering::Relaxed);
+        }
         match self
             .treekem_engine
             .update_group_key(group_id, self.local_device_id)
@@ -598,6 +1863,26 @@ impl GroupManager {
                 };
 
                 self.broadcast(group_id, update_payload).await?;
+
+                // 新纪元的密钥经一对一会话逐个分发给其余成员
+                let members: Vec<DeviceId> = self
+                    .groups
+                    .get(&group_id)
+                    .map(|group| group.members.keys().copied().collect())
+                    .unwrap_or_default();
+                for member_id in members {
+                    if member_id == self.local_device_id {
+                        continue;
+                    }
+                    if let Err(e) = self.deliver_group_key(group_id, member_id).await {
+                        log::warn!(
+                            "Failed to deliver key of group {} to {}: {}",
+                            group_id,
+                            member_id,
+                            e
+                        );
+                    }
+                }
                 Ok(())
             }
             Err(e) => {
@@ -611,23 +1896,105 @@ impl GroupManager {
         }
     }
 
+    /// 将群组当前纪元的密钥以与 `device_id` 的一对一会话密钥加密后发给它
+    ///
+    /// 需要已设置签名引擎（见 `set_message_signer`）且与该设备已建立会话
+    pub async fn deliver_group_key(&self, group_id: GroupId, device_id: DeviceId) -> Result<()> {
+        let Some(engine) = self.signer.read().clone() else {
+            return Err(XLinkError::invalid_state(
+                "deliver_group_key",
+                "No crypto engine to seal the group key",
+                file!(),
+            ));
+        };
+        let (epoch, secret) = self.treekem_engine.group_secret(group_id)?;
+        let context = group_key_context(self.local_device_id, device_id, group_id, epoch);
+        let sealed_secret = engine.encrypt(&device_id, &secret, &context)?;
+
+        let mut message = Message::new(
+            self.local_device_id,
+            device_id,
+            MessagePayload::GroupKeyDelivery {
+                group_id,
+                epoch,
+                sealed_secret,
+            },
+        );
+        message.group_id = Some(group_id);
+        engine.sign_message(&mut message);
+
+        let channel = self.router.select_channel(&message).await?;
+        self.router.send_through(channel.as_ref(), message).await?;
+        log::info!(
+            "Delivered key of group {} at epoch {} to {}",
+            group_id,
+            epoch,
+            device_id
+        );
+        Ok(())
+    }
+
+    /// 解开管理员分发的群组密钥并安装
+    fn install_delivered_key(
+        &self,
+        message: &Message,
+        group_id: GroupId,
+        epoch: u64,
+        sealed_secret: &[u8],
+    ) -> Result<()> {
+        if !self.is_member(group_id, message.sender, true) {
+            return Err(XLinkError::invalid_state(
+                "install_delivered_key",
+                "Group key delivered by a device that is not a group admin",
+                file!(),
+            ));
+        }
+        let Some(engine) = self.signer.read().clone() else {
+            return Err(XLinkError::invalid_state(
+                "install_delivered_key",
+                "No crypto engine to open the group key",
+                file!(),
+            ));
+        };
+        let context = group_key_context(message.sender, self.local_device_id, group_id, epoch);
+        let secret: [u8; 32] = engine
+            .decrypt(&message.sender, sealed_secret, &context)?
+            .try_into()
+            .map_err(|_| {
+                XLinkError::invalid_ciphertext("Group key must be 32 bytes".to_string(), file!())
+            })?;
+        if self
+            .treekem_engine
+            .install_group_secret(group_id, epoch, secret)
+        {
+            log::info!(
+                "Installed key of group {} at epoch {} from {}",
+                group_id,
+                epoch,
+                message.sender
+            );
+        }
+        Ok(())
+    }
+
     pub fn encrypt_group_message(
         &self,
         group_id: GroupId,
         payload: &MessagePayload,
     ) -> Result<MessagePayload> {
         self.treekem_engine
-            .encrypt_group_message(group_id, payload)
+            .encrypt_group_message(group_id, self.local_device_id, payload)
             .map_err(|e| XLinkError::encryption_failed("TreeKEM", &e.to_string(), file!()))
     }
 
     pub fn decrypt_group_message(
         &self,
         group_id: GroupId,
+        sender: DeviceId,
         encrypted_payload: &MessagePayload,
     ) -> Result<MessagePayload> {
         self.treekem_engine
-            .decrypt_group_message(group_id, encrypted_payload)
+            .decrypt_group_message(group_id, sender, encrypted_payload)
             .map_err(|e| XLinkError::encryption_failed("TreeKEM", &e.to_string(), file!()))
     }
 
@@ -745,13 +2112,7 @@ impl GroupManager {
 
     /// 清理过期的邀请记录，防止内存泄漏
     pub fn cleanup_expired_invites(&self, max_age_hours: u64) {
-        let current_time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_err(|_| {
-                log::warn!("SystemTime before UNIX_EPOCH, using 0");
-            })
-            .unwrap_or(std::time::Duration::ZERO)
-            .as_secs();
+        let current_time = self.clock.now_unix_secs();
         let max_age_seconds = max_age_hours * 3600;
 
         let mut removed_count = 0;
@@ -768,55 +2129,145 @@ impl GroupManager {
         }
     }
 
-    /// 更新群组成员状态
+    /// 更新群组成员状态，状态发生变化时通知在线状态订阅者
+    ///
+    /// 标记为 Online 时同时刷新成员的 `last_seen`
     pub async fn update_member_state(
         &self,
         group_id: GroupId,
         device_id: DeviceId,
         status: MemberStatus,
     ) -> Result<()> {
-        if let Some(mut group) = self.groups.get_mut(&group_id) {
-            if let Some(member) = group.members.get_mut(&device_id) {
-                // 更新成员状态
-                match status {
-                    MemberStatus::Online => {
-                        member.status = MemberStatus::Online;
-                        member.last_seen = SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
-                    }
-                    MemberStatus::Offline => {
-                        member.status = MemberStatus::Offline;
-                    }
-                    MemberStatus::Away => {
-                        member.status = MemberStatus::Away;
-                    }
-                    MemberStatus::Busy => {
-                        member.status = MemberStatus::Busy;
-                    }
-                }
+        if let Some(event) = self.set_member_status(group_id, device_id, status) {
+            log::info!(
+                "Updated member {} state in group {}: {:?}",
+                device_id,
+                group_id,
+                status
+            );
+            self.notify_group_state_change(event);
+        }
+        Ok(())
+    }
 
-                // 更新成员的最后活跃时间、状态等信息
-                log::info!(
-                    "Updated member {} state in group {}: {:?}",
-                    device_id,
-                    group_id,
-                    status
-                );
+    /// 在设备所在的全部群组中更新其状态，由心跳在收到 Ping/Pong 或判定超时时调用
+    pub fn set_member_presence(&self, device_id: DeviceId, status: MemberStatus) {
+        let group_ids: Vec<GroupId> = self
+            .groups
+            .iter()
+            .filter(|group| group.members.contains_key(&device_id))
+            .map(|group| group.id)
+            .collect();
+        for group_id in group_ids {
+            if let Some(event) = self.set_member_status(group_id, device_id, status) {
+                self.notify_group_state_change(event);
             }
         }
+    }
 
-        // 触发状态同步事件
-        self.notify_group_state_change(group_id).await;
-        Ok(())
+    /// 按 [`PresenceConfig`] 将长时间未活跃的成员降为 Away 或 Offline
+    ///
+    /// Busy 由应用设置，只会因超过 `offline_after` 变为 Offline；本地设备不参与判定
+    pub fn refresh_presence(&self) {
+        let config = *self.presence_config.read();
+        let now = self.clock.now_unix_secs();
+        let mut events = Vec::new();
+        for mut group in self.groups.iter_mut() {
+            let group_id = group.id;
+            for member in group.members.values_mut() {
+                if member.device_id == self.local_device_id {
+                    continue;
+                }
+                let idle = Duration::from_secs(now.saturating_sub(member.last_seen));
+                let status = if idle >= config.offline_after {
+                    MemberStatus::Offline
+                } else if idle >= config.away_after && member.status == MemberStatus::Online {
+                    MemberStatus::Away
+                } else {
+                    continue;
+                };
+                if member.status != status {
+                    events.push(PresenceEvent {
+                        group_id,
+                        device_id: member.device_id,
+                        previous: member.status,
+                        status,
+                    });
+                    member.status = status;
+                }
+            }
+        }
+        for event in events {
+            log::info!(
+                "Member {} in group {} is now {:?} (inactive)",
+                event.device_id,
+                event.group_id,
+                event.status
+            );
+            self.notify_group_state_change(event);
+        }
+    }
+
+    /// 设置在线状态判定阈值
+    pub fn set_presence_config(&self, config: PresenceConfig) {
+        *self.presence_config.write() = config;
+    }
+
+    /// 当前的在线状态判定阈值
+    pub fn presence_config(&self) -> PresenceConfig {
+        *self.presence_config.read()
+    }
+
+    /// 订阅群组成员的在线状态变化，群组不存在时返回 `group_not_found`
+    pub fn subscribe_presence(&self, group_id: GroupId) -> Result<mpsc::Receiver<PresenceEvent>> {
+        if !self.groups.contains_key(&group_id) {
+            return Err(XLinkError::group_not_found(group_id.to_string(), file!()));
+        }
+        let (tx, rx) = mpsc::channel(PRESENCE_CHANNEL_CAPACITY);
+        self.presence_subscribers
+            .entry(group_id)
+            .or_default()
+            .push(tx);
+        Ok(rx)
+    }
+
+    /// 修改成员状态，状态确有变化时返回对应的事件
+    fn set_member_status(
+        &self,
+        group_id: GroupId,
+        device_id: DeviceId,
+        status: MemberStatus,
+    ) -> Option<PresenceEvent> {
+        let mut group = self.groups.get_mut(&group_id)?;
+        let member = group.members.get_mut(&device_id)?;
+        if status == MemberStatus::Online {
+            member.last_seen = self.clock.now_unix_secs();
+        }
+        let previous = std::mem::replace(&mut member.status, status);
+        (previous != status).then_some(PresenceEvent {
+            group_id,
+            device_id,
+            previous,
+            status,
+        })
     }
 
-    /// 通知群组状态变更
-    pub async fn notify_group_state_change(&self, group_id: GroupId) {
-        log::info!("Group {} state changed", group_id);
-        // TODO: 实现状态变更通知逻辑
-        // 这里可以发送通知给其他成员，或者触发事件监听器
+    /// 向群组的在线状态订阅者投递状态变化，已关闭的订阅被移除
+    pub fn notify_group_state_change(&self, event: PresenceEvent) {
+        let Some(mut subscribers) = self.presence_subscribers.get_mut(&event.group_id) else {
+            return;
+        };
+        subscribers.retain(|tx| match tx.try_send(event.clone()) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                log::warn!(
+                    "Presence subscriber for group {} is full, dropping event",
+                    event.group_id
+                );
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        });
     }
 
     /// 大规模群组性能优化 - 分层广播
@@ -859,14 +2310,8 @@ impl GroupManager {
                     GroupMember {
                         device_id: member_id,
                         role: MemberRole::Member,
-                        joined_at: SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
-                        last_seen: SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
+                        joined_at: self.clock.now_unix_secs(),
+                        last_seen: self.clock.now_unix_secs(),
                         status: MemberStatus::Online,
                     },
                 );
@@ -877,10 +2322,7 @@ impl GroupManager {
                 id: sub_group_id,
                 name: format!("{}_sub_{}", group.name, sub_group_id),
                 members: sub_group_members,
-                created_at: SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                created_at: self.clock.now_unix_secs(),
             };
 
             self.groups.insert(sub_group_id, sub_group);
@@ -903,28 +2345,46 @@ impl GroupManager {
     pub async fn handle_incoming_group_message(&self, message: &Message) -> Result<()> {
         log::info!("Handling group message: {:?}", message.id);
         if let Some(group_id) = message.group_id {
-            // 首先尝试解密消息
-            let decrypted_payload = match self
-                .treekem_engine
-                .decrypt_group_message(group_id, &message.payload)
-            {
-                Ok(decrypted) => decrypted,
-                Err(e) => {
+            if !message.payload.is_group_control() && !self.groups.contains_key(&group_id) {
+                return self.handle_unknown_group_message(group_id, message).await;
+            }
+
+            // 控制消息以明文传输，须带有发送方有效的身份签名；其余消息需要先解密
+            let decrypted_payload = if message.payload.is_group_control() {
+                if let Err(e) = self.verify_control_signature(message) {
                     log::warn!(
-                        "Failed to decrypt group message from {} in group {}: {}",
+                        "Rejecting group control message {} from {}: {}",
+                        message.id,
                         message.sender,
-                        group_id,
                         e
                     );
-                    // 尝试反序列化原始payload
-                    match &message.payload {
-                        MessagePayload::Binary(data) => {
-                            match serde_json::from_slice::<MessagePayload>(data) {
-                                Ok(payload) => payload,
-                                Err(_) => message.payload.clone(), // 回退到原始payload
+                    return Err(e);
+                }
+                message.payload.clone()
+            } else {
+                match self.treekem_engine.decrypt_group_message(
+                    group_id,
+                    message.sender,
+                    &message.payload,
+                ) {
+                    Ok(decrypted) => decrypted,
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to decrypt group message from {} in group {}: {}",
+                            message.sender,
+                            group_id,
+                            e
+                        );
+                        // 兼容旧版本：仅接受序列化后的控制消息，不允许借此绕过加密
+                        match &message.payload {
+                            MessagePayload::Binary(data) => {
+                                match serde_json::from_slice::<MessagePayload>(data) {
+                                    Ok(payload) if payload.is_group_control() => payload,
+                                    _ => message.payload.clone(), // 回退到原始payload
+                                }
                             }
+                            _ => message.payload.clone(), // 回退到原始payload
                         }
-                        _ => message.payload.clone(), // 回退到原始payload
                     }
                 }
             };
@@ -938,10 +2398,7 @@ impl GroupManager {
                         }
                         self.processed_invites.insert(group_id, message.timestamp);
 
-                        let now = SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
+                        let now = self.clock.now_unix_secs();
                         let mut members = HashMap::new();
                         members.insert(
                             message.sender,
@@ -991,19 +2448,75 @@ impl GroupManager {
                         }
                     }
                 }
+                MessagePayload::JoinRequest { .. } => {
+                    // 仅管理员处理申请，且已是成员的设备无需审批
+                    let already_member = self
+                        .groups
+                        .get(&group_id)
+                        .is_some_and(|g| g.members.contains_key(&message.sender));
+                    if !self.is_local_admin(group_id) || already_member {
+                        log::debug!(
+                            "Ignoring join request from {} for group {}",
+                            message.sender,
+                            group_id
+                        );
+                        return Ok(());
+                    }
+                    let is_new = self
+                        .pending_join_requests
+                        .insert((group_id, message.sender), message.timestamp)
+                        .is_none();
+                    if is_new {
+                        self.emit_event(GroupEvent::JoinRequested {
+                            group_id,
+                            device_id: message.sender,
+                        });
+                    }
+                }
                 MessagePayload::GroupAck {
-                    original_msg_id,
-                    responder,
+                    original_msg_id, ..
+                } => {
+                    // 处理群组ACK消息：确认方以经签名认证的发送方为准，不采信负载中的 responder
+                    if !self.is_member(group_id, message.sender, false) {
+                        log::warn!(
+                            "Ignoring group ack from non-member {} in group {}",
+                            message.sender,
+                            group_id
+                        );
+                        return Ok(());
+                    }
+                    self.handle_ack(*original_msg_id, message.sender).await;
+                }
+                MessagePayload::GroupKeyDelivery {
+                    group_id,
+                    epoch,
+                    sealed_secret,
                 } => {
-                    // 处理群组ACK消息
-                    self.handle_ack(*original_msg_id, *responder).await;
+                    if let Err(e) =
+                        self.install_delivered_key(message, *group_id, *epoch, sealed_secret)
+                    {
+                        log::warn!(
+                            "Rejected key of group {} from {}: {}",
+                            group_id,
+                            message.sender,
+                            e
+                        );
+                    }
                 }
                 MessagePayload::GroupKeyUpdate {
                     group_id,
                     epoch,
                     update_path,
                 } => {
-                    // 处理群组密钥更新消息
+                    // 处理群组密钥更新消息，仅接受该群组管理员发起的更新
+                    if !self.is_member(*group_id, message.sender, true) {
+                        log::warn!(
+                            "Ignoring key update of group {} from non-admin {}",
+                            group_id,
+                            message.sender
+                        );
+                        return Ok(());
+                    }
                     if let Err(e) = self
                         .handle_key_update(*group_id, *epoch, update_path.clone())
                         .await
@@ -1015,10 +2528,7 @@ impl GroupManager {
                     // 更新发送者的最后活跃时间
                     if let Some(mut group) = self.groups.get_mut(&group_id) {
                         if let Some(member) = group.members.get_mut(&message.sender) {
-                            member.last_seen = SystemTime::now()
-                                .duration_since(SystemTime::UNIX_EPOCH)
-                                .unwrap()
-                                .as_secs();
+                            member.last_seen = self.clock.now_unix_secs();
                             member.status = MemberStatus::Online;
                         }
                     }
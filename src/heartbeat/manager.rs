@@ -1,29 +1,199 @@
 use crate::capability::manager::CapabilityManager;
-use crate::core::types::{ChannelType, DeviceId, Message, MessagePayload, NetworkType};
+use crate::core::clock::{system_clock, Clock};
+use crate::core::error::Result;
+use crate::core::types::{
+    ChannelState, ChannelType, DeviceId, MemberStatus, Message, MessagePayload, PresenceHint,
+};
+use crate::group::manager::GroupManager;
 use crate::router::selector::Router;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
-// F6: 近场设备心跳间隔 1-5秒
-const NEAR_MIN_INTERVAL: Duration = Duration::from_secs(1);
-const NEAR_MAX_INTERVAL: Duration = Duration::from_secs(5);
+/// 默认最短心跳间隔：近期有失败的链路收紧到该值
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+/// 默认最长心跳间隔：稳定的低延迟链路放宽到该值
+pub const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(60);
 
-// F6: 远程设备心跳间隔 30-60秒，动态调整
-const REMOTE_MIN_INTERVAL: Duration = Duration::from_secs(30);
-const REMOTE_MAX_INTERVAL: Duration = Duration::from_secs(60);
+/// 对端静默超过最长间隔的该倍数后标记为离线
+const OFFLINE_AFTER_MAX_INTERVALS: u32 = 3;
 
-// F6: 3次心跳失败后标记为不可达
-const FAILURE_THRESHOLD: u32 = 3;
+/// RTT 达到该值（毫秒）时间隔收紧到最短间隔
+const RTT_TIGHTEN_MS: f32 = 1000.0;
 
-// F6: 信号强度阈值（dBm)
-const SIGNAL_STRENGTH_NEAR_THRESHOLD: i8 = -60; // -60dBm 以上认为是近场
+/// 心跳调度的基础 Tick，最短间隔更小时以最短间隔为准
+const BASE_TICK: Duration = Duration::from_secs(1);
+
+/// 主动探测时每个通道发送的 Ping 数
+pub const PROBES_PER_CHANNEL: u32 = 3;
+
+/// 按需主动探测对端各通道的 RTT 与丢包率
+///
+/// 探测 Ping 直接经各通道发出而不经路由选择，Pong 由 [`HeartbeatManager::handle_heartbeat`] 回送。
+/// 探测期间不持有心跳管理器的锁，以免阻塞 Pong 的处理。
+#[derive(Clone)]
+pub struct PeerProber {
+    local_device_id: DeviceId,
+    router: Arc<Router>,
+    cap_manager: Arc<CapabilityManager>,
+    // 等待 Pong 的探测: 探测令牌 -> 收到 Pong 的时间
+    pending: Arc<DashMap<u64, oneshot::Sender<Instant>>>,
+    last_token: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PeerProber {
+    /// 探测对端在所有本地通道上的状态，更新能力管理器并返回最新状态
+    ///
+    /// 各通道并发探测，每个通道最多等待 `timeout`；未收到任何 Pong 的通道标记为不可用
+    pub async fn probe_peer(
+        &self,
+        device_id: DeviceId,
+        timeout: Duration,
+    ) -> Result<Vec<(ChannelType, ChannelState)>> {
+        let probes = self
+            .router
+            .get_channels()
+            .iter()
+            .map(|(channel_type, channel)| {
+                let channel = channel.clone();
+                async move {
+                    let state = self.probe_channel(device_id, channel, timeout).await;
+                    (*channel_type, state)
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut states = futures::future::join_all(probes).await;
+        states.sort_by_key(|(channel_type, _)| *channel_type as u8);
+
+        for (channel_type, state) in &states {
+            if !state.available {
+                self.cap_manager
+                    .record_channel_failure(device_id, *channel_type);
+            }
+            self.cap_manager
+                .update_channel_state(device_id, *channel_type, state.clone());
+        }
+        log::info!(
+            "Refreshed {} channel states for peer {}",
+            states.len(),
+            device_id
+        );
+        Ok(states)
+    }
+
+    async fn probe_channel(
+        &self,
+        device_id: DeviceId,
+        channel: Arc<dyn crate::core::traits::Channel>,
+        timeout: Duration,
+    ) -> ChannelState {
+        let channel_type = channel.channel_type();
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut waiting = Vec::new();
+        for _ in 0..PROBES_PER_CHANNEL {
+            let token = self.next_token();
+            let (tx, rx) = oneshot::channel();
+            self.pending.insert(token, tx);
+            let sent_at = Instant::now();
+            let mut ping = Message::new(
+                self.local_device_id,
+                device_id,
+                MessagePayload::Ping(token, None),
+            );
+            self.router.sign_message(&mut ping);
+            match tokio::time::timeout_at(deadline, channel.send(ping)).await {
+                Ok(Ok(())) => waiting.push((token, sent_at, rx)),
+                Ok(Err(e)) => {
+                    self.pending.remove(&token);
+                    log::debug!(
+                        "Probe over {:?} to {} failed: {}",
+                        channel_type,
+                        device_id,
+                        e
+                    );
+                }
+                Err(_) => {
+                    self.pending.remove(&token);
+                    break;
+                }
+            }
+        }
+
+        let mut rtts = Vec::new();
+        for (token, sent_at, rx) in waiting {
+            if let Ok(Ok(received_at)) = tokio::time::timeout_at(deadline, rx).await {
+                rtts.push(received_at.saturating_duration_since(sent_at).as_millis() as u32);
+            }
+            self.pending.remove(&token);
+        }
+
+        let mut state = self
+            .cap_manager
+            .get_channel_state(&device_id, &channel_type)
+            .unwrap_or_default();
+        state.packet_loss_rate = 1.0 - rtts.len() as f32 / PROBES_PER_CHANNEL as f32;
+        state.available = !rtts.is_empty();
+        if let (Some(min), Some(max)) = (rtts.iter().min(), rtts.iter().max()) {
+            state.rtt_ms = rtts.iter().sum::<u32>() / rtts.len() as u32;
+            state.jitter_ms = max - min;
+            state.failure_count = 0;
+            state.last_heartbeat = self.clock.now_unix_millis();
+        } else {
+            state.failure_count = state.failure_count.saturating_add(1);
+        }
+        state
+    }
+
+    /// 生成唯一的探测令牌，取值贴近毫秒时间戳，迟到的 Pong 仍可按普通心跳计算 RTT
+    fn next_token(&self) -> u64 {
+        let now = self.clock.now_unix_millis();
+        let mut last = self.last_token.load(Ordering::Relaxed);
+        loop {
+            let token = now.max(last + 1);
+            match self.last_token.compare_exchange_weak(
+                last,
+                token,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return token,
+                Err(current) => last = current,
+            }
+        }
+    }
+
+    /// 若 Pong 对应一个等待中的探测则完成该探测并返回 true
+    fn complete(&self, token: u64) -> bool {
+        match self.pending.remove(&token) {
+            Some((_, tx)) => {
+                let _ = tx.send(Instant::now());
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 pub struct HeartbeatManager {
     local_device_id: DeviceId,
     router: Arc<Router>,
     cap_manager: Arc<CapabilityManager>,
     running_task: Option<JoinHandle<()>>,
+    prober: PeerProber,
+    // 心跳 Ping 是否携带本地能力摘要
+    presence_hints: Arc<AtomicBool>,
+    // 自适应心跳间隔的上下限 (min, max)
+    interval_bounds: Arc<RwLock<(Duration, Duration)>>,
+    // 接收成员在线状态变化的群组管理器
+    group_manager: Arc<RwLock<Weak<GroupManager>>>,
+    // 心跳时间戳与静默超时的时间来源
+    clock: Arc<dyn Clock>,
 }
 
 impl HeartbeatManager {
@@ -32,14 +202,87 @@ impl HeartbeatManager {
         router: Arc<Router>,
         cap_manager: Arc<CapabilityManager>,
     ) -> Self {
+        Self::with_clock(local_device_id, router, cap_manager, system_clock())
+    }
+
+    /// 使用指定时钟创建，心跳时间戳与离线判定均以该时钟为准
+    pub fn with_clock(
+        local_device_id: DeviceId,
+        router: Arc<Router>,
+        cap_manager: Arc<CapabilityManager>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let prober = PeerProber {
+            local_device_id,
+            router: router.clone(),
+            cap_manager: cap_manager.clone(),
+            pending: Arc::new(DashMap::new()),
+            last_token: Arc::new(AtomicU64::new(0)),
+            clock: clock.clone(),
+        };
         Self {
             local_device_id,
             router,
             cap_manager,
             running_task: None,
+            prober,
+            presence_hints: Arc::new(AtomicBool::new(false)),
+            interval_bounds: Arc::new(RwLock::new((DEFAULT_MIN_INTERVAL, DEFAULT_MAX_INTERVAL))),
+            group_manager: Arc::new(RwLock::new(Weak::new())),
+            clock,
+        }
+    }
+
+    /// 设置接收成员在线状态的群组管理器
+    ///
+    /// 收到 Ping/Pong 的对端在其所在群组中标记为在线，静默超时的对端标记为离线；
+    /// 心跳任务每个周期还会按 `last_seen` 刷新群组成员状态
+    pub fn set_group_manager(&self, group_manager: Weak<GroupManager>) {
+        *self.group_manager.write() = group_manager;
+    }
+
+    fn update_presence(&self, device_id: DeviceId, status: MemberStatus) {
+        if let Some(gm) = self.group_manager.read().upgrade() {
+            gm.set_member_presence(device_id, status);
         }
     }
 
+    /// 设置自适应心跳间隔的上下限，对运行中的心跳任务立即生效
+    ///
+    /// `min` 大于 `max` 时两者互换；对端静默超过 `max` 的 3 倍即标记为离线
+    pub fn set_interval_bounds(&self, min: Duration, max: Duration) {
+        *self.interval_bounds.write() = (min.min(max), min.max(max));
+    }
+
+    /// 当前的心跳间隔上下限 (min, max)
+    pub fn interval_bounds(&self) -> (Duration, Duration) {
+        *self.interval_bounds.read()
+    }
+
+    /// 根据通道状态计算到下一次 Ping 的间隔
+    ///
+    /// 无失败时按 RTT 在上下限之间插值：RTT 越低越接近 `max`；每次近期失败使间隔减半，
+    /// 直至 `min`
+    pub fn next_interval(&self, state: &ChannelState) -> Duration {
+        let (min, max) = self.interval_bounds();
+        adaptive_interval(state, min, max)
+    }
+
+    /// 设置心跳 Ping 是否携带本地能力摘要，对运行中的心跳任务立即生效
+    pub fn set_presence_hints(&self, enabled: bool) {
+        self.presence_hints.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 心跳 Ping 是否携带本地能力摘要
+    pub fn presence_hints(&self) -> bool {
+        self.presence_hints.load(Ordering::Relaxed)
+    }
+
+    /// 获取主动探测器，可在释放心跳管理器的锁后使用
+    pub fn prober(&self) -> PeerProber {
+        self.prober.clone()
+    }
+
     pub fn start(&mut self) -> Option<JoinHandle<()>> {
         if self.running_task.is_some() {
             return None;
@@ -48,110 +291,84 @@ impl HeartbeatManager {
         let router = self.router.clone();
         let cap_manager = self.cap_manager.clone();
         let local_id = self.local_device_id;
+        let presence_hints = self.presence_hints.clone();
+
+        let interval_bounds = self.interval_bounds.clone();
+        let group_manager = self.group_manager.clone();
+        let clock = self.clock.clone();
 
         let task = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(1)); // 基础 Tick
+            // 设备 -> (开始跟踪的时间, 上一次发出 Ping 的时间)，单位毫秒
+            let mut tracked: HashMap<DeviceId, (u64, u64)> = HashMap::new();
 
             loop {
-                interval.tick().await;
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_else(|_| Duration::from_secs(0))
-                    .as_millis() as u64;
+                let (min_interval, max_interval) = *interval_bounds.read();
+                tokio::time::sleep(BASE_TICK.min(min_interval)).await;
+                let now = clock.now_unix_millis();
 
                 // 遍历所有已知设备
                 let devices = cap_manager.get_all_remote_devices();
+                tracked.retain(|device_id, _| devices.contains(device_id));
+                let gm = group_manager.read().upgrade();
+                if let Some(gm) = &gm {
+                    gm.refresh_presence();
+                }
 
                 for device_id in devices {
-                    // 获取该设备最优通道状态
                     // 这里简化逻辑：检查任意一个通道的状态
                     // 实际应遍历该设备所有通道
                     let channel_type = ChannelType::Internet; // 默认检查 Internet，实际应动态获取
 
-                    if let Some(mut state) =
-                        cap_manager.get_channel_state(&device_id, &channel_type)
-                    {
-                        // 1. 动态间隔判断 - 基于距离、信号强度和RTT
-                        let distance = state.distance_meters.unwrap_or(20.0);
-                        let signal_strength = state.signal_strength.unwrap_or(-100);
-                        let network_type = state.network_type;
-
-                        let required_interval = if distance <= 10.0
-                            || signal_strength >= SIGNAL_STRENGTH_NEAR_THRESHOLD
-                            || state.rtt_ms < 100
-                        {
-                            // 近场设备或低延迟：1-5秒动态调整
-                            let base_interval = NEAR_MIN_INTERVAL;
-                            let max_interval = NEAR_MAX_INTERVAL;
-
-                            // 根据距离调整间隔：1米以内 1秒，10米 5秒
-                            let distance_factor = (distance / 10.0).clamp(0.0, 1.0);
-
-                            // 综合考虑信号强度和RTT作为微调
-                            let signal_factor =
-                                ((signal_strength + 100) as f32 / 40.0).clamp(0.0, 1.0);
-                            let rtt_factor = (state.rtt_ms as f32 / 200.0).clamp(0.0, 1.0);
-
-                            let factor =
-                                distance_factor * 0.7 + signal_factor * 0.15 + rtt_factor * 0.15;
-                            let interval_ms = base_interval.as_millis() as f32
-                                + (max_interval.as_millis() as f32
-                                    - base_interval.as_millis() as f32)
-                                    * factor;
-
-                            Duration::from_millis(interval_ms as u64)
-                        } else {
-                            // 远程设备：30-60秒动态调整
-                            let base_interval = REMOTE_MIN_INTERVAL;
-                            let max_interval = REMOTE_MAX_INTERVAL;
-
-                            // 根据网络类型和RTT调整间隔
-                            let network_factor = match network_type {
-                                NetworkType::Bluetooth => 0.3, // BLE设备更频繁
-                                NetworkType::WiFi => 0.5,
-                                NetworkType::Ethernet => 1.0,
-                                _ => 0.8, // 其他网络类型使用默认值
-                            };
-
-                            let rtt_factor = (state.rtt_ms as f32 / 1000.0).clamp(0.0, 1.0);
-                            let factor = (network_factor + rtt_factor) / 2.0;
-
-                            let interval_ms = base_interval.as_millis() as f32
-                                + (max_interval.as_millis() as f32
-                                    - base_interval.as_millis() as f32)
-                                    * factor;
-
-                            Duration::from_millis(interval_ms as u64)
-                        };
-
-                        let elapsed = now.saturating_sub(state.last_heartbeat);
-                        if elapsed < required_interval.as_millis() as u64 {
-                            continue; // 还没到时间
+                    let Some(mut state) = cap_manager.get_channel_state(&device_id, &channel_type)
+                    else {
+                        continue;
+                    };
+                    let (since, last_ping) = tracked.entry(device_id).or_insert((now, 0));
+
+                    // 1. 静默超过最长间隔的 3 倍则标记为离线
+                    let offline_after =
+                        (max_interval * OFFLINE_AFTER_MAX_INTERVALS).as_millis() as u64;
+                    let silent_for = now.saturating_sub(state.last_heartbeat.max(*since));
+                    if state.available && silent_for >= offline_after {
+                        cap_manager.record_channel_failure(device_id, channel_type);
+                        state.available = false;
+                        log::warn!(
+                            "Device {} marked offline (silent for {}ms)",
+                            device_id,
+                            silent_for
+                        );
+                        cap_manager.update_channel_state(device_id, channel_type, state.clone());
+                        if let Some(gm) = &gm {
+                            gm.set_member_presence(device_id, MemberStatus::Offline);
                         }
+                    }
 
-                        // 2. 发送 Ping
-                        let payload = MessagePayload::Ping(now);
-                        let msg = Message::new(local_id, device_id, payload);
-
-                        // 乐观更新：增加失败计数，如果 Pong 回来会重置
-                        state.failure_count += 1;
-                        if state.failure_count >= FAILURE_THRESHOLD {
-                            state.available = false;
-                            log::warn!(
-                                "Device {} marked unavailable ({} failures)",
-                                device_id,
-                                state.failure_count
-                            );
-                        }
-                        cap_manager.update_channel_state(device_id, channel_type, state);
-
-                        let r_clone = router.clone();
-                        tokio::spawn(async move {
-                            if let Ok(ch) = r_clone.select_channel(&msg).await {
-                                let _ = ch.send(msg).await;
-                            }
-                        });
+                    // 2. 自适应间隔判断 - 基于 RTT 与近期失败
+                    let required_interval = adaptive_interval(&state, min_interval, max_interval);
+                    let elapsed = now.saturating_sub(state.last_heartbeat.max(*last_ping));
+                    if elapsed < required_interval.as_millis() as u64 {
+                        continue; // 还没到时间
                     }
+
+                    // 3. 发送 Ping
+                    let hint = presence_hints
+                        .load(Ordering::Relaxed)
+                        .then(|| PresenceHint::from_capabilities(&cap_manager.get_local_caps()));
+                    let payload = MessagePayload::Ping(now, hint);
+                    let mut msg = Message::new(local_id, device_id, payload);
+                    router.sign_message(&mut msg);
+                    *last_ping = now;
+
+                    // 乐观更新：增加失败计数，如果 Pong 回来会重置
+                    state.failure_count = state.failure_count.saturating_add(1);
+                    cap_manager.update_channel_state(device_id, channel_type, state);
+
+                    let r_clone = router.clone();
+                    tokio::spawn(async move {
+                        if let Ok(ch) = r_clone.select_channel(&msg).await {
+                            let _ = ch.send(msg).await;
+                        }
+                    });
                 }
             }
         });
@@ -167,26 +384,36 @@ impl HeartbeatManager {
     }
 
     pub async fn handle_heartbeat(&self, message: &Message) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        let now = self.clock.now_unix_millis();
+
+        match &message.payload {
+            MessagePayload::Ping(ts, hint) => {
+                self.update_presence(message.sender, MemberStatus::Online);
+                if let Some(hint) = hint {
+                    if self.cap_manager.apply_presence_hint(message.sender, hint) {
+                        log::debug!("Heartbeat updated capabilities of {}", message.sender);
+                    }
+                }
 
-        match message.payload {
-            MessagePayload::Ping(ts) => {
                 // 回复 Pong
-                let response = Message::new(
+                let mut response = Message::new(
                     self.local_device_id,
                     message.sender,
-                    MessagePayload::Pong(ts),
+                    MessagePayload::Pong(*ts),
                 );
+                self.router.sign_message(&mut response);
                 if let Ok(ch) = self.router.select_channel(&response).await {
                     let _ = ch.send(response).await;
                 }
             }
+            MessagePayload::Pong(ts) if self.prober.complete(*ts) => {
+                // 主动探测的 Pong，由探测方统计
+                self.update_presence(message.sender, MemberStatus::Online);
+            }
             MessagePayload::Pong(ts) => {
+                self.update_presence(message.sender, MemberStatus::Online);
                 // 计算 RTT
-                let rtt = (now.saturating_sub(ts)) as u32;
+                let rtt = (now.saturating_sub(*ts)) as u32;
                 // 假设通过 Internet 收到，实际应从 Message 元数据获取接收通道
                 let channel_type = ChannelType::Internet;
 
@@ -209,3 +436,15 @@ impl HeartbeatManager {
         }
     }
 }
+
+/// 按 RTT 与近期失败计算心跳间隔，结果总在 [min, max] 内
+fn adaptive_interval(state: &ChannelState, min: Duration, max: Duration) -> Duration {
+    if state.failure_count > 0 {
+        let halved = max
+            .checked_div(1u32 << state.failure_count.min(31))
+            .unwrap_or(min);
+        return halved.max(min);
+    }
+    let rtt_factor = (state.rtt_ms as f32 / RTT_TIGHTEN_MS).clamp(0.0, 1.0);
+    max.saturating_sub((max - min).mul_f32(rtt_factor)).max(min)
+}
@@ -5,6 +5,7 @@ pub mod crypto;
 pub mod router;
 pub mod storage;
 // 新增模块
+pub mod builder;
 pub mod discovery;
 pub mod ffi;
 pub mod group;
@@ -14,9 +15,13 @@ pub mod utils;
 
 use crate::capability::manager::CapabilityManager;
 use crate::core::error::Result;
+use crate::core::send_handle::{SendHandle, SendOutcome};
 use crate::core::traits::{Channel, MessageHandler, Storage};
-use crate::core::types::{ChannelType, DeviceCapabilities, DeviceId, Message, MessagePayload};
+use crate::core::types::{
+    ChannelType, DeviceCapabilities, DeviceId, Message, MessagePayload, MessagePriority,
+};
 use crate::crypto::engine::CryptoEngine;
+use crate::crypto::state_seal::{self, StateKeyProvider};
 use crate::router::selector::Router;
 
 // 引入新模块
@@ -26,7 +31,8 @@ use crate::discovery::manager::DiscoveryManager;
 use crate::discovery::manager_test::DiscoveryManager;
 use crate::group::manager::GroupManager;
 use crate::heartbeat::manager::HeartbeatManager;
-use crate::media::stream_manager::StreamManager;
+use crate::media::stream_manager::{ChunkIntegrity, StreamManager};
+use ed25519_dalek::VerifyingKey;
 use x25519_dalek::PublicKey;
 
 use async_trait::async_trait;
@@ -37,6 +43,9 @@ use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
+use zeroize::Zeroize;
+
+pub use crate::builder::XLinkBuilder;
 
 pub struct XLink {
     device_id: DeviceId,
@@ -51,17 +60,264 @@ pub struct XLink {
     discovery_manager: Arc<Mutex<DiscoveryManager>>,
     stream_manager: Arc<StreamManager>,
     cap_detector: Arc<Mutex<crate::capability::detector::LocalCapabilityDetector>>,
+    // 限流窗口与各管理器共用的时间来源
+    clock: Arc<dyn crate::core::clock::Clock>,
 
-    rate_limiter: Arc<DashMap<DeviceId, (Instant, u32)>>,
+    rate_limiter: Arc<DashMap<(DeviceId, MessagePriority), (Instant, u32)>>,
+    rate_limits: Arc<parking_lot::RwLock<crate::core::types::PriorityRateLimits>>,
+    rate_limit_config: Arc<parking_lot::RwLock<crate::core::types::RateLimitConfig>>,
+    // 本机跨优先级的出站总量窗口
+    send_rate_window: Arc<crate::core::rate_window::SendRateWindow>,
+    // 按对端限制在途发送数
+    send_slots: Arc<DashMap<DeviceId, Arc<tokio::sync::Semaphore>>>,
+    send_concurrency: Arc<parking_lot::RwLock<crate::core::types::PeerSendConcurrency>>,
     metrics: Arc<crate::core::metrics::MetricsCollector>,
+    metrics_config: Arc<parking_lot::RwLock<crate::core::types::MetricsConfig>>,
+    // 发送、接收与存储过程中产生或捕获的错误统计，只在出错时加锁
+    error_stats: Arc<parking_lot::Mutex<crate::core::error::ErrorStatistics>>,
+    // 计量网络的累计用量，跨重启保留
+    traffic_budget: Arc<crate::core::budget::TrafficBudget>,
+    audit_query: Arc<parking_lot::RwLock<crate::core::types::AuditQueryConfig>>,
     receive_tasks: Arc<DashMap<ChannelType, JoinHandle<()>>>,
     background_tasks: Arc<DashMap<String, JoinHandle<()>>>,
     app_rx: Arc<Mutex<mpsc::Receiver<Message>>>,
     app_tx: mpsc::Sender<Message>,
+    app_queue: crate::core::types::AppQueueConfig,
     compliance: Arc<crate::core::types::ComplianceConfig>,
-    plugins: Arc<DashMap<String, Arc<dyn crate::core::traits::Plugin>>>,
+    plugins: Plugins,
+    // 有序交付：发送序号（按接收方）与接收端重排缓冲（按发送方）
+    send_sequences: Arc<DashMap<DeviceId, u64>>,
+    // 本实例的序号纪元（启动时的 Unix 毫秒），接收端据此识别发送方重启后重新开始的序号
+    sequence_epoch: u64,
+    reorder_buffers: Arc<DashMap<DeviceId, crate::core::ordering::ReorderBuffer>>,
+    dedup: Arc<parking_lot::Mutex<crate::core::dedup::DedupCache>>,
+    clock_skew: Arc<parking_lot::RwLock<crate::core::types::ClockSkewConfig>>,
+    message_age: Arc<parking_lot::RwLock<crate::core::types::MessageAgeConfig>>,
+    reorder_config: Arc<parking_lot::RwLock<crate::core::types::ReorderBufferConfig>>,
+    stream_delivery: Arc<parking_lot::RwLock<crate::core::types::StreamDeliveryConfig>>,
+    routing_config: Arc<parking_lot::RwLock<crate::core::types::RoutingConfig>>,
+    group_key_config: Arc<parking_lot::RwLock<crate::core::types::GroupKeyConfig>>,
+    send_retry: Arc<parking_lot::RwLock<crate::core::types::SendRetryConfig>>,
+    reconnect_config: Arc<parking_lot::RwLock<crate::core::types::ReconnectConfig>>,
+    // 发送时发现通道断开后通知对应的通道监管任务重连
+    channel_disconnects: Arc<DashMap<ChannelType, Arc<tokio::sync::Notify>>>,
+    events: crate::core::events::EventBus,
+    journal: SharedJournal,
+    pending_requests: PendingRequests,
+    pending_replies: PendingReplies,
+    pending_acks: PendingAcks,
+    receive_pool: SharedReceivePool,
+    receive_pipeline: Arc<parking_lot::RwLock<crate::core::types::ReceivePipelineConfig>>,
+    topic_subscribers: TopicSubscribers,
+    battery_policy: Arc<parking_lot::RwLock<crate::core::types::BatteryPolicy>>,
+    channel_warmup: Arc<parking_lot::RwLock<crate::core::types::ChannelWarmupConfig>>,
+    previous_exit: Arc<parking_lot::RwLock<Option<crate::core::types::PreviousExit>>>,
+    state_key_provider: Arc<parking_lot::RwLock<Option<Arc<dyn StateKeyProvider>>>>,
+    pinned_identity_keys: PinnedIdentityKeys,
+    pinned_public_keys: crate::discovery::verification::PinnedPublicKeys,
+    // 优雅关闭：是否仍接受新的发送、在途发送登记与关闭宽限期
+    accepting_sends: Arc<std::sync::atomic::AtomicBool>,
+    in_flight_sends: InFlightSends,
+    in_flight_drained: Arc<tokio::sync::Notify>,
+    shutdown_config: Arc<parking_lot::RwLock<crate::core::types::ShutdownConfig>>,
+    compression_config: Arc<parking_lot::RwLock<crate::core::types::CompressionConfig>>,
+    stream_config: Arc<parking_lot::RwLock<crate::core::types::StreamConfig>>,
+    delivery_mode: Arc<parking_lot::RwLock<crate::core::types::DeliveryMode>>,
+}
+
+/// 对端的在途发送名额，释放时同步更新在途指标
+struct SendSlot {
+    _permit: tokio::sync::OwnedSemaphorePermit,
+    recipient: DeviceId,
+    metrics: Arc<crate::core::metrics::MetricsCollector>,
+}
+
+impl Drop for SendSlot {
+    fn drop(&mut self) {
+        self.metrics.record_send_finished(self.recipient);
+    }
+}
+
+/// 在途发送：消息 ID -> (消息, 是否为尽力发送)
+type InFlightSends = Arc<DashMap<uuid::Uuid, (Message, bool)>>;
+
+/// 在途发送的登记，发送结束或被取消时注销并唤醒等待关闭的任务
+struct InFlightSend {
+    sends: InFlightSends,
+    drained: Arc<tokio::sync::Notify>,
+    message_id: uuid::Uuid,
+}
+
+impl Drop for InFlightSend {
+    fn drop(&mut self) {
+        self.sends.remove(&self.message_id);
+        self.drained.notify_waiters();
+    }
+}
+
+/// 等待响应的请求：关联 ID -> (请求接收方, 响应通知)
+type PendingRequests =
+    Arc<DashMap<uuid::Uuid, (DeviceId, tokio::sync::oneshot::Sender<MessagePayload>)>>;
+/// 尚未回复的入站请求：关联 ID -> (请求发送方, 接收时间)
+type PendingReplies = Arc<DashMap<uuid::Uuid, (DeviceId, Instant)>>;
+/// 等待确认的点对点消息：消息 ID -> (接收方, 确认通知)
+type PendingAcks = Arc<DashMap<uuid::Uuid, (DeviceId, tokio::sync::oneshot::Sender<()>)>>;
+
+/// 发送消息时附带的请求-响应关联信息
+enum Correlation {
+    Request(uuid::Uuid),
+    Reply(uuid::Uuid),
+}
+
+/// 发送消息时的可选项，未指定的字段取默认值（普通优先级、无序、持久化）
+struct SendOptions {
+    priority: MessagePriority,
+    require_ordered: bool,
+    correlation: Option<Correlation>,
+    // 尽力发送：跳过持久化与待发送队列
+    ephemeral: bool,
+    // 应用主题，接收端据此分发给主题订阅者
+    topic: Option<String>,
+    // 预先分配的消息 ID，便于在发送完成前定位持久化副本
+    message_id: uuid::Uuid,
+    // 要求接收方回送确认
+    require_ack: bool,
+    // 过期时间（Unix 秒），过期后崩溃恢复不再重发
+    expires_at: Option<u64>,
+    // 取得调度名额时回传调度记录
+    dispatch_record:
+        Option<tokio::sync::oneshot::Sender<crate::router::send_queue::DispatchRecord>>,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            priority: MessagePriority::Normal,
+            require_ordered: false,
+            correlation: None,
+            ephemeral: false,
+            topic: None,
+            message_id: uuid::Uuid::new_v4(),
+            require_ack: false,
+            expires_at: None,
+            dispatch_record: None,
+        }
+    }
+}
+
+/// 已注册的插件，按注册顺序运行消息钩子
+type Plugins = Arc<parking_lot::RwLock<Vec<Arc<dyn crate::core::traits::Plugin>>>>;
+
+/// 错误链中是否包含存储空间不足 (0704)
+fn is_storage_full(error: &crate::core::error::XLinkError) -> bool {
+    error.source_iter().any(|e| e.code().0 == 704)
+}
+
+/// 按注册顺序对消息运行插件钩子，任一插件丢弃时返回 false
+async fn apply_plugins(plugins: &Plugins, message: &mut Message, inbound: bool) -> Result<bool> {
+    use crate::core::traits::PluginAction;
+    // 先取快照再调用钩子，钩子运行期间注册或卸载插件不会阻塞
+    let snapshot: Vec<_> = plugins.read().clone();
+    for plugin in snapshot {
+        let action = if inbound {
+            plugin.on_inbound(message).await?
+        } else {
+            plugin.on_outbound(message).await?
+        };
+        match action {
+            PluginAction::Continue => {}
+            PluginAction::Replace(payload) => message.payload = payload,
+            PluginAction::Drop => {
+                log::debug!("Message {} dropped by plugin {}", message.id, plugin.name());
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// 中止时一并中止所持有的任务，通道监管任务被中止时不会遗留接收任务
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// 监管单个通道：接收任务退出或发送报告断开时按指数退避重连，成功后重启接收并重置退避
+async fn supervise_channel(
+    channel: Arc<dyn Channel>,
+    handler: Arc<dyn MessageHandler>,
+    receive_task: Option<JoinHandle<()>>,
+    disconnected: Arc<tokio::sync::Notify>,
+    config: Arc<parking_lot::RwLock<crate::core::types::ReconnectConfig>>,
+) {
+    let ctype = channel.channel_type();
+    let mut receiver = receive_task.map(AbortOnDrop);
+    let mut backoff = crate::core::retry::ReconnectBackoff::new(*config.read());
+    loop {
+        match receiver.as_mut() {
+            Some(task) => {
+                tokio::select! {
+                    _ = &mut task.0 => {
+                        log::warn!("Receive task of channel {:?} exited", ctype);
+                        receiver = None;
+                    }
+                    _ = disconnected.notified() => {}
+                }
+            }
+            None => disconnected.notified().await,
+        }
+        if !config.read().enabled {
+            log::warn!("Channel {:?} disconnected, reconnection disabled", ctype);
+            continue;
+        }
+
+        // 每轮重连从初始等待开始，并采用最新配置
+        backoff.reset(*config.read());
+        loop {
+            let delay = backoff.next_delay();
+            tokio::time::sleep(delay).await;
+            let restarted = match channel.reconnect().await {
+                Ok(()) if receiver.is_none() => channel
+                    .start_with_handler(handler.clone())
+                    .await
+                    .map(|task| {
+                        receiver = task.map(AbortOnDrop);
+                    }),
+                result => result,
+            };
+            match restarted {
+                Ok(()) => {
+                    log::info!(
+                        "Channel {:?} reconnected after {} attempts",
+                        ctype,
+                        backoff.attempts()
+                    );
+                    break;
+                }
+                Err(e) => log::warn!(
+                    "Reconnecting channel {:?} failed (attempt {}, retry after backoff): {}",
+                    ctype,
+                    backoff.attempts(),
+                    e
+                ),
+            }
+        }
+    }
 }
 
+/// 已固定的对端身份公钥：设备 ID -> Ed25519 公钥，带签名的入站消息据此校验
+type PinnedIdentityKeys = Arc<DashMap<DeviceId, VerifyingKey>>;
+/// 主题订阅者：主题 -> 订阅者队列
+type TopicSubscribers = Arc<DashMap<String, Vec<mpsc::Sender<Message>>>>;
+/// 可选的消息日志，SDK 与消息处理器共享
+type SharedJournal = Arc<parking_lot::RwLock<Option<Arc<crate::storage::journal::MessageJournal>>>>;
+/// 接收工作池，None 表示在通道接收任务中内联处理
+type SharedReceivePool =
+    Arc<parking_lot::RwLock<Option<Arc<crate::core::receive_pool::ReceivePool>>>>;
+
 impl Drop for XLink {
     fn drop(&mut self) {
         log::info!("Dropping UnifiedPush SDK for device {}", self.device_id);
@@ -117,44 +373,254 @@ impl Drop for XLink {
             self.rate_limiter.remove(&device_id);
         }
 
-        let plugin_keys: Vec<_> = self
-            .plugins
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
-        for plugin_name in plugin_keys {
-            self.plugins.remove(&plugin_name);
-        }
+        self.plugins.write().clear();
+
+        crate::utils::remove_keys(
+            &self.send_sequences,
+            crate::utils::get_all_keys(&self.send_sequences),
+        );
+        crate::utils::remove_keys(
+            &self.reorder_buffers,
+            crate::utils::get_all_keys(&self.reorder_buffers),
+        );
     }
 }
 
 struct SdkMessageHandler {
     app_tx: mpsc::Sender<Message>,
+    app_queue: crate::core::types::AppQueueConfig,
     _crypto: Arc<CryptoEngine>,
     // 使用 Weak 引用打破循环引用
     group_manager: std::sync::Weak<GroupManager>,
     heartbeat_manager: std::sync::Weak<Mutex<HeartbeatManager>>,
     stream_manager: std::sync::Weak<StreamManager>,
     // DoS 防护：限制每个设备的连接/消息速率
-    rate_limiter: Arc<DashMap<DeviceId, (Instant, u32)>>,
+    rate_limiter: Arc<DashMap<(DeviceId, MessagePriority), (Instant, u32)>>,
+    rate_limits: Arc<parking_lot::RwLock<crate::core::types::PriorityRateLimits>>,
+    rate_limit_config: Arc<parking_lot::RwLock<crate::core::types::RateLimitConfig>>,
+    clock: Arc<dyn crate::core::clock::Clock>,
     metrics: Arc<crate::core::metrics::MetricsCollector>,
+    error_stats: Arc<parking_lot::Mutex<crate::core::error::ErrorStatistics>>,
+    // 有序消息的接收端重排缓冲
+    reorder_buffers: Arc<DashMap<DeviceId, crate::core::ordering::ReorderBuffer>>,
+    // 跨通道去重：最近收到的 (发送方, 消息 ID)
+    dedup: Arc<parking_lot::Mutex<crate::core::dedup::DedupCache>>,
+    // 时间戳时钟偏差容忍配置
+    clock_skew: Arc<parking_lot::RwLock<crate::core::types::ClockSkewConfig>>,
+    message_age: Arc<parking_lot::RwLock<crate::core::types::MessageAgeConfig>>,
+    reorder_config: Arc<parking_lot::RwLock<crate::core::types::ReorderBufferConfig>>,
+    stream_delivery: Arc<parking_lot::RwLock<crate::core::types::StreamDeliveryConfig>>,
+    events: crate::core::events::EventBus,
+    journal: SharedJournal,
+    pending_requests: PendingRequests,
+    pending_replies: PendingReplies,
+    pending_acks: PendingAcks,
+    receive_pool: SharedReceivePool,
+    topic_subscribers: TopicSubscribers,
+    pinned_identity_keys: PinnedIdentityKeys,
+    plugins: Plugins,
+    // 回送点对点确认所需的本地设备 ID 与路由器
+    local_device_id: DeviceId,
+    router: std::sync::Weak<Router>,
+    // 应答重传请求时查找待发送消息
+    storage: Arc<dyn Storage>,
+}
+
+impl SdkMessageHandler {
+    /// 向发送方回送点对点确认，后台发送不阻塞接收流程
+    fn acknowledge(&self, sender: DeviceId, message_id: uuid::Uuid) {
+        self.reply_in_background(sender, MessagePayload::Ack(message_id));
+    }
+
+    /// 向流的发送方回送数据分片确认，供发送方记录可续传进度
+    fn acknowledge_chunk(&self, sender: DeviceId, stream_id: uuid::Uuid, chunk_index: u32) {
+        self.reply_in_background(
+            sender,
+            MessagePayload::StreamChunkAck {
+                stream_id,
+                chunk_index,
+            },
+        );
+    }
+
+    fn reply_in_background(&self, recipient: DeviceId, payload: MessagePayload) {
+        let Some(router) = self.router.upgrade() else {
+            return;
+        };
+        let mut reply = Message::new(self.local_device_id, recipient, payload);
+        router.sign_message(&mut reply);
+        tokio::spawn(async move {
+            let reply_id = reply.id;
+            let result = match router.select_channel(&reply).await {
+                Ok(channel) => channel.send(reply).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::debug!("Failed to send reply {} to {}: {}", reply_id, recipient, e);
+            }
+        });
+    }
+
+    /// 从待发送队列与（未脱敏的）消息日志中查找对端请求重传的有序消息，在后台按序号重发
+    ///
+    /// 只重发发往请求方且属于所请求纪元的消息，每次最多
+    /// [`MAX_RETRANSMIT_PER_REQUEST`](crate::core::ordering::MAX_RETRANSMIT_PER_REQUEST) 条
+    fn answer_retransmit_request(
+        &self,
+        requester: DeviceId,
+        epoch: u64,
+        sequences: Vec<std::ops::Range<u64>>,
+    ) {
+        let Some(router) = self.router.upgrade() else {
+            return;
+        };
+        let storage = self.storage.clone();
+        let journal = self.journal.read().clone();
+        tokio::spawn(async move {
+            let requested = |message: &Message| {
+                message.recipient == requester
+                    && message.sequence_epoch == Some(epoch)
+                    && message
+                        .sequence
+                        .is_some_and(|sequence| sequences.iter().any(|r| r.contains(&sequence)))
+            };
+            let mut found = std::collections::BTreeMap::new();
+            match storage.list_pending_messages().await {
+                Ok(pending) => {
+                    for message in pending.into_iter().filter(|m| requested(m)) {
+                        found.entry(message.sequence).or_insert(message);
+                    }
+                }
+                Err(e) => log::warn!("Failed to read pending messages for retransmit: {}", e),
+            }
+            if let Some(journal) = journal.filter(|journal| !journal.is_redacting()) {
+                match crate::storage::journal::MessageJournal::load(journal.path()).await {
+                    Ok(entries) => {
+                        for entry in entries {
+                            if entry.direction
+                                == crate::storage::journal::JournalDirection::Outbound
+                                && requested(&entry.message)
+                            {
+                                found.entry(entry.message.sequence).or_insert(entry.message);
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to read journal for retransmit: {}", e),
+                }
+            }
+            log::info!(
+                "Retransmitting {} ordered message(s) requested by {}",
+                found.len(),
+                requester
+            );
+            for message in found
+                .into_values()
+                .take(crate::core::ordering::MAX_RETRANSMIT_PER_REQUEST)
+            {
+                let message_id = message.id;
+                let result = match router.select_channel(&message).await {
+                    Ok(channel) => channel.send(message).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    log::debug!(
+                        "Failed to retransmit {} to {}: {}",
+                        message_id,
+                        requester,
+                        e
+                    );
+                }
+            }
+        });
+    }
+
+    /// 将带主题的消息分发给该主题的全部订阅者，已关闭的订阅者随之移除
+    ///
+    /// 至少一个订阅者收到时返回 None；消息无主题或无存活订阅者时原样返回，由调用方回退到普通接收队列
+    async fn deliver_to_topic(&self, message: Message) -> Option<Message> {
+        let subscribers = match &message.topic {
+            Some(topic) => {
+                let Some(mut entry) = self.topic_subscribers.get_mut(topic) else {
+                    return Some(message);
+                };
+                entry.retain(|tx| !tx.is_closed());
+                entry.clone()
+            }
+            None => return Some(message),
+        };
+
+        let mut delivered = false;
+        for tx in subscribers {
+            delivered |= tx.send(message.clone()).await.is_ok();
+        }
+        (!delivered).then_some(message)
+    }
 }
 
+/// 低电量关闭时持久化的（已封装）SDK 状态在存储元数据中的键
+pub const SDK_STATE_KEY: &str = "sdk_state";
+/// 正常退出标记在存储元数据中的键
+const SHUTDOWN_MARKER_KEY: &str = "shutdown_marker";
+/// 因电量过低被拒绝的发送建议的重试间隔
+const BATTERY_FLOOR_RETRY_DELAY_MS: u64 = 60_000;
+/// 每个主题订阅者的接收队列容量
+const TOPIC_SUBSCRIBER_CAPACITY: usize = 100;
 /// Rate Limiter 配置常量
 const RATE_LIMIT_MAX_RETRIES: usize = 3;
 const RATE_LIMIT_WINDOW_SECS: u64 = 1;
-const RATE_LIMIT_MAX_COUNT: u32 = 100;
+/// 未回复的入站请求保留时长，超时后不再允许回复
+const PENDING_REPLY_TTL_SECS: u64 = 300;
+/// 错误统计报告列出的常见错误码数量
+const ERROR_REPORT_TOP_CODES: usize = 10;
+/// 群组密钥自动轮换任务在后台任务表中的名称前缀
+const GROUP_KEY_ROTATION_TASK_PREFIX: &str = "group_key_rotation_";
 
 #[async_trait]
 impl MessageHandler for SdkMessageHandler {
-    async fn handle_message(&self, mut message: Message) -> Result<()> {
-        // DoS 防护：限制每秒最多 100 条消息
+    async fn handle_message(&self, message: Message) -> Result<()> {
+        let result = self.process_message(message).await;
+        if let Err(e) = &result {
+            self.error_stats.lock().record(e);
+        }
+        result
+    }
+}
+
+impl SdkMessageHandler {
+    async fn process_message(&self, mut message: Message) -> Result<()> {
+        // 启用工作池时交由发送方对应的工作者处理
+        let pool = self.receive_pool.read().clone();
+        if let Some(pool) = pool {
+            return pool.dispatch(message).await;
+        }
+
+        // 消息日志：记录原始入站消息，便于回放重现
+        let journal = self.journal.read().clone();
+        if let Some(journal) = journal {
+            if let Err(e) = journal
+                .record(
+                    crate::storage::journal::JournalDirection::Inbound,
+                    None,
+                    &message,
+                )
+                .await
+            {
+                log::warn!("Failed to journal inbound message {}: {}", message.id, e);
+            }
+        }
+
+        // DoS 防护：按发送方与消息优先级分别限制每秒消息数
         // 改进的速率限制策略，防止通过并发访问绕过限制
-        let now = Instant::now();
-        let should_rate_limit = {
+        let now = self.clock.instant_now();
+        let rate_key = (message.sender, message.priority);
+        let limit = self
+            .rate_limit_config
+            .read()
+            .inbound_limit(self.rate_limits.read().limit_for(message.priority));
+        let exceeded_limit = limit.filter(|&limit| {
             let mut retries = 0;
             loop {
-                match self.rate_limiter.try_get_mut(&message.sender) {
+                match self.rate_limiter.try_get_mut(&rate_key) {
                     dashmap::try_result::TryResult::Present(mut rate_entry) => {
                         let (last_reset, count) = rate_entry.value_mut();
 
@@ -166,15 +632,13 @@ impl MessageHandler for SdkMessageHandler {
                             break false;
                         } else {
                             *count = count.saturating_add(1);
-                            break *count > RATE_LIMIT_MAX_COUNT;
+                            break *count > limit;
                         }
                     }
                     dashmap::try_result::TryResult::Absent => {
                         // 新条目，插入并允许通过（使用饱和加法防止溢出）
-                        self.rate_limiter.insert(
-                            message.sender,
-                            (now, 1u32.saturating_add(0)),
-                        );
+                        self.rate_limiter
+                            .insert(rate_key, (now, 1u32.saturating_add(0)));
                         break false;
                     }
                     dashmap::try_result::TryResult::Locked => {
@@ -193,49 +657,292 @@ impl MessageHandler for SdkMessageHandler {
                     }
                 }
             }
-        };
+        });
 
-        if should_rate_limit {
+        if let Some(limit) = exceeded_limit {
             log::warn!(
-                "DoS Protection: Rate limit exceeded for device {}",
+                "DoS Protection: {:?} rate limit exceeded for device {}",
+                message.priority,
                 message.sender
             );
             return Err(crate::core::error::XLinkError::resource_exhausted(
-                format!("Rate limit exceeded for device {}", message.sender),
-                101,
-                100,
+                format!(
+                    "{:?} rate limit exceeded for device {}",
+                    message.priority, message.sender
+                ),
+                (limit + 1).into(),
+                limit.into(),
                 file!(),
             ));
         }
 
+        // 身份签名：已固定发送方公钥时必须携带有效签名，中继篡改或剥离签名的消息在解密前即被拒绝
+        if let Some(sender_key) = self.pinned_identity_keys.get(&message.sender) {
+            let Some(signature) = message.signature.as_deref() else {
+                log::warn!(
+                    "Rejecting message {} from {}: missing identity signature",
+                    message.id,
+                    message.sender
+                );
+                return Err(
+                    crate::core::error::XLinkError::signature_verification_failed(
+                        "Ed25519",
+                        "missing identity signature from pinned sender",
+                        file!(),
+                    ),
+                );
+            };
+            if let Err(e) =
+                CryptoEngine::verify_with_key(&sender_key, &message.signing_bytes(), signature)
+            {
+                log::warn!(
+                    "Rejecting message {} from {}: invalid identity signature",
+                    message.id,
+                    message.sender
+                );
+                return Err(e);
+            }
+        }
+
         log::info!("SDK received message: {}", message.id);
 
+        // 时钟偏差容忍：发送方时钟异常时钳制或拒绝时间戳，避免污染在线状态与排序
+        let skew = *self.clock_skew.read();
+        let local_now = self.clock.now_unix_secs();
+
+        // 过期消息（例如中继恢复后补发的积压消息）：按原始时间戳判断，放宽时钟偏差容忍窗口
+        let age_config = *self.message_age.read();
+        let mut stale = false;
+        if let Some(max_age_secs) = age_config.max_age_secs {
+            let age_secs = local_now.saturating_sub(message.timestamp);
+            if age_secs > max_age_secs.saturating_add(skew.max_skew_secs) {
+                self.metrics.record_stale();
+                let delivered =
+                    age_config.action == crate::core::types::StaleMessageAction::Deliver;
+                log::warn!(
+                    "Stale message {} from {}: {}s old (max {}s), delivered={}",
+                    message.id,
+                    message.sender,
+                    age_secs,
+                    max_age_secs,
+                    delivered
+                );
+                self.events
+                    .publish(crate::core::events::SdkEvent::StaleMessageReceived {
+                        message_id: message.id,
+                        sender: message.sender,
+                        age_secs,
+                        delivered,
+                    });
+                if !delivered {
+                    return Ok(());
+                }
+                stale = true;
+            }
+        }
+
+        let earliest = local_now.saturating_sub(skew.max_skew_secs);
+        let latest = local_now.saturating_add(skew.max_skew_secs);
+        // 已判定为过期并按配置交付的消息保留原始时间戳，便于应用识别
+        if !stale && (message.timestamp < earliest || message.timestamp > latest) {
+            log::warn!(
+                "Clock skew detected for message {} from {}: timestamp={}, local={}, tolerance={}s",
+                message.id,
+                message.sender,
+                message.timestamp,
+                local_now,
+                skew.max_skew_secs
+            );
+            match skew.action {
+                crate::core::types::ClockSkewAction::Reject => {
+                    return Err(crate::core::error::XLinkError::invalid_protocol_message(
+                        "timestamp".to_string(),
+                        format!(
+                            "timestamp {} outside {}s of local time {}",
+                            message.timestamp, skew.max_skew_secs, local_now
+                        ),
+                        file!(),
+                    ));
+                }
+                crate::core::types::ClockSkewAction::Clamp => {
+                    message.timestamp = if message.timestamp > latest {
+                        local_now
+                    } else {
+                        earliest
+                    };
+                }
+            }
+        }
+
         self.metrics.record_receive(0); // 暂时记为0字节
 
+        // 请求-响应：响应交给等待中的请求方，不透传给 App
+        if let Some(correlation_id) = message.in_reply_to {
+            let expected = self
+                .pending_requests
+                .get(&correlation_id)
+                .map(|entry| entry.0 == message.sender)
+                .unwrap_or(false);
+            if expected {
+                if let Some((_, (_, tx))) = self.pending_requests.remove(&correlation_id) {
+                    let _ = tx.send(message.payload);
+                }
+            } else {
+                log::debug!(
+                    "Dropping response {} from {} to unknown or expired request {}",
+                    message.id,
+                    message.sender,
+                    correlation_id
+                );
+            }
+            return Ok(());
+        }
+        if let Some(correlation_id) = message.correlation_id {
+            let ttl = Duration::from_secs(PENDING_REPLY_TTL_SECS);
+            let now = self.clock.instant_now();
+            self.pending_replies
+                .retain(|_, (_, received)| now.duration_since(*received) < ttl);
+            self.pending_replies
+                .insert(correlation_id, (message.sender, now));
+        }
+
+        // 点对点确认：交给等待中的发送方，不透传给 App
+        if let (MessagePayload::Ack(acked_id), None) = (&message.payload, message.group_id) {
+            let expected = self
+                .pending_acks
+                .get(acked_id)
+                .map(|entry| entry.0 == message.sender)
+                .unwrap_or(false);
+            if expected {
+                if let Some((_, (_, tx))) = self.pending_acks.remove(acked_id) {
+                    let _ = tx.send(());
+                }
+            } else {
+                log::debug!(
+                    "Dropping ack from {} for unknown or expired message {}",
+                    message.sender,
+                    acked_id
+                );
+            }
+            return Ok(());
+        }
+
+        // 有序交付的重传请求：从本地保存的消息中查找并重发，不透传给 App
+        if let MessagePayload::RetransmitRequest { epoch, sequences } = &message.payload {
+            self.answer_retransmit_request(message.sender, *epoch, sequences.clone());
+            return Ok(());
+        }
+
+        // 跨通道去重：同一消息经直连与中继重复到达时只处理一次，心跳与流分片自行处理重复
+        let exempt = matches!(
+            message.payload,
+            MessagePayload::Ping(..)
+                | MessagePayload::Pong(_)
+                | MessagePayload::StreamChunk { .. }
+                | MessagePayload::StreamChunkAck { .. }
+        );
+        if !exempt && !self.dedup.lock().insert(message.sender, message.id) {
+            log::debug!(
+                "Dropping duplicate message {} from {}",
+                message.id,
+                message.sender
+            );
+            return Ok(());
+        }
+
+        let mut replayed = Vec::new();
+
+        // 每个数据分片到达即回送确认，重复到达的分片同样确认以防前一次确认丢失；
+        // CRC32 不符的分片不确认，发送方续传时重发
+        if let MessagePayload::StreamChunk {
+            stream_id,
+            total_chunks,
+            chunk_index,
+            ref data,
+            chunk_crc,
+            ..
+        } = message.payload
+        {
+            if chunk_index < total_chunks && crate::media::integrity::chunk_intact(data, chunk_crc)
+            {
+                self.acknowledge_chunk(message.sender, stream_id, chunk_index);
+            }
+        }
+
+        // 压缩负载先解压，插件与应用看到的始终是原始 Binary
+        if let MessagePayload::Compressed { algorithm, data } = &message.payload {
+            let data = crate::core::compression::decompress(data, *algorithm)?;
+            message.payload = MessagePayload::Binary(data);
+        }
+
         // F6: 拦截心跳消息
         match message.payload {
-            MessagePayload::Ping(_) | MessagePayload::Pong(_) => {
+            MessagePayload::Ping(..) | MessagePayload::Pong(_) => {
                 if let Some(hm) = self.heartbeat_manager.upgrade() {
                     let hb = hm.lock().await;
                     hb.handle_heartbeat(&message).await;
                 }
                 return Ok(()); // 心跳消息不透传给 App
             }
+            MessagePayload::StreamChunkAck {
+                stream_id,
+                chunk_index,
+            } => {
+                if let Some(sm) = self.stream_manager.upgrade() {
+                    sm.handle_chunk_ack(message.sender, stream_id, chunk_index)
+                        .await;
+                }
+                return Ok(()); // 分片确认不透传给 App
+            }
+            MessagePayload::StreamChunk { .. }
+                if self.stream_delivery.read().deliver_raw_stream_chunks =>
+            {
+                // 应用自行重组：分片原样交付
+            }
             MessagePayload::StreamChunk {
                 stream_id,
                 total_chunks,
                 chunk_index,
                 data,
+                fec_group_size,
+                compression,
+                checksum,
+                chunk_crc,
                 ..
             } => {
                 // F8: 拦截流分片
                 if let Some(sm) = self.stream_manager.upgrade() {
                     match sm
-                        .handle_chunk(stream_id, total_chunks, chunk_index, data)
+                        .handle_chunk_verified(
+                            message.sender,
+                            stream_id,
+                            total_chunks,
+                            chunk_index,
+                            data,
+                            fec_group_size,
+                            ChunkIntegrity {
+                                checksum,
+                                chunk_crc,
+                            },
+                        )
                         .await
                     {
                         Ok(Some(full_data)) => {
-                            // 重组完成，替换 payload 传给 App
+                            // 重组完成，按分片上的压缩标记解压后替换 payload 传给 App
+                            let full_data = match compression {
+                                Some(algorithm) => {
+                                    match crate::core::compression::decompress(
+                                        &full_data, algorithm,
+                                    ) {
+                                        Ok(data) => data,
+                                        Err(e) => {
+                                            log::error!("Stream decompression error: {}", e);
+                                            return Ok(());
+                                        }
+                                    }
+                                }
+                                None => full_data,
+                            };
                             message.payload = MessagePayload::Binary(full_data);
                         }
                         Ok(None) => {
@@ -250,21 +957,218 @@ impl MessageHandler for SdkMessageHandler {
                     return Ok(());
                 }
             }
-            MessagePayload::GroupInvite { .. } => {
+            MessagePayload::GroupInvite { group_id, .. } => {
                 // F4: 自动处理群组邀请
                 if let Some(gm) = self.group_manager.upgrade() {
                     gm.as_ref().handle_incoming_group_message(&message).await?;
+                    // 先于邀请到达并被缓存的群组消息，紧随邀请之后补交
+                    replayed = gm.take_buffered_group_messages(group_id);
                 }
                 // 邀请消息同时也透传给 App 通知用户
             }
+            MessagePayload::JoinRequest { .. } => {
+                // 入群申请交由 GroupManager 记录，通过 SdkEvent::Group 通知管理员
+                if let Some(gm) = self.group_manager.upgrade() {
+                    gm.as_ref().handle_incoming_group_message(&message).await?;
+                }
+                return Ok(());
+            }
             _ => {
-                // F4: 如果是普通群组消息，也需要 GroupManager 处理一下（如去重、排序），这里简化直接透传
+                // F4: 未加入群组的消息按未知群组策略处理，不交付给 App
+                if let (Some(group_id), Some(gm)) = (message.group_id, self.group_manager.upgrade())
+                {
+                    if !message.payload.is_group_control() && gm.get_group(group_id).await.is_none()
+                    {
+                        return gm.handle_incoming_group_message(&message).await;
+                    }
+                }
+                // 已加入群组的普通消息，这里简化直接透传
+            }
+        }
+
+        // 有序消息先经重排缓冲，按序号恢复顺序后再交付；先放弃等待已超时的空洞
+        let mut ready = if message.sequence.is_some() {
+            let sender = message.sender;
+            let (message_id, sequence) = (message.id, message.sequence);
+            let epoch = message.sequence_epoch.unwrap_or(0);
+            let config = *self.reorder_config.read();
+            let expired = self.release_expired_gap(sender, &config);
+            let pushed = self
+                .reorder_buffers
+                .entry(sender)
+                .or_default()
+                .push(message, &config);
+            if let Some(overflow) = pushed.overflow {
+                self.metrics.record_reorder_overflow();
+                if overflow.policy == crate::core::types::ReorderOverflowPolicy::RequestRetransmit {
+                    // 被丢弃的消息随重传再次到达，不能按重复消息丢弃
+                    self.dedup.lock().forget(sender, message_id);
+                    let mut sequences = vec![overflow.missing.clone()];
+                    sequences.extend(sequence.map(|sequence| sequence..sequence + 1));
+                    self.reply_in_background(
+                        sender,
+                        MessagePayload::RetransmitRequest { epoch, sequences },
+                    );
+                }
+                self.events
+                    .publish(crate::core::events::SdkEvent::ReorderBufferOverflow {
+                        sender,
+                        missing: overflow.missing,
+                        policy: overflow.policy,
+                    });
+            }
+            expired.into_iter().chain(pushed.ready).collect()
+        } else {
+            vec![message]
+        };
+        ready.extend(replayed);
+        self.deliver(ready).await
+    }
+}
+
+impl SdkMessageHandler {
+    /// 发送方缺失序号等待超时时跳过空洞，返回随后可以按序交付的消息
+    ///
+    /// 此前因接收队列满而放回缓冲区的消息也一并返回
+    fn release_expired_gap(
+        &self,
+        sender: DeviceId,
+        config: &crate::core::types::ReorderBufferConfig,
+    ) -> Vec<Message> {
+        let Some(mut buffer) = self.reorder_buffers.get_mut(&sender) else {
+            return Vec::new();
+        };
+        let mut ready = buffer.take_ready();
+        let gap = config
+            .gap_timeout_ms
+            .and_then(|timeout_ms| buffer.release_expired(Duration::from_millis(timeout_ms)));
+        drop(buffer);
+        let Some(gap) = gap else {
+            return ready;
+        };
+        log::warn!(
+            "Gave up waiting for sequences {}..{} from {}",
+            gap.missing.start,
+            gap.missing.end,
+            sender
+        );
+        self.events
+            .publish(crate::core::events::SdkEvent::ReorderGapSkipped {
+                sender,
+                missing: gap.missing,
+            });
+        ready.extend(gap.ready);
+        ready
+    }
+
+    /// 交付所有发送方中等待超时的缓冲消息，由后台任务定期调用
+    async fn release_expired_gaps(&self) -> Result<()> {
+        let config = *self.reorder_config.read();
+        let senders: Vec<DeviceId> = self.reorder_buffers.iter().map(|e| *e.key()).collect();
+        for sender in senders {
+            let ready = self.release_expired_gap(sender, &config);
+            if !ready.is_empty() {
+                self.deliver(ready).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 接收队列满导致交付中断时处理本批剩余的消息
+    ///
+    /// 有序消息放回发送方的重排缓冲区，之后按序交付；所有未交付的消息都允许发送方重发
+    fn requeue_undelivered(&self, undelivered: Vec<Message>) {
+        {
+            let mut dedup = self.dedup.lock();
+            for message in &undelivered {
+                dedup.forget(message.sender, message.id);
+            }
+        }
+
+        let mut by_sender: HashMap<DeviceId, Vec<Message>> = HashMap::new();
+        for message in undelivered {
+            if message.sequence.is_some() {
+                by_sender.entry(message.sender).or_default().push(message);
             }
         }
+        for (sender, messages) in by_sender {
+            let dropped = self
+                .reorder_buffers
+                .entry(sender)
+                .or_default()
+                .requeue(messages);
+            for message in dropped {
+                log::debug!(
+                    "Ordered message {} from {} left for the sender to resend",
+                    message.id,
+                    sender
+                );
+            }
+        }
+    }
 
-        // 交付给 App
-        if let Err(e) = self.app_tx.send(message).await {
-            log::error!("Failed to deliver message to app: {}", e);
+    /// 依次经过入站插件、主题订阅者后交付给 App
+    async fn deliver(&self, ready: Vec<Message>) -> Result<()> {
+        // 交付给 App：带主题的消息优先交给主题订阅者
+        let mut ready = ready.into_iter();
+        while let Some(mut message) = ready.next() {
+            let (message_id, sender) = (message.id, message.sender);
+            // 有序消息保留插件处理前的副本，交付超时时放回重排缓冲
+            let original = message.sequence.is_some().then(|| message.clone());
+            // 群组消息的确认由 GroupManager 以 GroupAck 处理
+            let wants_ack = message.require_ack && message.group_id.is_none();
+            // 被插件丢弃的消息视为已处理，照常确认以免发送方重发
+            if !apply_plugins(&self.plugins, &mut message, true).await? {
+                if wants_ack {
+                    self.acknowledge(sender, message_id);
+                }
+                continue;
+            }
+            let message = match self.deliver_to_topic(message).await {
+                Some(message) => message,
+                None => {
+                    self.events
+                        .publish(crate::core::events::SdkEvent::MessageReceived {
+                            message_id,
+                            sender,
+                        });
+                    if wants_ack {
+                        self.acknowledge(sender, message_id);
+                    }
+                    continue;
+                }
+            };
+            // 队列满时等待应用取走消息，超时则对通道形成背压而不是丢弃
+            let timeout = Duration::from_millis(self.app_queue.send_timeout_ms);
+            match tokio::time::timeout(timeout, self.app_tx.send(message)).await {
+                Err(_) => {
+                    log::warn!(
+                        "App receive queue full, rejecting message {} from {}",
+                        message_id,
+                        sender
+                    );
+                    // 未交付的消息允许发送方重发
+                    self.dedup.lock().forget(sender, message_id);
+                    self.requeue_undelivered(original.into_iter().chain(ready).collect());
+                    return Err(crate::core::error::XLinkError::resource_exhausted(
+                        "app receive queue",
+                        self.app_queue.capacity as u64,
+                        self.app_queue.capacity as u64,
+                        file!(),
+                    ));
+                }
+                Ok(Err(e)) => log::error!("Failed to deliver message to app: {}", e),
+                Ok(Ok(())) => {
+                    self.events
+                        .publish(crate::core::events::SdkEvent::MessageReceived {
+                            message_id,
+                            sender,
+                        });
+                    if wants_ack {
+                        self.acknowledge(sender, message_id);
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -272,8 +1176,13 @@ impl MessageHandler for SdkMessageHandler {
 }
 
 impl XLink {
+    /// 以链式配置创建 SDK 实例，见 [`XLinkBuilder`]
+    pub fn builder(config: DeviceCapabilities) -> XLinkBuilder {
+        XLinkBuilder::new(config)
+    }
+
     pub async fn new(config: DeviceCapabilities, channels: Vec<Arc<dyn Channel>>) -> Result<Self> {
-        Self::with_storage_path(config, channels, "storage".to_string()).await
+        XLinkBuilder::new(config).channels(channels).build().await
     }
 
     pub async fn with_storage_path(
@@ -281,7 +1190,24 @@ impl XLink {
         channels: Vec<Arc<dyn Channel>>,
         storage_path: String,
     ) -> Result<Self> {
-        let storage = Arc::new(crate::storage::file_store::FileStorage::new(&storage_path).await?);
+        XLinkBuilder::new(config)
+            .channels(channels)
+            .storage_path(storage_path)
+            .build()
+            .await
+    }
+
+    /// 切换存储后端：先将 `previous` 中的数据迁移到 `storage`，再以 `storage` 创建 SDK 实例
+    ///
+    /// 迁移可重复执行，已迁移的数据会被跳过，因此可在确认旧后端可删除之前每次启动都调用
+    pub async fn with_storage_migration(
+        config: DeviceCapabilities,
+        channels: Vec<Arc<dyn Channel>>,
+        previous: Arc<dyn Storage>,
+        storage: Arc<dyn Storage>,
+    ) -> Result<Self> {
+        let report = crate::storage::migrate(previous.as_ref(), storage.as_ref()).await?;
+        log::info!("Migrated storage backend at startup: {:?}", report);
         Self::with_storage(config, channels, storage).await
     }
 
@@ -290,12 +1216,42 @@ impl XLink {
         config: DeviceCapabilities,
         channels: Vec<Arc<dyn Channel>>,
         storage: Arc<dyn Storage>,
+    ) -> Result<Self> {
+        XLinkBuilder::new(config)
+            .channels(channels)
+            .storage(storage)
+            .build()
+            .await
+    }
+
+    /// 使用自定义存储实现与应用接收队列配置创建 SDK 实例
+    pub async fn with_app_queue(
+        config: DeviceCapabilities,
+        channels: Vec<Arc<dyn Channel>>,
+        storage: Arc<dyn Storage>,
+        app_queue: crate::core::types::AppQueueConfig,
+    ) -> Result<Self> {
+        XLinkBuilder::new(config)
+            .channels(channels)
+            .storage(storage)
+            .app_queue(app_queue)
+            .build()
+            .await
+    }
+
+    /// 组装 SDK 实例，由 [`XLinkBuilder::build`] 在校验配置并打开存储后调用
+    async fn assemble(
+        config: DeviceCapabilities,
+        channels: Vec<Arc<dyn Channel>>,
+        storage: Arc<dyn Storage>,
+        app_queue: crate::core::types::AppQueueConfig,
+        clock: Arc<dyn crate::core::clock::Clock>,
     ) -> Result<Self> {
         let device_id = config.device_id;
         let cap_manager = Arc::new(CapabilityManager::new(config));
         let crypto = Arc::new(CryptoEngine::new());
 
-        let (app_tx, app_rx) = mpsc::channel(100);
+        let (app_tx, app_rx) = mpsc::channel(app_queue.capacity.max(1));
 
         let mut channel_map = HashMap::new();
         for ch in channels {
@@ -303,16 +1259,41 @@ impl XLink {
         }
 
         let router = Arc::new(Router::new(channel_map, cap_manager.clone()));
+        let traffic_budget = Arc::new(crate::core::budget::TrafficBudget::new());
+        router.set_traffic_budget(Some(traffic_budget.clone()));
 
         // 初始化新模块
-        let group_manager = Arc::new(GroupManager::new(device_id, router.clone()));
-        let heartbeat_manager = Arc::new(Mutex::new(HeartbeatManager::new(
+        let group_manager = Arc::new(GroupManager::with_clock(
+            device_id,
+            router.clone(),
+            clock.clone(),
+        ));
+        router.set_message_signer(Some(crypto.clone()));
+        group_manager.set_message_signer(Some(crypto.clone()));
+        let heartbeat_manager = HeartbeatManager::with_clock(
             device_id,
             router.clone(),
             cap_manager.clone(),
+            clock.clone(),
+        );
+        heartbeat_manager.set_group_manager(Arc::downgrade(&group_manager));
+        let heartbeat_manager = Arc::new(Mutex::new(heartbeat_manager));
+        // 统一事件总线：汇聚流媒体与群组事件（能力变化见 attach_capability_events）
+        let events = crate::core::events::EventBus::new();
+
+        let mut discovery = DiscoveryManager::new(cap_manager.clone());
+        discovery.set_discovered_peers(Arc::new(crate::discovery::peers::DiscoveredPeers::new(
+            crate::discovery::verification::DiscoveryConfig::default().max_peers,
+            cap_manager.clone(),
+            events.clone(),
         )));
-        let discovery_manager = Arc::new(Mutex::new(DiscoveryManager::new(cap_manager.clone())));
-        let stream_manager = Arc::new(StreamManager::new(device_id, router.clone()));
+        let discovery_manager = Arc::new(Mutex::new(discovery));
+        let stream_manager = Arc::new(StreamManager::with_clock(
+            device_id,
+            router.clone(),
+            clock.clone(),
+        ));
+        stream_manager.set_transfer_storage(Some(storage.clone()));
         let cap_detector = Arc::new(Mutex::new(
             crate::capability::detector::LocalCapabilityDetector::new(cap_manager.clone()),
         ));
@@ -322,7 +1303,17 @@ impl XLink {
         let receive_tasks = Arc::new(DashMap::new());
         let background_tasks = Arc::new(DashMap::new());
 
-        Ok(Self {
+        let stream_events = events.clone();
+        stream_manager.register_event_handler(Box::new(move |event| {
+            stream_events.publish(crate::core::events::SdkEvent::Stream(event));
+        }));
+        let group_events = events.clone();
+        group_manager.register_event_handler(Box::new(move |event| {
+            group_events.publish(crate::core::events::SdkEvent::Group(event));
+        }));
+
+        let sequence_epoch = clock.now_unix_millis().max(1);
+        let sdk = Self {
             device_id,
             router,
             cap_manager,
@@ -333,56 +1324,279 @@ impl XLink {
             discovery_manager,
             stream_manager,
             cap_detector,
+            clock,
             rate_limiter,
             metrics,
             receive_tasks,
             background_tasks,
             app_rx: Arc::new(Mutex::new(app_rx)),
             app_tx,
+            app_queue,
             compliance: Arc::new(crate::core::types::ComplianceConfig::default()),
-            plugins: Arc::new(DashMap::new()),
-        })
+            plugins: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            send_sequences: Arc::new(DashMap::new()),
+            sequence_epoch,
+            reorder_buffers: Arc::new(DashMap::new()),
+            dedup: Arc::new(parking_lot::Mutex::new(
+                crate::core::dedup::DedupCache::new(crate::core::types::DedupConfig::default()),
+            )),
+            clock_skew: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ClockSkewConfig::default(),
+            )),
+            reorder_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ReorderBufferConfig::default(),
+            )),
+            message_age: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::MessageAgeConfig::default(),
+            )),
+            stream_delivery: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::StreamDeliveryConfig::default(),
+            )),
+            routing_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::RoutingConfig::default(),
+            )),
+            group_key_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::GroupKeyConfig::default(),
+            )),
+            send_retry: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::SendRetryConfig::default(),
+            )),
+            reconnect_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ReconnectConfig::default(),
+            )),
+            channel_disconnects: Arc::new(DashMap::new()),
+            rate_limits: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::PriorityRateLimits::default(),
+            )),
+            rate_limit_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::RateLimitConfig::default(),
+            )),
+            send_rate_window: Arc::new(crate::core::rate_window::SendRateWindow::new(
+                Duration::from_secs(RATE_LIMIT_WINDOW_SECS),
+            )),
+            send_slots: Arc::new(DashMap::new()),
+            send_concurrency: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::PeerSendConcurrency::default(),
+            )),
+            metrics_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::MetricsConfig::default(),
+            )),
+            error_stats: Arc::new(parking_lot::Mutex::new(
+                crate::core::error::ErrorStatistics::new(),
+            )),
+            traffic_budget,
+            audit_query: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::AuditQueryConfig::default(),
+            )),
+            events,
+            journal: Arc::new(parking_lot::RwLock::new(None)),
+            pending_requests: Arc::new(DashMap::new()),
+            pending_replies: Arc::new(DashMap::new()),
+            pending_acks: Arc::new(DashMap::new()),
+            receive_pool: Arc::new(parking_lot::RwLock::new(None)),
+            receive_pipeline: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ReceivePipelineConfig::default(),
+            )),
+            topic_subscribers: Arc::new(DashMap::new()),
+            battery_policy: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::BatteryPolicy::default(),
+            )),
+            channel_warmup: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ChannelWarmupConfig::default(),
+            )),
+            previous_exit: Arc::new(parking_lot::RwLock::new(None)),
+            state_key_provider: Arc::new(parking_lot::RwLock::new(None)),
+            pinned_identity_keys: Arc::new(DashMap::new()),
+            pinned_public_keys: Arc::new(DashMap::new()),
+            accepting_sends: Arc::new(std::sync::atomic::AtomicBool::new(true)),
+            in_flight_sends: Arc::new(DashMap::new()),
+            in_flight_drained: Arc::new(tokio::sync::Notify::new()),
+            shutdown_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::ShutdownConfig::default(),
+            )),
+            compression_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::CompressionConfig::default(),
+            )),
+            stream_config: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::StreamConfig::default(),
+            )),
+            delivery_mode: Arc::new(parking_lot::RwLock::new(
+                crate::core::types::DeliveryMode::default(),
+            )),
+        };
+        sdk.attach_capability_events();
+        sdk.attach_channel_identity();
+        // 群组控制消息以明文传输，按已固定的身份公钥校验其签名
+        sdk.group_manager
+            .set_identity_keys(sdk.pinned_identity_keys.clone());
+        Ok(sdk)
     }
 
-    pub async fn start(&self) -> Result<()> {
-        log::info!("Starting UnifiedPush SDK for device {}", self.device_id);
-
-        // 确保清理旧任务，防止重复启动导致的泄露
-        self.stop().await;
-
-        // 启动时进行崩溃恢复
-        match self.recover_from_crash().await {
-            Ok(_) => log::info!("Crash recovery completed successfully"),
-            Err(e) => log::error!("Crash recovery failed: {}", e),
+    /// 将本机身份与已固定的对端公钥交给各通道，供其在传输层认证对端
+    ///
+    /// 身份引擎被替换（如导入状态）后需重新调用
+    fn attach_channel_identity(&self) {
+        let identity = crate::core::traits::ChannelIdentity {
+            device_id: self.device_id,
+            signer: self.crypto.clone(),
+            pinned_keys: self.pinned_identity_keys.clone(),
+        };
+        for channel in self.router.get_channels().values() {
+            channel.set_identity(identity.clone());
         }
+    }
+
+    /// 将能力变化转发到统一事件总线
+    ///
+    /// stop() 会清理能力管理器的监听器，因此 start() 时需重新挂接
+    fn attach_capability_events(&self) {
+        let events = self.events.clone();
+        self.cap_manager.watch_capability_changes(
+            "sdk_event_bus",
+            Box::new(move |change| {
+                events.publish(crate::core::events::SdkEvent::Capability(change));
+            }),
+        );
+    }
+
+    /// 订阅 SDK 统一事件流
+    ///
+    /// 投递是有损的广播语义，订阅者落后过多时最旧的事件会被丢弃，详见 [`crate::core::events`]。
+    /// 各专用订阅接口仍然可用。
+    pub fn events(&self) -> impl futures::Stream<Item = crate::core::events::SdkEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn start(&self) -> Result<()> {
+        log::info!("Starting UnifiedPush SDK for device {}", self.device_id);
+
+        // 拒绝重复启动，防止任务重复创建导致泄露（需先调用 stop）
+        // 启动前设置的群组密钥轮换任务不算作已启动
+        let started_tasks = self
+            .background_tasks
+            .iter()
+            .filter(|entry| !entry.key().starts_with(GROUP_KEY_ROTATION_TASK_PREFIX))
+            .count();
+        if !self.receive_tasks.is_empty() || started_tasks > 0 {
+            log::warn!(
+                "SDK for device {} already started with {} tracked tasks",
+                self.device_id,
+                self.receive_tasks.len() + self.background_tasks.len()
+            );
+            return Err(crate::core::error::XLinkError::invalid_state(
+                "start",
+                "SDK already started, call stop() first",
+                file!(),
+            ));
+        }
+
+        self.attach_capability_events();
+
+        if let Err(e) = self.traffic_budget.load(self.storage.as_ref()).await {
+            log::warn!("Failed to restore traffic budget usage: {}", e);
+        }
+
+        // 恢复上次运行保存的累计指标
+        if self.metrics_config.read().persist_across_restarts {
+            if let Err(e) = self.metrics.load(self.storage.as_ref()).await {
+                log::warn!("Failed to restore persisted metrics: {}", e);
+            }
+        }
+
+        // 根据上次退出标记决定是否需要崩溃恢复；标记随即清除，本次运行崩溃时不会残留
+        let previous_exit = match self.take_shutdown_marker().await {
+            Some(marker) => crate::core::types::PreviousExit::Clean(marker),
+            None => crate::core::types::PreviousExit::Crashed,
+        };
+        match &previous_exit {
+            crate::core::types::PreviousExit::Clean(marker) if marker.pending_messages == 0 => {
+                log::info!(
+                    "Previous run shut down cleanly ({:?}), skipping crash recovery",
+                    marker.reason
+                );
+            }
+            crate::core::types::PreviousExit::Clean(marker) => {
+                log::info!(
+                    "Previous run shut down cleanly ({:?}) with {} pending messages, resending",
+                    marker.reason,
+                    marker.pending_messages
+                );
+                match self.resend_pending_messages(&mut |_| {}).await {
+                    Ok((total, failed)) => log::info!(
+                        "Resent {} pending messages after restart, {} failed",
+                        total - failed,
+                        failed
+                    ),
+                    Err(e) => {
+                        log::error!("Failed to resend pending messages: {}", e);
+                        self.record_error(&e);
+                    }
+                }
+            }
+            crate::core::types::PreviousExit::Crashed => {
+                log::warn!("No clean shutdown marker found, previous run crashed");
+                match self.recover_from_crash().await {
+                    Ok(_) => log::info!("Crash recovery completed successfully"),
+                    Err(e) => {
+                        log::error!("Crash recovery failed: {}", e);
+                        self.record_error(&e);
+                    }
+                }
+            }
+        }
+        *self.previous_exit.write() = Some(previous_exit);
 
         // 启动各通道接收任务，并保存 handle 以便后续清理
-        let handler = Arc::new(SdkMessageHandler {
-            app_tx: self.app_tx.clone(),
-            _crypto: self.crypto.clone(),
-            group_manager: Arc::downgrade(&self.group_manager),
-            heartbeat_manager: Arc::downgrade(&self.heartbeat_manager),
-            stream_manager: Arc::downgrade(&self.stream_manager),
-            rate_limiter: self.rate_limiter.clone(),
-            metrics: self.metrics.clone(),
-        });
+        let handler = Arc::new(self.message_handler());
 
+        // 每个通道由监管任务持有接收任务，断开后负责重连与重启接收
         for (ctype, channel) in self.router.get_channels() {
             let channel = channel.clone();
             let ctype = *ctype;
             let h = handler.clone();
 
-            match channel.start_with_handler(h).await {
-                Ok(Some(task)) => {
-                    self.receive_tasks.insert(ctype, task);
+            let receive_task = match channel.start_with_handler(h.clone()).await {
+                Ok(task) => {
+                    if task.is_none() {
+                        log::debug!("Channel {:?} started without background task", ctype);
+                    }
+                    task
                 }
-                Ok(None) => {
-                    log::debug!("Channel {:?} started without background task", ctype);
+                Err(e) => {
+                    log::error!("Failed to start channel {:?}: {}", ctype, e);
+                    None
                 }
-                Err(e) => log::error!("Failed to start channel {:?}: {}", ctype, e),
-            }
+            };
+            let disconnected = self
+                .channel_disconnects
+                .entry(ctype)
+                .or_insert_with(|| Arc::new(tokio::sync::Notify::new()))
+                .clone();
+            let supervisor = tokio::spawn(supervise_channel(
+                channel,
+                h,
+                receive_task,
+                disconnected,
+                self.reconnect_config.clone(),
+            ));
+            self.receive_tasks.insert(ctype, supervisor);
         }
 
+        // 定期交付等待缺失序号超时的有序消息，发送方不再发送时也不会无限滞留
+        let reorder_handler = handler.clone();
+        let reorder_task = tokio::spawn(async move {
+            loop {
+                let timeout_ms = reorder_handler.reorder_config.read().gap_timeout_ms;
+                let interval = timeout_ms.map_or(1000, |ms| (ms / 2).clamp(50, 1000));
+                tokio::time::sleep(Duration::from_millis(interval)).await;
+                if let Err(e) = reorder_handler.release_expired_gaps().await {
+                    log::warn!("Failed to deliver timed-out ordered messages: {}", e);
+                }
+            }
+        });
+        self.background_tasks
+            .insert("reorder_gap_release".to_string(), reorder_task);
+
         // 启动后台服务
         if let Some(task) = self.heartbeat_manager.lock().await.start() {
             self.background_tasks.insert("heartbeat".to_string(), task);
@@ -414,6 +1628,7 @@ impl XLink {
 
         // 启动数据保留清理任务
         let storage = self.storage.clone();
+        let stream_manager = self.stream_manager.clone();
         let retention_days = self.compliance.retention_days;
         let cleanup_task = tokio::spawn(async move {
             loop {
@@ -422,6 +1637,10 @@ impl XLink {
                         Ok(count) => log::info!("Compliance: Cleaned up {} old records", count),
                         Err(e) => log::error!("Compliance: Cleanup failed: {}", e),
                     }
+                    // 未完成的可续传传输清单同样受保留期约束
+                    stream_manager
+                        .cleanup_expired_transfers(retention_days)
+                        .await;
                 }
                 tokio::time::sleep(Duration::from_secs(24 * 3600)).await; // 每天清理一次
             }
@@ -431,18 +1650,18 @@ impl XLink {
 
         // 启动内存泄漏防护清理任务
         let group_manager = self.group_manager.clone();
+        let clock = self.clock.clone();
         let memory_cleanup_task = tokio::spawn(async move {
+            let mut last_results_cleanup = clock.instant_now();
             loop {
                 // 每6小时清理一次过期的邀请记录
                 group_manager.cleanup_expired_invites(24); // 清理24小时前的邀请记录
 
                 // 每12小时清理一次广播结果通道
-                if tokio::time::Instant::now()
-                    .elapsed()
-                    .as_secs()
-                    .is_multiple_of(12 * 3600)
-                {
+                let now = clock.instant_now();
+                if now.duration_since(last_results_cleanup) >= Duration::from_secs(12 * 3600) {
                     group_manager.cleanup_expired_broadcast_results().await;
+                    last_results_cleanup = now;
                 }
 
                 tokio::time::sleep(Duration::from_secs(6 * 3600)).await; // 每6小时检查一次
@@ -451,38 +1670,173 @@ impl XLink {
         self.background_tasks
             .insert("memory_cleanup".to_string(), memory_cleanup_task);
 
+        // 按配置在后台预热最近活跃对端的通道
+        let warmup = *self.channel_warmup.read();
+        if warmup.enabled {
+            let router = self.router.clone();
+            let warmup_task = tokio::spawn(async move {
+                let report = Self::run_channel_warmup(&router, warmup).await;
+                log::info!(
+                    "Channel warm-up finished: {} peers, {} channels warmed, {} failed",
+                    report.peers.len(),
+                    report.warmed.len(),
+                    report.failed
+                );
+            });
+            self.background_tasks
+                .insert("channel_warmup".to_string(), warmup_task);
+        }
+
         Ok(())
     }
 
+    /// 立即预热最近活跃对端的通道，返回预热结果
+    ///
+    /// 与 `start` 后的自动预热相同：遵循预热配置中的对端数、并发数与电量限制，
+    /// 电量过低且未充电时不预热
+    pub async fn warm_up_channels(&self) -> crate::router::warmup::WarmupReport {
+        let config = *self.channel_warmup.read();
+        Self::run_channel_warmup(&self.router, config).await
+    }
+
+    async fn run_channel_warmup(
+        router: &Router,
+        config: crate::core::types::ChannelWarmupConfig,
+    ) -> crate::router::warmup::WarmupReport {
+        let local_caps = router.capability_manager().get_local_caps();
+        let low_battery = local_caps
+            .battery_level
+            .is_some_and(|level| level < config.min_battery_level);
+        if low_battery && !local_caps.is_charging {
+            log::info!("Skipping channel warm-up on low battery");
+            return crate::router::warmup::WarmupReport::default();
+        }
+        crate::router::warmup::warm_up_recent_peers(
+            router,
+            Duration::from_secs(config.recent_within_secs),
+            config.max_peers,
+            config.max_concurrency,
+        )
+        .await
+    }
+
+    /// 配置加密状态的封装密钥来源，配置后导出与内部持久化的状态均为密文
+    ///
+    /// 移动端应传入从系统密钥库取出密钥的 `StateKeyProvider`，见 `crypto::state_seal`
+    pub fn set_state_key_provider(&self, provider: Option<Arc<dyn StateKeyProvider>>) {
+        *self.state_key_provider.write() = provider;
+    }
+
+    /// 是否启用加密状态的静态加密
+    pub fn state_encryption_enabled(&self) -> bool {
+        self.state_key_provider.read().is_some()
+    }
+
     /// 导出 SDK 完整状态（用于设备迁移 UAT-F-024）
+    ///
+    /// 状态带版本信封；配置了状态密钥时返回封装后的密文
     pub fn export_sdk_state(&self) -> Result<Vec<u8>> {
+        let provider = self.state_key_provider.read().clone();
+        self.export_sdk_state_with(provider.as_deref())
+    }
+
+    /// 以给定的封装密钥来源导出状态，None 时返回明文
+    fn export_sdk_state_with(&self, provider: Option<&dyn StateKeyProvider>) -> Result<Vec<u8>> {
         let crypto_state = self.crypto.export_state()?;
-        let serialized = serde_json::to_vec(&crypto_state).map_err(|e| {
+        let mut serialized = crate::storage::versioned::encode(&crypto_state).map_err(|e| {
             crate::core::error::XLinkError::serialization_failed(
                 "export_sdk_state",
                 &format!("Failed to serialize SDK state: {}", e),
                 file!(),
             )
         })?;
-        Ok(serialized)
+        match provider {
+            Some(provider) => {
+                let sealed = state_seal::seal(provider, &serialized);
+                serialized.zeroize();
+                sealed
+            }
+            None => Ok(serialized),
+        }
     }
 
     /// 导入 SDK 完整状态（用于设备迁移 UAT-F-024）
+    ///
+    /// 封装后的状态需要先配置相同的状态密钥
     pub fn import_sdk_state(&mut self, data: &[u8]) -> Result<()> {
-        let crypto_state: crate::crypto::engine::CryptoState = serde_json::from_slice(data)
-            .map_err(|e| {
-                crate::core::error::XLinkError::serialization_failed(
-                    "import_sdk_state",
-                    &format!("Failed to deserialize SDK state: {}", e),
+        let opened;
+        let data = if state_seal::is_sealed(data) {
+            let provider = self.state_key_provider.read().clone().ok_or_else(|| {
+                crate::core::error::XLinkError::invalid_input(
+                    "state_key_provider",
+                    "Sealed SDK state requires a state key provider",
                     file!(),
                 )
             })?;
+            opened = zeroize::Zeroizing::new(state_seal::open(provider.as_ref(), data)?);
+            opened.as_slice()
+        } else {
+            data
+        };
+        // 兼容未带版本信封的旧导出文件
+        let crypto_state: crate::crypto::engine::CryptoState = crate::storage::versioned::decode(
+            crate::storage::versioned::RecordKind::CryptoState,
+            data,
+        )
+        .map_err(|e| {
+            crate::core::error::XLinkError::serialization_failed(
+                "import_sdk_state",
+                &format!("Failed to deserialize SDK state: {}", e),
+                file!(),
+            )
+        })?;
         self.crypto = Arc::new(crate::crypto::engine::CryptoEngine::import_state(
             crypto_state,
         )?);
+        self.router.set_message_signer(Some(self.crypto.clone()));
+        self.group_manager
+            .set_message_signer(Some(self.crypto.clone()));
+        self.attach_channel_identity();
         Ok(())
     }
 
+    /// 当前仍在运行的 SDK 托管任务数量（通道接收任务 + 后台任务）
+    pub fn active_task_count(&self) -> usize {
+        let receive = self
+            .receive_tasks
+            .iter()
+            .filter(|entry| !entry.value().is_finished())
+            .count();
+        let background = self
+            .background_tasks
+            .iter()
+            .filter(|entry| !entry.value().is_finished())
+            .count();
+        receive + background
+    }
+
+    /// 列出所有 SDK 托管任务的名称及存活状态（按名称排序）
+    pub fn task_statuses(&self) -> Vec<crate::core::types::TaskStatus> {
+        let mut statuses: Vec<_> = self
+            .receive_tasks
+            .iter()
+            .map(|entry| crate::core::types::TaskStatus {
+                name: format!("receive_{:?}", entry.key()),
+                alive: !entry.value().is_finished(),
+            })
+            .chain(
+                self.background_tasks
+                    .iter()
+                    .map(|entry| crate::core::types::TaskStatus {
+                        name: entry.key().clone(),
+                        alive: !entry.value().is_finished(),
+                    }),
+            )
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
     /// 后台扫描发现模拟 (UAT-F-030)
     pub async fn simulate_background_discovery(&self, device_id: DeviceId) -> Result<()> {
         let discovery = self.discovery_manager.lock().await;
@@ -490,13 +1844,101 @@ impl XLink {
     }
 
     pub async fn stop(&self) {
-        log::info!("Stopping UnifiedPush SDK for device {}", self.device_id);
+        self.stop_with_reason(crate::core::types::ShutdownReason::Requested)
+            .await
+    }
+
+    /// 优雅关闭：停止接受新的发送，在宽限期内等待在途发送及其存储写入完成，
+    /// 超时仍未发出的消息转入待发送队列，随后执行与 `stop` 相同的清理
+    ///
+    /// 需要立即停止时使用 `stop`
+    pub async fn shutdown(self) {
+        self.drain_in_flight_sends().await;
+        self.stop().await;
+    }
+
+    /// 设置优雅关闭配置
+    pub fn set_shutdown_config(&self, config: crate::core::types::ShutdownConfig) {
+        *self.shutdown_config.write() = config;
+    }
 
-        // 停止所有通道接收任务
+    /// 获取当前的优雅关闭配置
+    pub fn shutdown_config(&self) -> crate::core::types::ShutdownConfig {
+        *self.shutdown_config.read()
+    }
+
+    /// 停止接受新的发送并等待在途发送完成，宽限期内未完成的消息写入待发送队列
+    async fn drain_in_flight_sends(&self) {
+        self.accepting_sends
+            .store(false, std::sync::atomic::Ordering::Release);
+        let grace = Duration::from_millis(self.shutdown_config.read().grace_period_ms);
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            // 先注册通知再检查，避免错过检查与等待之间完成的发送
+            let drained = self.in_flight_drained.notified();
+            if self.in_flight_sends.is_empty() {
+                return;
+            }
+            if tokio::time::timeout_at(deadline, drained).await.is_err() {
+                break;
+            }
+        }
+
+        let unsent: Vec<_> = self
+            .in_flight_sends
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        log::warn!(
+            "Shutdown grace period of {}ms elapsed with {} sends in flight",
+            grace.as_millis(),
+            unsent.len()
+        );
+        for (message, ephemeral) in unsent {
+            if ephemeral {
+                continue;
+            }
+            if let Err(e) = self.storage.save_pending_message(&message).await {
+                log::error!(
+                    "Failed to queue in-flight message {} at shutdown: {}",
+                    message.id,
+                    e
+                );
+                self.record_error(&e);
+            }
+        }
+    }
+
+    /// 关闭过程中拒绝新的发送
+    fn ensure_accepting_sends(&self) -> Result<()> {
+        if self
+            .accepting_sends
+            .load(std::sync::atomic::Ordering::Acquire)
+        {
+            Ok(())
+        } else {
+            Err(crate::core::error::XLinkError::invalid_state(
+                "send",
+                "SDK is shutting down",
+                file!(),
+            ))
+        }
+    }
+
+    /// 停止 SDK 并记录停止原因，下次启动据此跳过不必要的崩溃恢复
+    pub async fn stop_with_reason(&self, reason: crate::core::types::ShutdownReason) {
+        log::info!(
+            "Stopping UnifiedPush SDK for device {} ({:?})",
+            self.device_id,
+            reason
+        );
+
+        // 停止所有通道接收任务（含其监管任务）
         for entry in self.receive_tasks.iter() {
             entry.value().abort();
         }
         self.receive_tasks.clear();
+        self.channel_disconnects.clear();
 
         // 停止所有后台任务
         for entry in self.background_tasks.iter() {
@@ -535,11 +1977,28 @@ impl XLink {
 
         // 显式清理 DashMap
         self.rate_limiter.clear();
-        self.plugins.clear();
+        self.send_rate_window.clear();
+        self.send_slots.clear();
+        self.pending_requests.clear();
+        self.pending_replies.clear();
+        self.pending_acks.clear();
+        self.plugins.write().clear();
+
+        if let Err(e) = self.traffic_budget.persist(self.storage.as_ref()).await {
+            log::warn!("Failed to persist traffic budget usage: {}", e);
+        }
 
-        // 清理指标收集器
+        // 清理指标收集器（按配置先保存累计计数）
+        if self.metrics_config.read().persist_across_restarts {
+            if let Err(e) = self.metrics.persist(self.storage.as_ref()).await {
+                log::warn!("Failed to persist metrics: {}", e);
+            }
+        }
         self.metrics.clear();
 
+        // 所有任务停止后写入正常退出标记
+        self.write_shutdown_marker(reason).await;
+
         // 清理路由器中的通道引用，防止内存泄漏
         self.router.clear_channels().await;
 
@@ -547,6 +2006,372 @@ impl XLink {
     }
 
     pub async fn send(&self, recipient: DeviceId, payload: MessagePayload) -> Result<()> {
+        self.send_with_options(recipient, payload, SendOptions::default())
+            .await
+    }
+
+    /// 尽力发送、不持久化的消息（如在线状态、遥测）
+    ///
+    /// 跳过发送前的存储写入与失败后的待发送队列，失败不会重试，崩溃后也不会恢复。
+    /// 适用于丢失可以接受、但不希望承担磁盘 IO 的高频短时数据。
+    pub async fn send_ephemeral(&self, recipient: DeviceId, payload: MessagePayload) -> Result<()> {
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                ephemeral: true,
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// 以指定优先级发送消息，各优先级的发送速率分别限制
+    pub async fn send_with_priority(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        priority: MessagePriority,
+    ) -> Result<()> {
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                priority,
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// 发送带有效期的消息
+    ///
+    /// 超过 `ttl` 仍未送达的消息在崩溃恢复时从待发送队列移除，不再重发。
+    pub async fn send_with_ttl(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        ttl: Duration,
+    ) -> Result<()> {
+        let expires_at = Duration::from_millis(self.clock.now_unix_millis())
+            .saturating_add(ttl)
+            .as_secs();
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                expires_at: Some(expires_at),
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// 以指定优先级发送消息并返回调度记录
+    ///
+    /// 记录包含入队与交给路由的时间、全局调度序号以及取得名额时的有效优先级，
+    /// 可据此观察优先级排队与等待提升的效果
+    pub async fn send_prioritized(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        priority: MessagePriority,
+    ) -> Result<crate::router::send_queue::DispatchRecord> {
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                priority,
+                dispatch_record: Some(tx),
+                ..SendOptions::default()
+            },
+        )
+        .await?;
+        rx.try_recv().map_err(|_| {
+            crate::core::error::XLinkError::invalid_state(
+                "send_prioritized",
+                "send completed without a dispatch record",
+                file!(),
+            )
+        })
+    }
+
+    /// 发送要求有序交付的消息
+    ///
+    /// 仅经有序通道发送，并附带按接收方递增的序号，接收端据此恢复顺序。
+    /// 没有可用的有序通道时返回错误，不会静默降级为无序发送。
+    pub async fn send_ordered(&self, recipient: DeviceId, payload: MessagePayload) -> Result<()> {
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                require_ordered: true,
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
+    /// 发送可在完成前取消的消息，适用于带取消按钮的大文件发送
+    ///
+    /// 返回发送句柄与发送 future：future 在消息发出（大消息为全部分片发出）后完成；
+    /// 通过句柄取消时经流控制停止剩余分片，并清除该消息的持久化副本，future 返回 `SendOutcome::Cancelled`
+    pub fn send_cancellable(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+    ) -> (
+        SendHandle,
+        impl std::future::Future<Output = Result<SendOutcome>> + '_,
+    ) {
+        let message_id = uuid::Uuid::new_v4();
+        let (handle, mut cancel_rx) = SendHandle::new(message_id);
+        let send = async move {
+            let options = SendOptions {
+                message_id,
+                ..SendOptions::default()
+            };
+            // 优先检查取消，发送开始前已取消时不再发出
+            let outcome = tokio::select! {
+                biased;
+                _ = crate::core::send_handle::cancelled(&mut cancel_rx) => {
+                    // 发送中途被取消：尚未发出的消息可能已持久化，一并清除
+                    let _ = self.storage.remove_message(&message_id).await;
+                    let _ = self.storage.remove_pending_message(&message_id).await;
+                    return Ok(SendOutcome::Cancelled);
+                }
+                outcome = self.send_with_outcome(recipient, payload, options) => outcome?,
+            };
+
+            // 流式发送在后台匀速进行，等待全部分片发出或被取消
+            if let SendOutcome::Streamed { stream_id } = outcome {
+                if let Some(mut progress) = self.stream_manager.stream_progress(stream_id) {
+                    loop {
+                        tokio::select! {
+                            changed = progress.changed() => {
+                                if changed.is_err() {
+                                    break;
+                                }
+                            }
+                            _ = crate::core::send_handle::cancelled(&mut cancel_rx) => {
+                                // 停止失败说明流已发完，按发送成功处理
+                                if self.stream_manager.stop_stream(stream_id).is_ok() {
+                                    return Ok(SendOutcome::Cancelled);
+                                }
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(outcome)
+        };
+        (handle, send)
+    }
+
+    /// 续传中断的大消息：只重发接收方尚未确认的分片，返回重发的分片数
+    ///
+    /// `stream_id` 取自 `SendOutcome::Streamed`。分片数据只保留在内存中，SDK 重启后无法续传
+    pub async fn resume_transfer(&self, stream_id: uuid::Uuid) -> Result<u32> {
+        self.ensure_accepting_sends()?;
+        self.stream_manager.resume_transfer(stream_id).await
+    }
+
+    /// 发送请求并等待响应（请求-响应语义）
+    ///
+    /// 请求携带新的关联 ID，接收方通过 `reply` 回复；超时未收到响应返回超时错误，
+    /// 之后到达的响应会被丢弃
+    pub async fn request(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        timeout: Duration,
+    ) -> Result<MessagePayload> {
+        let correlation_id = uuid::Uuid::new_v4();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.pending_requests
+            .insert(correlation_id, (recipient, tx));
+
+        if let Err(e) = self
+            .send_with_options(
+                recipient,
+                payload,
+                SendOptions {
+                    correlation: Some(Correlation::Request(correlation_id)),
+                    ..SendOptions::default()
+                },
+            )
+            .await
+        {
+            self.pending_requests.remove(&correlation_id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(crate::core::error::XLinkError::invalid_state(
+                "request",
+                "pending request cancelled",
+                file!(),
+            )),
+            Err(_) => {
+                self.pending_requests.remove(&correlation_id);
+                Err(crate::core::error::XLinkError::timeout(
+                    format!("request {} to {}", correlation_id, recipient),
+                    timeout.as_millis() as u64,
+                    file!(),
+                ))
+            }
+        }
+    }
+
+    /// 发送要求确认的点对点消息，等待接收方交付给应用后的确认
+    ///
+    /// 消息未能发出时返回 `AckStatus::Failed`（非尽力发送的消息已进入待发送队列）；
+    /// `timeout` 内未收到确认返回 `AckStatus::TimedOut`，此时消息仍可能稍后到达
+    pub async fn send_with_ack(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        timeout: Duration,
+    ) -> Result<crate::core::types::AckStatus> {
+        use crate::core::types::AckStatus;
+
+        let message_id = uuid::Uuid::new_v4();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.pending_acks.insert(message_id, (recipient, tx));
+
+        if let Err(e) = self
+            .send_with_options(
+                recipient,
+                payload,
+                SendOptions {
+                    message_id,
+                    require_ack: true,
+                    ..SendOptions::default()
+                },
+            )
+            .await
+        {
+            self.pending_acks.remove(&message_id);
+            log::warn!("Acknowledged send {} failed: {}", message_id, e);
+            return Ok(AckStatus::Failed);
+        }
+
+        let status = match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(())) => AckStatus::Delivered,
+            Ok(Err(_)) => AckStatus::Failed,
+            Err(_) => AckStatus::TimedOut,
+        };
+        self.pending_acks.remove(&message_id);
+        if status == AckStatus::Delivered {
+            match self.storage.get_delivery_receipt(&message_id).await {
+                Ok(Some(mut receipt)) => {
+                    receipt.acknowledged_at = Some(self.clock.now_unix_secs());
+                    self.save_delivery_receipt(receipt).await;
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to load delivery receipt {}: {}", message_id, e),
+            }
+        }
+        Ok(status)
+    }
+
+    /// 查询消息的投递回执，消息未成功发出或回执已按保留天数清理时返回 None
+    pub async fn get_delivery_receipt(
+        &self,
+        message_id: uuid::Uuid,
+    ) -> Result<Option<crate::core::types::DeliveryReceipt>> {
+        self.storage.get_delivery_receipt(&message_id).await
+    }
+
+    /// 保存投递回执，失败只记录日志，不影响已成功的发送
+    async fn save_delivery_receipt(&self, receipt: crate::core::types::DeliveryReceipt) {
+        if let Err(e) = self.storage.save_delivery_receipt(&receipt).await {
+            log::warn!(
+                "Failed to save delivery receipt for {}: {}",
+                receipt.message_id,
+                e
+            );
+            self.record_error(&e);
+        }
+    }
+
+    /// 计入错误统计
+    fn record_error(&self, error: &crate::core::error::XLinkError) {
+        self.error_stats.lock().record(error);
+    }
+
+    /// 回复收到的请求，响应按关联 ID 路由回请求方
+    ///
+    /// 每个请求只能回复一次；未知或已过期的关联 ID 返回状态错误
+    pub async fn reply(&self, correlation_id: uuid::Uuid, payload: MessagePayload) -> Result<()> {
+        let (_, (requester, _)) =
+            self.pending_replies
+                .remove(&correlation_id)
+                .ok_or_else(|| {
+                    crate::core::error::XLinkError::invalid_state(
+                        "reply".to_string(),
+                        format!("no pending request with correlation id {}", correlation_id),
+                        file!(),
+                    )
+                })?;
+        self.send_with_options(
+            requester,
+            payload,
+            SendOptions {
+                correlation: Some(Correlation::Reply(correlation_id)),
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
+    async fn send_with_options(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        options: SendOptions,
+    ) -> Result<()> {
+        self.send_with_outcome(recipient, payload, options)
+            .await
+            .map(|_| ())
+    }
+
+    async fn send_with_outcome(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        options: SendOptions,
+    ) -> Result<SendOutcome> {
+        let result = self
+            .try_send_with_outcome(recipient, payload, options)
+            .await;
+        if let Err(e) = &result {
+            self.record_error(e);
+        }
+        result
+    }
+
+    async fn try_send_with_outcome(
+        &self,
+        recipient: DeviceId,
+        payload: MessagePayload,
+        options: SendOptions,
+    ) -> Result<SendOutcome> {
+        let SendOptions {
+            priority,
+            require_ordered,
+            correlation,
+            ephemeral,
+            topic,
+            message_id,
+            require_ack,
+            expires_at,
+            dispatch_record,
+        } = options;
+        self.ensure_accepting_sends()?;
         log::info!(
             "Sending message from {} to {} with payload: {:?}",
             self.device_id,
@@ -554,14 +2379,35 @@ impl XLink {
             payload
         );
 
-        // DoS 防护：限制发送速率，每秒最多 100 条消息
+        // 电量低于下限时只放行高优先级消息，为重要消息保留电量
+        let local_caps = self.cap_manager.get_local_caps();
+        let battery_policy = *self.battery_policy.read();
+        if battery_policy.blocks(priority, local_caps.battery_level, local_caps.is_charging) {
+            return Err(crate::core::error::XLinkError::resource_exhausted(
+                format!("battery too low for {:?} priority send", priority),
+                local_caps.battery_level.unwrap_or(0).into(),
+                battery_policy.battery_floor.unwrap_or(0).into(),
+                file!(),
+            )
+            .with_retry_suggestion(crate::core::error::RetrySuggestion::Retryable {
+                max_attempts: 3,
+                base_delay_ms: BATTERY_FLOOR_RETRY_DELAY_MS,
+            }));
+        }
+
+        // DoS 防护：按优先级分别限制发送速率
         {
-            let now = Instant::now();
-            let rate_limit_exceeded = {
+            let now = self.clock.instant_now();
+            let rate_key = (self.device_id, priority);
+            let limit = self
+                .rate_limit_config
+                .read()
+                .outbound_limit(self.rate_limits.read().limit_for(priority));
+            let exceeded_limit = limit.filter(|&limit| {
                 // 使用 try_get_mut 策略，添加重试机制
                 let mut result = false;
                 for _ in 0..RATE_LIMIT_MAX_RETRIES {
-                    match self.rate_limiter.try_get_mut(&self.device_id) {
+                    match self.rate_limiter.try_get_mut(&rate_key) {
                         dashmap::try_result::TryResult::Present(mut entry) => {
                             let (last_reset, count) = entry.value_mut();
                             let duration = now.saturating_duration_since(*last_reset);
@@ -571,13 +2417,13 @@ impl XLink {
                                 result = false;
                             } else {
                                 *count = count.saturating_add(1);
-                                result = *count > RATE_LIMIT_MAX_COUNT;
+                                result = *count > limit;
                             }
                             break;
                         }
                         dashmap::try_result::TryResult::Absent => {
                             // 设备未注册，创建新条目
-                            self.rate_limiter.insert(self.device_id, (now, 1));
+                            self.rate_limiter.insert(rate_key, (now, 1));
                             result = false;
                             break;
                         }
@@ -592,50 +2438,159 @@ impl XLink {
                     log::warn!("Rate limiter lock contention - rejecting for safety");
                 }
                 result
-            };
+            });
 
-            if rate_limit_exceeded {
+            if let Some(limit) = exceeded_limit {
                 log::warn!(
-                    "DoS Protection: Send rate limit exceeded for device {}",
+                    "DoS Protection: {:?} send rate limit exceeded for device {}",
+                    priority,
                     self.device_id
                 );
                 return Err(crate::core::error::XLinkError::resource_exhausted(
-                    format!("Send rate limit exceeded for device {}", self.device_id),
-                    (RATE_LIMIT_MAX_COUNT + 1).into(),
-                    RATE_LIMIT_MAX_COUNT.into(),
+                    format!(
+                        "{:?} send rate limit exceeded for device {}",
+                        priority, self.device_id
+                    ),
+                    (limit + 1).into(),
+                    limit.into(),
                     file!(),
                 ));
             }
+
+            // 各优先级合计的出站总量，Critical 消息先使用独立的小额度
+            let total_limit = self.rate_limit_config.read().outbound_limit(u32::MAX);
+            if let Some(limit) = total_limit {
+                let reserve = self.rate_limits.read().critical_reserve;
+                if !self.send_rate_window.admit(priority, limit, reserve, now) {
+                    log::warn!(
+                        "DoS Protection: total send rate limit exceeded for device {}",
+                        self.device_id
+                    );
+                    return Err(crate::core::error::XLinkError::resource_exhausted(
+                        format!(
+                            "total send rate limit exceeded for device {}",
+                            self.device_id
+                        ),
+                        (limit + 1).into(),
+                        limit.into(),
+                        file!(),
+                    ));
+                }
+            }
         }
 
+        // 限制同一对端的在途发送数，超出的发送排队等待
+        let _slot = self.acquire_send_slot(recipient).await?;
+
         // F10: 性能优化 - 增加发送指标记录
         self.metrics.record_send(ChannelType::Internet, 0); // 提前记录，实际发送后会再次记录准确值
 
-        // 检查是否是流式传输
-        if let MessagePayload::Binary(data) = &payload {
-            if data.len() > 1024 * 32 {
-                // 如果大于 32KB，自动走流式传输
-                log::info!("Using stream transmission for large message");
-                self.stream_manager
-                    .send_video_stream(recipient, data.clone(), None)
-                    .await?;
-                return Ok(());
-            }
+        let mut message = Message::new(self.device_id, recipient, payload);
+        message.id = message_id;
+        message.timestamp = self.clock.now_unix_secs();
+        message.priority = priority;
+        message.require_ordered = require_ordered;
+        message.topic = topic;
+        message.require_ack = require_ack;
+        message.expires_at = expires_at;
+        match correlation {
+            Some(Correlation::Request(id)) => message.correlation_id = Some(id),
+            Some(Correlation::Reply(id)) => message.in_reply_to = Some(id),
+            None => {}
         }
 
-        let message = Message::new(self.device_id, recipient, payload);
-        log::info!("Created message: {}", message.id);
+        // 插件按注册顺序检查或改写出站消息，被丢弃的消息不发出也不报错
+        if !apply_plugins(&self.plugins, &mut message, false).await? {
+            return Ok(SendOutcome::Dropped);
+        }
 
-        // F10: 性能优化 - 对于高频小消息，考虑异步保存存储或批量保存
-        // 这里暂时保持同步保存以确保可靠性，但在高负载下可能是瓶颈
-        self.storage.save_message(&message).await?;
-        log::info!("Message saved to storage");
+        // 大二进制负载按配置压缩，压缩后以 Compressed 负载发出
+        if let MessagePayload::Binary(data) = &message.payload {
+            let config = *self.compression_config.read();
+            if let Some((algorithm, data)) =
+                crate::core::compression::compress_payload(data, &config)
+            {
+                message.payload = MessagePayload::Compressed { algorithm, data };
+            }
+        }
 
-        let channel = match self.router.select_channel(&message).await {
-            Ok(ch) => ch,
-            Err(e) if e.code().0 == 105 => {
-                // 如果没有找到路由，可能是因为还没有对方的 ChannelState 信息
-                // 在测试环境中，我们自动为目标设备添加默认的 ChannelState
+        // Ordered 交付模式下的消息同样分配序号，但不限制通道
+        let sequenced = require_ordered
+            || *self.delivery_mode.read() == crate::core::types::DeliveryMode::Ordered;
+
+        // 检查是否是流式传输（有序、请求-响应、带主题与要求确认的消息不分片，避免丢失序号、关联 ID、主题或确认）
+        // 阈值按压缩后的大小判断
+        let threshold = self.stream_config.read().auto_stream_threshold_bytes;
+        let stream_data = match &message.payload {
+            MessagePayload::Binary(data) => Some((data, None)),
+            MessagePayload::Compressed { algorithm, data } => Some((data, Some(*algorithm))),
+            _ => None,
+        };
+        let chunkable = !sequenced
+            && message.correlation_id.is_none()
+            && message.in_reply_to.is_none()
+            && message.topic.is_none()
+            && !message.require_ack;
+        match (stream_data, threshold) {
+            (Some((data, compression)), Some(threshold)) if chunkable && data.len() > threshold => {
+                // 超过阈值（默认 32KB），自动走流式传输
+                log::info!("Using stream transmission for large message");
+                self.admit_streamed_send(&message)?;
+                let config = crate::media::stream_manager::VideoConfig {
+                    compression,
+                    ..Default::default()
+                };
+                let stream_id = self
+                    .stream_manager
+                    .send_video_stream(recipient, data.clone(), Some(config))
+                    .await?;
+                self.metrics.record_streamed_send();
+                if ephemeral {
+                    self.metrics.record_ephemeral_send();
+                }
+                return Ok(SendOutcome::Streamed { stream_id });
+            }
+            (Some((data, compression)), None)
+                if chunkable && data.len() > crate::media::stream_manager::CHUNK_SIZE =>
+            {
+                // 关闭自动流式传输时，超过单个分片的负载经可靠分片发送：等待接收方确认全部分片，
+                // 确认超时的分片重发，不走尽力而为的视频帧路径
+                log::info!("Using acknowledged chunked transmission for large message");
+                self.admit_streamed_send(&message)?;
+                let stream_id = self
+                    .stream_manager
+                    .send_reliable_stream(recipient, data.clone(), compression)
+                    .await?;
+                self.metrics.record_chunked_send();
+                if ephemeral {
+                    self.metrics.record_ephemeral_send();
+                }
+                return Ok(SendOutcome::Chunked { stream_id });
+            }
+            _ => {}
+        }
+        log::info!("Created message: {}", message.id);
+        // 登记为在途发送，优雅关闭据此等待发送与存储写入完成
+        self.in_flight_sends
+            .insert(message.id, (message.clone(), ephemeral));
+        let _in_flight = InFlightSend {
+            sends: self.in_flight_sends.clone(),
+            drained: self.in_flight_drained.clone(),
+            message_id: message.id,
+        };
+
+        // F10: 性能优化 - 对于高频小消息，考虑异步保存存储或批量保存
+        // 这里暂时保持同步保存以确保可靠性，但在高负载下可能是瓶颈
+        if !ephemeral {
+            self.save_outgoing_message(&message).await?;
+            log::info!("Message saved to storage");
+        }
+
+        let channel = match self.router.select_channel(&message).await {
+            Ok(ch) => ch,
+            Err(e) if e.code().0 == 105 && self.routing_config.read().auto_seed_channel_state => {
+                // 如果没有找到路由，可能是因为还没有对方的 ChannelState 信息
+                // 仅在测试环境显式开启时，自动为目标设备添加默认的 ChannelState
                 log::warn!(
                     "No route found for {}, adding default test state",
                     recipient
@@ -650,10 +2605,7 @@ impl XLink {
                         signal_strength: Some(80),
                         network_type: crate::core::types::NetworkType::WiFi,
                         failure_count: 0,
-                        last_heartbeat: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_else(|_| Duration::from_secs(0))
-                            .as_secs(),
+                        last_heartbeat: self.clock.now_unix_secs(),
                         distance_meters: Some(10.0), // 默认近距离
                     };
                     self.cap_manager
@@ -666,28 +2618,118 @@ impl XLink {
         };
         log::info!("Selected channel: {:?}", channel.channel_type());
 
-        match channel.send(message.clone()).await {
+        // 省流模式下计量网络用量耗尽时只放行紧急消息
+        let metered = self
+            .router
+            .admit_metered(channel.channel_type(), priority)?;
+        self.events
+            .publish(crate::core::events::SdkEvent::ChannelSelected {
+                message_id: message.id,
+                recipient,
+                channel: channel.channel_type(),
+            });
+
+        // 选路成功后再分配序号，避免路由失败留下序号空洞
+        if sequenced {
+            let mut next = self.send_sequences.entry(recipient).or_insert(0);
+            message.sequence = Some(*next);
+            message.sequence_epoch = Some(self.sequence_epoch);
+            *next += 1;
+        }
+        self.crypto.sign_message(&mut message);
+
+        // 记录实际发出的消息（含序号），应答有序消息的重传请求时从中查找
+        let journal = self.journal.read().clone();
+        if let Some(journal) = journal {
+            if let Err(e) = journal
+                .record(
+                    crate::storage::journal::JournalDirection::Outbound,
+                    Some(channel.channel_type()),
+                    &message,
+                )
+                .await
+            {
+                log::warn!("Failed to journal outbound message {}: {}", message.id, e);
+            }
+        }
+
+        // 经路由的发送队列取得通道名额：按优先级排队，排队过久的低优先级发送逐级提升
+        let permit = self
+            .router
+            .acquire_send_permit(channel.channel_type(), priority)
+            .await;
+        if let Some(tx) = dispatch_record {
+            let _ = tx.send(permit.record());
+        }
+        // 尽力发送的消息失败即丢弃，不做重试
+        let result = if !ephemeral && self.send_retry.read().retry_transient_failures {
+            crate::core::retry::retry_with_suggestion(|| channel.send(message.clone())).await
+        } else {
+            channel.send(message.clone()).await
+        };
+        drop(permit);
+        match result {
             Ok(_) => {
                 log::info!("Message sent successfully");
+                self.events
+                    .publish(crate::core::events::SdkEvent::MessageSent {
+                        message_id: message.id,
+                        recipient,
+                        channel: channel.channel_type(),
+                    });
                 // 发送成功，记录字节数
                 let bytes = match &message.payload {
                     MessagePayload::Text(t) => t.len() as u64,
                     MessagePayload::Binary(b) => b.len() as u64,
+                    MessagePayload::Compressed { data, .. } => data.len() as u64,
                     _ => 0,
                 };
                 self.metrics.record_send(channel.channel_type(), bytes);
+                if metered && self.traffic_budget.record(bytes) {
+                    if let Err(e) = self.traffic_budget.persist(self.storage.as_ref()).await {
+                        log::warn!("Failed to persist traffic budget usage: {}", e);
+                    }
+                }
+                self.cap_manager
+                    .record_channel_result(recipient, channel.channel_type(), true);
+                if ephemeral {
+                    self.metrics.record_ephemeral_send();
+                    return Ok(SendOutcome::Sent { message_id });
+                }
                 self.storage.remove_message(&message.id).await?;
 
                 // 发送成功，也从待发送队列中移除（如果存在）
                 let _ = self.storage.remove_pending_message(&message.id).await;
-                Ok(())
+                self.save_delivery_receipt(crate::core::types::DeliveryReceipt {
+                    message_id: message.id,
+                    recipient,
+                    channel: channel.channel_type(),
+                    delivered_at: self.clock.now_unix_secs(),
+                    acknowledged_at: None,
+                })
+                .await;
+                Ok(SendOutcome::Sent { message_id })
             }
             Err(e) => {
                 log::error!("Failed to send message: {}", e);
+                self.cap_manager
+                    .record_channel_result(recipient, channel.channel_type(), false);
+                if e.code().0 == 202 {
+                    self.report_channel_disconnected(channel.channel_type());
+                }
+                self.events
+                    .publish(crate::core::events::SdkEvent::MessageSendFailed {
+                        message_id: message.id,
+                        recipient,
+                        reason: e.to_string(),
+                    });
 
-                // 发送失败，保存到待发送队列用于崩溃恢复
-                if let Err(save_err) = self.storage.save_pending_message(&message).await {
+                // 发送失败，保存到待发送队列用于崩溃恢复（尽力发送的消息直接丢弃）
+                if ephemeral {
+                    self.metrics.record_ephemeral_dropped();
+                } else if let Err(save_err) = self.storage.save_pending_message(&message).await {
                     log::error!("Failed to save pending message for recovery: {}", save_err);
+                    self.record_error(&save_err);
                 } else {
                     log::info!("Saved message {} to pending queue for recovery", message.id);
                 }
@@ -697,32 +2739,69 @@ impl XLink {
         }
     }
 
+    /// 流式发送前按预计使用的通道检查计量流量预算，分片发出后各自计入用量
+    fn admit_streamed_send(&self, message: &Message) -> Result<()> {
+        if let Some(ctype) = self.router.explain_route(message).chosen {
+            self.router.admit_metered(ctype, message.priority)?;
+        }
+        Ok(())
+    }
+
     // F4: 群组 API
+
+    /// 创建群组，成员的公钥需先通过 `register_device_key` 注册
+    ///
+    /// 缺少公钥的成员使创建失败，错误的 `missing_member_keys()` 列出这些设备
     pub async fn create_group(
         &self,
         name: String,
         members: Vec<DeviceId>,
     ) -> Result<crate::core::types::GroupId> {
-        // 自动为所有成员（包括自己）注册随机公钥以满足 TreeKEM 要求
-        // 在真实场景中，这些公钥应该通过密钥交换或预共享获取
-        use rand::rngs::OsRng;
-        use x25519_dalek::StaticSecret;
+        self.prepare_group_keys(&members)?;
+        let group = self.group_manager.create_group(name, members).await?;
+        Ok(group.id)
+    }
 
-        // 注册自己的公钥
+    /// 只以已注册公钥的成员创建群组，返回群组 ID 与被跳过的成员
+    pub async fn create_group_partial(
+        &self,
+        name: String,
+        members: Vec<DeviceId>,
+    ) -> Result<(crate::core::types::GroupId, Vec<DeviceId>)> {
+        self.prepare_group_keys(&members)?;
+        let (group, skipped) = self
+            .group_manager
+            .create_group_partial(name, members)
+            .await?;
+        Ok((group.id, skipped))
+    }
+
+    /// 注册本机公钥；开启测试用占位公钥时为缺少公钥的成员注册随机公钥
+    fn prepare_group_keys(&self, members: &[DeviceId]) -> Result<()> {
         self.group_manager
             .register_device_key(self.device_id, self.crypto.public_key())?;
 
-        // 为其他成员注册随机公钥
-        for member_id in &members {
-            if *member_id != self.device_id {
+        if self.group_key_config.read().placeholder_keys_for_testing {
+            use rand::rngs::OsRng;
+            use x25519_dalek::StaticSecret;
+            for member_id in self.group_manager.missing_device_keys(members) {
+                log::warn!("Registering placeholder public key for {}", member_id);
                 let secret = StaticSecret::random_from_rng(OsRng);
-                let public = PublicKey::from(&secret);
-                self.group_manager.register_device_key(*member_id, public)?;
+                self.group_manager
+                    .register_device_key(member_id, PublicKey::from(&secret))?;
             }
         }
+        Ok(())
+    }
 
-        let group = self.group_manager.create_group(name, members).await?;
-        Ok(group.id)
+    /// 设置群组密钥配置
+    pub fn set_group_key_config(&self, config: crate::core::types::GroupKeyConfig) {
+        *self.group_key_config.write() = config;
+    }
+
+    /// 获取当前的群组密钥配置
+    pub fn group_key_config(&self) -> crate::core::types::GroupKeyConfig {
+        *self.group_key_config.read()
     }
 
     pub async fn send_to_group(
@@ -730,15 +2809,411 @@ impl XLink {
         group_id: crate::core::types::GroupId,
         payload: MessagePayload,
     ) -> Result<()> {
-        self.group_manager.broadcast(group_id, payload).await?;
+        self.send_to_group_members(
+            group_id,
+            payload,
+            crate::group::manager::BroadcastTargets::All,
+        )
+        .await?;
         Ok(())
     }
 
+    /// 只向在线成员发送群组消息，离线成员的消息放入待发送队列
+    ///
+    /// 返回结果列出被跳过的成员
+    pub async fn send_to_group_online(
+        &self,
+        group_id: crate::core::types::GroupId,
+        payload: MessagePayload,
+    ) -> Result<crate::group::manager::OnlineBroadcast> {
+        self.send_to_group_members(
+            group_id,
+            payload,
+            crate::group::manager::BroadcastTargets::OnlineOnly,
+        )
+        .await
+    }
+
+    /// 向群组中的部分成员发送消息，见 [`GroupManager::broadcast_to`]
+    ///
+    /// 因离线被跳过的成员（仅 `OnlineOnly`）与被扇出策略延后的成员的消息放入待发送队列
+    pub async fn send_to_group_members(
+        &self,
+        group_id: crate::core::types::GroupId,
+        payload: MessagePayload,
+        targets: crate::group::manager::BroadcastTargets,
+    ) -> Result<crate::group::manager::OnlineBroadcast> {
+        self.ensure_accepting_sends()?;
+        let outcome = self
+            .group_manager
+            .broadcast_to(group_id, payload, targets)
+            .await?;
+        for message in &outcome.deferred {
+            if let Err(e) = self.storage.save_pending_message(message).await {
+                log::error!(
+                    "Failed to queue group message {} for deferred member {}: {}",
+                    message.id,
+                    message.recipient,
+                    e
+                );
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// 设置群组广播的远程扇出策略（限制计费网络上的发送量）
+    pub fn set_group_fanout_policy(&self, policy: crate::group::manager::BroadcastFanoutPolicy) {
+        self.group_manager.set_fanout_policy(policy);
+    }
+
+    /// 占用对端的一个在途发送名额，名额随返回值释放
+    async fn acquire_send_slot(&self, recipient: DeviceId) -> Result<SendSlot> {
+        let config = *self.send_concurrency.read();
+        let semaphore = self
+            .send_slots
+            .entry(recipient)
+            .or_insert_with(|| Arc::new(tokio::sync::Semaphore::new(config.max_in_flight.max(1))))
+            .clone();
+        let permit = match tokio::time::timeout(
+            Duration::from_millis(config.queue_timeout_ms),
+            semaphore.acquire_owned(),
+        )
+        .await
+        {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_)) => {
+                return Err(crate::core::error::XLinkError::invalid_state(
+                    "acquire send slot",
+                    "send slots closed",
+                    file!(),
+                ))
+            }
+            Err(_) => {
+                log::warn!(
+                    "Send to {} waited {}ms for an in-flight slot",
+                    recipient,
+                    config.queue_timeout_ms
+                );
+                return Err(crate::core::error::XLinkError::timeout(
+                    format!("waiting for send slot to {}", recipient),
+                    config.queue_timeout_ms,
+                    file!(),
+                ));
+            }
+        };
+        self.metrics.record_send_started(recipient);
+        Ok(SendSlot {
+            _permit: permit,
+            recipient,
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// 设置每个对端的并发发送限制
+    ///
+    /// 新限制对之后首次出现的对端立即生效；已有对端在其在途发送完成后切换到新限制
+    pub fn set_peer_send_concurrency(&self, config: crate::core::types::PeerSendConcurrency) {
+        *self.send_concurrency.write() = config;
+        self.send_slots.clear();
+    }
+
+    /// 设置发送端负载压缩配置，对之后的发送生效
+    pub fn set_compression_config(&self, config: crate::core::types::CompressionConfig) {
+        *self.compression_config.write() = config;
+    }
+
+    /// 获取当前的负载压缩配置
+    pub fn compression_config(&self) -> crate::core::types::CompressionConfig {
+        *self.compression_config.read()
+    }
+
+    /// 设置自动流式传输阈值，对之后的发送生效
+    pub fn set_stream_config(&self, config: crate::core::types::StreamConfig) {
+        *self.stream_config.write() = config;
+    }
+
+    /// 获取当前的自动流式传输配置
+    pub fn stream_config(&self) -> crate::core::types::StreamConfig {
+        *self.stream_config.read()
+    }
+
+    /// 设置流数据交付方式（SDK 内部重组或原样交付分片）
+    pub fn set_stream_delivery_config(&self, config: crate::core::types::StreamDeliveryConfig) {
+        *self.stream_delivery.write() = config;
+    }
+
+    /// 获取当前的流数据交付配置
+    pub fn stream_delivery_config(&self) -> crate::core::types::StreamDeliveryConfig {
+        *self.stream_delivery.read()
+    }
+
+    /// 设置发送路由配置（如找不到路由时是否伪造默认通道状态）
+    pub fn set_routing_config(&self, config: crate::core::types::RoutingConfig) {
+        *self.routing_config.write() = config;
+    }
+
+    /// 获取当前的发送路由配置
+    pub fn routing_config(&self) -> crate::core::types::RoutingConfig {
+        *self.routing_config.read()
+    }
+
+    /// 设置发送重试配置（如通道暂时断开时是否自动退避重试）
+    pub fn set_send_retry_config(&self, config: crate::core::types::SendRetryConfig) {
+        *self.send_retry.write() = config;
+    }
+
+    /// 获取当前的发送重试配置
+    pub fn send_retry_config(&self) -> crate::core::types::SendRetryConfig {
+        *self.send_retry.read()
+    }
+
+    /// 设置通道断线重连配置，新的退避参数从下一轮重连开始生效
+    pub fn set_reconnect_config(&self, config: crate::core::types::ReconnectConfig) {
+        *self.reconnect_config.write() = config;
+    }
+
+    /// 获取当前的通道断线重连配置
+    pub fn reconnect_config(&self) -> crate::core::types::ReconnectConfig {
+        *self.reconnect_config.read()
+    }
+
+    /// 通知通道监管任务该通道已断开，由其按退避重连；SDK 未启动时无效
+    fn report_channel_disconnected(&self, ctype: ChannelType) {
+        if let Some(disconnected) = self.channel_disconnects.get(&ctype) {
+            log::warn!(
+                "Channel {:?} reported disconnected, scheduling reconnect",
+                ctype
+            );
+            disconnected.notify_one();
+        }
+    }
+
+    /// 设置路由评分配置（如近期失败惩罚的时间窗口）
+    pub fn set_scorer_config(&self, config: crate::router::scoring::ScorerConfig) {
+        self.router.set_scorer_config(config);
+    }
+
+    /// 设置通道熔断配置（连续失败阈值与冷却时间）
+    pub fn set_circuit_breaker_config(&self, config: crate::router::breaker::CircuitBreakerConfig) {
+        self.cap_manager.set_circuit_breaker_config(config);
+    }
+
+    /// 对端通道当前的熔断状态
+    pub fn breaker_state(
+        &self,
+        peer: DeviceId,
+        channel: ChannelType,
+    ) -> crate::router::breaker::BreakerState {
+        self.cap_manager.breaker_state(&peer, &channel)
+    }
+
+    /// 设置发送队列配置（每种通道的最大在途发送数），群组广播与流媒体帧经该队列发送
+    pub fn set_send_queue_config(&self, config: crate::router::send_queue::SendQueueConfig) {
+        self.router.set_send_queue_config(config);
+    }
+
+    /// 获取当前的发送队列配置
+    pub fn send_queue_config(&self) -> crate::router::send_queue::SendQueueConfig {
+        self.router.send_queue_config()
+    }
+
+    /// 设置自定义路由策略替代内置评分，`None` 恢复内置评分
+    pub fn set_routing_strategy(
+        &self,
+        strategy: Option<Arc<dyn crate::router::strategy::RoutingStrategy>>,
+    ) {
+        self.router.set_strategy(strategy);
+    }
+
+    /// 设置流量类别到通道的固定映射，替换已有映射；固定通道不可用时回退到评分选路
+    pub fn set_traffic_class_channels(
+        &self,
+        pins: HashMap<crate::core::types::TrafficClass, ChannelType>,
+    ) {
+        self.router.set_class_channels(pins);
+    }
+
+    /// 设置指标配置（如是否跨重启保留累计计数）
+    pub fn set_metrics_config(&self, config: crate::core::types::MetricsConfig) {
+        *self.metrics_config.write() = config;
+    }
+
+    /// 设置电量保护策略，立即对后续发送生效
+    pub fn set_battery_policy(&self, policy: crate::core::types::BatteryPolicy) {
+        *self.battery_policy.write() = policy;
+    }
+
+    /// 设置通道预热配置，在下次 `start` 时生效
+    pub fn set_channel_warmup_config(&self, config: crate::core::types::ChannelWarmupConfig) {
+        *self.channel_warmup.write() = config;
+    }
+
+    /// 获取当前的通道预热配置
+    pub fn channel_warmup_config(&self) -> crate::core::types::ChannelWarmupConfig {
+        *self.channel_warmup.read()
+    }
+
+    /// 获取当前的电量保护策略
+    pub fn battery_policy(&self) -> crate::core::types::BatteryPolicy {
+        *self.battery_policy.read()
+    }
+
+    /// 获取当前的指标配置
+    pub fn metrics_config(&self) -> crate::core::types::MetricsConfig {
+        *self.metrics_config.read()
+    }
+
+    /// 获取当前的对端并发发送限制
+    pub fn peer_send_concurrency(&self) -> crate::core::types::PeerSendConcurrency {
+        *self.send_concurrency.read()
+    }
+
+    /// 设置按优先级划分的收发速率限制
+    pub fn set_priority_rate_limits(&self, limits: crate::core::types::PriorityRateLimits) {
+        *self.rate_limits.write() = limits;
+    }
+
+    /// 设置收发两个方向的速率限制，速率为 0 时关闭该方向的限流
+    pub fn set_rate_limit_config(&self, config: crate::core::types::RateLimitConfig) {
+        *self.rate_limit_config.write() = config;
+    }
+
+    /// 获取当前的收发速率限制
+    pub fn rate_limit_config(&self) -> crate::core::types::RateLimitConfig {
+        *self.rate_limit_config.read()
+    }
+
+    /// 获取当前的优先级速率限制
+    pub fn priority_rate_limits(&self) -> crate::core::types::PriorityRateLimits {
+        *self.rate_limits.read()
+    }
+
+    /// 设置入站消息的最大年龄策略
+    pub fn set_message_age_config(&self, config: crate::core::types::MessageAgeConfig) {
+        *self.message_age.write() = config;
+    }
+
+    /// 获取当前的最大消息年龄策略
+    pub fn message_age_config(&self) -> crate::core::types::MessageAgeConfig {
+        *self.message_age.read()
+    }
+
+    /// 设置接收端重排缓冲区的大小与溢出策略
+    pub fn set_reorder_buffer_config(&self, config: crate::core::types::ReorderBufferConfig) {
+        *self.reorder_config.write() = config;
+    }
+
+    /// 获取当前的重排缓冲区配置
+    pub fn reorder_buffer_config(&self) -> crate::core::types::ReorderBufferConfig {
+        *self.reorder_config.read()
+    }
+
+    /// 设置点对点消息的交付顺序，对之后的发送生效
+    pub fn set_delivery_mode(&self, mode: crate::core::types::DeliveryMode) {
+        *self.delivery_mode.write() = mode;
+    }
+
+    /// 获取当前的交付顺序
+    pub fn delivery_mode(&self) -> crate::core::types::DeliveryMode {
+        *self.delivery_mode.read()
+    }
+
+    /// 设置接收端跨通道去重的记录容量与有效期
+    pub fn set_dedup_config(&self, config: crate::core::types::DedupConfig) {
+        self.dedup.lock().set_config(config);
+    }
+
+    /// 获取当前的接收端去重配置
+    pub fn dedup_config(&self) -> crate::core::types::DedupConfig {
+        self.dedup.lock().config()
+    }
+
+    /// 设置接收消息时间戳的时钟偏差容忍配置
+    pub fn set_clock_skew_config(&self, config: crate::core::types::ClockSkewConfig) {
+        *self.clock_skew.write() = config;
+    }
+
+    /// 获取当前的时钟偏差容忍配置
+    pub fn clock_skew_config(&self) -> crate::core::types::ClockSkewConfig {
+        *self.clock_skew.read()
+    }
+
+    /// 设置单次群组广播的最大并发发送数
+    pub fn set_group_broadcast_concurrency(&self, limit: usize) {
+        self.group_manager.set_broadcast_concurrency(limit);
+    }
+
+    /// 设置群组广播去重窗口（None 关闭，默认关闭）
+    pub fn set_group_broadcast_dedup(&self, window: Option<Duration>) {
+        self.group_manager.set_broadcast_dedup_window(window);
+    }
+
+    /// 设置未加入群组的消息的处理策略（默认丢弃），处理时发布 `GroupEvent::UnknownGroupMessage`
+    pub fn set_unknown_group_policy(&self, policy: crate::group::manager::UnknownGroupPolicy) {
+        self.group_manager.set_unknown_group_policy(policy);
+    }
+
+    /// 获取当前的未知群组消息处理策略
+    pub fn unknown_group_policy(&self) -> crate::group::manager::UnknownGroupPolicy {
+        self.group_manager.unknown_group_policy()
+    }
+
     pub fn register_device_key(&self, device_id: DeviceId, public_key: PublicKey) -> Result<()> {
         self.group_manager
             .register_device_key(device_id, public_key)
     }
 
+    /// 列出受信任的对端公钥：(设备 ID, 公钥, 会话是否经过签名认证)
+    pub fn trusted_peers(&self) -> Vec<(DeviceId, PublicKey, bool)> {
+        let mut peers: Vec<_> = self
+            .group_manager
+            .registered_device_keys()
+            .into_iter()
+            .filter(|(device_id, _)| *device_id != self.device_id)
+            .filter_map(|(device_id, key_bytes)| {
+                let key: [u8; 32] = key_bytes.try_into().ok()?;
+                let verified = self
+                    .crypto
+                    .is_session_authenticated(&device_id)
+                    .unwrap_or(false);
+                Some((device_id, PublicKey::from(key), verified))
+            })
+            .collect();
+        peers.sort_by_key(|(device_id, _, _)| device_id.0);
+        peers
+    }
+
+    /// 撤销对端公钥
+    ///
+    /// 先将该设备从本地担任管理员的群组中移除并轮换这些群组的密钥，再从 TreeKEM 引擎
+    /// 移除公钥、丢弃加密会话并取消固定其身份公钥与静态公钥，之后与该设备通信需重新
+    /// 注册公钥并握手。撤销操作会记录到审计日志。
+    pub async fn revoke_peer_key(&self, device_id: DeviceId) -> Result<()> {
+        let groups = self
+            .group_manager
+            .remove_device_from_groups(device_id)
+            .await;
+        let had_key = self.group_manager.revoke_device_key(device_id);
+        let had_session = self.crypto.remove_session(&device_id);
+        let had_identity = self.unpin_identity_key(device_id);
+        let had_public_key = self.unpin_public_key(device_id);
+        if groups.is_empty() && !had_key && !had_session && !had_identity && !had_public_key {
+            return Err(crate::core::error::XLinkError::device_not_found(
+                device_id.to_string(),
+                file!(),
+            ));
+        }
+
+        log::warn!(
+            "Revoked trusted key for peer {} and removed it from {} group(s)",
+            device_id,
+            groups.len()
+        );
+        self.log_audit(&format!("Revoked peer key for device {}", device_id))
+            .await;
+        Ok(())
+    }
+
     pub fn encrypt_group_message(
         &self,
         group_id: crate::core::types::GroupId,
@@ -747,19 +3222,138 @@ impl XLink {
         self.group_manager.encrypt_group_message(group_id, payload)
     }
 
+    /// 解密群组消息，`sender` 须与加密时的发送方一致
     pub fn decrypt_group_message(
         &self,
         group_id: crate::core::types::GroupId,
+        sender: DeviceId,
         encrypted_payload: &MessagePayload,
     ) -> Result<MessagePayload> {
         self.group_manager
-            .decrypt_group_message(group_id, encrypted_payload)
+            .decrypt_group_message(group_id, sender, encrypted_payload)
     }
 
     pub async fn rotate_group_key(&self, group_id: crate::core::types::GroupId) -> Result<()> {
         self.group_manager.rotate_group_key(group_id).await
     }
 
+    /// 设置群组密钥的自动轮换策略，None 表示关闭，仅限管理员
+    ///
+    /// 轮换任务由 SDK 的后台任务表托管，`stop` 时随群组信息一起清除
+    pub fn set_group_rotation_policy(
+        &self,
+        group_id: crate::core::types::GroupId,
+        policy: Option<crate::group::manager::RotationPolicy>,
+    ) -> Result<()> {
+        match policy {
+            Some(policy) => {
+                let task = self.group_manager.set_rotation_policy(group_id, policy)?;
+                self.track_rotation_task(group_id, task);
+            }
+            None => {
+                self.group_manager.clear_rotation_policy(group_id);
+                let name = format!("{}{}", GROUP_KEY_ROTATION_TASK_PREFIX, group_id);
+                if let Some((_, task)) = self.background_tasks.remove(&name) {
+                    task.abort();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 将群组的轮换任务登记到后台任务表，替换并中止旧任务
+    fn track_rotation_task(&self, group_id: crate::core::types::GroupId, task: JoinHandle<()>) {
+        let name = format!("{}{}", GROUP_KEY_ROTATION_TASK_PREFIX, group_id);
+        if let Some(previous) = self.background_tasks.insert(name, task) {
+            previous.abort();
+        }
+    }
+
+    /// 从群组中移除成员并轮换群组密钥
+    pub async fn remove_group_member(
+        &self,
+        group_id: crate::core::types::GroupId,
+        device_id: DeviceId,
+    ) -> Result<()> {
+        self.group_manager.remove_member(group_id, device_id).await
+    }
+
+    /// 将群组管理员身份移交给另一名成员，本地设备降为普通成员
+    pub fn transfer_group_admin(
+        &self,
+        group_id: crate::core::types::GroupId,
+        to: DeviceId,
+    ) -> Result<()> {
+        self.group_manager.transfer_admin(group_id, to)
+    }
+
+    /// 向群组管理员申请加入群组
+    pub async fn request_join_group(
+        &self,
+        group_id: crate::core::types::GroupId,
+        admin: DeviceId,
+    ) -> Result<()> {
+        self.group_manager.request_join(group_id, admin).await
+    }
+
+    /// 批准入群申请，新成员加入后群组密钥随即轮换
+    pub async fn approve_join(
+        &self,
+        group_id: crate::core::types::GroupId,
+        device_id: DeviceId,
+    ) -> Result<()> {
+        self.group_manager.approve_join(group_id, device_id).await?;
+        self.log_audit(&format!(
+            "Approved join request of device {} for group {}",
+            device_id, group_id
+        ))
+        .await;
+        Ok(())
+    }
+
+    /// 拒绝入群申请
+    pub fn deny_join(
+        &self,
+        group_id: crate::core::types::GroupId,
+        device_id: DeviceId,
+    ) -> Result<()> {
+        self.group_manager.deny_join(group_id, device_id)
+    }
+
+    /// 列出群组中待审批的入群申请
+    pub fn pending_join_requests(&self, group_id: crate::core::types::GroupId) -> Vec<DeviceId> {
+        self.group_manager.pending_join_requests(group_id)
+    }
+
+    /// 主动探测对端在各通道上的 RTT 与丢包率，立即更新路由所用的通道状态
+    ///
+    /// 每个通道发送若干 Ping 并最多等待 `timeout`，适合在重要发送前获取准确的路由数据，
+    /// 而不必等待周期心跳
+    pub async fn refresh_peer(
+        &self,
+        device_id: DeviceId,
+        timeout: Duration,
+    ) -> Result<Vec<(ChannelType, crate::core::types::ChannelState)>> {
+        let prober = self.heartbeat_manager.lock().await.prober();
+        prober.probe_peer(device_id, timeout).await
+    }
+
+    /// 设置心跳 Ping 是否携带本地电量与通道摘要，对端据此更新能力而无需单独通告
+    pub async fn set_heartbeat_presence_hints(&self, enabled: bool) {
+        self.heartbeat_manager
+            .lock()
+            .await
+            .set_presence_hints(enabled);
+    }
+
+    /// 设置自适应心跳间隔的上下限：稳定低延迟链路放宽到 `max`，近期失败的链路收紧到 `min`
+    pub async fn set_heartbeat_interval_bounds(&self, min: Duration, max: Duration) {
+        self.heartbeat_manager
+            .lock()
+            .await
+            .set_interval_bounds(min, max);
+    }
+
     pub fn router(&self) -> Arc<Router> {
         self.router.clone()
     }
@@ -773,22 +3367,131 @@ impl XLink {
         rx.recv().await
     }
 
+    /// 非阻塞地取出一条待处理消息，队列为空或另一个 `receive` 正在等待时返回 None
+    pub fn try_receive(&self) -> Option<Message> {
+        let mut rx = self.app_rx.try_lock().ok()?;
+        rx.try_recv().ok()
+    }
+
+    /// 应用接收队列配置
+    pub fn app_queue_config(&self) -> crate::core::types::AppQueueConfig {
+        self.app_queue
+    }
+
+    /// 订阅应用主题，返回该主题入站消息的流
+    ///
+    /// 同一主题可有多个订阅者，每条消息交给全部订阅者；带主题的消息只要有订阅者收到就不再进入 `receive`。
+    /// 丢弃返回的流即取消订阅
+    pub fn subscribe_topic(
+        &self,
+        topic: impl Into<String>,
+    ) -> impl futures::Stream<Item = Message> + Send + 'static {
+        let (tx, rx) = mpsc::channel(TOPIC_SUBSCRIBER_CAPACITY);
+        self.topic_subscribers
+            .entry(topic.into())
+            .or_default()
+            .push(tx);
+        futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|message| (message, rx))
+        })
+    }
+
+    /// 发送带应用主题的消息，接收端据此分发给对应主题的订阅者
+    ///
+    /// 带主题的大消息不走流式分片，以保留主题
+    pub async fn send_to_topic(
+        &self,
+        recipient: DeviceId,
+        topic: impl Into<String>,
+        payload: MessagePayload,
+    ) -> Result<()> {
+        self.send_with_options(
+            recipient,
+            payload,
+            SendOptions {
+                topic: Some(topic.into()),
+                ..SendOptions::default()
+            },
+        )
+        .await
+    }
+
     pub fn get_message_handler(&self) -> Arc<dyn MessageHandler> {
-        Arc::new(SdkMessageHandler {
+        Arc::new(self.message_handler())
+    }
+
+    fn message_handler(&self) -> SdkMessageHandler {
+        SdkMessageHandler {
             app_tx: self.app_tx.clone(),
+            app_queue: self.app_queue,
             _crypto: self.crypto.clone(),
             group_manager: Arc::downgrade(&self.group_manager),
             heartbeat_manager: Arc::downgrade(&self.heartbeat_manager),
             stream_manager: Arc::downgrade(&self.stream_manager),
             rate_limiter: self.rate_limiter.clone(),
+            clock: self.clock.clone(),
             metrics: self.metrics.clone(),
-        })
+            error_stats: self.error_stats.clone(),
+            reorder_buffers: self.reorder_buffers.clone(),
+            dedup: self.dedup.clone(),
+            clock_skew: self.clock_skew.clone(),
+            message_age: self.message_age.clone(),
+            reorder_config: self.reorder_config.clone(),
+            stream_delivery: self.stream_delivery.clone(),
+            rate_limits: self.rate_limits.clone(),
+            rate_limit_config: self.rate_limit_config.clone(),
+            events: self.events.clone(),
+            journal: self.journal.clone(),
+            pending_requests: self.pending_requests.clone(),
+            pending_replies: self.pending_replies.clone(),
+            pending_acks: self.pending_acks.clone(),
+            receive_pool: self.receive_pool.clone(),
+            topic_subscribers: self.topic_subscribers.clone(),
+            pinned_identity_keys: self.pinned_identity_keys.clone(),
+            plugins: self.plugins.clone(),
+            local_device_id: self.device_id,
+            router: Arc::downgrade(&self.router),
+            storage: self.storage.clone(),
+        }
+    }
+
+    /// 设置接收流水线配置，立即替换工作池
+    ///
+    /// 旧工作池队列中尚未处理的消息会被丢弃，建议在 `start` 之前配置
+    pub fn set_receive_pipeline_config(&self, config: crate::core::types::ReceivePipelineConfig) {
+        let pool = (config.workers > 0).then(|| {
+            // 工作者内联处理，不持有工作池，避免循环引用
+            let mut worker_handler = self.message_handler();
+            worker_handler.receive_pool = Arc::new(parking_lot::RwLock::new(None));
+            Arc::new(crate::core::receive_pool::ReceivePool::new(
+                config.workers,
+                config.queue_capacity,
+                Arc::new(worker_handler),
+            ))
+        });
+        *self.receive_pool.write() = pool;
+        *self.receive_pipeline.write() = config;
+    }
+
+    /// 获取当前的接收流水线配置
+    pub fn receive_pipeline_config(&self) -> crate::core::types::ReceivePipelineConfig {
+        *self.receive_pipeline.read()
     }
 
     pub fn capability_manager(&self) -> Arc<CapabilityManager> {
         self.cap_manager.clone()
     }
 
+    /// 使远程设备的缓存能力失效，路由将不再考虑该设备的任何通道
+    pub fn invalidate_device(&self, device_id: DeviceId) {
+        self.cap_manager.invalidate_device(device_id);
+    }
+
+    /// 使远程设备的单个通道失效（例如对端告知已失去 BLE 能力）
+    pub fn invalidate_channel(&self, device_id: DeviceId, channel: ChannelType) {
+        self.cap_manager.invalidate_channel(device_id, channel);
+    }
+
     pub fn device_id(&self) -> DeviceId {
         self.device_id
     }
@@ -797,10 +3500,100 @@ impl XLink {
         self.metrics.get_report()
     }
 
+    /// 设置流量偏好（资费、月流量上限与省流模式）
+    pub fn set_traffic_preferences(
+        &self,
+        preferences: crate::media::stream_manager::UserTrafficPreferences,
+    ) {
+        self.traffic_budget.set_limit_mb(
+            preferences
+                .enable_data_saver
+                .then_some(preferences.monthly_data_limit_mb),
+        );
+        self.stream_manager.update_user_preferences(preferences);
+    }
+
+    /// 获取当前的流量偏好
+    pub fn traffic_preferences(&self) -> crate::media::stream_manager::UserTrafficPreferences {
+        self.stream_manager.get_user_preferences()
+    }
+
+    /// 本计费周期内计量网络剩余的流量额度（MB）
+    pub fn remaining_budget_mb(&self) -> u64 {
+        self.traffic_budget.remaining_mb(
+            self.stream_manager
+                .get_user_preferences()
+                .monthly_data_limit_mb,
+        )
+    }
+
+    /// 本计费周期内因群组扇出策略避开蜂窝网络而未发出的流量（字节）
+    pub fn avoided_metered_bytes(&self) -> u64 {
+        self.traffic_budget.avoided_bytes()
+    }
+
+    /// 开始新的计费周期：计量网络用量归零并写入存储
+    pub async fn reset_traffic_budget(&self) -> Result<()> {
+        self.traffic_budget.reset();
+        self.traffic_budget.persist(self.storage.as_ref()).await
+    }
+
+    /// 错误统计报告：最常见的错误码、按类别计数与最近一分钟各错误码的次数
+    ///
+    /// 统计发送、接收处理与存储过程中产生或捕获的错误
+    pub fn error_statistics_report(&self) -> crate::core::error::ErrorStatsSnapshot {
+        self.error_stats.lock().snapshot(ERROR_REPORT_TOP_CODES)
+    }
+
+    /// 解释消息会经哪个通道发送，以及其余通道落选的原因，不发送任何消息
+    pub fn explain_route(
+        &self,
+        message: &Message,
+    ) -> crate::router::introspection::RouteExplanation {
+        self.router.explain_route(message)
+    }
+
+    /// 导出当前路由表（每个已知对端的候选通道、评分与选择结果），不产生任何流量
+    ///
+    /// 可配合 `router::introspection::format_routing_table` 输出到日志或问题报告
+    pub fn routing_table(&self) -> Vec<crate::router::introspection::PeerRoutingInfo> {
+        self.router.routing_table()
+    }
+
     pub fn public_key(&self) -> PublicKey {
         self.crypto.public_key()
     }
 
+    /// 本机的身份公钥（Ed25519），SDK 发出的消息均以对应私钥签名，供对端固定
+    pub fn identity_public_key(&self) -> VerifyingKey {
+        self.crypto.verifying_key()
+    }
+
+    /// 固定对端的身份公钥，之后该设备发来的带签名消息须通过校验才会交付
+    ///
+    /// 签名不匹配的消息被拒绝并返回 `signature_verification_failed` (0305)；
+    /// QUIC 通道也仅在对端以该密钥完成认证后才将回复绑定到其入站连接
+    pub fn pin_identity_key(&self, device_id: DeviceId, key: VerifyingKey) {
+        self.pinned_identity_keys.insert(device_id, key);
+    }
+
+    /// 取消固定对端的身份公钥，返回此前是否已固定
+    pub fn unpin_identity_key(&self, device_id: DeviceId) -> bool {
+        self.pinned_identity_keys.remove(&device_id).is_some()
+    }
+
+    /// 固定对端的静态公钥（X25519），开启发现校验后只有持有该密钥的对端才会被接纳
+    ///
+    /// 未固定公钥或广播声明的公钥与之不符的对端均被拒绝
+    pub fn pin_public_key(&self, device_id: DeviceId, key: PublicKey) {
+        self.pinned_public_keys.insert(device_id, key);
+    }
+
+    /// 取消固定对端的静态公钥，返回此前是否已固定
+    pub fn unpin_public_key(&self, device_id: DeviceId) -> bool {
+        self.pinned_public_keys.remove(&device_id).is_some()
+    }
+
     // --- 企业级管理 API ---
 
     /// 获取当前合规性配置
@@ -814,21 +3607,180 @@ impl XLink {
         log::info!("Compliance config updated");
     }
 
-    /// 导出审计日志
+    /// 配置发现结果校验
+    ///
+    /// `verify_peers` 开启后，发现的对端需针对其经 [`XLink::pin_public_key`] 固定的公钥
+    /// 通过 `challenger` 完成挑战-应答才会被标记为可路由，结果以 `SdkEvent::DeviceVerified` / `SdkEvent::DeviceRejected` 发布
+    pub async fn configure_discovery_verification(
+        &self,
+        config: crate::discovery::verification::DiscoveryConfig,
+        challenger: Arc<dyn crate::discovery::verification::PeerChallenger>,
+    ) {
+        let max_peers = config.max_peers;
+        let verifier = Arc::new(crate::discovery::verification::PeerVerifier::new(
+            config.clone(),
+            challenger,
+            self.cap_manager.clone(),
+            self.events.clone(),
+            self.pinned_public_keys.clone(),
+        ));
+        let mut discovery = self.discovery_manager.lock().await;
+        discovery.set_verifier(verifier);
+        discovery.set_config(config);
+        if let Some(peers) = discovery.discovered_peers() {
+            peers.set_max_peers(max_peers);
+        }
+    }
+
+    /// 设置发现配置（对端上限与 BLE 广播过滤），不改变挑战-应答校验
+    pub async fn set_discovery_config(
+        &self,
+        config: crate::discovery::verification::DiscoveryConfig,
+    ) {
+        let mut discovery = self.discovery_manager.lock().await;
+        if let Some(peers) = discovery.discovered_peers() {
+            peers.set_max_peers(config.max_peers);
+        }
+        discovery.set_config(config);
+    }
+
+    /// BLE 扫描回调：按服务 UUID 与 RSSI 阈值过滤广播，通过后按发现结果接纳
+    ///
+    /// 返回对端是否被标记为可路由；被过滤的广播不会登记任何通道状态
+    pub async fn handle_ble_advertisement(
+        &self,
+        advertisement: crate::discovery::ble::BleAdvertisement,
+    ) -> bool {
+        let config = self.discovery_manager.lock().await.config().clone();
+        match advertisement.filter(&config) {
+            Some(peer) => self.admit_discovered_peer(peer).await,
+            None => false,
+        }
+    }
+
+    /// 设置发现对端数量上限，超出部分立即按最久未见淘汰并发布 `SdkEvent::DeviceLost`
+    pub async fn set_max_discovered_peers(&self, max_peers: usize) {
+        if let Some(peers) = self.discovery_manager.lock().await.discovered_peers() {
+            peers.set_max_peers(max_peers);
+        }
+    }
+
+    /// 当前经发现机制登记的对端数量（不含手动注册的对端）
+    pub async fn discovered_peer_count(&self) -> usize {
+        self.discovery_manager
+            .lock()
+            .await
+            .discovered_peers()
+            .map_or(0, |peers| peers.len())
+    }
+
+    /// 接纳一个发现结果（例如来自自定义发现机制），返回对端是否被标记为可路由
+    ///
+    /// 未配置校验时直接登记
+    pub async fn admit_discovered_peer(
+        &self,
+        peer: crate::discovery::verification::DiscoveredPeer,
+    ) -> bool {
+        let (verifier, discovered_peers) = {
+            let discovery = self.discovery_manager.lock().await;
+            (discovery.verifier(), discovery.discovered_peers())
+        };
+        let device_id = peer.capabilities.device_id;
+        // 应用已自行注册的对端不计入发现上限
+        let manual = discovered_peers
+            .as_ref()
+            .is_some_and(|peers| peers.is_manual(&device_id));
+        let admitted = match verifier {
+            Some(verifier) => verifier.admit(peer).await,
+            None => {
+                self.cap_manager.register_remote_device(peer.capabilities);
+                self.cap_manager
+                    .update_channel_state(device_id, peer.channel, peer.state);
+                true
+            }
+        };
+        if admitted && !manual {
+            if let Some(peers) = discovered_peers {
+                peers.observe(device_id);
+            }
+        }
+        admitted
+    }
+
+    /// 应答对端发来的发现校验挑战
+    pub fn respond_to_discovery_challenge(
+        &self,
+        challenge: &crate::discovery::verification::DiscoveryChallenge,
+    ) -> crate::discovery::verification::DiscoveryChallengeResponse {
+        self.crypto
+            .respond_to_discovery_challenge(self.device_id, challenge)
+    }
+
+    /// 开启消息日志，记录之后处理的所有入站与出站消息
+    ///
+    /// 隐私保护模式（`privacy_mode`）开启时，负载在写入前脱敏
+    pub async fn enable_message_journal<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let journal =
+            crate::storage::journal::MessageJournal::open(path, self.compliance.privacy_mode)
+                .await?;
+        log::info!("Message journal enabled at {:?}", journal.path());
+        *self.journal.write() = Some(Arc::new(journal));
+        Ok(())
+    }
+
+    /// 关闭消息日志
+    pub fn disable_message_journal(&self) {
+        if self.journal.write().take().is_some() {
+            log::info!("Message journal disabled");
+        }
+    }
+
+    /// 导出最近 100 条原始审计日志，结构化查询使用 `query_audit_logs`
     pub async fn export_audit_logs(&self) -> Result<Vec<String>> {
         self.storage.get_audit_logs(100).await
     }
 
+    /// 分页查询审计日志（从新到旧）
+    ///
+    /// 单页读取的字节数受 [`crate::core::types::AuditQueryConfig`] 限制，达到上限时
+    /// `partial` 为 true，可从 `next_offset` 继续查询。
+    pub async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<crate::core::types::AuditLogPage> {
+        let max_bytes_scanned = self.audit_query.read().max_bytes_scanned;
+        self.storage
+            .get_audit_logs_paged(offset, limit, max_bytes_scanned)
+            .await
+    }
+
+    /// 设置审计日志查询配置
+    pub fn set_audit_query_config(&self, config: crate::core::types::AuditQueryConfig) {
+        *self.audit_query.write() = config;
+    }
+
+    /// 获取当前的审计日志查询配置
+    pub fn audit_query_config(&self) -> crate::core::types::AuditQueryConfig {
+        *self.audit_query.read()
+    }
+
+    /// 按时间范围、设备与操作内容过滤审计记录，分页返回（从新到旧，`page` 从 0 开始）
+    pub async fn query_audit_logs(
+        &self,
+        filter: crate::core::types::AuditFilter,
+        page: usize,
+        page_size: usize,
+    ) -> Result<crate::core::types::AuditEntryPage> {
+        self.storage
+            .query_audit_logs(&filter, page, page_size)
+            .await
+    }
+
     /// 记录管理操作到审计日志
-    #[allow(dead_code)]
     async fn log_audit(&self, action: &str) {
-        let entry = format!(
-            "[{:?}] Device: {} Action: {}",
-            std::time::SystemTime::now(),
-            self.device_id,
-            action
-        );
-        let _ = self.storage.save_audit_log(entry).await;
+        let entry = crate::core::types::AuditEntry::new(self.device_id, action);
+        let _ = self.storage.save_audit_entry(&entry).await;
     }
 
     /// 获取系统运行指标报告 (用于监控后台)
@@ -838,18 +3790,31 @@ impl XLink {
 
     // --- 插件管理 ---
 
-    /// 注册插件
+    /// 注册插件，消息钩子按注册顺序运行；同名插件原位替换，保留原有顺序
     pub fn register_plugin(&self, plugin: Arc<dyn crate::core::traits::Plugin>) -> Result<()> {
         let name = plugin.name().to_string();
         plugin.on_load()?;
-        self.plugins.insert(name.clone(), plugin);
+        {
+            let mut plugins = self.plugins.write();
+            match plugins.iter().position(|p| p.name() == name) {
+                Some(position) => plugins[position] = plugin,
+                None => plugins.push(plugin),
+            }
+        }
         log::info!("Plugin loaded: {}", name);
         Ok(())
     }
 
     /// 卸载插件
     pub fn unregister_plugin(&self, name: &str) -> Result<()> {
-        if let Some((_, plugin)) = self.plugins.remove(name) {
+        let removed = {
+            let mut plugins = self.plugins.write();
+            plugins
+                .iter()
+                .position(|p| p.name() == name)
+                .map(|position| plugins.remove(position))
+        };
+        if let Some(plugin) = removed {
             plugin.on_unload()?;
             log::info!("Plugin unloaded: {}", name);
         }
@@ -871,20 +3836,180 @@ impl XLink {
     }
 
     /// 恢复设备崩溃后的待发送消息
+    ///
+    /// 已过期的消息从待发送队列移除且不返回，过期数量记入审计日志
     pub async fn recover_pending_messages(&self) -> Result<Vec<Message>> {
-        let messages = self
+        self.recover_pending_messages_filtered(crate::core::types::RecoveryFilter::default())
+            .await
+    }
+
+    /// 只恢复满足 `filter` 的待发送消息，其余消息留在队列中等待之后的完整恢复
+    ///
+    /// 筛选在存储层完成；命中的消息中已过期的同样被移除且不返回
+    pub async fn recover_pending_messages_filtered(
+        &self,
+        filter: crate::core::types::RecoveryFilter,
+    ) -> Result<Vec<Message>> {
+        let now = self.clock.now_unix_secs();
+        let (expired, messages): (Vec<Message>, Vec<Message>) = self
             .storage
-            .get_pending_messages_for_recovery(&self.device_id)
-            .await?;
+            .get_pending_messages_for_recovery_filtered(&self.device_id, &filter)
+            .await?
+            .into_iter()
+            .partition(|message| message.is_expired(now));
+
+        if !expired.is_empty() {
+            for message in &expired {
+                self.storage.remove_pending_message(&message.id).await?;
+            }
+            log::info!("Dropped {} expired pending messages", expired.len());
+            self.storage
+                .save_audit_entry(&crate::core::types::AuditEntry::new(
+                    self.device_id,
+                    format!("Crash recovery expired {} pending messages", expired.len()),
+                ))
+                .await?;
+        }
+
         log::info!("Recovered {} pending messages after crash", messages.len());
         Ok(messages)
     }
 
+    /// 上一次运行的退出方式，`start` 之前为 None
+    pub fn previous_exit(&self) -> Option<crate::core::types::PreviousExit> {
+        self.previous_exit.read().clone()
+    }
+
+    /// 重新发送待发送队列中的消息，每处理一条调用一次 `progress`，返回 (总数, 失败数)
+    async fn resend_pending_messages(
+        &self,
+        progress: &mut (dyn FnMut(crate::core::types::RecoveryProgress) + Send),
+    ) -> Result<(usize, usize)> {
+        let pending_messages = self.recover_pending_messages().await?;
+        let total_messages = pending_messages.len();
+        log::info!("Found {} pending messages to retry", total_messages);
+
+        let mut failed_count = 0;
+        for (index, message) in pending_messages.into_iter().enumerate() {
+            let options = SendOptions {
+                expires_at: message.expires_at,
+                ..SendOptions::default()
+            };
+            match self
+                .send_with_options(message.recipient, message.payload.clone(), options)
+                .await
+            {
+                Ok(_) => {
+                    // 发送成功，从待发送队列中移除
+                    self.storage.remove_pending_message(&message.id).await?;
+                    log::info!("Successfully resent pending message {}", message.id);
+                }
+                Err(e) => {
+                    failed_count += 1;
+                    log::error!("Failed to resend pending message {}: {}", message.id, e);
+                }
+            }
+            let processed = index + 1;
+            progress(crate::core::types::RecoveryProgress {
+                total: total_messages,
+                processed,
+                succeeded: processed - failed_count,
+                failed: failed_count,
+            });
+        }
+        Ok((total_messages, failed_count))
+    }
+
+    /// 写入正常退出标记，失败时仅记录日志（下次启动按崩溃处理）
+    async fn write_shutdown_marker(&self, reason: crate::core::types::ShutdownReason) {
+        let pending_messages = self
+            .storage
+            .get_pending_messages_for_recovery(&self.device_id)
+            .await
+            .map(|messages| messages.len())
+            .unwrap_or(0);
+        let marker = crate::core::types::ShutdownMarker {
+            reason,
+            timestamp: self.clock.now_unix_secs(),
+            pending_messages,
+        };
+        let result = match serde_json::to_vec(&marker) {
+            Ok(data) => self.storage.save_metadata(SHUTDOWN_MARKER_KEY, data).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to write shutdown marker: {}", e);
+        }
+    }
+
+    /// 读取并清除退出标记（以空值覆盖），标记缺失或损坏时返回 None
+    async fn take_shutdown_marker(&self) -> Option<crate::core::types::ShutdownMarker> {
+        let data = match self.storage.load_metadata(SHUTDOWN_MARKER_KEY).await {
+            Ok(data) => data?,
+            Err(e) => {
+                log::warn!("Failed to read shutdown marker: {}", e);
+                return None;
+            }
+        };
+        if data.is_empty() {
+            return None;
+        }
+        if let Err(e) = self
+            .storage
+            .save_metadata(SHUTDOWN_MARKER_KEY, Vec::new())
+            .await
+        {
+            log::warn!("Failed to clear shutdown marker: {}", e);
+        }
+        serde_json::from_slice(&data).ok()
+    }
+
     /// 获取存储使用情况（用于存储空间管理）
     pub async fn get_storage_usage(&self) -> Result<u64> {
         self.storage.get_storage_usage().await
     }
 
+    /// 保存待发送消息；存储空间不足时清理出容纳该消息所需的空间并重试一次
+    ///
+    /// 只删除最旧的已存消息与审计日志，待发送消息不受影响；其他写入错误直接返回。
+    /// 清理后仍空间不足则发布 `StorageFull` 事件并返回存储空间不足错误
+    async fn save_outgoing_message(&self, message: &Message) -> Result<()> {
+        let first_error = match self.storage.save_message(message).await {
+            Ok(()) => return Ok(()),
+            Err(e) if is_storage_full(&e) => e,
+            Err(e) => return Err(e),
+        };
+        log::warn!(
+            "Storage full while saving message {}, reclaiming space: {}",
+            message.id,
+            first_error
+        );
+
+        let needed = serde_json::to_vec(message)
+            .map(|encoded| encoded.len() as u64)
+            .unwrap_or_default();
+        let usage = self.storage.get_storage_usage().await.unwrap_or(0);
+        if let Err(e) = self.cleanup_storage(usage.saturating_sub(needed)).await {
+            log::warn!("Storage cleanup failed: {}", e);
+        }
+
+        match self.storage.save_message(message).await {
+            Ok(()) => Ok(()),
+            Err(e) if is_storage_full(&e) => {
+                self.events
+                    .publish(crate::core::events::SdkEvent::StorageFull {
+                        message_id: message.id,
+                        reason: e.to_string(),
+                    });
+                Err(
+                    crate::core::error::XLinkError::storage_full(e.to_string(), file!())
+                        .with_source(e),
+                )
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// 清理存储空间到指定大小
     pub async fn cleanup_storage(&self, target_size_bytes: u64) -> Result<u64> {
         let removed = self.storage.cleanup_storage(target_size_bytes).await?;
@@ -896,55 +4021,67 @@ impl XLink {
     pub async fn handle_low_battery_shutdown(&self) -> Result<()> {
         log::warn!("Low battery detected, performing graceful shutdown");
 
-        // 1. 保存所有待发送消息
-        let pending_messages = self.recover_pending_messages().await?;
+        // 1. 只取出关键消息，其余待发送消息原样保留到下次完整恢复
+        let pending_messages = self
+            .recover_pending_messages_filtered(crate::core::types::RecoveryFilter {
+                min_priority: Some(MessagePriority::Critical),
+                ..Default::default()
+            })
+            .await?;
         log::info!(
-            "Saved {} pending messages before shutdown",
+            "Saved {} critical pending messages before shutdown",
             pending_messages.len()
         );
 
         // 2. 记录审计日志
         self.storage
-            .save_audit_log("Low battery shutdown initiated".to_string())
+            .save_audit_entry(&crate::core::types::AuditEntry::new(
+                self.device_id,
+                "Low battery shutdown initiated",
+            ))
             .await?;
 
-        // 3. 导出SDK状态用于恢复
-        let state_data = self.export_sdk_state()?;
-        log::info!(
-            "Exported SDK state ({} bytes) for recovery",
-            state_data.len()
-        );
+        // 3. 导出SDK状态用于恢复；仅在配置状态密钥时落盘，避免私钥以明文持久化
+        //    导出与落盘使用同一份密钥来源快照，期间更换配置也不会写出明文
+        let provider = self.state_key_provider.read().clone();
+        match provider {
+            Some(provider) => {
+                let state_data = self.export_sdk_state_with(Some(provider.as_ref()))?;
+                log::info!(
+                    "Exported SDK state ({} bytes) for recovery",
+                    state_data.len()
+                );
+                self.storage
+                    .save_metadata(SDK_STATE_KEY, state_data)
+                    .await?;
+            }
+            None => log::warn!("No state key configured, SDK state not persisted"),
+        }
 
         // 4. 清理非关键数据以节省电量
         let _ = self.cleanup_storage(1024 * 1024).await; // 保留1MB
 
+        // 5. 标记为正常退出，下次启动无需崩溃恢复
+        self.write_shutdown_marker(crate::core::types::ShutdownReason::LowBattery)
+            .await;
+
         Ok(())
     }
 
     /// 设备启动后恢复状态
     pub async fn recover_from_crash(&self) -> Result<()> {
-        log::info!("Starting crash recovery process");
+        self.recover_from_crash_with_progress(|_| {}).await
+    }
 
-        // 1. 恢复待发送消息
-        let pending_messages = self.recover_pending_messages().await?;
-        let total_messages = pending_messages.len();
-        log::info!("Found {} messages to retry after crash", total_messages);
+    /// 设备启动后恢复状态，每重发一条待发送消息（无论成败）都向 `progress` 上报一次进度
+    pub async fn recover_from_crash_with_progress(
+        &self,
+        mut progress: impl FnMut(crate::core::types::RecoveryProgress) + Send,
+    ) -> Result<()> {
+        log::info!("Starting crash recovery process");
 
-        // 2. 尝试重新发送这些消息
-        let mut failed_count = 0;
-        for message in pending_messages {
-            match self.send(message.recipient, message.payload.clone()).await {
-                Ok(_) => {
-                    // 发送成功，从待发送队列中移除
-                    self.storage.remove_pending_message(&message.id).await?;
-                    log::info!("Successfully resent message {} after crash", message.id);
-                }
-                Err(e) => {
-                    failed_count += 1;
-                    log::error!("Failed to resend message {} after crash: {}", message.id, e);
-                }
-            }
-        }
+        // 1-2. 恢复待发送消息并尝试重新发送
+        let (total_messages, failed_count) = self.resend_pending_messages(&mut progress).await?;
 
         log::info!(
             "Crash recovery completed: {} messages resent, {} failed",
@@ -954,10 +4091,10 @@ impl XLink {
 
         // 3. 记录恢复完成
         self.storage
-            .save_audit_log(format!(
-                "Crash recovery completed: {} messages processed",
-                total_messages
-            ))
+            .save_audit_entry(
+                &crate::core::types::AuditEntry::new(self.device_id, "Crash recovery completed")
+                    .with_details(format!("{} messages processed", total_messages)),
+            )
             .await?;
 
         Ok(())
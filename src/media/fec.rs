@@ -0,0 +1,95 @@
+//! 流分片的 XOR 前向纠错
+//!
+//! 数据分片按 `group_size` 分组，每组追加一个校验分片，组内丢失任意一个数据分片都
+//! 可由其余分片与校验分片异或恢复。校验分片的分片序号从 `total_chunks` 开始，第
+//! `k` 组的校验分片序号为 `total_chunks + k`，不支持纠错的接收端按越界分片处理。
+//!
+//! 校验分片格式：4 字节大端的组内分片长度异或值，随后是组内分片补零到最长分片后
+//! 的逐字节异或，恢复时据此还原被丢失分片的原始长度。
+
+use std::collections::HashMap;
+
+const LENGTH_PREFIX: usize = 4;
+
+/// 数据分片总数为 `total_chunks` 时的校验分组数
+pub fn group_count(total_chunks: u32, group_size: u32) -> u32 {
+    total_chunks.div_ceil(group_size.max(1))
+}
+
+/// 第 `group` 组包含的数据分片序号范围
+pub fn group_range(group: u32, total_chunks: u32, group_size: u32) -> std::ops::Range<u32> {
+    let group_size = group_size.max(1);
+    let start = group.saturating_mul(group_size).min(total_chunks);
+    start..start.saturating_add(group_size).min(total_chunks)
+}
+
+/// 为一组数据分片生成校验分片
+pub fn parity_chunk<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut lengths = 0u32;
+    let mut body: Vec<u8> = Vec::new();
+    for chunk in chunks {
+        lengths ^= chunk.len() as u32;
+        xor_into(&mut body, chunk);
+    }
+    let mut parity = lengths.to_be_bytes().to_vec();
+    parity.extend_from_slice(&body);
+    parity
+}
+
+/// 在数据分片之后按组插入校验分片，返回 (分片序号, 数据) 的发送序列
+///
+/// 每组的校验分片紧跟在该组数据分片之后发出
+pub fn interleave_parity(chunks: Vec<Vec<u8>>, group_size: u32) -> Vec<(u32, Vec<u8>)> {
+    let total_chunks = chunks.len() as u32;
+    let group_size = group_size.max(1) as usize;
+    let parities: Vec<Vec<u8>> = chunks
+        .chunks(group_size)
+        .map(|group| parity_chunk(group.iter().map(Vec::as_slice)))
+        .collect();
+
+    let mut out = Vec::with_capacity(chunks.len() + parities.len());
+    let mut data = chunks.into_iter().enumerate();
+    for (group, parity) in parities.into_iter().enumerate() {
+        out.extend(
+            data.by_ref()
+                .take(group_size)
+                .map(|(index, chunk)| (index as u32, chunk)),
+        );
+        out.push((total_chunks + group as u32, parity));
+    }
+    out
+}
+
+/// 用校验分片恢复组内唯一丢失的数据分片，`received` 为组内已收到的其余分片
+///
+/// 校验分片格式不合法或推算出的长度越界时返回 None
+pub fn recover_missing(
+    parity: &[u8],
+    received: &HashMap<u32, Vec<u8>>,
+    range: std::ops::Range<u32>,
+) -> Option<Vec<u8>> {
+    let prefix: [u8; LENGTH_PREFIX] = parity.get(..LENGTH_PREFIX)?.try_into().ok()?;
+    let mut length = u32::from_be_bytes(prefix);
+    let mut body = parity[LENGTH_PREFIX..].to_vec();
+    for index in range {
+        if let Some(chunk) = received.get(&index) {
+            length ^= chunk.len() as u32;
+            xor_into(&mut body, chunk);
+        }
+    }
+    let length = length as usize;
+    if length > body.len() {
+        return None;
+    }
+    body.truncate(length);
+    Some(body)
+}
+
+fn xor_into(acc: &mut Vec<u8>, data: &[u8]) {
+    if acc.len() < data.len() {
+        acc.resize(data.len(), 0);
+    }
+    for (a, b) in acc.iter_mut().zip(data) {
+        *a ^= b;
+    }
+}
@@ -0,0 +1,45 @@
+//! 流分片完整性校验
+//!
+//! 发送方为每个分片附带 CRC32，接收方据此在分片到达时丢弃损坏的分片并等待重发；
+//! 整个流另附全部数据分片拼接后的 SHA-256，重组完成后校验，发现分片级校验
+//! 无法覆盖的错序或截断。
+
+use sha2::{Digest, Sha256};
+
+// CRC-32（IEEE 802.3，反射多项式 0xEDB88320）查找表
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// 计算数据的 CRC32
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// 分片数据与携带的 CRC32 是否一致，未携带 CRC32 时视为完好
+pub fn chunk_intact(data: &[u8], chunk_crc: Option<u32>) -> bool {
+    chunk_crc.is_none_or(|crc| crc32(data) == crc)
+}
+
+/// 计算整个流的 SHA-256 摘要
+pub fn payload_digest(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
@@ -1 +1,3 @@
+pub mod fec;
+pub mod integrity;
 pub mod stream_manager;
@@ -1,13 +1,31 @@
+use crate::core::clock::{system_clock, Clock};
 use crate::core::error::{Result, XLinkError};
-use crate::core::types::{DeviceId, Message, MessagePayload, NetworkType};
+use crate::core::traits::Storage;
+use crate::core::types::{
+    ChannelType, CompressionAlgorithm, DeviceId, Message, MessagePayload, NetworkType,
+};
+use crate::media::{fec, integrity};
 use crate::router::selector::Router;
+use crate::utils::lock_helper::{lock_order, lock_ordered};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use uuid::Uuid;
 
-const CHUNK_SIZE: usize = 1024 * 32;
+/// 流式传输的分片大小，不超过该大小的负载无需分片
+pub const CHUNK_SIZE: usize = 1024 * 32;
+const STREAM_CONTROL_CAPACITY: usize = 16;
+
+// 可靠分片发送：每轮分片发完后等待确认的时长、最多续传的轮数与检查确认进度的间隔
+const RELIABLE_ACK_TIMEOUT_MS: u64 = 2000;
+const RELIABLE_MAX_RESENDS: u32 = 3;
+const RELIABLE_ACK_POLL_MS: u64 = 10;
+
+// 可续传传输清单的存储键：索引键记录全部未完成的流 ID，单个清单以流 ID 为后缀
+const TRANSFER_INDEX_KEY: &str = "transfer_manifests";
+const TRANSFER_MANIFEST_KEY_PREFIX: &str = "transfer_manifest_";
 
 // F8: 音频/视频流处理常量
 const AUDIO_SAMPLE_RATE: u32 = 48000; // 48kHz 音频采样率
@@ -30,6 +48,47 @@ const BITRATE_ADJUSTMENT_INTERVAL_MS: u64 = 1000; // 每秒调整一次
 const RTT_THRESHOLD_HIGH_MS: u32 = 300; // 高延迟阈值
 const PACKET_LOSS_THRESHOLD_HIGH: f32 = 0.05; // 高丢包率阈值
 
+// F8: 缓冲区溢出策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BufferOverflowPolicy {
+    /// 丢弃缓冲区中最旧的数据，为新帧腾出空间
+    #[default]
+    DropOldest,
+    /// 丢弃新到达的帧，保留已缓冲的数据
+    DropNewest,
+}
+
+// F8: 音视频缓冲区配置
+#[derive(Debug, Clone)]
+pub struct MediaBufferConfig {
+    pub max_audio_buffer_bytes: usize,
+    pub max_video_buffer_bytes: usize,
+    pub overflow_policy: BufferOverflowPolicy,
+}
+
+impl Default for MediaBufferConfig {
+    fn default() -> Self {
+        Self {
+            max_audio_buffer_bytes: AUDIO_FRAME_SIZE * 100, // 最多缓存100帧
+            max_video_buffer_bytes: 1024 * 1024,            // 最多缓存1MB
+            overflow_policy: BufferOverflowPolicy::DropOldest,
+        }
+    }
+}
+
+// F8: 流事件，用于通知应用层传输质量变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// 缓冲区溢出，已丢弃部分数据
+    BufferOverflow {
+        stream_id: Uuid,
+        dropped_bytes: usize,
+    },
+}
+
+/// 流事件监听器
+pub type StreamEventHandler = Box<dyn Fn(StreamEvent) + Send + Sync>;
+
 // F8: 流类型枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StreamType {
@@ -77,6 +136,10 @@ pub struct VideoConfig {
     pub bitrate: u32,
     pub codec: VideoCodec,
     pub keyframe_interval: u32, // 关键帧间隔（帧数）
+    /// 前向纠错分组大小：每组数据分片追加一个 XOR 校验分片，None 表示不生成
+    pub fec_group_size: Option<u32>,
+    /// 待发送数据已使用的压缩算法，随分片告知接收端在重组后解压
+    pub compression: Option<CompressionAlgorithm>,
 }
 
 impl Default for VideoConfig {
@@ -88,6 +151,8 @@ impl Default for VideoConfig {
             bitrate: VIDEO_BITRATE_INITIAL,
             codec: VideoCodec::H264,
             keyframe_interval: 30, // 1秒一个关键帧
+            fec_group_size: None,
+            compression: None,
         }
     }
 }
@@ -109,6 +174,126 @@ pub struct StreamMetadata {
     pub video_config: Option<VideoConfig>,
     pub estimated_bandwidth_bps: u32,
     pub target_latency_ms: u32,
+    /// 分片前向纠错分组大小，每组可恢复一个丢失的数据分片
+    ///
+    /// 仅作用于经重组的分片流；音频帧逐帧交付、不经重组，不生成校验分片
+    pub fec_group_size: Option<u32>,
+}
+
+/// 音频抖动缓冲统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JitterStats {
+    /// 收到的音频帧总数，含迟到丢弃的帧
+    pub frames_received: u64,
+    /// 已到达播放时间并交付的帧数
+    pub frames_played: u64,
+    /// 晚于播放位置到达而被丢弃的帧数
+    pub late_frames_dropped: u64,
+    /// 当前缓冲中等待播放的帧数
+    pub buffered_frames: usize,
+}
+
+/// 可续传传输清单：记录视频流的数据分片中哪些已被接收方确认
+///
+/// 清单经 `Storage` 元数据持久化，校验分片不记录确认，续传时只重发未确认的数据分片
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferManifest {
+    pub stream_id: Uuid,
+    pub recipient: DeviceId,
+    /// 数据分片总数，不含校验分片
+    pub total_chunks: u32,
+    /// 已确认分片位图，第 i 位对应数据分片 i
+    pub acked: Vec<u8>,
+    pub fec_group_size: Option<u32>,
+    #[serde(default)]
+    pub compression: Option<CompressionAlgorithm>,
+    /// 全部数据分片拼接后的 SHA-256，续传的分片同样携带
+    #[serde(default)]
+    pub checksum: Option<[u8; 32]>,
+    /// 创建时间（Unix 秒），超过数据保留期的未完成清单由清理任务删除
+    pub created_at: u64,
+}
+
+impl TransferManifest {
+    fn new(
+        stream_id: Uuid,
+        recipient: DeviceId,
+        total_chunks: u32,
+        fec_group_size: Option<u32>,
+        compression: Option<CompressionAlgorithm>,
+        checksum: Option<[u8; 32]>,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            stream_id,
+            recipient,
+            total_chunks,
+            acked: vec![0; (total_chunks as usize).div_ceil(8)],
+            fec_group_size,
+            compression,
+            checksum,
+            created_at,
+        }
+    }
+
+    pub fn is_acked(&self, chunk_index: u32) -> bool {
+        self.acked
+            .get(chunk_index as usize / 8)
+            .is_some_and(|byte| byte & (1 << (chunk_index % 8)) != 0)
+    }
+
+    /// 记录分片确认，分片首次被确认时返回 true
+    fn mark_acked(&mut self, chunk_index: u32) -> bool {
+        if chunk_index >= self.total_chunks || self.is_acked(chunk_index) {
+            return false;
+        }
+        self.acked[chunk_index as usize / 8] |= 1 << (chunk_index % 8);
+        true
+    }
+
+    /// 合并另一份清单的确认位，用于续传时吸收已持久化的进度
+    fn merge_acked(&mut self, other: &TransferManifest) {
+        for (byte, other) in self.acked.iter_mut().zip(&other.acked) {
+            *byte |= other;
+        }
+    }
+
+    /// 尚未确认的数据分片序号
+    pub fn missing_chunks(&self) -> Vec<u32> {
+        (0..self.total_chunks)
+            .filter(|i| !self.is_acked(*i))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        (0..self.total_chunks).all(|i| self.is_acked(i))
+    }
+}
+
+fn transfer_manifest_key(stream_id: Uuid) -> String {
+    format!("{}{}", TRANSFER_MANIFEST_KEY_PREFIX, stream_id.simple())
+}
+
+/// 发送端保留的可续传传输：清单与全部数据分片，续传时从中挑出未确认的分片
+struct OutgoingTransfer {
+    manifest: TransferManifest,
+    chunks: Vec<Vec<u8>>,
+    bitrate: u32,
+}
+
+/// 可靠分片发送被中途放弃时停止该流的剩余分片
+struct StopStreamOnDrop<'a> {
+    manager: &'a StreamManager,
+    stream_id: Uuid,
+    armed: bool,
+}
+
+impl Drop for StopStreamOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = self.manager.stop_stream(self.stream_id);
+        }
+    }
 }
 
 // F8: 媒体帧定义，用于重组和同步
@@ -187,35 +372,74 @@ pub struct NetworkStats {
 // F9: 网络监控器
 pub struct NetworkMonitor {
     current_network: NetworkType,
-    network_change_handlers: Vec<Box<dyn Fn(NetworkType) + Send + Sync>>,
+    // 调用方强制指定的网络类型，设置后优先于检测结果
+    override_network: Option<NetworkType>,
+    network_change_handlers: Vec<NetworkChangeHandler>,
 }
 
+/// 网络变化回调
+pub type NetworkChangeHandler = Arc<dyn Fn(NetworkType) + Send + Sync>;
+
 impl NetworkMonitor {
     pub fn new() -> Self {
         Self {
             current_network: NetworkType::Unknown,
+            override_network: None,
             network_change_handlers: Vec::new(),
         }
     }
 
+    /// 当前生效的网络类型，设置了覆盖值时返回覆盖值
     pub fn detect_network_type(&self) -> NetworkType {
-        self.current_network
+        self.override_network.unwrap_or(self.current_network)
+    }
+
+    /// 当前的网络类型覆盖值
+    pub fn network_type_override(&self) -> Option<NetworkType> {
+        self.override_network
+    }
+
+    /// 设置或清除网络类型覆盖值，生效的网络类型改变时返回需要通知的回调
+    ///
+    /// 与 [`set_network_type`](Self::set_network_type) 相同，调用方应在释放锁之后调用回调
+    pub fn set_network_type_override(
+        &mut self,
+        network: Option<NetworkType>,
+    ) -> Vec<NetworkChangeHandler> {
+        let previous = self.detect_network_type();
+        self.override_network = network;
+        if self.detect_network_type() == previous {
+            return Vec::new();
+        }
+        self.network_change_handlers.clone()
     }
 
     pub fn register_network_change_handler(
         &mut self,
         handler: Box<dyn Fn(NetworkType) + Send + Sync>,
     ) {
-        self.network_change_handlers.push(handler);
+        self.network_change_handlers.push(Arc::from(handler));
     }
 
     pub fn update_network_type(&mut self, new_network: NetworkType) {
-        if self.current_network != new_network {
-            self.current_network = new_network;
-            for handler in &self.network_change_handlers {
-                handler(new_network);
-            }
+        for handler in self.set_network_type(new_network) {
+            handler(new_network);
+        }
+    }
+
+    /// 记录新的网络类型，返回需要通知的回调
+    ///
+    /// 设置了覆盖值时只记录检测结果，不通知回调，清除覆盖值后才生效。
+    /// 监控器位于共享锁内时，调用方应先释放锁再调用回调，避免回调内获取其他锁形成嵌套
+    pub fn set_network_type(&mut self, new_network: NetworkType) -> Vec<NetworkChangeHandler> {
+        if self.current_network == new_network {
+            return Vec::new();
+        }
+        self.current_network = new_network;
+        if self.override_network.is_some() {
+            return Vec::new();
         }
+        self.network_change_handlers.clone()
     }
 }
 
@@ -232,9 +456,17 @@ struct BitrateController {
     _network_type: NetworkType,
     rtt_ms: u32,
     packet_loss_rate: f32,
+    // 上次调整码率的时间（Unix 毫秒）
     last_adjustment_time: u64,
 }
 
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 impl BitrateController {
     fn new(network_type: NetworkType) -> Self {
         let initial_bitrate = match network_type {
@@ -252,22 +484,30 @@ impl BitrateController {
             _network_type: network_type,
             rtt_ms: 0,
             packet_loss_rate: 0.0,
-            last_adjustment_time: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            last_adjustment_time: now_millis(),
         }
     }
 
+    /// 以指定码率为起点创建控制器，码率限制在合法范围内
+    fn with_bitrate(network_type: NetworkType, bitrate: u32) -> Self {
+        let mut controller = Self::new(network_type);
+        controller.set_bitrate(bitrate);
+        controller
+    }
+
+    fn set_bitrate(&mut self, bitrate: u32) {
+        let bitrate = bitrate.clamp(VIDEO_BITRATE_MIN, VIDEO_BITRATE_MAX);
+        self.current_bitrate = bitrate;
+        self.target_bitrate = bitrate;
+    }
+
     fn update_network_stats(&mut self, rtt_ms: u32, packet_loss_rate: f32) {
         self.rtt_ms = rtt_ms;
         self.packet_loss_rate = packet_loss_rate;
 
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        if current_time - self.last_adjustment_time >= BITRATE_ADJUSTMENT_INTERVAL_MS / 1000 {
+        let current_time = now_millis();
+        if current_time.saturating_sub(self.last_adjustment_time) >= BITRATE_ADJUSTMENT_INTERVAL_MS
+        {
             self.adjust_bitrate();
             self.last_adjustment_time = current_time;
         }
@@ -306,6 +546,10 @@ impl BitrateController {
 struct StreamSession {
     total_chunks: u32,
     received_chunks: HashMap<u32, Vec<u8>>,
+    // 前向纠错校验分片：组序号 -> 校验数据
+    parity_chunks: HashMap<u32, Vec<u8>>,
+    // 发送方声明的重组后数据 SHA-256，首个携带摘要的分片到达时记录
+    checksum: Option<[u8; 32]>,
     last_activity: u64,
     stream_type: StreamType,
     #[allow(dead_code)]
@@ -315,8 +559,11 @@ struct StreamSession {
     audio_buffer: Option<Vec<u8>>, // 音频帧缓冲区
     #[allow(dead_code)]
     video_frame_buffer: Option<Vec<u8>>, // 视频帧缓冲区
-    #[allow(dead_code)]
-    jitter_buffer: Vec<Vec<u8>>, // 抖动缓冲区
+    // F8: 音频抖动缓冲区，按时间戳排序，到达播放时间后才交付
+    jitter_buffer: Vec<MediaFrame>,
+    // 已交付的最新音频帧时间戳，早于它到达的帧视为迟到
+    playout_position: Option<u64>,
+    jitter_stats: JitterStats,
     // F8: 优先级队列，按时间戳排序
     priority_queue: Vec<MediaFrame>,
     // F9: 网络统计信息
@@ -324,19 +571,20 @@ struct StreamSession {
 }
 
 impl StreamSession {
-    fn new(stream_type: StreamType, total_chunks: u32) -> Self {
+    fn new(stream_type: StreamType, total_chunks: u32, last_activity: u64) -> Self {
         Self {
             total_chunks,
             received_chunks: HashMap::new(),
-            last_activity: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            parity_chunks: HashMap::new(),
+            checksum: None,
+            last_activity,
             stream_type,
             metadata: None,
             audio_buffer: None,
             video_frame_buffer: None,
             jitter_buffer: Vec::new(),
+            playout_position: None,
+            jitter_stats: JitterStats::default(),
             priority_queue: Vec::new(),
             network_stats: Some(NetworkStats {
                 rtt_ms: 0,
@@ -354,6 +602,24 @@ impl StreamSession {
         self.received_chunks.len() == self.total_chunks as usize
     }
 
+    /// 组内恰好丢失一个数据分片且校验分片已到达时恢复该分片
+    fn recover_group(&mut self, group: u32, group_size: u32) {
+        let Some(parity) = self.parity_chunks.get(&group) else {
+            return;
+        };
+        let range = fec::group_range(group, self.total_chunks, group_size);
+        let mut missing = range
+            .clone()
+            .filter(|i| !self.received_chunks.contains_key(i));
+        let (Some(index), None) = (missing.next(), missing.next()) else {
+            return;
+        };
+        if let Some(data) = fec::recover_missing(parity, &self.received_chunks, range) {
+            log::debug!("Recovered chunk {} from parity group {}", index, group);
+            self.received_chunks.insert(index, data);
+        }
+    }
+
     fn get_data(&self) -> Vec<u8> {
         let mut result = Vec::new();
         for i in 0..self.total_chunks {
@@ -375,60 +641,367 @@ enum StreamControlMessage {
     AdjustBitrate(u32),
 }
 
+/// 视频分片匀速发送任务所需的共享状态
+struct PacingContext {
+    stream_id: Uuid,
+    local_device_id: DeviceId,
+    recipient: DeviceId,
+    // 数据分片总数，不含校验分片
+    total_chunks: u32,
+    fec_group_size: Option<u32>,
+    compression: Option<CompressionAlgorithm>,
+    checksum: Option<[u8; 32]>,
+    router: Arc<Router>,
+    clock: Arc<dyn Clock>,
+    bitrate_controllers: Arc<Mutex<HashMap<Uuid, BitrateController>>>,
+    controllers: Arc<Mutex<HashMap<Uuid, mpsc::Sender<StreamControlMessage>>>>,
+    progress: Arc<Mutex<HashMap<Uuid, watch::Receiver<u32>>>>,
+    // 已发出的分片数，任务结束时随上下文一起释放
+    chunks_sent: watch::Sender<u32>,
+}
+
+impl PacingContext {
+    fn current_bitrate(&self) -> u32 {
+        lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+            .expect("Failed to acquire bitrate_controllers lock")
+            .get(&self.stream_id)
+            .map(|c| c.get_current_bitrate())
+            .unwrap_or(VIDEO_BITRATE_INITIAL)
+    }
+
+    fn set_bitrate(&self, bitrate: u32) {
+        if let Some(controller) =
+            lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                .expect("Failed to acquire bitrate_controllers lock")
+                .get_mut(&self.stream_id)
+        {
+            controller.set_bitrate(bitrate);
+        }
+    }
+
+    /// 用对端在发送通道上的当前 RTT 与丢包率更新码率控制器
+    fn apply_link_stats(&self, channel: ChannelType) {
+        let Some(state) = self
+            .router
+            .capability_manager()
+            .get_channel_state(&self.recipient, &channel)
+        else {
+            return;
+        };
+        if let Some(controller) =
+            lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                .expect("Failed to acquire bitrate_controllers lock")
+                .get_mut(&self.stream_id)
+        {
+            controller.update_network_stats(state.rtt_ms, state.packet_loss_rate);
+            log::debug!(
+                "Stream {} bitrate {} bps (RTT: {}ms, Loss: {:.2}%)",
+                self.stream_id,
+                controller.get_current_bitrate(),
+                state.rtt_ms,
+                state.packet_loss_rate * 100.0
+            );
+        }
+    }
+}
+
+/// 按当前码率匀速发送视频分片
+///
+/// 每个分片发出后按 `分片比特数 / 当前码率` 推进下一次发送时间，码率在发送过程中
+/// 被调整时立即生效。每隔 `BITRATE_ADJUSTMENT_INTERVAL_MS` 读取对端发送通道的 RTT 与
+/// 丢包率反馈给码率控制器，链路持续丢包时发送速率随之下降。分片布局在开始发送时确定，
+/// 接收端据此重组，因此码率变化只影响发送节奏。分片之间处理控制消息：暂停时阻塞直到恢复或停止。
+async fn pace_video_chunks(
+    ctx: PacingContext,
+    chunks: Vec<(u32, Vec<u8>)>,
+    mut control_rx: mpsc::Receiver<StreamControlMessage>,
+) {
+    let mut next_send = tokio::time::Instant::now();
+    let mut last_stats_read = tokio::time::Instant::now();
+    let stats_interval = std::time::Duration::from_millis(BITRATE_ADJUSTMENT_INTERVAL_MS);
+
+    'chunks: for (i, (chunk_index, chunk)) in chunks.into_iter().enumerate() {
+        tokio::time::sleep_until(next_send).await;
+
+        // 处理分片间到达的控制消息
+        let mut paused = false;
+        loop {
+            let control = if paused {
+                control_rx.recv().await
+            } else {
+                match control_rx.try_recv() {
+                    Ok(control) => Some(control),
+                    Err(_) => break,
+                }
+            };
+            match control {
+                Some(StreamControlMessage::Pause) => paused = true,
+                Some(StreamControlMessage::Resume) | Some(StreamControlMessage::Start) => {
+                    if paused {
+                        // 暂停期间不累积发送配额
+                        next_send = tokio::time::Instant::now();
+                    }
+                    paused = false;
+                }
+                Some(StreamControlMessage::AdjustBitrate(bitrate)) => ctx.set_bitrate(bitrate),
+                Some(StreamControlMessage::Stop) | None => {
+                    log::info!("Video stream {} stopped after {} chunks", ctx.stream_id, i);
+                    break 'chunks;
+                }
+            }
+        }
+
+        let chunk_bits = chunk.len() as u64 * 8;
+        let chunk_crc = integrity::crc32(&chunk);
+        let mut message = Message::new(
+            ctx.local_device_id,
+            ctx.recipient,
+            MessagePayload::StreamChunk {
+                stream_id: ctx.stream_id,
+                chunk_index,
+                total_chunks: ctx.total_chunks,
+                data: chunk,
+                sent_at: ctx.clock.now_unix_millis(),
+                fec_group_size: ctx.fec_group_size,
+                compression: ctx.compression,
+                checksum: ctx.checksum,
+                chunk_crc: Some(chunk_crc),
+            },
+        );
+        ctx.router.sign_message(&mut message);
+        let mut sent_via = None;
+        if let Ok(channel) = ctx.router.select_channel(&message).await {
+            let ctype = channel.channel_type();
+            sent_via = Some(ctype);
+            match channel.send(message).await {
+                // 分片同样计入计量网络的流量预算
+                Ok(()) => {
+                    ctx.router.record_metered(ctype, chunk_bits / 8);
+                }
+                Err(e) => log::warn!(
+                    "Failed to send chunk {} of stream {}: {}",
+                    chunk_index,
+                    ctx.stream_id,
+                    e
+                ),
+            }
+        }
+        ctx.chunks_sent.send_replace(i as u32 + 1);
+
+        // 先更新控制器再记录读取时间，保证下次读取时控制器的调整间隔也已届满
+        if last_stats_read.elapsed() >= stats_interval {
+            if let Some(channel) = sent_via {
+                ctx.apply_link_stats(channel);
+            }
+            last_stats_read = tokio::time::Instant::now();
+        }
+
+        let bitrate = u64::from(ctx.current_bitrate().max(1));
+        next_send += std::time::Duration::from_micros(chunk_bits * 1_000_000 / bitrate);
+    }
+
+    lock_ordered(&ctx.controllers, lock_order::CONTROLLERS)
+        .expect("Failed to acquire controllers lock")
+        .remove(&ctx.stream_id);
+    lock_ordered(&ctx.progress, lock_order::PROGRESS)
+        .expect("Failed to acquire progress lock")
+        .remove(&ctx.stream_id);
+    log::info!("Video stream {} sent to {}", ctx.stream_id, ctx.recipient);
+}
+
+/// 流分片携带的完整性校验信息，字段为 None 时跳过对应校验
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkIntegrity {
+    /// 全部数据分片拼接后的 SHA-256
+    pub checksum: Option<[u8; 32]>,
+    /// 本分片数据的 CRC32
+    pub chunk_crc: Option<u32>,
+}
+
+/// 流会话键：(发送方设备, 流 ID)，本地发起的流以本机设备为发送方
+///
+/// 不同发送方的流 ID 相互独立，碰撞或被恶意复用的流 ID 不会串入其他发送方的重组
+type SessionKey = (DeviceId, Uuid);
+
 #[allow(dead_code)]
 pub struct StreamManager {
     local_device_id: DeviceId,
     router: Arc<Router>,
-    sessions: Arc<Mutex<HashMap<Uuid, StreamSession>>>,
-    #[allow(dead_code)]
+    sessions: Arc<Mutex<HashMap<SessionKey, StreamSession>>>,
     controllers: Arc<Mutex<HashMap<Uuid, mpsc::Sender<StreamControlMessage>>>>,
     bitrate_controllers: Arc<Mutex<HashMap<Uuid, BitrateController>>>,
     network_monitor: Arc<Mutex<NetworkMonitor>>,
     user_preferences: Arc<Mutex<UserTrafficPreferences>>,
+    buffer_config: Arc<Mutex<MediaBufferConfig>>,
+    event_handlers: Arc<Mutex<Vec<StreamEventHandler>>>,
+    // 发送中视频流的进度：已发出的分片数
+    progress: Arc<Mutex<HashMap<Uuid, watch::Receiver<u32>>>>,
+    // 尚未全部确认的视频流，保留分片数据以便续传
+    transfers: Arc<Mutex<HashMap<Uuid, OutgoingTransfer>>>,
+    // 持久化传输清单的存储，未设置时清单只保存在内存中
+    transfer_storage: parking_lot::RwLock<Option<Arc<dyn Storage>>>,
+    // 串行化清单写入，避免并发确认以旧进度覆盖新进度
+    transfer_persist: tokio::sync::Mutex<()>,
+    // 会话活动时间、超时清理与清单保留期的时间来源
+    clock: Arc<dyn Clock>,
+}
+
+/// 将数据追加到有界缓冲区，返回因溢出而丢弃的字节数
+fn push_bounded(
+    buffer: &mut Vec<u8>,
+    data: &[u8],
+    max_bytes: usize,
+    policy: BufferOverflowPolicy,
+) -> usize {
+    match policy {
+        BufferOverflowPolicy::DropOldest => {
+            buffer.extend_from_slice(data);
+            let excess = buffer.len().saturating_sub(max_bytes);
+            buffer.drain(0..excess);
+            excess
+        }
+        BufferOverflowPolicy::DropNewest => {
+            if buffer.len() + data.len() > max_bytes {
+                data.len()
+            } else {
+                buffer.extend_from_slice(data);
+                0
+            }
+        }
+    }
 }
 
 impl StreamManager {
     // F8: 处理接收到的流分片
+    //
+    // 携带 `fec_group_size` 的流中，序号不小于 `total_chunks` 的分片为校验分片：组内只丢失
+    // 一个数据分片时立即恢复；最后一组的校验分片到达后仍未收齐则判定失败，返回首个无法
+    // 恢复的分片序号。尚无会话的校验分片视为已重组完成的流的残留分片，直接忽略。
     pub async fn handle_chunk(
         &self,
+        sender: DeviceId,
+        stream_id: Uuid,
+        total_chunks: u32,
+        chunk_index: u32,
+        data: Vec<u8>,
+        fec_group_size: Option<u32>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.handle_chunk_verified(
+            sender,
+            stream_id,
+            total_chunks,
+            chunk_index,
+            data,
+            fec_group_size,
+            ChunkIntegrity::default(),
+        )
+        .await
+    }
+
+    /// 处理携带完整性校验信息的流分片
+    ///
+    /// CRC32 不符的分片不写入会话并返回错误，调用方不应确认该分片，发送方续传时重发；
+    /// 重组完成后校验整个流的 SHA-256，不符时丢弃会话并返回错误。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn handle_chunk_verified(
+        &self,
+        sender: DeviceId,
         stream_id: Uuid,
         total_chunks: u32,
         chunk_index: u32,
         data: Vec<u8>,
+        fec_group_size: Option<u32>,
+        verify: ChunkIntegrity,
     ) -> Result<Option<Vec<u8>>> {
+        if !integrity::chunk_intact(&data, verify.chunk_crc) {
+            log::warn!(
+                "Dropping corrupted chunk {} of stream {} from {}",
+                chunk_index,
+                stream_id,
+                sender
+            );
+            return Err(XLinkError::stream_integrity_failed(
+                stream_id.to_string(),
+                format!("CRC32 mismatch on chunk {}", chunk_index),
+                file!(),
+            ));
+        }
+
+        let fec_group_size = fec_group_size.filter(|size| *size > 0);
+        let is_parity = fec_group_size.is_some() && chunk_index >= total_chunks;
+        let now = self.clock.now_unix_secs();
         let is_complete;
+        let mut unrecoverable = None;
         {
-            let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
+
+            if is_parity && !sessions.contains_key(&(sender, stream_id)) {
+                return Ok(None);
+            }
 
             // 获取或创建会话
             let session = sessions
-                .entry(stream_id)
-                .or_insert_with(|| StreamSession::new(StreamType::Data, total_chunks));
+                .entry((sender, stream_id))
+                .or_insert_with(|| StreamSession::new(StreamType::Data, total_chunks, now));
 
             // 更新会话信息
             session.total_chunks = total_chunks;
-            session.received_chunks.insert(chunk_index, data);
-            session.last_activity = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            if verify.checksum.is_some() {
+                session.checksum = verify.checksum;
+            }
+            match fec_group_size {
+                Some(group_size) if is_parity => {
+                    let group = chunk_index - total_chunks;
+                    let groups = fec::group_count(total_chunks, group_size);
+                    if group < groups {
+                        session.parity_chunks.insert(group, data);
+                        session.recover_group(group, group_size);
+                        if group + 1 == groups && !session.is_complete() {
+                            unrecoverable = (0..total_chunks)
+                                .find(|i| !session.received_chunks.contains_key(i));
+                        }
+                    }
+                }
+                Some(group_size) => {
+                    session.received_chunks.insert(chunk_index, data);
+                    session.recover_group(chunk_index / group_size, group_size);
+                }
+                None => {
+                    session.received_chunks.insert(chunk_index, data);
+                }
+            }
+            session.last_activity = now;
 
             log::debug!(
-                "Received chunk {}/{} for stream {}",
+                "Received chunk {}/{} for stream {} from {}",
                 chunk_index + 1,
                 total_chunks,
-                stream_id
+                stream_id,
+                sender
             );
 
             is_complete = session.received_chunks.len() as u32 == session.total_chunks;
+            if unrecoverable.is_some() {
+                sessions.remove(&(sender, stream_id));
+            }
+        }
+
+        if let Some(index) = unrecoverable {
+            return Err(XLinkError::stream_init_failed(
+                "chunk_assembly".to_string(),
+                format!("Missing chunk {} for stream {}", index, stream_id),
+                file!(),
+            ));
         }
 
         // 检查是否所有分片都已接收
         if is_complete {
             let session_opt;
             {
-                let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-                session_opt = sessions.remove(&stream_id);
+                let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                    .expect("Failed to acquire sessions lock");
+                session_opt = sessions.remove(&(sender, stream_id));
             }
 
             if let Some(mut session) = session_opt {
@@ -446,6 +1019,16 @@ impl StreamManager {
                     }
                 }
 
+                if let Some(expected) = session.checksum {
+                    if integrity::payload_digest(&full_data) != expected {
+                        return Err(XLinkError::stream_integrity_failed(
+                            stream_id.to_string(),
+                            "SHA-256 mismatch after reassembly".to_string(),
+                            file!(),
+                        ));
+                    }
+                }
+
                 log::info!(
                     "Stream {} reassembled successfully ({} chunks, {} bytes)",
                     stream_id,
@@ -460,6 +1043,15 @@ impl StreamManager {
     }
 
     pub fn new(local_device_id: DeviceId, router: Arc<Router>) -> Self {
+        Self::with_clock(local_device_id, router, system_clock())
+    }
+
+    /// 使用指定时钟创建，会话超时与传输清单保留期均以该时钟为准
+    pub fn with_clock(
+        local_device_id: DeviceId,
+        router: Arc<Router>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         let manager = Self {
             local_device_id,
             router,
@@ -468,6 +1060,13 @@ impl StreamManager {
             bitrate_controllers: Arc::new(Mutex::new(HashMap::new())),
             network_monitor: Arc::new(Mutex::new(NetworkMonitor::new())),
             user_preferences: Arc::new(Mutex::new(UserTrafficPreferences::default())),
+            buffer_config: Arc::new(Mutex::new(MediaBufferConfig::default())),
+            event_handlers: Arc::new(Mutex::new(Vec::new())),
+            progress: Arc::new(Mutex::new(HashMap::new())),
+            transfers: Arc::new(Mutex::new(HashMap::new())),
+            transfer_storage: parking_lot::RwLock::new(None),
+            transfer_persist: tokio::sync::Mutex::new(()),
+            clock,
         };
 
         // 注册网络变更处理程序
@@ -479,8 +1078,7 @@ impl StreamManager {
     fn register_network_change_handler(&self) {
         let bitrate_controllers = Arc::clone(&self.bitrate_controllers);
 
-        self.network_monitor
-            .lock()
+        lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
             .unwrap()
             .register_network_change_handler(Box::new(move |new_network| {
                 log::info!(
@@ -489,7 +1087,9 @@ impl StreamManager {
                 );
 
                 // 根据网络类型调整所有活跃的码率控制器
-                let mut controllers = bitrate_controllers.lock().expect("Failed to acquire bitrate_controllers lock");
+                let mut controllers =
+                    lock_ordered(&bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                        .expect("Failed to acquire bitrate_controllers lock");
                 for (_, controller) in controllers.iter_mut() {
                     // 重新初始化码率控制器以适应新的网络环境
                     *controller = BitrateController::new(new_network);
@@ -500,6 +1100,47 @@ impl StreamManager {
             }));
     }
 
+    /// 通知网络类型变化，在释放网络监控器锁之后调用回调
+    pub fn update_network_type(&self, new_network: NetworkType) {
+        let handlers = lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
+            .expect("Failed to acquire network_monitor lock")
+            .set_network_type(new_network);
+        for handler in handlers {
+            handler(new_network);
+        }
+    }
+
+    /// 强制指定网络类型，跳过网卡探测；传入 None 恢复为检测结果
+    ///
+    /// 生效的网络类型改变时通过网络变化回调重新初始化码率控制器。
+    /// 适用于测试，以及容器或共享热点等检测结果不可靠的环境
+    pub fn set_network_type_override(&self, network: Option<NetworkType>) {
+        let (handlers, effective) = {
+            let mut monitor = lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
+                .expect("Failed to acquire network_monitor lock");
+            let handlers = monitor.set_network_type_override(network);
+            (handlers, monitor.detect_network_type())
+        };
+        log::info!("Network type override set to {:?}", network);
+        for handler in handlers {
+            handler(effective);
+        }
+    }
+
+    /// 当前的网络类型覆盖值
+    pub fn network_type_override(&self) -> Option<NetworkType> {
+        lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
+            .expect("Failed to acquire network_monitor lock")
+            .network_type_override()
+    }
+
+    /// 当前网络类型
+    pub fn network_type(&self) -> NetworkType {
+        lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
+            .expect("Failed to acquire network_monitor lock")
+            .detect_network_type()
+    }
+
     // F8: 发送音频流（专用接口）
     pub async fn send_audio_stream(
         &self,
@@ -514,6 +1155,7 @@ impl StreamManager {
             video_config: None,
             estimated_bandwidth_bps: 128_000,
             target_latency_ms: MAX_AUDIO_LATENCY_MS,
+            fec_group_size: None,
         };
 
         log::info!(
@@ -527,21 +1169,23 @@ impl StreamManager {
         // 创建音频特定的流会话
         let stream_id = Uuid::new_v4();
         {
-            let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
             sessions.insert(
-                stream_id,
+                (self.local_device_id, stream_id),
                 StreamSession {
                     total_chunks: 0,
                     received_chunks: HashMap::new(),
-                    last_activity: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
+                    parity_chunks: HashMap::new(),
+                    checksum: None,
+                    last_activity: self.clock.now_unix_secs(),
                     stream_type: StreamType::Audio,
                     metadata: Some(metadata),
                     audio_buffer: Some(Vec::with_capacity(AUDIO_FRAME_SIZE * 10)),
                     video_frame_buffer: None,
                     jitter_buffer: Vec::new(),
+                    playout_position: None,
+                    jitter_stats: JitterStats::default(),
                     priority_queue: Vec::new(),
                     network_stats: Some(NetworkStats {
                         rtt_ms: 0,
@@ -558,8 +1202,7 @@ impl StreamManager {
 
         // 初始化音频码率控制器
         let bitrate_controller = BitrateController::new(NetworkType::Unknown);
-        self.bitrate_controllers
-            .lock()
+        lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
             .unwrap()
             .insert(stream_id, bitrate_controller);
 
@@ -575,21 +1218,16 @@ impl StreamManager {
                     stream_id,
                     frame_index: i as u64,
                     data: frame.clone(),
-                    timestamp: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64,
+                    timestamp: self.clock.now_unix_millis(),
                 },
             );
             frame_message.priority = crate::core::types::MessagePriority::High; // 音频流高优先级
 
-            let channel_res = self.router.select_channel(&frame_message).await;
-            if let Ok(channel) = channel_res {
-                let msg = frame_message;
-                tokio::spawn(async move {
-                    let _ = channel.send(msg).await;
-                });
-            }
+            // 经发送队列交给通道，避免高帧率时压垮慢通道
+            let router = self.router.clone();
+            tokio::spawn(async move {
+                let _ = router.enqueue_send(frame_message).await;
+            });
         }
 
         log::info!(
@@ -629,17 +1267,18 @@ impl StreamManager {
                 total_chunks: 0,                      // 音频流是持续的
                 data: frame_data,
                 sent_at: timestamp,
+                fec_group_size: None,
+                compression: None,
+                checksum: None,
+                chunk_crc: None,
             },
         );
 
         // 尝试发送，忽略可能的路由错误（音频流允许丢包）
-        let channel_res = self.router.select_channel(&frame_message).await;
-        if let Ok(channel) = channel_res {
-            let msg = frame_message;
-            tokio::spawn(async move {
-                let _ = channel.send(msg).await;
-            });
-        }
+        let router = self.router.clone();
+        tokio::spawn(async move {
+            let _ = router.enqueue_send(frame_message).await;
+        });
 
         Ok(())
     }
@@ -658,6 +1297,7 @@ impl StreamManager {
             video_config: Some(video_config.clone()),
             estimated_bandwidth_bps: video_config.bitrate,
             target_latency_ms: 100, // 视频流目标延迟 100ms
+            fec_group_size: video_config.fec_group_size,
         };
 
         log::info!(
@@ -672,21 +1312,23 @@ impl StreamManager {
         // 创建视频特定的流会话
         let stream_id = Uuid::new_v4();
         {
-            let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
             sessions.insert(
-                stream_id,
+                (self.local_device_id, stream_id),
                 StreamSession {
                     total_chunks: 0,
                     received_chunks: HashMap::new(),
-                    last_activity: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
+                    parity_chunks: HashMap::new(),
+                    checksum: None,
+                    last_activity: self.clock.now_unix_secs(),
                     stream_type: StreamType::Video,
                     metadata: Some(metadata),
                     audio_buffer: None,
                     video_frame_buffer: Some(Vec::new()),
                     jitter_buffer: Vec::new(),
+                    playout_position: None,
+                    jitter_stats: JitterStats::default(),
                     priority_queue: Vec::new(),
                     network_stats: Some(NetworkStats {
                         rtt_ms: 0,
@@ -701,53 +1343,411 @@ impl StreamManager {
             );
         }
 
-        // 初始化视频码率控制器
-        let bitrate_controller = BitrateController::new(NetworkType::Unknown);
+        // 初始化视频码率控制器（以配置码率为起点）
+        let bitrate_controller =
+            BitrateController::with_bitrate(NetworkType::Unknown, video_config.bitrate);
         {
-            let mut controllers = self.bitrate_controllers.lock().expect("Failed to acquire bitrate_controllers lock");
+            let mut controllers =
+                lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                    .expect("Failed to acquire bitrate_controllers lock");
             controllers.insert(stream_id, bitrate_controller);
         }
 
-        // 将视频数据分片处理
+        // 将视频数据分片处理，开启前向纠错时每组数据分片后紧跟一个校验分片
+        let total_chunks = video_data.len().div_ceil(CHUNK_SIZE) as u32;
+        let checksum = integrity::payload_digest(&video_data);
         let chunks = self.split_video_into_chunks(video_data, &video_config);
-
-        // 发送视频分片
-        for (i, chunk) in chunks.iter().enumerate() {
-            let chunk_message = Message::new(
-                self.local_device_id,
-                recipient,
-                MessagePayload::StreamChunk {
-                    stream_id,
-                    chunk_index: i as u32,
-                    total_chunks: chunks.len() as u32,
-                    data: chunk.clone(),
-                    sent_at: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64,
+        let fec_group_size = video_config.fec_group_size.filter(|size| *size > 0);
+
+        // 记录传输清单，链路中断后可只重发未确认的分片
+        let data_chunks = chunks
+            .iter()
+            .filter(|(index, _)| *index < total_chunks)
+            .map(|(_, chunk)| chunk.clone())
+            .collect();
+        let manifest = TransferManifest::new(
+            stream_id,
+            recipient,
+            total_chunks,
+            fec_group_size,
+            video_config.compression,
+            Some(checksum),
+            self.clock.now_unix_secs(),
+        );
+        lock_ordered(&self.transfers, lock_order::TRANSFERS)
+            .expect("Failed to acquire transfers lock")
+            .insert(
+                stream_id,
+                OutgoingTransfer {
+                    manifest: manifest.clone(),
+                    chunks: data_chunks,
+                    bitrate: video_config.bitrate,
                 },
             );
+        self.add_to_transfer_index(stream_id).await;
+        self.persist_transfer(stream_id).await;
 
-            // 尝试发送
-            let channel_res = self.router.select_channel(&chunk_message).await;
-            if let Ok(channel) = channel_res {
-                let msg = chunk_message;
-                tokio::spawn(async move {
-                    let _ = channel.send(msg).await;
-                });
-            }
-        }
+        self.spawn_pacing(&manifest, chunks);
 
         log::info!(
-            "Video stream {} sent successfully to {}",
+            "Video stream {} to {} scheduled for paced sending",
             stream_id,
             recipient
         );
         Ok(stream_id)
     }
 
+    /// 登记控制通道与进度后，在后台按当前码率匀速发送分片，避免一次性涌入通道
+    fn spawn_pacing(&self, manifest: &TransferManifest, chunks: Vec<(u32, Vec<u8>)>) {
+        let stream_id = manifest.stream_id;
+        // 控制通道：发送期间可暂停、恢复、停止或调整码率
+        let (control_tx, control_rx) = mpsc::channel(STREAM_CONTROL_CAPACITY);
+        lock_ordered(&self.controllers, lock_order::CONTROLLERS)
+            .expect("Failed to acquire controllers lock")
+            .insert(stream_id, control_tx);
+        let (chunks_sent, progress_rx) = watch::channel(0);
+        lock_ordered(&self.progress, lock_order::PROGRESS)
+            .expect("Failed to acquire progress lock")
+            .insert(stream_id, progress_rx);
+
+        tokio::spawn(pace_video_chunks(
+            PacingContext {
+                stream_id,
+                local_device_id: self.local_device_id,
+                recipient: manifest.recipient,
+                total_chunks: manifest.total_chunks,
+                fec_group_size: manifest.fec_group_size,
+                compression: manifest.compression,
+                checksum: manifest.checksum,
+                router: self.router.clone(),
+                clock: self.clock.clone(),
+                bitrate_controllers: self.bitrate_controllers.clone(),
+                controllers: self.controllers.clone(),
+                progress: self.progress.clone(),
+                chunks_sent,
+            },
+            chunks,
+            control_rx,
+        ));
+    }
+
+    /// 设置持久化传输清单的存储，设置前发起的传输不会补写
+    pub fn set_transfer_storage(&self, storage: Option<Arc<dyn Storage>>) {
+        *self.transfer_storage.write() = storage;
+    }
+
+    /// 发送中或等待续传的视频流的传输清单，全部确认或未知的流返回 None
+    pub fn transfer_manifest(&self, stream_id: Uuid) -> Option<TransferManifest> {
+        lock_ordered(&self.transfers, lock_order::TRANSFERS)
+            .expect("Failed to acquire transfers lock")
+            .get(&stream_id)
+            .map(|t| t.manifest.clone())
+    }
+
+    /// 处理接收方回送的分片确认，只接受流的原接收方的确认
+    ///
+    /// 全部数据分片确认后释放保留的分片数据并删除持久化清单
+    pub async fn handle_chunk_ack(&self, sender: DeviceId, stream_id: Uuid, chunk_index: u32) {
+        let complete = {
+            let mut transfers = lock_ordered(&self.transfers, lock_order::TRANSFERS)
+                .expect("Failed to acquire transfers lock");
+            let Some(transfer) = transfers.get_mut(&stream_id) else {
+                return;
+            };
+            if transfer.manifest.recipient != sender || !transfer.manifest.mark_acked(chunk_index) {
+                return;
+            }
+            let complete = transfer.manifest.is_complete();
+            if complete {
+                transfers.remove(&stream_id);
+            }
+            complete
+        };
+
+        if complete {
+            log::info!("Transfer {} acknowledged by {}", stream_id, sender);
+            self.remove_transfer_record(stream_id).await;
+        } else {
+            self.persist_transfer(stream_id).await;
+        }
+    }
+
+    /// 续传视频流：只重发尚未被确认的数据分片，返回重发的分片数
+    ///
+    /// 持久化清单中的确认进度会先合并到内存清单。分片数据只保留在内存中，进程重启后
+    /// 无法续传；流仍在发送中时返回错误，应先等待发送结束或调用 `stop_stream`
+    pub async fn resume_transfer(&self, stream_id: Uuid) -> Result<u32> {
+        if lock_ordered(&self.controllers, lock_order::CONTROLLERS)
+            .expect("Failed to acquire controllers lock")
+            .contains_key(&stream_id)
+        {
+            return Err(XLinkError::invalid_state(
+                "resume_transfer".to_string(),
+                format!("Stream {} is still sending", stream_id),
+                file!(),
+            ));
+        }
+
+        let persisted = self.load_transfer(stream_id).await;
+        let (manifest, bitrate, chunks) = {
+            let mut transfers = lock_ordered(&self.transfers, lock_order::TRANSFERS)
+                .expect("Failed to acquire transfers lock");
+            let Some(transfer) = transfers.get_mut(&stream_id) else {
+                return Err(XLinkError::stream_disconnected(
+                    format!("stream_id={}", stream_id),
+                    format!("No resumable transfer: {}", stream_id),
+                    file!(),
+                ));
+            };
+            if let Some(persisted) = &persisted {
+                transfer.manifest.merge_acked(persisted);
+            }
+            let chunks: Vec<(u32, Vec<u8>)> = transfer
+                .manifest
+                .missing_chunks()
+                .into_iter()
+                .filter_map(|i| {
+                    transfer
+                        .chunks
+                        .get(i as usize)
+                        .map(|chunk| (i, chunk.clone()))
+                })
+                .collect();
+            (transfer.manifest.clone(), transfer.bitrate, chunks)
+        };
+
+        let resent = chunks.len() as u32;
+        if resent == 0 {
+            lock_ordered(&self.transfers, lock_order::TRANSFERS)
+                .expect("Failed to acquire transfers lock")
+                .remove(&stream_id);
+            self.remove_transfer_record(stream_id).await;
+            return Ok(0);
+        }
+
+        lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+            .expect("Failed to acquire bitrate_controllers lock")
+            .entry(stream_id)
+            .or_insert_with(|| BitrateController::with_bitrate(NetworkType::Unknown, bitrate));
+        self.persist_transfer(stream_id).await;
+        self.spawn_pacing(&manifest, chunks);
+
+        log::info!(
+            "Resuming transfer {} to {}: {} of {} chunks unacknowledged",
+            stream_id,
+            manifest.recipient,
+            resent,
+            manifest.total_chunks
+        );
+        Ok(resent)
+    }
+
+    /// 可靠分片发送：分片发出后等待接收方逐片确认，确认超时的分片经续传重发
+    ///
+    /// 全部数据分片确认后返回流 ID。续传轮数用尽仍有未确认的分片时返回超时错误，
+    /// 传输清单保留，之后仍可调用 `resume_transfer`。返回前 future 被丢弃时停止剩余分片
+    pub async fn send_reliable_stream(
+        &self,
+        recipient: DeviceId,
+        data: Vec<u8>,
+        compression: Option<CompressionAlgorithm>,
+    ) -> Result<Uuid> {
+        let config = VideoConfig {
+            bitrate: VIDEO_BITRATE_MAX,
+            compression,
+            ..Default::default()
+        };
+        let stream_id = self
+            .send_video_stream(recipient, data, Some(config))
+            .await?;
+        let mut guard = StopStreamOnDrop {
+            manager: self,
+            stream_id,
+            armed: true,
+        };
+
+        for round in 0..=RELIABLE_MAX_RESENDS {
+            if round > 0 {
+                let resent = self.resume_transfer(stream_id).await?;
+                log::warn!(
+                    "Reliable stream {} to {}: resending {} unacknowledged chunks (round {})",
+                    stream_id,
+                    recipient,
+                    resent,
+                    round
+                );
+            }
+            if self.wait_for_transfer_acks(stream_id).await {
+                guard.armed = false;
+                return Ok(stream_id);
+            }
+        }
+
+        guard.armed = false;
+        Err(XLinkError::timeout(
+            format!("acknowledgement of stream {} by {}", stream_id, recipient),
+            RELIABLE_ACK_TIMEOUT_MS * u64::from(RELIABLE_MAX_RESENDS + 1),
+            file!(),
+        ))
+    }
+
+    /// 等待本轮分片发完，再在确认超时内等待全部数据分片被确认
+    async fn wait_for_transfer_acks(&self, stream_id: Uuid) -> bool {
+        if let Some(mut progress) = self.stream_progress(stream_id) {
+            while progress.changed().await.is_ok() {}
+        }
+        let poll = std::time::Duration::from_millis(RELIABLE_ACK_POLL_MS);
+        let deadline =
+            tokio::time::Instant::now() + std::time::Duration::from_millis(RELIABLE_ACK_TIMEOUT_MS);
+        loop {
+            if self.transfer_manifest(stream_id).is_none() {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(poll).await;
+        }
+    }
+
+    /// 删除创建时间早于保留期的未完成传输清单及其分片数据，返回删除的条数
+    pub async fn cleanup_expired_transfers(&self, retention_days: u32) -> u64 {
+        let threshold = self
+            .clock
+            .now_unix_secs()
+            .saturating_sub(u64::from(retention_days) * 24 * 3600);
+
+        let mut expired: Vec<Uuid> = {
+            let mut transfers = lock_ordered(&self.transfers, lock_order::TRANSFERS)
+                .expect("Failed to acquire transfers lock");
+            let expired: Vec<Uuid> = transfers
+                .values()
+                .filter(|t| t.manifest.created_at < threshold)
+                .map(|t| t.manifest.stream_id)
+                .collect();
+            for stream_id in &expired {
+                transfers.remove(stream_id);
+            }
+            expired
+        };
+
+        // 进程重启后残留在存储中的清单同样按创建时间清理
+        for stream_id in self.load_transfer_index().await {
+            if expired.contains(&stream_id) {
+                continue;
+            }
+            match self.load_transfer(stream_id).await {
+                Some(manifest) if manifest.created_at >= threshold => {}
+                _ if self.transfer_manifest(stream_id).is_some() => {}
+                _ => expired.push(stream_id),
+            }
+        }
+
+        for stream_id in &expired {
+            self.remove_transfer_record(*stream_id).await;
+        }
+        if !expired.is_empty() {
+            log::info!("Cleaned up {} expired transfer manifests", expired.len());
+        }
+        expired.len() as u64
+    }
+
+    fn transfer_storage(&self) -> Option<Arc<dyn Storage>> {
+        self.transfer_storage.read().clone()
+    }
+
+    /// 将内存中的最新清单写入存储
+    async fn persist_transfer(&self, stream_id: Uuid) {
+        let Some(storage) = self.transfer_storage() else {
+            return;
+        };
+        let _persist = self.transfer_persist.lock().await;
+        let Some(manifest) = self.transfer_manifest(stream_id) else {
+            return;
+        };
+        let result = match serde_json::to_vec(&manifest) {
+            Ok(data) => {
+                storage
+                    .save_metadata(&transfer_manifest_key(stream_id), data)
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to persist transfer manifest {}: {}", stream_id, e);
+        }
+    }
+
+    /// 读取持久化清单，缺失、已清除或损坏时返回 None
+    async fn load_transfer(&self, stream_id: Uuid) -> Option<TransferManifest> {
+        let storage = self.transfer_storage()?;
+        let data = storage
+            .load_metadata(&transfer_manifest_key(stream_id))
+            .await
+            .ok()??;
+        serde_json::from_slice(&data).ok()
+    }
+
+    async fn load_transfer_index(&self) -> Vec<Uuid> {
+        let Some(storage) = self.transfer_storage() else {
+            return Vec::new();
+        };
+        match storage.load_metadata(TRANSFER_INDEX_KEY).await {
+            Ok(Some(data)) => serde_json::from_slice(&data).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    async fn save_transfer_index(&self, index: &[Uuid]) {
+        let Some(storage) = self.transfer_storage() else {
+            return;
+        };
+        let result = match serde_json::to_vec(index) {
+            Ok(data) => storage.save_metadata(TRANSFER_INDEX_KEY, data).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to save transfer index: {}", e);
+        }
+    }
+
+    async fn add_to_transfer_index(&self, stream_id: Uuid) {
+        let _persist = self.transfer_persist.lock().await;
+        let mut index = self.load_transfer_index().await;
+        if !index.contains(&stream_id) {
+            index.push(stream_id);
+            self.save_transfer_index(&index).await;
+        }
+    }
+
+    /// 以空值覆盖持久化清单并从索引中移除
+    async fn remove_transfer_record(&self, stream_id: Uuid) {
+        let Some(storage) = self.transfer_storage() else {
+            return;
+        };
+        let _persist = self.transfer_persist.lock().await;
+        if let Err(e) = storage
+            .save_metadata(&transfer_manifest_key(stream_id), Vec::new())
+            .await
+        {
+            log::warn!("Failed to clear transfer manifest {}: {}", stream_id, e);
+        }
+        let mut index = self.load_transfer_index().await;
+        let before = index.len();
+        index.retain(|id| *id != stream_id);
+        if index.len() != before {
+            self.save_transfer_index(&index).await;
+        }
+    }
+
     // F8: 将视频数据分片
-    fn split_video_into_chunks(&self, video_data: Vec<u8>, _config: &VideoConfig) -> Vec<Vec<u8>> {
+    //
+    // 返回 (分片序号, 数据) 的发送序列，配置了 `fec_group_size` 时按组插入校验分片
+    fn split_video_into_chunks(
+        &self,
+        video_data: Vec<u8>,
+        config: &VideoConfig,
+    ) -> Vec<(u32, Vec<u8>)> {
         let mut chunks = Vec::new();
         let target_chunk_size = CHUNK_SIZE;
 
@@ -755,12 +1755,20 @@ impl StreamManager {
             chunks.push(chunk.to_vec());
         }
 
-        chunks
+        match config.fec_group_size.filter(|size| *size > 0) {
+            Some(group_size) => fec::interleave_parity(chunks, group_size),
+            None => chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| (i as u32, chunk))
+                .collect(),
+        }
     }
 
     // F8: 接收流数据
     pub async fn receive_stream_data(
         &self,
+        sender: DeviceId,
         stream_id: Uuid,
         chunk_index: u32,
         total_chunks: u32,
@@ -769,40 +1777,41 @@ impl StreamManager {
         let mut result_data = Vec::new();
 
         {
-            let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-            let session = sessions.entry(stream_id).or_insert(StreamSession {
-                total_chunks,
-                received_chunks: HashMap::new(),
-                last_activity: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                stream_type: StreamType::Data, // 默认类型
-                metadata: None,
-                audio_buffer: None,
-                video_frame_buffer: None,
-                jitter_buffer: Vec::new(),
-                priority_queue: Vec::new(),
-                network_stats: Some(NetworkStats {
-                    rtt_ms: 0,
-                    packet_loss_rate: 0.0,
-                    bytes_sent: 0,
-                    bytes_received: 0,
-                    packets_sent: 0,
-                    packets_received: 0,
-                    bandwidth_bps: 0,
-                }),
-            });
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
+            let session = sessions
+                .entry((sender, stream_id))
+                .or_insert(StreamSession {
+                    total_chunks,
+                    received_chunks: HashMap::new(),
+                    parity_chunks: HashMap::new(),
+                    checksum: None,
+                    last_activity: self.clock.now_unix_secs(),
+                    stream_type: StreamType::Data, // 默认类型
+                    metadata: None,
+                    audio_buffer: None,
+                    video_frame_buffer: None,
+                    jitter_buffer: Vec::new(),
+                    playout_position: None,
+                    jitter_stats: JitterStats::default(),
+                    priority_queue: Vec::new(),
+                    network_stats: Some(NetworkStats {
+                        rtt_ms: 0,
+                        packet_loss_rate: 0.0,
+                        bytes_sent: 0,
+                        bytes_received: 0,
+                        packets_sent: 0,
+                        packets_received: 0,
+                        bandwidth_bps: 0,
+                    }),
+                });
 
             session.received_chunks.insert(chunk_index, data);
-            session.last_activity = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            session.last_activity = self.clock.now_unix_secs();
 
             if session.is_complete() {
                 result_data = session.get_data();
-                sessions.remove(&stream_id);
+                sessions.remove(&(sender, stream_id));
                 log::info!(
                     "Stream {} completed, received {} chunks",
                     stream_id,
@@ -828,45 +1837,68 @@ impl StreamManager {
         frame_data: Vec<u8>,
         timestamp: u64,
     ) -> Result<()> {
-        let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-        if let Some(session) = sessions.get_mut(&stream_id) {
-            if session.stream_type == StreamType::Audio {
-                // 将音频帧添加到缓冲区
-                if let Some(ref mut buffer) = session.audio_buffer {
-                    buffer.extend_from_slice(&frame_data);
-
-                    // 检查缓冲区大小，避免过度累积
-                    if buffer.len() > AUDIO_FRAME_SIZE * 100 {
-                        // 最多缓存100帧
-                        buffer.drain(0..AUDIO_FRAME_SIZE);
+        let config = self.get_buffer_config();
+        let mut dropped_bytes = 0;
+        {
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
+            if let Some(session) = sessions.get_mut(&(self.local_device_id, stream_id)) {
+                if session.stream_type == StreamType::Audio {
+                    // 将音频帧添加到缓冲区，超出上限时按策略丢弃
+                    if let Some(ref mut buffer) = session.audio_buffer {
+                        dropped_bytes = push_bounded(
+                            buffer,
+                            &frame_data,
+                            config.max_audio_buffer_bytes,
+                            config.overflow_policy,
+                        );
+
+                        log::debug!(
+                            "Audio frame processed for stream {}, buffer size: {} bytes",
+                            stream_id,
+                            buffer.len()
+                        );
                     }
 
-                    log::debug!(
-                        "Audio frame processed for stream {}, buffer size: {} bytes",
-                        stream_id,
-                        buffer.len()
-                    );
-                }
-
-                // 创建媒体帧并添加到优先级队列
-                let media_frame = MediaFrame {
-                    stream_id,
-                    frame_index: timestamp / 20, // 假设20ms一帧
-                    timestamp,
-                    frame_type: FrameType::Audio,
-                    data: frame_data,
-                };
-
-                session.priority_queue.push(media_frame);
-                // 按时间戳排序
-                session.priority_queue.sort_by_key(|f| f.timestamp);
+                    // 新帧被丢弃时不进入抖动缓冲区
+                    let frame_rejected = dropped_bytes > 0
+                        && config.overflow_policy == BufferOverflowPolicy::DropNewest;
+                    session.jitter_stats.frames_received += 1;
+                    if session
+                        .playout_position
+                        .is_some_and(|position| timestamp < position)
+                    {
+                        // 比已播放的帧更早，错过了播放时间
+                        session.jitter_stats.late_frames_dropped += 1;
+                        log::debug!(
+                            "Dropped late audio frame {} for stream {}",
+                            timestamp,
+                            stream_id
+                        );
+                    } else if !frame_rejected {
+                        let media_frame = MediaFrame {
+                            stream_id,
+                            frame_index: timestamp / 20, // 假设20ms一帧
+                            timestamp,
+                            frame_type: FrameType::Audio,
+                            data: frame_data,
+                        };
+
+                        // 按时间戳插入，吸收乱序到达
+                        let position = session
+                            .jitter_buffer
+                            .partition_point(|f| f.timestamp <= timestamp);
+                        session.jitter_buffer.insert(position, media_frame);
+                    }
 
-                session.last_activity = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
+                    session.last_activity = self.clock.now_unix_secs();
+                }
             }
         }
+
+        if dropped_bytes > 0 {
+            self.emit_buffer_overflow(stream_id, dropped_bytes);
+        }
         Ok(())
     }
 
@@ -878,74 +1910,213 @@ impl StreamManager {
         frame_type: FrameType,
         timestamp: u64,
     ) -> Result<()> {
-        let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-        if let Some(session) = sessions.get_mut(&stream_id) {
-            if session.stream_type == StreamType::Video {
-                // 将视频帧添加到缓冲区
-                if let Some(ref mut buffer) = session.video_frame_buffer {
-                    buffer.extend_from_slice(&frame_data);
-
-                    // 检查缓冲区大小，避免过度累积
-                    if buffer.len() > 1024 * 1024 {
-                        // 最多缓存1MB
-                        buffer.clear();
+        let config = self.get_buffer_config();
+        let mut dropped_bytes = 0;
+        {
+            let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
+            if let Some(session) = sessions.get_mut(&(self.local_device_id, stream_id)) {
+                if session.stream_type == StreamType::Video {
+                    // 将视频帧添加到缓冲区，超出上限时按策略丢弃
+                    if let Some(ref mut buffer) = session.video_frame_buffer {
+                        dropped_bytes = push_bounded(
+                            buffer,
+                            &frame_data,
+                            config.max_video_buffer_bytes,
+                            config.overflow_policy,
+                        );
+
+                        log::debug!(
+                            "Video frame processed for stream {}, type: {:?}, size: {} bytes",
+                            stream_id,
+                            frame_type,
+                            frame_data.len()
+                        );
+                    }
+
+                    // 新帧被丢弃时不进入优先级队列
+                    let frame_rejected = dropped_bytes > 0
+                        && config.overflow_policy == BufferOverflowPolicy::DropNewest;
+                    if !frame_rejected {
+                        // 创建媒体帧并添加到优先级队列
+                        let media_frame = MediaFrame {
+                            stream_id,
+                            frame_index: timestamp / 33, // 假设30fps，约33ms一帧
+                            timestamp,
+                            frame_type,
+                            data: frame_data,
+                        };
+
+                        session.priority_queue.push(media_frame);
+                        // 按时间戳排序，确保关键帧优先
+                        session.priority_queue.sort_by(|a, b| {
+                            // 关键帧优先级最高
+                            let a_priority = match a.frame_type {
+                                FrameType::VideoIFrame => 0,
+                                FrameType::VideoPFrame => 1,
+                                FrameType::Audio => 2,
+                            };
+                            let b_priority = match b.frame_type {
+                                FrameType::VideoIFrame => 0,
+                                FrameType::VideoPFrame => 1,
+                                FrameType::Audio => 2,
+                            };
+
+                            a_priority
+                                .cmp(&b_priority)
+                                .then(a.timestamp.cmp(&b.timestamp))
+                        });
                     }
 
-                    log::debug!(
-                        "Video frame processed for stream {}, type: {:?}, size: {} bytes",
-                        stream_id,
-                        frame_type,
-                        frame_data.len()
-                    );
+                    session.last_activity = self.clock.now_unix_secs();
                 }
+            }
+        }
 
-                // 创建媒体帧并添加到优先级队列
-                let media_frame = MediaFrame {
-                    stream_id,
-                    frame_index: timestamp / 33, // 假设30fps，约33ms一帧
-                    timestamp,
-                    frame_type,
-                    data: frame_data,
-                };
+        if dropped_bytes > 0 {
+            self.emit_buffer_overflow(stream_id, dropped_bytes);
+        }
+        Ok(())
+    }
 
-                session.priority_queue.push(media_frame);
-                // 按时间戳排序，确保关键帧优先
-                session.priority_queue.sort_by(|a, b| {
-                    // 关键帧优先级最高
-                    let a_priority = match a.frame_type {
-                        FrameType::VideoIFrame => 0,
-                        FrameType::VideoPFrame => 1,
-                        FrameType::Audio => 2,
-                    };
-                    let b_priority = match b.frame_type {
-                        FrameType::VideoIFrame => 0,
-                        FrameType::VideoPFrame => 1,
-                        FrameType::Audio => 2,
-                    };
-
-                    a_priority
-                        .cmp(&b_priority)
-                        .then(a.timestamp.cmp(&b.timestamp))
-                });
+    // F8: 更新音视频缓冲区配置
+    pub fn update_buffer_config(&self, config: MediaBufferConfig) {
+        *self
+            .buffer_config
+            .lock()
+            .expect("Failed to acquire buffer_config lock") = config.clone();
+        log::info!("Updated media buffer config: {:?}", config);
+    }
 
-                session.last_activity = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-            }
+    // F8: 获取音视频缓冲区配置
+    pub fn get_buffer_config(&self) -> MediaBufferConfig {
+        self.buffer_config
+            .lock()
+            .expect("Failed to acquire buffer_config lock")
+            .clone()
+    }
+
+    // F8: 注册流事件监听器
+    pub fn register_event_handler(&self, handler: StreamEventHandler) {
+        self.event_handlers
+            .lock()
+            .expect("Failed to acquire event_handlers lock")
+            .push(handler);
+    }
+
+    fn emit_buffer_overflow(&self, stream_id: Uuid, dropped_bytes: usize) {
+        log::warn!(
+            "Media buffer overflow for stream {}: dropped {} bytes",
+            stream_id,
+            dropped_bytes
+        );
+        let event = StreamEvent::BufferOverflow {
+            stream_id,
+            dropped_bytes,
+        };
+        let handlers = self
+            .event_handlers
+            .lock()
+            .expect("Failed to acquire event_handlers lock");
+        for handler in handlers.iter() {
+            handler(event.clone());
         }
-        Ok(())
     }
 
     // F8: 获取待处理的媒体帧
+    //
+    // 音频帧只交付时间戳早于 `当前时间 - target_latency_ms` 的部分，较新的帧留在抖动
+    // 缓冲区中等待乱序到达的帧补齐；视频帧返回并清空优先级队列
     pub fn get_pending_media_frames(&self, stream_id: Uuid) -> Vec<MediaFrame> {
-        let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-        if let Some(session) = sessions.get_mut(&stream_id) {
-            // 返回并清空优先级队列
-            std::mem::take(&mut session.priority_queue)
-        } else {
-            Vec::new()
+        let now_ms = self.clock.now_unix_millis();
+        let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+            .expect("Failed to acquire sessions lock");
+        let Some(session) = sessions.get_mut(&(self.local_device_id, stream_id)) else {
+            return Vec::new();
+        };
+
+        let target_latency_ms = session
+            .metadata
+            .as_ref()
+            .map(|m| m.target_latency_ms)
+            .unwrap_or(MAX_AUDIO_LATENCY_MS);
+        let deadline = now_ms.saturating_sub(u64::from(target_latency_ms));
+        let due = session
+            .jitter_buffer
+            .partition_point(|f| f.timestamp <= deadline);
+        let mut frames: Vec<MediaFrame> = session.jitter_buffer.drain(..due).collect();
+        if let Some(last) = frames.last() {
+            session.playout_position = Some(last.timestamp);
+            session.jitter_stats.frames_played += frames.len() as u64;
         }
+
+        frames.append(&mut session.priority_queue);
+        frames
+    }
+
+    /// 音频流的抖动缓冲统计，流不存在或不是音频流时返回 None
+    pub fn jitter_stats(&self, stream_id: Uuid) -> Option<JitterStats> {
+        let sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+            .expect("Failed to acquire sessions lock");
+        let session = sessions.get(&(self.local_device_id, stream_id))?;
+        (session.stream_type == StreamType::Audio).then_some(JitterStats {
+            buffered_frames: session.jitter_buffer.len(),
+            ..session.jitter_stats
+        })
+    }
+
+    /// 暂停正在发送的视频流，已发出的分片不受影响
+    pub fn pause_stream(&self, stream_id: Uuid) -> Result<()> {
+        self.send_control(stream_id, StreamControlMessage::Pause)
+    }
+
+    /// 恢复已暂停的视频流
+    pub fn resume_stream(&self, stream_id: Uuid) -> Result<()> {
+        self.send_control(stream_id, StreamControlMessage::Resume)
+    }
+
+    /// 停止发送视频流的剩余分片
+    pub fn stop_stream(&self, stream_id: Uuid) -> Result<()> {
+        self.send_control(stream_id, StreamControlMessage::Stop)
+    }
+
+    /// 订阅发送中视频流的进度（已发出的分片数）
+    ///
+    /// 发送结束（全部发出或被停止）时发送端关闭，`changed()` 返回错误；流已结束或不存在时返回 None
+    pub fn stream_progress(&self, stream_id: Uuid) -> Option<watch::Receiver<u32>> {
+        lock_ordered(&self.progress, lock_order::PROGRESS)
+            .expect("Failed to acquire progress lock")
+            .get(&stream_id)
+            .cloned()
+    }
+
+    /// 直接设置视频流的发送码率（bps），后续分片按新码率匀速发送
+    pub fn set_stream_bitrate(&self, stream_id: Uuid, bitrate: u32) -> Result<()> {
+        self.send_control(stream_id, StreamControlMessage::AdjustBitrate(bitrate))
+    }
+
+    fn send_control(&self, stream_id: Uuid, control: StreamControlMessage) -> Result<()> {
+        let sender = lock_ordered(&self.controllers, lock_order::CONTROLLERS)
+            .expect("Failed to acquire controllers lock")
+            .get(&stream_id)
+            .cloned();
+        sender
+            .and_then(|tx| tx.try_send(control).ok())
+            .ok_or_else(|| {
+                XLinkError::stream_disconnected(
+                    format!("stream_id={}", stream_id),
+                    format!("Stream not sending: {}", stream_id),
+                    file!(),
+                )
+            })
+    }
+
+    /// 视频流码率控制器的当前码率（bps），未知的流返回 None
+    pub fn stream_bitrate(&self, stream_id: Uuid) -> Option<u32> {
+        lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+            .expect("Failed to acquire bitrate_controllers lock")
+            .get(&stream_id)
+            .map(|c| c.get_current_bitrate())
     }
 
     // F8: 自适应码率调整
@@ -955,7 +2126,9 @@ impl StreamManager {
         rtt_ms: u32,
         packet_loss_rate: f32,
     ) -> Result<u32> {
-        let mut controllers = self.bitrate_controllers.lock().expect("Failed to acquire bitrate_controllers lock");
+        let mut controllers =
+            lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                .expect("Failed to acquire bitrate_controllers lock");
         if let Some(controller) = controllers.get_mut(&stream_id) {
             controller.update_network_stats(rtt_ms, packet_loss_rate);
             let new_bitrate = controller.get_current_bitrate();
@@ -978,7 +2151,8 @@ impl StreamManager {
 
     // F9: 获取流量统计信息
     pub fn get_traffic_statistics(&self) -> TrafficStatistics {
-        let sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+        let sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+            .expect("Failed to acquire sessions lock");
         let mut total_sent = 0u64;
         let mut total_received = 0u64;
         let mut total_packets_sent = 0u64;
@@ -1015,7 +2189,9 @@ impl StreamManager {
             }
         }
 
-        let network_type = self.network_monitor.lock().expect("Failed to acquire network_monitor lock").detect_network_type();
+        let network_type = lock_ordered(&self.network_monitor, lock_order::NETWORK_MONITOR)
+            .expect("Failed to acquire network_monitor lock")
+            .detect_network_type();
 
         TrafficStatistics {
             total_bytes_sent: total_sent,
@@ -1024,27 +2200,33 @@ impl StreamManager {
             total_packets_received,
             network_type,
             app_breakdown,
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            timestamp: self.clock.now_unix_secs(),
         }
     }
 
     // F9: 更新用户流量偏好设置
     pub fn update_user_preferences(&self, preferences: UserTrafficPreferences) {
-        *self.user_preferences.lock().expect("Failed to acquire user_preferences lock") = preferences.clone();
+        *self
+            .user_preferences
+            .lock()
+            .expect("Failed to acquire user_preferences lock") = preferences.clone();
         log::info!("Updated user traffic preferences: {:?}", preferences);
     }
 
     // F9: 获取用户流量偏好设置
     pub fn get_user_preferences(&self) -> UserTrafficPreferences {
-        self.user_preferences.lock().expect("Failed to acquire user_preferences lock").clone()
+        self.user_preferences
+            .lock()
+            .expect("Failed to acquire user_preferences lock")
+            .clone()
     }
 
     // F9: 估算流量成本
     pub fn estimate_traffic_cost(&self, bytes: u64, network_type: NetworkType) -> f32 {
-        let preferences = self.user_preferences.lock().expect("Failed to acquire user_preferences lock");
+        let preferences = self
+            .user_preferences
+            .lock()
+            .expect("Failed to acquire user_preferences lock");
         let mb = bytes as f32 / (1024.0 * 1024.0);
 
         match network_type {
@@ -1058,6 +2240,11 @@ impl StreamManager {
 
     // F9: 实时网络类型检测
     pub async fn detect_network_type(&self) -> NetworkType {
+        // 设置了覆盖值时直接返回，不再探测
+        if let Some(network) = self.network_type_override() {
+            return network;
+        }
+
         // 1. 首先尝试通过系统接口检测
         let interfaces = pnet_datalink::interfaces();
         for interface in interfaces {
@@ -1070,7 +2257,8 @@ impl StreamManager {
 
         // 2. 基于网络特征进行智能检测 (回退方案)
         let (total_rtt, total_loss, count) = {
-            let sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+            let sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+                .expect("Failed to acquire sessions lock");
             let mut total_rtt = 0u32;
             let mut total_loss = 0.0f32;
             let mut count = 0u32;
@@ -1124,16 +2312,14 @@ impl StreamManager {
 
     // 清理超时的会话
     pub fn cleanup_timeout_sessions(&self) {
-        let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+            .expect("Failed to acquire sessions lock");
+        let current_time = self.clock.now_unix_secs();
         let timeout_duration = 300; // 5分钟超时
 
-        sessions.retain(|stream_id, session| {
-            if current_time - session.last_activity > timeout_duration {
-                log::info!("Cleaning up timeout session: {}", stream_id);
+        sessions.retain(|(sender, stream_id), session| {
+            if current_time.saturating_sub(session.last_activity) > timeout_duration {
+                log::info!("Cleaning up timeout session: {} from {}", stream_id, sender);
                 false
             } else {
                 true
@@ -1143,11 +2329,21 @@ impl StreamManager {
 
     /// 清理所有活动流，防止内存泄漏
     pub fn clear_streams(&self) {
-        let mut sessions = self.sessions.lock().expect("Failed to acquire sessions lock");
+        let mut sessions = lock_ordered(&self.sessions, lock_order::SESSIONS)
+            .expect("Failed to acquire sessions lock");
         sessions.clear();
-        let mut controllers = self.controllers.lock().expect("Failed to acquire controllers lock");
+        let mut controllers = lock_ordered(&self.controllers, lock_order::CONTROLLERS)
+            .expect("Failed to acquire controllers lock");
         controllers.clear();
-        let mut bitrate_controllers = self.bitrate_controllers.lock().expect("Failed to acquire bitrate_controllers lock");
+        lock_ordered(&self.progress, lock_order::PROGRESS)
+            .expect("Failed to acquire progress lock")
+            .clear();
+        lock_ordered(&self.transfers, lock_order::TRANSFERS)
+            .expect("Failed to acquire transfers lock")
+            .clear();
+        let mut bitrate_controllers =
+            lock_ordered(&self.bitrate_controllers, lock_order::BITRATE_CONTROLLERS)
+                .expect("Failed to acquire bitrate_controllers lock");
         bitrate_controllers.clear();
     }
 }
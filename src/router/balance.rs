@@ -0,0 +1,90 @@
+//! 等价通道间的负载均衡
+//!
+//! 内置评分默认只选择得分最高的通道。多个通道得分相差不超过 `epsilon` 时，
+//! [`BalanceMode`] 决定是否把同一对端的消息分摊到这些通道上。
+
+use crate::core::types::{ChannelType, DeviceId};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// 等价通道间的分配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BalanceMode {
+    /// 始终选择得分最高的通道
+    #[default]
+    Single,
+    /// 按对端轮流使用等价通道
+    RoundRobin,
+    /// 按得分加权随机选择等价通道
+    Weighted,
+}
+
+/// 负载均衡配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BalanceConfig {
+    pub mode: BalanceMode,
+    /// 与最高分相差不超过该值的通道视为等价
+    pub epsilon: f64,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            mode: BalanceMode::Single,
+            epsilon: 0.05,
+        }
+    }
+}
+
+/// 在等价通道间分配消息，保存每个对端的轮询位置
+#[derive(Debug, Default)]
+pub struct Balancer {
+    next: HashMap<DeviceId, usize>,
+}
+
+impl Balancer {
+    /// 从已评分的候选通道（得分均大于 0）中为 `target` 选择一个
+    pub fn pick(
+        &mut self,
+        target: DeviceId,
+        scored: &[(ChannelType, f64)],
+        config: &BalanceConfig,
+    ) -> Option<ChannelType> {
+        let best = scored.iter().map(|(_, score)| *score).reduce(f64::max)?;
+        let mut equivalent: Vec<(ChannelType, f64)> = scored
+            .iter()
+            .copied()
+            .filter(|(_, score)| best - score <= config.epsilon)
+            .collect();
+        if config.mode == BalanceMode::Single || equivalent.len() == 1 {
+            return scored
+                .iter()
+                .find(|(_, score)| *score == best)
+                .map(|(ctype, _)| *ctype);
+        }
+        // 按通道类型排序，使轮询顺序与候选的遍历顺序无关
+        equivalent.sort_by_key(|(ctype, _)| *ctype as u8);
+
+        if config.mode == BalanceMode::RoundRobin {
+            let next = self.next.entry(target).or_insert(0);
+            let chosen = equivalent[*next % equivalent.len()].0;
+            *next = next.wrapping_add(1);
+            return Some(chosen);
+        }
+
+        let total: f64 = equivalent.iter().map(|(_, score)| score).sum();
+        let mut point = rand::thread_rng().gen_range(0.0..total);
+        for (ctype, score) in &equivalent {
+            if point < *score {
+                return Some(*ctype);
+            }
+            point -= score;
+        }
+        equivalent.last().map(|(ctype, _)| *ctype)
+    }
+
+    /// 清空轮询位置
+    pub fn clear(&mut self) {
+        self.next.clear();
+    }
+}
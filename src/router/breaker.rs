@@ -0,0 +1,131 @@
+//! 通道熔断
+//!
+//! 对端某通道的连续失败次数（`ChannelState::failure_count`）达到阈值时熔断，
+//! 冷却期内路由不再选择该通道；冷却结束后放行一次探测发送（半开），
+//! 探测成功后闭合，探测失败则重新进入冷却。
+
+use crate::core::types::{ChannelType, DeviceId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 熔断配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// 连续失败次数达到该值时熔断，为 0 时不熔断
+    pub failure_threshold: u32,
+    /// 熔断后到允许探测发送的冷却时间
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 单个对端通道的熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// 正常参与路由
+    Closed,
+    /// 冷却中，或探测发送尚未返回，路由跳过该通道
+    Open,
+    /// 冷却结束，允许一次探测发送
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Trip {
+    opened_at: Instant,
+    probe_started: Option<Instant>,
+}
+
+/// 按 (对端, 通道) 记录熔断时间与探测进度
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    trips: HashMap<(DeviceId, ChannelType), Trip>,
+}
+
+impl CircuitBreaker {
+    /// 当前熔断状态
+    ///
+    /// `failure_count` 已回落到阈值以下（例如心跳收到 Pong）时视为闭合。
+    /// 探测发送超过一个冷却期仍未返回结果时允许再次探测
+    pub fn state(
+        &self,
+        peer: DeviceId,
+        channel: ChannelType,
+        failure_count: u32,
+        config: &CircuitBreakerConfig,
+    ) -> BreakerState {
+        if config.failure_threshold == 0 || failure_count < config.failure_threshold {
+            return BreakerState::Closed;
+        }
+        let Some(trip) = self.trips.get(&(peer, channel)) else {
+            return BreakerState::Closed;
+        };
+        if trip.opened_at.elapsed() < config.cooldown {
+            return BreakerState::Open;
+        }
+        match trip.probe_started {
+            Some(started) if started.elapsed() < config.cooldown => BreakerState::Open,
+            _ => BreakerState::HalfOpen,
+        }
+    }
+
+    /// 记录一次发送结果，`failure_count` 为记录后的连续失败次数
+    ///
+    /// 成功时闭合；失败次数达到阈值时（重新）熔断并开始新的冷却期
+    pub fn record(
+        &mut self,
+        peer: DeviceId,
+        channel: ChannelType,
+        success: bool,
+        failure_count: u32,
+        config: &CircuitBreakerConfig,
+    ) {
+        if success {
+            self.trips.remove(&(peer, channel));
+            return;
+        }
+        if config.failure_threshold > 0 && failure_count >= config.failure_threshold {
+            if self.trips.contains_key(&(peer, channel)) {
+                log::debug!("Circuit for {:?} to {} re-opened", channel, peer);
+            } else {
+                log::warn!(
+                    "Circuit for {:?} to {} opened after {} consecutive failures",
+                    channel,
+                    peer,
+                    failure_count
+                );
+            }
+            self.trips.insert(
+                (peer, channel),
+                Trip {
+                    opened_at: Instant::now(),
+                    probe_started: None,
+                },
+            );
+        }
+    }
+
+    /// 半开通道被选中发送，探测返回前其余发送继续跳过该通道
+    pub fn begin_probe(&mut self, peer: DeviceId, channel: ChannelType) {
+        if let Some(trip) = self.trips.get_mut(&(peer, channel)) {
+            trip.probe_started = Some(Instant::now());
+        }
+    }
+
+    /// 移除指定对端的全部熔断记录
+    pub fn forget_device(&mut self, peer: DeviceId) {
+        self.trips.retain(|(device, _), _| *device != peer);
+    }
+
+    /// 清空全部熔断记录
+    pub fn clear(&mut self) {
+        self.trips.clear();
+    }
+}
@@ -0,0 +1,211 @@
+//! 路由表内省
+//!
+//! 汇总能力管理器中每个已知对端的候选通道状态与评分，供调试与问题报告使用；
+//! [`RouteExplanation`] 针对单条消息说明每个通道被选中或落选的原因。
+//! 只读取路由状态，不记录流量统计与路由历史。
+
+use crate::core::types::{ChannelState, ChannelType, DeviceId, MessagePriority};
+use std::fmt;
+
+/// 通道未参与路由选择的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteExclusion {
+    /// 对端上报了该通道，但本地未注册对应通道
+    NoLocalChannel,
+    /// 通道当前标记为不可用
+    Unavailable,
+    /// 评分为零，不会被选择
+    ZeroScore,
+    /// 连续失败触发熔断，处于冷却中
+    CircuitOpen,
+}
+
+/// 单个候选通道的路由信息
+#[derive(Debug, Clone)]
+pub struct ChannelRouteInfo {
+    pub channel: ChannelType,
+    pub state: ChannelState,
+    pub score: f64,
+    /// 存在未应答的心跳，状态可能已过时
+    pub stale: bool,
+    pub excluded: Option<RouteExclusion>,
+}
+
+/// 单个对端的路由信息
+#[derive(Debug, Clone)]
+pub struct PeerRoutingInfo {
+    pub device_id: DeviceId,
+    /// 评分所用的消息优先级
+    pub priority: MessagePriority,
+    /// 按评分从高到低排列
+    pub channels: Vec<ChannelRouteInfo>,
+    /// 当前会被选中的通道
+    pub chosen: Option<ChannelType>,
+    /// 选择结果来自路由历史预测
+    pub predicted: bool,
+}
+
+/// 单条消息的候选通道被选中或落选的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteDecision {
+    /// 被选中
+    Chosen,
+    /// 没有该对端在此通道上的状态
+    NoState,
+    /// 通道当前标记为不可用
+    Unavailable,
+    /// 连续失败触发熔断，处于冷却中
+    CircuitOpen,
+    /// 消息要求有序交付而通道无序
+    Unordered,
+    /// 消息超出通道负载上限
+    PayloadTooLarge { max_payload: usize },
+    /// 评分为零，低于可选阈值
+    BelowThreshold,
+    /// 可选但得分低于被选中的通道，或被策略、预测排在后面
+    LostTiebreak,
+}
+
+/// 选中通道的决定依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteSource {
+    /// 流量类别固定的通道
+    Pinned,
+    /// 路由历史预测
+    Predicted,
+    /// 自定义路由策略
+    Strategy,
+    /// 内置评分最高
+    Scored,
+    /// 没有可用通道
+    NoRoute,
+}
+
+/// 单个候选通道的选路解释
+#[derive(Debug, Clone)]
+pub struct CandidateExplanation {
+    pub channel: ChannelType,
+    pub state: Option<ChannelState>,
+    /// 通过熔断、有序与负载检查后计算的评分
+    pub score: Option<f64>,
+    pub available: bool,
+    pub decision: RouteDecision,
+}
+
+/// 单条消息的选路解释，由 `Router::explain_route` 生成
+#[derive(Debug, Clone)]
+pub struct RouteExplanation {
+    pub recipient: DeviceId,
+    pub priority: MessagePriority,
+    /// 每个本地通道一项，按评分从高到低排列
+    pub candidates: Vec<CandidateExplanation>,
+    pub chosen: Option<ChannelType>,
+    pub source: RouteSource,
+}
+
+impl RouteExplanation {
+    /// 指定通道的解释
+    pub fn candidate(&self, channel: ChannelType) -> Option<&CandidateExplanation> {
+        self.candidates.iter().find(|c| c.channel == channel)
+    }
+}
+
+impl fmt::Display for RouteDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteDecision::Chosen => write!(f, "chosen"),
+            RouteDecision::NoState => write!(f, "no channel state"),
+            RouteDecision::Unavailable => write!(f, "unavailable"),
+            RouteDecision::CircuitOpen => write!(f, "circuit open"),
+            RouteDecision::Unordered => write!(f, "unordered channel"),
+            RouteDecision::PayloadTooLarge { max_payload } => {
+                write!(f, "payload exceeds {} bytes", max_payload)
+            }
+            RouteDecision::BelowThreshold => write!(f, "below threshold"),
+            RouteDecision::LostTiebreak => write!(f, "lost tiebreak"),
+        }
+    }
+}
+
+impl fmt::Display for RouteExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "route to {} ({:?}): ", self.recipient, self.priority)?;
+        match self.chosen {
+            Some(channel) => writeln!(f, "chosen {:?} ({:?})", channel, self.source)?,
+            None => writeln!(f, "no route")?,
+        }
+        for candidate in &self.candidates {
+            write!(f, "  {:?}: ", candidate.channel)?;
+            match candidate.score {
+                Some(score) => write!(f, "score={:.4}", score)?,
+                None => write!(f, "score=-")?,
+            }
+            writeln!(
+                f,
+                " available={} [{}]",
+                candidate.available, candidate.decision
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RouteExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteExclusion::NoLocalChannel => write!(f, "no local channel"),
+            RouteExclusion::Unavailable => write!(f, "unavailable"),
+            RouteExclusion::ZeroScore => write!(f, "zero score"),
+            RouteExclusion::CircuitOpen => write!(f, "circuit open"),
+        }
+    }
+}
+
+impl fmt::Display for PeerRoutingInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer {} ({:?}): ", self.device_id, self.priority)?;
+        match self.chosen {
+            Some(channel) if self.predicted => writeln!(f, "chosen {:?} (predicted)", channel)?,
+            Some(channel) => writeln!(f, "chosen {:?}", channel)?,
+            None => writeln!(f, "no route")?,
+        }
+        for info in &self.channels {
+            let marker = if Some(info.channel) == self.chosen {
+                '*'
+            } else {
+                ' '
+            };
+            write!(
+                f,
+                "  {} {:?}: score={:.4} rtt={}ms loss={:.2} failures={} network={:?}",
+                marker,
+                info.channel,
+                info.score,
+                info.state.rtt_ms,
+                info.state.packet_loss_rate,
+                info.state.failure_count,
+                info.state.network_type
+            )?;
+            if info.stale {
+                write!(f, " [stale]")?;
+            }
+            if let Some(reason) = info.excluded {
+                write!(f, " [excluded: {}]", reason)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// 将完整路由表格式化为多行日志文本
+pub fn format_routing_table(table: &[PeerRoutingInfo]) -> String {
+    if table.is_empty() {
+        return "routing table: no known peers\n".to_string();
+    }
+    let mut out = format!("routing table: {} peers\n", table.len());
+    for peer in table {
+        out.push_str(&peer.to_string());
+    }
+    out
+}
@@ -1,3 +1,9 @@
+pub mod balance;
+pub mod breaker;
+pub mod introspection;
 pub mod predictor;
 pub mod scoring;
 pub mod selector;
+pub mod send_queue;
+pub mod strategy;
+pub mod warmup;
@@ -1,6 +1,25 @@
 use crate::core::types::{
     ChannelState, ChannelType, DeviceCapabilities, MessagePriority, NetworkType,
 };
+use std::time::Duration;
+
+/// 评分配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScorerConfig {
+    /// 近期失败的统计窗口，窗口外的失败不再影响评分
+    pub failure_window: Duration,
+    /// 每次（按时间衰减后的）近期失败的惩罚系数，0 表示关闭该模式
+    pub recent_failure_penalty: f64,
+}
+
+impl Default for ScorerConfig {
+    fn default() -> Self {
+        Self {
+            failure_window: Duration::from_secs(60),
+            recent_failure_penalty: 0.0,
+        }
+    }
+}
 
 pub struct Scorer;
 
@@ -71,4 +90,21 @@ impl Scorer {
         // Clamp to 0.0 - 1.0
         final_score.clamp(0.0, 1.0)
     }
+
+    /// 在基础评分上叠加近期失败惩罚
+    ///
+    /// `recent_failure_weight` 为按时间衰减后的近期失败加权和（见
+    /// `CapabilityManager::recent_failure_weight`），刚发生的失败惩罚最重，
+    /// 随窗口推移逐渐恢复原有评分。
+    pub fn score_with_recent_failures(
+        channel: ChannelType,
+        state: &ChannelState,
+        device_caps: &DeviceCapabilities,
+        priority: MessagePriority,
+        recent_failure_weight: f64,
+        config: &ScorerConfig,
+    ) -> f64 {
+        let base = Self::score(channel, state, device_caps, priority);
+        base / (1.0 + config.recent_failure_penalty.max(0.0) * recent_failure_weight)
+    }
 }
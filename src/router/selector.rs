@@ -1,8 +1,21 @@
 use crate::capability::manager::CapabilityManager;
+use crate::core::budget::{self, TrafficBudget};
 use crate::core::error::{Result, XLinkError};
 use crate::core::traits::Channel;
-use crate::core::types::{ChannelType, DeviceId, Message, MessagePayload};
-use crate::router::scoring::Scorer;
+use crate::core::types::{
+    ChannelState, ChannelType, DeviceCapabilities, DeviceId, Message, MessagePayload,
+    MessagePriority, TrafficClass,
+};
+use crate::crypto::engine::CryptoEngine;
+use crate::router::balance::{BalanceConfig, BalanceMode, Balancer};
+use crate::router::breaker::BreakerState;
+use crate::router::introspection::{
+    CandidateExplanation, ChannelRouteInfo, PeerRoutingInfo, RouteDecision, RouteExclusion,
+    RouteExplanation, RouteSource,
+};
+use crate::router::scoring::{Scorer, ScorerConfig};
+use crate::router::send_queue::{SendPermit, SendQueue, SendQueueConfig};
+use crate::router::strategy::RoutingStrategy;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -11,12 +24,7 @@ use std::sync::Mutex;
 macro_rules! lock {
     ($lock:expr, $field:expr) => {
         $lock.lock().map_err(|_| {
-            XLinkError::resource_exhausted(
-                format!("Mutex poisoned: {}", $field),
-                0,
-                0,
-                file!(),
-            )
+            XLinkError::resource_exhausted(format!("Mutex poisoned: {}", $field), 0, 0, file!())
         })
     };
 }
@@ -27,6 +35,14 @@ pub struct Router {
     traffic_stats: Mutex<HashMap<ChannelType, u64>>,
     route_history: Mutex<HashMap<DeviceId, Vec<ChannelType>>>,
     traffic_thresholds: HashMap<ChannelType, u64>,
+    scorer_config: Mutex<ScorerConfig>,
+    class_channels: Mutex<HashMap<TrafficClass, ChannelType>>,
+    strategy: Mutex<Option<Arc<dyn RoutingStrategy>>>,
+    balance_config: Mutex<BalanceConfig>,
+    balancer: Mutex<Balancer>,
+    send_queue: SendQueue,
+    signer: Mutex<Option<Arc<CryptoEngine>>>,
+    traffic_budget: Mutex<Option<Arc<TrafficBudget>>>,
 }
 
 impl Router {
@@ -40,6 +56,14 @@ impl Router {
             traffic_stats: Mutex::new(HashMap::new()),
             route_history: Mutex::new(HashMap::new()),
             traffic_thresholds: HashMap::new(),
+            scorer_config: Mutex::new(ScorerConfig::default()),
+            class_channels: Mutex::new(HashMap::new()),
+            strategy: Mutex::new(None),
+            balance_config: Mutex::new(BalanceConfig::default()),
+            balancer: Mutex::new(Balancer::default()),
+            send_queue: SendQueue::new(SendQueueConfig::default()),
+            signer: Mutex::new(None),
+            traffic_budget: Mutex::new(None),
         }
     }
 
@@ -48,10 +72,227 @@ impl Router {
         self
     }
 
+    pub fn with_scorer_config(self, config: ScorerConfig) -> Self {
+        self.set_scorer_config(config);
+        self
+    }
+
+    /// 更新评分配置（如近期失败惩罚窗口）
+    pub fn set_scorer_config(&self, config: ScorerConfig) {
+        if let Ok(mut current) = lock!(self.scorer_config, "scorer_config") {
+            *current = config;
+        }
+    }
+
+    /// 获取当前评分配置
+    pub fn scorer_config(&self) -> ScorerConfig {
+        lock!(self.scorer_config, "scorer_config")
+            .map(|config| *config)
+            .unwrap_or_default()
+    }
+
+    /// 设置为 SDK 自身发出的消息（确认、心跳、流分片、群组控制消息等）签名的引擎
+    pub fn set_message_signer(&self, signer: Option<Arc<CryptoEngine>>) {
+        if let Ok(mut current) = lock!(self.signer, "signer") {
+            *current = signer;
+        }
+    }
+
+    /// 以本机身份密钥签名消息，未设置签名引擎时不做处理；须在消息的最后一次修改之后调用
+    pub fn sign_message(&self, message: &mut Message) {
+        let signer = lock!(self.signer, "signer")
+            .ok()
+            .and_then(|signer| signer.clone());
+        if let Some(signer) = signer {
+            signer.sign_message(message);
+        }
+    }
+
+    /// 设置计量网络的流量预算，点对点发送、流式分片与群组扇出共用
+    pub fn set_traffic_budget(&self, traffic_budget: Option<Arc<TrafficBudget>>) {
+        if let Ok(mut current) = lock!(self.traffic_budget, "traffic_budget") {
+            *current = traffic_budget;
+        }
+    }
+
+    /// 经计量通道发送前检查流量预算，返回该通道的流量是否计入预算
+    ///
+    /// 预算耗尽时非 `Critical` 的发送返回资源耗尽错误；未设置预算时不计量
+    pub fn admit_metered(&self, ctype: ChannelType, priority: MessagePriority) -> Result<bool> {
+        let Some(traffic_budget) = self.metered_budget(ctype) else {
+            return Ok(false);
+        };
+        traffic_budget.admit(priority)?;
+        Ok(true)
+    }
+
+    /// 累计经计量通道发出的字节数，跨过整 MB 边界时返回 true
+    pub fn record_metered(&self, ctype: ChannelType, bytes: u64) -> bool {
+        self.metered_budget(ctype)
+            .is_some_and(|traffic_budget| traffic_budget.record(bytes))
+    }
+
+    /// 记录一次为避免计量流量而未直接发出的发送
+    pub fn record_metered_avoided(&self, bytes: u64) {
+        if let Some(traffic_budget) = lock!(self.traffic_budget, "traffic_budget")
+            .ok()
+            .and_then(|traffic_budget| traffic_budget.clone())
+        {
+            traffic_budget.record_avoided(bytes);
+        }
+    }
+
+    fn metered_budget(&self, ctype: ChannelType) -> Option<Arc<TrafficBudget>> {
+        if !budget::is_metered(ctype, self.cap_manager.local_network_type()) {
+            return None;
+        }
+        lock!(self.traffic_budget, "traffic_budget")
+            .ok()
+            .and_then(|traffic_budget| traffic_budget.clone())
+    }
+
+    /// 使用自定义路由策略替代内置评分
+    pub fn with_strategy(self, strategy: Arc<dyn RoutingStrategy>) -> Self {
+        self.set_strategy(Some(strategy));
+        self
+    }
+
+    /// 设置自定义路由策略，`None` 恢复内置评分
+    pub fn set_strategy(&self, strategy: Option<Arc<dyn RoutingStrategy>>) {
+        if let Ok(mut current) = lock!(self.strategy, "strategy") {
+            *current = strategy;
+        }
+    }
+
+    /// 当前的自定义路由策略
+    pub fn strategy(&self) -> Option<Arc<dyn RoutingStrategy>> {
+        lock!(self.strategy, "strategy")
+            .ok()
+            .and_then(|strategy| strategy.clone())
+    }
+
+    /// 设置得分相近的通道间的分配方式
+    ///
+    /// 非 `Single` 模式下不使用预测性路由，以免历史记录把流量固定到单个通道
+    pub fn set_balance_mode(&self, mode: BalanceMode) {
+        if let Ok(mut config) = lock!(self.balance_config, "balance_config") {
+            config.mode = mode;
+        }
+    }
+
+    /// 设置视为等价通道的最大得分差
+    pub fn set_balance_epsilon(&self, epsilon: f64) {
+        if let Ok(mut config) = lock!(self.balance_config, "balance_config") {
+            config.epsilon = epsilon.max(0.0);
+        }
+    }
+
+    /// 获取当前的负载均衡配置
+    pub fn balance_config(&self) -> BalanceConfig {
+        lock!(self.balance_config, "balance_config")
+            .map(|config| *config)
+            .unwrap_or_default()
+    }
+
+    /// 更新发送队列配置（每种通道的最大在途发送数与排队提升周期）
+    pub fn set_send_queue_config(&self, config: SendQueueConfig) {
+        self.send_queue.set_config(config);
+    }
+
+    /// 获取当前发送队列配置
+    pub fn send_queue_config(&self) -> SendQueueConfig {
+        self.send_queue.config()
+    }
+
+    /// 选择通道并经发送队列发送，消息交给通道发送完成后返回所用通道
+    ///
+    /// 通道在途发送数达到上限时按消息优先级排队，高优先级消息先发送
+    pub async fn enqueue_send(&self, message: Message) -> Result<ChannelType> {
+        let channel = self.select_channel(&message).await?;
+        self.send_through(channel.as_ref(), message).await?;
+        Ok(channel.channel_type())
+    }
+
+    /// 经发送队列把消息交给已选定的通道，发送结果计入通道熔断；未签名的消息在发送前签名
+    ///
+    /// 计量通道上先检查流量预算，发送成功后累计计量流量
+    pub async fn send_through(&self, channel: &dyn Channel, mut message: Message) -> Result<()> {
+        if message.signature.is_none() {
+            self.sign_message(&mut message);
+        }
+        let ctype = channel.channel_type();
+        let recipient = message.recipient;
+        let metered = self.admit_metered(ctype, message.priority)?;
+        let bytes = payload_size(&message.payload) as u64;
+        let _permit = self.acquire_send_permit(ctype, message.priority).await;
+        let result = channel.send(message).await;
+        self.cap_manager
+            .record_channel_result(recipient, ctype, result.is_ok());
+        if metered && result.is_ok() {
+            self.record_metered(ctype, bytes);
+        }
+        result
+    }
+
+    /// 等待通道的发送名额，名额随返回值释放
+    ///
+    /// 供自行调用通道发送（例如需要重试）的调用方使用，发送结果由调用方计入熔断
+    pub async fn acquire_send_permit(
+        &self,
+        ctype: ChannelType,
+        priority: MessagePriority,
+    ) -> SendPermit<'_> {
+        self.send_queue.acquire(ctype, priority).await
+    }
+
+    /// 通道是否因熔断处于冷却中
+    fn circuit_open(&self, target: &DeviceId, ctype: &ChannelType) -> bool {
+        self.cap_manager.breaker_state(target, ctype) == BreakerState::Open
+    }
+
+    /// 对端是否有冷却结束、等待探测的通道
+    fn probe_pending(&self, target: &DeviceId) -> bool {
+        self.channels
+            .keys()
+            .any(|ctype| self.cap_manager.breaker_state(target, ctype) == BreakerState::HalfOpen)
+    }
+
+    /// 某种通道当前在途与排队中的发送数
+    pub fn send_queue_depth(&self, ctype: ChannelType) -> (usize, usize) {
+        (
+            self.send_queue.in_flight(ctype),
+            self.send_queue.queued(ctype),
+        )
+    }
+
+    /// 计算通道评分，按配置叠加该对端通道的近期失败惩罚
+    fn score_channel(
+        &self,
+        target: &DeviceId,
+        ctype: ChannelType,
+        state: &ChannelState,
+        local_caps: &DeviceCapabilities,
+        priority: MessagePriority,
+    ) -> f64 {
+        let config = self.scorer_config();
+        let weight = if config.recent_failure_penalty > 0.0 {
+            self.cap_manager
+                .recent_failure_weight(target, &ctype, config.failure_window)
+        } else {
+            0.0
+        };
+        Scorer::score_with_recent_failures(ctype, state, local_caps, priority, weight, &config)
+    }
+
     pub fn get_channels(&self) -> &HashMap<ChannelType, Arc<dyn Channel>> {
         &self.channels
     }
 
+    /// 获取路由使用的能力管理器
+    pub fn capability_manager(&self) -> Arc<CapabilityManager> {
+        self.cap_manager.clone()
+    }
+
     /// 获取通道累计流量（字节）
     pub fn get_traffic_stats(&self) -> Result<HashMap<ChannelType, u64>> {
         let stats = lock!(self.traffic_stats, "traffic_stats")?;
@@ -80,6 +321,73 @@ impl Router {
         // 锁中毒时静默忽略，避免影响主流程
     }
 
+    /// 检查通道是否满足消息的有序交付要求
+    fn satisfies_ordering(&self, message: &Message, ctype: &ChannelType) -> bool {
+        !message.require_ordered
+            || self
+                .channels
+                .get(ctype)
+                .is_some_and(|channel| channel.is_ordered())
+    }
+
+    /// 检查通道能否承载消息大小，超出上限时返回该上限
+    fn payload_limit_exceeded(&self, message: &Message, ctype: &ChannelType) -> Option<usize> {
+        let max_payload = self.channels.get(ctype)?.max_payload()?;
+        (payload_size(&message.payload) > max_payload).then_some(max_payload)
+    }
+
+    /// 将某类流量固定到指定通道
+    pub fn set_class_channel(&self, class: TrafficClass, ctype: ChannelType) {
+        if let Ok(mut pins) = lock!(self.class_channels, "class_channels") {
+            pins.insert(class, ctype);
+        }
+    }
+
+    /// 以新映射替换全部流量类别固定通道
+    pub fn set_class_channels(&self, pins: HashMap<TrafficClass, ChannelType>) {
+        if let Ok(mut current) = lock!(self.class_channels, "class_channels") {
+            *current = pins;
+        }
+    }
+
+    /// 取消某类流量的固定通道
+    pub fn clear_class_channel(&self, class: TrafficClass) {
+        if let Ok(mut pins) = lock!(self.class_channels, "class_channels") {
+            pins.remove(&class);
+        }
+    }
+
+    /// 当前的流量类别固定通道
+    pub fn class_channels(&self) -> HashMap<TrafficClass, ChannelType> {
+        lock!(self.class_channels, "class_channels")
+            .map(|pins| pins.clone())
+            .unwrap_or_default()
+    }
+
+    /// 消息所属流量类别的固定通道；通道未注册、对端不可用或不满足消息要求时返回 None
+    fn pinned_channel(&self, message: &Message) -> Option<ChannelType> {
+        let class = TrafficClass::of(message);
+        let ctype = *lock!(self.class_channels, "class_channels")
+            .ok()?
+            .get(&class)?;
+        let usable = self.channels.contains_key(&ctype)
+            && self
+                .cap_manager
+                .get_channel_state(&message.recipient, &ctype)
+                .is_some_and(|state| state.available)
+            && !self.circuit_open(&message.recipient, &ctype)
+            && self.satisfies_ordering(message, &ctype)
+            && self.payload_limit_exceeded(message, &ctype).is_none();
+        if !usable {
+            log::debug!(
+                "Pinned channel {:?} for {:?} unavailable, falling back to scoring",
+                ctype,
+                class
+            );
+        }
+        usable.then_some(ctype)
+    }
+
     /// 记录路由历史
     fn record_history(&self, target: DeviceId, ctype: ChannelType) {
         if let Ok(mut history) = lock!(self.route_history, "route_history") {
@@ -113,64 +421,147 @@ impl Router {
         None
     }
 
+    /// 历史预测的通道当前可用且分数尚可时返回该通道；有通道等待熔断探测时不预测
+    fn predicted_channel(
+        &self,
+        message: &Message,
+        local_caps: &DeviceCapabilities,
+    ) -> Option<ChannelType> {
+        let target = &message.recipient;
+        if self.probe_pending(target) {
+            return None;
+        }
+        let predicted_ctype = self.predict_best_channel(target)?;
+        let state = self
+            .cap_manager
+            .get_channel_state(target, &predicted_ctype)?;
+        let usable = state.available
+            && !self.circuit_open(target, &predicted_ctype)
+            && self.satisfies_ordering(message, &predicted_ctype)
+            && self
+                .payload_limit_exceeded(message, &predicted_ctype)
+                .is_none();
+        // 只要分数尚可，就直接使用，减少计算开销
+        (usable
+            && self.score_channel(
+                target,
+                predicted_ctype,
+                &state,
+                local_caps,
+                message.priority,
+            ) > 0.6)
+            .then_some(predicted_ctype)
+    }
+
     pub async fn select_channel(&self, message: &Message) -> Result<Arc<dyn Channel>> {
         let target = &message.recipient;
         let local_caps = self.cap_manager.get_local_caps();
 
-        let mut best_score = -1.0;
-        let mut best_channel_type = None;
+        // 流量类别固定的通道优先于评分，固定通道不可用时回退到评分
+        let pinned = self.pinned_channel(message);
+        let mut best_channel_type = pinned;
 
-        // F7: 预测性路由 - 检查历史记录
-        if let Some(predicted_ctype) = self.predict_best_channel(target) {
-            if let Some(state) = self.cap_manager.get_channel_state(target, &predicted_ctype) {
-                if state.available {
-                    // 如果预测的通道当前可用，则优先考虑
-                    let score =
-                        Scorer::score(predicted_ctype, &state, &local_caps, message.priority);
-                    if score > 0.6 {
-                        // 只要分数尚可，就直接使用，减少计算开销
-                        best_score = score;
-                        best_channel_type = Some(predicted_ctype);
-                    }
-                }
-            }
+        // 自定义策略完全接管排序，此时不使用预测性路由
+        let strategy = self.strategy();
+        let balance = self.balance_config();
+
+        // F7: 预测性路由 - 检查历史记录（已命中固定通道、启用负载均衡或有通道等待熔断探测时跳过）
+        if best_channel_type.is_none() && strategy.is_none() && balance.mode == BalanceMode::Single
+        {
+            best_channel_type = self.predicted_channel(message, &local_caps);
         }
 
+        let mut skipped_unordered = false;
+        // 因负载超限被排除的通道中最大的上限
+        let mut oversize_limit: Option<usize> = None;
+        // 交给自定义策略排序的候选通道
+        let mut candidates: Vec<(ChannelType, ChannelState)> = Vec::new();
+        // 内置评分下得分大于 0 的候选通道
+        let mut scored: Vec<(ChannelType, f64)> = Vec::new();
         if best_channel_type.is_none() {
             // Iterate over all registered channels
             for ctype in self.channels.keys() {
                 // Check if we have state info for this target on this channel
                 if let Some(state) = self.cap_manager.get_channel_state(target, ctype) {
-                    let score = Scorer::score(*ctype, &state, &local_caps, message.priority);
+                    // 熔断冷却中的通道不参与选择
+                    if self.circuit_open(target, ctype) {
+                        log::debug!("Channel {:?} circuit open for {}", ctype, target);
+                        continue;
+                    }
+                    // 要求有序交付的消息只能走有序通道
+                    if !self.satisfies_ordering(message, ctype) {
+                        skipped_unordered = true;
+                        continue;
+                    }
+                    // 消息超出通道负载上限时提前排除，而不是等到发送时失败
+                    if let Some(limit) = self.payload_limit_exceeded(message, ctype) {
+                        if state.available {
+                            oversize_limit = oversize_limit.max(Some(limit));
+                        }
+                        continue;
+                    }
+                    if strategy.is_some() {
+                        candidates.push((*ctype, state));
+                        continue;
+                    }
+                    let score =
+                        self.score_channel(target, *ctype, &state, &local_caps, message.priority);
 
                     log::debug!("Channel {:?} score: {:.4}", ctype, score);
 
-                    if score > best_score && score > 0.0 {
-                        best_score = score;
-                        best_channel_type = Some(*ctype);
+                    if score > 0.0 {
+                        scored.push((*ctype, score));
                     }
                 }
             }
+
+            if strategy.is_none() {
+                best_channel_type = lock!(self.balancer, "balancer")
+                    .ok()
+                    .and_then(|mut balancer| balancer.pick(*target, &scored, &balance));
+            }
+
+            if let Some(strategy) = &strategy {
+                let ranked_input: Vec<(ChannelType, &ChannelState)> = candidates
+                    .iter()
+                    .map(|(ctype, state)| (*ctype, state))
+                    .collect();
+                best_channel_type = strategy
+                    .rank(&ranked_input, &local_caps, message)
+                    .into_iter()
+                    .find(|ctype| candidates.iter().any(|(candidate, _)| candidate == ctype));
+            }
         }
 
         if let Some(ctype) = best_channel_type {
             let channel = self.channels.get(&ctype).unwrap().clone();
 
+            // 半开通道只放行这一次探测发送
+            if self.cap_manager.breaker_state(target, &ctype) == BreakerState::HalfOpen {
+                log::info!("Probing half-open channel {:?} to {}", ctype, target);
+                self.cap_manager.begin_breaker_probe(*target, ctype);
+            }
+
             // 记录消息预计流量
-            let bytes = match &message.payload {
-                MessagePayload::Text(t) => t.len() as u64,
-                MessagePayload::Binary(b) => b.len() as u64,
-                MessagePayload::StreamChunk { data, .. } => data.len() as u64,
-                MessagePayload::StreamFrame { data, .. } => data.len() as u64,
-                MessagePayload::GroupKeyUpdate { update_path, .. } => update_path.len() as u64,
-                _ => 64,
-            };
-            self.record_traffic(ctype, bytes);
+            self.record_traffic(ctype, payload_size(&message.payload) as u64);
 
-            // 记录历史
-            self.record_history(*target, ctype);
+            // 记录历史（固定通道由策略决定，不参与预测）
+            if pinned.is_none() {
+                self.record_history(*target, ctype);
+            }
 
             Ok(channel)
+        } else if let Some(max_payload) = oversize_limit {
+            // 只有负载上限不足的通道可用，由调用方改走分片或缩小消息
+            Err(XLinkError::payload_too_large_for_channels(
+                target.to_string(),
+                payload_size(&message.payload),
+                max_payload,
+                file!(),
+            ))
+        } else if skipped_unordered {
+            // 不静默降级到无序通道，交由调用方决定
+            Err(XLinkError::no_ordered_channel(target.to_string(), file!()))
         } else {
             Err(XLinkError::no_route_found(
                 target.to_string(),
@@ -180,12 +571,194 @@ impl Router {
         }
     }
 
+    /// 解释消息会如何选路：列出每个本地通道的评分、可用性与未被选中的原因
+    ///
+    /// 与 `select_channel` 的判断一致，但不发送、不记录流量统计与路由历史，
+    /// 也不占用半开通道的探测机会。启用负载均衡时等价通道中总是列出得分最高者
+    pub fn explain_route(&self, message: &Message) -> RouteExplanation {
+        let target = &message.recipient;
+        let local_caps = self.cap_manager.get_local_caps();
+        let strategy = self.strategy();
+
+        let mut candidates: Vec<CandidateExplanation> = Vec::new();
+        // 通过熔断、有序与负载检查，可交给策略或评分选择的通道
+        let mut eligible: Vec<(ChannelType, ChannelState)> = Vec::new();
+        for ctype in self.channels.keys() {
+            let state = self.cap_manager.get_channel_state(target, ctype);
+            let mut score = None;
+            let decision = match &state {
+                None => Some(RouteDecision::NoState),
+                Some(_) if self.circuit_open(target, ctype) => Some(RouteDecision::CircuitOpen),
+                Some(_) if !self.satisfies_ordering(message, ctype) => {
+                    Some(RouteDecision::Unordered)
+                }
+                Some(state) => match self.payload_limit_exceeded(message, ctype) {
+                    Some(max_payload) => Some(RouteDecision::PayloadTooLarge { max_payload }),
+                    None => {
+                        let value = self.score_channel(
+                            target,
+                            *ctype,
+                            state,
+                            &local_caps,
+                            message.priority,
+                        );
+                        score = Some(value);
+                        if strategy.is_some() {
+                            eligible.push((*ctype, state.clone()));
+                        }
+                        if !state.available {
+                            Some(RouteDecision::Unavailable)
+                        } else if value <= 0.0 {
+                            Some(RouteDecision::BelowThreshold)
+                        } else {
+                            if strategy.is_none() {
+                                eligible.push((*ctype, state.clone()));
+                            }
+                            None
+                        }
+                    }
+                },
+            };
+            candidates.push(CandidateExplanation {
+                channel: *ctype,
+                available: state.as_ref().is_some_and(|state| state.available),
+                state,
+                score,
+                // 暂记为落选，确定选中通道后再改写
+                decision: decision.unwrap_or(RouteDecision::LostTiebreak),
+            });
+        }
+        candidates.sort_by(|a, b| {
+            b.score
+                .unwrap_or(f64::MIN)
+                .total_cmp(&a.score.unwrap_or(f64::MIN))
+                .then_with(|| (a.channel as u8).cmp(&(b.channel as u8)))
+        });
+
+        let (chosen, source) = if let Some(ctype) = self.pinned_channel(message) {
+            (Some(ctype), RouteSource::Pinned)
+        } else if let Some(strategy) = &strategy {
+            let ranked_input: Vec<(ChannelType, &ChannelState)> = eligible
+                .iter()
+                .map(|(ctype, state)| (*ctype, state))
+                .collect();
+            let chosen = strategy
+                .rank(&ranked_input, &local_caps, message)
+                .into_iter()
+                .find(|ctype| eligible.iter().any(|(candidate, _)| candidate == ctype));
+            (chosen, RouteSource::Strategy)
+        } else if let Some(ctype) = (self.balance_config().mode == BalanceMode::Single)
+            .then(|| self.predicted_channel(message, &local_caps))
+            .flatten()
+        {
+            (Some(ctype), RouteSource::Predicted)
+        } else {
+            let chosen = candidates
+                .iter()
+                .find(|candidate| {
+                    eligible
+                        .iter()
+                        .any(|(ctype, _)| *ctype == candidate.channel)
+                })
+                .map(|candidate| candidate.channel);
+            (chosen, RouteSource::Scored)
+        };
+        for candidate in &mut candidates {
+            if Some(candidate.channel) == chosen {
+                candidate.decision = RouteDecision::Chosen;
+            }
+        }
+
+        RouteExplanation {
+            recipient: *target,
+            priority: message.priority,
+            candidates,
+            chosen,
+            source: chosen.map_or(RouteSource::NoRoute, |_| source),
+        }
+    }
+
+    /// 导出当前路由表：每个已知对端的候选通道、评分与会被选中的通道
+    ///
+    /// 按普通优先级、无序消息评估，与 `select_channel` 的选择逻辑一致，
+    /// 但不记录流量统计与路由历史。评分与选择结果始终按内置评分计算，不反映自定义路由策略
+    /// 与负载均衡，等价通道中总是列出得分最高者。
+    pub fn routing_table(&self) -> Vec<PeerRoutingInfo> {
+        let priority = MessagePriority::Normal;
+        let local_caps = self.cap_manager.get_local_caps();
+
+        let mut table: Vec<PeerRoutingInfo> = self
+            .cap_manager
+            .get_known_devices()
+            .into_iter()
+            .map(|device_id| {
+                let mut channels: Vec<ChannelRouteInfo> = self
+                    .cap_manager
+                    .get_channel_states(&device_id)
+                    .into_iter()
+                    .map(|(channel, state)| {
+                        let score =
+                            self.score_channel(&device_id, channel, &state, &local_caps, priority);
+                        let excluded = if !self.channels.contains_key(&channel) {
+                            Some(RouteExclusion::NoLocalChannel)
+                        } else if !state.available {
+                            Some(RouteExclusion::Unavailable)
+                        } else if self.circuit_open(&device_id, &channel) {
+                            Some(RouteExclusion::CircuitOpen)
+                        } else if score <= 0.0 {
+                            Some(RouteExclusion::ZeroScore)
+                        } else {
+                            None
+                        };
+                        ChannelRouteInfo {
+                            channel,
+                            stale: state.failure_count > 0,
+                            state,
+                            score,
+                            excluded,
+                        }
+                    })
+                    .collect();
+                channels.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+                // 与 select_channel 相同：预测通道可用且分数尚可时优先使用
+                let predicted = self.predict_best_channel(&device_id).filter(|ctype| {
+                    channels.iter().any(|info| {
+                        info.channel == *ctype
+                            && info.state.available
+                            && info.excluded.is_none()
+                            && info.score > 0.6
+                    })
+                });
+                let chosen = predicted.or_else(|| {
+                    channels
+                        .iter()
+                        .find(|info| info.excluded.is_none())
+                        .map(|info| info.channel)
+                });
+
+                PeerRoutingInfo {
+                    device_id,
+                    priority,
+                    channels,
+                    chosen,
+                    predicted: predicted.is_some(),
+                }
+            })
+            .collect();
+        table.sort_by_key(|peer| peer.device_id.0);
+        table
+    }
+
     /// 清理路由器中的数据，防止内存泄漏
     pub async fn clear_channels(&self) {
         // 清理流量统计
         if let Ok(mut stats) = lock!(self.traffic_stats, "traffic_stats") {
             stats.clear();
         }
+        if let Ok(mut balancer) = lock!(self.balancer, "balancer") {
+            balancer.clear();
+        }
         // 清理路由历史
         if let Ok(mut history) = lock!(self.route_history, "route_history") {
             history.clear();
@@ -208,3 +781,17 @@ impl Router {
         log::debug!("Router: Synchronously cleared traffic stats and route history");
     }
 }
+
+/// 估算消息负载字节数，用于流量统计与通道负载上限检查
+pub(crate) fn payload_size(payload: &MessagePayload) -> usize {
+    match payload {
+        MessagePayload::Text(t) => t.len(),
+        MessagePayload::Binary(b) => b.len(),
+        MessagePayload::Compressed { data, .. } => data.len(),
+        MessagePayload::StreamChunk { data, .. } => data.len(),
+        MessagePayload::StreamFrame { data, .. } => data.len(),
+        MessagePayload::GroupKeyUpdate { update_path, .. } => update_path.len(),
+        MessagePayload::GroupKeyDelivery { sealed_secret, .. } => sealed_secret.len(),
+        _ => 64,
+    }
+}
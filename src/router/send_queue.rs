@@ -0,0 +1,290 @@
+//! 按通道限制并发的优先级发送队列
+//!
+//! 每种通道最多同时有 `max_in_flight` 条消息交给通道发送，超出的发送排队等待名额：
+//! 优先级高的先获得名额，同一优先级按入队顺序。队列为空且有空闲名额时直接发送，
+//! 不产生额外等待。
+//!
+//! 排队每满 `aging_after_ms` 的发送有效优先级提升一级（最高到 Critical），
+//! 持续涌入的高优先级消息因此无法让低优先级消息无限期等待。
+
+use crate::core::types::{ChannelType, MessagePriority};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// 发送队列配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendQueueConfig {
+    /// 每种通道的最大在途发送数，0 表示不限制
+    pub max_in_flight: usize,
+    /// 排队每满该时长有效优先级提升一级，0 表示不提升
+    #[serde(default = "default_aging_after_ms")]
+    pub aging_after_ms: u64,
+}
+
+fn default_aging_after_ms() -> u64 {
+    2000
+}
+
+impl Default for SendQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 32,
+            aging_after_ms: default_aging_after_ms(),
+        }
+    }
+}
+
+/// 一次发送取得名额的记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchRecord {
+    /// 发送时指定的优先级
+    pub priority: MessagePriority,
+    /// 取得名额时的有效优先级（含排队提升）
+    pub effective_priority: MessagePriority,
+    /// 通道内的放行序号，越小越早交给通道
+    pub sequence: u64,
+    pub enqueued_at: Instant,
+    pub dispatched_at: Instant,
+}
+
+impl DispatchRecord {
+    /// 排队等待名额的时间
+    pub fn waited(&self) -> Duration {
+        self.dispatched_at
+            .saturating_duration_since(self.enqueued_at)
+    }
+}
+
+/// 排队 `waited` 后的有效优先级
+fn aged_priority(
+    priority: MessagePriority,
+    waited: Duration,
+    aging_after: Duration,
+) -> MessagePriority {
+    const LEVELS: [MessagePriority; 4] = [
+        MessagePriority::Low,
+        MessagePriority::Normal,
+        MessagePriority::High,
+        MessagePriority::Critical,
+    ];
+    if aging_after.is_zero() {
+        return priority;
+    }
+    let base = LEVELS.iter().position(|&p| p == priority).unwrap_or(0);
+    let bumps = (waited.as_millis() / aging_after.as_millis()) as usize;
+    LEVELS[base.saturating_add(bumps).min(LEVELS.len() - 1)]
+}
+
+/// 排队等待名额的发送
+struct Waiter {
+    priority: MessagePriority,
+    seq: u64,
+    enqueued_at: Instant,
+    grant: oneshot::Sender<DispatchRecord>,
+}
+
+#[derive(Default)]
+struct ChannelQueue {
+    in_flight: usize,
+    waiting: Vec<Waiter>,
+    next_seq: u64,
+    next_dispatch: u64,
+}
+
+impl ChannelQueue {
+    fn record(
+        &mut self,
+        priority: MessagePriority,
+        effective_priority: MessagePriority,
+        enqueued_at: Instant,
+    ) -> DispatchRecord {
+        self.next_dispatch += 1;
+        DispatchRecord {
+            priority,
+            effective_priority,
+            sequence: self.next_dispatch,
+            enqueued_at,
+            dispatched_at: Instant::now(),
+        }
+    }
+
+    /// 把一个名额交给有效优先级最高的等待者（同级先入队者优先），没有仍在等待的发送时返回 false
+    fn hand_over(&mut self, aging_after: Duration) -> bool {
+        loop {
+            let now = Instant::now();
+            let Some((effective, _, index)) = self
+                .waiting
+                .iter()
+                .enumerate()
+                .map(|(index, waiter)| {
+                    let waited = now.saturating_duration_since(waiter.enqueued_at);
+                    let effective = aged_priority(waiter.priority, waited, aging_after);
+                    (effective, Reverse(waiter.seq), index)
+                })
+                .max()
+            else {
+                return false;
+            };
+            let waiter = self.waiting.swap_remove(index);
+            let record = self.record(waiter.priority, effective, waiter.enqueued_at);
+            if waiter.grant.send(record).is_ok() {
+                return true;
+            }
+        }
+    }
+}
+
+/// 各通道的优先级发送队列
+#[derive(Default)]
+pub struct SendQueue {
+    config: Mutex<SendQueueConfig>,
+    queues: Mutex<HashMap<ChannelType, ChannelQueue>>,
+}
+
+/// 发送名额，释放时交给下一个等待者
+pub struct SendPermit<'a> {
+    queue: &'a SendQueue,
+    channel: ChannelType,
+    record: DispatchRecord,
+}
+
+impl SendPermit<'_> {
+    /// 本次取得名额的记录
+    pub fn record(&self) -> DispatchRecord {
+        self.record
+    }
+}
+
+impl Drop for SendPermit<'_> {
+    fn drop(&mut self) {
+        self.queue.release(self.channel);
+    }
+}
+
+/// 尚未拿到名额的等待，取消时归还可能已经转交过来的名额
+struct PendingGrant<'a> {
+    queue: &'a SendQueue,
+    channel: ChannelType,
+    rx: Option<oneshot::Receiver<DispatchRecord>>,
+}
+
+impl Drop for PendingGrant<'_> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.queue.release(self.channel);
+            }
+        }
+    }
+}
+
+impl SendQueue {
+    pub fn new(config: SendQueueConfig) -> Self {
+        Self {
+            config: Mutex::new(config),
+            queues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 更新配置，调高上限时立即放行相应数量的等待者
+    pub fn set_config(&self, config: SendQueueConfig) {
+        *self.config.lock() = config;
+        let aging_after = Duration::from_millis(config.aging_after_ms);
+        let mut queues = self.queues.lock();
+        for queue in queues.values_mut() {
+            while (config.max_in_flight == 0 || queue.in_flight < config.max_in_flight)
+                && queue.hand_over(aging_after)
+            {
+                queue.in_flight += 1;
+            }
+        }
+    }
+
+    pub fn config(&self) -> SendQueueConfig {
+        *self.config.lock()
+    }
+
+    /// 等待 `channel` 的发送名额
+    pub async fn acquire(&self, channel: ChannelType, priority: MessagePriority) -> SendPermit<'_> {
+        let max_in_flight = self.config.lock().max_in_flight;
+        let enqueued_at = Instant::now();
+        let rx = {
+            let mut queues = self.queues.lock();
+            let queue = queues.entry(channel).or_default();
+            if max_in_flight == 0 || (queue.waiting.is_empty() && queue.in_flight < max_in_flight) {
+                queue.in_flight += 1;
+                let record = queue.record(priority, priority, enqueued_at);
+                return SendPermit {
+                    queue: self,
+                    channel,
+                    record,
+                };
+            }
+            let (grant, rx) = oneshot::channel();
+            queue.next_seq += 1;
+            queue.waiting.push(Waiter {
+                priority,
+                seq: queue.next_seq,
+                enqueued_at,
+                grant,
+            });
+            rx
+        };
+
+        let mut pending = PendingGrant {
+            queue: self,
+            channel,
+            rx: Some(rx),
+        };
+        let mut record = None;
+        if let Some(rx) = pending.rx.as_mut() {
+            // 名额由释放方连同在途计数一起转交
+            record = rx.await.ok();
+        }
+        pending.rx = None;
+        SendPermit {
+            queue: self,
+            channel,
+            record: record.unwrap_or(DispatchRecord {
+                priority,
+                effective_priority: priority,
+                sequence: 0,
+                enqueued_at,
+                dispatched_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// `channel` 当前交给通道发送中的消息数
+    pub fn in_flight(&self, channel: ChannelType) -> usize {
+        self.queues
+            .lock()
+            .get(&channel)
+            .map_or(0, |queue| queue.in_flight)
+    }
+
+    /// `channel` 当前排队等待名额的发送数
+    pub fn queued(&self, channel: ChannelType) -> usize {
+        self.queues
+            .lock()
+            .get(&channel)
+            .map_or(0, |queue| queue.waiting.len())
+    }
+
+    fn release(&self, channel: ChannelType) {
+        let config = *self.config.lock();
+        let mut queues = self.queues.lock();
+        if let Some(queue) = queues.get_mut(&channel) {
+            // 上限调低后先让在途数回落到上限以内，再转交名额
+            let over_limit = config.max_in_flight != 0 && queue.in_flight > config.max_in_flight;
+            if over_limit || !queue.hand_over(Duration::from_millis(config.aging_after_ms)) {
+                queue.in_flight = queue.in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! 可插拔的路由策略
+//!
+//! 默认情况下 [`Router`](crate::router::selector::Router) 使用内置评分（[`Scorer`]，
+//! 叠加近期失败惩罚与预测性路由）选择通道。集成方可以实现 [`RoutingStrategy`]
+//! 完全接管候选通道的排序，例如按负载大小固定通道，或基于 `failure_count` 与
+//! `last_heartbeat` 实现熔断。
+
+use crate::core::types::{ChannelState, ChannelType, DeviceCapabilities, Message};
+use crate::router::scoring::Scorer;
+
+/// 路由策略：对候选通道排序
+///
+/// 候选通道已经过路由器筛选：本地已注册、对端有通道状态、满足消息的有序交付要求且
+/// 不超出通道负载上限，但不保证 `available` 为真。返回的通道按从优到劣排列，
+/// 路由器选择第一个；未出现在结果中的候选视为被策略排除，结果为空时返回无可用路由。
+pub trait RoutingStrategy: Send + Sync {
+    fn rank(
+        &self,
+        candidates: &[(ChannelType, &ChannelState)],
+        caps: &DeviceCapabilities,
+        msg: &Message,
+    ) -> Vec<ChannelType>;
+}
+
+/// 基于 [`Scorer`] 的默认策略：排除不可用与零分通道，按评分从高到低排序
+///
+/// 不包含路由器内置的近期失败惩罚与预测性路由，供自定义策略回退使用。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScorerStrategy;
+
+impl RoutingStrategy for ScorerStrategy {
+    fn rank(
+        &self,
+        candidates: &[(ChannelType, &ChannelState)],
+        caps: &DeviceCapabilities,
+        msg: &Message,
+    ) -> Vec<ChannelType> {
+        let mut scored: Vec<(ChannelType, f64)> = candidates
+            .iter()
+            .map(|(ctype, state)| (*ctype, Scorer::score(*ctype, state, caps, msg.priority)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().map(|(ctype, _)| ctype).collect()
+    }
+}
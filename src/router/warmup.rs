@@ -0,0 +1,89 @@
+//! 通道预热
+//!
+//! 启动后为最近活跃的对端提前调用各通道的 `Channel::warm_up`，让首条消息不再承担连接建立延迟。
+//! 只预热对端曾经出现过的通道，并发数与对端数量均有上限。
+
+use crate::core::types::{ChannelType, DeviceId};
+use crate::router::selector::Router;
+use futures::StreamExt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 一次预热的结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// 参与预热的对端
+    pub peers: Vec<DeviceId>,
+    /// 预热成功的（对端, 通道）
+    pub warmed: Vec<(DeviceId, ChannelType)>,
+    /// 预热失败的次数
+    pub failed: usize,
+}
+
+/// 按最近活跃时间挑选对端并预热其通道
+pub async fn warm_up_recent_peers(
+    router: &Router,
+    recent_within: Duration,
+    max_peers: usize,
+    max_concurrency: usize,
+) -> WarmupReport {
+    let cap_manager = router.capability_manager();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let cutoff = now.saturating_sub(recent_within.as_secs());
+
+    // 对端最近活跃时间取其各通道中最新的心跳
+    let mut peers: Vec<(DeviceId, u64, Vec<ChannelType>)> = cap_manager
+        .get_known_devices()
+        .into_iter()
+        .filter_map(|device| {
+            let states = cap_manager.get_channel_states(&device);
+            let last_active = states.iter().map(|(_, s)| s.last_heartbeat).max()?;
+            let channels = states
+                .into_iter()
+                .map(|(channel, _)| channel)
+                .filter(|channel| router.get_channels().contains_key(channel))
+                .collect::<Vec<_>>();
+            (last_active >= cutoff && !channels.is_empty()).then_some((
+                device,
+                last_active,
+                channels,
+            ))
+        })
+        .collect();
+    peers.sort_unstable_by_key(|(_, last_active, _)| std::cmp::Reverse(*last_active));
+    peers.truncate(max_peers);
+
+    let targets: Vec<(DeviceId, ChannelType)> = peers
+        .iter()
+        .flat_map(|(device, _, channels)| channels.iter().map(move |channel| (*device, *channel)))
+        .collect();
+    let results: Vec<_> = futures::stream::iter(targets)
+        .map(|(device, channel_type)| async move {
+            let result = match router.get_channels().get(&channel_type) {
+                Some(channel) => channel.warm_up(&device).await,
+                None => Ok(()),
+            };
+            if let Err(e) = &result {
+                log::debug!("Warm-up of {:?} to {} failed: {}", channel_type, device, e);
+            }
+            (device, channel_type, result.is_ok())
+        })
+        .buffer_unordered(max_concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut report = WarmupReport {
+        peers: peers.into_iter().map(|(device, _, _)| device).collect(),
+        ..Default::default()
+    };
+    for (device, channel, ok) in results {
+        if ok {
+            report.warmed.push((device, channel));
+        } else {
+            report.failed += 1;
+        }
+    }
+    report
+}
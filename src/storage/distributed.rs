@@ -117,6 +117,14 @@ impl crate::core::traits::Storage for DistributedStorageAdapter {
             priority: message.priority,
             timestamp: message.timestamp,
             require_ack: message.require_ack,
+            require_ordered: message.require_ordered,
+            sequence: message.sequence,
+            sequence_epoch: message.sequence_epoch,
+            correlation_id: message.correlation_id,
+            in_reply_to: message.in_reply_to,
+            topic: message.topic.clone(),
+            expires_at: message.expires_at,
+            signature: message.signature.clone(),
         };
         self.local_cache.save_message(&hash_message).await
     }
@@ -154,6 +162,28 @@ impl crate::core::traits::Storage for DistributedStorageAdapter {
         self.local_cache.get_audit_logs(limit).await
     }
 
+    async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+    ) -> crate::core::error::Result<crate::core::types::AuditLogPage> {
+        self.local_cache
+            .get_audit_logs_paged(offset, limit, max_bytes_scanned)
+            .await
+    }
+
+    async fn query_audit_logs(
+        &self,
+        filter: &crate::core::types::AuditFilter,
+        page: usize,
+        page_size: usize,
+    ) -> crate::core::error::Result<crate::core::types::AuditEntryPage> {
+        self.local_cache
+            .query_audit_logs(filter, page, page_size)
+            .await
+    }
+
     async fn cleanup_old_data(&self, days: u32) -> crate::core::error::Result<u64> {
         self.local_cache.cleanup_old_data(days).await
     }
@@ -174,6 +204,16 @@ impl crate::core::traits::Storage for DistributedStorageAdapter {
             .await
     }
 
+    async fn get_pending_messages_for_recovery_filtered(
+        &self,
+        device_id: &crate::core::types::DeviceId,
+        filter: &crate::core::types::RecoveryFilter,
+    ) -> crate::core::error::Result<Vec<crate::core::types::Message>> {
+        self.local_cache
+            .get_pending_messages_for_recovery_filtered(device_id, filter)
+            .await
+    }
+
     async fn remove_pending_message(
         &self,
         message_id: &uuid::Uuid,
@@ -181,6 +221,32 @@ impl crate::core::traits::Storage for DistributedStorageAdapter {
         self.local_cache.remove_pending_message(message_id).await
     }
 
+    async fn list_messages(&self) -> crate::core::error::Result<Vec<crate::core::types::Message>> {
+        // 本地缓存只保存哈希引用，逐条从分布式存储下载实际内容
+        let mut messages = Vec::new();
+        for hash_msg in self.local_cache.list_messages().await? {
+            if let crate::core::types::MessagePayload::Text(hash) = &hash_msg.payload {
+                let data = self.distributed_store.download(hash).await?;
+                messages.push(serde_json::from_slice(&data).map_err(Into::<XLinkError>::into)?);
+            }
+        }
+        Ok(messages)
+    }
+
+    async fn list_pending_messages(
+        &self,
+    ) -> crate::core::error::Result<Vec<crate::core::types::Message>> {
+        self.local_cache.list_pending_messages().await
+    }
+
+    async fn save_metadata(&self, key: &str, value: Vec<u8>) -> crate::core::error::Result<()> {
+        self.local_cache.save_metadata(key, value).await
+    }
+
+    async fn load_metadata(&self, key: &str) -> crate::core::error::Result<Option<Vec<u8>>> {
+        self.local_cache.load_metadata(key).await
+    }
+
     async fn get_storage_usage(&self) -> crate::core::error::Result<u64> {
         self.local_cache.get_storage_usage().await
     }
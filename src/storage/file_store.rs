@@ -1,6 +1,8 @@
 use crate::core::error::{Result, XLinkError};
 use crate::core::traits::Storage;
-use crate::core::types::{DeviceId, Message};
+use crate::core::types::{AuditLogPage, DeliveryReceipt, DeviceId, Message, RecoveryFilter};
+use crate::crypto::state_seal::{self, StaticStateKey};
+use crate::storage::versioned::{self, RecordKind};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use std::path::{Path, PathBuf};
@@ -14,10 +16,24 @@ pub struct FileStorage {
     message_index: Arc<DashMap<Uuid, DeviceId>>,
     // 待发送消息 ID 到发送者 DeviceId 的索引，用于优化 remove_pending_message 的 O(N) 扫描问题
     pending_index: Arc<DashMap<Uuid, DeviceId>>,
+    // 消息记录的静态加密密钥；索引与文件名仍为明文
+    record_key: Option<StaticStateKey>,
 }
 
 impl FileStorage {
     pub async fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path, None).await
+    }
+
+    /// 创建加密存储：消息与待发送消息以 XChaCha20-Poly1305 封装后写入磁盘
+    ///
+    /// 启用加密前写入的明文记录仍可读取；无法解密的记录在读取时记录日志并跳过。
+    /// 密文与记录类型及消息 ID 绑定，被挪到其他记录文件下的密文无法解密。
+    pub async fn new_encrypted<P: AsRef<Path>>(path: P, key: [u8; 32]) -> Result<Self> {
+        Self::open(path, Some(StaticStateKey::new(key))).await
+    }
+
+    async fn open<P: AsRef<Path>>(path: P, record_key: Option<StaticStateKey>) -> Result<Self> {
         let base_path = path.as_ref().to_path_buf();
 
         // 验证路径安全性
@@ -33,6 +49,7 @@ impl FileStorage {
             base_path,
             message_index: Arc::new(DashMap::new()),
             pending_index: Arc::new(DashMap::new()),
+            record_key,
         };
 
         storage.rebuild_index().await?;
@@ -40,7 +57,7 @@ impl FileStorage {
     }
 
     /// 验证路径安全性，防止路径遍历攻击
-    fn validate_path(path: &Path) -> Result<()> {
+    pub(crate) fn validate_path(path: &Path) -> Result<()> {
         // 检查路径是否包含 ".." 或其他危险模式
         let path_str = path.to_string_lossy();
         if path_str.contains("..") {
@@ -201,6 +218,52 @@ impl FileStorage {
         Ok(())
     }
 
+    /// 加密记录的关联数据：记录类型与消息 ID
+    fn record_context(kind: RecordKind, message_id: &Uuid) -> Vec<u8> {
+        let label: &[u8] = match kind {
+            RecordKind::Message => b"message",
+            RecordKind::PendingMessage => b"pending",
+            RecordKind::CryptoState => b"crypto_state",
+        };
+        [label, message_id.as_bytes().as_slice()].concat()
+    }
+
+    /// 序列化消息记录，配置了密钥时加密
+    fn encode_record(&self, kind: RecordKind, message: &Message) -> Result<Vec<u8>> {
+        let content = versioned::encode(message)?;
+        match &self.record_key {
+            Some(key) => state_seal::seal_with_context(
+                key,
+                &content,
+                &Self::record_context(kind, &message.id),
+            ),
+            None => Ok(content),
+        }
+    }
+
+    /// 解析 `path` 处的消息记录，加密记录先按文件名中的消息 ID 解密
+    fn decode_record(&self, kind: RecordKind, path: &Path, content: &[u8]) -> Result<Message> {
+        match &self.record_key {
+            Some(key) if state_seal::is_sealed(content) => {
+                let message_id = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| Uuid::parse_str(s).ok())
+                    .ok_or_else(|| {
+                        XLinkError::invalid_ciphertext(
+                            "Encrypted record file name is not a message id".to_string(),
+                            file!(),
+                        )
+                    })?;
+                let context = Self::record_context(kind, &message_id);
+                let plaintext =
+                    zeroize::Zeroizing::new(state_seal::open_with_context(key, content, &context)?);
+                versioned::decode(kind, &plaintext)
+            }
+            _ => versioned::decode(kind, content),
+        }
+    }
+
     fn get_device_dir(&self, device_id: &DeviceId) -> PathBuf {
         self.base_path.join(device_id.to_string())
     }
@@ -256,6 +319,40 @@ impl FileStorage {
         self.get_pending_device_dir(device_id)
             .join(format!("{}.json", message_id))
     }
+
+    /// 安全地获取元数据文件路径，键只允许字母、数字、`-` 与 `_`
+    fn get_metadata_path(&self, key: &str) -> Result<PathBuf> {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(XLinkError::invalid_input(
+                "metadata_key",
+                "Invalid metadata key",
+                file!(),
+            ));
+        }
+        Ok(self.base_path.join("metadata").join(format!("{}.bin", key)))
+    }
+
+    fn get_receipt_path(&self, message_id: &Uuid) -> PathBuf {
+        self.base_path
+            .join("receipts")
+            .join(format!("{}.json", message_id))
+    }
+
+    /// 受保留期清理的目录：按设备分组的消息、待发送消息、审计日志与投递回执
+    ///
+    /// metadata/ 下保存流量预算、指标、退出标记与传输清单等状态，不随保留期删除
+    fn is_retention_dir(name: &str) -> bool {
+        matches!(name, "pending" | "audit" | "receipts") || name.parse::<DeviceId>().is_ok()
+    }
+
+    /// 按容量清理时可删除的目录：按设备分组的已存消息与审计日志，待发送消息不在其中
+    fn is_reclaimable_dir(name: &str) -> bool {
+        name == "audit" || name.parse::<DeviceId>().is_ok()
+    }
 }
 
 #[async_trait]
@@ -269,7 +366,7 @@ impl Storage for FileStorage {
         }
 
         let path = self.get_message_path(&message.recipient, &message.id);
-        let content = serde_json::to_vec(message).map_err(Into::<XLinkError>::into)?;
+        let content = self.encode_record(RecordKind::Message, message)?;
         fs::write(path, content)
             .await
             .map_err(Into::<XLinkError>::into)?;
@@ -297,10 +394,15 @@ impl Storage for FileStorage {
         {
             let path = entry.path();
             if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content = fs::read(path).await.map_err(Into::<XLinkError>::into)?;
-                let message: Message =
-                    serde_json::from_slice(&content).map_err(Into::<XLinkError>::into)?;
-                messages.push(message);
+                let content = fs::read(&path).await.map_err(Into::<XLinkError>::into)?;
+                match self.decode_record(RecordKind::Message, &path, &content) {
+                    Ok(message) => messages.push(message),
+                    // 仅加密存储跳过无法解密的记录，明文存储中损坏的记录照旧报错
+                    Err(e) if self.record_key.is_some() => {
+                        log::warn!("Skipping unreadable message {}: {}", path.display(), e)
+                    }
+                    Err(e) => return Err(e),
+                }
             }
         }
 
@@ -371,12 +473,85 @@ impl Storage for FileStorage {
         Ok(logs)
     }
 
+    async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+    ) -> Result<AuditLogPage> {
+        let audit_dir = self.base_path.join("audit");
+        if !audit_dir.exists() {
+            return Ok(AuditLogPage::default());
+        }
+
+        // 文件名即写入时间戳，仅枚举目录即可建立索引，无需读取偏移之前的日志内容
+        let mut index = Vec::new();
+        let mut entries = fs::read_dir(audit_dir)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(Into::<XLinkError>::into)?
+        {
+            let path = entry.path();
+            let timestamp = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u128>().ok());
+            if let Some(timestamp) = timestamp {
+                index.push((timestamp, path));
+            }
+        }
+        index.sort_unstable_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+
+        let mut page = AuditLogPage::default();
+        let mut scanned = 0u64;
+        let mut remaining = index.into_iter().skip(offset).peekable();
+        while page.entries.len() < limit {
+            let Some((_, path)) = remaining.peek() else {
+                break;
+            };
+            let size = fs::metadata(path)
+                .await
+                .map_err(Into::<XLinkError>::into)?
+                .len();
+            if !page.entries.is_empty() && scanned + size > max_bytes_scanned {
+                page.partial = true;
+                break;
+            }
+            let content = fs::read_to_string(path)
+                .await
+                .map_err(Into::<XLinkError>::into)?;
+            scanned += size;
+            page.entries.push(content);
+            remaining.next();
+        }
+        if remaining.peek().is_some() {
+            page.next_offset = Some(offset + page.entries.len());
+        }
+        Ok(page)
+    }
+
     async fn cleanup_old_data(&self, days: u32) -> Result<u64> {
         let mut count = 0;
         let now = std::time::SystemTime::now();
         let threshold = std::time::Duration::from_secs((days * 24 * 3600) as u64);
 
-        let mut stack = vec![self.base_path.clone()];
+        let mut stack = Vec::new();
+        let mut entries = fs::read_dir(&self.base_path)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(Into::<XLinkError>::into)?
+        {
+            if entry.path().is_dir() && Self::is_retention_dir(&entry.file_name().to_string_lossy())
+            {
+                stack.push(entry.path());
+            }
+        }
         while let Some(dir) = stack.pop() {
             let mut entries = fs::read_dir(dir).await.map_err(Into::<XLinkError>::into)?;
             while let Some(entry) = entries
@@ -393,15 +568,14 @@ impl Storage for FileStorage {
                     Ok(d) => d,
                     Err(_) => continue,
                 };
-                if elapsed > threshold {
-                    if metadata.is_file() {
-                        fs::remove_file(entry.path())
-                            .await
-                            .map_err(Into::<XLinkError>::into)?;
-                        count += 1;
-                    }
-                } else if metadata.is_dir() {
+                // 目录的修改时间只随增删文件变化，无论新旧都要进入检查其中的文件
+                if metadata.is_dir() {
                     stack.push(entry.path());
+                } else if elapsed > threshold && metadata.is_file() {
+                    fs::remove_file(entry.path())
+                        .await
+                        .map_err(Into::<XLinkError>::into)?;
+                    count += 1;
                 }
             }
         }
@@ -417,7 +591,7 @@ impl Storage for FileStorage {
         }
 
         let path = self.get_pending_message_path(&message.sender, &message.id);
-        let content = serde_json::to_vec(message).map_err(Into::<XLinkError>::into)?;
+        let content = self.encode_record(RecordKind::PendingMessage, message)?;
         fs::write(path, content)
             .await
             .map_err(Into::<XLinkError>::into)?;
@@ -430,6 +604,15 @@ impl Storage for FileStorage {
     async fn get_pending_messages_for_recovery(
         &self,
         device_id: &DeviceId,
+    ) -> Result<Vec<Message>> {
+        self.get_pending_messages_for_recovery_filtered(device_id, &RecoveryFilter::default())
+            .await
+    }
+
+    async fn get_pending_messages_for_recovery_filtered(
+        &self,
+        device_id: &DeviceId,
+        filter: &RecoveryFilter,
     ) -> Result<Vec<Message>> {
         let pending_dir = self.get_pending_device_dir_safe(device_id)?;
         if !pending_dir.exists() {
@@ -448,13 +631,25 @@ impl Storage for FileStorage {
         {
             let path = entry.path();
             if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content = fs::read(path).await.map_err(Into::<XLinkError>::into)?;
-                let message: Message =
-                    serde_json::from_slice(&content).map_err(Into::<XLinkError>::into)?;
-                messages.push(message);
+                let content = fs::read(&path).await.map_err(Into::<XLinkError>::into)?;
+                match self.decode_record(RecordKind::PendingMessage, &path, &content) {
+                    Ok(message) if filter.matches(&message) => messages.push(message),
+                    Ok(_) => {}
+                    Err(e) if self.record_key.is_some() => log::warn!(
+                        "Skipping unreadable pending message {}: {}",
+                        path.display(),
+                        e
+                    ),
+                    Err(e) => return Err(e),
+                }
             }
         }
 
+        // 目录遍历无序，限制条数时按消息时间取最早的
+        if let Some(max_count) = filter.max_count {
+            messages.sort_by_key(|message| message.timestamp);
+            messages.truncate(max_count);
+        }
         Ok(messages)
     }
 
@@ -471,6 +666,93 @@ impl Storage for FileStorage {
         Ok(())
     }
 
+    async fn list_messages(&self) -> Result<Vec<Message>> {
+        let devices: std::collections::HashSet<DeviceId> = self
+            .message_index
+            .iter()
+            .map(|entry| *entry.value())
+            .collect();
+        let mut messages = Vec::new();
+        for device_id in devices {
+            messages.extend(self.get_pending_messages(&device_id).await?);
+        }
+        Ok(messages)
+    }
+
+    async fn list_pending_messages(&self) -> Result<Vec<Message>> {
+        let devices: std::collections::HashSet<DeviceId> = self
+            .pending_index
+            .iter()
+            .map(|entry| *entry.value())
+            .collect();
+        let mut messages = Vec::new();
+        for device_id in devices {
+            messages.extend(self.get_pending_messages_for_recovery(&device_id).await?);
+        }
+        Ok(messages)
+    }
+
+    async fn save_delivery_receipt(&self, receipt: &DeliveryReceipt) -> Result<()> {
+        let path = self.get_receipt_path(&receipt.message_id);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(Into::<XLinkError>::into)?;
+        }
+        let content = serde_json::to_vec(receipt).map_err(Into::<XLinkError>::into)?;
+        fs::write(path, content)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        Ok(())
+    }
+
+    async fn get_delivery_receipt(&self, message_id: &Uuid) -> Result<Option<DeliveryReceipt>> {
+        let content = match fs::read(self.get_receipt_path(message_id)).await {
+            Ok(content) => content,
+            // 回退读取早期版本以元数据保存的回执
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                match self
+                    .load_metadata(&DeliveryReceipt::metadata_key(message_id))
+                    .await?
+                {
+                    Some(content) if !content.is_empty() => content,
+                    _ => return Ok(None),
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(
+            serde_json::from_slice(&content).map_err(Into::<XLinkError>::into)?,
+        ))
+    }
+
+    async fn save_metadata(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        let path = self.get_metadata_path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(Into::<XLinkError>::into)?;
+        }
+        // 先写临时文件再重命名，避免中途崩溃留下半截数据
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, value)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        fs::rename(tmp_path, path)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        Ok(())
+    }
+
+    async fn load_metadata(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.get_metadata_path(key)?;
+        match fs::read(path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     async fn get_storage_usage(&self) -> Result<u64> {
         let mut total_size = 0u64;
         let mut stack = vec![self.base_path.clone()];
@@ -503,8 +785,20 @@ impl Storage for FileStorage {
         let mut removed_size = 0u64;
         let mut files_to_remove = Vec::new();
 
-        // 收集所有文件及其修改时间
-        let mut stack = vec![self.base_path.clone()];
+        // 收集可回收目录中的文件及其修改时间
+        let mut stack = Vec::new();
+        let mut top = fs::read_dir(&self.base_path)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        while let Some(entry) = top.next_entry().await.map_err(Into::<XLinkError>::into)? {
+            let reclaimable = entry
+                .file_name()
+                .to_str()
+                .is_some_and(Self::is_reclaimable_dir);
+            if reclaimable && entry.path().is_dir() {
+                stack.push(entry.path());
+            }
+        }
         while let Some(dir) = stack.pop() {
             let mut entries = fs::read_dir(dir).await.map_err(Into::<XLinkError>::into)?;
             while let Some(entry) = entries
@@ -0,0 +1,185 @@
+//! 可回放的消息日志
+//!
+//! 开启后按 JSON Lines 格式记录 SDK 处理的每一条入站与出站消息（含记录时间与所选通道），
+//! 用于在本地重现现场问题。合规配置开启隐私保护模式时，消息负载在写入前脱敏。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::types::{ChannelType, Message, MessagePayload};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// 脱敏后文本负载的占位内容
+pub const REDACTED_TEXT: &str = "[REDACTED]";
+
+/// 消息方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalDirection {
+    Inbound,
+    Outbound,
+}
+
+/// 日志条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 记录时间（Unix 毫秒）
+    pub recorded_at_ms: u64,
+    pub direction: JournalDirection,
+    /// 出站消息所选通道；入站消息为 None
+    pub channel: Option<ChannelType>,
+    pub message: Message,
+}
+
+pub struct MessageJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+    redact: bool,
+}
+
+impl MessageJournal {
+    /// 打开（或创建）日志文件并以追加方式写入
+    pub async fn open<P: AsRef<Path>>(path: P, redact: bool) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if path.to_string_lossy().contains("..") {
+            return Err(XLinkError::invalid_input(
+                "journal_path",
+                "Path traversal not allowed",
+                file!(),
+            ));
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+            redact,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 是否对负载脱敏
+    pub fn is_redacting(&self) -> bool {
+        self.redact
+    }
+
+    /// 记录一条消息
+    pub async fn record(
+        &self,
+        direction: JournalDirection,
+        channel: Option<ChannelType>,
+        message: &Message,
+    ) -> Result<()> {
+        let mut message = message.clone();
+        if self.redact {
+            message.payload = redact_payload(&message.payload);
+        }
+
+        let entry = JournalEntry {
+            recorded_at_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            direction,
+            channel,
+            message,
+        };
+
+        let mut line = serde_json::to_vec(&entry).map_err(Into::<XLinkError>::into)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().await;
+        file.write_all(&line)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+        file.flush().await.map_err(Into::<XLinkError>::into)
+    }
+
+    /// 读取日志文件中的全部条目
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Vec<JournalEntry>> {
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(Into::<XLinkError>::into)?;
+
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::<XLinkError>::into))
+            .collect()
+    }
+}
+
+/// 将日志中记录的入站消息按原顺序回放到 SDK 的消息处理器，返回回放条数
+///
+/// 单条消息处理失败（例如被限流或拒绝）只记录告警，不中断回放
+pub async fn replay<P: AsRef<Path>>(journal: P, into: &crate::XLink) -> Result<usize> {
+    let handler = into.get_message_handler();
+    let mut replayed = 0;
+
+    for entry in MessageJournal::load(journal).await? {
+        if entry.direction != JournalDirection::Inbound {
+            continue;
+        }
+        let message_id = entry.message.id;
+        if let Err(e) = handler.handle_message(entry.message).await {
+            log::warn!("Replay of message {} failed: {}", message_id, e);
+        }
+        replayed += 1;
+    }
+
+    Ok(replayed)
+}
+
+/// 脱敏负载：清除用户数据，保留控制信息
+fn redact_payload(payload: &MessagePayload) -> MessagePayload {
+    match payload {
+        MessagePayload::Text(_) => MessagePayload::Text(REDACTED_TEXT.to_string()),
+        MessagePayload::Binary(_) => MessagePayload::Binary(Vec::new()),
+        MessagePayload::StreamChunk {
+            stream_id,
+            total_chunks,
+            chunk_index,
+            sent_at,
+            fec_group_size,
+            compression,
+            checksum,
+            ..
+        } => MessagePayload::StreamChunk {
+            stream_id: *stream_id,
+            total_chunks: *total_chunks,
+            chunk_index: *chunk_index,
+            data: Vec::new(),
+            sent_at: *sent_at,
+            fec_group_size: *fec_group_size,
+            compression: *compression,
+            checksum: *checksum,
+            chunk_crc: None,
+        },
+        MessagePayload::Compressed { algorithm, .. } => MessagePayload::Compressed {
+            algorithm: *algorithm,
+            data: Vec::new(),
+        },
+        MessagePayload::StreamFrame {
+            stream_id,
+            frame_index,
+            timestamp,
+            ..
+        } => MessagePayload::StreamFrame {
+            stream_id: *stream_id,
+            frame_index: *frame_index,
+            data: Vec::new(),
+            timestamp: *timestamp,
+        },
+        other => other.clone(),
+    }
+}
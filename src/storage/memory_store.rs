@@ -1,128 +1,366 @@
-use crate::core::error::Result;
+//! 内存存储后端
+//!
+//! `InMemoryStorage` 不触碰文件系统，适合测试与无需持久化的临时设备。
+//! 存储用量按记录的序列化字节数计算，按天清理与按容量清理都以写入时间为准，
+//! 与 `FileStorage`、`SqliteStorage` 的语义一致。
+
+use crate::core::error::{Result, XLinkError};
 use crate::core::traits::Storage;
-use crate::core::types::{DeviceId, Message};
+use crate::core::types::{AuditLogPage, DeliveryReceipt, DeviceId, Message, RecoveryFilter};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// 一条存储记录及其写入时间（Unix 秒）、写入序号与序列化字节数
 #[derive(Clone)]
-pub struct MemoryStorage {
-    messages: Arc<DashMap<DeviceId, Vec<Message>>>,
-    pending_messages: Arc<DashMap<DeviceId, Vec<Message>>>,
-    audit_logs: Arc<DashMap<String, Vec<String>>>,
-    message_index: Arc<DashMap<Uuid, DeviceId>>,
-    pending_index: Arc<DashMap<Uuid, DeviceId>>,
+struct Stored<T> {
+    created_at: u64,
+    seq: u64,
+    size: u64,
+    value: T,
+}
+
+/// 按容量清理时的候选记录，消息以 (消息 ID, 接收方) 标识
+enum Victim {
+    Message(Uuid, DeviceId),
+    AuditLog(u64),
+}
+
+/// 消息 ID -> 存有该 ID 记录的分组
+///
+/// 群组广播对每个接收方使用同一消息 ID，同一 ID 可能对应多条记录
+type RecordIndex = DashMap<Uuid, Vec<DeviceId>>;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn serialized_size(message: &Message) -> Result<u64> {
+    serde_json::to_vec(message)
+        .map(|bytes| bytes.len() as u64)
+        .map_err(Into::<XLinkError>::into)
+}
+
+/// 从按设备分组的记录中移除指定消息，`recipient` 为 None 时移除该 ID 的全部记录，
+/// 返回被移除记录的总字节数
+fn remove_indexed(
+    records: &DashMap<DeviceId, Vec<Stored<Message>>>,
+    index: &RecordIndex,
+    message_id: &Uuid,
+    recipient: Option<&DeviceId>,
+) -> u64 {
+    let Some(partitions) = index.get(message_id).map(|p| p.clone()) else {
+        return 0;
+    };
+    let mut removed = 0u64;
+    let mut remaining = Vec::new();
+    for device_id in partitions {
+        let Some(mut entry) = records.get_mut(&device_id) else {
+            continue;
+        };
+        entry.retain(|r| {
+            let matches = r.value.id == *message_id
+                && recipient.is_none_or(|recipient| r.value.recipient == *recipient);
+            if matches {
+                removed += r.size;
+            }
+            !matches
+        });
+        if entry.iter().any(|r| r.value.id == *message_id) {
+            remaining.push(device_id);
+        }
+    }
+    if remaining.is_empty() {
+        index.remove(message_id);
+    } else {
+        index.insert(*message_id, remaining);
+    }
+    removed
 }
 
-impl MemoryStorage {
+/// 移除早于 `threshold` 写入的记录，返回移除的条数
+fn remove_older_than(
+    records: &DashMap<DeviceId, Vec<Stored<Message>>>,
+    index: &RecordIndex,
+    threshold: u64,
+) -> u64 {
+    let mut removed = Vec::new();
+    for mut entry in records.iter_mut() {
+        let device_id = *entry.key();
+        entry.retain(|r| {
+            let keep = r.created_at >= threshold;
+            if !keep {
+                removed.push((r.value.id, device_id));
+            }
+            keep
+        });
+    }
+    // 分组中已不再有该 ID 的记录时从索引中移除该分组
+    for (message_id, device_id) in &removed {
+        let still_present = records
+            .get(device_id)
+            .is_some_and(|entry| entry.iter().any(|r| r.value.id == *message_id));
+        if still_present {
+            continue;
+        }
+        if let Some(mut partitions) = index.get_mut(message_id) {
+            partitions.retain(|d| d != device_id);
+        }
+        index.remove_if(message_id, |_, partitions| partitions.is_empty());
+    }
+    removed.len() as u64
+}
+
+#[derive(Clone)]
+pub struct InMemoryStorage {
+    /// 按接收方分组
+    messages: Arc<DashMap<DeviceId, Vec<Stored<Message>>>>,
+    /// 按发送方分组，与文件和 SQLite 后端一致，恢复时以本机设备 ID 查询
+    pending_messages: Arc<DashMap<DeviceId, Vec<Stored<Message>>>>,
+    audit_logs: Arc<parking_lot::Mutex<Vec<Stored<String>>>>,
+    message_index: Arc<RecordIndex>,
+    pending_index: Arc<RecordIndex>,
+    metadata: Arc<DashMap<String, Vec<u8>>>,
+    receipts: Arc<DashMap<Uuid, Stored<DeliveryReceipt>>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+/// 旧名称，保留以兼容既有代码
+pub type MemoryStorage = InMemoryStorage;
+
+impl InMemoryStorage {
     pub fn new() -> Self {
         Self {
             messages: Arc::new(DashMap::new()),
             pending_messages: Arc::new(DashMap::new()),
-            audit_logs: Arc::new(DashMap::new()),
+            audit_logs: Arc::new(parking_lot::Mutex::new(Vec::new())),
             message_index: Arc::new(DashMap::new()),
             pending_index: Arc::new(DashMap::new()),
+            metadata: Arc::new(DashMap::new()),
+            receipts: Arc::new(DashMap::new()),
+            next_seq: Arc::new(AtomicU64::new(0)),
         }
     }
+
+    fn stamp<T>(&self, value: T, size: u64) -> Stored<T> {
+        Stored {
+            created_at: now_secs(),
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            size,
+            value,
+        }
+    }
+
+    /// 写入消息记录，同一消息 ID 与接收方重复写入时替换旧记录
+    fn insert_indexed(
+        &self,
+        records: &DashMap<DeviceId, Vec<Stored<Message>>>,
+        index: &RecordIndex,
+        device_id: DeviceId,
+        message: &Message,
+    ) -> Result<()> {
+        let record = self.stamp(message.clone(), serialized_size(message)?);
+        remove_indexed(records, index, &message.id, Some(&message.recipient));
+        records.entry(device_id).or_default().push(record);
+        let mut partitions = index.entry(message.id).or_default();
+        if !partitions.contains(&device_id) {
+            partitions.push(device_id);
+        }
+        Ok(())
+    }
 }
 
-impl Default for MemoryStorage {
+impl Default for InMemoryStorage {
     fn default() -> Self {
         Self::new()
     }
 }
 
 #[async_trait]
-impl Storage for MemoryStorage {
+impl Storage for InMemoryStorage {
     async fn save_message(&self, message: &Message) -> Result<()> {
-        let mut entry = self.messages.entry(message.recipient).or_default();
-        entry.push(message.clone());
-        self.message_index.insert(message.id, message.recipient);
-        Ok(())
+        self.insert_indexed(
+            &self.messages,
+            &self.message_index,
+            message.recipient,
+            message,
+        )
     }
 
     async fn get_pending_messages(&self, device_id: &DeviceId) -> Result<Vec<Message>> {
-        match self.messages.get(device_id) {
-            Some(msgs) => Ok(msgs.clone()),
-            None => Ok(Vec::new()),
-        }
+        Ok(self
+            .messages
+            .get(device_id)
+            .map(|records| records.iter().map(|r| r.value.clone()).collect())
+            .unwrap_or_default())
     }
 
     async fn remove_message(&self, message_id: &Uuid) -> Result<()> {
-        if let Some((_, device_id)) = self.message_index.remove(message_id) {
-            if let Some(mut entry) = self.messages.get_mut(&device_id) {
-                entry.retain(|m| m.id != *message_id);
-            }
-        }
+        remove_indexed(&self.messages, &self.message_index, message_id, None);
         Ok(())
     }
 
     async fn save_audit_log(&self, log: String) -> Result<()> {
-        let mut logs = self.audit_logs.entry("default".to_string()).or_default();
-        logs.push(log);
+        let size = log.len() as u64;
+        let record = self.stamp(log, size);
+        self.audit_logs.lock().push(record);
         Ok(())
     }
 
     async fn get_audit_logs(&self, limit: usize) -> Result<Vec<String>> {
-        match self.audit_logs.get("default") {
-            Some(logs) => Ok(logs.iter().rev().take(limit).cloned().collect()),
-            None => Ok(Vec::new()),
-        }
+        Ok(self
+            .audit_logs
+            .lock()
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|r| r.value.clone())
+            .collect())
     }
 
-    async fn cleanup_old_data(&self, _days: u32) -> Result<u64> {
-        Ok(0)
+    async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+    ) -> Result<AuditLogPage> {
+        let logs = self.audit_logs.lock();
+        Ok(AuditLogPage::collect(
+            offset,
+            limit,
+            max_bytes_scanned,
+            logs.iter().rev().skip(offset).map(|r| r.value.clone()),
+        ))
+    }
+
+    async fn cleanup_old_data(&self, days: u32) -> Result<u64> {
+        let threshold = now_secs().saturating_sub(u64::from(days) * 24 * 3600);
+        let mut removed = remove_older_than(&self.messages, &self.message_index, threshold);
+        removed += remove_older_than(&self.pending_messages, &self.pending_index, threshold);
+
+        let receipts = self.receipts.len();
+        self.receipts.retain(|_, r| r.created_at >= threshold);
+        removed += (receipts - self.receipts.len()) as u64;
+
+        let mut logs = self.audit_logs.lock();
+        let before = logs.len();
+        logs.retain(|r| r.created_at >= threshold);
+        removed += (before - logs.len()) as u64;
+        Ok(removed)
     }
 
     async fn save_pending_message(&self, message: &Message) -> Result<()> {
-        let mut entry = self.pending_messages.entry(message.recipient).or_default();
-        entry.push(message.clone());
-        self.pending_index.insert(message.id, message.recipient);
-        Ok(())
+        self.insert_indexed(
+            &self.pending_messages,
+            &self.pending_index,
+            message.sender,
+            message,
+        )
     }
 
     async fn get_pending_messages_for_recovery(
         &self,
         device_id: &DeviceId,
     ) -> Result<Vec<Message>> {
-        match self.pending_messages.get(device_id) {
-            Some(msgs) => Ok(msgs.clone()),
-            None => Ok(Vec::new()),
-        }
+        Ok(self
+            .pending_messages
+            .get(device_id)
+            .map(|records| records.iter().map(|r| r.value.clone()).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_pending_messages_for_recovery_filtered(
+        &self,
+        device_id: &DeviceId,
+        filter: &RecoveryFilter,
+    ) -> Result<Vec<Message>> {
+        Ok(self
+            .pending_messages
+            .get(device_id)
+            .map(|records| filter.apply(records.iter().map(|r| r.value.clone())))
+            .unwrap_or_default())
     }
 
     async fn remove_pending_message(&self, message_id: &Uuid) -> Result<()> {
-        if let Some((_, device_id)) = self.pending_index.remove(message_id) {
-            if let Some(mut entry) = self.pending_messages.get_mut(&device_id) {
-                entry.retain(|m| m.id != *message_id);
-            }
-        }
+        remove_indexed(
+            &self.pending_messages,
+            &self.pending_index,
+            message_id,
+            None,
+        );
         Ok(())
     }
 
-    async fn get_storage_usage(&self) -> Result<u64> {
-        let mut total_size = 0u64;
+    async fn list_messages(&self) -> Result<Vec<Message>> {
+        Ok(self
+            .messages
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .value()
+                    .iter()
+                    .map(|r| r.value.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
 
-        for entry in self.messages.iter() {
-            for msg in entry.value() {
-                total_size += std::mem::size_of_val(msg) as u64;
-            }
-        }
+    async fn list_pending_messages(&self) -> Result<Vec<Message>> {
+        Ok(self
+            .pending_messages
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .value()
+                    .iter()
+                    .map(|r| r.value.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
 
-        for entry in self.pending_messages.iter() {
-            for msg in entry.value() {
-                total_size += std::mem::size_of_val(msg) as u64;
-            }
-        }
+    async fn save_delivery_receipt(&self, receipt: &DeliveryReceipt) -> Result<()> {
+        let size = serde_json::to_vec(receipt)
+            .map(|bytes| bytes.len() as u64)
+            .map_err(Into::<XLinkError>::into)?;
+        let record = self.stamp(receipt.clone(), size);
+        self.receipts.insert(receipt.message_id, record);
+        Ok(())
+    }
 
-        for entry in self.audit_logs.iter() {
-            for log in entry.value() {
-                total_size += log.len() as u64;
-            }
-        }
+    async fn get_delivery_receipt(&self, message_id: &Uuid) -> Result<Option<DeliveryReceipt>> {
+        Ok(self.receipts.get(message_id).map(|r| r.value.clone()))
+    }
 
-        Ok(total_size)
+    async fn save_metadata(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.metadata.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn load_metadata(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.metadata.get(key).map(|entry| entry.value().clone()))
+    }
+
+    async fn get_storage_usage(&self) -> Result<u64> {
+        let records: u64 = self
+            .messages
+            .iter()
+            .chain(self.pending_messages.iter())
+            .flat_map(|entry| entry.value().iter().map(|r| r.size).collect::<Vec<_>>())
+            .sum();
+        let audit: u64 = self.audit_logs.lock().iter().map(|r| r.size).sum();
+        let receipts: u64 = self.receipts.iter().map(|r| r.size).sum();
+        let metadata: u64 = self
+            .metadata
+            .iter()
+            .map(|entry| (entry.key().len() + entry.value().len()) as u64)
+            .sum();
+        Ok(records + audit + receipts + metadata)
     }
 
     async fn cleanup_storage(&self, target_size_bytes: u64) -> Result<u64> {
@@ -131,35 +369,40 @@ impl Storage for MemoryStorage {
             return Ok(0);
         }
 
-        let mut removed_size = 0u64;
-
-        let message_keys: Vec<_> = self.messages.iter().map(|entry| *entry.key()).collect();
-        for device_id in message_keys {
-            if let Some((_, messages)) = self.messages.remove(&device_id) {
-                removed_size += messages.len() as u64 * std::mem::size_of::<Message>() as u64;
+        // 按写入时间从旧到新删除消息与审计日志，待发送消息与元数据保留
+        let mut oldest: Vec<(u64, u64, Victim)> = Vec::new();
+        for entry in self.messages.iter() {
+            for r in entry.value() {
+                oldest.push((
+                    r.created_at,
+                    r.seq,
+                    Victim::Message(r.value.id, r.value.recipient),
+                ));
             }
         }
-
-        let pending_keys: Vec<_> = self
-            .pending_messages
-            .iter()
-            .map(|entry| *entry.key())
-            .collect();
-        for device_id in pending_keys {
-            if let Some((_, messages)) = self.pending_messages.remove(&device_id) {
-                removed_size += messages.len() as u64 * std::mem::size_of::<Message>() as u64;
-            }
+        for r in self.audit_logs.lock().iter() {
+            oldest.push((r.created_at, r.seq, Victim::AuditLog(r.seq)));
         }
+        oldest.sort_by_key(|(created_at, seq, _)| (*created_at, *seq));
 
-        let audit_keys: Vec<_> = self
-            .audit_logs
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
-        for key in audit_keys {
-            if let Some((_, logs)) = self.audit_logs.remove(&key) {
-                removed_size += logs.len() as u64 * 100;
+        let excess = current_size - target_size_bytes;
+        let mut removed_size = 0u64;
+        for (_, _, victim) in oldest {
+            if removed_size >= excess {
+                break;
             }
+            removed_size += match victim {
+                Victim::Message(id, recipient) => {
+                    remove_indexed(&self.messages, &self.message_index, &id, Some(&recipient))
+                }
+                Victim::AuditLog(seq) => {
+                    let mut logs = self.audit_logs.lock();
+                    match logs.iter().position(|r| r.seq == seq) {
+                        Some(position) => logs.remove(position).size,
+                        None => 0,
+                    }
+                }
+            };
         }
 
         Ok(removed_size)
@@ -170,45 +413,6 @@ impl Storage for MemoryStorage {
     }
 
     fn clear_indexes(&self) {
-        let message_keys: Vec<_> = self.messages.iter().map(|entry| *entry.key()).collect();
-        for key in message_keys {
-            self.messages.remove(&key);
-        }
-
-        let pending_keys: Vec<_> = self
-            .pending_messages
-            .iter()
-            .map(|entry| *entry.key())
-            .collect();
-        for key in pending_keys {
-            self.pending_messages.remove(&key);
-        }
-
-        let audit_keys: Vec<_> = self
-            .audit_logs
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
-        for key in audit_keys {
-            self.audit_logs.remove(&key);
-        }
-
-        let index_message_keys: Vec<_> = self
-            .message_index
-            .iter()
-            .map(|entry| *entry.key())
-            .collect();
-        for key in index_message_keys {
-            self.message_index.remove(&key);
-        }
-
-        let index_pending_keys: Vec<_> = self
-            .pending_index
-            .iter()
-            .map(|entry| *entry.key())
-            .collect();
-        for key in index_pending_keys {
-            self.pending_index.remove(&key);
-        }
+        // 索引与数据同在内存中，随实例一同释放，无需单独清理
     }
 }
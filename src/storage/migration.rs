@@ -0,0 +1,144 @@
+//! 存储后端迁移
+//!
+//! 将消息、待发送消息与审计日志从一个存储后端复制到另一个后端。
+//! 迁移是幂等的：目标中已存在的消息（按消息 ID）与审计日志（按内容计数）会被跳过，
+//! 中途失败后重新执行即可从断点继续。源存储不会被修改。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::Storage;
+use std::collections::{HashMap, HashSet};
+
+/// 迁移结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub messages_copied: usize,
+    pub messages_skipped: usize,
+    pub pending_copied: usize,
+    pub pending_skipped: usize,
+    pub audit_logs_copied: usize,
+    pub audit_logs_skipped: usize,
+}
+
+impl MigrationReport {
+    /// 本次迁移实际复制的条目总数
+    pub fn total_copied(&self) -> usize {
+        self.messages_copied + self.pending_copied + self.audit_logs_copied
+    }
+}
+
+/// 将 `from` 中的全部数据迁移到 `to`，完成后校验目标数据完整
+pub async fn migrate(from: &dyn Storage, to: &dyn Storage) -> Result<MigrationReport> {
+    let mut report = MigrationReport::default();
+
+    // 消息
+    let source_messages = from.list_messages().await?;
+    let existing: HashSet<_> = to.list_messages().await?.iter().map(|m| m.id).collect();
+    for message in &source_messages {
+        if existing.contains(&message.id) {
+            report.messages_skipped += 1;
+        } else {
+            to.save_message(message).await?;
+            report.messages_copied += 1;
+        }
+    }
+
+    // 待发送消息
+    let source_pending = from.list_pending_messages().await?;
+    let existing: HashSet<_> = to
+        .list_pending_messages()
+        .await?
+        .iter()
+        .map(|m| m.id)
+        .collect();
+    for message in &source_pending {
+        if existing.contains(&message.id) {
+            report.pending_skipped += 1;
+        } else {
+            to.save_pending_message(message).await?;
+            report.pending_copied += 1;
+        }
+    }
+
+    // 审计日志没有唯一标识，按内容出现次数去重
+    let source_logs = from.get_audit_logs(usize::MAX).await?;
+    let mut existing_logs = count_logs(to.get_audit_logs(usize::MAX).await?);
+    for log in source_logs.iter().rev() {
+        match existing_logs.get_mut(log) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                report.audit_logs_skipped += 1;
+            }
+            _ => {
+                to.save_audit_log(log.clone()).await?;
+                report.audit_logs_copied += 1;
+            }
+        }
+    }
+
+    verify(
+        to,
+        &source_messages.iter().map(|m| m.id).collect::<HashSet<_>>(),
+        &source_pending.iter().map(|m| m.id).collect::<HashSet<_>>(),
+        count_logs(source_logs),
+    )
+    .await?;
+
+    log::info!(
+        "Storage migration completed: {} copied, {} already present",
+        report.total_copied(),
+        report.messages_skipped + report.pending_skipped + report.audit_logs_skipped
+    );
+    Ok(report)
+}
+
+fn count_logs(logs: Vec<String>) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for log in logs {
+        *counts.entry(log).or_insert(0) += 1;
+    }
+    counts
+}
+
+async fn verify(
+    to: &dyn Storage,
+    messages: &HashSet<uuid::Uuid>,
+    pending: &HashSet<uuid::Uuid>,
+    logs: HashMap<String, usize>,
+) -> Result<()> {
+    let migrated: HashSet<_> = to.list_messages().await?.iter().map(|m| m.id).collect();
+    let missing = messages.difference(&migrated).count();
+    if missing > 0 {
+        return Err(XLinkError::storage_migration_failed(
+            format!("{} messages missing from target", missing),
+            file!(),
+        ));
+    }
+
+    let migrated: HashSet<_> = to
+        .list_pending_messages()
+        .await?
+        .iter()
+        .map(|m| m.id)
+        .collect();
+    let missing = pending.difference(&migrated).count();
+    if missing > 0 {
+        return Err(XLinkError::storage_migration_failed(
+            format!("{} pending messages missing from target", missing),
+            file!(),
+        ));
+    }
+
+    let migrated = count_logs(to.get_audit_logs(usize::MAX).await?);
+    let missing: usize = logs
+        .iter()
+        .map(|(log, count)| count.saturating_sub(migrated.get(log).copied().unwrap_or(0)))
+        .sum();
+    if missing > 0 {
+        return Err(XLinkError::storage_migration_failed(
+            format!("{} audit logs missing from target", missing),
+            file!(),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,3 +1,10 @@
 pub mod distributed;
 pub mod file_store;
+pub mod journal;
 pub mod memory_store;
+pub mod migration;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_store;
+pub mod versioned;
+
+pub use migration::{migrate, MigrationReport};
@@ -0,0 +1,544 @@
+//! SQLite 存储后端
+//!
+//! `FileStorage` 每条消息一个文件，崩溃恢复时需要遍历整个目录树重建索引，
+//! 在待发送消息较多的移动设备上很慢。`SqliteStorage` 将消息与待发送消息分别存入
+//! 以 `(recipient, message_id)`、`(sender, message_id)` 为主键的两张表，删除与按设备
+//! 查询都走索引，启动时无需重建任何内存索引。记录内容沿用版本信封编码。
+//! 审计日志另存时间、设备与操作列，按条件查询时直接在 SQL 中过滤。
+
+use crate::core::error::{Result, XLinkError};
+use crate::core::traits::Storage;
+use crate::core::types::{
+    AuditEntry, AuditEntryPage, AuditFilter, AuditLogPage, DeliveryReceipt, DeviceId, Message,
+    RecoveryFilter,
+};
+use crate::storage::file_store::FileStorage;
+use crate::storage::versioned::{self, RecordKind};
+use async_trait::async_trait;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        recipient TEXT NOT NULL,
+        message_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        body BLOB NOT NULL,
+        PRIMARY KEY (recipient, message_id)
+    );
+    CREATE INDEX IF NOT EXISTS messages_by_id ON messages (message_id);
+    CREATE INDEX IF NOT EXISTS messages_by_age ON messages (created_at);
+
+    CREATE TABLE IF NOT EXISTS pending_messages (
+        sender TEXT NOT NULL,
+        message_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        body BLOB NOT NULL,
+        PRIMARY KEY (sender, message_id)
+    );
+    CREATE INDEX IF NOT EXISTS pending_by_id ON pending_messages (message_id);
+    CREATE INDEX IF NOT EXISTS pending_by_age ON pending_messages (created_at);
+
+    CREATE TABLE IF NOT EXISTS audit_logs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        created_at INTEGER NOT NULL,
+        entry TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS delivery_receipts (
+        message_id TEXT PRIMARY KEY,
+        created_at INTEGER NOT NULL,
+        body BLOB NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS receipts_by_age ON delivery_receipts (created_at);
+
+    CREATE TABLE IF NOT EXISTS metadata (
+        key TEXT PRIMARY KEY,
+        value BLOB NOT NULL
+    );
+";
+
+/// 基于 SQLite 的存储实现
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// 打开（不存在时创建）指定路径的数据库文件
+    pub async fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        FileStorage::validate_path(&path)?;
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(Into::<XLinkError>::into)?;
+        }
+
+        let conn = tokio::task::spawn_blocking(move || -> rusqlite::Result<Connection> {
+            let conn = Connection::open(path)?;
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            conn.pragma_update(None, "synchronous", "NORMAL")?;
+            conn.execute_batch(SCHEMA)?;
+            Self::migrate_audit_columns(&conn)?;
+            Ok(conn)
+        })
+        .await
+        .map_err(|e| XLinkError::storage_init_failed("SQLite", &e.to_string(), file!()))?
+        .map_err(|e| XLinkError::storage_init_failed("SQLite", &e.to_string(), file!()))?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 为旧版本创建的审计日志表补充结构化列并回填已有记录，随后建立查询索引
+    fn migrate_audit_columns(conn: &Connection) -> rusqlite::Result<()> {
+        let has_action = conn
+            .prepare("SELECT 1 FROM pragma_table_info('audit_logs') WHERE name = 'action'")?
+            .exists([])?;
+        if !has_action {
+            conn.execute_batch(
+                "ALTER TABLE audit_logs ADD COLUMN timestamp INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE audit_logs ADD COLUMN device_id TEXT;
+                 ALTER TABLE audit_logs ADD COLUMN action TEXT NOT NULL DEFAULT '';",
+            )?;
+            let rows: Vec<(i64, String)> = conn
+                .prepare("SELECT id, entry FROM audit_logs")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+            let mut update = conn.prepare(
+                "UPDATE audit_logs SET timestamp = ?1, device_id = ?2, action = ?3 WHERE id = ?4",
+            )?;
+            for (id, line) in rows {
+                let (timestamp, device_id, action) = Self::audit_columns(&line);
+                update.execute(params![timestamp, device_id, action, id])?;
+            }
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS audit_by_time ON audit_logs (timestamp);
+             CREATE INDEX IF NOT EXISTS audit_by_device ON audit_logs (device_id);",
+        )
+    }
+
+    /// 审计日志的结构化列：时间、设备与操作
+    fn audit_columns(line: &str) -> (i64, Option<String>, String) {
+        let entry = AuditEntry::parse(line);
+        (
+            i64::try_from(entry.timestamp).unwrap_or(i64::MAX),
+            entry.device_id.map(|id| id.to_string()),
+            entry.action,
+        )
+    }
+
+    /// 在阻塞线程池上执行数据库操作
+    async fn with_conn<T, F>(&self, operation: &'static str, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| {
+                XLinkError::storage_read_failed(operation, "SQLite connection poisoned", file!())
+            })?;
+            f(&conn).map_err(|e| Self::map_error(operation, e))
+        })
+        .await
+        .map_err(|e| XLinkError::storage_read_failed(operation, &e.to_string(), file!()))?
+    }
+
+    fn map_error(operation: &str, error: rusqlite::Error) -> XLinkError {
+        let reason = error.to_string();
+        match error.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DiskFull) => XLinkError::storage_full(reason, file!()),
+            _ => XLinkError::storage_write_failed(operation, &reason, file!()),
+        }
+    }
+
+    fn now_secs() -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+
+    fn decode_rows(kind: RecordKind, rows: Vec<Vec<u8>>) -> Result<Vec<Message>> {
+        rows.iter()
+            .map(|body| versioned::decode(kind, body))
+            .collect()
+    }
+
+    async fn query_bodies(
+        &self,
+        operation: &'static str,
+        sql: &'static str,
+        key: Option<String>,
+    ) -> Result<Vec<Vec<u8>>> {
+        self.with_conn(operation, move |conn| {
+            let mut stmt = conn.prepare_cached(sql)?;
+            let rows = stmt.query_map(params_from_iter(key), |row| row.get(0))?;
+            rows.collect()
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn save_message(&self, message: &Message) -> Result<()> {
+        let body = versioned::encode(message)?;
+        let (recipient, id) = (message.recipient.to_string(), message.id.to_string());
+        self.with_conn("save_message", move |conn| {
+            conn.prepare_cached(
+                "INSERT OR REPLACE INTO messages (recipient, message_id, created_at, body)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![recipient, id, Self::now_secs(), body])
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn get_pending_messages(&self, device_id: &DeviceId) -> Result<Vec<Message>> {
+        let rows = self
+            .query_bodies(
+                "get_pending_messages",
+                "SELECT body FROM messages WHERE recipient = ?1 ORDER BY created_at, rowid",
+                Some(device_id.to_string()),
+            )
+            .await?;
+        Self::decode_rows(RecordKind::Message, rows)
+    }
+
+    async fn remove_message(&self, message_id: &Uuid) -> Result<()> {
+        let id = message_id.to_string();
+        self.with_conn("remove_message", move |conn| {
+            conn.prepare_cached("DELETE FROM messages WHERE message_id = ?1")?
+                .execute(params![id])
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn save_audit_log(&self, log: String) -> Result<()> {
+        let (timestamp, device_id, action) = Self::audit_columns(&log);
+        self.with_conn("save_audit_log", move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO audit_logs (created_at, entry, timestamp, device_id, action)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![Self::now_secs(), log, timestamp, device_id, action])
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn query_audit_logs(
+        &self,
+        filter: &AuditFilter,
+        page: usize,
+        page_size: usize,
+    ) -> Result<AuditEntryPage> {
+        const CONDITIONS: &str = "(?1 IS NULL OR timestamp >= ?1)
+             AND (?2 IS NULL OR timestamp < ?2)
+             AND (?3 IS NULL OR device_id = ?3)
+             AND (?4 IS NULL OR instr(action, ?4) > 0)";
+        let since = filter
+            .since
+            .map(|since| i64::try_from(since).unwrap_or(i64::MAX));
+        let until = filter
+            .until
+            .map(|until| i64::try_from(until).unwrap_or(i64::MAX));
+        let device_id = filter.device_id.map(|id| id.to_string());
+        let action = filter.action_contains.clone();
+        let fetch = i64::try_from(page_size).unwrap_or(i64::MAX);
+        let skip = i64::try_from(page.saturating_mul(page_size)).unwrap_or(i64::MAX);
+        let (total, lines) = self
+            .with_conn("query_audit_logs", move |conn| {
+                let total: i64 = conn
+                    .prepare_cached(&format!(
+                        "SELECT COUNT(*) FROM audit_logs WHERE {}",
+                        CONDITIONS
+                    ))?
+                    .query_row(params![since, until, device_id, action], |row| row.get(0))?;
+                let mut stmt = conn.prepare_cached(&format!(
+                    "SELECT entry FROM audit_logs WHERE {} ORDER BY id DESC LIMIT ?5 OFFSET ?6",
+                    CONDITIONS
+                ))?;
+                let lines = stmt
+                    .query_map(
+                        params![since, until, device_id, action, fetch, skip],
+                        |row| row.get::<_, String>(0),
+                    )?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok((total as usize, lines))
+            })
+            .await?;
+        Ok(AuditEntryPage {
+            entries: lines.iter().map(|line| AuditEntry::parse(line)).collect(),
+            total,
+            partial: false,
+        })
+    }
+
+    async fn get_audit_logs(&self, limit: usize) -> Result<Vec<String>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_conn("get_audit_logs", move |conn| {
+            let mut stmt =
+                conn.prepare_cached("SELECT entry FROM audit_logs ORDER BY id DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit], |row| row.get(0))?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_audit_logs_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+        max_bytes_scanned: u64,
+    ) -> Result<AuditLogPage> {
+        // 多取一条用于判断是否还有下一页
+        let fetch = i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX);
+        let skip = i64::try_from(offset).unwrap_or(i64::MAX);
+        let entries: Vec<String> = self
+            .with_conn("get_audit_logs_paged", move |conn| {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT entry FROM audit_logs ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+                )?;
+                let rows = stmt.query_map(params![fetch, skip], |row| row.get(0))?;
+                rows.collect()
+            })
+            .await?;
+        Ok(AuditLogPage::collect(
+            offset,
+            limit,
+            max_bytes_scanned,
+            entries,
+        ))
+    }
+
+    async fn cleanup_old_data(&self, days: u32) -> Result<u64> {
+        let threshold = Self::now_secs() - i64::from(days) * 24 * 3600;
+        self.with_conn("cleanup_old_data", move |conn| {
+            let mut removed = 0u64;
+            for sql in [
+                "DELETE FROM messages WHERE created_at < ?1",
+                "DELETE FROM pending_messages WHERE created_at < ?1",
+                "DELETE FROM audit_logs WHERE created_at < ?1",
+                "DELETE FROM delivery_receipts WHERE created_at < ?1",
+            ] {
+                removed += conn.prepare_cached(sql)?.execute(params![threshold])? as u64;
+            }
+            Ok(removed)
+        })
+        .await
+    }
+
+    async fn save_pending_message(&self, message: &Message) -> Result<()> {
+        let body = versioned::encode(message)?;
+        let (sender, id) = (message.sender.to_string(), message.id.to_string());
+        self.with_conn("save_pending_message", move |conn| {
+            conn.prepare_cached(
+                "INSERT OR REPLACE INTO pending_messages (sender, message_id, created_at, body)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![sender, id, Self::now_secs(), body])
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn get_pending_messages_for_recovery(
+        &self,
+        device_id: &DeviceId,
+    ) -> Result<Vec<Message>> {
+        let rows = self
+            .query_bodies(
+                "get_pending_messages_for_recovery",
+                "SELECT body FROM pending_messages WHERE sender = ?1 ORDER BY created_at, rowid",
+                Some(device_id.to_string()),
+            )
+            .await?;
+        Self::decode_rows(RecordKind::PendingMessage, rows)
+    }
+
+    async fn get_pending_messages_for_recovery_filtered(
+        &self,
+        device_id: &DeviceId,
+        filter: &RecoveryFilter,
+    ) -> Result<Vec<Message>> {
+        // 逐行解码并筛选，取满 `max_count` 条即停止读取；created_at 精度为秒，同一秒内按 rowid 保持写入顺序
+        let (sender, filter) = (device_id.to_string(), *filter);
+        self.with_conn("get_pending_messages_for_recovery_filtered", move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT body FROM pending_messages WHERE sender = ?1 ORDER BY created_at, rowid",
+            )?;
+            let mut rows = stmt.query(params![sender])?;
+            let limit = filter.max_count.unwrap_or(usize::MAX);
+            let mut messages = Vec::new();
+            while messages.len() < limit {
+                let Some(row) = rows.next()? else {
+                    break;
+                };
+                let body: Vec<u8> = row.get(0)?;
+                match versioned::decode::<Message>(RecordKind::PendingMessage, &body) {
+                    Ok(message) if filter.matches(&message) => messages.push(message),
+                    Ok(_) => {}
+                    Err(e) => return Ok(Err(e)),
+                }
+            }
+            Ok(Ok(messages))
+        })
+        .await?
+    }
+
+    async fn remove_pending_message(&self, message_id: &Uuid) -> Result<()> {
+        let id = message_id.to_string();
+        self.with_conn("remove_pending_message", move |conn| {
+            conn.prepare_cached("DELETE FROM pending_messages WHERE message_id = ?1")?
+                .execute(params![id])
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn list_messages(&self) -> Result<Vec<Message>> {
+        let rows = self
+            .query_bodies(
+                "list_messages",
+                "SELECT body FROM messages ORDER BY created_at, rowid",
+                None,
+            )
+            .await?;
+        Self::decode_rows(RecordKind::Message, rows)
+    }
+
+    async fn list_pending_messages(&self) -> Result<Vec<Message>> {
+        let rows = self
+            .query_bodies(
+                "list_pending_messages",
+                "SELECT body FROM pending_messages ORDER BY created_at, rowid",
+                None,
+            )
+            .await?;
+        Self::decode_rows(RecordKind::PendingMessage, rows)
+    }
+
+    async fn save_delivery_receipt(&self, receipt: &DeliveryReceipt) -> Result<()> {
+        let body = serde_json::to_vec(receipt).map_err(Into::<XLinkError>::into)?;
+        let id = receipt.message_id.to_string();
+        self.with_conn("save_delivery_receipt", move |conn| {
+            conn.prepare_cached(
+                "INSERT OR REPLACE INTO delivery_receipts (message_id, created_at, body)
+                 VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![id, Self::now_secs(), body])
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn get_delivery_receipt(&self, message_id: &Uuid) -> Result<Option<DeliveryReceipt>> {
+        let id = message_id.to_string();
+        let body: Option<Vec<u8>> = self
+            .with_conn("get_delivery_receipt", move |conn| {
+                conn.prepare_cached("SELECT body FROM delivery_receipts WHERE message_id = ?1")?
+                    .query_row(params![id], |row| row.get(0))
+                    .optional()
+            })
+            .await?;
+        body.map(|body| serde_json::from_slice(&body).map_err(Into::<XLinkError>::into))
+            .transpose()
+    }
+
+    async fn save_metadata(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        let key = key.to_string();
+        self.with_conn("save_metadata", move |conn| {
+            conn.prepare_cached("INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)")?
+                .execute(params![key, value])
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn load_metadata(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let key = key.to_string();
+        self.with_conn("load_metadata", move |conn| {
+            conn.prepare_cached("SELECT value FROM metadata WHERE key = ?1")?
+                .query_row(params![key], |row| row.get(0))
+                .optional()
+        })
+        .await
+    }
+
+    async fn get_storage_usage(&self) -> Result<u64> {
+        self.with_conn("get_storage_usage", |conn| {
+            let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok((page_count * page_size) as u64)
+        })
+        .await
+    }
+
+    async fn cleanup_storage(&self, target_size_bytes: u64) -> Result<u64> {
+        let current_size = self.get_storage_usage().await?;
+        if current_size <= target_size_bytes {
+            return Ok(0);
+        }
+
+        // 按写入时间从旧到新删除消息与审计日志（待发送消息保留），删除量按记录大小估算，
+        // 最后收缩数据库文件
+        let excess = current_size - target_size_bytes;
+        self.with_conn("cleanup_storage", move |conn| {
+            let mut oldest: Vec<(i64, &'static str, i64, u64)> = Vec::new();
+            for (table, sql) in [
+                (
+                    "messages",
+                    "SELECT rowid, created_at, LENGTH(body) FROM messages",
+                ),
+                (
+                    "audit_logs",
+                    "SELECT rowid, created_at, LENGTH(entry) FROM audit_logs",
+                ),
+            ] {
+                let mut stmt = conn.prepare(sql)?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(1)?,
+                        table,
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(2)? as u64,
+                    ))
+                })?;
+                for row in rows {
+                    oldest.push(row?);
+                }
+            }
+            oldest.sort_by_key(|(created_at, _, rowid, _)| (*created_at, *rowid));
+
+            let mut removed_size = 0u64;
+            for (_, table, rowid, size) in oldest {
+                if removed_size >= excess {
+                    break;
+                }
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE rowid = ?1", table),
+                    params![rowid],
+                )?;
+                removed_size += size;
+            }
+            conn.execute_batch("VACUUM")?;
+            Ok(removed_size)
+        })
+        .await
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn clear_indexes(&self) {
+        // 索引由数据库维护，没有需要释放的内存索引
+    }
+}
@@ -0,0 +1,91 @@
+//! 持久化记录的版本信封
+//!
+//! 写入磁盘的消息、待发送消息与加密状态统一包装为 `{ "v": 版本, "data": 记录 }`。
+//! 读取时按版本依次执行迁移钩子升级到当前版本，再反序列化为当前结构；
+//! 未知版本返回明确的 `serialization_failed`，而不是字段缺失之类的晦涩错误。
+//!
+//! 版本历史：
+//! - v1：无信封的裸 JSON 记录（引入信封之前写入的文件）
+//! - v2：引入信封，记录结构与 v1 相同
+
+use crate::core::error::{Result, XLinkError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 当前写入的记录版本
+pub const CURRENT_RECORD_VERSION: u16 = 2;
+
+/// 持久化记录的种类，迁移钩子按种类区分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Message,
+    PendingMessage,
+    CryptoState,
+}
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a, T> {
+    v: u16,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    v: u16,
+    data: Value,
+}
+
+/// 以当前版本编码记录
+pub fn encode<T: Serialize>(record: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(&EnvelopeRef {
+        v: CURRENT_RECORD_VERSION,
+        data: record,
+    })
+    .map_err(Into::<XLinkError>::into)
+}
+
+/// 解码任意已知版本的记录，旧版本先升级到当前版本
+pub fn decode<T: DeserializeOwned>(kind: RecordKind, bytes: &[u8]) -> Result<T> {
+    let value: Value = serde_json::from_slice(bytes).map_err(Into::<XLinkError>::into)?;
+    let is_envelope = value
+        .as_object()
+        .is_some_and(|object| object.len() == 2 && object.contains_key("v"));
+    let (mut version, mut data) = if is_envelope {
+        let envelope: Envelope = serde_json::from_value(value).map_err(Into::<XLinkError>::into)?;
+        (envelope.v, envelope.data)
+    } else {
+        (1, value)
+    };
+
+    if version == 0 || version > CURRENT_RECORD_VERSION {
+        return Err(XLinkError::serialization_failed(
+            format!("decode {:?} record", kind),
+            format!(
+                "unknown record version {} (supported 1..={})",
+                version, CURRENT_RECORD_VERSION
+            ),
+            file!(),
+        ));
+    }
+    while version < CURRENT_RECORD_VERSION {
+        data = upgrade(kind, version, data)?;
+        version += 1;
+    }
+    serde_json::from_value(data).map_err(Into::<XLinkError>::into)
+}
+
+/// 迁移钩子：将 `from` 版本的记录升级到下一版本
+///
+/// 记录结构变化时在此追加分支，并提升 `CURRENT_RECORD_VERSION`
+fn upgrade(kind: RecordKind, from: u16, data: Value) -> Result<Value> {
+    match (kind, from) {
+        // v1 -> v2 只引入了信封，记录本身不变
+        (_, 1) => Ok(data),
+        _ => Err(XLinkError::serialization_failed(
+            format!("upgrade {:?} record", kind),
+            format!("no migration from version {}", from),
+            file!(),
+        )),
+    }
+}
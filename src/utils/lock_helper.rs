@@ -1,14 +1,31 @@
 //! 锁操作辅助工具
 //!
 //! 提供安全的锁操作封装，避免直接使用 `lock().unwrap()` 导致的 panic 风险。
+//!
+//! # 锁层级
+//!
+//! 需要同时持有多把锁时，必须按 [`lock_order`] 中层级从低到高的顺序获取，
+//! 持有高层级锁时不得再获取同级或更低层级的锁。[`lock_ordered`] 在 debug 构建中
+//! 按线程跟踪已持有的层级，违反顺序时立即 panic；release 构建不做检查。
+//!
+//! 层级检查只覆盖同步锁（`std::sync::Mutex`）。异步锁（`tokio::sync::Mutex`/`RwLock`，
+//! 如心跳、发现与群组广播结果）的守卫可能跨 `.await` 在线程间迁移，无法按线程跟踪，
+//! 约定如下：
+//! - 持有同步锁时不得 `.await`，也不得获取异步锁；
+//! - 异步锁之间同样按获取顺序从外到内：发现管理器 → 心跳管理器 → 群组广播结果；
+//! - 回调（网络变化、事件处理器）必须在释放触发方的锁之后调用。
 
-use std::sync::{Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 /// 安全获取 Mutex 锁
 ///
 /// 返回 `Result` 类型而非直接 panic，允许调用者处理锁中毒情况。
 #[inline]
-pub fn lock_mutex<T>(lock: &Mutex<T>) -> Result<std::sync::MutexGuard<'_, T>, PoisonError<std::sync::MutexGuard<'_, T>>> {
+pub fn lock_mutex<T>(
+    lock: &Mutex<T>,
+) -> Result<std::sync::MutexGuard<'_, T>, PoisonError<std::sync::MutexGuard<'_, T>>> {
     lock.lock()
 }
 
@@ -16,7 +33,9 @@ pub fn lock_mutex<T>(lock: &Mutex<T>) -> Result<std::sync::MutexGuard<'_, T>, Po
 ///
 /// 返回 `Result` 类型，允许调用者处理锁中毒情况。
 #[inline]
-pub fn read_rwlock<T>(lock: &RwLock<T>) -> Result<RwLockReadGuard<'_, T>, PoisonError<RwLockReadGuard<'_, T>>> {
+pub fn read_rwlock<T>(
+    lock: &RwLock<T>,
+) -> Result<RwLockReadGuard<'_, T>, PoisonError<RwLockReadGuard<'_, T>>> {
     lock.read()
 }
 
@@ -24,7 +43,9 @@ pub fn read_rwlock<T>(lock: &RwLock<T>) -> Result<RwLockReadGuard<'_, T>, Poison
 ///
 /// 返回 `Result` 类型，允许调用者处理锁中毒情况。
 #[inline]
-pub fn write_rwlock<T>(lock: &RwLock<T>) -> Result<RwLockWriteGuard<'_, T>, PoisonError<RwLockWriteGuard<'_, T>>> {
+pub fn write_rwlock<T>(
+    lock: &RwLock<T>,
+) -> Result<RwLockWriteGuard<'_, T>, PoisonError<RwLockWriteGuard<'_, T>>> {
     lock.write()
 }
 
@@ -52,6 +73,116 @@ pub fn try_write_rwlock<T>(lock: &RwLock<T>) -> Option<RwLockWriteGuard<'_, T>>
     lock.try_write().ok()
 }
 
+/// 锁在层级中的位置，`rank` 越小越先获取
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockLevel {
+    pub rank: u16,
+    pub name: &'static str,
+}
+
+impl LockLevel {
+    pub const fn new(rank: u16, name: &'static str) -> Self {
+        Self { rank, name }
+    }
+}
+
+/// SDK 内同步锁的层级
+pub mod lock_order {
+    use super::LockLevel;
+
+    /// 流会话表
+    pub const SESSIONS: LockLevel = LockLevel::new(10, "sessions");
+    /// 流控制通道表
+    pub const CONTROLLERS: LockLevel = LockLevel::new(20, "controllers");
+    /// 流发送进度表
+    pub const PROGRESS: LockLevel = LockLevel::new(30, "progress");
+    /// 可续传传输表
+    pub const TRANSFERS: LockLevel = LockLevel::new(35, "transfers");
+    /// 码率控制器表
+    pub const BITRATE_CONTROLLERS: LockLevel = LockLevel::new(40, "bitrate_controllers");
+    /// 网络监控器；叶子锁，持有期间不得获取其他锁或调用网络变化回调
+    pub const NETWORK_MONITOR: LockLevel = LockLevel::new(50, "network_monitor");
+}
+
+thread_local! {
+    // 当前线程已持有的锁层级，仅 debug 构建使用
+    static HELD_LOCKS: RefCell<Vec<LockLevel>> = const { RefCell::new(Vec::new()) };
+}
+
+fn enter_level(level: LockLevel) {
+    if cfg!(debug_assertions) {
+        HELD_LOCKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(top) = held.iter().find(|h| h.rank >= level.rank) {
+                let (top, level) = (top.name, level.name);
+                drop(held);
+                panic!("Lock order violation: acquiring `{level}` while holding `{top}`");
+            }
+            held.push(level);
+        });
+    }
+}
+
+fn exit_level(level: LockLevel) {
+    if cfg!(debug_assertions) {
+        HELD_LOCKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(pos) = held.iter().rposition(|h| *h == level) {
+                held.remove(pos);
+            }
+        });
+    }
+}
+
+/// 按层级获取的锁守卫，释放时退出该层级
+pub struct OrderedGuard<G> {
+    guard: G,
+    level: LockLevel,
+}
+
+impl<G> Deref for OrderedGuard<G>
+where
+    G: Deref,
+{
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G> DerefMut for OrderedGuard<G>
+where
+    G: DerefMut,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<G> Drop for OrderedGuard<G> {
+    fn drop(&mut self) {
+        exit_level(self.level);
+    }
+}
+
+/// 按层级获取 Mutex 锁
+///
+/// debug 构建中，当前线程已持有同级或更高层级的锁时 panic。
+pub fn lock_ordered<T>(
+    lock: &Mutex<T>,
+    level: LockLevel,
+) -> Result<OrderedGuard<MutexGuard<'_, T>>, PoisonError<MutexGuard<'_, T>>> {
+    enter_level(level);
+    match lock.lock() {
+        Ok(guard) => Ok(OrderedGuard { guard, level }),
+        Err(e) => {
+            exit_level(level);
+            Err(e)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,4 +228,28 @@ mod tests {
             assert_eq!(*guard, 200);
         }
     }
+
+    #[test]
+    fn test_lock_ordered_allows_ascending_order() {
+        let outer = Mutex::new(1);
+        let inner = Mutex::new(2);
+        let a = lock_ordered(&outer, lock_order::SESSIONS).unwrap();
+        let b = lock_ordered(&inner, lock_order::BITRATE_CONTROLLERS).unwrap();
+        assert_eq!(*a + *b, 3);
+        drop(b);
+        drop(a);
+
+        // 释放后可以重新从任意层级开始
+        let _b = lock_ordered(&inner, lock_order::BITRATE_CONTROLLERS).unwrap();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Lock order violation")]
+    fn test_lock_ordered_panics_on_inversion() {
+        let outer = Mutex::new(());
+        let inner = Mutex::new(());
+        let _monitor = lock_ordered(&outer, lock_order::NETWORK_MONITOR).unwrap();
+        let _controllers = lock_ordered(&inner, lock_order::BITRATE_CONTROLLERS).unwrap();
+    }
 }
@@ -15,13 +15,15 @@ use xlink::capability::manager::CapabilityManager;
 use xlink::core::error::Result;
 use xlink::core::traits::{Channel as ChannelTrait, MessageHandler};
 use xlink::core::types::{
-    ChannelType, DeviceCapabilities, DeviceId, DeviceType, Message, MessagePayload, NetworkType,
+    ChannelType, DeviceCapabilities, DeviceId, DeviceType, GroupKeyConfig, Message, MessagePayload,
+    NetworkType, RoutingConfig,
 };
 use xlink::XLink;
 
 // We need these imports for the TestSdkBuilder
 
 use xlink::channels::memory::MemoryChannel;
+use xlink::storage::memory_store::InMemoryStorage;
 
 // Define NoOpMessageHandler for testing
 pub struct NoOpMessageHandler;
@@ -180,11 +182,23 @@ impl TestSdkBuilder {
         let sdk = if let Some(storage_path) = self.storage_path {
             XLink::with_storage_path(self.device_capabilities, channels, storage_path).await?
         } else {
-            XLink::new(self.device_capabilities, channels).await?
+            // 默认使用内存存储，避免测试之间通过 storage 目录相互影响
+            XLink::with_storage(
+                self.device_capabilities,
+                channels,
+                Arc::new(InMemoryStorage::new()),
+            )
+            .await?
         };
 
-        // Note: We would need to expose routing strategy setting in the actual SDK
-        // For now, this is a placeholder
+        // 测试环境中的对端通常没有真实的通道状态，找不到路由时自动伪造
+        sdk.set_routing_config(RoutingConfig {
+            auto_seed_channel_state: true,
+        });
+        // 测试中的成员大多没有真实公钥，建群时为其注册占位公钥
+        sdk.set_group_key_config(GroupKeyConfig {
+            placeholder_keys_for_testing: true,
+        });
 
         Ok(sdk)
     }
@@ -447,8 +461,12 @@ impl TestEnvironment {
 
 /// Clean up test resources
 pub async fn cleanup_test_environment(env: TestEnvironment) {
-    // Add any cleanup logic here
-    drop(env);
+    // 不再被其他任务持有的设备走优雅关闭，等待在途发送完成
+    for device in env.devices {
+        if let Ok(sdk) = Arc::try_unwrap(device) {
+            sdk.shutdown().await;
+        }
+    }
 }
 
 /// Reset global test state
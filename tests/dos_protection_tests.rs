@@ -8,10 +8,19 @@ use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
-use xlink::core::types::{DeviceId, MessagePayload};
+use xlink::channels::memory::MemoryChannel;
+use xlink::core::clock::MockClock;
+use xlink::core::types::{
+    AppQueueConfig, ChannelType, DeviceId, Message, MessagePayload, MessagePriority,
+    PeerSendConcurrency, PriorityRateLimits, RateLimitConfig, RoutingConfig,
+};
+use xlink::router::send_queue::SendQueueConfig;
+use xlink::storage::memory_store::MemoryStorage;
 use xlink::XLink;
 
-use crate::common::{test_device_id, NetworkSimulator, TestSdkBuilder};
+use crate::common::{
+    test_device_capabilities, test_device_id, NetworkSimulator, NoOpMessageHandler, TestSdkBuilder,
+};
 
 mod common;
 
@@ -490,3 +499,519 @@ async fn test_uat_dos_protection_comprehensive() {
 
     println!("\nUAT comprehensive DoS protection test completed successfully!");
 }
+
+#[tokio::test]
+async fn test_critical_priority_not_starved_by_low_priority_burst() {
+    // SEC-PEN-004: 低优先级流量被限流时，关键消息仍有独立配额
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_priority_rate_limits(PriorityRateLimits {
+        low: 5,
+        ..Default::default()
+    });
+    let target_device = test_device_id();
+
+    // 发送端：耗尽 Low 配额
+    for i in 0..5 {
+        sdk.send_with_priority(
+            target_device,
+            MessagePayload::Text(format!("telemetry {}", i)),
+            MessagePriority::Low,
+        )
+        .await
+        .unwrap();
+    }
+    let err = sdk
+        .send_with_priority(
+            target_device,
+            MessagePayload::Text("telemetry overflow".to_string()),
+            MessagePriority::Low,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+    assert!(sdk
+        .send_with_priority(
+            target_device,
+            MessagePayload::Text("alert".to_string()),
+            MessagePriority::Critical,
+        )
+        .await
+        .is_ok());
+
+    // 接收端：同一发送方的 Low 消息被限流，Critical 消息照常处理
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let message_with = |priority| {
+        let mut message = Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text("inbound".to_string()),
+        );
+        message.priority = priority;
+        message
+    };
+    for _ in 0..5 {
+        handler
+            .handle_message(message_with(MessagePriority::Low))
+            .await
+            .unwrap();
+    }
+    assert!(handler
+        .handle_message(message_with(MessagePriority::Low))
+        .await
+        .is_err());
+    assert!(handler
+        .handle_message(message_with(MessagePriority::Critical))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_send_queue_orders_by_priority_and_ages_waiters() {
+    // SEC-PEN-009: 通道名额不足时按优先级发送，排队过久的普通消息逐级提升，不被关键消息饿死
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 30));
+    let sdk = Arc::new(
+        TestSdkBuilder::new()
+            .with_channel(channel.clone())
+            .build()
+            .await
+            .unwrap(),
+    );
+    let router = sdk.router();
+    let target_device = test_device_id();
+    let send = |priority: MessagePriority| {
+        let sdk = sdk.clone();
+        tokio::spawn(async move {
+            sdk.send_prioritized(
+                target_device,
+                MessagePayload::Text(format!("{:?}", priority)),
+                priority,
+            )
+            .await
+            .unwrap()
+        })
+    };
+    // 依次入队，保证入队顺序确定：返回发送任务与等待其进入队列的 future
+    let enqueue = |priority: MessagePriority| {
+        let router = router.clone();
+        let queued = router.send_queue_depth(ChannelType::Lan).1;
+        let handle = send(priority);
+        let entered = async move {
+            while router.send_queue_depth(ChannelType::Lan).1 == queued {
+                tokio::task::yield_now().await;
+            }
+        };
+        (handle, entered)
+    };
+    let occupy = || {
+        let router = router.clone();
+        let blocker = send(MessagePriority::Low);
+        let started = async move {
+            while router.send_queue_depth(ChannelType::Lan).0 == 0 {
+                tokio::task::yield_now().await;
+            }
+        };
+        (blocker, started)
+    };
+
+    // 关闭排队提升：严格按优先级，同级按入队顺序
+    sdk.set_send_queue_config(SendQueueConfig {
+        max_in_flight: 1,
+        aging_after_ms: 0,
+    });
+    let (blocker, started) = occupy();
+    started.await;
+    let mut waiters = Vec::new();
+    for priority in [
+        MessagePriority::Normal,
+        MessagePriority::Low,
+        MessagePriority::Critical,
+        MessagePriority::High,
+    ] {
+        let (waiter, entered) = enqueue(priority);
+        entered.await;
+        waiters.push(waiter);
+    }
+    blocker.await.unwrap();
+    let mut records = Vec::new();
+    for waiter in waiters {
+        records.push(waiter.await.unwrap());
+    }
+    records.sort_by_key(|record| record.sequence);
+    let order: Vec<_> = records.iter().map(|record| record.priority).collect();
+    assert_eq!(
+        order,
+        vec![
+            MessagePriority::Critical,
+            MessagePriority::High,
+            MessagePriority::Normal,
+            MessagePriority::Low,
+        ]
+    );
+    assert!(records
+        .iter()
+        .all(|record| record.effective_priority == record.priority));
+
+    // 开启排队提升：持续涌入的关键消息无法让普通消息一直等待
+    sdk.set_send_queue_config(SendQueueConfig {
+        max_in_flight: 1,
+        aging_after_ms: 40,
+    });
+    let (blocker, started) = occupy();
+    started.await;
+    let (normal, entered) = enqueue(MessagePriority::Normal);
+    entered.await;
+    let mut criticals = Vec::new();
+    for _ in 0..10 {
+        let (critical, entered) = enqueue(MessagePriority::Critical);
+        entered.await;
+        criticals.push(critical);
+    }
+    blocker.await.unwrap();
+    let normal = normal.await.unwrap();
+    let mut last_critical = 0;
+    for critical in criticals {
+        last_critical = last_critical.max(critical.await.unwrap().sequence);
+    }
+    assert_eq!(normal.effective_priority, MessagePriority::Critical);
+    assert!(normal.waited() >= Duration::from_millis(80));
+    assert!(normal.sequence < last_critical);
+    assert_eq!(channel.get_sent_messages().await.len(), 17);
+}
+
+#[tokio::test]
+async fn test_total_send_rate_spans_priorities_with_critical_reserve() {
+    // SEC-PEN-010: 出站总量跨优先级计数，Critical 消息在独立额度内不计入总量
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_rate_limit_config(RateLimitConfig {
+        per_device_send_per_sec: 3,
+        ..Default::default()
+    });
+    sdk.set_priority_rate_limits(PriorityRateLimits {
+        critical_reserve: 2,
+        ..Default::default()
+    });
+    let target_device = test_device_id();
+    let send = |priority: MessagePriority| {
+        sdk.send_with_priority(
+            target_device,
+            MessagePayload::Text(format!("{:?}", priority)),
+            priority,
+        )
+    };
+
+    // 每个优先级都未超过各自的上限，但合计超过总量
+    send(MessagePriority::Normal).await.unwrap();
+    send(MessagePriority::Normal).await.unwrap();
+    send(MessagePriority::High).await.unwrap();
+    assert_eq!(send(MessagePriority::Low).await.unwrap_err().code().0, 104);
+
+    // Critical 消息先用独立额度，用完后同样受总量限制
+    send(MessagePriority::Critical).await.unwrap();
+    send(MessagePriority::Critical).await.unwrap();
+    assert_eq!(
+        send(MessagePriority::Critical).await.unwrap_err().code().0,
+        104
+    );
+
+    // 新窗口恢复
+    sleep(Duration::from_millis(1100)).await;
+    send(MessagePriority::Low).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rate_limit_window_follows_injected_clock() {
+    // SEC-PEN-011: 限流窗口按注入的时钟重置，推进模拟时钟即可进入新窗口，无需真实等待
+    let clock = Arc::new(MockClock::new());
+    let sdk = XLink::builder(test_device_capabilities())
+        .channel(Arc::new(MemoryChannel::new(
+            Arc::new(NoOpMessageHandler),
+            0,
+        )))
+        .storage(Arc::new(MemoryStorage::new()))
+        .rate_limit(RateLimitConfig {
+            per_sender_per_sec: 2,
+            per_device_send_per_sec: 2,
+            burst: 0,
+        })
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    let target_device = test_device_id();
+    let send = || sdk.send(target_device, MessagePayload::Text("tick".to_string()));
+
+    send().await.unwrap();
+    send().await.unwrap();
+    assert_eq!(send().await.unwrap_err().code().0, 104);
+
+    // 时钟未推进时窗口不会自行重置
+    sleep(Duration::from_millis(1100)).await;
+    assert_eq!(send().await.unwrap_err().code().0, 104);
+
+    clock.advance(Duration::from_secs(1));
+    send().await.unwrap();
+
+    // 入站限流同样以注入的时钟计时
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let inbound = || {
+        Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text("inbound".to_string()),
+        )
+    };
+    handler.handle_message(inbound()).await.unwrap();
+    handler.handle_message(inbound()).await.unwrap();
+    assert!(handler.handle_message(inbound()).await.is_err());
+    clock.advance(Duration::from_millis(1100));
+    handler.handle_message(inbound()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_per_peer_send_concurrency_cap() {
+    // SEC-PEN-005: 同一对端的并发发送不超过配置的在途上限
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 20));
+    let sdk = Arc::new(
+        TestSdkBuilder::new()
+            .with_channel(channel.clone())
+            .build()
+            .await
+            .unwrap(),
+    );
+    sdk.set_priority_rate_limits(PriorityRateLimits {
+        normal: 1000,
+        ..Default::default()
+    });
+    sdk.set_peer_send_concurrency(PeerSendConcurrency {
+        max_in_flight: 4,
+        queue_timeout_ms: 10_000,
+    });
+    let target_device = test_device_id();
+
+    let handles: Vec<_> = (0..100)
+        .map(|i| {
+            let sdk = sdk.clone();
+            tokio::spawn(async move {
+                sdk.send(target_device, MessagePayload::Text(format!("sync {}", i)))
+                    .await
+            })
+        })
+        .collect();
+
+    // 发送过程中在途数始终不超过上限
+    let mut observed_max = 0;
+    while !handles.iter().all(|h| h.is_finished()) {
+        let in_flight = sdk
+            .metrics_report()
+            .in_flight_sends
+            .get(&target_device)
+            .copied()
+            .unwrap_or(0);
+        assert!(in_flight <= 4, "in-flight sends {} exceed cap", in_flight);
+        observed_max = observed_max.max(in_flight);
+        sleep(Duration::from_millis(5)).await;
+    }
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    assert!(observed_max > 1);
+    assert_eq!(channel.get_sent_messages().await.len(), 100);
+    assert!(sdk.metrics_report().in_flight_sends.is_empty());
+}
+
+#[tokio::test]
+async fn test_queued_send_times_out_when_peer_saturated() {
+    // SEC-PEN-006: 排队超过等待时间的发送返回超时错误
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 300));
+    let sdk = Arc::new(
+        TestSdkBuilder::new()
+            .with_channel(channel)
+            .build()
+            .await
+            .unwrap(),
+    );
+    sdk.set_peer_send_concurrency(PeerSendConcurrency {
+        max_in_flight: 1,
+        queue_timeout_ms: 50,
+    });
+    let target_device = test_device_id();
+
+    let first = {
+        let sdk = sdk.clone();
+        tokio::spawn(async move {
+            sdk.send(target_device, MessagePayload::Text("slow".to_string()))
+                .await
+        })
+    };
+    sleep(Duration::from_millis(20)).await;
+
+    let err = sdk
+        .send(target_device, MessagePayload::Text("queued".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 101);
+
+    // 其他对端不受影响
+    sdk.send(test_device_id(), MessagePayload::Text("other".to_string()))
+        .await
+        .unwrap();
+    first.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_configurable_rate_limits_and_disable() {
+    // SEC-PEN-007: 收发速率上限可配置（含突发额度），速率为 0 时关闭限流
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_rate_limit_config(RateLimitConfig {
+        per_sender_per_sec: 3,
+        per_device_send_per_sec: 2,
+        burst: 1,
+    });
+    let target_device = test_device_id();
+
+    // 出站：速率 2 + 突发 1
+    for i in 0..3 {
+        sdk.send(target_device, MessagePayload::Text(format!("sync {}", i)))
+            .await
+            .unwrap();
+    }
+    let err = sdk
+        .send(target_device, MessagePayload::Text("overflow".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+
+    // 入站：速率 3 + 突发 1
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let inbound = || {
+        Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text("inbound".to_string()),
+        )
+    };
+    for _ in 0..4 {
+        handler.handle_message(inbound()).await.unwrap();
+    }
+    assert!(handler.handle_message(inbound()).await.is_err());
+
+    // 速率为 0 关闭限流，远超默认的每秒 100 条也不被拒绝
+    sdk.set_rate_limit_config(RateLimitConfig {
+        per_sender_per_sec: 0,
+        per_device_send_per_sec: 0,
+        burst: 0,
+    });
+    for i in 0..150 {
+        sdk.send(target_device, MessagePayload::Text(format!("bulk {}", i)))
+            .await
+            .unwrap();
+    }
+    // 接收队列容量有限，入站消息边收边取
+    for _ in 0..150 {
+        handler.handle_message(inbound()).await.unwrap();
+        sdk.receive().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_app_queue_backpressure_rejects_instead_of_dropping() {
+    // SEC-PEN-008: 应用接收队列满时对通道返回资源耗尽错误，取走消息后恢复接收
+    let sdk = XLink::with_app_queue(
+        test_device_capabilities(),
+        vec![],
+        Arc::new(MemoryStorage::new()),
+        AppQueueConfig {
+            capacity: 2,
+            send_timeout_ms: 50,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(sdk.app_queue_config().capacity, 2);
+    assert!(sdk.try_receive().is_none());
+
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let inbound = |text: &str| {
+        Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text(text.to_string()),
+        )
+    };
+    handler.handle_message(inbound("first")).await.unwrap();
+    handler.handle_message(inbound("second")).await.unwrap();
+    let err = handler
+        .handle_message(inbound("overflow"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+
+    let first = sdk.try_receive().unwrap();
+    assert_eq!(first.payload, MessagePayload::Text("first".to_string()));
+    handler.handle_message(inbound("third")).await.unwrap();
+    let second = sdk.try_receive().unwrap();
+    assert_eq!(second.payload, MessagePayload::Text("second".to_string()));
+    let third = sdk.try_receive().unwrap();
+    assert_eq!(third.payload, MessagePayload::Text("third".to_string()));
+    assert!(sdk.try_receive().is_none());
+}
+
+#[tokio::test]
+async fn test_app_queue_backpressure_requeues_rest_of_ordered_batch() {
+    // SEC-PEN-012: 有序消息成批交付时接收队列满，剩余消息放回重排缓冲，重发后按序交付
+    let sdk = XLink::with_app_queue(
+        test_device_capabilities(),
+        vec![],
+        Arc::new(MemoryStorage::new()),
+        AppQueueConfig {
+            capacity: 2,
+            send_timeout_ms: 50,
+        },
+    )
+    .await
+    .unwrap();
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let messages: Vec<Message> = (0..4)
+        .map(|seq| {
+            let mut message = Message::new(
+                sender,
+                sdk.device_id(),
+                MessagePayload::Text(format!("msg-{}", seq)),
+            );
+            message.require_ordered = true;
+            message.sequence = Some(seq);
+            message
+        })
+        .collect();
+
+    for message in &messages[1..] {
+        handler.handle_message(message.clone()).await.unwrap();
+    }
+    // 序号 0 到达后一次交出 0..4，队列只能容纳前两条
+    let err = handler
+        .handle_message(messages[0].clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+    for expected in 0..2 {
+        assert_eq!(sdk.try_receive().unwrap().sequence, Some(expected));
+    }
+    assert!(sdk.try_receive().is_none());
+
+    // 发送方重发未交付的消息，剩余消息按序补交且不重复
+    handler.handle_message(messages[2].clone()).await.unwrap();
+    handler.handle_message(messages[3].clone()).await.unwrap();
+    for expected in 2..4 {
+        assert_eq!(sdk.try_receive().unwrap().sequence, Some(expected));
+    }
+    assert!(sdk.try_receive().is_none());
+}
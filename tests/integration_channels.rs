@@ -1,17 +1,32 @@
 //! Integration tests for all communication channels
 //!
-//! This module combines tests for Bluetooth, WiFi Direct, Remote (ntfy),
-//! and channel switching mechanisms.
+//! This module combines tests for Bluetooth, WiFi Direct, Remote (ntfy), WebSocket,
+//! QUIC, and channel switching mechanisms.
 
 mod common;
 
-use crate::common::{test_device_capabilities, test_device_id, NetworkSimulator, TestSdkBuilder};
+use crate::common::{
+    test_device_capabilities, test_device_id, NetworkSimulator, NoOpMessageHandler, TestSdkBuilder,
+};
+use futures::StreamExt;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use xlink::channels::bluetooth::BluetoothChannel;
+use xlink::channels::memory::MemoryChannel;
 use xlink::channels::remote::RemoteChannel;
+use xlink::channels::websocket::WebSocketChannel;
 use xlink::channels::wifi::WiFiDirectChannel;
-use xlink::core::types::{ChannelType, DeviceCapabilities, DeviceType, MessagePayload};
+use xlink::core::error::{RetrySuggestion, XLinkError};
+use xlink::core::events::SdkEvent;
+use xlink::core::traits::{Channel, MessageHandler, Storage};
+use xlink::core::types::{
+    ChannelState, ChannelType, ChannelWarmupConfig, DeliveryMode, DeviceCapabilities, DeviceId,
+    DeviceType, Message, MessagePayload, ReconnectConfig, ReorderBufferConfig,
+    ReorderOverflowPolicy, RoutingConfig, SendRetryConfig,
+};
+use xlink::storage::memory_store::MemoryStorage;
+use xlink::XLink;
 
 // ==================== Bluetooth LE Tests ====================
 
@@ -213,3 +228,1204 @@ async fn test_channel_switching_performance() {
         elapsed
     );
 }
+
+// ==================== Ordered Delivery Tests ====================
+
+#[tokio::test]
+async fn test_ordered_send_rejects_unordered_channel() {
+    // IT-ORD-001: 只有无序通道时，有序发送返回错误而不是静默乱序
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    assert!(!channel.is_ordered());
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+
+    let err = sdk
+        .send_ordered(test_device_id(), MessagePayload::Text("one".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 205);
+    assert!(channel.get_sent_messages().await.is_empty());
+
+    // 普通发送仍可走无序通道
+    sdk.send(test_device_id(), MessagePayload::Text("two".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_ordered_send_assigns_sequences() {
+    // IT-ORD-002: 有序发送走有序通道并按接收方分配递增序号
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0).with_ordering(true));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    let peer = test_device_id();
+
+    for i in 0..3 {
+        sdk.send_ordered(peer, MessagePayload::Text(format!("msg-{}", i)))
+            .await
+            .unwrap();
+    }
+
+    let sequences: Vec<_> = channel
+        .get_sent_messages()
+        .await
+        .iter()
+        .map(|m| (m.require_ordered, m.sequence))
+        .collect();
+    assert_eq!(
+        sequences,
+        vec![(true, Some(0)), (true, Some(1)), (true, Some(2))]
+    );
+}
+
+#[tokio::test]
+async fn test_ordered_receive_restores_order() {
+    // IT-ORD-003: 无序通道注入乱序后，接收端按序号恢复顺序再交付
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+
+    let ordered_message = |seq: u64| {
+        let mut message = Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text(format!("msg-{}", seq)),
+        );
+        message.require_ordered = true;
+        message.sequence = Some(seq);
+        message
+    };
+
+    for seq in [2, 0, 3, 1] {
+        handler.handle_message(ordered_message(seq)).await.unwrap();
+    }
+    // 重复的旧序号被丢弃
+    handler.handle_message(ordered_message(1)).await.unwrap();
+
+    for expected in 0..4 {
+        let received = tokio::time::timeout(std::time::Duration::from_secs(1), sdk.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.sequence, Some(expected));
+    }
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_reorder_buffer_overflow_policies() {
+    // IT-ORD-004: 序号空洞超过缓冲上限时按配置的溢出策略处理，并记录指标
+    for policy in [
+        ReorderOverflowPolicy::DeliverOutOfOrder,
+        ReorderOverflowPolicy::Drop,
+        ReorderOverflowPolicy::RequestRetransmit,
+    ] {
+        let sdk = TestSdkBuilder::new().build().await.unwrap();
+        sdk.set_reorder_buffer_config(ReorderBufferConfig {
+            max_entries_per_sender: 4,
+            overflow_policy: policy,
+            ..Default::default()
+        });
+        let mut events = Box::pin(sdk.events());
+        let handler = sdk.get_message_handler();
+        let sender = test_device_id();
+        let ordered_message = |seq: u64| {
+            let mut message = Message::new(
+                sender,
+                sdk.device_id(),
+                MessagePayload::Text(format!("msg-{}", seq)),
+            );
+            message.require_ordered = true;
+            message.sequence = Some(seq);
+            message
+        };
+
+        // 序号 0 之后出现巨大空洞，第 5 条乱序消息触发溢出
+        for seq in [0, 1000, 1001, 1002, 1003, 1004] {
+            handler.handle_message(ordered_message(seq)).await.unwrap();
+        }
+
+        let mut received = Vec::new();
+        while let Ok(Some(message)) =
+            tokio::time::timeout(std::time::Duration::from_millis(100), sdk.receive()).await
+        {
+            received.push(message.sequence.unwrap());
+        }
+        let expected: Vec<u64> = match policy {
+            ReorderOverflowPolicy::DeliverOutOfOrder => vec![0, 1000, 1001, 1002, 1003, 1004],
+            ReorderOverflowPolicy::Drop | ReorderOverflowPolicy::RequestRetransmit => vec![0],
+        };
+        assert_eq!(received, expected, "{:?}", policy);
+        assert_eq!(sdk.metrics_report().total_reorder_overflows, 1);
+
+        let overflow = loop {
+            if let SdkEvent::ReorderBufferOverflow {
+                sender: from,
+                missing,
+                policy: applied,
+            } = events.next().await.unwrap()
+            {
+                break (from, missing, applied);
+            }
+        };
+        assert_eq!(overflow, (sender, 1..1000, policy));
+    }
+}
+
+#[tokio::test]
+async fn test_reorder_gap_timeout_releases_buffered_messages() {
+    // IT-ORD-005: 缺失序号等待超时后跳过空洞，后台任务在没有新消息时也会交付
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_reorder_buffer_config(ReorderBufferConfig {
+        gap_timeout_ms: Some(100),
+        ..Default::default()
+    });
+    sdk.start().await.unwrap();
+    let mut events = Box::pin(sdk.events());
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let sequenced = |seq: u64| {
+        let mut message = Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text(format!("msg-{}", seq)),
+        );
+        message.sequence = Some(seq);
+        message
+    };
+
+    for seq in [0, 2, 3] {
+        handler.handle_message(sequenced(seq)).await.unwrap();
+    }
+    let first = tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.sequence, Some(0));
+    // 超时前继续等待序号 1
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), sdk.receive())
+            .await
+            .is_err()
+    );
+
+    for expected in [2, 3] {
+        let received = tokio::time::timeout(Duration::from_secs(2), sdk.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.sequence, Some(expected));
+    }
+    let skipped = loop {
+        if let SdkEvent::ReorderGapSkipped {
+            sender: from,
+            missing,
+        } = events.next().await.unwrap()
+        {
+            break (from, missing);
+        }
+    };
+    assert_eq!(skipped, (sender, 1..2));
+
+    // 被跳过的序号 1 迟到后仍交付一次，再次到达时按重复消息丢弃
+    handler.handle_message(sequenced(1)).await.unwrap();
+    let late = tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(late.sequence, Some(1));
+    handler.handle_message(sequenced(1)).await.unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+    sdk.stop().await;
+}
+
+#[tokio::test]
+async fn test_ordered_delivery_mode_sequences_sends_on_any_channel() {
+    // IT-ORD-006: Ordered 交付模式为普通发送分配序号，无序通道也可使用
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    let peer = test_device_id();
+    assert_eq!(sdk.delivery_mode(), DeliveryMode::Unordered);
+
+    sdk.send(peer, MessagePayload::Text("unordered".to_string()))
+        .await
+        .unwrap();
+    sdk.set_delivery_mode(DeliveryMode::Ordered);
+    for i in 0..2 {
+        sdk.send(peer, MessagePayload::Text(format!("msg-{}", i)))
+            .await
+            .unwrap();
+    }
+
+    let sequences: Vec<_> = channel
+        .get_sent_messages()
+        .await
+        .iter()
+        .map(|m| m.sequence)
+        .collect();
+    assert_eq!(sequences, vec![None, Some(0), Some(1)]);
+    let epochs: Vec<_> = channel
+        .get_sent_messages()
+        .await
+        .iter()
+        .map(|m| m.sequence_epoch)
+        .collect();
+    assert!(epochs[1].is_some());
+    assert_eq!(epochs[1], epochs[2]);
+}
+
+#[tokio::test]
+async fn test_sender_restart_starts_new_sequence_epoch() {
+    // IT-ORD-007: 发送端重启后序号从 0 重新开始，新纪元的消息不被当作重复丢弃
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let ordered_message = |epoch: u64, seq: u64| {
+        let mut message = Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text(format!("msg-{}-{}", epoch, seq)),
+        );
+        message.require_ordered = true;
+        message.sequence = Some(seq);
+        message.sequence_epoch = Some(epoch);
+        message
+    };
+
+    for seq in 0..3 {
+        handler
+            .handle_message(ordered_message(1, seq))
+            .await
+            .unwrap();
+    }
+    // 重启后的发送端使用更大的纪元，序号重新从 0 开始
+    for seq in 0..2 {
+        handler
+            .handle_message(ordered_message(2, seq))
+            .await
+            .unwrap();
+    }
+    // 旧纪元迟到的消息不再参与排序，直接交付
+    handler.handle_message(ordered_message(1, 5)).await.unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..6 {
+        let message = tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        received.push((message.sequence_epoch, message.sequence));
+    }
+    assert_eq!(
+        received,
+        vec![
+            (Some(1), Some(0)),
+            (Some(1), Some(1)),
+            (Some(1), Some(2)),
+            (Some(2), Some(0)),
+            (Some(2), Some(1)),
+            (Some(1), Some(5)),
+        ]
+    );
+}
+
+/// 等待通道发出至少 `count` 条满足条件的消息
+async fn wait_for_sent(
+    channel: &MemoryChannel,
+    count: usize,
+    matches: impl Fn(&Message) -> bool,
+) -> Vec<Message> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    loop {
+        let sent: Vec<Message> = channel
+            .get_sent_messages()
+            .await
+            .into_iter()
+            .filter(|m| matches(m))
+            .collect();
+        if sent.len() >= count || tokio::time::Instant::now() >= deadline {
+            return sent;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_reorder_overflow_requests_retransmit_from_sender() {
+    // IT-ORD-008: 缓冲区溢出时向发送方请求重传，发送方从待发送队列重发缺失与被丢弃的消息
+    let sender_channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sender_storage = Arc::new(MemoryStorage::new());
+    let sender = XLink::with_storage(
+        test_device_capabilities(),
+        vec![sender_channel.clone()],
+        sender_storage.clone(),
+    )
+    .await
+    .unwrap();
+    sender.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    sender.set_delivery_mode(DeliveryMode::Ordered);
+
+    let receiver_channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let receiver = XLink::with_storage(
+        test_device_capabilities(),
+        vec![receiver_channel.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    receiver.set_reorder_buffer_config(ReorderBufferConfig {
+        max_entries_per_sender: 2,
+        overflow_policy: ReorderOverflowPolicy::RequestRetransmit,
+        gap_timeout_ms: None,
+    });
+    receiver.capability_manager().update_channel_state(
+        sender.device_id(),
+        ChannelType::Lan,
+        receiver_channel
+            .check_state(&sender.device_id())
+            .await
+            .unwrap(),
+    );
+
+    // 发送失败的有序消息带着序号进入发送方的待发送队列
+    sender_channel.set_failure(true);
+    for i in 0..7 {
+        assert!(sender
+            .send(
+                receiver.device_id(),
+                MessagePayload::Text(format!("msg-{}", i))
+            )
+            .await
+            .is_err());
+    }
+    sender_channel.set_failure(false);
+    let mut queued = sender_storage.list_pending_messages().await.unwrap();
+    queued.sort_by_key(|m| m.sequence);
+    assert_eq!(queued.len(), 7);
+
+    // 序号 1..4 丢失，4、5 填满缓冲区后 6 溢出被丢弃
+    let handler = receiver.get_message_handler();
+    for seq in [0, 4, 5, 6] {
+        handler.handle_message(queued[seq].clone()).await.unwrap();
+    }
+    let requests = wait_for_sent(&receiver_channel, 1, |m| {
+        matches!(m.payload, MessagePayload::RetransmitRequest { .. })
+    })
+    .await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].payload,
+        MessagePayload::RetransmitRequest {
+            epoch: queued[0].sequence_epoch.unwrap(),
+            sequences: vec![1..4, 6..7],
+        }
+    );
+
+    // 发送方应答请求，重发缺失的序号与被丢弃的消息
+    sender
+        .get_message_handler()
+        .handle_message(requests[0].clone())
+        .await
+        .unwrap();
+    let retransmitted = wait_for_sent(&sender_channel, 4, |m| m.sequence.is_some()).await;
+    let sequences: Vec<_> = retransmitted.iter().map(|m| m.sequence.unwrap()).collect();
+    assert_eq!(sequences, vec![1, 2, 3, 6]);
+    for message in retransmitted {
+        handler.handle_message(message).await.unwrap();
+    }
+
+    for expected in 0..7 {
+        let received = tokio::time::timeout(Duration::from_secs(1), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.sequence, Some(expected));
+    }
+}
+
+// ==================== Channel Warm-up Tests ====================
+
+/// 记录预热目标的通道，模拟按对端建立的出站连接
+#[derive(Default)]
+struct ConnectingChannel {
+    connections: std::sync::Mutex<Vec<DeviceId>>,
+}
+
+#[async_trait::async_trait]
+impl Channel for ConnectingChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, _message: Message) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState::default())
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+
+    async fn warm_up(&self, target: &DeviceId) -> xlink::core::error::Result<()> {
+        self.connections.lock().unwrap().push(*target);
+        Ok(())
+    }
+}
+
+fn seen_at(last_heartbeat: u64) -> ChannelState {
+    ChannelState {
+        available: true,
+        last_heartbeat,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_channel_warmup_connects_recent_peers_after_start() {
+    let channel = Arc::new(ConnectingChannel::default());
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_channel_warmup_config(ChannelWarmupConfig {
+        enabled: true,
+        ..Default::default()
+    });
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (recent, stale, ble_only) = (test_device_id(), test_device_id(), test_device_id());
+    let caps = sdk.capability_manager();
+    caps.update_channel_state(recent, ChannelType::Lan, seen_at(now - 60));
+    caps.update_channel_state(stale, ChannelType::Lan, seen_at(now - 7 * 24 * 3600));
+    // 只出现在本地没有的通道上的对端无需预热
+    caps.update_channel_state(ble_only, ChannelType::BluetoothLE, seen_at(now));
+
+    sdk.start().await.unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+    while channel.connections.lock().unwrap().is_empty() && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(*channel.connections.lock().unwrap(), vec![recent]);
+    sdk.stop().await;
+}
+
+#[tokio::test]
+async fn test_channel_warmup_skipped_on_low_battery() {
+    let channel = Arc::new(ConnectingChannel::default());
+    let mut low_battery = test_device_capabilities();
+    low_battery.battery_level = Some(5);
+    let sdk = TestSdkBuilder::new()
+        .with_device_capabilities(low_battery)
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    sdk.capability_manager()
+        .update_channel_state(test_device_id(), ChannelType::Lan, seen_at(now));
+
+    let report = sdk.warm_up_channels().await;
+    assert!(report.peers.is_empty());
+    assert!(channel.connections.lock().unwrap().is_empty());
+}
+
+// ==================== Send Retry Tests ====================
+
+/// 前若干次发送失败的通道，失败时返回指定重试建议的断开错误
+struct FlakyChannel {
+    failures_left: std::sync::atomic::AtomicU32,
+    attempts: std::sync::atomic::AtomicU32,
+    suggestion: RetrySuggestion,
+}
+
+impl FlakyChannel {
+    fn new(failures: u32, suggestion: RetrySuggestion) -> Self {
+        Self {
+            failures_left: std::sync::atomic::AtomicU32::new(failures),
+            attempts: std::sync::atomic::AtomicU32::new(0),
+            suggestion,
+        }
+    }
+
+    fn attempts(&self) -> u32 {
+        self.attempts.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for FlakyChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, _message: Message) -> xlink::core::error::Result<()> {
+        use std::sync::atomic::Ordering;
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        let failed = self
+            .failures_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        if failed {
+            return Err(XLinkError::channel_disconnected("link flapped", file!())
+                .with_retry_suggestion(self.suggestion));
+        }
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState::default())
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+}
+
+/// 接收任务很快退出、重连前若干次失败的通道，记录启动与重连次数
+struct DroppingChannel {
+    starts: std::sync::atomic::AtomicU32,
+    reconnects: std::sync::atomic::AtomicU32,
+    failing_reconnects: std::sync::atomic::AtomicU32,
+    disconnect_sends: std::sync::atomic::AtomicBool,
+}
+
+impl DroppingChannel {
+    fn new(failing_reconnects: u32) -> Self {
+        Self {
+            starts: std::sync::atomic::AtomicU32::new(0),
+            reconnects: std::sync::atomic::AtomicU32::new(0),
+            failing_reconnects: std::sync::atomic::AtomicU32::new(failing_reconnects),
+            disconnect_sends: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    fn starts(&self) -> u32 {
+        self.starts.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn reconnects(&self) -> u32 {
+        self.reconnects.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for DroppingChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, _message: Message) -> xlink::core::error::Result<()> {
+        if self
+            .disconnect_sends
+            .swap(false, std::sync::atomic::Ordering::SeqCst)
+        {
+            return Err(XLinkError::channel_disconnected("peer reset", file!())
+                .with_retry_suggestion(RetrySuggestion::NoRetry));
+        }
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState::default())
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+
+    async fn start_with_handler(
+        &self,
+        _handler: Arc<dyn MessageHandler>,
+    ) -> xlink::core::error::Result<Option<tokio::task::JoinHandle<()>>> {
+        // 首次启动的接收任务立即退出，模拟连接断开；重启后的接收任务保持运行
+        let first = self
+            .starts
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            == 0;
+        Ok(Some(tokio::spawn(async move {
+            if !first {
+                std::future::pending::<()>().await;
+            }
+        })))
+    }
+
+    async fn reconnect(&self) -> xlink::core::error::Result<()> {
+        use std::sync::atomic::Ordering;
+        self.reconnects.fetch_add(1, Ordering::SeqCst);
+        let failed = self
+            .failing_reconnects
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        if failed {
+            return Err(XLinkError::channel_disconnected("still offline", file!()));
+        }
+        Ok(())
+    }
+}
+
+async fn wait_until(mut done: impl FnMut() -> bool, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    done()
+}
+
+// 多线程运行时：start 启动的 mDNS 发现任务会阻塞所在线程，重连监督须在其他线程上按时推进
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_dropped_channel_reconnects_with_backoff() {
+    // IT-RCN-001: 接收任务退出后按退避重连并重启接收；发送报告断开时再次重连
+    let channel = Arc::new(DroppingChannel::new(2));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    sdk.set_reconnect_config(ReconnectConfig {
+        enabled: true,
+        initial_delay_ms: 5,
+        max_delay_ms: 20,
+    });
+    sdk.start().await.unwrap();
+
+    // 前两次重连失败，第三次成功后重启接收任务
+    assert!(wait_until(|| channel.starts() == 2, Duration::from_secs(2)).await);
+    assert_eq!(channel.reconnects(), 3);
+    assert!(sdk
+        .task_statuses()
+        .iter()
+        .any(|status| status.name == "receive_Lan" && status.alive));
+
+    // 接收任务仍在运行时，发送返回断开同样触发重连，但不重复启动接收
+    channel
+        .disconnect_sends
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+    let err = sdk
+        .send(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 202);
+    assert!(wait_until(|| channel.reconnects() == 4, Duration::from_secs(2)).await);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(channel.starts(), 2);
+
+    sdk.stop().await;
+    assert_eq!(sdk.active_task_count(), 0);
+}
+
+async fn sdk_with_flaky_channel(
+    channel: Arc<FlakyChannel>,
+    retry: bool,
+) -> (XLink, Arc<MemoryStorage>) {
+    let storage = Arc::new(MemoryStorage::new());
+    let sdk = XLink::with_storage(test_device_capabilities(), vec![channel], storage.clone())
+        .await
+        .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    sdk.set_send_retry_config(SendRetryConfig {
+        retry_transient_failures: retry,
+    });
+    (sdk, storage)
+}
+
+const FAST_RETRY: RetrySuggestion = RetrySuggestion::Retryable {
+    max_attempts: 3,
+    base_delay_ms: 5,
+};
+
+#[tokio::test]
+async fn test_send_retries_transient_disconnect_before_pending_queue() {
+    let channel = Arc::new(FlakyChannel::new(2, FAST_RETRY));
+    let (sdk, storage) = sdk_with_flaky_channel(channel.clone(), true).await;
+
+    sdk.send(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .unwrap();
+    assert_eq!(channel.attempts(), 3);
+    assert!(storage.list_pending_messages().await.unwrap().is_empty());
+
+    // 重试耗尽后仍放入待发送队列
+    let channel = Arc::new(FlakyChannel::new(10, FAST_RETRY));
+    let (sdk, storage) = sdk_with_flaky_channel(channel.clone(), true).await;
+    let err = sdk
+        .send(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 202);
+    assert_eq!(channel.attempts(), 4);
+    assert_eq!(storage.list_pending_messages().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_send_without_retry_or_retryable_error_fails_immediately() {
+    // 未开启重试时首次失败即进入待发送队列
+    let channel = Arc::new(FlakyChannel::new(1, FAST_RETRY));
+    let (sdk, storage) = sdk_with_flaky_channel(channel.clone(), false).await;
+    assert!(sdk
+        .send(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .is_err());
+    assert_eq!(channel.attempts(), 1);
+    assert_eq!(storage.list_pending_messages().await.unwrap().len(), 1);
+
+    // 不可重试的错误即使开启重试也不会等待
+    let channel = Arc::new(FlakyChannel::new(1, RetrySuggestion::NoRetry));
+    let (sdk, _storage) = sdk_with_flaky_channel(channel.clone(), true).await;
+    let started = std::time::Instant::now();
+    assert!(sdk
+        .send(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .is_err());
+    assert_eq!(channel.attempts(), 1);
+    assert!(started.elapsed() < std::time::Duration::from_millis(500));
+
+    // 尽力发送的消息即使开启重试也只尝试一次
+    let channel = Arc::new(FlakyChannel::new(1, FAST_RETRY));
+    let (sdk, storage) = sdk_with_flaky_channel(channel.clone(), true).await;
+    assert!(sdk
+        .send_ephemeral(test_device_id(), MessagePayload::Text("hello".into()))
+        .await
+        .is_err());
+    assert_eq!(channel.attempts(), 1);
+    assert!(storage.list_pending_messages().await.unwrap().is_empty());
+}
+
+// ==================== WebSocket Tests ====================
+
+struct ForwardingHandler(tokio::sync::mpsc::UnboundedSender<Message>);
+
+#[async_trait::async_trait]
+impl MessageHandler for ForwardingHandler {
+    async fn handle_message(&self, message: Message) -> xlink::core::error::Result<()> {
+        let _ = self.0.send(message);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_channel_round_trip_and_disconnect() {
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let inbound = Message::new(
+        test_device_id(),
+        test_device_id(),
+        MessagePayload::Text("from server".into()),
+    );
+    let inbound_json = serde_json::to_string(&inbound).unwrap();
+    let (frame_tx, mut frame_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    // 服务器下发一条消息，收到客户端的第一帧文本后关闭连接
+    let server = tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        ws.send(WsMessage::Text(inbound_json)).await.unwrap();
+        while let Some(Ok(frame)) = ws.next().await {
+            if let WsMessage::Text(text) = frame {
+                let _ = frame_tx.send(text);
+                break;
+            }
+        }
+        let _ = ws.close(None).await;
+    });
+
+    let channel = WebSocketChannel::new(format!("ws://{}", addr));
+    assert_eq!(channel.channel_type(), ChannelType::Internet);
+
+    // 未连接时发送返回通道断开错误
+    let outbound = Message::new(
+        test_device_id(),
+        test_device_id(),
+        MessagePayload::Text("from client".into()),
+    );
+    let err = channel.send(outbound.clone()).await.unwrap_err();
+    assert_eq!(err.code().0, 202);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let task = channel
+        .start_with_handler(Arc::new(ForwardingHandler(tx)))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let received = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.id, inbound.id);
+
+    let state = channel.check_state(&outbound.recipient).await.unwrap();
+    assert!(state.available);
+
+    channel.send(outbound.clone()).await.unwrap();
+    let text = tokio::time::timeout(Duration::from_secs(5), frame_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let echoed: Message = serde_json::from_str(&text).unwrap();
+    assert_eq!(echoed.id, outbound.id);
+    server.await.unwrap();
+
+    // 服务器关闭连接后发送失败，交由待发送队列恢复
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while channel.is_connected().await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let err = channel.send(outbound).await.unwrap_err();
+    assert_eq!(err.code().0, 202);
+    assert!(
+        !channel
+            .check_state(&inbound.recipient)
+            .await
+            .unwrap()
+            .available
+    );
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_websocket_reconnect_skips_remaining_backoff() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let channel = WebSocketChannel::new(format!("ws://{}", addr));
+    // 接收任务未运行时无需重连，交由调用方重新启动
+    channel.reconnect().await.unwrap();
+
+    // 服务器接受一次连接后立即关闭并停止监听
+    let server = tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let _ = ws.close(None).await;
+    });
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let task = channel
+        .start_with_handler(Arc::new(ForwardingHandler(tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    server.await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while channel.is_connected().await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // 两次重连失败后退避到 2 秒，服务器恢复后立即重连而不必等完剩余的退避
+    tokio::time::sleep(Duration::from_millis(1700)).await;
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let server = tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        while let Some(Ok(_)) = ws.next().await {}
+    });
+    tokio::time::timeout(Duration::from_secs(1), channel.reconnect())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(channel.is_connected().await);
+    // 已连接时直接返回
+    channel.reconnect().await.unwrap();
+
+    task.abort();
+    server.abort();
+}
+
+// ==================== QUIC Tests ====================
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn test_quic_channel_round_trip_and_disconnect() {
+    use dashmap::DashMap;
+    use xlink::channels::quic::{QuicChannel, QuicTlsConfig};
+    use xlink::core::traits::ChannelIdentity;
+    use xlink::crypto::engine::CryptoEngine;
+
+    let loopback: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server =
+        QuicChannel::new(loopback, None, QuicTlsConfig::insecure_for_test().unwrap()).unwrap();
+    let server_addr = server.local_addr().unwrap();
+    let client = QuicChannel::new(
+        loopback,
+        Some(server_addr),
+        QuicTlsConfig::insecure_for_test().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(client.channel_type(), ChannelType::Internet);
+
+    let client_id = test_device_id();
+    let server_id = test_device_id();
+    // 服务端固定客户端的身份公钥，只有以该密钥认证的连接才会绑定到客户端
+    let client_crypto = Arc::new(CryptoEngine::new());
+    let server_pins = Arc::new(DashMap::new());
+    server_pins.insert(client_id, client_crypto.verifying_key());
+    server.set_identity(ChannelIdentity {
+        device_id: server_id,
+        signer: Arc::new(CryptoEngine::new()),
+        pinned_keys: server_pins,
+    });
+    client.set_identity(ChannelIdentity {
+        device_id: client_id,
+        signer: client_crypto,
+        pinned_keys: Arc::new(DashMap::new()),
+    });
+    let outbound = Message::new(
+        client_id,
+        server_id,
+        MessagePayload::Text("from client".into()),
+    );
+
+    // 未连接时发送返回通道断开错误
+    let err = client.send(outbound.clone()).await.unwrap_err();
+    assert_eq!(err.code().0, 202);
+
+    let (server_tx, mut server_rx) = tokio::sync::mpsc::unbounded_channel();
+    let server_task = server
+        .start_with_handler(Arc::new(ForwardingHandler(server_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    let (client_tx, mut client_rx) = tokio::sync::mpsc::unbounded_channel();
+    let client_task = client
+        .start_with_handler(Arc::new(ForwardingHandler(client_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !client.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let state = client.check_state(&server_id).await.unwrap();
+    assert!(state.available);
+
+    client.send(outbound.clone()).await.unwrap();
+    let received = tokio::time::timeout(Duration::from_secs(5), server_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.id, outbound.id);
+
+    // 服务端经由客户端已认证的入站连接回复
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !server.check_state(&client_id).await.unwrap().available {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let reply = Message::new(
+        server_id,
+        client_id,
+        MessagePayload::Text("from server".into()),
+    );
+    server.send(reply.clone()).await.unwrap();
+    let received = tokio::time::timeout(Duration::from_secs(5), client_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.id, reply.id);
+
+    // 服务端关闭后发送失败，交由待发送队列恢复
+    server_task.abort();
+    drop(server);
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while client.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let err = client.send(outbound).await.unwrap_err();
+    assert_eq!(err.code().0, 202);
+    assert!(!client.check_state(&server_id).await.unwrap().available);
+
+    client_task.abort();
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn test_quic_channel_does_not_bind_impostor_connection() {
+    use dashmap::DashMap;
+    use xlink::channels::quic::{QuicChannel, QuicTlsConfig};
+    use xlink::core::traits::ChannelIdentity;
+    use xlink::crypto::engine::CryptoEngine;
+
+    let loopback: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server =
+        QuicChannel::new(loopback, None, QuicTlsConfig::insecure_for_test().unwrap()).unwrap();
+    let impostor = QuicChannel::new(
+        loopback,
+        Some(server.local_addr().unwrap()),
+        QuicTlsConfig::insecure_for_test().unwrap(),
+    )
+    .unwrap();
+
+    let victim_id = test_device_id();
+    let server_id = test_device_id();
+    let server_pins = Arc::new(DashMap::new());
+    server_pins.insert(victim_id, CryptoEngine::new().verifying_key());
+    server.set_identity(ChannelIdentity {
+        device_id: server_id,
+        signer: Arc::new(CryptoEngine::new()),
+        pinned_keys: server_pins,
+    });
+    // 冒充者声称是受害设备，但持有的是另一把身份密钥
+    impostor.set_identity(ChannelIdentity {
+        device_id: victim_id,
+        signer: Arc::new(CryptoEngine::new()),
+        pinned_keys: Arc::new(DashMap::new()),
+    });
+
+    let (server_tx, mut server_rx) = tokio::sync::mpsc::unbounded_channel();
+    let server_task = server
+        .start_with_handler(Arc::new(ForwardingHandler(server_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    let (impostor_tx, _impostor_rx) = tokio::sync::mpsc::unbounded_channel();
+    let impostor_task = impostor
+        .start_with_handler(Arc::new(ForwardingHandler(impostor_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !impostor.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let spoofed = Message::new(victim_id, server_id, MessagePayload::Text("spoofed".into()));
+    impostor.send(spoofed).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // 认证失败的连接不会绑定到受害设备，发往它的回复不会落到冒充者手中
+    assert!(!server.check_state(&victim_id).await.unwrap().available);
+    let reply = Message::new(
+        server_id,
+        victim_id,
+        MessagePayload::Text("for the victim".into()),
+    );
+    let err = server.send(reply).await.unwrap_err();
+    assert_eq!(err.code().0, 202);
+
+    impostor_task.abort();
+    server_task.abort();
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn test_quic_reconnect_wakes_relay_loop() {
+    use xlink::channels::quic::{QuicChannel, QuicTlsConfig};
+
+    let loopback: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    // 只接受入站连接的通道没有需要恢复的中继连接
+    let standalone =
+        QuicChannel::new(loopback, None, QuicTlsConfig::insecure_for_test().unwrap()).unwrap();
+    standalone.reconnect().await.unwrap();
+
+    // 预留一个端口作为尚未启动的中继地址
+    let relay_addr = std::net::UdpSocket::bind(loopback)
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let client = QuicChannel::new(
+        loopback,
+        Some(relay_addr),
+        QuicTlsConfig::insecure_for_test().unwrap(),
+    )
+    .unwrap();
+    // 连接任务未运行时交由调用方重新启动
+    client.reconnect().await.unwrap();
+    let (client_tx, _client_rx) = tokio::sync::mpsc::unbounded_channel();
+    let client_task = client
+        .start_with_handler(Arc::new(ForwardingHandler(client_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!client.is_connected());
+
+    // 中继恢复后，重连请求唤醒中继循环并等到连接建立
+    let relay = QuicChannel::new(
+        relay_addr,
+        None,
+        QuicTlsConfig::insecure_for_test().unwrap(),
+    )
+    .unwrap();
+    let (relay_tx, _relay_rx) = tokio::sync::mpsc::unbounded_channel();
+    let relay_task = relay
+        .start_with_handler(Arc::new(ForwardingHandler(relay_tx)))
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(3), client.reconnect())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(client.is_connected());
+
+    client_task.abort();
+    relay_task.abort();
+}
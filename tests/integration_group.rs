@@ -5,14 +5,35 @@
 
 mod common;
 
-use crate::common::{test_device_capabilities, test_device_id, NetworkSimulator, TestSdkBuilder};
-use std::collections::HashMap;
+use crate::common::{
+    test_device_capabilities, test_device_id, NetworkSimulator, NoOpMessageHandler, TestSdkBuilder,
+};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use ed25519_dalek::VerifyingKey;
+use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use xlink::capability::manager::CapabilityManager;
-use xlink::core::types::MessagePayload;
-use xlink::group::manager::GroupManager;
+use xlink::channels::memory::MemoryChannel;
+use xlink::core::clock::MockClock;
+use xlink::core::events::SdkEvent;
+use xlink::core::traits::{Channel, Storage};
+use xlink::core::types::{
+    ChannelState, ChannelType, DeviceId, GroupId, MemberRole, MemberStatus, Message,
+    MessagePayload, NetworkType,
+};
+use xlink::crypto::engine::CryptoEngine;
+use xlink::crypto::treekem::TreeKemEngine;
+use xlink::group::manager::{
+    BroadcastFanoutPolicy, BroadcastTargets, GroupEvent, GroupManager, PresenceConfig,
+    PresenceEvent, RotationPolicy, UnknownGroupPolicy,
+};
 use xlink::router::selector::Router;
+use xlink::storage::memory_store::MemoryStorage;
+use xlink::XLink;
 
 // ==================== Group Management (Unit-like Integration) ====================
 
@@ -46,6 +67,186 @@ async fn test_create_and_manage_group() {
     assert!(group_manager.get_group(group.id).await.is_none());
 }
 
+#[tokio::test]
+async fn test_admin_transfer_and_role_enforcement() {
+    // UT-GRP-003: 仅管理员可添加成员、轮换密钥；移交管理员后原管理员降为普通成员
+    let creator_id = test_device_id();
+    let bob = test_device_id();
+    let router = Arc::new(Router::new(
+        HashMap::new(),
+        Arc::new(CapabilityManager::new(test_device_capabilities())),
+    ));
+    let group_manager = GroupManager::new(creator_id, router);
+    for device_id in [creator_id, bob] {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(device_id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Roles".to_string(), vec![creator_id, bob])
+        .await
+        .unwrap();
+
+    // 唯一管理员不能直接离开，也不能移交给非成员
+    let err = group_manager.leave_group(group.id).await.unwrap_err();
+    assert_eq!(err.code().0, 407);
+    let err = group_manager
+        .transfer_admin(group.id, test_device_id())
+        .unwrap_err();
+    assert_eq!(err.code().0, 403);
+
+    group_manager.transfer_admin(group.id, bob).unwrap();
+    let group = group_manager.get_group(group.id).await.unwrap();
+    assert_eq!(group.members[&bob].role, MemberRole::Admin);
+    assert_eq!(group.members[&creator_id].role, MemberRole::Member);
+
+    // 降级后不再拥有管理员权限
+    for err in [
+        group_manager
+            .add_member(group.id, test_device_id())
+            .await
+            .unwrap_err(),
+        group_manager.rotate_group_key(group.id).await.unwrap_err(),
+        group_manager
+            .transfer_admin(group.id, creator_id)
+            .unwrap_err(),
+    ] {
+        assert_eq!(err.code().0, 407);
+    }
+    assert_eq!(
+        group_manager
+            .get_group(group.id)
+            .await
+            .unwrap()
+            .members
+            .len(),
+        2
+    );
+
+    group_manager.leave_group(group.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_presence_events_follow_member_activity() {
+    // UT-GRP-004: 成员状态变化推送给订阅者，按最后活跃时间降为 Away/Offline
+    let creator_id = test_device_id();
+    let bob = test_device_id();
+    let router = Arc::new(Router::new(
+        HashMap::new(),
+        Arc::new(CapabilityManager::new(test_device_capabilities())),
+    ));
+    let group_manager = GroupManager::new(creator_id, router);
+    for device_id in [creator_id, bob] {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(device_id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Presence".to_string(), vec![creator_id, bob])
+        .await
+        .unwrap();
+
+    let err = group_manager
+        .subscribe_presence(GroupId(uuid::Uuid::new_v4()))
+        .unwrap_err();
+    assert_eq!(err.code().0, 401);
+    let mut presence = group_manager.subscribe_presence(group.id).unwrap();
+
+    // 状态未变化时不推送
+    group_manager.set_member_presence(bob, MemberStatus::Online);
+    assert!(presence.try_recv().is_err());
+
+    group_manager
+        .update_member_state(group.id, bob, MemberStatus::Offline)
+        .await
+        .unwrap();
+    assert_eq!(
+        presence.try_recv().unwrap(),
+        PresenceEvent {
+            group_id: group.id,
+            device_id: bob,
+            previous: MemberStatus::Online,
+            status: MemberStatus::Offline,
+        }
+    );
+
+    // 心跳重新收到 Bob 后恢复在线，随后按阈值降为 Away
+    group_manager.set_member_presence(bob, MemberStatus::Online);
+    assert_eq!(presence.try_recv().unwrap().status, MemberStatus::Online);
+    group_manager.set_presence_config(PresenceConfig {
+        away_after: Duration::ZERO,
+        offline_after: Duration::from_secs(3600),
+    });
+    group_manager.refresh_presence();
+    let event = presence.try_recv().unwrap();
+    assert_eq!((event.device_id, event.status), (bob, MemberStatus::Away));
+    group_manager.refresh_presence();
+    assert!(presence.try_recv().is_err());
+
+    group_manager.set_presence_config(PresenceConfig {
+        away_after: Duration::ZERO,
+        offline_after: Duration::ZERO,
+    });
+    group_manager.refresh_presence();
+    let event = presence.try_recv().unwrap();
+    assert_eq!(
+        (event.previous, event.status),
+        (MemberStatus::Away, MemberStatus::Offline)
+    );
+    // 本地设备不参与判定
+    let group = group_manager.get_group(group.id).await.unwrap();
+    assert_eq!(group.members[&creator_id].status, MemberStatus::Online);
+
+    // 离开群组后订阅随之关闭
+    group_manager.transfer_admin(group.id, bob).unwrap();
+    group_manager.leave_group(group.id).await.unwrap();
+    assert!(presence.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn test_create_group_reports_members_without_keys() {
+    // UT-GRP-005: 缺少公钥的成员使建群失败并在错误中列出；create_group_partial 跳过这些成员
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    let keyed = test_device_id();
+    let keyless = test_device_id();
+    let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    sdk.register_device_key(keyed, pk).unwrap();
+
+    let err = sdk
+        .create_group("Strict".to_string(), vec![keyed, keyless])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 408);
+    assert_eq!(err.missing_member_keys(), vec![keyless]);
+
+    let (group_id, skipped) = sdk
+        .create_group_partial("Partial".to_string(), vec![keyed, keyless, keyless])
+        .await
+        .unwrap();
+    assert_eq!(skipped, vec![keyless]);
+    let group = sdk.group_manager().get_group(group_id).await.unwrap();
+    assert!(group.members.contains_key(&keyed));
+    assert!(!group.members.contains_key(&keyless));
+
+    // 测试环境可显式开启占位公钥
+    let test_sdk = TestSdkBuilder::new().build().await.unwrap();
+    assert!(test_sdk.group_key_config().placeholder_keys_for_testing);
+    test_sdk
+        .create_group("Placeholder".to_string(), vec![keyless])
+        .await
+        .unwrap();
+}
+
 // ==================== Secure Group Communication (TreeKEM) ====================
 
 #[tokio::test]
@@ -79,7 +280,7 @@ async fn test_treekem_group_security() {
 
     if let MessagePayload::Binary(data) = encrypted {
         let decrypted = sdk
-            .decrypt_group_message(group_id, &MessagePayload::Binary(data))
+            .decrypt_group_message(group_id, sdk.device_id(), &MessagePayload::Binary(data))
             .unwrap();
         assert_eq!(decrypted, payload);
     } else {
@@ -90,6 +291,398 @@ async fn test_treekem_group_security() {
     assert!(sdk.rotate_group_key(group_id).await.is_ok());
 }
 
+#[test]
+fn test_treekem_message_key_cached_per_epoch() {
+    let sender = test_device_id();
+    let engine = TreeKemEngine::new(sender);
+    let group_id = GroupId(uuid::Uuid::new_v4());
+    engine.create_group(group_id, vec![sender]).unwrap();
+
+    // 同一纪元内复用密钥，但每条消息的 nonce 各不相同
+    let payload = MessagePayload::Text("busy chat".to_string());
+    let mut nonces = std::collections::HashSet::new();
+    let mut first_epoch = Vec::new();
+    for _ in 0..1000 {
+        let encrypted = engine
+            .encrypt_group_message(group_id, sender, &payload)
+            .unwrap();
+        let MessagePayload::Binary(bytes) = &encrypted else {
+            panic!("group ciphertext should be binary");
+        };
+        assert!(nonces.insert(bytes[..24].to_vec()));
+        assert_eq!(
+            engine
+                .decrypt_group_message(group_id, sender, &encrypted)
+                .unwrap(),
+            payload
+        );
+        first_epoch.push(encrypted);
+    }
+
+    // 换钥后缓存失效，旧纪元密文不可解，新纪元消息正常往返
+    engine.rotate_group_key(group_id).unwrap();
+    assert!(engine
+        .decrypt_group_message(group_id, sender, &first_epoch[0])
+        .is_err());
+    let encrypted = engine
+        .encrypt_group_message(group_id, sender, &payload)
+        .unwrap();
+    assert_eq!(
+        engine
+            .decrypt_group_message(group_id, sender, &encrypted)
+            .unwrap(),
+        payload
+    );
+
+    // 以同一 ID 重建群组（纪元回到 0）不会沿用旧密钥
+    engine.create_group(group_id, vec![sender]).unwrap();
+    assert!(engine
+        .decrypt_group_message(group_id, sender, &encrypted)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_fresh_device_can_process_invite() {
+    // IT-GRP-004: 新成员在没有群组密钥的情况下也能处理邀请
+    let alice_id = test_device_id();
+    let bob_id = test_device_id();
+    let alice_pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    let bob_pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, channel.clone());
+    let alice_caps = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    alice_caps.update_channel_state(
+        bob_id,
+        ChannelType::Lan,
+        channel.check_state(&bob_id).await.unwrap(),
+    );
+    let alice = GroupManager::new(alice_id, Arc::new(Router::new(channels, alice_caps)));
+    alice.register_device_key(alice_id, alice_pk).unwrap();
+    alice.register_device_key(bob_id, bob_pk).unwrap();
+    let alice_identity = Arc::new(CryptoEngine::new());
+    alice.set_message_signer(Some(alice_identity.clone()));
+
+    let group = alice
+        .create_group("Invite Group".to_string(), vec![alice_id, bob_id])
+        .await
+        .unwrap();
+    alice.send_invite(group.id, bob_id).await.unwrap();
+
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    let invite = &sent[0];
+    assert!(matches!(invite.payload, MessagePayload::GroupInvite { .. }));
+
+    // Bob 只知道 Alice 的公钥，没有任何群组密钥
+    let bob_router = Arc::new(Router::new(
+        HashMap::new(),
+        Arc::new(CapabilityManager::new(test_device_capabilities())),
+    ));
+    let bob = GroupManager::new(bob_id, bob_router);
+    bob.register_device_key(alice_id, alice_pk).unwrap();
+
+    // 明文邀请必须带有邀请者的有效身份签名
+    let mut unsigned = invite.clone();
+    unsigned.signature = None;
+    assert!(bob.handle_incoming_group_message(&unsigned).await.is_err());
+    assert!(bob.handle_incoming_group_message(invite).await.is_err());
+    assert!(bob.get_group(group.id).await.is_none());
+
+    bob.set_identity_keys(identity_keys(&[(alice_id, &alice_identity)]));
+    bob.handle_incoming_group_message(invite).await.unwrap();
+
+    let joined = bob
+        .get_group(group.id)
+        .await
+        .expect("Bob should join group");
+    assert_eq!(joined.name, "Invite Group");
+    assert!(joined.members.contains_key(&alice_id));
+}
+
+#[tokio::test]
+async fn test_broadcast_caps_remote_fanout() {
+    // IT-GRP-005: 远程扇出上限，超出部分的消息交由调用方放入待发送队列
+    let local_id = test_device_id();
+    let ble = Arc::new(
+        MemoryChannel::new(Arc::new(NoOpMessageHandler), 0).with_type(ChannelType::BluetoothLE),
+    );
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::BluetoothLE, ble.clone());
+    channels.insert(ChannelType::Lan, lan.clone());
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+
+    let nearby: Vec<_> = (0..2).map(|_| test_device_id()).collect();
+    let remote: Vec<_> = (0..5).map(|_| test_device_id()).collect();
+    for id in &nearby {
+        cap_manager.update_channel_state(
+            *id,
+            ChannelType::BluetoothLE,
+            ble.check_state(id).await.unwrap(),
+        );
+    }
+    for id in &remote {
+        cap_manager.update_channel_state(*id, ChannelType::Lan, lan.check_state(id).await.unwrap());
+    }
+
+    let router = Arc::new(Router::new(channels, cap_manager));
+    let group_manager = GroupManager::new(local_id, router.clone());
+    let mut members = vec![local_id];
+    members.extend(nearby.iter().copied());
+    members.extend(remote.iter().copied());
+    for id in &members {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(*id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Fanout Group".to_string(), members)
+        .await
+        .unwrap();
+
+    group_manager.set_fanout_policy(BroadcastFanoutPolicy {
+        max_remote_sends: Some(2),
+        avoid_cellular_when_cost_sensitive: false,
+    });
+    let outcome = group_manager
+        .broadcast_to(
+            group.id,
+            MessagePayload::Text("hello".to_string()),
+            BroadcastTargets::All,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(ble.get_sent_messages().await.len(), 2);
+    assert_eq!(lan.get_sent_messages().await.len(), 2);
+    // 超出上限的 3 个远程成员被报告，且各有一条待发送消息
+    assert_eq!(outcome.fanout_deferred.len(), 3);
+    assert!(outcome.skipped.is_empty());
+    let queued: HashSet<_> = outcome.deferred.iter().map(|m| m.recipient).collect();
+    assert_eq!(queued, outcome.fanout_deferred.iter().copied().collect());
+    assert!(outcome
+        .deferred
+        .iter()
+        .all(|m| m.id == outcome.message_id && m.group_id == Some(group.id)));
+    let stats = router.get_traffic_stats().unwrap();
+    assert!(stats.get(&ChannelType::Lan).copied().unwrap_or(0) > 0);
+}
+
+#[tokio::test]
+async fn test_broadcast_skips_cellular_when_cost_sensitive() {
+    // IT-GRP-006: 流量敏感时不通过蜂窝网络直接扇出
+    let local_id = test_device_id();
+    let remote_id = test_device_id();
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, lan.clone());
+    // test_device_capabilities 默认 data_cost_sensitive = true
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let mut state = lan.check_state(&remote_id).await.unwrap();
+    state.network_type = NetworkType::Cellular5G;
+    cap_manager.update_channel_state(remote_id, ChannelType::Lan, state);
+
+    let router = Arc::new(Router::new(channels, cap_manager));
+    let budget = Arc::new(xlink::core::budget::TrafficBudget::new());
+    router.set_traffic_budget(Some(budget.clone()));
+    let group_manager = GroupManager::new(local_id, router);
+    for id in [local_id, remote_id] {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Cellular Group".to_string(), vec![local_id, remote_id])
+        .await
+        .unwrap();
+
+    group_manager.set_fanout_policy(BroadcastFanoutPolicy {
+        max_remote_sends: None,
+        avoid_cellular_when_cost_sensitive: true,
+    });
+    let outcome = group_manager
+        .broadcast_to(
+            group.id,
+            MessagePayload::Text("hello".to_string()),
+            BroadcastTargets::All,
+        )
+        .await
+        .unwrap();
+
+    assert!(lan.get_sent_messages().await.is_empty());
+    assert_eq!(outcome.fanout_deferred, vec![remote_id]);
+    assert_eq!(outcome.deferred.len(), 1);
+    assert_eq!(outcome.deferred[0].recipient, remote_id);
+    // 避开的蜂窝流量计入流量统计
+    assert!(budget.avoided_bytes() > 0);
+    assert_eq!(budget.used_bytes(), 0);
+}
+
+#[tokio::test]
+async fn test_duplicate_concurrent_broadcasts_coalesce() {
+    // IT-GRP-007: 开启去重后，并发的相同广播只扇出一次
+    let local_id = test_device_id();
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 5));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, lan.clone());
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+
+    let peers: Vec<_> = (0..3).map(|_| test_device_id()).collect();
+    for id in &peers {
+        cap_manager.update_channel_state(*id, ChannelType::Lan, lan.check_state(id).await.unwrap());
+    }
+    let group_manager = Arc::new(GroupManager::new(
+        local_id,
+        Arc::new(Router::new(channels, cap_manager)),
+    ));
+    let mut members = vec![local_id];
+    members.extend(peers.iter().copied());
+    for id in &members {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(*id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Photo Group".to_string(), members)
+        .await
+        .unwrap();
+    let photo = MessagePayload::Binary(vec![7u8; 1024]);
+
+    // 默认关闭：重复广播各自扇出
+    assert!(group_manager.broadcast_dedup_window().is_none());
+    let first = group_manager
+        .broadcast(group.id, photo.clone())
+        .await
+        .unwrap();
+    let second = group_manager
+        .broadcast(group.id, photo.clone())
+        .await
+        .unwrap();
+    assert_ne!(first, second);
+    assert_eq!(lan.get_sent_messages().await.len(), 2 * peers.len());
+    lan.clear_sent_messages().await;
+
+    group_manager.set_broadcast_dedup_window(Some(std::time::Duration::from_secs(5)));
+    let sends = (0..10).map(|_| {
+        let group_manager = group_manager.clone();
+        let photo = photo.clone();
+        async move { group_manager.broadcast(group.id, photo).await.unwrap() }
+    });
+    let ids = futures::future::join_all(sends).await;
+    assert!(ids.iter().all(|id| *id == ids[0]));
+    assert_eq!(lan.get_sent_messages().await.len(), peers.len());
+
+    // 只在扇出进行中合并：前一次结束后，窗口内的相同广播重新扇出
+    let again = group_manager
+        .broadcast(group.id, photo.clone())
+        .await
+        .unwrap();
+    assert_ne!(again, ids[0]);
+    assert_eq!(lan.get_sent_messages().await.len(), 2 * peers.len());
+
+    // 不同负载不受影响
+    let other = group_manager
+        .broadcast(group.id, MessagePayload::Text("caption".to_string()))
+        .await
+        .unwrap();
+    assert_ne!(other, ids[0]);
+    assert_eq!(lan.get_sent_messages().await.len(), 3 * peers.len());
+}
+
+/// 记录同时在途发送数峰值的测试通道
+struct ConcurrencyProbeChannel {
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+    sent: AtomicUsize,
+}
+
+#[async_trait]
+impl Channel for ConcurrencyProbeChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, _message: Message) -> xlink::core::error::Result<()> {
+        let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(current, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.sent.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState {
+            available: true,
+            rtt_ms: 10,
+            packet_loss_rate: 0.0,
+            bandwidth_bps: 1_000_000,
+            ..Default::default()
+        })
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_large_broadcast_bounded_concurrency() {
+    // IT-GRP-008: 200 成员群组广播的并发发送数不超过配置上限
+    let local_id = test_device_id();
+    let probe = Arc::new(ConcurrencyProbeChannel {
+        in_flight: AtomicUsize::new(0),
+        peak: AtomicUsize::new(0),
+        sent: AtomicUsize::new(0),
+    });
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, probe.clone());
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+
+    let peers: Vec<_> = (0..200).map(|_| test_device_id()).collect();
+    for id in &peers {
+        cap_manager.update_channel_state(
+            *id,
+            ChannelType::Lan,
+            probe.check_state(id).await.unwrap(),
+        );
+    }
+    let group_manager = GroupManager::new(local_id, Arc::new(Router::new(channels, cap_manager)));
+    let mut members = vec![local_id];
+    members.extend(peers.iter().copied());
+    for id in &members {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        group_manager.register_device_key(*id, pk).unwrap();
+    }
+    let group = group_manager
+        .create_group("Large Group".to_string(), members)
+        .await
+        .unwrap();
+
+    group_manager.set_broadcast_concurrency(8);
+    assert_eq!(group_manager.broadcast_concurrency(), 8);
+    group_manager
+        .broadcast(group.id, MessagePayload::Text("hello".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(probe.sent.load(Ordering::SeqCst), peers.len());
+    let peak = probe.peak.load(Ordering::SeqCst);
+    assert!(peak <= 8, "peak concurrency {} exceeded limit", peak);
+    assert!(peak > 1, "sends should still run concurrently");
+}
+
 // ==================== Multi-device Integration ====================
 
 #[tokio::test]
@@ -119,6 +712,243 @@ async fn test_multi_device_group_broadcast() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_broadcast_to_online_skips_offline_members() {
+    // IT-GRP-009: 只向在线成员广播，离线成员的消息进入待发送队列
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let storage = Arc::new(MemoryStorage::new());
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![lan.clone()],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+
+    let peers: Vec<_> = (0..5).map(|_| test_device_id()).collect();
+    for id in &peers {
+        let secret = x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng);
+        sdk.register_device_key(*id, x25519_dalek::PublicKey::from(&secret))
+            .unwrap();
+        sdk.capability_manager().update_channel_state(
+            *id,
+            ChannelType::Lan,
+            lan.check_state(id).await.unwrap(),
+        );
+    }
+    let group_id = sdk
+        .create_group("Partially Idle".to_string(), peers.clone())
+        .await
+        .unwrap();
+
+    // peers[0] 标记为离线；peers[1] 的在线状态数据显示通道不可用
+    sdk.group_manager()
+        .update_member_state(group_id, peers[0], MemberStatus::Offline)
+        .await
+        .unwrap();
+    let mut unreachable = lan.check_state(&peers[1]).await.unwrap();
+    unreachable.available = false;
+    sdk.capability_manager()
+        .update_channel_state(peers[1], ChannelType::Lan, unreachable);
+
+    let outcome = sdk
+        .send_to_group_online(group_id, MessagePayload::Text("hello".to_string()))
+        .await
+        .unwrap();
+
+    let sorted = |mut ids: Vec<DeviceId>| {
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    };
+    assert_eq!(sorted(outcome.skipped.clone()), sorted(peers[..2].to_vec()));
+    let sent = lan.get_sent_messages().await;
+    assert_eq!(
+        sorted(sent.iter().map(|m| m.recipient).collect()),
+        sorted(peers[2..].to_vec())
+    );
+
+    let pending = storage.list_pending_messages().await.unwrap();
+    assert_eq!(
+        sorted(pending.iter().map(|m| m.recipient).collect()),
+        sorted(peers[..2].to_vec())
+    );
+    assert!(pending
+        .iter()
+        .all(|m| m.id == outcome.message_id && m.group_id == Some(group_id)));
+}
+
+#[tokio::test]
+async fn test_revoked_peer_is_removed_from_groups_and_rekeyed_out() {
+    // IT-GRP-013: 撤销对端公钥时将其移出群组并轮换密钥，之后的密钥更新与群组消息都不再发给它
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![lan.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+
+    let (bob, mallory) = (test_device_id(), test_device_id());
+    for id in [bob, mallory] {
+        let secret = x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng);
+        sdk.register_device_key(id, x25519_dalek::PublicKey::from(&secret))
+            .unwrap();
+        sdk.capability_manager().update_channel_state(
+            id,
+            ChannelType::Lan,
+            lan.check_state(&id).await.unwrap(),
+        );
+    }
+    sdk.pin_identity_key(mallory, CryptoEngine::new().verifying_key());
+    let group_id = sdk
+        .create_group("Revocation".to_string(), vec![bob, mallory])
+        .await
+        .unwrap();
+    let before_revocation = sdk
+        .encrypt_group_message(group_id, &MessagePayload::Text("before".to_string()))
+        .unwrap();
+    lan.clear_sent_messages().await;
+
+    sdk.revoke_peer_key(mallory).await.unwrap();
+    let members = sdk
+        .group_manager()
+        .get_group(group_id)
+        .await
+        .unwrap()
+        .members;
+    assert!(!members.contains_key(&mallory));
+    assert!(members.contains_key(&bob));
+    assert!(!sdk.unpin_identity_key(mallory));
+
+    // 群组已进入新纪元，旧密钥加密的内容无法再解密
+    assert!(sdk
+        .decrypt_group_message(group_id, sdk.device_id(), &before_revocation)
+        .is_err());
+    let key_updates: Vec<_> = lan
+        .get_sent_messages()
+        .await
+        .into_iter()
+        .filter(|m| m.payload.is_group_control())
+        .collect();
+    assert!(!key_updates.is_empty());
+    assert!(key_updates.iter().all(|m| m.recipient == bob));
+
+    // 撤销后的群组消息只发往剩余成员
+    lan.clear_sent_messages().await;
+    sdk.send_to_group(group_id, MessagePayload::Text("after".to_string()))
+        .await
+        .unwrap();
+    let recipients: Vec<_> = lan
+        .get_sent_messages()
+        .await
+        .iter()
+        .map(|m| m.recipient)
+        .collect();
+    assert_eq!(recipients, vec![bob]);
+}
+
+#[tokio::test]
+async fn test_broadcast_to_selected_members() {
+    // IT-GRP-012: 按角色或设备列表向部分成员广播，ACK 只追踪选中的成员，列表含非成员时报错
+    let lan = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![lan.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+
+    let peers: Vec<_> = (0..4).map(|_| test_device_id()).collect();
+    for id in &peers {
+        let secret = x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng);
+        sdk.register_device_key(*id, x25519_dalek::PublicKey::from(&secret))
+            .unwrap();
+        sdk.capability_manager().update_channel_state(
+            *id,
+            ChannelType::Lan,
+            lan.check_state(id).await.unwrap(),
+        );
+    }
+    let group_id = sdk
+        .create_group("Selective".to_string(), peers.clone())
+        .await
+        .unwrap();
+    let sorted = |mut ids: Vec<DeviceId>| {
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    };
+    let recipients_of = |message_id: uuid::Uuid| {
+        let lan = lan.clone();
+        async move {
+            let recipients = lan
+                .get_sent_messages()
+                .await
+                .into_iter()
+                .filter(|m| m.id == message_id)
+                .map(|m| m.recipient)
+                .collect();
+            sorted(recipients)
+        }
+    };
+
+    // 管理员移交给 peers[0] 后，本机降为普通成员且不向自己发送
+    sdk.group_manager()
+        .transfer_admin(group_id, peers[0])
+        .unwrap();
+    let admins = sdk
+        .send_to_group_members(
+            group_id,
+            MessagePayload::Text("admins".to_string()),
+            BroadcastTargets::Role(MemberRole::Admin),
+        )
+        .await
+        .unwrap();
+    assert_eq!(recipients_of(admins.message_id).await, vec![peers[0]]);
+    let (pending, _, _) = sdk
+        .group_manager()
+        .get_ack_status(admins.message_id)
+        .await
+        .unwrap();
+    assert_eq!(pending, 1);
+
+    let selected: HashSet<DeviceId> = [peers[1], peers[3]].into_iter().collect();
+    let subset = sdk
+        .send_to_group_members(
+            group_id,
+            MessagePayload::Text("subset".to_string()),
+            BroadcastTargets::Devices(selected),
+        )
+        .await
+        .unwrap();
+    assert!(subset.skipped.is_empty());
+    assert_eq!(
+        recipients_of(subset.message_id).await,
+        sorted(vec![peers[1], peers[3]])
+    );
+    let (pending, _, _) = sdk
+        .group_manager()
+        .get_ack_status(subset.message_id)
+        .await
+        .unwrap();
+    assert_eq!(pending, 2);
+
+    // 列表中有非成员时整体拒绝，不向任何人发送
+    let sent_before = lan.get_sent_messages().await.len();
+    let with_outsider: HashSet<DeviceId> = [peers[1], test_device_id()].into_iter().collect();
+    let err = sdk
+        .send_to_group_members(
+            group_id,
+            MessagePayload::Text("outsider".to_string()),
+            BroadcastTargets::Devices(with_outsider),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 403);
+    assert_eq!(lan.get_sent_messages().await.len(), sent_before);
+}
+
 // ==================== Large Scale Performance ====================
 
 #[tokio::test]
@@ -157,3 +987,666 @@ async fn test_large_group_performance() {
         creation_time, broadcast_time
     );
 }
+
+/// 构建一个通过 MemoryChannel 可达 `peers` 的 GroupManager
+async fn reachable_group_manager(
+    local_id: DeviceId,
+    peers: &[DeviceId],
+) -> (GroupManager, Arc<MemoryChannel>) {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, channel.clone());
+    let caps = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    for peer in peers {
+        caps.update_channel_state(
+            *peer,
+            ChannelType::Lan,
+            channel.check_state(peer).await.unwrap(),
+        );
+    }
+    let manager = GroupManager::new(local_id, Arc::new(Router::new(channels, caps)));
+    (manager, channel)
+}
+
+/// 由设备身份引擎构造已固定的身份公钥表，供校验群组控制消息的签名
+fn identity_keys(devices: &[(DeviceId, &CryptoEngine)]) -> Arc<DashMap<DeviceId, VerifyingKey>> {
+    Arc::new(
+        devices
+            .iter()
+            .map(|(device_id, engine)| (*device_id, engine.verifying_key()))
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn test_join_request_approval_rekeys_group() {
+    // IT-GRP-006: 入群申请 -> 管理员审批 -> 新成员加入并轮换密钥
+    let admin_id = test_device_id();
+    let member_id = test_device_id();
+    let (admin, admin_channel) = reachable_group_manager(admin_id, &[member_id]).await;
+    let (member, member_channel) = reachable_group_manager(member_id, &[admin_id]).await;
+    let admin_pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    admin.register_device_key(admin_id, admin_pk).unwrap();
+    // 群组密钥经双方的一对一会话分发
+    let admin_crypto = Arc::new(CryptoEngine::new());
+    let member_crypto = Arc::new(CryptoEngine::new());
+    admin_crypto
+        .establish_session(member_id, member_crypto.public_key())
+        .unwrap();
+    member_crypto
+        .establish_session(admin_id, admin_crypto.public_key())
+        .unwrap();
+    let keys = identity_keys(&[(admin_id, &admin_crypto), (member_id, &member_crypto)]);
+    admin.set_identity_keys(keys.clone());
+    member.set_identity_keys(keys);
+    admin.set_message_signer(Some(admin_crypto));
+    member.set_message_signer(Some(member_crypto));
+
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    admin.register_event_handler(Box::new(move |event| {
+        recorded.lock().unwrap().push(event);
+    }));
+
+    let group = admin
+        .create_group("Join Group".to_string(), vec![admin_id])
+        .await
+        .unwrap();
+    let before_join = admin
+        .encrypt_group_message(group.id, &MessagePayload::Text("before".to_string()))
+        .unwrap();
+
+    member.request_join(group.id, admin_id).await.unwrap();
+    let request = member_channel.get_sent_messages().await.remove(0);
+    assert!(matches!(
+        request.payload,
+        MessagePayload::JoinRequest { .. }
+    ));
+
+    admin.handle_incoming_group_message(&request).await.unwrap();
+    assert_eq!(admin.pending_join_requests(group.id), vec![member_id]);
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![GroupEvent::JoinRequested {
+            group_id: group.id,
+            device_id: member_id,
+        }]
+    );
+
+    admin.approve_join(group.id, member_id).await.unwrap();
+    assert!(admin.pending_join_requests(group.id).is_empty());
+    assert!(admin
+        .get_group(group.id)
+        .await
+        .unwrap()
+        .members
+        .contains_key(&member_id));
+
+    // 新成员收到邀请和新纪元的密钥更新
+    let sent = admin_channel.get_sent_messages().await;
+    assert!(sent
+        .iter()
+        .all(|m| m.recipient == member_id && m.payload.is_group_control()));
+    assert!(sent
+        .iter()
+        .any(|m| matches!(m.payload, MessagePayload::GroupInvite { .. })));
+    assert!(sent
+        .iter()
+        .any(|m| matches!(m.payload, MessagePayload::GroupKeyUpdate { epoch, .. } if epoch > 0)));
+    assert!(sent
+        .iter()
+        .any(|m| matches!(m.payload, MessagePayload::GroupKeyDelivery { epoch, .. } if epoch > 0)));
+
+    // 新成员处理邀请与密钥分发后，可解密管理员之后的广播
+    for message in &sent {
+        member.handle_incoming_group_message(message).await.unwrap();
+    }
+    admin_channel.clear_sent_messages().await;
+    let welcome = MessagePayload::Text("hello new member".to_string());
+    admin.broadcast(group.id, welcome.clone()).await.unwrap();
+    let broadcast = admin_channel.get_sent_messages().await.remove(0);
+    assert_eq!(broadcast.recipient, member_id);
+    assert_eq!(
+        member
+            .decrypt_group_message(group.id, admin_id, &broadcast.payload)
+            .unwrap(),
+        welcome
+    );
+    // 加入之前的密文对新成员同样不可解
+    assert!(member
+        .decrypt_group_message(group.id, admin_id, &before_join)
+        .is_err());
+
+    // 轮换后的群组消息可解密，加入前的密文不再可解
+    let payload = MessagePayload::Text("welcome".to_string());
+    let encrypted = admin.encrypt_group_message(group.id, &payload).unwrap();
+    assert_eq!(
+        admin
+            .decrypt_group_message(group.id, admin_id, &encrypted)
+            .unwrap(),
+        payload
+    );
+    assert!(admin
+        .decrypt_group_message(group.id, admin_id, &before_join)
+        .is_err());
+
+    // 已是成员的设备再次申请会被忽略
+    admin.handle_incoming_group_message(&request).await.unwrap();
+    assert!(admin.pending_join_requests(group.id).is_empty());
+    assert_eq!(events.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_join_request_denied_and_non_admin_ignored() {
+    // IT-GRP-007: 拒绝申请后不再待审批；非管理员不处理申请
+    let admin_id = test_device_id();
+    let requester_id = test_device_id();
+    let (admin, _) = reachable_group_manager(admin_id, &[requester_id]).await;
+    let (requester, requester_channel) = reachable_group_manager(requester_id, &[admin_id]).await;
+    let admin_pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    admin.register_device_key(admin_id, admin_pk).unwrap();
+    let requester_identity = Arc::new(CryptoEngine::new());
+    requester.set_message_signer(Some(requester_identity.clone()));
+    let keys = identity_keys(&[(requester_id, &requester_identity)]);
+    admin.set_identity_keys(keys.clone());
+    let group = admin
+        .create_group("Closed Group".to_string(), vec![admin_id])
+        .await
+        .unwrap();
+
+    requester.request_join(group.id, admin_id).await.unwrap();
+    let request = requester_channel.get_sent_messages().await.remove(0);
+    admin.handle_incoming_group_message(&request).await.unwrap();
+
+    admin.deny_join(group.id, requester_id).unwrap();
+    assert!(admin.pending_join_requests(group.id).is_empty());
+    assert!(admin.deny_join(group.id, requester_id).is_err());
+    assert!(admin.approve_join(group.id, requester_id).await.is_err());
+    assert!(!admin
+        .get_group(group.id)
+        .await
+        .unwrap()
+        .members
+        .contains_key(&requester_id));
+
+    // 不是该群组管理员的设备不记录申请
+    let (bystander, _) = reachable_group_manager(test_device_id(), &[]).await;
+    bystander.set_identity_keys(keys);
+    bystander
+        .handle_incoming_group_message(&request)
+        .await
+        .unwrap();
+    assert!(bystander.pending_join_requests(group.id).is_empty());
+}
+
+#[tokio::test]
+async fn test_plaintext_control_messages_require_authorized_signer() {
+    // IT-GRP-014: 明文控制消息须带成员的有效签名，密钥更新仅接受管理员，ACK 以签名发送方为确认方
+    let admin_id = test_device_id();
+    let member_id = test_device_id();
+    let other_id = test_device_id();
+    let outsider_id = test_device_id();
+    let (admin, _) = reachable_group_manager(admin_id, &[member_id, other_id]).await;
+    for device_id in [admin_id, member_id, other_id] {
+        let public_key = x25519_dalek::PublicKey::from(
+            &x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng),
+        );
+        admin.register_device_key(device_id, public_key).unwrap();
+    }
+    let admin_identity = CryptoEngine::new();
+    let member_identity = CryptoEngine::new();
+    let outsider_identity = CryptoEngine::new();
+    admin.set_identity_keys(identity_keys(&[
+        (admin_id, &admin_identity),
+        (member_id, &member_identity),
+        (outsider_id, &outsider_identity),
+    ]));
+    let group = admin
+        .create_group(
+            "Control Group".to_string(),
+            vec![admin_id, member_id, other_id],
+        )
+        .await
+        .unwrap();
+    let before = admin
+        .encrypt_group_message(group.id, &MessagePayload::Text("before".to_string()))
+        .unwrap();
+
+    let control = |sender: DeviceId, identity: Option<&CryptoEngine>, payload| {
+        let mut message = Message::new(sender, admin_id, payload);
+        message.group_id = Some(group.id);
+        if let Some(identity) = identity {
+            identity.sign_message(&mut message);
+        }
+        message
+    };
+    let key_update = MessagePayload::GroupKeyUpdate {
+        group_id: group.id,
+        epoch: 42,
+        update_path: Vec::new(),
+    };
+
+    // 未签名或签名与发送方不符的控制消息被拒绝
+    assert!(admin
+        .handle_incoming_group_message(&control(admin_id, None, key_update.clone()))
+        .await
+        .is_err());
+    assert!(admin
+        .handle_incoming_group_message(&control(
+            admin_id,
+            Some(&outsider_identity),
+            key_update.clone()
+        ))
+        .await
+        .is_err());
+    // 签名有效但非管理员发起的密钥更新被忽略，纪元不变
+    for (sender, identity) in [
+        (outsider_id, &outsider_identity),
+        (member_id, &member_identity),
+    ] {
+        admin
+            .handle_incoming_group_message(&control(sender, Some(identity), key_update.clone()))
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        admin
+            .decrypt_group_message(group.id, admin_id, &before)
+            .unwrap(),
+        MessagePayload::Text("before".to_string())
+    );
+
+    // ACK 记在签名发送方名下，负载中冒充的确认方不被采信
+    let message_id = admin
+        .broadcast(group.id, MessagePayload::Text("ack me".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(admin.get_ack_status(message_id).await, Some((2, 0, 0)));
+    let forged_ack = MessagePayload::GroupAck {
+        original_msg_id: message_id,
+        responder: other_id,
+    };
+    admin
+        .handle_incoming_group_message(&control(
+            outsider_id,
+            Some(&outsider_identity),
+            forged_ack.clone(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(admin.get_ack_status(message_id).await, Some((2, 0, 0)));
+    for _ in 0..2 {
+        admin
+            .handle_incoming_group_message(&control(
+                member_id,
+                Some(&member_identity),
+                forged_ack.clone(),
+            ))
+            .await
+            .unwrap();
+    }
+    assert_eq!(admin.get_ack_status(message_id).await, Some((1, 1, 0)));
+
+    // 管理员签名的密钥更新正常生效
+    admin
+        .handle_incoming_group_message(&control(admin_id, Some(&admin_identity), key_update))
+        .await
+        .unwrap();
+    assert!(admin
+        .decrypt_group_message(group.id, admin_id, &before)
+        .is_err());
+}
+
+fn group_message(sender: DeviceId, recipient: DeviceId, group_id: GroupId, text: &str) -> Message {
+    let mut message = Message::new(sender, recipient, MessagePayload::Text(text.to_string()));
+    message.group_id = Some(group_id);
+    message
+}
+
+/// 管理员签名的群组邀请
+fn signed_invite(
+    admin: (DeviceId, &CryptoEngine),
+    recipient: DeviceId,
+    group_id: GroupId,
+    name: &str,
+) -> Message {
+    let mut invite = Message::new(
+        admin.0,
+        recipient,
+        MessagePayload::GroupInvite {
+            group_id,
+            name: name.to_string(),
+        },
+    );
+    invite.group_id = Some(group_id);
+    admin.1.sign_message(&mut invite);
+    invite
+}
+
+#[tokio::test]
+async fn test_remove_member_rekeys_remaining_members() {
+    // IT-GRP-010: 管理员移除成员后轮换密钥，密钥更新只发给剩余成员
+    let (admin_id, bob, carol) = (test_device_id(), test_device_id(), test_device_id());
+    let (admin, channel) = reachable_group_manager(admin_id, &[bob, carol]).await;
+    for device_id in [admin_id, bob, carol] {
+        let pk = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+            rand::rngs::OsRng,
+        ));
+        admin.register_device_key(device_id, pk).unwrap();
+    }
+    let group = admin
+        .create_group("Removal".to_string(), vec![admin_id, bob, carol])
+        .await
+        .unwrap();
+    let before_removal = admin
+        .encrypt_group_message(group.id, &MessagePayload::Text("before".to_string()))
+        .unwrap();
+
+    let err = admin
+        .remove_member(group.id, test_device_id())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 403);
+
+    admin.remove_member(group.id, carol).await.unwrap();
+    let members = admin.get_group(group.id).await.unwrap().members;
+    assert!(!members.contains_key(&carol));
+    assert_eq!(members.len(), 2);
+
+    let key_updates: Vec<_> = channel
+        .get_sent_messages()
+        .await
+        .into_iter()
+        .filter(|m| matches!(m.payload, MessagePayload::GroupKeyUpdate { .. }))
+        .collect();
+    assert!(!key_updates.is_empty());
+    assert!(key_updates.iter().all(|m| m.recipient == bob));
+    assert!(admin
+        .decrypt_group_message(group.id, admin_id, &before_removal)
+        .is_err());
+
+    // 移除本地设备等同于离开群组
+    admin.transfer_admin(group.id, bob).unwrap();
+    admin.remove_member(group.id, admin_id).await.unwrap();
+    assert!(admin.get_group(group.id).await.is_none());
+}
+
+#[tokio::test]
+async fn test_scheduled_rotation_after_message_count() {
+    // IT-GRP-011: 自动轮换按消息数提前触发，任务由 SDK 托管，关闭策略或 stop 时中止
+    let peer = test_device_id();
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.capability_manager().update_channel_state(
+        peer,
+        ChannelType::Lan,
+        channel.check_state(&peer).await.unwrap(),
+    );
+    let group_id = sdk
+        .create_group("Rotating".to_string(), vec![sdk.device_id(), peer])
+        .await
+        .unwrap();
+    let key_updates = |messages: Vec<Message>| {
+        messages
+            .into_iter()
+            .filter_map(|m| match m.payload {
+                MessagePayload::GroupKeyUpdate { epoch, .. } => Some(epoch),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let err = sdk
+        .set_group_rotation_policy(
+            group_id,
+            Some(RotationPolicy {
+                every: Duration::ZERO,
+                after_n_messages: 3,
+            }),
+        )
+        .unwrap_err();
+    assert_eq!(err.code().0, 102);
+
+    let policy = RotationPolicy {
+        every: Duration::from_secs(3600),
+        after_n_messages: 3,
+    };
+    sdk.set_group_rotation_policy(group_id, Some(policy))
+        .unwrap();
+    sdk.start().await.unwrap();
+    let task_name = format!("group_key_rotation_{}", group_id);
+    assert!(sdk
+        .task_statuses()
+        .iter()
+        .any(|status| status.name == task_name && status.alive));
+
+    // 未达到消息数时不轮换
+    for i in 0..2 {
+        sdk.send_to_group(group_id, MessagePayload::Text(format!("msg {}", i)))
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(key_updates(channel.get_sent_messages().await).is_empty());
+
+    // 第三条消息触发轮换，密钥更新在该消息之后发出
+    sdk.send_to_group(group_id, MessagePayload::Text("msg 2".to_string()))
+        .await
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while key_updates(channel.get_sent_messages().await).is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let sent = channel.get_sent_messages().await;
+    let epochs = key_updates(sent.clone());
+    assert_eq!(epochs.len(), 1);
+    assert!(epochs[0] > 0);
+    let update_index = sent
+        .iter()
+        .position(|m| matches!(m.payload, MessagePayload::GroupKeyUpdate { .. }))
+        .unwrap();
+    let group_messages_before_update = sent[..update_index]
+        .iter()
+        .filter(|m| m.group_id == Some(group_id) && matches!(m.payload, MessagePayload::Binary(_)))
+        .count();
+    assert_eq!(group_messages_before_update, 3);
+
+    // 关闭策略后任务从后台任务表移除
+    assert_eq!(sdk.group_manager().rotation_policy(group_id), Some(policy));
+    sdk.set_group_rotation_policy(group_id, None).unwrap();
+    assert!(!sdk
+        .task_statuses()
+        .iter()
+        .any(|status| status.name == task_name));
+    assert_eq!(sdk.group_manager().rotation_policy(group_id), None);
+
+    // 重新启用后由 stop 一并清理
+    sdk.set_group_rotation_policy(group_id, Some(policy))
+        .unwrap();
+    sdk.stop().await;
+    assert!(sdk.task_statuses().is_empty());
+    assert_eq!(sdk.group_manager().rotation_policy(group_id), None);
+}
+
+#[tokio::test]
+async fn test_unknown_group_message_dropped_or_requests_join() {
+    // IT-GRP-008: 未加入群组的消息默认丢弃；RequestJoin 策略向发送方申请加入且限制频率
+    let local_id = test_device_id();
+    let sender_id = test_device_id();
+    let (manager, channel) = reachable_group_manager(local_id, &[sender_id]).await;
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    manager.register_event_handler(Box::new(move |event| {
+        recorded.lock().unwrap().push(event);
+    }));
+    let group_id = GroupId::new();
+
+    assert_eq!(manager.unknown_group_policy(), UnknownGroupPolicy::Drop);
+    let dropped = group_message(sender_id, local_id, group_id, "early");
+    manager
+        .handle_incoming_group_message(&dropped)
+        .await
+        .unwrap();
+    assert!(manager.take_buffered_group_messages(group_id).is_empty());
+    assert!(channel.get_sent_messages().await.is_empty());
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![GroupEvent::UnknownGroupMessage {
+            group_id,
+            sender: sender_id,
+            message_id: dropped.id,
+            policy: UnknownGroupPolicy::Drop,
+        }]
+    );
+
+    manager.set_unknown_group_policy(UnknownGroupPolicy::RequestJoin);
+    for text in ["one", "two"] {
+        manager
+            .handle_incoming_group_message(&group_message(sender_id, local_id, group_id, text))
+            .await
+            .unwrap();
+    }
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].recipient, sender_id);
+    assert_eq!(sent[0].payload, MessagePayload::JoinRequest { group_id });
+    assert_eq!(events.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_unknown_group_messages_buffered_until_invite() {
+    // IT-GRP-009: Buffer 策略下，先于邀请到达的群组消息在邀请之后补交给应用
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_unknown_group_policy(UnknownGroupPolicy::Buffer {
+        ttl: std::time::Duration::from_secs(30),
+        max_messages: 2,
+    });
+    let mut events = Box::pin(sdk.events());
+    let handler = sdk.get_message_handler();
+    let admin_id = test_device_id();
+    let admin_identity = CryptoEngine::new();
+    sdk.pin_identity_key(admin_id, admin_identity.verifying_key());
+    let group_id = GroupId::new();
+
+    for text in ["first", "second", "third"] {
+        let mut message = group_message(admin_id, sdk.device_id(), group_id, text);
+        admin_identity.sign_message(&mut message);
+        handler.handle_message(message).await.unwrap();
+    }
+    match tokio::time::timeout(std::time::Duration::from_secs(1), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+    {
+        SdkEvent::Group(GroupEvent::UnknownGroupMessage {
+            group_id: id,
+            policy,
+            ..
+        }) => {
+            assert_eq!(id, group_id);
+            assert!(matches!(policy, UnknownGroupPolicy::Buffer { .. }));
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+
+    let invite = signed_invite(
+        (admin_id, &admin_identity),
+        sdk.device_id(),
+        group_id,
+        "Late Invite",
+    );
+    handler.handle_message(invite).await.unwrap();
+
+    let received = sdk.receive().await.unwrap();
+    assert!(matches!(
+        received.payload,
+        MessagePayload::GroupInvite { .. }
+    ));
+    // 每个群组最多缓存两条，最旧的一条被丢弃
+    for expected in ["second", "third"] {
+        let received = sdk.receive().await.unwrap();
+        assert_eq!(received.group_id, Some(group_id));
+        assert_eq!(received.payload, MessagePayload::Text(expected.to_string()));
+    }
+}
+
+#[tokio::test]
+async fn test_group_expiry_follows_injected_clock() {
+    // UT-GRP-006: 未知群组消息缓存过期与成员在线状态衰减按注入的时钟计时，无需真实等待
+    let clock = Arc::new(MockClock::new());
+    let sdk = XLink::builder(test_device_capabilities())
+        .storage(Arc::new(MemoryStorage::new()))
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_unknown_group_policy(UnknownGroupPolicy::Buffer {
+        ttl: Duration::from_secs(30),
+        max_messages: 4,
+    });
+    let handler = sdk.get_message_handler();
+    let admin_id = test_device_id();
+    let admin_identity = CryptoEngine::new();
+    sdk.pin_identity_key(admin_id, admin_identity.verifying_key());
+    let group_id = GroupId::new();
+
+    // 第一条消息在邀请到达前已缓存超过 30 秒
+    let mut expired = group_message(admin_id, sdk.device_id(), group_id, "expired");
+    admin_identity.sign_message(&mut expired);
+    handler.handle_message(expired).await.unwrap();
+    clock.advance(Duration::from_secs(31));
+    let mut fresh = group_message(admin_id, sdk.device_id(), group_id, "fresh");
+    admin_identity.sign_message(&mut fresh);
+    handler.handle_message(fresh).await.unwrap();
+
+    let invite = signed_invite(
+        (admin_id, &admin_identity),
+        sdk.device_id(),
+        group_id,
+        "Clocked",
+    );
+    handler.handle_message(invite).await.unwrap();
+    assert!(matches!(
+        sdk.receive().await.unwrap().payload,
+        MessagePayload::GroupInvite { .. }
+    ));
+    assert_eq!(
+        sdk.receive().await.unwrap().payload,
+        MessagePayload::Text("fresh".to_string())
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+
+    // 管理员的最后活跃时间取自模拟时钟，时钟推进后依次降为 Away 与 Offline
+    let group_manager = sdk.group_manager();
+    group_manager.set_presence_config(PresenceConfig {
+        away_after: Duration::from_secs(60),
+        offline_after: Duration::from_secs(300),
+    });
+    let admin_status =
+        || async { group_manager.get_group(group_id).await.unwrap().members[&admin_id].status };
+    group_manager.refresh_presence();
+    assert_eq!(admin_status().await, MemberStatus::Online);
+    clock.advance(Duration::from_secs(61));
+    group_manager.refresh_presence();
+    assert_eq!(admin_status().await, MemberStatus::Away);
+    clock.advance(Duration::from_secs(240));
+    group_manager.refresh_presence();
+    assert_eq!(admin_status().await, MemberStatus::Offline);
+}
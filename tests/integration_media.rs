@@ -0,0 +1,1190 @@
+//! Integration tests for media stream handling
+//!
+//! This module covers audio/video frame buffering and stream events.
+
+mod common;
+
+use crate::common::{
+    create_test_cap_manager, test_device_capabilities, test_device_id, NoOpMessageHandler,
+    TestSdkBuilder,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use xlink::channels::memory::MemoryChannel;
+use xlink::core::clock::MockClock;
+use xlink::core::send_handle::SendOutcome;
+use xlink::core::traits::{Channel, Storage};
+use xlink::core::types::{
+    ChannelState, ChannelType, CompressionAlgorithm, CompressionConfig, DeviceId, Message,
+    MessagePayload, NetworkType, RoutingConfig, StreamConfig, StreamDeliveryConfig,
+};
+use xlink::media::stream_manager::{
+    BufferOverflowPolicy, ChunkIntegrity, FrameType, JitterStats, MediaBufferConfig, StreamEvent,
+    StreamManager, VideoConfig,
+};
+use xlink::router::selector::Router;
+use xlink::storage::memory_store::MemoryStorage;
+use xlink::XLink;
+
+fn test_stream_manager() -> StreamManager {
+    let router = Arc::new(Router::new(HashMap::new(), create_test_cap_manager()));
+    StreamManager::new(test_device_id(), router)
+}
+
+/// 带有一条可达内存通道的流管理器，用于观察实际发出的分片
+async fn connected_stream_manager(recipient: DeviceId) -> (StreamManager, Arc<MemoryChannel>) {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, channel.clone());
+    let cap_manager = create_test_cap_manager();
+    cap_manager.update_channel_state(
+        recipient,
+        ChannelType::Lan,
+        channel.check_state(&recipient).await.unwrap(),
+    );
+    let router = Arc::new(Router::new(channels, cap_manager));
+    (StreamManager::new(test_device_id(), router), channel)
+}
+
+async fn wait_for_sent(channel: &MemoryChannel, count: usize, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if channel.get_sent_messages().await.len() >= count {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+fn collect_events(manager: &StreamManager) -> Arc<Mutex<Vec<StreamEvent>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    manager.register_event_handler(Box::new(move |event| {
+        sink.lock().unwrap().push(event);
+    }));
+    events
+}
+
+// ==================== Buffer Overflow ====================
+
+#[tokio::test]
+async fn test_audio_buffer_overflow_emits_event() {
+    // UT-MED-001: 音频缓冲区溢出时通知应用层
+    let manager = test_stream_manager();
+    manager.update_buffer_config(MediaBufferConfig {
+        max_audio_buffer_bytes: 1000,
+        max_video_buffer_bytes: 1000,
+        overflow_policy: BufferOverflowPolicy::DropOldest,
+    });
+    let events = collect_events(&manager);
+
+    let stream_id = manager
+        .send_audio_stream(test_device_id(), Vec::new(), None)
+        .await
+        .unwrap();
+
+    manager
+        .process_audio_frame(stream_id, vec![1u8; 600], 0)
+        .unwrap();
+    assert!(events.lock().unwrap().is_empty());
+
+    manager
+        .process_audio_frame(stream_id, vec![2u8; 600], 20)
+        .unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![StreamEvent::BufferOverflow {
+            stream_id,
+            dropped_bytes: 200
+        }]
+    );
+    // Drop-oldest keeps the newest frame queued
+    assert_eq!(manager.get_pending_media_frames(stream_id).len(), 2);
+}
+
+#[tokio::test]
+async fn test_video_buffer_drop_newest_policy() {
+    // UT-MED-002: drop-newest 策略丢弃新到达的帧
+    let manager = test_stream_manager();
+    manager.update_buffer_config(MediaBufferConfig {
+        max_audio_buffer_bytes: 1000,
+        max_video_buffer_bytes: 1000,
+        overflow_policy: BufferOverflowPolicy::DropNewest,
+    });
+    let events = collect_events(&manager);
+
+    let stream_id = manager
+        .send_video_stream(test_device_id(), Vec::new(), None)
+        .await
+        .unwrap();
+
+    manager
+        .process_video_frame(stream_id, vec![1u8; 800], FrameType::VideoIFrame, 0)
+        .unwrap();
+    manager
+        .process_video_frame(stream_id, vec![2u8; 800], FrameType::VideoPFrame, 33)
+        .unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![StreamEvent::BufferOverflow {
+            stream_id,
+            dropped_bytes: 800
+        }]
+    );
+    let frames = manager.get_pending_media_frames(stream_id);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].frame_type, FrameType::VideoIFrame);
+}
+
+// ==================== Jitter Buffer ====================
+
+#[tokio::test]
+async fn test_audio_jitter_buffer_holds_recent_frames_and_drops_late_ones() {
+    // UT-MED-003: 抖动缓冲区按时间戳排序，只交付超过目标延迟的帧，迟到帧被丢弃并计数
+    let manager = test_stream_manager();
+    let stream_id = manager
+        .send_audio_stream(test_device_id(), Vec::new(), None)
+        .await
+        .unwrap();
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    // 音频目标延迟 200ms：两帧已到播放时间且乱序到达，一帧仍在缓冲期内
+    for timestamp in [now_ms - 1000, now_ms - 1040, now_ms] {
+        manager
+            .process_audio_frame(stream_id, vec![0u8; 10], timestamp)
+            .unwrap();
+    }
+    let played: Vec<u64> = manager
+        .get_pending_media_frames(stream_id)
+        .iter()
+        .map(|f| f.timestamp)
+        .collect();
+    assert_eq!(played, vec![now_ms - 1040, now_ms - 1000]);
+
+    // 比已播放位置更早的帧错过播放时间
+    manager
+        .process_audio_frame(stream_id, vec![0u8; 10], now_ms - 1020)
+        .unwrap();
+    assert!(manager.get_pending_media_frames(stream_id).is_empty());
+
+    assert_eq!(
+        manager.jitter_stats(stream_id),
+        Some(JitterStats {
+            frames_received: 4,
+            frames_played: 2,
+            late_frames_dropped: 1,
+            buffered_frames: 1,
+        })
+    );
+    assert_eq!(manager.jitter_stats(uuid::Uuid::new_v4()), None);
+}
+
+// ==================== Chunk Pacing ====================
+
+#[tokio::test]
+async fn test_video_chunks_paced_to_bitrate() {
+    // UT-MED-010: 分片按目标码率匀速发送，而不是一次性突发
+    let recipient = test_device_id();
+    let (manager, channel) = connected_stream_manager(recipient).await;
+    let config = VideoConfig {
+        bitrate: 2_000_000,
+        ..Default::default()
+    };
+
+    // 8 个 32KB 分片，2Mbps 下相邻分片间隔约 131ms
+    let started = Instant::now();
+    manager
+        .send_video_stream(recipient, vec![0u8; 8 * 32 * 1024], Some(config))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let early = channel.get_sent_messages().await.len();
+    assert!((1..8).contains(&early), "sent {} chunks in 300ms", early);
+
+    assert!(wait_for_sent(&channel, 8, Duration::from_secs(5)).await);
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(800) && elapsed < Duration::from_millis(2500),
+        "elapsed {:?}",
+        elapsed
+    );
+}
+
+#[tokio::test]
+async fn test_video_bitrate_ramps_down_on_lossy_link() {
+    // UT-MED-013: 发送过程中读取对端通道状态，持续高丢包时码率下降
+    let recipient = test_device_id();
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(ChannelType::Lan, channel.clone());
+    let cap_manager = create_test_cap_manager();
+    let mut state = channel.check_state(&recipient).await.unwrap();
+    state.packet_loss_rate = 0.1;
+    cap_manager.update_channel_state(recipient, ChannelType::Lan, state);
+    let manager = StreamManager::new(
+        test_device_id(),
+        Arc::new(Router::new(channels, cap_manager)),
+    );
+
+    let config = VideoConfig {
+        bitrate: 2_000_000,
+        ..Default::default()
+    };
+    let stream_id = manager
+        .send_video_stream(recipient, vec![0u8; 24 * 32 * 1024], Some(config))
+        .await
+        .unwrap();
+    assert_eq!(manager.stream_bitrate(stream_id), Some(2_000_000));
+
+    let deadline = Instant::now() + Duration::from_secs(4);
+    while manager.stream_bitrate(stream_id) == Some(2_000_000) && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let bitrate = manager.stream_bitrate(stream_id).unwrap();
+    assert!(bitrate < 2_000_000, "bitrate stayed at {}", bitrate);
+    manager.stop_stream(stream_id).unwrap();
+}
+
+#[tokio::test]
+async fn test_video_stream_pause_resume_stop() {
+    // UT-MED-011: 发送中的视频流可暂停、恢复与停止
+    let recipient = test_device_id();
+    let (manager, channel) = connected_stream_manager(recipient).await;
+    let config = VideoConfig {
+        bitrate: 2_000_000,
+        ..Default::default()
+    };
+
+    let stream_id = manager
+        .send_video_stream(recipient, vec![0u8; 8 * 32 * 1024], Some(config))
+        .await
+        .unwrap();
+    assert!(wait_for_sent(&channel, 1, Duration::from_secs(1)).await);
+
+    manager.pause_stream(stream_id).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let paused_at = channel.get_sent_messages().await.len();
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(channel.get_sent_messages().await.len(), paused_at);
+
+    manager.resume_stream(stream_id).unwrap();
+    assert!(wait_for_sent(&channel, paused_at + 1, Duration::from_secs(1)).await);
+
+    manager.stop_stream(stream_id).unwrap();
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let stopped_at = channel.get_sent_messages().await.len();
+    assert!(stopped_at < 8);
+
+    // 停止后控制通道已注销
+    assert!(manager.pause_stream(stream_id).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_network_changes_during_active_streams_do_not_deadlock() {
+    // UT-MED-012: 活跃流期间频繁切换网络，回调与码率调整、统计查询并发执行不死锁
+    let recipient = test_device_id();
+    let (manager, _channel) = connected_stream_manager(recipient).await;
+    let manager = Arc::new(manager);
+    let config = VideoConfig {
+        bitrate: 8_000_000,
+        ..Default::default()
+    };
+
+    let mut streams = Vec::new();
+    for _ in 0..4 {
+        streams.push(
+            manager
+                .send_video_stream(recipient, vec![0u8; 16 * 32 * 1024], Some(config.clone()))
+                .await
+                .unwrap(),
+        );
+    }
+
+    let networks = [
+        NetworkType::WiFi,
+        NetworkType::Cellular4G,
+        NetworkType::Ethernet,
+        NetworkType::Bluetooth,
+    ];
+    let mut workers = Vec::new();
+    for worker in 0..4 {
+        let manager = manager.clone();
+        let streams = streams.clone();
+        workers.push(tokio::task::spawn_blocking(move || {
+            for i in 0..500 {
+                if worker % 2 == 0 {
+                    manager.update_network_type(networks[(i + worker) % networks.len()]);
+                } else {
+                    let _ = manager.adjust_stream_bitrate(streams[i % streams.len()], 50, 0.01);
+                    let _ = manager.get_traffic_statistics();
+                }
+            }
+        }));
+    }
+
+    tokio::time::timeout(Duration::from_secs(20), futures::future::join_all(workers))
+        .await
+        .expect("network changes deadlocked with active streams")
+        .into_iter()
+        .for_each(|result| result.unwrap());
+    assert!(networks.contains(&manager.network_type()));
+}
+
+#[tokio::test]
+async fn test_network_type_override_bypasses_detection() {
+    // UT-MED-017: 覆盖网络类型后检测结果固定，码率控制器按覆盖值重新初始化
+    let recipient = test_device_id();
+    let (manager, _channel) = connected_stream_manager(recipient).await;
+    let stream_id = manager
+        .send_video_stream(
+            recipient,
+            Vec::new(),
+            Some(VideoConfig {
+                bitrate: 800_000,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    manager.update_network_type(NetworkType::Ethernet);
+
+    manager.set_network_type_override(Some(NetworkType::Cellular4G));
+    assert_eq!(
+        manager.network_type_override(),
+        Some(NetworkType::Cellular4G)
+    );
+    assert_eq!(manager.network_type(), NetworkType::Cellular4G);
+    assert_eq!(manager.detect_network_type().await, NetworkType::Cellular4G);
+    assert_eq!(manager.stream_bitrate(stream_id), Some(200_000));
+
+    // 覆盖期间检测到的变化只记录，不触发回调
+    manager.update_network_type(NetworkType::WiFi);
+    assert_eq!(manager.network_type(), NetworkType::Cellular4G);
+    assert_eq!(manager.stream_bitrate(stream_id), Some(200_000));
+
+    // 清除覆盖后恢复为最近的检测结果
+    manager.set_network_type_override(None);
+    assert_eq!(manager.network_type(), NetworkType::WiFi);
+    assert_eq!(manager.stream_bitrate(stream_id), Some(500_000));
+}
+
+// ==================== Cancellable Send ====================
+
+/// 带有一条可达内存通道的 SDK
+async fn connected_sdk(recipient: DeviceId) -> (XLink, Arc<MemoryChannel>) {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.capability_manager().update_channel_state(
+        recipient,
+        ChannelType::Lan,
+        channel.check_state(&recipient).await.unwrap(),
+    );
+    (sdk, channel)
+}
+
+#[tokio::test]
+async fn test_cancel_stops_streaming_send() {
+    let recipient = test_device_id();
+    let (sdk, channel) = connected_sdk(recipient).await;
+
+    // 16 个 32KB 分片，默认码率下相邻分片间隔约 0.5s
+    let (handle, send) =
+        sdk.send_cancellable(recipient, MessagePayload::Binary(vec![0u8; 16 * 32 * 1024]));
+    let cancel = async {
+        assert!(wait_for_sent(&channel, 1, Duration::from_secs(2)).await);
+        handle.cancel();
+    };
+    let (outcome, _) = tokio::join!(send, cancel);
+    assert_eq!(outcome.unwrap(), SendOutcome::Cancelled);
+    assert!(handle.is_cancelled());
+
+    // 取消后不再发出后续分片
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let stopped_at = channel.get_sent_messages().await.len();
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(channel.get_sent_messages().await.len(), stopped_at);
+    assert!(stopped_at < 16);
+}
+
+/// 发送永不完成的通道，用于在发送途中取消
+struct StalledChannel;
+
+#[async_trait::async_trait]
+impl Channel for StalledChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, _message: Message) -> xlink::core::error::Result<()> {
+        std::future::pending().await
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState {
+            available: true,
+            ..Default::default()
+        })
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_in_flight_send_removes_persisted_copy() {
+    let recipient = test_device_id();
+    let storage = Arc::new(MemoryStorage::new());
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![Arc::new(StalledChannel)],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+
+    let (handle, send) = sdk.send_cancellable(recipient, MessagePayload::Text("hi".to_string()));
+    let cancel = async {
+        // 等待消息落盘后再取消
+        while storage.list_messages().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        handle.cancel();
+    };
+    let (outcome, _) = tokio::join!(send, cancel);
+    assert_eq!(outcome.unwrap(), SendOutcome::Cancelled);
+    assert!(storage.list_messages().await.unwrap().is_empty());
+    assert!(storage.list_pending_messages().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_cancel_before_send_discards_message() {
+    let recipient = test_device_id();
+    let (sdk, channel) = connected_sdk(recipient).await;
+
+    let (handle, send) = sdk.send_cancellable(recipient, MessagePayload::Text("hi".to_string()));
+    handle.cancel();
+    assert_eq!(send.await.unwrap(), SendOutcome::Cancelled);
+    assert!(channel.get_sent_messages().await.is_empty());
+
+    // 未取消的发送正常完成
+    let (handle, send) = sdk.send_cancellable(recipient, MessagePayload::Text("hi".to_string()));
+    assert_eq!(
+        send.await.unwrap(),
+        SendOutcome::Sent {
+            message_id: handle.message_id()
+        }
+    );
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+}
+
+// ==================== Raw Chunk Delivery ====================
+
+fn chunk_message(
+    sender: DeviceId,
+    recipient: DeviceId,
+    stream_id: uuid::Uuid,
+    index: u32,
+    total: u32,
+) -> Message {
+    Message::new(
+        sender,
+        recipient,
+        MessagePayload::StreamChunk {
+            stream_id,
+            total_chunks: total,
+            chunk_index: index,
+            data: vec![index as u8; 16],
+            sent_at: 0,
+            fec_group_size: None,
+            compression: None,
+            checksum: None,
+            chunk_crc: None,
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_raw_stream_chunks_delivered_when_enabled() {
+    // UT-MED-012: 开启原样交付后，应用按到达顺序收到每个分片
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.set_stream_delivery_config(StreamDeliveryConfig {
+        deliver_raw_stream_chunks: true,
+    });
+    let handler = sdk.get_message_handler();
+    let stream_id = uuid::Uuid::new_v4();
+    let sender = test_device_id();
+
+    for index in [1, 0] {
+        handler
+            .handle_message(chunk_message(sender, sdk.device_id(), stream_id, index, 2))
+            .await
+            .unwrap();
+    }
+
+    for expected in [1, 0] {
+        let received = sdk.receive().await.unwrap();
+        match received.payload {
+            MessagePayload::StreamChunk {
+                stream_id: id,
+                chunk_index,
+                data,
+                ..
+            } => {
+                assert_eq!(id, stream_id);
+                assert_eq!(chunk_index, expected);
+                assert_eq!(data, vec![expected as u8; 16]);
+            }
+            other => panic!("unexpected payload: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_stream_chunks_reassembled_by_default() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let stream_id = uuid::Uuid::new_v4();
+    let sender = test_device_id();
+
+    for index in [1, 0] {
+        handler
+            .handle_message(chunk_message(sender, sdk.device_id(), stream_id, index, 2))
+            .await
+            .unwrap();
+    }
+
+    let received = sdk.receive().await.unwrap();
+    let mut expected = vec![0u8; 16];
+    expected.extend(vec![1u8; 16]);
+    assert_eq!(received.payload, MessagePayload::Binary(expected));
+}
+
+#[tokio::test]
+async fn test_same_stream_id_from_two_senders_reassembled_independently() {
+    // UT-MED-013: 不同发送方复用同一流 ID 时各自独立重组，互不串扰
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let stream_id = uuid::Uuid::new_v4();
+    let (alice, bob) = (test_device_id(), test_device_id());
+    let chunk = |sender: DeviceId, index: u32, fill: u8| {
+        Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::StreamChunk {
+                stream_id,
+                total_chunks: 2,
+                chunk_index: index,
+                data: vec![fill; 16],
+                sent_at: 0,
+                fec_group_size: None,
+                compression: None,
+                checksum: None,
+                chunk_crc: None,
+            },
+        )
+    };
+
+    // 分片交错到达：Alice 0、Bob 1、Bob 0、Alice 1
+    for message in [
+        chunk(alice, 0, 0xA0),
+        chunk(bob, 1, 0xB1),
+        chunk(bob, 0, 0xB0),
+        chunk(alice, 1, 0xA1),
+    ] {
+        handler.handle_message(message).await.unwrap();
+    }
+
+    let mut bob_data = vec![0xB0; 16];
+    bob_data.extend(vec![0xB1; 16]);
+    let mut alice_data = vec![0xA0; 16];
+    alice_data.extend(vec![0xA1; 16]);
+    for (sender, expected) in [(bob, bob_data), (alice, alice_data)] {
+        let received = sdk.receive().await.unwrap();
+        assert_eq!(received.sender, sender);
+        assert_eq!(received.payload, MessagePayload::Binary(expected));
+    }
+}
+
+// ==================== Forward Error Correction ====================
+
+fn stream_chunk_parts(message: &Message) -> (uuid::Uuid, u32, u32, Vec<u8>, Option<u32>) {
+    match &message.payload {
+        MessagePayload::StreamChunk {
+            stream_id,
+            total_chunks,
+            chunk_index,
+            data,
+            fec_group_size,
+            ..
+        } => (
+            *stream_id,
+            *total_chunks,
+            *chunk_index,
+            data.clone(),
+            *fec_group_size,
+        ),
+        other => panic!("unexpected payload: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_video_stream_fec_recovers_one_lost_chunk_per_group() {
+    // UT-MED-014: 每组丢失一个数据分片时由校验分片恢复，重组结果与原始数据一致
+    let recipient = test_device_id();
+    let (manager, channel) = connected_stream_manager(recipient).await;
+    let config = VideoConfig {
+        bitrate: 8_000_000,
+        fec_group_size: Some(2),
+        ..Default::default()
+    };
+    let video: Vec<u8> = (0..4 * 32 * 1024 + 100).map(|i| (i % 251) as u8).collect();
+    manager
+        .send_video_stream(recipient, video.clone(), Some(config))
+        .await
+        .unwrap();
+
+    // 5 个数据分片分为 3 组，每组数据分片之后紧跟校验分片
+    assert!(wait_for_sent(&channel, 8, Duration::from_secs(5)).await);
+    let sent = channel.get_sent_messages().await;
+    let parts: Vec<_> = sent.iter().map(stream_chunk_parts).collect();
+    let order: Vec<u32> = parts.iter().map(|(_, _, index, _, _)| *index).collect();
+    assert_eq!(order, vec![0, 1, 5, 2, 3, 6, 4, 7]);
+    assert!(parts
+        .iter()
+        .all(|(_, total, _, _, fec)| *total == 5 && *fec == Some(2)));
+
+    // 丢失分片 1 与最后一个较短的分片 4
+    let receiver = test_stream_manager();
+    let sender = test_device_id();
+    let mut result = None;
+    for (stream_id, total, index, data, fec) in parts.iter().cloned() {
+        if index == 1 || index == 4 {
+            continue;
+        }
+        if let Some(full) = receiver
+            .handle_chunk(sender, stream_id, total, index, data, fec)
+            .await
+            .unwrap()
+        {
+            result = Some(full);
+        }
+    }
+    assert_eq!(result, Some(video));
+
+    // 重组完成后迟到的校验分片被忽略
+    let (stream_id, total, index, data, fec) = parts[7].clone();
+    assert_eq!(
+        receiver
+            .handle_chunk(sender, stream_id, total, index, data, fec)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_fec_reports_first_unrecoverable_chunk() {
+    let receiver = test_stream_manager();
+    let (sender, stream_id) = (test_device_id(), uuid::Uuid::new_v4());
+    let chunks: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 16]).collect();
+
+    // 第二组丢失两个数据分片，超出单个校验分片的恢复能力
+    let mut outcome = Ok(None);
+    for (index, data) in xlink::media::fec::interleave_parity(chunks, 2) {
+        if index == 2 || index == 3 {
+            continue;
+        }
+        outcome = receiver
+            .handle_chunk(sender, stream_id, 4, index, data, Some(2))
+            .await;
+    }
+    let err = outcome.unwrap_err();
+    assert!(
+        err.original_message().contains("Missing chunk 2"),
+        "unexpected error: {}",
+        err.original_message()
+    );
+}
+
+#[tokio::test]
+async fn test_stream_integrity_rejects_corrupted_chunk_and_bad_digest() {
+    // UT-MED-018: CRC32 不符的分片被拒收且不计入重组，重发完好分片后通过 SHA-256 校验
+    let recipient = test_device_id();
+    let (manager, channel) = connected_stream_manager(recipient).await;
+    let config = VideoConfig {
+        bitrate: 8_000_000,
+        ..Default::default()
+    };
+    let video: Vec<u8> = (0..2 * 32 * 1024 + 10).map(|i| (i % 251) as u8).collect();
+    manager
+        .send_video_stream(recipient, video.clone(), Some(config))
+        .await
+        .unwrap();
+    assert!(wait_for_sent(&channel, 3, Duration::from_secs(5)).await);
+
+    let chunks: Vec<_> = channel
+        .get_sent_messages()
+        .await
+        .into_iter()
+        .filter_map(|m| match m.payload {
+            MessagePayload::StreamChunk {
+                stream_id,
+                total_chunks,
+                chunk_index,
+                data,
+                checksum,
+                chunk_crc,
+                ..
+            } => Some((
+                stream_id,
+                total_chunks,
+                chunk_index,
+                data,
+                ChunkIntegrity {
+                    checksum,
+                    chunk_crc,
+                },
+            )),
+            _ => None,
+        })
+        .collect();
+    assert!(chunks
+        .iter()
+        .all(|(.., verify)| verify.checksum.is_some() && verify.chunk_crc.is_some()));
+
+    let receiver = test_stream_manager();
+    let sender = test_device_id();
+    let (stream_id, total, _, _, _) = chunks[0];
+    for (_, _, index, data, verify) in chunks.iter().take(2).cloned() {
+        assert_eq!(
+            receiver
+                .handle_chunk_verified(sender, stream_id, total, index, data, None, verify)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    // 最后一个分片传输中损坏：拒收并指出分片序号，流不会以错误数据完成
+    let (_, _, index, mut data, verify) = chunks[2].clone();
+    data[0] ^= 0xFF;
+    let err = receiver
+        .handle_chunk_verified(sender, stream_id, total, index, data, None, verify)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 605);
+    assert!(err.original_message().contains("chunk 2"));
+
+    let (_, _, index, data, verify) = chunks[2].clone();
+    let full = receiver
+        .handle_chunk_verified(sender, stream_id, total, index, data, None, verify)
+        .await
+        .unwrap();
+    assert_eq!(full, Some(video));
+
+    // 分片各自完好但整体摘要不符时，重组后报告校验失败
+    let wrong_digest = ChunkIntegrity {
+        checksum: Some([0; 32]),
+        chunk_crc: None,
+    };
+    let other_stream = uuid::Uuid::new_v4();
+    let outcome = receiver
+        .handle_chunk_verified(sender, other_stream, 1, 0, vec![1; 16], None, wrong_digest)
+        .await;
+    assert_eq!(outcome.unwrap_err().code().0, 605);
+}
+
+#[tokio::test]
+async fn test_session_timeout_follows_injected_clock() {
+    // UT-MED-019: 会话超时按注入的时钟判定，推进模拟时钟即可触发清理，无需真实等待
+    let clock = Arc::new(MockClock::new());
+    let router = Arc::new(Router::new(HashMap::new(), create_test_cap_manager()));
+    let manager = StreamManager::with_clock(test_device_id(), router, clock.clone());
+    let sender = test_device_id();
+    let (stale, fresh) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+
+    let first_chunk = |stream_id| manager.handle_chunk(sender, stream_id, 2, 0, vec![0; 16], None);
+    assert_eq!(first_chunk(stale).await.unwrap(), None);
+    clock.advance(Duration::from_secs(200));
+    assert_eq!(first_chunk(fresh).await.unwrap(), None);
+
+    // 超时为 5 分钟：stale 已静默 350 秒，fresh 只静默 150 秒
+    clock.advance(Duration::from_secs(150));
+    manager.cleanup_timeout_sessions();
+
+    let last_chunk = |stream_id| manager.handle_chunk(sender, stream_id, 2, 1, vec![1; 16], None);
+    let mut expected = vec![0; 16];
+    expected.extend_from_slice(&[1; 16]);
+    assert_eq!(last_chunk(fresh).await.unwrap(), Some(expected));
+    assert_eq!(last_chunk(stale).await.unwrap(), None);
+}
+
+// ==================== Resumable Transfer ====================
+
+fn sent_chunk_indices(messages: &[Message]) -> Vec<u32> {
+    let mut indices: Vec<u32> = messages
+        .iter()
+        .filter_map(|m| match m.payload {
+            MessagePayload::StreamChunk { chunk_index, .. } => Some(chunk_index),
+            _ => None,
+        })
+        .collect();
+    indices.sort_unstable();
+    indices
+}
+
+#[tokio::test]
+async fn test_resume_transfer_resends_only_unacked_chunks() {
+    // UT-MED-015: 续传只重发未确认的分片，全部确认后清除持久化清单
+    let recipient = test_device_id();
+    let (manager, channel) = connected_stream_manager(recipient).await;
+    let storage = Arc::new(MemoryStorage::new());
+    manager.set_transfer_storage(Some(storage.clone()));
+    let config = VideoConfig {
+        bitrate: 2_000_000,
+        ..Default::default()
+    };
+
+    let stream_id = manager
+        .send_video_stream(recipient, vec![7u8; 4 * 32 * 1024], Some(config))
+        .await
+        .unwrap();
+    assert!(wait_for_sent(&channel, 4, Duration::from_secs(5)).await);
+    for index in [0, 2] {
+        manager.handle_chunk_ack(recipient, stream_id, index).await;
+    }
+    // 非接收方的确认被忽略
+    manager
+        .handle_chunk_ack(test_device_id(), stream_id, 1)
+        .await;
+    assert_eq!(
+        manager
+            .transfer_manifest(stream_id)
+            .unwrap()
+            .missing_chunks(),
+        vec![1, 3]
+    );
+    let key = format!("transfer_manifest_{}", stream_id.simple());
+    assert!(storage.load_metadata(&key).await.unwrap().is_some());
+
+    // 等待首轮发送结束后续传
+    while manager.stream_progress(stream_id).is_some() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    channel.clear_sent_messages().await;
+    assert_eq!(manager.resume_transfer(stream_id).await.unwrap(), 2);
+    assert!(wait_for_sent(&channel, 2, Duration::from_secs(5)).await);
+    assert_eq!(
+        sent_chunk_indices(&channel.get_sent_messages().await),
+        vec![1, 3]
+    );
+
+    for index in [1, 3] {
+        manager.handle_chunk_ack(recipient, stream_id, index).await;
+    }
+    assert!(manager.transfer_manifest(stream_id).is_none());
+    assert_eq!(storage.load_metadata(&key).await.unwrap(), Some(Vec::new()));
+    assert!(manager.resume_transfer(stream_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_received_data_chunks_are_acknowledged() {
+    let sender = test_device_id();
+    let (sdk, channel) = connected_sdk(sender).await;
+    let handler = sdk.get_message_handler();
+    let stream_id = uuid::Uuid::new_v4();
+
+    handler
+        .handle_message(chunk_message(sender, sdk.device_id(), stream_id, 1, 2))
+        .await
+        .unwrap();
+
+    assert!(wait_for_sent(&channel, 1, Duration::from_secs(2)).await);
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent[0].recipient, sender);
+    assert_eq!(
+        sent[0].payload,
+        MessagePayload::StreamChunkAck {
+            stream_id,
+            chunk_index: 1
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_expired_transfer_manifests_cleaned_up() {
+    let recipient = test_device_id();
+    let (manager, _channel) = connected_stream_manager(recipient).await;
+    let storage = Arc::new(MemoryStorage::new());
+    manager.set_transfer_storage(Some(storage.clone()));
+
+    let stream_id = manager
+        .send_video_stream(recipient, vec![0u8; 2 * 32 * 1024], None)
+        .await
+        .unwrap();
+    assert_eq!(manager.cleanup_expired_transfers(1).await, 0);
+    assert!(manager.transfer_manifest(stream_id).is_some());
+
+    // 保留期为 0 天时创建时间早于当前秒的清单即过期
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(manager.cleanup_expired_transfers(0).await, 1);
+    assert!(manager.transfer_manifest(stream_id).is_none());
+    let index = storage
+        .load_metadata("transfer_manifests")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(index, b"[]".to_vec());
+}
+
+// ==================== Payload Compression ====================
+
+/// 由 16 个字节值组成的伪随机数据，可压缩但压缩率有限
+fn low_entropy_bytes(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            b'a' + ((state >> 16) % 16) as u8
+        })
+        .collect()
+}
+
+async fn compressing_sdk(recipient: DeviceId) -> (XLink, Arc<MemoryChannel>) {
+    let (sdk, channel) = connected_sdk(recipient).await;
+    sdk.set_compression_config(CompressionConfig {
+        algorithm: Some(CompressionAlgorithm::Deflate),
+        min_size_bytes: 1024,
+        ..Default::default()
+    });
+    (sdk, channel)
+}
+
+#[tokio::test]
+async fn test_large_binary_compressed_below_stream_threshold_sent_directly() {
+    // UT-MED-016: 压缩后不足 32KB 的负载直接发送，接收端解压后交付原始数据
+    let receiver = TestSdkBuilder::new().build().await.unwrap();
+    let (sdk, channel) = compressing_sdk(receiver.device_id()).await;
+    let original = b"xlink compression ".repeat(12 * 1024);
+    assert!(original.len() > 32 * 1024);
+
+    sdk.send(
+        receiver.device_id(),
+        MessagePayload::Binary(original.clone()),
+    )
+    .await
+    .unwrap();
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    match &sent[0].payload {
+        MessagePayload::Compressed { algorithm, data } => {
+            assert_eq!(*algorithm, CompressionAlgorithm::Deflate);
+            assert!(data.len() < 32 * 1024);
+        }
+        other => panic!("expected compressed payload, got {:?}", other),
+    }
+
+    receiver
+        .get_message_handler()
+        .handle_message(sent[0].clone())
+        .await
+        .unwrap();
+    let received = receiver.receive().await.unwrap();
+    assert_eq!(received.payload, MessagePayload::Binary(original));
+}
+
+#[tokio::test]
+async fn test_high_entropy_binary_skips_compression() {
+    let peer = test_device_id();
+    let (sdk, channel) = compressing_sdk(peer).await;
+    let mut state = 0x9e37_79b9_u32;
+    let random: Vec<u8> = (0..8 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+
+    sdk.send(peer, MessagePayload::Binary(random.clone()))
+        .await
+        .unwrap();
+    assert_eq!(
+        channel.get_sent_messages().await[0].payload,
+        MessagePayload::Binary(random)
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_stream_decompressed_after_reassembly() {
+    let receiver = TestSdkBuilder::new().build().await.unwrap();
+    let (sdk, channel) = compressing_sdk(receiver.device_id()).await;
+    let original = low_entropy_bytes(100 * 1024);
+
+    sdk.send(
+        receiver.device_id(),
+        MessagePayload::Binary(original.clone()),
+    )
+    .await
+    .unwrap();
+    // 压缩后仍超过 32KB，经流式传输分为两个分片
+    assert!(wait_for_sent(&channel, 2, Duration::from_secs(5)).await);
+    let handler = receiver.get_message_handler();
+    for message in channel.get_sent_messages().await {
+        match &message.payload {
+            MessagePayload::StreamChunk { compression, .. } => {
+                assert_eq!(*compression, Some(CompressionAlgorithm::Deflate));
+            }
+            other => panic!("expected stream chunk, got {:?}", other),
+        }
+        handler.handle_message(message).await.unwrap();
+    }
+
+    let received = receiver.receive().await.unwrap();
+    assert_eq!(received.payload, MessagePayload::Binary(original));
+}
+
+// ==================== Auto Stream Threshold ====================
+
+#[tokio::test]
+async fn test_auto_stream_threshold_is_configurable() {
+    let recipient = test_device_id();
+    let (sdk, channel) = connected_sdk(recipient).await;
+    sdk.set_stream_config(StreamConfig {
+        auto_stream_threshold_bytes: Some(4 * 1024),
+    });
+
+    // 恰好等于阈值的负载走直接路径
+    let (_, send) = sdk.send_cancellable(recipient, MessagePayload::Binary(vec![1u8; 4 * 1024]));
+    assert!(matches!(send.await.unwrap(), SendOutcome::Sent { .. }));
+    assert!(matches!(
+        channel.get_sent_messages().await[0].payload,
+        MessagePayload::Binary(_)
+    ));
+
+    // 超过阈值的负载改经流式传输，并计入指标
+    let (_, send) = sdk.send_cancellable(recipient, MessagePayload::Binary(vec![2u8; 8 * 1024]));
+    assert!(matches!(send.await.unwrap(), SendOutcome::Streamed { .. }));
+    let sent = channel.get_sent_messages().await;
+    assert!(matches!(
+        sent.last().unwrap().payload,
+        MessagePayload::StreamChunk { .. }
+    ));
+    assert_eq!(sdk.metrics_report().total_streamed_sends, 1);
+}
+
+#[tokio::test]
+async fn test_disabled_auto_stream_chunks_large_binary_with_acknowledgement() {
+    // UT-MED-017: 关闭自动流式传输后，单分片以内的负载直接发送，更大的负载经可靠分片发送：
+    // 等待接收方确认全部分片，丢失的分片在确认超时后重发
+    let to_receiver = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let to_sender = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sender = TestSdkBuilder::new()
+        .with_channel(to_receiver.clone())
+        .build()
+        .await
+        .unwrap();
+    let receiver = TestSdkBuilder::new()
+        .with_channel(to_sender.clone())
+        .build()
+        .await
+        .unwrap();
+    sender.capability_manager().update_channel_state(
+        receiver.device_id(),
+        ChannelType::Lan,
+        to_receiver
+            .check_state(&receiver.device_id())
+            .await
+            .unwrap(),
+    );
+    receiver.capability_manager().update_channel_state(
+        sender.device_id(),
+        ChannelType::Lan,
+        to_sender.check_state(&sender.device_id()).await.unwrap(),
+    );
+    sender.set_stream_config(StreamConfig {
+        auto_stream_threshold_bytes: None,
+    });
+
+    let small = MessagePayload::Binary(vec![3u8; 32 * 1024]);
+    let (_, send) = sender.send_cancellable(receiver.device_id(), small.clone());
+    assert!(matches!(send.await.unwrap(), SendOutcome::Sent { .. }));
+    assert_eq!(to_receiver.get_sent_messages().await[0].payload, small);
+    to_receiver.clear_sent_messages().await;
+
+    // 双向转发，首次发出的分片 1 丢失
+    let relay = {
+        let (to_receiver, to_sender) = (to_receiver.clone(), to_sender.clone());
+        let (receiver_handler, sender_handler) =
+            (receiver.get_message_handler(), sender.get_message_handler());
+        tokio::spawn(async move {
+            let (mut forwarded, mut acked, mut dropped) = (0, 0, false);
+            loop {
+                let sent = to_receiver.get_sent_messages().await;
+                for message in sent[forwarded..].iter().cloned() {
+                    let lost = matches!(
+                        message.payload,
+                        MessagePayload::StreamChunk { chunk_index: 1, .. }
+                    ) && !dropped;
+                    if lost {
+                        dropped = true;
+                    } else {
+                        receiver_handler.handle_message(message).await.unwrap();
+                    }
+                }
+                forwarded = sent.len();
+                let acks = to_sender.get_sent_messages().await;
+                for message in acks[acked..].iter().cloned() {
+                    sender_handler.handle_message(message).await.unwrap();
+                }
+                acked = acks.len();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+    };
+
+    let original = vec![9u8; 4 * 32 * 1024];
+    let (_, send) = sender.send_cancellable(
+        receiver.device_id(),
+        MessagePayload::Binary(original.clone()),
+    );
+    let SendOutcome::Chunked { stream_id } = send.await.unwrap() else {
+        panic!("expected acknowledged chunked send");
+    };
+    relay.abort();
+    // 全部确认后不再保留续传数据
+    assert!(sender.resume_transfer(stream_id).await.is_err());
+    let received = receiver.receive().await.unwrap();
+    assert_eq!(received.payload, MessagePayload::Binary(original));
+
+    let sent = to_receiver.get_sent_messages().await;
+    let resent = sent
+        .iter()
+        .filter(|m| {
+            matches!(
+                m.payload,
+                MessagePayload::StreamChunk { chunk_index: 1, .. }
+            )
+        })
+        .count();
+    assert_eq!(resent, 2);
+    let metrics = sender.metrics_report();
+    assert_eq!(metrics.total_chunked_sends, 1);
+    assert_eq!(metrics.total_streamed_sends, 0);
+}
@@ -5,13 +5,41 @@
 
 mod common;
 
+use futures::StreamExt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
-use crate::common::{establish_device_sessions, test_device_id, NetworkSimulator, TestSdkBuilder};
-use xlink::core::traits::Storage;
-use xlink::core::types::{ChannelType, DeviceCapabilities, DeviceType, MessagePayload};
+use crate::common::{
+    establish_device_sessions, test_device_capabilities, test_device_id, test_device_with_battery,
+    NetworkSimulator, NoOpMessageHandler, TestSdkBuilder,
+};
+use xlink::capability::manager::CapabilityEvent;
+use xlink::channels::memory::MemoryChannel;
+use xlink::core::clock::MockClock;
+use xlink::core::error::RetrySuggestion;
+use xlink::core::events::SdkEvent;
+use xlink::core::metrics::MetricsCollector;
+use xlink::core::traits::{Channel, MessageHandler, Plugin, PluginAction, Storage};
+use xlink::core::types::{
+    AckStatus, AuditEntry, AuditFilter, AuditQueryConfig, BatteryPolicy, ChannelState, ChannelType,
+    ClockSkewAction, ClockSkewConfig, ComplianceConfig, CompressionConfig, DedupConfig,
+    DeliveryReceipt, DeviceCapabilities, DeviceId, DeviceType, GroupId, Message, MessageAgeConfig,
+    MessagePayload, MessagePriority, MetricsConfig, NetworkType, PreviousExit, RateLimitConfig,
+    RecoveryFilter, RoutingConfig, ShutdownConfig, ShutdownReason, StaleMessageAction,
+    MAX_AUDIT_QUERY_SCAN_BYTES,
+};
+use xlink::crypto::engine::{CryptoEngine, CryptoState};
+use xlink::crypto::state_seal::{is_sealed, StateKeyProvider, StaticStateKey};
+use xlink::media::stream_manager::UserTrafficPreferences;
 use xlink::storage::file_store::FileStorage;
+use xlink::storage::journal::{replay, JournalDirection, MessageJournal, REDACTED_TEXT};
+use xlink::storage::memory_store::{InMemoryStorage, MemoryStorage};
+use xlink::storage::migrate;
+use xlink::storage::sqlite_store::SqliteStorage;
+use xlink::storage::versioned::{self, RecordKind, CURRENT_RECORD_VERSION};
+use xlink::{XLink, XLinkBuilder, SDK_STATE_KEY};
 
 // ==================== End-to-End User Scenarios ====================
 
@@ -82,6 +110,214 @@ async fn test_emergency_communication_scenario() {
 
 // ==================== Storage Management ====================
 
+#[tokio::test]
+async fn test_file_storage_reads_unversioned_records() {
+    // 引入版本信封之前写入的 v1 记录（裸 JSON）在当前版本下仍可读取
+    let storage_path = "./test_storage_versioned";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    let legacy = Message::new(sender, recipient, MessagePayload::Text("v1".to_string()));
+    let device_dir = std::path::Path::new(storage_path).join(recipient.to_string());
+    tokio::fs::create_dir_all(&device_dir).await.unwrap();
+    tokio::fs::write(
+        device_dir.join(format!("{}.json", legacy.id)),
+        serde_json::to_vec(&legacy).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let storage = FileStorage::new(storage_path).await.unwrap();
+    let messages = storage.get_pending_messages(&recipient).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, legacy.id);
+    assert_eq!(messages[0].payload, legacy.payload);
+
+    // 新写入的记录带当前版本号
+    let current = Message::new(sender, recipient, MessagePayload::Text("v2".to_string()));
+    storage.save_message(&current).await.unwrap();
+    let raw = tokio::fs::read(device_dir.join(format!("{}.json", current.id)))
+        .await
+        .unwrap();
+    let envelope: serde_json::Value = serde_json::from_slice(&raw).unwrap();
+    assert_eq!(envelope["v"], CURRENT_RECORD_VERSION);
+    assert_eq!(storage.list_messages().await.unwrap().len(), 2);
+
+    // 未知的未来版本返回明确的序列化错误
+    let future = serde_json::json!({ "v": CURRENT_RECORD_VERSION + 1, "data": legacy });
+    let err =
+        versioned::decode::<Message>(RecordKind::Message, &serde_json::to_vec(&future).unwrap())
+            .unwrap_err();
+    assert_eq!(err.code().0, 103);
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_encrypted_file_storage_round_trip() {
+    let storage_path = "./test_storage_encrypted";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let key = [7u8; 32];
+    let storage = FileStorage::new_encrypted(storage_path, key).await.unwrap();
+
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    let msg = Message::new(
+        sender,
+        recipient,
+        MessagePayload::Text("top secret".to_string()),
+    );
+    storage.save_message(&msg).await.unwrap();
+    storage.save_pending_message(&msg).await.unwrap();
+
+    // 磁盘上不出现明文
+    let raw = tokio::fs::read(
+        std::path::Path::new(storage_path)
+            .join(recipient.to_string())
+            .join(format!("{}.json", msg.id)),
+    )
+    .await
+    .unwrap();
+    assert!(is_sealed(&raw));
+    assert!(!String::from_utf8_lossy(&raw).contains("top secret"));
+
+    // 重新打开后索引仅依据文件名重建，内容透明解密
+    drop(storage);
+    let storage = FileStorage::new_encrypted(storage_path, key).await.unwrap();
+    let messages = storage.get_pending_messages(&recipient).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].payload, msg.payload);
+    assert_eq!(
+        storage
+            .get_pending_messages_for_recovery(&sender)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // 损坏的记录被跳过，不影响其余消息的恢复
+    let corrupt_dir = std::path::Path::new(storage_path).join(recipient.to_string());
+    tokio::fs::write(
+        corrupt_dir.join(format!("{}.json", uuid::Uuid::new_v4())),
+        b"not a record",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        storage
+            .get_pending_messages(&recipient)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // 密钥错误时无法解密的记录同样被跳过
+    let wrong_key = FileStorage::new_encrypted(storage_path, [8u8; 32])
+        .await
+        .unwrap();
+    assert!(wrong_key
+        .get_pending_messages(&recipient)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(wrong_key.list_pending_messages().await.unwrap().is_empty());
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_encrypted_file_storage_binds_records_to_id_and_kind() {
+    let storage_path = "./test_storage_encrypted_binding";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let storage = FileStorage::new_encrypted(storage_path, [7u8; 32])
+        .await
+        .unwrap();
+
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    let first = Message::new(sender, recipient, MessagePayload::Text("first".to_string()));
+    let second = Message::new(
+        sender,
+        recipient,
+        MessagePayload::Text("second".to_string()),
+    );
+    storage.save_message(&first).await.unwrap();
+    storage.save_message(&second).await.unwrap();
+    storage.save_pending_message(&second).await.unwrap();
+
+    // 把一条记录的密文拷到另一条消息的文件下，无法通过校验
+    let base = std::path::Path::new(storage_path);
+    let message_file = |id: uuid::Uuid| {
+        base.join(recipient.to_string())
+            .join(format!("{}.json", id))
+    };
+    let first_record = tokio::fs::read(message_file(first.id)).await.unwrap();
+    tokio::fs::write(message_file(second.id), &first_record)
+        .await
+        .unwrap();
+    let messages = storage.get_pending_messages(&recipient).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, first.id);
+
+    // 消息记录的密文不能冒充同 ID 的待发送记录
+    let pending_file = base
+        .join("pending")
+        .join(sender.to_string())
+        .join(format!("{}.json", second.id));
+    storage.save_message(&second).await.unwrap();
+    let second_record = tokio::fs::read(message_file(second.id)).await.unwrap();
+    tokio::fs::write(&pending_file, &second_record)
+        .await
+        .unwrap();
+    assert!(storage
+        .get_pending_messages_for_recovery(&sender)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_plaintext_file_storage_reports_corrupt_records() {
+    let storage_path = "./test_storage_plaintext_corrupt";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let storage = FileStorage::new(storage_path).await.unwrap();
+
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    let msg = Message::new(sender, recipient, MessagePayload::Text("hello".to_string()));
+    storage.save_message(&msg).await.unwrap();
+    storage.save_pending_message(&msg).await.unwrap();
+
+    // 未启用加密时，损坏的记录仍以错误上报而不是被静默丢弃
+    let base = std::path::Path::new(storage_path);
+    tokio::fs::write(
+        base.join(recipient.to_string())
+            .join(format!("{}.json", uuid::Uuid::new_v4())),
+        b"not a record",
+    )
+    .await
+    .unwrap();
+    tokio::fs::write(
+        base.join("pending")
+            .join(sender.to_string())
+            .join(format!("{}.json", uuid::Uuid::new_v4())),
+        b"not a record",
+    )
+    .await
+    .unwrap();
+    assert!(storage.get_pending_messages(&recipient).await.is_err());
+    assert!(storage
+        .get_pending_messages_for_recovery(&sender)
+        .await
+        .is_err());
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
 #[tokio::test]
 async fn test_storage_cleanup() {
     // UT-STO-001: 存储清理逻辑
@@ -113,49 +349,2761 @@ async fn test_storage_cleanup() {
     let _ = tokio::fs::remove_dir_all(storage_path).await;
 }
 
-// ==================== Crash Recovery ====================
+#[tokio::test]
+async fn test_in_memory_storage_usage_and_cleanup() {
+    let storage = InMemoryStorage::new();
+    let sender = test_device_id();
+    let recipient = test_device_id();
+
+    let messages: Vec<_> = (0..20)
+        .map(|i| {
+            Message::new(
+                sender,
+                recipient,
+                MessagePayload::Text(format!("Msg {}", i)),
+            )
+        })
+        .collect();
+    for msg in &messages {
+        storage.save_message(msg).await.unwrap();
+    }
+    // 重复写入同一消息替换旧记录
+    storage.save_message(&messages[0]).await.unwrap();
+
+    let expected: u64 = messages
+        .iter()
+        .map(|m| serde_json::to_vec(m).unwrap().len() as u64)
+        .sum();
+    assert_eq!(storage.get_storage_usage().await.unwrap(), expected);
+
+    // 刚写入的数据不会被按天清理
+    assert_eq!(storage.cleanup_old_data(1).await.unwrap(), 0);
+
+    // 按容量清理从最早写入的记录开始
+    let usage = storage.get_storage_usage().await.unwrap();
+    let removed = storage.cleanup_storage(usage / 2).await.unwrap();
+    assert!(removed >= usage / 2);
+    assert!(storage.get_storage_usage().await.unwrap() <= usage / 2);
+    let remaining = storage.list_messages().await.unwrap();
+    assert!(!remaining.is_empty());
+    assert!(remaining.iter().all(|m| m.id != messages[1].id));
+    assert!(remaining.iter().any(|m| m.id == messages[19].id));
+
+    // 清理索引不会丢失数据，且可向下转型
+    storage.clear_indexes();
+    assert_eq!(
+        storage.list_messages().await.unwrap().len(),
+        remaining.len()
+    );
+    let dyn_storage: Arc<dyn Storage> = Arc::new(storage);
+    assert!(dyn_storage
+        .as_any()
+        .downcast_ref::<InMemoryStorage>()
+        .is_some());
+}
 
 #[tokio::test]
-async fn test_system_recovery_after_restart() {
-    // IT-REC-001: 重启后的状态恢复
-    let storage_path = "./test_recovery_sys";
+async fn test_migrate_file_storage_to_memory() {
+    let storage_path = "./test_storage_migrate";
     let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let source = FileStorage::new(storage_path).await.unwrap();
 
-    let caps = DeviceCapabilities {
-        device_id: test_device_id(),
-        device_type: DeviceType::Smartphone,
-        device_name: "Recovery Device".to_string(),
-        supported_channels: [ChannelType::Lan].into_iter().collect(),
-        battery_level: Some(100),
-        is_charging: true,
-        data_cost_sensitive: false,
-    };
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    for i in 0..5 {
+        let msg = Message::new(
+            sender,
+            recipient,
+            MessagePayload::Text(format!("Msg {}", i)),
+        );
+        source.save_message(&msg).await.unwrap();
+    }
+    for i in 0..3 {
+        let msg = Message::new(
+            sender,
+            recipient,
+            MessagePayload::Text(format!("Pending {}", i)),
+        );
+        source.save_pending_message(&msg).await.unwrap();
+    }
+    source.save_audit_log("login".to_string()).await.unwrap();
+    source.save_audit_log("logout".to_string()).await.unwrap();
 
-    // 1. First run: Create a group and save state
-    {
-        let sdk = TestSdkBuilder::new()
-            .with_device_capabilities(caps.clone())
-            .with_storage_path(storage_path.to_string())
-            .build()
+    let target = MemoryStorage::new();
+    let report = migrate(&source, &target).await.unwrap();
+    assert_eq!(report.messages_copied, 5);
+    assert_eq!(report.pending_copied, 3);
+    assert_eq!(report.audit_logs_copied, 2);
+
+    let mut expected: Vec<_> = source.list_messages().await.unwrap();
+    let mut migrated: Vec<_> = target.get_pending_messages(&recipient).await.unwrap();
+    expected.sort_by_key(|m| m.id);
+    migrated.sort_by_key(|m| m.id);
+    assert_eq!(
+        expected
+            .iter()
+            .map(|m| (m.id, &m.payload))
+            .collect::<Vec<_>>(),
+        migrated
+            .iter()
+            .map(|m| (m.id, &m.payload))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(target.list_pending_messages().await.unwrap().len(), 3);
+    let mut logs = target.get_audit_logs(10).await.unwrap();
+    logs.sort();
+    assert_eq!(logs, vec!["login".to_string(), "logout".to_string()]);
+
+    // 重复执行时跳过已迁移的数据
+    let report = migrate(&source, &target).await.unwrap();
+    assert_eq!(report.total_copied(), 0);
+    assert_eq!(report.messages_skipped, 5);
+    assert_eq!(report.pending_skipped, 3);
+    assert_eq!(report.audit_logs_skipped, 2);
+    assert_eq!(target.list_messages().await.unwrap().len(), 5);
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_sqlite_storage_round_trip() {
+    // UT-STO-002: SQLite 存储与 FileStorage 行为一致
+    let storage_dir = "./test_storage_sqlite";
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+    let db_path = format!("{}/xlink.db", storage_dir);
+
+    // 数据库路径同样拒绝路径遍历
+    assert!(SqliteStorage::new("./test_storage_sqlite/../escape.db")
+        .await
+        .is_err());
+
+    let storage = SqliteStorage::new(&db_path).await.unwrap();
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    let messages: Vec<_> = (0..5)
+        .map(|i| {
+            Message::new(
+                sender,
+                recipient,
+                MessagePayload::Text(format!("Msg {}", i)),
+            )
+        })
+        .collect();
+    for msg in &messages {
+        storage.save_message(msg).await.unwrap();
+        storage.save_pending_message(msg).await.unwrap();
+    }
+    assert_eq!(
+        storage
+            .get_pending_messages(&recipient)
+            .await
+            .unwrap()
+            .len(),
+        5
+    );
+    assert!(storage
+        .get_pending_messages(&sender)
+        .await
+        .unwrap()
+        .is_empty());
+    // 待发送消息按发送方查询
+    assert_eq!(
+        storage
+            .get_pending_messages_for_recovery(&sender)
+            .await
+            .unwrap()
+            .len(),
+        5
+    );
+
+    storage.remove_message(&messages[0].id).await.unwrap();
+    storage
+        .remove_pending_message(&messages[1].id)
+        .await
+        .unwrap();
+    assert_eq!(storage.list_messages().await.unwrap().len(), 4);
+    assert_eq!(storage.list_pending_messages().await.unwrap().len(), 4);
+
+    for log in ["first", "second", "third"] {
+        storage.save_audit_log(log.to_string()).await.unwrap();
+    }
+    assert_eq!(
+        storage.get_audit_logs(2).await.unwrap(),
+        vec!["third".to_string(), "second".to_string()]
+    );
+    let page = storage.get_audit_logs_paged(1, 1, u64::MAX).await.unwrap();
+    assert_eq!(page.entries, vec!["second".to_string()]);
+    assert_eq!(page.next_offset, Some(2));
+
+    storage.save_metadata("key", vec![1, 2, 3]).await.unwrap();
+    assert_eq!(
+        storage.load_metadata("key").await.unwrap(),
+        Some(vec![1, 2, 3])
+    );
+    assert_eq!(storage.load_metadata("missing").await.unwrap(), None);
+
+    // 刚写入的数据不会被按天清理
+    assert_eq!(storage.cleanup_old_data(1).await.unwrap(), 0);
+    assert!(storage.get_storage_usage().await.unwrap() > 0);
+
+    // 重新打开后数据仍在，且可整体迁移到其他后端
+    drop(storage);
+    let storage = SqliteStorage::new(&db_path).await.unwrap();
+    let target = MemoryStorage::new();
+    let report = migrate(&storage, &target).await.unwrap();
+    assert_eq!(report.messages_copied, 4);
+    assert_eq!(report.pending_copied, 4);
+    assert_eq!(report.audit_logs_copied, 3);
+
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+}
+
+#[tokio::test]
+async fn test_sqlite_storage_cleanup() {
+    let storage_dir = "./test_storage_sqlite_cleanup";
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+    let storage = SqliteStorage::new(format!("{}/xlink.db", storage_dir))
+        .await
+        .unwrap();
+
+    let sender = test_device_id();
+    let recipient = test_device_id();
+    // 最早写入的待发送消息也不会被按容量清理
+    let queued = Message::new(
+        sender,
+        recipient,
+        MessagePayload::Text("queued".to_string()),
+    );
+    storage.save_pending_message(&queued).await.unwrap();
+    for i in 0..200 {
+        let msg = Message::new(
+            sender,
+            recipient,
+            MessagePayload::Text(format!("Msg {} {}", i, "x".repeat(2048))),
+        );
+        storage.save_message(&msg).await.unwrap();
+    }
+
+    let usage = storage.get_storage_usage().await.unwrap();
+    assert!(usage > 0);
+
+    storage.cleanup_storage(usage / 2).await.unwrap();
+    let final_usage = storage.get_storage_usage().await.unwrap();
+    assert!(final_usage <= usage / 2 + 64 * 1024);
+    assert!(!storage.list_messages().await.unwrap().is_empty());
+    let pending = storage.list_pending_messages().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, queued.id);
+
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+}
+
+#[tokio::test]
+async fn test_audit_logs_paged_over_large_log() {
+    let storage_path = "./test_storage_audit_paging";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let sdk = TestSdkBuilder::new()
+        .with_storage_path(storage_path.to_string())
+        .build()
+        .await
+        .unwrap();
+
+    // 合成大体量审计日志：300 条、每条约 1KB，文件名为写入时间戳
+    let audit_dir = std::path::Path::new(storage_path).join("audit");
+    tokio::fs::create_dir_all(&audit_dir).await.unwrap();
+    let entry = |i: usize| format!("entry-{:04} {}", i, "x".repeat(1000));
+    for i in 0..300 {
+        tokio::fs::write(audit_dir.join(format!("{}.log", 1_000_000 + i)), entry(i))
             .await
             .unwrap();
+    }
 
-        sdk.create_group("Persistent Group".to_string(), vec![caps.device_id])
+    // 逐页读取覆盖全部条目，从新到旧且不重不漏
+    let mut collected = Vec::new();
+    let mut offset = Some(0);
+    while let Some(current) = offset {
+        let page = sdk.get_audit_logs_paged(current, 40).await.unwrap();
+        assert!(!page.partial);
+        assert!(page.entries.len() <= 40);
+        collected.extend(page.entries);
+        offset = page.next_offset;
+    }
+    assert_eq!(collected, (0..300).rev().map(entry).collect::<Vec<_>>());
+
+    // 扫描字节上限：提前返回部分结果，可从 next_offset 继续
+    sdk.set_audit_query_config(AuditQueryConfig {
+        max_bytes_scanned: 4500,
+    });
+    let page = sdk.get_audit_logs_paged(100, 40).await.unwrap();
+    assert!(page.partial);
+    assert_eq!(
+        page.entries,
+        (196..200).rev().map(entry).collect::<Vec<_>>()
+    );
+    assert_eq!(page.next_offset, Some(104));
+
+    // 上限小于单条大小时仍返回一条，保证分页推进
+    sdk.set_audit_query_config(AuditQueryConfig {
+        max_bytes_scanned: 10,
+    });
+    let page = sdk.get_audit_logs_paged(299, 40).await.unwrap();
+    assert_eq!(page.entries, vec![entry(0)]);
+    assert!(!page.partial);
+    assert_eq!(page.next_offset, None);
+    assert!(sdk
+        .get_audit_logs_paged(300, 40)
+        .await
+        .unwrap()
+        .entries
+        .is_empty());
+
+    // 内存存储按相同语义分页
+    let memory = MemoryStorage::new();
+    for i in 0..10 {
+        memory.save_audit_log(format!("log {}", i)).await.unwrap();
+    }
+    let page = memory.get_audit_logs_paged(2, 3, 1024).await.unwrap();
+    assert_eq!(page.entries, vec!["log 7", "log 6", "log 5"]);
+    assert_eq!(page.next_offset, Some(5));
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+/// 向存储写入一组审计记录并校验按时间、设备与操作过滤的分页查询
+async fn assert_audit_query_filters(storage: &dyn Storage, local: DeviceId) {
+    let other = test_device_id();
+    let entry = |timestamp: u64, device_id: DeviceId, action: &str| AuditEntry {
+        timestamp,
+        device_id: Some(device_id),
+        action: action.to_string(),
+        details: None,
+    };
+    storage
+        .save_audit_log("legacy plain text key rotation".to_string())
+        .await
+        .unwrap();
+    for (i, timestamp) in (1_000..1_010).enumerate() {
+        let device = if i % 2 == 0 { local } else { other };
+        let action = if i < 5 { "key rotation" } else { "group join" };
+        storage
+            .save_audit_entry(&entry(timestamp, device, action))
             .await
             .unwrap();
-    } // SDK dropped here
+    }
 
-    // 2. Second run: Recovery
+    // 按操作子串过滤，旧格式的纯文本日志同样参与匹配
+    let page = storage
+        .query_audit_logs(
+            &AuditFilter {
+                action_contains: Some("key rotation".to_string()),
+                ..Default::default()
+            },
+            0,
+            100,
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.total, 6);
+    assert_eq!(page.entries[0].timestamp, 1_004);
+    assert_eq!(page.entries[5].device_id, None);
+
+    // 时间范围与设备组合过滤，结果从新到旧分页
+    let filter = AuditFilter {
+        since: Some(1_002),
+        until: Some(1_009),
+        device_id: Some(local),
+        ..Default::default()
+    };
+    let first = storage.query_audit_logs(&filter, 0, 2).await.unwrap();
+    let second = storage.query_audit_logs(&filter, 1, 2).await.unwrap();
+    assert_eq!(first.total, 4);
+    let timestamps: Vec<u64> = first
+        .entries
+        .iter()
+        .chain(&second.entries)
+        .map(|e| e.timestamp)
+        .collect();
+    assert_eq!(timestamps, vec![1_008, 1_006, 1_004, 1_002]);
+    assert!(storage
+        .query_audit_logs(&filter, 2, 2)
+        .await
+        .unwrap()
+        .entries
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_query_audit_logs_filters_by_time_device_and_action() {
+    let storage = Arc::new(MemoryStorage::new());
+    let local = test_device_id();
+    assert_audit_query_filters(storage.as_ref(), local).await;
+
+    // SDK 的管理操作写入结构化记录
+    let sdk = XLink::with_storage(test_device_capabilities(), vec![], storage.clone())
+        .await
+        .unwrap();
+    let peer = test_device_id();
+    let peer_key = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    sdk.register_device_key(peer, peer_key).unwrap();
+    sdk.revoke_peer_key(peer).await.unwrap();
+    let page = sdk
+        .query_audit_logs(
+            AuditFilter {
+                device_id: Some(sdk.device_id()),
+                action_contains: Some("Revoked peer key".to_string()),
+                ..Default::default()
+            },
+            0,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.total, 1);
+    assert!(page.entries[0].action.contains(&peer.to_string()));
+    assert!(page.entries[0].timestamp > 1_010);
+}
+
+#[tokio::test]
+async fn test_sqlite_query_audit_logs_filters_in_sql() {
+    let storage_dir = "./test_storage_sqlite_audit_query";
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+    let path = format!("{}/xlink.db", storage_dir);
+    let local = test_device_id();
+
+    // 旧版本创建的审计日志表没有结构化列，打开时补充并回填
+    tokio::fs::create_dir_all(storage_dir).await.unwrap();
+    let legacy = AuditEntry {
+        timestamp: 900,
+        device_id: Some(local),
+        action: "legacy entry".to_string(),
+        details: None,
+    };
     {
-        let _sdk = TestSdkBuilder::new()
-            .with_device_capabilities(caps)
-            .with_storage_path(storage_path.to_string())
-            .build()
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE audit_logs (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 created_at INTEGER NOT NULL,
+                 entry TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO audit_logs (created_at, entry) VALUES (0, ?1)",
+            [serde_json::to_string(&legacy).unwrap()],
+        )
+        .unwrap();
+    }
+    let storage = SqliteStorage::new(&path).await.unwrap();
+    let page = storage
+        .query_audit_logs(
+            &AuditFilter {
+                until: Some(1_000),
+                device_id: Some(local),
+                ..Default::default()
+            },
+            0,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.entries, vec![legacy]);
+
+    storage.cleanup_old_data(0).await.unwrap();
+    assert_audit_query_filters(&storage, local).await;
+
+    drop(storage);
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+}
+
+#[tokio::test]
+async fn test_query_audit_logs_default_stops_at_scan_limit() {
+    let storage = MemoryStorage::new();
+    let device = test_device_id();
+    let details = "x".repeat(256 * 1024);
+    let count = (MAX_AUDIT_QUERY_SCAN_BYTES / details.len() as u64) as usize + 8;
+    for i in 0..count {
+        storage
+            .save_audit_entry(&AuditEntry {
+                timestamp: i as u64,
+                device_id: Some(device),
+                action: "bulk".to_string(),
+                details: Some(details.clone()),
+            })
+            .await
+            .unwrap();
+    }
+
+    // 扫描量达到上限后停止，返回已扫描部分的结果
+    let page = storage
+        .query_audit_logs(&AuditFilter::default(), 0, 2)
+        .await
+        .unwrap();
+    assert!(page.partial);
+    assert!(page.total < count);
+    let timestamps: Vec<u64> = page.entries.iter().map(|e| e.timestamp).collect();
+    assert_eq!(timestamps, vec![count as u64 - 1, count as u64 - 2]);
+}
+
+#[test]
+fn test_metrics_render_prometheus_text() {
+    let metrics = Arc::new(MetricsCollector::new());
+    metrics.record_send(ChannelType::Lan, 100);
+    metrics.record_send(ChannelType::Lan, 200);
+    metrics.record_send(ChannelType::BluetoothLE, 50);
+    metrics.record_receive(42);
+
+    // 渲染与计数并发进行
+    let writer = {
+        let metrics = metrics.clone();
+        std::thread::spawn(move || {
+            for _ in 0..1000 {
+                metrics.record_send(ChannelType::Internet, 1);
+            }
+        })
+    };
+    for _ in 0..10 {
+        assert!(metrics.to_prometheus().ends_with('\n'));
+    }
+    writer.join().unwrap();
+
+    let text = metrics.to_prometheus();
+    assert!(text.ends_with('\n'));
+    assert!(text.contains("# TYPE xlink_bytes_sent_total counter\n"));
+    assert!(text.contains("xlink_bytes_sent_total{channel=\"Lan\"} 300\n"));
+    assert!(text.contains("xlink_bytes_sent_total{channel=\"BluetoothLE\"} 50\n"));
+    assert!(text.contains("xlink_bytes_sent_total{channel=\"Internet\"} 1000\n"));
+    assert!(text.contains("xlink_messages_sent_total 1003\n"));
+    assert!(text.contains("xlink_messages_received_total 1\n"));
+    assert!(text.contains("xlink_bytes_received_total 42\n"));
+
+    // 每个样本都属于前面已声明 TYPE 的指标族，且格式为 `名称{标签} 数值`
+    let mut declared = std::collections::HashSet::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let mut parts = rest.split(' ');
+            declared.insert(parts.next().unwrap().to_string());
+            assert!(matches!(parts.next(), Some("counter") | Some("gauge")));
+        } else if !line.starts_with("# HELP ") {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            let name = series.split('{').next().unwrap();
+            assert!(declared.contains(name), "undeclared metric: {}", line);
+            assert!(value.parse::<f64>().is_ok(), "bad value: {}", line);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_metrics_survive_restart_when_persisted() {
+    let storage_path = "./test_storage_metrics";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let recipient = test_device_id();
+
+    let sdk = TestSdkBuilder::new()
+        .with_storage_path(storage_path.to_string())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_metrics_config(MetricsConfig {
+        persist_across_restarts: true,
+    });
+    for i in 0..3 {
+        sdk.send(recipient, MessagePayload::Text(format!("Msg {}", i)))
             .await
             .unwrap();
-        // Check if group exists (using internal state access if needed, or just verifying no crash)
-        // For now, we assume recovery works if the SDK starts without error
     }
+    let before = sdk.metrics_report();
+    assert!(before.total_sent > 0);
+    sdk.start().await.unwrap();
+    sdk.stop().await;
+
+    // 同一实例重新启动
+    sdk.start().await.unwrap();
+    assert_eq!(sdk.metrics_report().total_sent, before.total_sent);
+    assert_eq!(
+        sdk.metrics_report().total_bytes_sent,
+        before.total_bytes_sent
+    );
+    sdk.stop().await;
+    drop(sdk);
+
+    // 新进程（新实例）使用同一存储
+    let restarted = TestSdkBuilder::new()
+        .with_storage_path(storage_path.to_string())
+        .build()
+        .await
+        .unwrap();
+    restarted.set_metrics_config(MetricsConfig {
+        persist_across_restarts: true,
+    });
+    restarted.start().await.unwrap();
+    let after = restarted.metrics_report();
+    assert_eq!(after.total_sent, before.total_sent);
+    assert_eq!(after.total_bytes_sent, before.total_bytes_sent);
+    restarted.stop().await;
 
+    // 未开启持久化时计数从零开始
+    let ephemeral = TestSdkBuilder::new()
+        .with_storage_path(storage_path.to_string())
+        .build()
+        .await
+        .unwrap();
+    ephemeral.start().await.unwrap();
+    assert_eq!(ephemeral.metrics_report().total_sent, 0);
+    ephemeral.stop().await;
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_metered_budget_blocks_sends_until_reset() {
+    let storage_path = "./test_storage_traffic_budget";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let recipient = test_device_id();
+    let build = || async {
+        let internet = Arc::new(
+            MemoryChannel::new(Arc::new(NoOpMessageHandler), 1).with_type(ChannelType::Internet),
+        );
+        let sdk = TestSdkBuilder::new()
+            .with_channel(internet.clone())
+            .with_storage_path(storage_path.to_string())
+            .build()
+            .await
+            .unwrap();
+        sdk.set_traffic_preferences(UserTrafficPreferences {
+            monthly_data_limit_mb: 1,
+            enable_data_saver: true,
+            ..UserTrafficPreferences::default()
+        });
+        (sdk, internet)
+    };
+
+    // 启动后后台能力检测会立即探测并覆盖本地网络类型，待其首轮完成后再设置
+    let settle_detection = || tokio::time::sleep(Duration::from_millis(100));
+    let (sdk, internet) = build().await;
+    sdk.start().await.unwrap();
+    settle_detection().await;
+
+    // WiFi 下的互联网流量不计入预算
+    sdk.capability_manager()
+        .update_local_network_type(NetworkType::WiFi);
+    let full_mb = "x".repeat(1024 * 1024);
+    sdk.send(recipient, MessagePayload::Text(full_mb.clone()))
+        .await
+        .unwrap();
+    assert_eq!(sdk.remaining_budget_mb(), 1);
+
+    // 蜂窝网络下用满 1 MB 后，普通消息被拒绝
+    sdk.capability_manager()
+        .update_local_network_type(NetworkType::Cellular4G);
+    sdk.send(recipient, MessagePayload::Text(full_mb))
+        .await
+        .unwrap();
+    assert_eq!(sdk.remaining_budget_mb(), 0);
+    internet.clear_sent_messages().await;
+    let err = sdk
+        .send(recipient, MessagePayload::Text("over budget".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+    assert!(internet.get_sent_messages().await.is_empty());
+
+    // 紧急消息不受预算限制
+    sdk.send_with_priority(
+        recipient,
+        MessagePayload::Text("critical".to_string()),
+        MessagePriority::Critical,
+    )
+    .await
+    .unwrap();
+    assert_eq!(internet.get_sent_messages().await.len(), 1);
+    sdk.stop().await;
+    drop(sdk);
+
+    // 用量跨重启保留
+    let (restarted, _) = build().await;
+    restarted.start().await.unwrap();
+    settle_detection().await;
+    restarted
+        .capability_manager()
+        .update_local_network_type(NetworkType::Cellular5G);
+    assert_eq!(restarted.remaining_budget_mb(), 0);
+    assert!(restarted
+        .send(recipient, MessagePayload::Text("still over".to_string()))
+        .await
+        .is_err());
+
+    // 新计费周期恢复发送
+    restarted.reset_traffic_budget().await.unwrap();
+    assert_eq!(restarted.remaining_budget_mb(), 1);
+    restarted
+        .send(recipient, MessagePayload::Text("new cycle".to_string()))
+        .await
+        .unwrap();
+    restarted.stop().await;
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_metered_budget_covers_streamed_and_group_sends() {
+    let internet = Arc::new(
+        MemoryChannel::new(Arc::new(NoOpMessageHandler), 1).with_type(ChannelType::Internet),
+    );
+    let sdk = TestSdkBuilder::new()
+        .with_channel(internet.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_traffic_preferences(UserTrafficPreferences {
+        monthly_data_limit_mb: 1,
+        enable_data_saver: true,
+        ..UserTrafficPreferences::default()
+    });
+    sdk.capability_manager()
+        .update_local_network_type(NetworkType::Cellular4G);
+
+    let recipient = test_device_id();
+    let member = test_device_id();
+    for peer in [recipient, member] {
+        sdk.capability_manager().update_channel_state(
+            peer,
+            ChannelType::Internet,
+            ChannelState {
+                available: true,
+                rtt_ms: 10,
+                bandwidth_bps: 10_000_000,
+                packet_loss_rate: 0.0,
+                ..Default::default()
+            },
+        );
+    }
+    let group_id = sdk
+        .create_group("Metered".to_string(), vec![member])
+        .await
+        .unwrap();
+
+    // 超过自动流式阈值的二进制数据分片发出，分片流量计入预算；随机数据避免被压缩到阈值以下
+    let large: Vec<u8> = (0..64 * 1024).map(|_| rand::random::<u8>()).collect();
+    sdk.send(recipient, MessagePayload::Binary(large.clone()))
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while internet.get_sent_messages().await.len() < 2 {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("both chunks should be sent");
+    assert_eq!(sdk.remaining_budget_mb(), 0);
+
+    // 群组扇出同样计入预算，用满 1 MB 后普通发送被拒绝
+    sdk.send_to_group(group_id, MessagePayload::Text("x".repeat(1024 * 1024)))
+        .await
+        .unwrap();
+    let err = sdk
+        .send(recipient, MessagePayload::Text("over budget".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+
+    // 超出预算后流式发送与群组扇出都不再发出任何消息
+    internet.clear_sent_messages().await;
+    let err = sdk
+        .send(recipient, MessagePayload::Binary(large))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+    let _ = sdk
+        .send_to_group(group_id, MessagePayload::Text("still over".to_string()))
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(internet.get_sent_messages().await.is_empty());
+}
+
+/// 模拟磁盘已满的存储：写入消息失败，直到清理释放出足够空间
+struct FullDiskStorage {
+    inner: MemoryStorage,
+    full: AtomicBool,
+    /// 写入失败时返回的 IO 错误类型
+    failure: std::io::ErrorKind,
+    /// 清理是否能释放空间
+    reclaimable: bool,
+    cleanups: AtomicUsize,
+    /// 消息与待发送队列的写入次数（含删除）
+    writes: AtomicUsize,
+}
+
+impl FullDiskStorage {
+    fn new(reclaimable: bool) -> Self {
+        Self::failing_with(std::io::ErrorKind::StorageFull, reclaimable)
+    }
+
+    fn failing_with(failure: std::io::ErrorKind, reclaimable: bool) -> Self {
+        Self {
+            inner: MemoryStorage::new(),
+            full: AtomicBool::new(true),
+            failure,
+            reclaimable,
+            cleanups: AtomicUsize::new(0),
+            writes: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for FullDiskStorage {
+    async fn save_message(&self, message: &Message) -> xlink::core::error::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        if self.full.load(Ordering::SeqCst) {
+            return Err(std::io::Error::from(self.failure).into());
+        }
+        self.inner.save_message(message).await
+    }
+    async fn get_pending_messages(
+        &self,
+        device_id: &xlink::core::types::DeviceId,
+    ) -> xlink::core::error::Result<Vec<Message>> {
+        self.inner.get_pending_messages(device_id).await
+    }
+    async fn remove_message(&self, message_id: &uuid::Uuid) -> xlink::core::error::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.remove_message(message_id).await
+    }
+    async fn save_audit_log(&self, log: String) -> xlink::core::error::Result<()> {
+        self.inner.save_audit_log(log).await
+    }
+    async fn get_audit_logs(&self, limit: usize) -> xlink::core::error::Result<Vec<String>> {
+        self.inner.get_audit_logs(limit).await
+    }
+    async fn cleanup_old_data(&self, days: u32) -> xlink::core::error::Result<u64> {
+        self.inner.cleanup_old_data(days).await
+    }
+    async fn save_pending_message(&self, message: &Message) -> xlink::core::error::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.save_pending_message(message).await
+    }
+    async fn get_pending_messages_for_recovery(
+        &self,
+        device_id: &xlink::core::types::DeviceId,
+    ) -> xlink::core::error::Result<Vec<Message>> {
+        self.inner
+            .get_pending_messages_for_recovery(device_id)
+            .await
+    }
+    async fn remove_pending_message(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> xlink::core::error::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.remove_pending_message(message_id).await
+    }
+    async fn list_messages(&self) -> xlink::core::error::Result<Vec<Message>> {
+        self.inner.list_messages().await
+    }
+    async fn list_pending_messages(&self) -> xlink::core::error::Result<Vec<Message>> {
+        self.inner.list_pending_messages().await
+    }
+    async fn save_metadata(&self, key: &str, value: Vec<u8>) -> xlink::core::error::Result<()> {
+        self.inner.save_metadata(key, value).await
+    }
+    async fn load_metadata(&self, key: &str) -> xlink::core::error::Result<Option<Vec<u8>>> {
+        self.inner.load_metadata(key).await
+    }
+    async fn get_storage_usage(&self) -> xlink::core::error::Result<u64> {
+        self.inner.get_storage_usage().await
+    }
+    async fn cleanup_storage(&self, target_size_bytes: u64) -> xlink::core::error::Result<u64> {
+        self.cleanups.fetch_add(1, Ordering::SeqCst);
+        if self.reclaimable {
+            self.full.store(false, Ordering::SeqCst);
+        }
+        self.inner.cleanup_storage(target_size_bytes).await
+    }
+    fn clear_indexes(&self) {
+        self.inner.clear_indexes()
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_send_reclaims_storage_when_disk_full() {
+    let storage = Arc::new(FullDiskStorage::new(true));
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    let (sender, recipient) = (test_device_id(), test_device_id());
+    for i in 0..10 {
+        let old = Message::new(
+            sender,
+            recipient,
+            MessagePayload::Text(format!("old {}", i)),
+        );
+        storage.inner.save_message(&old).await.unwrap();
+    }
+    let queued = Message::new(
+        sender,
+        recipient,
+        MessagePayload::Text("queued".to_string()),
+    );
+    storage.inner.save_pending_message(&queued).await.unwrap();
+
+    sdk.send(test_device_id(), MessagePayload::Text("saved".to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(storage.cleanups.load(Ordering::SeqCst), 1);
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+    // 只清理容纳新消息所需的空间，待发送消息不受影响
+    let remaining = storage.inner.list_messages().await.unwrap();
+    assert!(
+        remaining.len() >= 9,
+        "reclaimed too much: {:?}",
+        remaining.len()
+    );
+    let pending = storage.inner.list_pending_messages().await.unwrap();
+    assert!(pending.iter().any(|m| m.id == queued.id));
+}
+
+#[tokio::test]
+async fn test_send_does_not_reclaim_storage_on_other_write_errors() {
+    let storage = Arc::new(FullDiskStorage::failing_with(
+        std::io::ErrorKind::PermissionDenied,
+        true,
+    ));
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+
+    // 非空间不足的写入错误原样返回，不删除任何已存数据
+    let err = sdk
+        .send(test_device_id(), MessagePayload::Text("denied".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 101);
+    assert_eq!(storage.cleanups.load(Ordering::SeqCst), 0);
+    assert!(channel.get_sent_messages().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_unknown_peer_has_no_route_without_auto_seed() {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    assert!(!sdk.routing_config().auto_seed_channel_state);
+
+    // 没有对端通道状态时如实返回无路由，而不是伪造状态后发送成功
+    let unknown = test_device_id();
+    let err = sdk
+        .send(unknown, MessagePayload::Text("hello".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 105);
+    assert!(channel.get_sent_messages().await.is_empty());
+    assert!(sdk
+        .capability_manager()
+        .get_channel_state(&unknown, &ChannelType::Lan)
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_send_reports_storage_full_after_failed_cleanup() {
+    let storage = Arc::new(FullDiskStorage::new(false));
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+    let mut events = Box::pin(sdk.events());
+
+    let err = sdk
+        .send(test_device_id(), MessagePayload::Text("lost".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 704);
+    assert!(err.source.is_some());
+    assert_eq!(
+        err.retry_suggestion(),
+        Some(RetrySuggestion::ManualIntervention)
+    );
+    assert_eq!(storage.cleanups.load(Ordering::SeqCst), 1);
+    assert!(channel.get_sent_messages().await.is_empty());
+
+    match next_event(&mut events).await {
+        SdkEvent::StorageFull { reason, .. } => assert!(!reason.is_empty()),
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_ephemeral_send_skips_storage() {
+    let storage = Arc::new(FullDiskStorage::new(false));
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    let recipient = test_device_id();
+
+    // 磁盘已满也不影响尽力发送，且不触发任何存储写入
+    sdk.send_ephemeral(recipient, MessagePayload::Text("presence".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+
+    // 发送失败直接丢弃，不进入待发送队列
+    channel.set_failure(true);
+    assert!(sdk
+        .send_ephemeral(recipient, MessagePayload::Text("telemetry".to_string()))
+        .await
+        .is_err());
+
+    assert_eq!(storage.writes.load(Ordering::SeqCst), 0);
+    assert_eq!(storage.cleanups.load(Ordering::SeqCst), 0);
+    assert!(storage.list_pending_messages().await.unwrap().is_empty());
+
+    let report = sdk.metrics_report();
+    assert_eq!(report.total_ephemeral_sent, 1);
+    assert_eq!(report.total_ephemeral_dropped, 1);
+
+    // 普通发送仍需先持久化
+    channel.set_failure(false);
+    assert!(sdk
+        .send(recipient, MessagePayload::Text("durable".to_string()))
+        .await
+        .is_err());
+    assert!(storage.writes.load(Ordering::SeqCst) > 0);
+}
+
+// ==================== Crash Recovery ====================
+
+#[tokio::test]
+async fn test_system_recovery_after_restart() {
+    // IT-REC-001: 重启后的状态恢复
+    let storage_path = "./test_recovery_sys";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+
+    let caps = DeviceCapabilities {
+        device_id: test_device_id(),
+        device_type: DeviceType::Smartphone,
+        device_name: "Recovery Device".to_string(),
+        supported_channels: [ChannelType::Lan].into_iter().collect(),
+        battery_level: Some(100),
+        is_charging: true,
+        data_cost_sensitive: false,
+    };
+
+    // 1. First run: Create a group and save state
+    {
+        let sdk = TestSdkBuilder::new()
+            .with_device_capabilities(caps.clone())
+            .with_storage_path(storage_path.to_string())
+            .build()
+            .await
+            .unwrap();
+
+        sdk.create_group("Persistent Group".to_string(), vec![caps.device_id])
+            .await
+            .unwrap();
+    } // SDK dropped here
+
+    // 2. Second run: Recovery
+    {
+        let _sdk = TestSdkBuilder::new()
+            .with_device_capabilities(caps)
+            .with_storage_path(storage_path.to_string())
+            .build()
+            .await
+            .unwrap();
+        // Check if group exists (using internal state access if needed, or just verifying no crash)
+        // For now, we assume recovery works if the SDK starts without error
+    }
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+async fn crash_recovery_count(storage: &MemoryStorage) -> usize {
+    storage
+        .get_audit_logs(1000)
+        .await
+        .unwrap()
+        .iter()
+        .filter(|log| log.contains("Crash recovery completed"))
+        .count()
+}
+
+#[tokio::test]
+async fn test_clean_shutdown_skips_crash_recovery() {
+    let storage = Arc::new(MemoryStorage::new());
+    let new_sdk = || {
+        XLink::with_storage(
+            test_device_capabilities(),
+            vec![Arc::new(MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                10,
+            ))],
+            storage.clone(),
+        )
+    };
+
+    let sdk = new_sdk().await.unwrap();
+    sdk.start().await.unwrap();
+    assert_eq!(sdk.previous_exit(), Some(PreviousExit::Crashed));
+    sdk.stop().await;
+    drop(sdk);
+    let recoveries = crash_recovery_count(&storage).await;
+
+    let sdk = new_sdk().await.unwrap();
+    sdk.start().await.unwrap();
+    match sdk.previous_exit() {
+        Some(PreviousExit::Clean(marker)) => {
+            assert_eq!(marker.reason, ShutdownReason::Requested);
+            assert_eq!(marker.pending_messages, 0);
+        }
+        other => panic!("expected clean exit, got {:?}", other),
+    }
+    assert_eq!(crash_recovery_count(&storage).await, recoveries);
+    sdk.stop().await;
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown_records_clean_exit() {
+    let storage = Arc::new(MemoryStorage::new());
+    let new_sdk = || {
+        XLink::with_storage(
+            test_device_capabilities(),
+            vec![Arc::new(MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                10,
+            ))],
+            storage.clone(),
+        )
+    };
+
+    let sdk = new_sdk().await.unwrap();
+    assert_eq!(sdk.shutdown_config(), ShutdownConfig::default());
+    sdk.set_shutdown_config(ShutdownConfig {
+        grace_period_ms: 100,
+    });
+    assert_eq!(sdk.shutdown_config().grace_period_ms, 100);
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+    sdk.start().await.unwrap();
+    sdk.send(test_device_id(), MessagePayload::Text("before".into()))
+        .await
+        .unwrap();
+    sdk.shutdown().await;
+
+    let sdk = new_sdk().await.unwrap();
+    sdk.start().await.unwrap();
+    match sdk.previous_exit() {
+        Some(PreviousExit::Clean(marker)) => {
+            assert_eq!(marker.reason, ShutdownReason::Requested);
+            assert_eq!(marker.pending_messages, 0);
+        }
+        other => panic!("expected clean exit, got {:?}", other),
+    }
+    sdk.stop().await;
+}
+
+#[tokio::test]
+async fn test_missing_shutdown_marker_runs_crash_recovery() {
+    let storage = Arc::new(MemoryStorage::new());
+    let new_sdk = || {
+        XLink::with_storage(
+            test_device_capabilities(),
+            vec![Arc::new(MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                10,
+            ))],
+            storage.clone(),
+        )
+    };
+
+    // 第一次运行启动后未调用 stop 即退出
+    let sdk = new_sdk().await.unwrap();
+    sdk.start().await.unwrap();
+    drop(sdk);
+    let recoveries = crash_recovery_count(&storage).await;
+
+    let sdk = new_sdk().await.unwrap();
+    sdk.start().await.unwrap();
+    assert_eq!(sdk.previous_exit(), Some(PreviousExit::Crashed));
+    assert_eq!(crash_recovery_count(&storage).await, recoveries + 1);
+    sdk.stop().await;
+}
+
+#[tokio::test]
+async fn test_filtered_recovery_leaves_unmatched_messages_pending() {
+    let storage_dir = "./test_recovery_filtered";
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+    let backends: Vec<Arc<dyn Storage>> = vec![
+        Arc::new(InMemoryStorage::new()),
+        Arc::new(
+            FileStorage::new(format!("{}/files", storage_dir))
+                .await
+                .unwrap(),
+        ),
+        Arc::new(
+            SqliteStorage::new(format!("{}/xlink.db", storage_dir))
+                .await
+                .unwrap(),
+        ),
+    ];
+
+    let caps = test_device_capabilities();
+    let peer = test_device_id();
+    let group = GroupId(uuid::Uuid::new_v4());
+    let pending: Vec<Message> = [
+        (MessagePriority::Low, None),
+        (MessagePriority::Critical, None),
+        (MessagePriority::High, Some(group)),
+        (MessagePriority::Critical, Some(group)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (priority, group_id))| {
+        let mut msg = Message::new(caps.device_id, peer, MessagePayload::Text(i.to_string()));
+        msg.priority = priority;
+        msg.group_id = group_id;
+        msg.timestamp = 1000 + i as u64;
+        msg
+    })
+    .collect();
+
+    for storage in backends {
+        for msg in &pending {
+            storage.save_pending_message(msg).await.unwrap();
+        }
+        let ids = |messages: Vec<Message>| {
+            let mut ids: Vec<_> = messages.into_iter().map(|m| m.id).collect();
+            ids.sort();
+            ids
+        };
+        let expected = |indices: &[usize]| {
+            let mut ids: Vec<_> = indices.iter().map(|&i| pending[i].id).collect();
+            ids.sort();
+            ids
+        };
+        let recover = |filter: RecoveryFilter| {
+            let (storage, device_id) = (storage.clone(), caps.device_id);
+            async move {
+                storage
+                    .get_pending_messages_for_recovery_filtered(&device_id, &filter)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let by_group = RecoveryFilter {
+            group_id: Some(group),
+            ..Default::default()
+        };
+        assert_eq!(ids(recover(by_group).await), expected(&[2, 3]));
+        let critical = RecoveryFilter {
+            min_priority: Some(MessagePriority::Critical),
+            ..Default::default()
+        };
+        assert_eq!(ids(recover(critical).await), expected(&[1, 3]));
+        let high_in_group = RecoveryFilter {
+            min_priority: Some(MessagePriority::High),
+            max_count: Some(1),
+            ..by_group
+        };
+        assert_eq!(ids(recover(high_in_group).await), expected(&[2]));
+        assert_eq!(
+            ids(recover(RecoveryFilter::default()).await),
+            expected(&[0, 1, 2, 3])
+        );
+    }
+
+    // 低电量关闭只取出关键消息，其余消息仍在待发送队列中
+    let storage = Arc::new(InMemoryStorage::new());
+    let sdk = XLink::with_storage(caps.clone(), vec![], storage.clone())
+        .await
+        .unwrap();
+    for msg in &pending {
+        storage.save_pending_message(msg).await.unwrap();
+    }
+    let recovered = sdk
+        .recover_pending_messages_filtered(RecoveryFilter {
+            group_id: Some(group),
+            min_priority: Some(MessagePriority::Critical),
+            max_count: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].id, pending[3].id);
+    assert_eq!(storage.list_pending_messages().await.unwrap().len(), 4);
+    sdk.handle_low_battery_shutdown().await.unwrap();
+    assert_eq!(sdk.recover_pending_messages().await.unwrap().len(), 4);
+
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+}
+
+#[tokio::test]
+async fn test_recovery_drops_expired_pending_messages() {
+    let storage_path = "./test_recovery_ttl";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let storage = Arc::new(FileStorage::new(storage_path).await.unwrap());
+    let caps = test_device_capabilities();
+    let sdk = XLink::with_storage(
+        caps.clone(),
+        vec![Arc::new(MemoryChannel::new(
+            Arc::new(NoOpMessageHandler),
+            10,
+        ))],
+        storage.clone(),
+    )
+    .await
+    .unwrap();
+
+    let peer = test_device_id();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut expired = Message::new(caps.device_id, peer, MessagePayload::Text("stale".into()));
+    expired.expires_at = Some(now - 60);
+    let mut fresh = Message::new(caps.device_id, peer, MessagePayload::Text("fresh".into()));
+    fresh.expires_at = Some(now + 3600);
+    let legacy = Message::new(caps.device_id, peer, MessagePayload::Text("legacy".into()));
+    for msg in [&expired, &fresh, &legacy] {
+        storage.save_pending_message(msg).await.unwrap();
+    }
+
+    // 过期消息从待发送队列移除，未设置有效期的旧消息照常恢复
+    let mut recovered: Vec<_> = sdk
+        .recover_pending_messages()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    recovered.sort();
+    let mut expected = vec![fresh.id, legacy.id];
+    expected.sort();
+    assert_eq!(recovered, expected);
+    assert_eq!(storage.list_pending_messages().await.unwrap().len(), 2);
+    assert!(storage
+        .get_audit_logs(10)
+        .await
+        .unwrap()
+        .iter()
+        .any(|log| log.contains("expired 1 pending messages")));
+
+    // 不含过期字段的旧记录反序列化为永不过期
+    let mut value = serde_json::to_value(&legacy).unwrap();
+    value.as_object_mut().unwrap().remove("expires_at");
+    let decoded: Message = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded.expires_at, None);
+    assert!(!decoded.is_expired(u64::MAX));
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_crash_recovery_reports_progress() {
+    let storage_path = "./test_recovery_progress";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    let storage = Arc::new(FileStorage::new(storage_path).await.unwrap());
+    let caps = test_device_capabilities();
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(caps.clone(), vec![channel.clone()], storage.clone())
+        .await
+        .unwrap();
+
+    // 仅有通道状态的对端可以重发成功，另一个对端无路由
+    let (reachable, unreachable) = (test_device_id(), test_device_id());
+    sdk.capability_manager().update_channel_state(
+        reachable,
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            rtt_ms: 10,
+            packet_loss_rate: 0.0,
+            bandwidth_bps: 10_000_000,
+            ..Default::default()
+        },
+    );
+    for (peer, text) in [(reachable, "a"), (reachable, "b"), (unreachable, "c")] {
+        let message = Message::new(caps.device_id, peer, MessagePayload::Text(text.into()));
+        storage.save_pending_message(&message).await.unwrap();
+    }
+
+    let mut events = Vec::new();
+    sdk.recover_from_crash_with_progress(|progress| events.push(progress))
+        .await
+        .unwrap();
+
+    // 每条消息上报一次，失败的重发同样计入进度
+    assert_eq!(events.len(), 3);
+    for (index, progress) in events.iter().enumerate() {
+        assert_eq!(progress.total, 3);
+        assert_eq!(progress.processed, index + 1);
+        assert_eq!(progress.succeeded + progress.failed, progress.processed);
+    }
+    let last = events.last().unwrap();
+    assert_eq!((last.succeeded, last.failed), (2, 1));
+    assert_eq!(channel.get_sent_messages().await.len(), 2);
+
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+}
+
+#[tokio::test]
+async fn test_send_with_ttl_sets_expiry() {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = XLink::with_storage(
+        test_device_capabilities(),
+        vec![channel.clone()],
+        Arc::new(MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+
+    sdk.send_with_ttl(
+        test_device_id(),
+        MessagePayload::Text("short lived".into()),
+        Duration::from_secs(30),
+    )
+    .await
+    .unwrap();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    let expires_at = sent[0].expires_at.unwrap();
+    assert!(expires_at > now && expires_at <= now + 30);
+}
+
+#[tokio::test]
+async fn test_send_with_ttl_follows_injected_clock() {
+    // 时间戳与过期时间取自注入的时钟，而不是系统时间
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let clock = Arc::new(MockClock::starting_at(1_000_000_000_000));
+    let sdk = XLink::builder(test_device_capabilities())
+        .channel(channel.clone())
+        .storage(Arc::new(MemoryStorage::new()))
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.set_routing_config(RoutingConfig {
+        auto_seed_channel_state: true,
+    });
+
+    clock.advance(Duration::from_millis(2_500));
+    sdk.send_with_ttl(
+        test_device_id(),
+        MessagePayload::Text("short lived".into()),
+        Duration::from_secs(30),
+    )
+    .await
+    .unwrap();
+
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].timestamp, 1_000_000_002);
+    assert_eq!(sent[0].expires_at, Some(1_000_000_032));
+}
+
+#[tokio::test]
+async fn test_persisted_crypto_state_is_sealed_when_state_key_configured() {
+    let storage = Arc::new(MemoryStorage::new());
+    let new_sdk = || {
+        XLink::with_storage(
+            test_device_capabilities(),
+            vec![Arc::new(MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                0,
+            ))],
+            storage.clone(),
+        )
+    };
+    let sdk = new_sdk().await.unwrap();
+    let plaintext = sdk.export_sdk_state().unwrap();
+    let state: CryptoState = versioned::decode(RecordKind::CryptoState, &plaintext).unwrap();
+    // 未配置状态密钥时低电量关闭不落盘
+    sdk.handle_low_battery_shutdown().await.unwrap();
+    assert!(storage
+        .load_metadata(SDK_STATE_KEY)
+        .await
+        .unwrap()
+        .is_none());
+    let key = Arc::new(StaticStateKey::new([7; 32]));
+    sdk.set_state_key_provider(Some(key.clone()));
+    assert!(sdk.state_encryption_enabled());
+
+    let is_plaintext = |data: &[u8]| {
+        data.windows(13).any(|w| w == b"static_secret")
+            || data.windows(32).any(|w| w == state.static_secret)
+            || data.windows(32).any(|w| w == state.signing_key)
+    };
+    let exported = sdk.export_sdk_state().unwrap();
+    assert!(is_sealed(&exported));
+    assert!(!is_plaintext(&exported));
+
+    // 低电量关闭时持久化的状态同样为密文
+    sdk.handle_low_battery_shutdown().await.unwrap();
+    let persisted = storage.load_metadata(SDK_STATE_KEY).await.unwrap().unwrap();
+    assert!(is_sealed(&persisted));
+    assert!(!is_plaintext(&persisted));
+
+    // 导入需要相同的状态密钥
+    let mut restored = new_sdk().await.unwrap();
+    assert!(restored.import_sdk_state(&persisted).is_err());
+    restored.set_state_key_provider(Some(Arc::new(StaticStateKey::new([8; 32]))));
+    assert!(restored.import_sdk_state(&persisted).is_err());
+    restored.set_state_key_provider(Some(key));
+    restored.import_sdk_state(&persisted).unwrap();
+    assert_eq!(restored.public_key(), sdk.public_key());
+}
+
+/// 第一次取密钥时撤销 SDK 上的状态密钥配置，模拟关闭过程中配置被更换
+struct RevokedMidwayKey {
+    sdk: std::sync::OnceLock<std::sync::Weak<XLink>>,
+}
+
+impl StateKeyProvider for RevokedMidwayKey {
+    fn state_key(&self) -> xlink::core::error::Result<[u8; 32]> {
+        if let Some(sdk) = self.sdk.get().and_then(|sdk| sdk.upgrade()) {
+            sdk.set_state_key_provider(None);
+        }
+        Ok([9; 32])
+    }
+}
+
+#[tokio::test]
+async fn test_low_battery_shutdown_seals_with_one_key_snapshot() {
+    let storage = Arc::new(MemoryStorage::new());
+    let sdk = Arc::new(
+        XLink::with_storage(test_device_capabilities(), vec![], storage.clone())
+            .await
+            .unwrap(),
+    );
+    let key = Arc::new(RevokedMidwayKey {
+        sdk: std::sync::OnceLock::new(),
+    });
+    key.sdk.set(Arc::downgrade(&sdk)).unwrap();
+    sdk.set_state_key_provider(Some(key));
+
+    // 导出期间配置被撤销，落盘的仍是按关闭开始时的密钥封装的密文
+    sdk.handle_low_battery_shutdown().await.unwrap();
+    assert!(!sdk.state_encryption_enabled());
+    let persisted = storage.load_metadata(SDK_STATE_KEY).await.unwrap().unwrap();
+    assert!(is_sealed(&persisted));
+}
+
+// ==================== Task Lifecycle ====================
+
+#[tokio::test]
+async fn test_start_twice_does_not_duplicate_tasks() {
+    // IT-LIFE-001: 重复启动不应导致后台任务重复
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    assert_eq!(sdk.active_task_count(), 0);
+
+    sdk.start().await.unwrap();
+    let first = sdk.task_statuses();
+    assert!(!first.is_empty());
+    assert!(first.iter().any(|t| t.name == "heartbeat" && t.alive));
+
+    let second_start = sdk.start().await;
+    assert!(second_start.is_err(), "Second start should be refused");
+    assert_eq!(second_start.unwrap_err().code().0, 106);
+
+    let second = sdk.task_statuses();
+    let names: Vec<_> = second.iter().map(|t| t.name.clone()).collect();
+    assert_eq!(
+        names,
+        first.iter().map(|t| t.name.clone()).collect::<Vec<_>>()
+    );
+
+    sdk.stop().await;
+    assert_eq!(sdk.active_task_count(), 0);
+    assert!(sdk.task_statuses().is_empty());
+}
+
+// ==================== Clock Skew Tolerance ====================
+
+#[tokio::test]
+async fn test_future_dated_message_is_clamped_or_rejected() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let future_message = || {
+        let mut message = Message::new(
+            test_device_id(),
+            sdk.device_id(),
+            MessagePayload::Text("from the future".to_string()),
+        );
+        message.timestamp = now + 3600;
+        message
+    };
+
+    // 默认钳制：时间戳被拉回本地当前时间
+    assert_eq!(sdk.clock_skew_config().action, ClockSkewAction::Clamp);
+    handler.handle_message(future_message()).await.unwrap();
+    let received = tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(received.timestamp >= now && received.timestamp < now + 60);
+
+    // 拒绝模式：消息不被交付
+    sdk.set_clock_skew_config(ClockSkewConfig {
+        max_skew_secs: 60,
+        action: ClockSkewAction::Reject,
+    });
+    let err = handler.handle_message(future_message()).await.unwrap_err();
+    assert_eq!(err.code().0, 802);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_stale_message_dropped_or_flagged() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let mut events = Box::pin(sdk.events());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let message_at = |timestamp| {
+        let mut message = Message::new(
+            test_device_id(),
+            sdk.device_id(),
+            MessagePayload::Text("buffered by relay".to_string()),
+        );
+        message.timestamp = timestamp;
+        message
+    };
+
+    // 最大年龄 10 分钟，时钟偏差容忍 60 秒
+    sdk.set_clock_skew_config(ClockSkewConfig {
+        max_skew_secs: 60,
+        action: ClockSkewAction::Clamp,
+    });
+    sdk.set_message_age_config(MessageAgeConfig {
+        max_age_secs: Some(600),
+        action: StaleMessageAction::Drop,
+    });
+
+    // 在年龄上限加偏差容忍之内的消息照常交付
+    handler.handle_message(message_at(now - 630)).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+        .await
+        .unwrap()
+        .is_some());
+
+    // 超龄消息被丢弃
+    let old = message_at(now - 3600);
+    let old_id = old.id;
+    handler.handle_message(old).await.unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), sdk.receive())
+            .await
+            .is_err()
+    );
+    let stale_event = loop {
+        if let SdkEvent::StaleMessageReceived {
+            message_id,
+            delivered,
+            age_secs,
+            ..
+        } = next_event(&mut events).await
+        {
+            break (message_id, delivered, age_secs);
+        }
+    };
+    assert_eq!(stale_event.0, old_id);
+    assert!(!stale_event.1);
+    assert!(stale_event.2 >= 3600);
+    assert_eq!(sdk.metrics_report().total_stale_received, 1);
+
+    // 标记模式：照常交付并保留原始时间戳
+    sdk.set_message_age_config(MessageAgeConfig {
+        max_age_secs: Some(600),
+        action: StaleMessageAction::Deliver,
+    });
+    handler
+        .handle_message(message_at(now - 3600))
+        .await
+        .unwrap();
+    let received = tokio::time::timeout(Duration::from_secs(1), sdk.receive())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.timestamp, now - 3600);
+    assert_eq!(sdk.metrics_report().total_stale_received, 2);
+}
+
+// ==================== Unified Event Bus ====================
+
+async fn next_event(events: &mut (impl futures::Stream<Item = SdkEvent> + Unpin)) -> SdkEvent {
+    tokio::time::timeout(Duration::from_secs(1), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_send_produces_event_sequence() {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    let mut events = Box::pin(sdk.events());
+    let peer = test_device_id();
+
+    sdk.send(peer, MessagePayload::Text("hello".to_string()))
+        .await
+        .unwrap();
+    let message_id = channel.get_sent_messages().await[0].id;
+
+    match next_event(&mut events).await {
+        SdkEvent::ChannelSelected {
+            message_id: id,
+            recipient,
+            channel,
+        } => {
+            assert_eq!(id, message_id);
+            assert_eq!(recipient, peer);
+            assert_eq!(channel, ChannelType::Lan);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+    match next_event(&mut events).await {
+        SdkEvent::MessageSent {
+            message_id: id,
+            channel,
+            ..
+        } => {
+            assert_eq!(id, message_id);
+            assert_eq!(channel, ChannelType::Lan);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+
+    // 入站消息交付后产生 MessageReceived
+    let incoming = Message::new(
+        peer,
+        sdk.device_id(),
+        MessagePayload::Text("reply".to_string()),
+    );
+    let incoming_id = incoming.id;
+    sdk.get_message_handler()
+        .handle_message(incoming)
+        .await
+        .unwrap();
+    match next_event(&mut events).await {
+        SdkEvent::MessageReceived { message_id, sender } => {
+            assert_eq!(message_id, incoming_id);
+            assert_eq!(sender, peer);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+// ==================== Message Journal ====================
+
+#[tokio::test]
+async fn test_message_journal_records_and_replays() {
+    let journal_path = "./test_journal_record.jsonl";
+    let _ = tokio::fs::remove_file(journal_path).await;
+
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.enable_message_journal(journal_path).await.unwrap();
+
+    let peer = test_device_id();
+    sdk.send(peer, MessagePayload::Text("outbound".to_string()))
+        .await
+        .unwrap();
+    let inbound: Vec<_> = (0..2)
+        .map(|i| {
+            Message::new(
+                peer,
+                sdk.device_id(),
+                MessagePayload::Text(format!("inbound-{}", i)),
+            )
+        })
+        .collect();
+    let handler = sdk.get_message_handler();
+    for message in &inbound {
+        handler.handle_message(message.clone()).await.unwrap();
+    }
+    sdk.disable_message_journal();
+
+    let entries = MessageJournal::load(journal_path).await.unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].direction, JournalDirection::Outbound);
+    assert_eq!(entries[0].channel, Some(ChannelType::Lan));
+    assert_eq!(
+        entries[0].message.payload,
+        MessagePayload::Text("outbound".to_string())
+    );
+    assert!(entries[1..]
+        .iter()
+        .all(|e| e.direction == JournalDirection::Inbound && e.channel.is_none()));
+
+    // 回放到新的 SDK 实例，入站消息按原顺序交付
+    let replay_sdk = TestSdkBuilder::new().build().await.unwrap();
+    let replayed = replay(journal_path, &replay_sdk).await.unwrap();
+    assert_eq!(replayed, 2);
+    for expected in &inbound {
+        let received = tokio::time::timeout(Duration::from_secs(1), replay_sdk.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.id, expected.id);
+        assert_eq!(received.payload, expected.payload);
+    }
+
+    let _ = tokio::fs::remove_file(journal_path).await;
+}
+
+#[tokio::test]
+async fn test_message_journal_redacts_in_privacy_mode() {
+    let journal_path = "./test_journal_redact.jsonl";
+    let _ = tokio::fs::remove_file(journal_path).await;
+
+    let mut sdk = TestSdkBuilder::new().build().await.unwrap();
+    sdk.update_compliance_config(ComplianceConfig {
+        privacy_mode: true,
+        ..Default::default()
+    });
+    sdk.enable_message_journal(journal_path).await.unwrap();
+
+    let incoming = Message::new(
+        test_device_id(),
+        sdk.device_id(),
+        MessagePayload::Text("secret".to_string()),
+    );
+    sdk.get_message_handler()
+        .handle_message(incoming.clone())
+        .await
+        .unwrap();
+
+    let entries = MessageJournal::load(journal_path).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message.id, incoming.id);
+    assert_eq!(
+        entries[0].message.payload,
+        MessagePayload::Text(REDACTED_TEXT.to_string())
+    );
+
+    let _ = tokio::fs::remove_file(journal_path).await;
+}
+
+// ==================== Request / Response ====================
+
+/// 把消息直接交给稍后绑定的对端处理器，用于把两个 SDK 实例连起来
+#[derive(Default)]
+struct RelayChannel {
+    target: std::sync::OnceLock<Arc<dyn MessageHandler>>,
+}
+
+#[async_trait::async_trait]
+impl Channel for RelayChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, message: Message) -> xlink::core::error::Result<()> {
+        if let Some(handler) = self.target.get() {
+            handler.handle_message(message).await?;
+        }
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState {
+            available: true,
+            rtt_ms: 1,
+            packet_loss_rate: 0.0,
+            ..Default::default()
+        })
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+}
+
+async fn connected_pair() -> (Arc<XLink>, Arc<XLink>) {
+    let to_bob = Arc::new(RelayChannel::default());
+    let to_alice = Arc::new(RelayChannel::default());
+    let alice = TestSdkBuilder::new()
+        .with_channel(to_bob.clone())
+        .build()
+        .await
+        .unwrap();
+    let bob = TestSdkBuilder::new()
+        .with_channel(to_alice.clone())
+        .build()
+        .await
+        .unwrap();
+    let _ = to_bob.target.set(bob.get_message_handler());
+    let _ = to_alice.target.set(alice.get_message_handler());
+    (Arc::new(alice), Arc::new(bob))
+}
+
+#[tokio::test]
+async fn test_request_response_round_trip() {
+    let (alice, bob) = connected_pair().await;
+
+    let responder = {
+        let bob = bob.clone();
+        tokio::spawn(async move {
+            let request = bob.receive().await.unwrap();
+            let correlation_id = request
+                .correlation_id
+                .expect("request carries correlation id");
+            let reply = match request.payload {
+                MessagePayload::Text(text) => MessagePayload::Text(text.to_uppercase()),
+                other => other,
+            };
+            bob.reply(correlation_id, reply).await.unwrap();
+            // 同一请求不能重复回复
+            assert!(bob
+                .reply(correlation_id, MessagePayload::Text("again".to_string()))
+                .await
+                .is_err());
+        })
+    };
+
+    let response = alice
+        .request(
+            bob.device_id(),
+            MessagePayload::Text("ping".to_string()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response, MessagePayload::Text("PING".to_string()));
+    responder.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_times_out_and_ignores_late_response() {
+    let (alice, bob) = connected_pair().await;
+
+    let err = alice
+        .request(
+            bob.device_id(),
+            MessagePayload::Text("anyone there?".to_string()),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 101);
+
+    // 超时后到达的响应被丢弃，不会作为普通消息交付给请求方
+    let request = bob.receive().await.unwrap();
+    bob.reply(
+        request.correlation_id.unwrap(),
+        MessagePayload::Text("too late".to_string()),
+    )
+    .await
+    .unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), alice.receive())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_send_with_ack_reports_delivery() {
+    let (alice, bob) = connected_pair().await;
+    // 确认经接收方的路由器直接回送，需要接收方已知到发送方的通道状态
+    bob.capability_manager().update_channel_state(
+        alice.device_id(),
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            ..Default::default()
+        },
+    );
+
+    let status = alice
+        .send_with_ack(
+            bob.device_id(),
+            MessagePayload::Text("did you get this?".to_string()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, AckStatus::Delivered);
+    let received = bob.receive().await.unwrap();
+    assert!(received.require_ack);
+
+    // 确认消息不会作为普通消息交付给发送方
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), alice.receive())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_send_with_ack_times_out_or_fails() {
+    // 对端从不确认
+    let silent = Arc::new(RelayChannel::default());
+    let _ = silent.target.set(Arc::new(NoOpMessageHandler));
+    let alice = TestSdkBuilder::new()
+        .with_channel(silent)
+        .build()
+        .await
+        .unwrap();
+    let status = alice
+        .send_with_ack(
+            test_device_id(),
+            MessagePayload::Text("hello?".to_string()),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, AckStatus::TimedOut);
+
+    // 通道发送失败
+    let broken = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    broken.set_failure(true);
+    let alice = TestSdkBuilder::new()
+        .with_channel(broken)
+        .build()
+        .await
+        .unwrap();
+    let status = alice
+        .send_with_ack(
+            test_device_id(),
+            MessagePayload::Text("hello?".to_string()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, AckStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_delivery_receipts_record_sends_and_acks() {
+    let (alice, bob) = connected_pair().await;
+    bob.capability_manager().update_channel_state(
+        alice.device_id(),
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            ..Default::default()
+        },
+    );
+
+    // 发送成功即写入回执，消息本身已从存储中移除
+    alice
+        .send(bob.device_id(), MessagePayload::Text("plain".to_string()))
+        .await
+        .unwrap();
+    let plain = bob.receive().await.unwrap();
+    let receipt = alice.get_delivery_receipt(plain.id).await.unwrap().unwrap();
+    assert_eq!(receipt.recipient, bob.device_id());
+    assert_eq!(receipt.channel, ChannelType::Lan);
+    assert!(receipt.delivered_at > 0);
+    assert_eq!(receipt.acknowledged_at, None);
+
+    // 收到确认后回执记录确认时间
+    let status = alice
+        .send_with_ack(
+            bob.device_id(),
+            MessagePayload::Text("acked".to_string()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, AckStatus::Delivered);
+    let acked = bob.receive().await.unwrap();
+    let receipt = alice.get_delivery_receipt(acked.id).await.unwrap().unwrap();
+    assert!(receipt.acknowledged_at.unwrap() >= receipt.delivered_at);
+    assert!(alice
+        .get_delivery_receipt(uuid::Uuid::new_v4())
+        .await
+        .unwrap()
+        .is_none());
+
+    // 回执随保留天数清理
+    let storage_dir = "./test_delivery_receipts";
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+    let backends: Vec<Arc<dyn Storage>> = vec![
+        Arc::new(InMemoryStorage::new()),
+        Arc::new(
+            FileStorage::new(format!("{}/files", storage_dir))
+                .await
+                .unwrap(),
+        ),
+        Arc::new(
+            SqliteStorage::new(format!("{}/xlink.db", storage_dir))
+                .await
+                .unwrap(),
+        ),
+    ];
+    for storage in &backends {
+        storage.save_delivery_receipt(&receipt).await.unwrap();
+        storage
+            .save_metadata("traffic_budget", b"kept".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_delivery_receipt(&receipt.message_id)
+                .await
+                .unwrap(),
+            Some(receipt.clone())
+        );
+    }
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    for storage in &backends {
+        storage.cleanup_old_data(0).await.unwrap();
+        assert_eq!(
+            storage
+                .get_delivery_receipt(&receipt.message_id)
+                .await
+                .unwrap(),
+            None::<DeliveryReceipt>
+        );
+        // 元数据保存的状态不随保留期清理
+        assert_eq!(
+            storage.load_metadata("traffic_budget").await.unwrap(),
+            Some(b"kept".to_vec())
+        );
+    }
+    let _ = tokio::fs::remove_dir_all(storage_dir).await;
+}
+
+// ==================== Battery Policy ====================
+
+#[tokio::test]
+async fn test_battery_floor_blocks_non_critical_sends() {
+    let sdk = TestSdkBuilder::new()
+        .with_device_capabilities(test_device_with_battery(10, false))
+        .build()
+        .await
+        .unwrap();
+    sdk.set_battery_policy(BatteryPolicy {
+        battery_floor: Some(20),
+    });
+    let recipient = test_device_id();
+    let text = |t: &str| MessagePayload::Text(t.to_string());
+
+    let err = sdk
+        .send_with_priority(recipient, text("telemetry"), MessagePriority::Low)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().0, 104);
+    assert!(err.is_retryable());
+    assert!(sdk.send(recipient, text("chat")).await.is_err());
+    assert!(sdk
+        .send_with_priority(recipient, text("alert"), MessagePriority::Critical)
+        .await
+        .is_ok());
+
+    // 充电后恢复普通发送
+    let mut caps = sdk.capability_manager().get_local_caps();
+    caps.is_charging = true;
+    sdk.capability_manager().update_local_capabilities(caps);
+    assert!(sdk
+        .send_with_priority(recipient, text("telemetry"), MessagePriority::Low)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_capability_events_emitted_only_on_change() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let manager = sdk.capability_manager();
+    let mut events = manager.subscribe();
+
+    let mut caps = manager.get_local_caps();
+    caps.battery_level = Some(15);
+    manager.update_local_capabilities(caps.clone());
+    assert_eq!(
+        events.try_recv().unwrap(),
+        CapabilityEvent::BatteryChanged(15)
+    );
+
+    caps.is_charging = true;
+    manager.update_local_capabilities(caps.clone());
+    assert_eq!(
+        events.try_recv().unwrap(),
+        CapabilityEvent::ChargingChanged(true)
+    );
+
+    // 值未变化时不发出事件
+    manager.update_local_capabilities(caps);
+    assert!(events.try_recv().is_err());
+
+    assert!(manager.update_local_network_type(NetworkType::Cellular4G));
+    assert!(!manager.update_local_network_type(NetworkType::Cellular4G));
+    assert_eq!(
+        events.try_recv().unwrap(),
+        CapabilityEvent::NetworkTypeChanged(NetworkType::Cellular4G)
+    );
+    assert!(events.try_recv().is_err());
+    assert_eq!(manager.local_network_type(), NetworkType::Cellular4G);
+}
+
+// ==================== Topic Delivery ====================
+
+#[tokio::test]
+async fn test_topic_messages_routed_to_subscribers() {
+    let (alice, bob) = connected_pair().await;
+    let mut chat = Box::pin(bob.subscribe_topic("chat"));
+    let mut presence = Box::pin(bob.subscribe_topic("presence"));
+
+    alice
+        .send_to_topic(
+            bob.device_id(),
+            "chat",
+            MessagePayload::Text("hello".to_string()),
+        )
+        .await
+        .unwrap();
+    alice
+        .send_to_topic(
+            bob.device_id(),
+            "presence",
+            MessagePayload::Text("online".to_string()),
+        )
+        .await
+        .unwrap();
+    // 无订阅者的主题与无主题消息回退到普通接收队列
+    alice
+        .send_to_topic(
+            bob.device_id(),
+            "files",
+            MessagePayload::Text("report".to_string()),
+        )
+        .await
+        .unwrap();
+    alice
+        .send(bob.device_id(), MessagePayload::Text("plain".to_string()))
+        .await
+        .unwrap();
+
+    let message = chat.next().await.unwrap();
+    assert_eq!(message.topic.as_deref(), Some("chat"));
+    assert_eq!(message.payload, MessagePayload::Text("hello".to_string()));
+    let message = presence.next().await.unwrap();
+    assert_eq!(message.payload, MessagePayload::Text("online".to_string()));
+
+    let fallback = bob.receive().await.unwrap();
+    assert_eq!(fallback.topic.as_deref(), Some("files"));
+    let plain = bob.receive().await.unwrap();
+    assert_eq!(plain.topic, None);
+    assert_eq!(plain.payload, MessagePayload::Text("plain".to_string()));
+
+    // 取消订阅后该主题同样回退到普通接收队列
+    drop(chat);
+    alice
+        .send_to_topic(
+            bob.device_id(),
+            "chat",
+            MessagePayload::Text("anyone?".to_string()),
+        )
+        .await
+        .unwrap();
+    let fallback = bob.receive().await.unwrap();
+    assert_eq!(
+        fallback.payload,
+        MessagePayload::Text("anyone?".to_string())
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), presence.next())
+            .await
+            .is_err()
+    );
+}
+
+// ==================== Active Peer Probing ====================
+
+/// 模拟在线对端：收到 Ping 立即以该对端身份回送 Pong
+struct EchoChannel {
+    peer: DeviceId,
+    local: std::sync::OnceLock<Arc<dyn MessageHandler>>,
+}
+
+#[async_trait::async_trait]
+impl Channel for EchoChannel {
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Lan
+    }
+
+    async fn send(&self, message: Message) -> xlink::core::error::Result<()> {
+        if let (MessagePayload::Ping(ts, _), Some(handler)) = (&message.payload, self.local.get()) {
+            let pong = Message::new(self.peer, message.sender, MessagePayload::Pong(*ts));
+            handler.handle_message(pong).await?;
+        }
+        Ok(())
+    }
+
+    async fn check_state(&self, _target: &DeviceId) -> xlink::core::error::Result<ChannelState> {
+        Ok(ChannelState::default())
+    }
+
+    async fn start(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_duplicate_messages_across_channels_delivered_once() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let message = |text: &str| {
+        Message::new(
+            sender,
+            sdk.device_id(),
+            MessagePayload::Text(text.to_string()),
+        )
+    };
+
+    // 同一消息经两条通道到达，只交付一次
+    let direct = message("direct");
+    handler.handle_message(direct.clone()).await.unwrap();
+    handler.handle_message(direct.clone()).await.unwrap();
+    assert_eq!(sdk.try_receive().unwrap().id, direct.id);
+    assert!(sdk.try_receive().is_none());
+
+    // 不同发送方的相同消息 ID 不视为重复
+    let mut relayed = direct.clone();
+    relayed.sender = test_device_id();
+    handler.handle_message(relayed).await.unwrap();
+    assert!(sdk.try_receive().is_some());
+
+    // 超出容量的旧记录被淘汰，再次到达时重新交付
+    sdk.set_dedup_config(DedupConfig {
+        capacity: 1,
+        ttl_secs: 300,
+    });
+    let (first, second) = (message("first"), message("second"));
+    for msg in [&first, &second, &first] {
+        handler.handle_message(msg.clone()).await.unwrap();
+    }
+    let delivered: Vec<_> = std::iter::from_fn(|| sdk.try_receive())
+        .map(|m| m.id)
+        .collect();
+    assert_eq!(delivered, vec![first.id, second.id, first.id]);
+
+    // 容量为 0 关闭去重
+    sdk.set_dedup_config(DedupConfig {
+        capacity: 0,
+        ttl_secs: 300,
+    });
+    handler.handle_message(first.clone()).await.unwrap();
+    handler.handle_message(first.clone()).await.unwrap();
+    assert_eq!(std::iter::from_fn(|| sdk.try_receive()).count(), 2);
+}
+
+#[tokio::test]
+async fn test_pinned_identity_rejects_tampered_messages() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let handler = sdk.get_message_handler();
+    let sender = test_device_id();
+    let identity = CryptoEngine::new();
+    sdk.pin_identity_key(sender, identity.verifying_key());
+
+    let mut message = Message::new(
+        sender,
+        sdk.device_id(),
+        MessagePayload::Text("signed".to_string()),
+    );
+    identity.sign_message(&mut message);
+    handler.handle_message(message.clone()).await.unwrap();
+    assert_eq!(sdk.try_receive().unwrap().id, message.id);
+
+    // 中继篡改负载后签名不再匹配
+    let mut tampered = message.clone();
+    tampered.id = uuid::Uuid::new_v4();
+    tampered.payload = MessagePayload::Text("tampered".to_string());
+    let err = handler.handle_message(tampered).await.unwrap_err();
+    assert_eq!(err.code().0, 305);
+    assert!(sdk.try_receive().is_none());
+
+    // 中继剥离签名后同样拒绝
+    let mut stripped = message.clone();
+    stripped.id = uuid::Uuid::new_v4();
+    stripped.signature = None;
+    let err = handler.handle_message(stripped).await.unwrap_err();
+    assert_eq!(err.code().0, 305);
+    assert!(sdk.try_receive().is_none());
+
+    // 取消固定后不再校验
+    assert!(sdk.unpin_identity_key(sender));
+    let mut forged = message.clone();
+    forged.id = uuid::Uuid::new_v4();
+    forged.signature = Some(vec![0u8; 64]);
+    handler.handle_message(forged).await.unwrap();
+    assert!(sdk.try_receive().is_some());
+}
+
+#[tokio::test]
+async fn test_sdk_replies_are_signed_for_pinned_peers() {
+    // 双方互相固定身份公钥后，SDK 自动回送的确认同样带签名，确认发送照常完成
+    let (alice, bob) = connected_pair().await;
+    alice.pin_identity_key(bob.device_id(), bob.identity_public_key());
+    bob.pin_identity_key(alice.device_id(), alice.identity_public_key());
+    bob.capability_manager().update_channel_state(
+        alice.device_id(),
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            ..Default::default()
+        },
+    );
+
+    let status = alice
+        .send_with_ack(
+            bob.device_id(),
+            MessagePayload::Text("signed both ways".to_string()),
+            Duration::from_secs(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, AckStatus::Delivered);
+    let received = bob.receive().await.unwrap();
+    assert!(received.signature.is_some());
+}
+
+#[tokio::test]
+async fn test_identity_key_survives_state_migration() {
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let exported = sdk.export_sdk_state().unwrap();
+
+    let mut restored = TestSdkBuilder::new().build().await.unwrap();
+    assert_ne!(restored.identity_public_key(), sdk.identity_public_key());
+    restored.import_sdk_state(&exported).unwrap();
+    assert_eq!(restored.identity_public_key(), sdk.identity_public_key());
+}
+
+#[tokio::test]
+async fn test_refresh_peer_probes_each_channel() {
+    let peer = test_device_id();
+    let echo = Arc::new(EchoChannel {
+        peer,
+        local: std::sync::OnceLock::new(),
+    });
+    // 不回应的通道：Ping 发出后石沉大海
+    let silent = Arc::new(
+        MemoryChannel::new(Arc::new(NoOpMessageHandler), 0).with_type(ChannelType::Internet),
+    );
+    let sdk = TestSdkBuilder::new()
+        .with_channel(echo.clone())
+        .with_channel(silent.clone())
+        .build()
+        .await
+        .unwrap();
+    let _ = echo.local.set(sdk.get_message_handler());
+
+    let states = sdk
+        .refresh_peer(peer, Duration::from_millis(200))
+        .await
+        .unwrap();
+    let state_of = |channel_type| {
+        states
+            .iter()
+            .find(|(ct, _)| *ct == channel_type)
+            .map(|(_, state)| state.clone())
+            .unwrap()
+    };
+    assert_eq!(states.len(), 2);
+
+    let lan = state_of(ChannelType::Lan);
+    assert!(lan.available);
+    assert_eq!(lan.packet_loss_rate, 0.0);
+    assert_eq!(lan.failure_count, 0);
+    assert!(lan.rtt_ms < 200);
+
+    let internet = state_of(ChannelType::Internet);
+    assert!(!internet.available);
+    assert_eq!(internet.packet_loss_rate, 1.0);
+    assert_eq!(silent.get_sent_messages().await.len(), 3);
+
+    // 探测结果已写入能力管理器供路由使用
+    let cap_manager = sdk.router().capability_manager();
+    assert!(
+        cap_manager
+            .get_channel_state(&peer, &ChannelType::Lan)
+            .unwrap()
+            .available
+    );
+    assert!(
+        !cap_manager
+            .get_channel_state(&peer, &ChannelType::Internet)
+            .unwrap()
+            .available
+    );
+}
+
+// ==================== Message Plugins ====================
+
+/// 出站将文本转为大写，入站丢弃含 "blocked" 的文本
+struct UppercasePlugin;
+
+#[async_trait::async_trait]
+impl Plugin for UppercasePlugin {
+    fn name(&self) -> &str {
+        "uppercase"
+    }
+    fn version(&self) -> &str {
+        "1.0"
+    }
+    fn on_load(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+    fn on_unload(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+
+    async fn on_outbound(&self, message: &mut Message) -> xlink::core::error::Result<PluginAction> {
+        Ok(match &message.payload {
+            MessagePayload::Text(text) => {
+                PluginAction::Replace(MessagePayload::Text(text.to_uppercase()))
+            }
+            _ => PluginAction::Continue,
+        })
+    }
+
+    async fn on_inbound(&self, message: &mut Message) -> xlink::core::error::Result<PluginAction> {
+        Ok(match &message.payload {
+            MessagePayload::Text(text) if text.contains("blocked") => PluginAction::Drop,
+            _ => PluginAction::Continue,
+        })
+    }
+}
+
+/// 原地追加感叹号，丢弃 "SPAM"
+struct ExclaimPlugin;
+
+#[async_trait::async_trait]
+impl Plugin for ExclaimPlugin {
+    fn name(&self) -> &str {
+        "exclaim"
+    }
+    fn version(&self) -> &str {
+        "1.0"
+    }
+    fn on_load(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+    fn on_unload(&self) -> xlink::core::error::Result<()> {
+        Ok(())
+    }
+
+    async fn on_outbound(&self, message: &mut Message) -> xlink::core::error::Result<PluginAction> {
+        if let MessagePayload::Text(text) = &mut message.payload {
+            if text == "SPAM" {
+                return Ok(PluginAction::Drop);
+            }
+            text.push('!');
+        }
+        Ok(PluginAction::Continue)
+    }
+}
+
+#[tokio::test]
+async fn test_plugins_transform_and_drop_messages_in_registration_order() {
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let sdk = TestSdkBuilder::new()
+        .with_channel(channel.clone())
+        .build()
+        .await
+        .unwrap();
+    sdk.register_plugin(Arc::new(UppercasePlugin)).unwrap();
+    sdk.register_plugin(Arc::new(ExclaimPlugin)).unwrap();
+    let peer = test_device_id();
+
+    // 先注册的插件先运行：先转大写再追加感叹号
+    sdk.send(peer, MessagePayload::Text("hello".to_string()))
+        .await
+        .unwrap();
+    let sent = channel.get_sent_messages().await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].payload, MessagePayload::Text("HELLO!".to_string()));
+
+    // 被丢弃的消息不报错也不发出
+    sdk.send(peer, MessagePayload::Text("spam".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+
+    // 入站钩子在交付应用之前运行
+    let handler = sdk.get_message_handler();
+    for text in ["blocked content", "welcome"] {
+        handler
+            .handle_message(Message::new(
+                peer,
+                sdk.device_id(),
+                MessagePayload::Text(text.to_string()),
+            ))
+            .await
+            .unwrap();
+    }
+    let received = sdk.receive().await.unwrap();
+    assert_eq!(
+        received.payload,
+        MessagePayload::Text("welcome".to_string())
+    );
+    assert!(sdk.try_receive().is_none());
+
+    // 卸载后不再改写
+    sdk.unregister_plugin("uppercase").unwrap();
+    sdk.send(peer, MessagePayload::Text("quiet".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        channel.get_sent_messages().await[1].payload,
+        MessagePayload::Text("quiet!".to_string())
+    );
+}
+
+// ==================== SDK Builder ====================
+
+#[tokio::test]
+async fn test_builder_applies_configuration_and_rejects_conflicting_storage() {
+    let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::new());
+
+    // 互斥或不完整的存储选项在打开存储前即被拒绝
+    for builder in [
+        XLinkBuilder::new(test_device_capabilities())
+            .storage_path("./test_storage_builder_conflict")
+            .storage(storage.clone()),
+        XLinkBuilder::new(test_device_capabilities())
+            .storage(storage.clone())
+            .encrypted_storage(true)
+            .storage_key([1u8; 32]),
+        XLinkBuilder::new(test_device_capabilities())
+            .storage_path("./test_storage_builder_conflict")
+            .encrypted_storage(true),
+        XLinkBuilder::new(test_device_capabilities())
+            .storage_path("./test_storage_builder_conflict")
+            .storage_key([1u8; 32]),
+    ] {
+        let err = builder.build().await.err().unwrap();
+        assert_eq!(err.code().0, 102);
+    }
+    assert!(!std::path::Path::new("./test_storage_builder_conflict").exists());
+
+    let channel = Arc::new(MemoryChannel::new(Arc::new(NoOpMessageHandler), 0));
+    let compliance = ComplianceConfig {
+        privacy_mode: true,
+        retention_days: 7,
+        ..Default::default()
+    };
+    let rate_limit = RateLimitConfig {
+        per_sender_per_sec: 5,
+        ..Default::default()
+    };
+    let compression = CompressionConfig {
+        min_size_bytes: 4096,
+        ..Default::default()
+    };
+    let sdk = XLink::builder(test_device_capabilities())
+        .channel(channel.clone())
+        .storage(storage)
+        .compliance(compliance.clone())
+        .rate_limit(rate_limit)
+        .compression(compression)
+        .plugin(Arc::new(UppercasePlugin))
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(sdk.get_compliance_config().retention_days, 7);
+    assert!(sdk.get_compliance_config().privacy_mode);
+    assert_eq!(sdk.rate_limit_config(), rate_limit);
+    assert_eq!(sdk.compression_config(), compression);
+
+    // 初始插件在创建时已注册
+    let peer = test_device_id();
+    sdk.capability_manager().update_channel_state(
+        peer,
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            rtt_ms: 10,
+            bandwidth_bps: 10_000_000,
+            ..Default::default()
+        },
+    );
+    sdk.send(peer, MessagePayload::Text("hi".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        channel.get_sent_messages().await[0].payload,
+        MessagePayload::Text("HI".to_string())
+    );
+
+    // 加密文件存储
+    let storage_path = "./test_storage_builder_encrypted";
+    let _ = tokio::fs::remove_dir_all(storage_path).await;
+    XLink::builder(test_device_capabilities())
+        .storage_path(storage_path)
+        .encrypted_storage(true)
+        .storage_key([9u8; 32])
+        .build()
+        .await
+        .unwrap();
+    assert!(std::path::Path::new(storage_path).exists());
     let _ = tokio::fs::remove_dir_all(storage_path).await;
 }
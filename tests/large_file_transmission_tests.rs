@@ -224,6 +224,10 @@ async fn test_100mb_file_transmission_with_resume() {
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap()
                         .as_millis() as u64,
+                    fec_group_size: None,
+                    compression: None,
+                    checksum: None,
+                    chunk_crc: None,
                 },
             )
             .await;
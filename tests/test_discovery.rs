@@ -0,0 +1,340 @@
+mod common;
+
+use crate::common::{test_device_capabilities, test_device_id, TestSdkBuilder};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use x25519_dalek::{PublicKey, StaticSecret};
+use xlink::capability::manager::CapabilityManager;
+use xlink::core::error::Result;
+use xlink::core::events::SdkEvent;
+use xlink::core::types::{ChannelState, ChannelType, DeviceId, DeviceType};
+use xlink::discovery::ble::{BleAdvertisement, XLINK_BLE_SERVICE_UUID};
+use xlink::discovery::txt::{decode_capabilities, encode_capabilities, DISCOVERY_PROTOCOL_VERSION};
+use xlink::discovery::verification::{
+    respond_to_challenge, DiscoveredPeer, DiscoveryChallenge, DiscoveryChallengeResponse,
+    DiscoveryConfig, PeerChallenger,
+};
+use xlink::XLink;
+
+/// 模拟发现到的网络端点：挑战由实际监听该端点的设备应答
+struct EndpointChallenger {
+    endpoint: Arc<XLink>,
+}
+
+#[async_trait]
+impl PeerChallenger for EndpointChallenger {
+    async fn challenge(
+        &self,
+        _peer: &DiscoveredPeer,
+        challenge: &DiscoveryChallenge,
+    ) -> Result<DiscoveryChallengeResponse> {
+        Ok(self.endpoint.respond_to_discovery_challenge(challenge))
+    }
+}
+
+fn advert(sdk: &XLink) -> DiscoveredPeer {
+    let mut capabilities = test_device_capabilities();
+    capabilities.device_id = sdk.device_id();
+    DiscoveredPeer {
+        capabilities,
+        claimed_public_key: sdk.public_key(),
+        channel: ChannelType::Lan,
+        state: ChannelState {
+            available: true,
+            rtt_ms: 10,
+            packet_loss_rate: 0.0,
+            ..Default::default()
+        },
+    }
+}
+
+async fn next_event(events: &mut (impl futures::Stream<Item = SdkEvent> + Unpin)) -> SdkEvent {
+    tokio::time::timeout(Duration::from_secs(1), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_verified_peer_becomes_routable() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    let peer = Arc::new(TestSdkBuilder::new().build().await.unwrap());
+    local
+        .configure_discovery_verification(
+            DiscoveryConfig {
+                verify_peers: true,
+                ..Default::default()
+            },
+            Arc::new(EndpointChallenger {
+                endpoint: peer.clone(),
+            }),
+        )
+        .await;
+    local.pin_public_key(peer.device_id(), peer.public_key());
+    let mut events = Box::pin(local.events());
+
+    assert!(local.admit_discovered_peer(advert(&peer)).await);
+
+    match next_event(&mut events).await {
+        SdkEvent::DeviceVerified { device_id } => assert_eq!(device_id, peer.device_id()),
+        other => panic!("unexpected event: {:?}", other),
+    }
+    let cap_manager = local.capability_manager();
+    assert!(cap_manager.get_remote_device(peer.device_id()).is_some());
+    assert!(cap_manager
+        .get_channel_state(&peer.device_id(), &ChannelType::Lan)
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_spoofed_advert_fails_verification() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    let victim = TestSdkBuilder::new().build().await.unwrap();
+    let attacker = Arc::new(TestSdkBuilder::new().build().await.unwrap());
+
+    // 攻击者冒用受害者的设备 ID 与公钥进行广播，但端点由攻击者应答
+    local
+        .configure_discovery_verification(
+            DiscoveryConfig {
+                verify_peers: true,
+                ..Default::default()
+            },
+            Arc::new(EndpointChallenger { endpoint: attacker }),
+        )
+        .await;
+    local.pin_public_key(victim.device_id(), victim.public_key());
+    let mut events = Box::pin(local.events());
+
+    assert!(!local.admit_discovered_peer(advert(&victim)).await);
+
+    match next_event(&mut events).await {
+        SdkEvent::DeviceRejected { device_id, .. } => assert_eq!(device_id, victim.device_id()),
+        other => panic!("unexpected event: {:?}", other),
+    }
+    let cap_manager = local.capability_manager();
+    assert!(cap_manager.get_remote_device(victim.device_id()).is_none());
+    assert!(cap_manager
+        .get_channel_state(&victim.device_id(), &ChannelType::Lan)
+        .is_none());
+}
+
+/// 冒充者：以自己的静态私钥、但声称是 `device_id` 来应答挑战
+struct ImpostorChallenger {
+    secret: StaticSecret,
+    device_id: DeviceId,
+}
+
+#[async_trait]
+impl PeerChallenger for ImpostorChallenger {
+    async fn challenge(
+        &self,
+        _peer: &DiscoveredPeer,
+        challenge: &DiscoveryChallenge,
+    ) -> Result<DiscoveryChallengeResponse> {
+        Ok(respond_to_challenge(
+            &self.secret,
+            self.device_id,
+            challenge,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_impostor_with_own_key_fails_verification() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    let victim = TestSdkBuilder::new().build().await.unwrap();
+    let secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
+    let impostor_key = PublicKey::from(&secret);
+    local
+        .configure_discovery_verification(
+            DiscoveryConfig {
+                verify_peers: true,
+                ..Default::default()
+            },
+            Arc::new(ImpostorChallenger {
+                secret,
+                device_id: victim.device_id(),
+            }),
+        )
+        .await;
+
+    // 冒充者广播受害者的设备 ID 与自己的公钥，并能以自己的私钥正确应答挑战
+    let mut spoofed = advert(&victim);
+    spoofed.claimed_public_key = impostor_key;
+
+    // 该设备尚未固定公钥时不接纳
+    assert!(!local.admit_discovered_peer(spoofed.clone()).await);
+
+    // 固定受害者的公钥后，声明的公钥与之不符同样被拒绝
+    local.pin_public_key(victim.device_id(), victim.public_key());
+    assert!(!local.admit_discovered_peer(spoofed).await);
+    assert!(local
+        .capability_manager()
+        .get_remote_device(victim.device_id())
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_unverified_discovery_is_trusted_by_default() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    let peer = TestSdkBuilder::new().build().await.unwrap();
+
+    assert!(local.admit_discovered_peer(advert(&peer)).await);
+    assert!(local
+        .capability_manager()
+        .get_remote_device(peer.device_id())
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_ble_advertisements_filtered_by_service_uuid_and_rssi() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    local
+        .set_discovery_config(DiscoveryConfig {
+            min_rssi: -75,
+            ..Default::default()
+        })
+        .await;
+    let cap_manager = local.capability_manager();
+    let ble_advert = |service_uuids: Vec<uuid::Uuid>, rssi: i16| {
+        let peer = advert(&local);
+        let mut capabilities = peer.capabilities;
+        capabilities.device_id = test_device_id();
+        BleAdvertisement {
+            capabilities,
+            claimed_public_key: peer.claimed_public_key,
+            service_uuids,
+            rssi,
+            tx_power: Some(-59),
+        }
+    };
+
+    // 未声明服务 UUID 或信号低于阈值的广播不登记通道状态
+    for advertisement in [
+        ble_advert(vec![uuid::Uuid::new_v4()], -40),
+        ble_advert(vec![XLINK_BLE_SERVICE_UUID], -80),
+    ] {
+        let device_id = advertisement.capabilities.device_id;
+        assert!(!local.handle_ble_advertisement(advertisement).await);
+        assert!(cap_manager
+            .get_channel_state(&device_id, &ChannelType::BluetoothLE)
+            .is_none());
+    }
+
+    // RSSI 换算为信号强度与估算距离：-79 dBm 相对 1 米处 -59 dBm 约 10 米
+    let advertisement = ble_advert(vec![uuid::Uuid::new_v4(), XLINK_BLE_SERVICE_UUID], -79);
+    let admitted = advertisement
+        .clone()
+        .filter(&DiscoveryConfig::default())
+        .unwrap();
+    assert_eq!(admitted.state.signal_strength, Some(-79));
+    assert!((admitted.state.distance_meters.unwrap() - 10.0).abs() < 0.01);
+
+    let advertisement = ble_advert(vec![XLINK_BLE_SERVICE_UUID], -70);
+    let device_id = advertisement.capabilities.device_id;
+    assert!(local.handle_ble_advertisement(advertisement).await);
+    let state = cap_manager
+        .get_channel_state(&device_id, &ChannelType::BluetoothLE)
+        .unwrap();
+    assert!(state.available);
+    assert_eq!(state.signal_strength, Some(-70));
+}
+
+#[tokio::test]
+async fn test_discovered_peers_evicted_least_recently_seen_first() {
+    let local = TestSdkBuilder::new().build().await.unwrap();
+    local.set_max_discovered_peers(3).await;
+    let cap_manager = local.capability_manager();
+
+    // 应用自行注册的对端不计入上限，也不会被淘汰
+    let manual = test_device_capabilities();
+    let manual_id = manual.device_id;
+    cap_manager.register_remote_device(manual.clone());
+    let mut manual_advert = advert(&local);
+    manual_advert.capabilities = manual;
+    assert!(local.admit_discovered_peer(manual_advert).await);
+
+    let peers: Vec<DeviceId> = (0..5).map(|_| test_device_id()).collect();
+    let discover = |device_id: DeviceId| {
+        let mut peer = advert(&local);
+        peer.capabilities.device_id = device_id;
+        peer
+    };
+    for device_id in &peers[..3] {
+        assert!(local.admit_discovered_peer(discover(*device_id)).await);
+    }
+    assert_eq!(local.discovered_peer_count().await, 3);
+
+    // 再次发现第一个对端，使第二个成为最久未见
+    assert!(local.admit_discovered_peer(discover(peers[0])).await);
+    let mut events = Box::pin(local.events());
+
+    for device_id in &peers[3..] {
+        assert!(local.admit_discovered_peer(discover(*device_id)).await);
+    }
+    for expected in [peers[1], peers[2]] {
+        match next_event(&mut events).await {
+            SdkEvent::DeviceLost { device_id } => assert_eq!(device_id, expected),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    assert_eq!(local.discovered_peer_count().await, 3);
+    assert!(cap_manager.get_remote_device(peers[1]).is_none());
+    assert!(cap_manager
+        .get_channel_state(&peers[2], &ChannelType::Lan)
+        .is_none());
+    for device_id in [peers[0], peers[3], peers[4], manual_id] {
+        assert!(cap_manager.get_remote_device(device_id).is_some());
+    }
+}
+
+#[test]
+fn test_mdns_txt_capabilities_enable_negotiation_on_discovery() {
+    let mut advertised = test_device_capabilities();
+    advertised.device_type = DeviceType::Laptop;
+    advertised.supported_channels = HashSet::from([ChannelType::Lan, ChannelType::BluetoothLE]);
+    advertised.battery_level = Some(42);
+
+    let txt = encode_capabilities(&advertised);
+    assert_eq!(
+        txt.get("pv").map(String::as_str),
+        Some(DISCOVERY_PROTOCOL_VERSION.to_string().as_str())
+    );
+    let decoded = decode_capabilities(&txt, "peer.local.").unwrap();
+    assert_eq!(decoded.device_id, advertised.device_id);
+    assert_eq!(decoded.device_type, DeviceType::Laptop);
+    assert_eq!(decoded.supported_channels, advertised.supported_channels);
+    assert_eq!(decoded.battery_level, Some(42));
+    assert_eq!(decoded.device_name, "peer.local.");
+
+    // 解析出的部分能力足以直接协商共同通道
+    let mut local = test_device_capabilities();
+    local.supported_channels = HashSet::from([ChannelType::Lan, ChannelType::Internet]);
+    let cap_manager = CapabilityManager::new(local);
+    cap_manager.register_remote_device(decoded);
+    assert_eq!(
+        cap_manager
+            .negotiate_channels(advertised.device_id)
+            .unwrap(),
+        vec![ChannelType::Lan]
+    );
+
+    // 协议版本不兼容或缺少设备 ID 的广播不予接纳
+    let mut incompatible = txt.clone();
+    incompatible.insert("pv".to_string(), "99".to_string());
+    let err = decode_capabilities(&incompatible, "peer.local.").unwrap_err();
+    assert_eq!(err.code().0, 801);
+    let mut anonymous = txt;
+    anonymous.remove("id");
+    assert_eq!(
+        decode_capabilities(&anonymous, "peer.local.")
+            .unwrap_err()
+            .code()
+            .0,
+        802
+    );
+}
@@ -42,6 +42,14 @@ mod tests {
                         .unwrap_or_default()
                         .as_secs(),
                     require_ack: true,
+                    require_ordered: false,
+                    sequence: None,
+                    sequence_epoch: None,
+                    correlation_id: None,
+                    in_reply_to: None,
+                    topic: None,
+                    expires_at: None,
+                    signature: None,
                 };
                 storage.save_message(&message).await.unwrap();
                 storage.save_pending_message(&message).await.unwrap();
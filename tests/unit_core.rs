@@ -5,15 +5,30 @@
 mod common;
 
 use crate::common::{
-    test_device_capabilities, test_device_id, test_text_message, NoOpMessageHandler,
+    test_device_capabilities, test_device_id, test_text_message, NoOpMessageHandler, TestSdkBuilder,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use xlink::capability::manager::CapabilityManager;
-use xlink::core::types::{ChannelType, DeviceCapabilities, DeviceType, MessagePayload};
+use xlink::core::clock::MockClock;
+use xlink::core::error::{RetrySuggestion, XLinkError};
+use xlink::core::retry::{backoff_delay, retry_with_suggestion, ReconnectBackoff};
+use xlink::core::traits::Channel;
+use xlink::core::types::{
+    ChannelState, ChannelType, DeviceCapabilities, DeviceType, Message, MessagePayload,
+    MessagePriority, PresenceHint, ReconnectConfig, TrafficClass,
+};
 use xlink::heartbeat::manager::HeartbeatManager;
-use xlink::router::scoring::Scorer;
+use xlink::router::balance::BalanceMode;
+use xlink::router::breaker::{BreakerState, CircuitBreakerConfig};
+use xlink::router::introspection::{
+    format_routing_table, RouteDecision, RouteExclusion, RouteExplanation, RouteSource,
+};
+use xlink::router::scoring::{Scorer, ScorerConfig};
 use xlink::router::selector::Router;
+use xlink::router::send_queue::{SendQueue, SendQueueConfig};
+use xlink::router::strategy::{RoutingStrategy, ScorerStrategy};
 
 // ==================== Router & Scoring Tests ====================
 
@@ -81,8 +96,785 @@ async fn test_router_cost_sensitive() {
     assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
 }
 
+#[tokio::test]
+async fn test_router_excludes_channels_too_small_for_message() {
+    // UT-ROU-007: 超出 BLE 负载上限的消息改走 LAN，只有 BLE 时返回明确错误
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let peer = test_device_id();
+    let state = xlink::core::types::ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state);
+
+    let ble: Arc<dyn Channel> = Arc::new(
+        xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+            .with_type(ChannelType::BluetoothLE),
+    );
+    let lan: Arc<dyn Channel> = Arc::new(xlink::channels::memory::MemoryChannel::new(
+        Arc::new(NoOpMessageHandler),
+        0,
+    ));
+    let max_payload = ble.max_payload().unwrap();
+    assert_eq!(lan.max_payload(), None);
+
+    let message = |len: usize| {
+        let mut msg = test_text_message("");
+        msg.recipient = peer;
+        msg.payload = MessagePayload::Binary(vec![0; len]);
+        msg
+    };
+
+    let channels = HashMap::from([
+        (ChannelType::BluetoothLE, ble.clone()),
+        (ChannelType::Lan, lan),
+    ]);
+    let router = Router::new(channels, cap_manager.clone());
+    let selected = router.select_channel(&message(64)).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
+    let selected = router
+        .select_channel(&message(max_payload + 1))
+        .await
+        .unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::Lan);
+
+    let router = Router::new(
+        HashMap::from([(ChannelType::BluetoothLE, ble)]),
+        cap_manager,
+    );
+    let err = router
+        .select_channel(&message(max_payload + 1))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code().0, 206);
+}
+
+#[tokio::test]
+async fn test_routing_table_introspection() {
+    // UT-ROU-006: 路由表导出候选通道、评分、选择结果与排除原因
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let peer = test_device_id();
+    let good = xlink::core::types::ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::Lan, good.clone());
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::BluetoothLE,
+        xlink::core::types::ChannelState {
+            available: false,
+            failure_count: 3,
+            ..good.clone()
+        },
+    );
+    // 本地未注册 Internet 通道
+    cap_manager.update_channel_state(peer, ChannelType::Internet, good);
+
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(
+        ChannelType::Lan,
+        Arc::new(xlink::channels::memory::MemoryChannel::new(
+            Arc::new(NoOpMessageHandler),
+            0,
+        )),
+    );
+    channels.insert(
+        ChannelType::BluetoothLE,
+        Arc::new(
+            xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                .with_type(ChannelType::BluetoothLE),
+        ),
+    );
+    let router = Router::new(channels, cap_manager);
+
+    let table = router.routing_table();
+    assert_eq!(table.len(), 1);
+    let info = &table[0];
+    assert_eq!(info.device_id, peer);
+    assert_eq!(info.chosen, Some(ChannelType::Lan));
+    assert_eq!(info.channels.len(), 3);
+
+    let channel = |ctype| info.channels.iter().find(|c| c.channel == ctype).unwrap();
+    assert!(channel(ChannelType::Lan).excluded.is_none());
+    let ble = channel(ChannelType::BluetoothLE);
+    assert_eq!(ble.excluded, Some(RouteExclusion::Unavailable));
+    assert!(ble.stale);
+    assert_eq!(ble.score, 0.0);
+    assert_eq!(
+        channel(ChannelType::Internet).excluded,
+        Some(RouteExclusion::NoLocalChannel)
+    );
+
+    // 只读：不产生流量统计
+    assert!(router.get_traffic_stats().unwrap().is_empty());
+
+    let text = format_routing_table(&table);
+    assert!(text.contains("chosen Lan"));
+    assert!(text.contains("[excluded: unavailable]"));
+    assert!(text.contains("[stale]"));
+}
+
+#[tokio::test]
+async fn test_recent_failures_deprioritize_channel_until_window_passes() {
+    // UT-ROU-007: 近期失败的通道被降级，窗口过后恢复
+    let mut caps = test_device_capabilities();
+    caps.is_charging = false;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let peer = test_device_id();
+    let state = xlink::core::types::ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state);
+
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(
+        ChannelType::Lan,
+        Arc::new(xlink::channels::memory::MemoryChannel::new(
+            Arc::new(NoOpMessageHandler),
+            0,
+        )),
+    );
+    channels.insert(
+        ChannelType::BluetoothLE,
+        Arc::new(
+            xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                .with_type(ChannelType::BluetoothLE),
+        ),
+    );
+    let window = std::time::Duration::from_millis(400);
+    let router = Router::new(channels, cap_manager.clone()).with_scorer_config(ScorerConfig {
+        failure_window: window,
+        recent_failure_penalty: 0.5,
+    });
+    let score_of = |ctype| {
+        router.routing_table()[0]
+            .channels
+            .iter()
+            .find(|c| c.channel == ctype)
+            .unwrap()
+            .score
+    };
+
+    assert_eq!(
+        router.routing_table()[0].chosen,
+        Some(ChannelType::BluetoothLE)
+    );
+    let healthy = score_of(ChannelType::BluetoothLE);
+
+    // 一阵密集失败后改走 LAN
+    for _ in 0..3 {
+        cap_manager.record_channel_failure(peer, ChannelType::BluetoothLE);
+    }
+    assert_eq!(
+        cap_manager.recent_failures(&peer, &ChannelType::BluetoothLE, window),
+        3
+    );
+    let fresh = score_of(ChannelType::BluetoothLE);
+    assert!(fresh < score_of(ChannelType::Lan));
+    assert_eq!(router.routing_table()[0].chosen, Some(ChannelType::Lan));
+
+    // 失败随时间衰减：惩罚减轻但尚未消失
+    tokio::time::sleep(window / 2).await;
+    let decayed = score_of(ChannelType::BluetoothLE);
+    assert!(decayed > fresh && decayed < healthy);
+
+    // 窗口过后通道恢复原有评分
+    tokio::time::sleep(window).await;
+    assert_eq!(
+        cap_manager.recent_failures(&peer, &ChannelType::BluetoothLE, window),
+        0
+    );
+    assert_eq!(score_of(ChannelType::BluetoothLE), healthy);
+    assert_eq!(
+        router.routing_table()[0].chosen,
+        Some(ChannelType::BluetoothLE)
+    );
+}
+
+#[tokio::test]
+async fn test_flapping_channel_skipped_while_circuit_open() {
+    // UT-ROU-012: 连续失败达到阈值后熔断，冷却期内跳过，冷却后放行一次探测
+    let mut caps = test_device_capabilities();
+    caps.is_charging = false;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let cooldown = Duration::from_millis(300);
+    cap_manager.set_circuit_breaker_config(CircuitBreakerConfig {
+        failure_threshold: 3,
+        cooldown,
+    });
+    let peer = test_device_id();
+    let state = ChannelState {
+        available: true,
+        rtt_ms: 10,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state);
+
+    let mut channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::new();
+    channels.insert(
+        ChannelType::Lan,
+        Arc::new(xlink::channels::memory::MemoryChannel::new(
+            Arc::new(NoOpMessageHandler),
+            0,
+        )),
+    );
+    channels.insert(
+        ChannelType::BluetoothLE,
+        Arc::new(
+            xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                .with_type(ChannelType::BluetoothLE),
+        ),
+    );
+    let router = Router::new(channels, cap_manager.clone());
+    let message = Message::new(
+        test_device_id(),
+        peer,
+        MessagePayload::Text("ping".to_string()),
+    );
+    let (router_ref, message_ref) = (&router, &message);
+    let select = move || async move {
+        router_ref
+            .select_channel(message_ref)
+            .await
+            .unwrap()
+            .channel_type()
+    };
+    let ble_state = || cap_manager.breaker_state(&peer, &ChannelType::BluetoothLE);
+
+    assert_eq!(select().await, ChannelType::BluetoothLE);
+
+    // 阈值以下的失败不熔断，成功清零失败计数
+    for _ in 0..2 {
+        cap_manager.record_channel_result(peer, ChannelType::BluetoothLE, false);
+    }
+    assert_eq!(ble_state(), BreakerState::Closed);
+    cap_manager.record_channel_result(peer, ChannelType::BluetoothLE, true);
+    assert_eq!(
+        cap_manager
+            .get_channel_state(&peer, &ChannelType::BluetoothLE)
+            .unwrap()
+            .failure_count,
+        0
+    );
+
+    for _ in 0..3 {
+        cap_manager.record_channel_result(peer, ChannelType::BluetoothLE, false);
+    }
+    assert_eq!(ble_state(), BreakerState::Open);
+    assert_eq!(select().await, ChannelType::Lan);
+    let ble = router.routing_table()[0]
+        .channels
+        .iter()
+        .find(|c| c.channel == ChannelType::BluetoothLE)
+        .unwrap()
+        .clone();
+    assert_eq!(ble.excluded, Some(RouteExclusion::CircuitOpen));
+
+    // 冷却结束后只放行一次探测，探测失败重新熔断
+    tokio::time::sleep(cooldown).await;
+    assert_eq!(ble_state(), BreakerState::HalfOpen);
+    assert_eq!(select().await, ChannelType::BluetoothLE);
+    assert_eq!(ble_state(), BreakerState::Open);
+    assert_eq!(select().await, ChannelType::Lan);
+    cap_manager.record_channel_result(peer, ChannelType::BluetoothLE, false);
+    assert_eq!(ble_state(), BreakerState::Open);
+
+    // 探测成功后闭合
+    tokio::time::sleep(cooldown).await;
+    assert_eq!(select().await, ChannelType::BluetoothLE);
+    cap_manager.record_channel_result(peer, ChannelType::BluetoothLE, true);
+    assert_eq!(ble_state(), BreakerState::Closed);
+    assert_eq!(select().await, ChannelType::BluetoothLE);
+}
+
+#[tokio::test]
+async fn test_explain_route_reports_every_candidate_without_sending() {
+    // UT-ROU-013: 选路解释列出每个本地通道的评分与落选原因，不记录流量与路由历史
+    let mut caps = test_device_capabilities();
+    caps.is_charging = false;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let peer = test_device_id();
+    let state = ChannelState {
+        available: true,
+        rtt_ms: 10,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state.clone());
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::WiFiDirect,
+        ChannelState {
+            available: false,
+            ..state
+        },
+    );
+
+    let memory = |ctype| -> Arc<dyn Channel> {
+        Arc::new(
+            xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                .with_type(ctype),
+        )
+    };
+    let ble = memory(ChannelType::BluetoothLE);
+    let max_payload = ble.max_payload().unwrap();
+    let channels = HashMap::from([
+        (ChannelType::BluetoothLE, ble),
+        (ChannelType::Lan, memory(ChannelType::Lan)),
+        (ChannelType::WiFiDirect, memory(ChannelType::WiFiDirect)),
+        (ChannelType::Internet, memory(ChannelType::Internet)),
+    ]);
+    let router = Router::new(channels, cap_manager);
+    let message =
+        |len: usize| Message::new(test_device_id(), peer, MessagePayload::Binary(vec![0; len]));
+
+    let explanation = router.explain_route(&message(64));
+    assert_eq!(explanation.candidates.len(), 4);
+    assert_eq!(explanation.chosen, Some(ChannelType::BluetoothLE));
+    assert_eq!(explanation.source, RouteSource::Scored);
+    let decision =
+        |explanation: &RouteExplanation, ctype| explanation.candidate(ctype).unwrap().decision;
+    assert_eq!(
+        decision(&explanation, ChannelType::BluetoothLE),
+        RouteDecision::Chosen
+    );
+    assert_eq!(
+        decision(&explanation, ChannelType::Lan),
+        RouteDecision::LostTiebreak
+    );
+    assert_eq!(
+        decision(&explanation, ChannelType::WiFiDirect),
+        RouteDecision::Unavailable
+    );
+    assert_eq!(
+        decision(&explanation, ChannelType::Internet),
+        RouteDecision::NoState
+    );
+    let ble_score = explanation
+        .candidate(ChannelType::BluetoothLE)
+        .unwrap()
+        .score
+        .unwrap();
+    assert!(
+        ble_score
+            > explanation
+                .candidate(ChannelType::Lan)
+                .unwrap()
+                .score
+                .unwrap()
+    );
+    assert_eq!(explanation.candidates[0].channel, ChannelType::BluetoothLE);
+    assert!(explanation.to_string().contains("lost tiebreak"));
+
+    // 超出 BLE 负载上限时改走 LAN
+    let explanation = router.explain_route(&message(max_payload + 1));
+    assert_eq!(explanation.chosen, Some(ChannelType::Lan));
+    assert_eq!(
+        decision(&explanation, ChannelType::BluetoothLE),
+        RouteDecision::PayloadTooLarge { max_payload }
+    );
+
+    // 解释与实际选路一致，且解释本身不留下痕迹
+    assert!(router.get_traffic_stats().unwrap().is_empty());
+    assert!(!router.routing_table()[0].predicted);
+    let selected = router.select_channel(&message(64)).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
+}
+
+#[tokio::test]
+async fn test_traffic_class_pinned_channel_overrides_scoring() {
+    // UT-ROU-008: 媒体流量固定到 LAN，文本仍按评分选路；固定通道不可用时回退评分
+    let mut caps = test_device_capabilities();
+    caps.is_charging = false;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let peer = test_device_id();
+    let state = xlink::core::types::ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state.clone());
+
+    let channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::from([
+        (
+            ChannelType::Lan,
+            Arc::new(xlink::channels::memory::MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                0,
+            )) as Arc<dyn Channel>,
+        ),
+        (
+            ChannelType::BluetoothLE,
+            Arc::new(
+                xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                    .with_type(ChannelType::BluetoothLE),
+            ) as Arc<dyn Channel>,
+        ),
+    ]);
+    let router = Router::new(channels, cap_manager.clone());
+    router.set_class_channel(TrafficClass::Media, ChannelType::Lan);
+
+    let mut text = test_text_message("hello");
+    text.recipient = peer;
+    let mut chunk = test_text_message("");
+    chunk.recipient = peer;
+    chunk.payload = MessagePayload::StreamChunk {
+        stream_id: uuid::Uuid::new_v4(),
+        total_chunks: 1,
+        chunk_index: 0,
+        data: vec![0; 16],
+        sent_at: 0,
+        fec_group_size: None,
+        compression: None,
+        checksum: None,
+        chunk_crc: None,
+    };
+    assert_eq!(TrafficClass::of(&chunk), TrafficClass::Media);
+    assert_eq!(TrafficClass::of(&text), TrafficClass::Data);
+
+    let selected = router.select_channel(&chunk).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::Lan);
+    let selected = router.select_channel(&text).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
+
+    // 固定通道不可用时回退到评分
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::Lan,
+        xlink::core::types::ChannelState {
+            available: false,
+            ..state
+        },
+    );
+    let selected = router.select_channel(&chunk).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
+}
+
+/// 大于 1MB 的二进制负载固定走 LAN，其余交给默认评分；失败过多的通道被熔断
+struct BulkOverLanStrategy;
+
+impl RoutingStrategy for BulkOverLanStrategy {
+    fn rank(
+        &self,
+        candidates: &[(ChannelType, &ChannelState)],
+        caps: &DeviceCapabilities,
+        msg: &Message,
+    ) -> Vec<ChannelType> {
+        let healthy: Vec<(ChannelType, &ChannelState)> = candidates
+            .iter()
+            .filter(|(_, state)| state.failure_count < 3 && state.last_heartbeat > 0)
+            .copied()
+            .collect();
+        let bulk = matches!(&msg.payload, MessagePayload::Binary(data) if data.len() > 1024 * 1024);
+        if bulk && healthy.iter().any(|(ctype, _)| *ctype == ChannelType::Lan) {
+            return vec![ChannelType::Lan];
+        }
+        ScorerStrategy.rank(&healthy, caps, msg)
+    }
+}
+
+#[tokio::test]
+async fn test_custom_routing_strategy_overrides_scoring() {
+    // UT-ROU-009: 自定义策略接管排序，可读取 failure_count 与 last_heartbeat
+    let mut caps = test_device_capabilities();
+    caps.is_charging = false;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let peer = test_device_id();
+    let state = ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        last_heartbeat: 1,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::BluetoothLE, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state.clone());
+
+    let channels: HashMap<ChannelType, Arc<dyn Channel>> = HashMap::from([
+        (
+            ChannelType::Lan,
+            Arc::new(xlink::channels::memory::MemoryChannel::new(
+                Arc::new(NoOpMessageHandler),
+                0,
+            )) as Arc<dyn Channel>,
+        ),
+        (
+            ChannelType::BluetoothLE,
+            Arc::new(
+                xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                    .with_type(ChannelType::BluetoothLE),
+            ) as Arc<dyn Channel>,
+        ),
+    ]);
+    let router =
+        Router::new(channels, cap_manager.clone()).with_strategy(Arc::new(BulkOverLanStrategy));
+
+    let message = |payload| {
+        let mut msg = test_text_message("");
+        msg.recipient = peer;
+        msg.payload = payload;
+        msg
+    };
+    let text = message(MessagePayload::Text("hello".into()));
+    let bulk = message(MessagePayload::Binary(vec![0; 1024 * 1024 + 1]));
+
+    // 小消息沿用默认评分（未充电时 BLE 更省电），大块二进制固定走 LAN
+    let selected = router.select_channel(&text).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::BluetoothLE);
+    let selected = router.select_channel(&bulk).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::Lan);
+
+    // 策略依据 failure_count 熔断 BLE
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::BluetoothLE,
+        ChannelState {
+            failure_count: 3,
+            ..state
+        },
+    );
+    let selected = router.select_channel(&text).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::Lan);
+
+    // 恢复内置评分
+    router.set_strategy(None);
+    assert!(router.strategy().is_none());
+}
+
+#[tokio::test]
+async fn test_send_queue_limits_in_flight_and_prefers_priority() {
+    // UT-ROU-010: 通道在途发送达到上限后按优先级排队，队列为空时直接发送
+    let queue = Arc::new(SendQueue::new(SendQueueConfig {
+        max_in_flight: 1,
+        ..Default::default()
+    }));
+    let first = queue.acquire(ChannelType::Lan, MessagePriority::Low).await;
+    assert_eq!(queue.in_flight(ChannelType::Lan), 1);
+
+    let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let mut waiters = Vec::new();
+    for priority in [
+        MessagePriority::Low,
+        MessagePriority::Normal,
+        MessagePriority::Critical,
+        MessagePriority::High,
+    ] {
+        let (waiting, order) = (queue.clone(), order.clone());
+        waiters.push(tokio::spawn(async move {
+            let _permit = waiting.acquire(ChannelType::Lan, priority).await;
+            order.lock().push(priority);
+        }));
+        while queue.queued(ChannelType::Lan) < waiters.len() {
+            tokio::task::yield_now().await;
+        }
+    }
+    // 其他通道不受影响
+    drop(
+        queue
+            .acquire(ChannelType::BluetoothLE, MessagePriority::Low)
+            .await,
+    );
+
+    drop(first);
+    for waiter in waiters {
+        waiter.await.unwrap();
+    }
+    assert_eq!(
+        *order.lock(),
+        vec![
+            MessagePriority::Critical,
+            MessagePriority::High,
+            MessagePriority::Normal,
+            MessagePriority::Low,
+        ]
+    );
+    assert_eq!(queue.in_flight(ChannelType::Lan), 0);
+
+    // Router 经队列交给选中的通道发送
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let peer = test_device_id();
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::Lan,
+        ChannelState {
+            available: true,
+            rtt_ms: 10,
+            packet_loss_rate: 0.0,
+            ..Default::default()
+        },
+    );
+    let channel = Arc::new(xlink::channels::memory::MemoryChannel::new(
+        Arc::new(NoOpMessageHandler),
+        0,
+    ));
+    let router = Router::new(
+        HashMap::from([(ChannelType::Lan, channel.clone() as Arc<dyn Channel>)]),
+        cap_manager,
+    );
+    router.set_send_queue_config(SendQueueConfig {
+        max_in_flight: 2,
+        ..Default::default()
+    });
+    assert_eq!(router.send_queue_config().max_in_flight, 2);
+    let mut message = test_text_message("queued");
+    message.recipient = peer;
+    assert_eq!(
+        router.enqueue_send(message).await.unwrap(),
+        ChannelType::Lan
+    );
+    assert_eq!(channel.get_sent_messages().await.len(), 1);
+    assert_eq!(router.send_queue_depth(ChannelType::Lan), (0, 0));
+}
+
+#[tokio::test]
+async fn test_balance_mode_spreads_traffic_across_equivalent_channels() {
+    // UT-ROU-011: 得分相近的通道按均衡模式分摊流量，流量统计反映分配结果
+    // 充电时功耗不影响评分，LAN 与 WiFi Direct 得分相同
+    // 默认通道状态按全部丢包计，显式设为无丢包，使后面劣化的通道得分明显更低
+    let mut caps = test_device_capabilities();
+    caps.is_charging = true;
+    let cap_manager = Arc::new(CapabilityManager::new(caps));
+    let peer = test_device_id();
+    let state = xlink::core::types::ChannelState {
+        available: true,
+        rtt_ms: 10,
+        packet_loss_rate: 0.0,
+        network_type: xlink::core::types::NetworkType::WiFi,
+        ..Default::default()
+    };
+    cap_manager.update_channel_state(peer, ChannelType::Lan, state.clone());
+    cap_manager.update_channel_state(peer, ChannelType::WiFiDirect, state);
+    let channels: HashMap<ChannelType, Arc<dyn Channel>> =
+        [ChannelType::Lan, ChannelType::WiFiDirect]
+            .into_iter()
+            .map(|ctype| {
+                let channel =
+                    xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 0)
+                        .with_type(ctype);
+                (ctype, Arc::new(channel) as Arc<dyn Channel>)
+            })
+            .collect();
+    let mut message = test_text_message("0123456789");
+    message.recipient = peer;
+
+    let spread = |router: &Router| {
+        let stats = router.get_traffic_stats().unwrap();
+        (
+            stats.get(&ChannelType::Lan).copied().unwrap_or(0),
+            stats.get(&ChannelType::WiFiDirect).copied().unwrap_or(0),
+        )
+    };
+
+    // 默认只用得分最高的通道
+    let router = Router::new(channels.clone(), cap_manager.clone());
+    assert_eq!(router.balance_config().mode, BalanceMode::Single);
+    for _ in 0..10 {
+        router.select_channel(&message).await.unwrap();
+    }
+    let (lan, wifi) = spread(&router);
+    assert!(lan == 0 || wifi == 0);
+    assert_eq!(lan + wifi, 100);
+
+    // 轮询：两个通道交替使用
+    let router = Router::new(channels.clone(), cap_manager.clone());
+    router.set_balance_mode(BalanceMode::RoundRobin);
+    let mut picked = Vec::new();
+    for _ in 0..10 {
+        picked.push(
+            router
+                .select_channel(&message)
+                .await
+                .unwrap()
+                .channel_type(),
+        );
+    }
+    assert!(picked.windows(2).all(|pair| pair[0] != pair[1]));
+    assert_eq!(spread(&router), (50, 50));
+
+    // 加权随机：两个通道都会被使用
+    let router = Router::new(channels.clone(), cap_manager.clone());
+    router.set_balance_mode(BalanceMode::Weighted);
+    for _ in 0..200 {
+        router.select_channel(&message).await.unwrap();
+    }
+    let (lan, wifi) = spread(&router);
+    assert!(lan > 0 && wifi > 0);
+
+    // 得分差超过 epsilon 的通道不参与均衡
+    let router = Router::new(channels, cap_manager.clone());
+    router.set_balance_mode(BalanceMode::RoundRobin);
+    cap_manager.update_channel_state(
+        peer,
+        ChannelType::WiFiDirect,
+        xlink::core::types::ChannelState {
+            available: true,
+            rtt_ms: 500,
+            packet_loss_rate: 0.3,
+            network_type: xlink::core::types::NetworkType::WiFi,
+            ..Default::default()
+        },
+    );
+    for _ in 0..10 {
+        router.select_channel(&message).await.unwrap();
+    }
+    assert_eq!(spread(&router), (100, 0));
+}
+
 // ==================== Capability Manager Tests ====================
 
+#[tokio::test]
+async fn test_capability_invalidation_removes_route_candidates() {
+    // UT-CAP-005: 精细化失效远程设备能力
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let target_device = test_device_id();
+
+    let mut channels: HashMap<ChannelType, Arc<dyn xlink::core::traits::Channel>> = HashMap::new();
+    for ctype in [ChannelType::BluetoothLE, ChannelType::Lan] {
+        let channel = Arc::new(
+            xlink::channels::memory::MemoryChannel::new(Arc::new(NoOpMessageHandler), 10)
+                .with_type(ctype),
+        );
+        let state = channel.check_state(&target_device).await.unwrap();
+        cap_manager.update_channel_state(target_device, ctype, state);
+        channels.insert(ctype, channel);
+    }
+
+    let router = Router::new(channels, cap_manager.clone());
+    let mut msg = test_text_message("invalidate");
+    msg.recipient = target_device;
+
+    cap_manager.invalidate_channel(target_device, ChannelType::BluetoothLE);
+    assert!(cap_manager
+        .get_channel_state(&target_device, &ChannelType::BluetoothLE)
+        .is_none());
+    let selected = router.select_channel(&msg).await.unwrap();
+    assert_eq!(selected.channel_type(), ChannelType::Lan);
+
+    cap_manager.invalidate_device(target_device);
+    assert!(router.select_channel(&msg).await.is_err());
+}
+
 #[tokio::test]
 async fn test_capability_detection() {
     // UT-CAP-001/004: 能力与电池检测
@@ -105,6 +897,54 @@ async fn test_capability_detection() {
     assert_eq!(detected.battery_level, Some(75));
 }
 
+#[test]
+fn test_negotiate_channels_intersects_and_orders_by_score() {
+    let mut local = test_device_capabilities();
+    local.supported_channels = HashSet::from([
+        ChannelType::Lan,
+        ChannelType::WiFiDirect,
+        ChannelType::Internet,
+    ]);
+    let cap_manager = CapabilityManager::new(local);
+
+    // 未注册能力的对端返回能力不匹配而不是空列表
+    let unknown = test_device_id();
+    let err = cap_manager.negotiate_channels(unknown).unwrap_err();
+    assert_eq!(err.code().0, 901);
+
+    let mut peer = test_device_capabilities();
+    peer.supported_channels = HashSet::from([
+        ChannelType::WiFiDirect,
+        ChannelType::Internet,
+        ChannelType::BluetoothLE,
+    ]);
+    cap_manager.register_remote_device(peer.clone());
+    let fast = ChannelState {
+        available: true,
+        rtt_ms: 5,
+        ..ChannelState::default()
+    };
+    let down = ChannelState {
+        available: false,
+        ..ChannelState::default()
+    };
+    cap_manager.update_channel_state(peer.device_id, ChannelType::WiFiDirect, down);
+    cap_manager.update_channel_state(peer.device_id, ChannelType::Internet, fast);
+    // 蓝牙只有对端支持，不参与协商；可用的公网通道排在当前不可用的 WiFi Direct 之前
+    assert_eq!(
+        cap_manager.negotiate_channels(peer.device_id).unwrap(),
+        vec![ChannelType::Internet, ChannelType::WiFiDirect]
+    );
+
+    // 没有共同通道时返回空列表
+    peer.supported_channels = HashSet::from([ChannelType::BluetoothMesh]);
+    cap_manager.register_remote_device(peer.clone());
+    assert!(cap_manager
+        .negotiate_channels(peer.device_id)
+        .unwrap()
+        .is_empty());
+}
+
 // ==================== Heartbeat Manager Tests ====================
 
 #[tokio::test]
@@ -122,16 +962,214 @@ async fn test_heartbeat_ping_pong() {
         sender: d2,
         recipient: d1,
         group_id: None,
-        payload: MessagePayload::Ping(12345),
+        payload: MessagePayload::Ping(12345, None),
         timestamp: 12345,
         priority: xlink::core::types::MessagePriority::Normal,
         require_ack: false,
+        require_ordered: false,
+        sequence: None,
+        sequence_epoch: None,
+        correlation_id: None,
+        in_reply_to: None,
+        topic: None,
+        expires_at: None,
+        signature: None,
     };
 
     heartbeat_manager.handle_heartbeat(&ping).await;
     // Success means no panic during handling
 }
 
+#[test]
+fn test_heartbeat_interval_adapts_to_link_quality() {
+    // UT-HBT-003: 心跳间隔随 RTT 与失败次数自适应
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let router = Arc::new(Router::new(HashMap::new(), cap_manager.clone()));
+    let heartbeat_manager = HeartbeatManager::new(test_device_id(), router, cap_manager);
+    heartbeat_manager.set_interval_bounds(Duration::from_secs(5), Duration::from_secs(60));
+
+    let stable = ChannelState {
+        rtt_ms: 0,
+        failure_count: 0,
+        ..ChannelState::default()
+    };
+    assert_eq!(
+        heartbeat_manager.next_interval(&stable),
+        Duration::from_secs(60)
+    );
+
+    // 延迟越高间隔越短，但不低于下限
+    let slow = ChannelState {
+        rtt_ms: 500,
+        ..stable.clone()
+    };
+    let interval = heartbeat_manager.next_interval(&slow);
+    assert!(interval > Duration::from_secs(5) && interval < Duration::from_secs(60));
+    let very_slow = ChannelState {
+        rtt_ms: 5000,
+        ..stable.clone()
+    };
+    assert_eq!(
+        heartbeat_manager.next_interval(&very_slow),
+        Duration::from_secs(5)
+    );
+
+    // 近期失败使间隔逐次减半直至下限
+    let failing = |failure_count| ChannelState {
+        failure_count,
+        ..stable.clone()
+    };
+    assert_eq!(
+        heartbeat_manager.next_interval(&failing(1)),
+        Duration::from_secs(30)
+    );
+    assert_eq!(
+        heartbeat_manager.next_interval(&failing(2)),
+        Duration::from_secs(15)
+    );
+    assert_eq!(
+        heartbeat_manager.next_interval(&failing(40)),
+        Duration::from_secs(5)
+    );
+}
+
+#[tokio::test]
+async fn test_silent_peer_marked_offline_after_three_max_intervals() {
+    let local = test_device_capabilities();
+    let cap_manager = Arc::new(CapabilityManager::new(local.clone()));
+    let router = Arc::new(Router::new(HashMap::new(), cap_manager.clone()));
+    let mut heartbeat_manager = HeartbeatManager::new(local.device_id, router, cap_manager.clone());
+    heartbeat_manager.set_interval_bounds(Duration::from_millis(20), Duration::from_millis(100));
+
+    let peer = test_device_capabilities();
+    cap_manager.register_remote_device(peer.clone());
+    cap_manager.update_channel_state(
+        peer.device_id,
+        ChannelType::Internet,
+        ChannelState {
+            available: true,
+            ..ChannelState::default()
+        },
+    );
+
+    let task = heartbeat_manager.start().unwrap();
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let state = cap_manager
+        .get_channel_state(&peer.device_id, &ChannelType::Internet)
+        .unwrap();
+    assert!(state.available, "peer should stay online before 3 * max");
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let state = cap_manager
+        .get_channel_state(&peer.device_id, &ChannelType::Internet)
+        .unwrap();
+    assert!(!state.available, "silent peer should be marked offline");
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_heartbeat_offline_detection_follows_injected_clock() {
+    // UT-HBT-004: 离线判定按注入的时钟计时，时钟不推进时静默的对端保持在线
+    let local = test_device_capabilities();
+    let cap_manager = Arc::new(CapabilityManager::new(local.clone()));
+    let router = Arc::new(Router::new(HashMap::new(), cap_manager.clone()));
+    let clock = Arc::new(MockClock::new());
+    let mut heartbeat_manager =
+        HeartbeatManager::with_clock(local.device_id, router, cap_manager.clone(), clock.clone());
+    heartbeat_manager.set_interval_bounds(Duration::from_millis(20), Duration::from_secs(60));
+
+    let peer = test_device_capabilities();
+    cap_manager.register_remote_device(peer.clone());
+    cap_manager.update_channel_state(
+        peer.device_id,
+        ChannelType::Internet,
+        ChannelState {
+            available: true,
+            ..ChannelState::default()
+        },
+    );
+    let available = || {
+        cap_manager
+            .get_channel_state(&peer.device_id, &ChannelType::Internet)
+            .unwrap()
+            .available
+    };
+
+    let task = heartbeat_manager.start().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(
+        available(),
+        "peer should stay online while the clock is frozen"
+    );
+
+    // 推进超过 3 倍最长间隔后，下一个心跳周期即标记离线
+    clock.advance(Duration::from_secs(181));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!available(), "silent peer should be marked offline");
+    task.abort();
+}
+
+// ==================== Message Encoding Tests ====================
+
+#[test]
+fn test_message_canonical_bytes_are_deterministic() {
+    let mut message = xlink::core::types::Message::new(
+        test_device_id(),
+        test_device_id(),
+        MessagePayload::Text("hello".to_string()),
+    );
+    message.sequence = Some(7);
+    message.topic = Some("chat".to_string());
+
+    // 同一消息的 JSON 字段顺序被打乱后，规范字节不变
+    let json = serde_json::to_value(&message).unwrap();
+    let mut fields: Vec<_> = json.as_object().unwrap().iter().collect();
+    fields.reverse();
+    let reordered = format!(
+        "{{{}}}",
+        fields
+            .iter()
+            .map(|(k, v)| format!("{:?}:{}", k, v))
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let decoded: xlink::core::types::Message = serde_json::from_str(&reordered).unwrap();
+    assert_eq!(decoded.canonical_bytes(), message.canonical_bytes());
+    assert_eq!(message.canonical_bytes(), message.clone().canonical_bytes());
+
+    // 任一字段变化都会改变规范字节
+    let mut changed = message.clone();
+    changed.payload = MessagePayload::Text("hellO".to_string());
+    assert_ne!(changed.canonical_bytes(), message.canonical_bytes());
+    let mut changed = message.clone();
+    changed.sequence = Some(8);
+    assert_ne!(changed.canonical_bytes(), message.canonical_bytes());
+}
+
+#[test]
+fn test_message_canonical_bytes_stay_small_for_large_payloads() {
+    // 大负载只以摘要参与规范字节，签名不会随负载大小放大内存占用
+    let mut message = xlink::core::types::Message::new(
+        test_device_id(),
+        test_device_id(),
+        MessagePayload::Binary(vec![0xA5; 8 * 1024 * 1024]),
+    );
+    let canonical = message.canonical_bytes();
+    assert!(canonical.len() < 1024, "got {} bytes", canonical.len());
+    assert_eq!(message.signing_bytes(), canonical);
+
+    if let MessagePayload::Binary(data) = &mut message.payload {
+        data[4 * 1024 * 1024] ^= 1;
+    }
+    assert_ne!(message.canonical_bytes(), canonical);
+
+    // 签名只影响规范字节，不影响签名覆盖的字节
+    let signing = message.signing_bytes();
+    message.signature = Some(vec![1; 64]);
+    assert_eq!(message.signing_bytes(), signing);
+    assert_ne!(message.canonical_bytes(), signing);
+}
+
 // ==================== Error Handling Tests ====================
 
 #[test]
@@ -213,6 +1251,110 @@ fn test_error_chain() {
     assert_eq!(outer.source.as_ref().unwrap().message(), "输入参数无效");
 }
 
+#[tokio::test]
+async fn test_send_failures_feed_error_statistics() {
+    // 发送失败计入 SDK 错误统计，报告包含常见错误码、类别计数与最近一分钟的次数
+    let sdk = xlink::XLink::with_storage(
+        test_device_capabilities(),
+        vec![],
+        Arc::new(xlink::storage::memory_store::MemoryStorage::new()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(sdk.error_statistics_report().total, 0);
+
+    let peer = test_device_id();
+    let mut last_err = None;
+    for _ in 0..3 {
+        last_err = sdk
+            .send(peer, MessagePayload::Text("unreachable".to_string()))
+            .await
+            .err();
+    }
+    let err = last_err.unwrap();
+    assert_eq!(err.code().0, 105);
+
+    let report = sdk.error_statistics_report();
+    assert_eq!(report.total, 3);
+    assert_eq!(report.most_common, vec![(105, 3)]);
+    assert_eq!(report.by_category[err.category().name()], 3);
+    assert_eq!(report.last_minute[&105], 3);
+    assert!(report.last_error.is_some());
+}
+
+#[test]
+fn test_backoff_delay_doubles_with_jitter() {
+    let suggestion = Some(RetrySuggestion::Retryable {
+        max_attempts: 3,
+        base_delay_ms: 100,
+    });
+    for (attempt, base) in [(0, 100), (1, 200), (2, 400)] {
+        let delay = backoff_delay(suggestion, attempt).unwrap();
+        assert!(delay >= Duration::from_millis(base));
+        assert!(delay <= Duration::from_millis(base + base / 2));
+    }
+    assert_eq!(backoff_delay(suggestion, 3), None);
+    assert_eq!(backoff_delay(Some(RetrySuggestion::NoRetry), 0), None);
+    assert_eq!(backoff_delay(None, 0), None);
+}
+
+#[test]
+fn test_reconnect_backoff_doubles_caps_and_resets() {
+    let config = ReconnectConfig {
+        enabled: true,
+        initial_delay_ms: 100,
+        max_delay_ms: 500,
+    };
+    let mut backoff = ReconnectBackoff::new(config);
+    let delays: Vec<u64> = (0..5)
+        .map(|_| backoff.next_delay().as_millis() as u64)
+        .collect();
+    assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+    assert_eq!(backoff.attempts(), 5);
+
+    backoff.reset(ReconnectConfig {
+        initial_delay_ms: 10,
+        ..config
+    });
+    assert_eq!(backoff.attempts(), 0);
+    assert_eq!(backoff.next_delay(), Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn test_retry_with_suggestion_stops_on_success_or_non_retryable() {
+    let fast = RetrySuggestion::Retryable {
+        max_attempts: 5,
+        base_delay_ms: 1,
+    };
+
+    let calls = std::sync::atomic::AtomicU32::new(0);
+    let value = retry_with_suggestion(|| async {
+        let call = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if call < 2 {
+            Err(XLinkError::channel_disconnected("flap", file!()).with_retry_suggestion(fast))
+        } else {
+            Ok(call)
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(value, 2);
+
+    // 不可重试的错误立即返回
+    let calls = std::sync::atomic::AtomicU32::new(0);
+    let err = retry_with_suggestion(|| async {
+        calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Err::<(), _>(XLinkError::invalid_input("field", "bad", file!()))
+    })
+    .await
+    .unwrap_err();
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(
+        err.code(),
+        XLinkError::invalid_input("field", "bad", file!()).code()
+    );
+}
+
 // ==================== Crypto Module Tests ====================
 
 #[test]
@@ -273,6 +1415,363 @@ fn test_session_expiration() {
     assert!(!sessions.sessions.is_empty());
 }
 
+#[test]
+fn test_direct_ciphertext_bound_to_context() {
+    // 一对一密文与发送方/接收方绑定，挪用到其他接收方时解密失败
+    use xlink::crypto::context::EncryptionContext;
+    use xlink::crypto::engine::CryptoEngine;
+
+    let alice = CryptoEngine::new();
+    let bob = CryptoEngine::new();
+    let alice_id = test_device_id();
+    let bob_id = test_device_id();
+    let carol_id = test_device_id();
+    alice.establish_session(bob_id, bob.public_key()).unwrap();
+    bob.establish_session(alice_id, alice.public_key()).unwrap();
+
+    let context = EncryptionContext::direct(alice_id, bob_id);
+    let ciphertext = alice.encrypt(&bob_id, b"hello bob", &context).unwrap();
+    assert_eq!(
+        bob.decrypt(&alice_id, &ciphertext, &context).unwrap(),
+        b"hello bob"
+    );
+
+    let ciphertext = alice.encrypt(&bob_id, b"for bob only", &context).unwrap();
+    let moved = EncryptionContext::direct(alice_id, carol_id);
+    assert!(bob.decrypt(&alice_id, &ciphertext, &moved).is_err());
+}
+
+#[test]
+fn test_mlkem_encapsulation_round_trip() {
+    // ML-KEM-768 封装与解封装得到相同共享密钥，篡改密文时隐式拒绝
+    use xlink::crypto::mlkem::{self, MlKemKeyPair};
+
+    let keypair = MlKemKeyPair::generate();
+    assert_eq!(keypair.public_key().len(), mlkem::PUBLIC_KEY_BYTES);
+    let (shared, mut ciphertext) = mlkem::encapsulate(keypair.public_key()).unwrap();
+    assert_eq!(ciphertext.len(), mlkem::CIPHERTEXT_BYTES);
+    assert_eq!(keypair.decapsulate(&ciphertext).unwrap(), shared);
+
+    let restored = MlKemKeyPair::from_seed(keypair.seed());
+    assert_eq!(restored.public_key(), keypair.public_key());
+
+    ciphertext[0] ^= 1;
+    assert_ne!(keypair.decapsulate(&ciphertext).unwrap(), shared);
+    assert!(keypair.decapsulate(&ciphertext[1..]).is_err());
+    assert!(mlkem::encapsulate(&[0u8; 16]).is_err());
+}
+
+/// NIST ACVP ML-KEM-encapDecap-FIPS203 internalProjection，tgId 2 tcId 26 的封装公钥
+const ACVP_ENCAP_EK: &str = concat!(
+    "89d2cb65f94dcbfc890efc7d0e5a7a38344d1641a3d0b024d50797a5f23c3a18b3101a1269069f43a842bacc",
+    "098a8821271c673db1beb33034e4d7774d16635c7c2c3c2763453538bc1632e1851591a51642974e5928abb8",
+    "e55fe55612f9b141aff015545394b2092e590970ec29a7b7e7aa1fb4493bf7cb731906c2a5cb49e661485906",
+    "4e19b8fa26af51c44b5e7535bfdac072b646d3ea490d277f0d97ced47395fed91e8f2bce0e3ca122c2025f74",
+    "067ab928a822b35653a74f06757629afb1a1caf237100ea935e793c8f58a71b3d6ae2c8658b10150d4a38f57",
+    "2a0d49d28ae89451d338326fdb3b4350036c1081117740edb86b12081c5c1223dbb5660d5b3cb3787d481849",
+    "304c68be875466f14ee5495c2bd795ae412d09002d65b8719b90cba3603ac4958ea03cc138c86f7851593125",
+    "334701b677f82f4952a4c93b5b4c134bb42a857fd15c650864a6aa94eb691c0b691be4684c1f5b7490467fc0",
+    "1b1d1fda4dda35c4ecc231bc73a6fef42c99d34eb82a4d014987b3e386910c62679a118f3c5bd9f467e41620",
+    "42424357db92ef484a4a1798c1257e870a30cb20aaa0335d83314fe0aa7e63a862648041a72a6321523220b1",
+    "ace9bb701b21ac1253cb812c15575a9085eabeade73a4ae76e6a7b158a20586d78a5ac620a5c9abcc9c04335",
+    "0a73656b0abe822da5e0ba76045fad75401d7a3b703791b7e99261710f86b72421d240a347638377205a152c",
+    "794130a4e047742b888303bddc309116764de7424cebea6db65348ac537e01a9cc56ea667d5aa87ac9aaa431",
+    "7d262c10143050b8d07a728ca633c13e468abcead372c77b8ecf3b986b98c1e55860b2b4216766ad874c35ed",
+    "7205068739230220b5a2317d102c598356f168acbe80608de4c9a710b8dd07078cd7c671058af1b0b8304a31",
+    "4f7b29be78a933c7b9294424954a1bf8bc745de86198659e0e1225a910726074969c39a97c19240601a46e01",
+    "3dcdcb677a8cbd2c95a40629c256f24a328951df57502ab30772cc7e5b850027c8551781ce4985bdacf6b865",
+    "c104e8a4bc65c41694d456b7169e45ab3d7acabeafe23ad6a7b94d1979a2f4c1cae7cd77d681d290b5d8e451",
+    "bfdcccf5310b9d12a88ec29b10255d5e17a192670aa9731c5ca67ec784c502781be8527d6fc003c6701b3632",
+    "284b40307a527c7620377feb0b73f722c9e3cd4dec64876b93ab5b7cfc4a657f852b659282864384f442b22e",
+    "8a21109387b8b47585fc680d0ba45c7a8b1d7274bda57845d100d0f42a3b74628773351fd7ac305b2497639b",
+    "e90b3f4f71a6aa3561eecc6a691bb5cb3914d8634ca1e1af543c049a8c6e868c51f0423bd2d5ae09b79e57c2",
+    "7f3fe3ae2b26a441babfc6718ce8c05b4fe793b910b8fbcbbe7f1013242b40e0514d0bdc5c88bac594c794ce",
+    "5122fbf34896819147b928381587963b0b90034aa07a10be176e01c80ad6a4b71b10af4241400a2a4cbbc059",
+    "61a15ec1474ed51a3cc6d35800679a462809caa3ab4f7094cd6610b4a700cba939e7eac93e38c99755908727",
+    "619ed76a34e53c4fa25bfc97008206697dd145e5b9188e5b014e941681e15fe3e132b8a3903474148ba28b98",
+    "7111c9bcb3989bbbc671c581b44a492845f288e62196e471fed3c39c1bbddb0837d0d4706b0922c4",
+);
+
+#[test]
+fn test_mlkem_matches_nist_acvp_vectors() {
+    // ML-KEM-768 密钥生成与封装结果与 NIST ACVP 已知答案一致（公钥与密文以 SHA-256 比对）
+    use sha2::{Digest, Sha256};
+    use xlink::crypto::mlkem::{self, MlKemKeyPair};
+
+    // ML-KEM-keyGen-FIPS203 internalProjection，tgId 2 tcId 26-28：(d, z, SHA-256(ek))
+    let keygen = [
+        (
+            "E34A701C4C87582F42264EE422D3C684D97611F2523EFE0C998AF05056D693DC",
+            "A85768F3486BD32A01BF9A8F21EA938E648EAE4E5448C34C3EB88820B159EEDD",
+            "7799c9d8eef172aa78c073514f2f039c240de8c5cb61bca82ba0bc46041ce279",
+        ),
+        (
+            "444F032DD19AE7518C4B35B0732A41DC567845ABA8BD7B04A9C413A0CF2DE0B5",
+            "DF0F282411F4A071489A8F618E2AE5AEF40131CAC5233D6D731522720C2FEB1C",
+            "9d027d1bffe13e5b754e793c6b54f92ce2f12858a3bf74421eba8622cd911670",
+        ),
+        (
+            "092271D05CA63C60880AF404D60BC4BB9539E2EA12969581898D56E0AC9A5A68",
+            "5AA6DC620A6E9A60CF19A7B4F0FF805BDA8219522A548EE5857C3FF6060C7A2F",
+            "304a6fb283e56f116cc78afa3bfc772a178ea92747478108527582ea331b90f1",
+        ),
+    ];
+    for (d, z, ek_digest) in keygen {
+        let mut seed = [0u8; mlkem::SEED_BYTES];
+        hex::decode_to_slice(d, &mut seed[..32]).unwrap();
+        hex::decode_to_slice(z, &mut seed[32..]).unwrap();
+        let keypair = MlKemKeyPair::from_seed(&seed);
+        assert_eq!(hex::encode(Sha256::digest(keypair.public_key())), ek_digest);
+    }
+
+    // 封装：(m, K, SHA-256(c))
+    let ek = hex::decode(ACVP_ENCAP_EK).unwrap();
+    let m: [u8; 32] =
+        hex::decode("2CE74AD291133518FE60C7DF5D251B9D82ADD48462FF505C6E547E949E6B6BF7")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let (shared, ciphertext) = mlkem::encapsulate_deterministic(&ek, &m).unwrap();
+    assert_eq!(
+        hex::encode(shared),
+        "2696d28e9c61c2a01ce9b1608dcb9d292785a0cd58efb7fe13b1de95f0db55b3"
+    );
+    assert_eq!(
+        hex::encode(Sha256::digest(&ciphertext)),
+        "ac57163b80ead205b8323e1402b8ca66bece40d8df9994b12d43bbb4f6e19bf4"
+    );
+}
+
+#[test]
+fn test_hybrid_session_round_trip_and_state_export() {
+    // 双方均为混合模式时协商混合会话，导出再导入后仍能继续解密
+    use xlink::crypto::context::EncryptionContext;
+    use xlink::crypto::engine::{CryptoEngine, HYBRID_WIRE_VERSION};
+
+    let alice = CryptoEngine::new_hybrid();
+    let bob = CryptoEngine::new_hybrid();
+    assert!(alice.is_hybrid());
+    let alice_id = test_device_id();
+    let bob_id = test_device_id();
+
+    let kem_ciphertext = alice
+        .initiate_session(bob_id, bob.public_key(), bob.kem_public_key().as_deref())
+        .unwrap()
+        .expect("both peers are hybrid");
+    bob.accept_hybrid_session(alice_id, alice.public_key(), &kem_ciphertext)
+        .unwrap();
+    assert_eq!(alice.is_session_hybrid(&bob_id), Some(true));
+
+    let context = EncryptionContext::direct(alice_id, bob_id);
+    let ciphertext = alice.encrypt(&bob_id, b"post-quantum", &context).unwrap();
+    assert_eq!(ciphertext[0], HYBRID_WIRE_VERSION);
+    assert_eq!(
+        bob.decrypt(&alice_id, &ciphertext, &context).unwrap(),
+        b"post-quantum"
+    );
+
+    let state = bob.export_state().unwrap();
+    assert!(state.kem_seed.is_some());
+    let restored = CryptoEngine::import_state(state).unwrap();
+    assert_eq!(restored.kem_public_key(), bob.kem_public_key());
+    assert_eq!(restored.is_session_hybrid(&alice_id), Some(true));
+    let ciphertext = alice.encrypt(&bob_id, b"after restart", &context).unwrap();
+    assert_eq!(
+        restored.decrypt(&alice_id, &ciphertext, &context).unwrap(),
+        b"after restart"
+    );
+}
+
+#[test]
+fn test_hybrid_session_downgrades_for_classic_peer() {
+    // 对端未广播 ML-KEM 公钥时降级为经典会话，经典引擎拒绝混合握手与混合密文
+    use xlink::crypto::context::EncryptionContext;
+    use xlink::crypto::engine::CryptoEngine;
+
+    let alice = CryptoEngine::new_hybrid();
+    let classic = CryptoEngine::new();
+    assert!(classic.kem_public_key().is_none());
+    let alice_id = test_device_id();
+    let classic_id = test_device_id();
+
+    let kem_ciphertext = alice
+        .initiate_session(
+            classic_id,
+            classic.public_key(),
+            classic.kem_public_key().as_deref(),
+        )
+        .unwrap();
+    assert!(kem_ciphertext.is_none());
+    classic
+        .establish_session(alice_id, alice.public_key())
+        .unwrap();
+    assert_eq!(alice.is_session_hybrid(&classic_id), Some(false));
+
+    let context = EncryptionContext::direct(alice_id, classic_id);
+    let ciphertext = alice.encrypt(&classic_id, b"classic", &context).unwrap();
+    assert_eq!(
+        classic.decrypt(&alice_id, &ciphertext, &context).unwrap(),
+        b"classic"
+    );
+
+    let err = classic
+        .accept_hybrid_session(alice_id, alice.public_key(), &[0u8; 1088])
+        .unwrap_err();
+    assert_eq!(err.code().0, 801);
+}
+
+#[test]
+fn test_hybrid_ciphertext_rejects_wrong_version() {
+    // 混合会话拒绝版本字节不符或被剥离的密文，版本不符的拒绝不影响后续解密
+    use xlink::crypto::context::EncryptionContext;
+    use xlink::crypto::engine::CryptoEngine;
+
+    let alice = CryptoEngine::new_hybrid();
+    let bob = CryptoEngine::new_hybrid();
+    let alice_id = test_device_id();
+    let bob_id = test_device_id();
+    let kem_ciphertext = alice
+        .initiate_session(bob_id, bob.public_key(), bob.kem_public_key().as_deref())
+        .unwrap()
+        .unwrap();
+    bob.accept_hybrid_session(alice_id, alice.public_key(), &kem_ciphertext)
+        .unwrap();
+
+    let context = EncryptionContext::direct(alice_id, bob_id);
+    let ciphertext = alice.encrypt(&bob_id, b"versioned", &context).unwrap();
+
+    let mut wrong_version = ciphertext.clone();
+    wrong_version[0] = 1;
+    let err = bob
+        .decrypt(&alice_id, &wrong_version, &context)
+        .unwrap_err();
+    assert_eq!(err.code().0, 801);
+
+    assert_eq!(
+        bob.decrypt(&alice_id, &ciphertext, &context).unwrap(),
+        b"versioned"
+    );
+
+    let ciphertext = alice.encrypt(&bob_id, b"stripped", &context).unwrap();
+    assert!(bob.decrypt(&alice_id, &ciphertext[1..], &context).is_err());
+}
+
+#[tokio::test]
+async fn test_heartbeat_presence_hint_updates_remote_capabilities() {
+    // UT-HBT-003: 携带能力摘要的心跳更新对端能力，未注册的对端被忽略
+    let local = test_device_id();
+    let peer = test_device_id();
+    let cap_manager = Arc::new(CapabilityManager::new(test_device_capabilities()));
+    let router = Arc::new(Router::new(HashMap::new(), cap_manager.clone()));
+    let heartbeat_manager = HeartbeatManager::new(local, router, cap_manager.clone());
+    heartbeat_manager.set_presence_hints(true);
+    assert!(heartbeat_manager.presence_hints());
+
+    cap_manager.register_remote_device(DeviceCapabilities {
+        device_id: peer,
+        battery_level: Some(90),
+        is_charging: false,
+        supported_channels: [ChannelType::Lan].into_iter().collect(),
+        ..test_device_capabilities()
+    });
+    let mut updated = cap_manager.get_remote_device(peer).unwrap();
+    updated.battery_level = Some(15);
+    updated.is_charging = true;
+    updated.supported_channels = [ChannelType::Lan, ChannelType::BluetoothLE]
+        .into_iter()
+        .collect();
+    let hint = PresenceHint::from_capabilities(&updated);
+
+    let ping = |sender| {
+        xlink::core::types::Message::new(sender, local, MessagePayload::Ping(1, Some(hint.clone())))
+    };
+    heartbeat_manager.handle_heartbeat(&ping(peer)).await;
+
+    let caps = cap_manager.get_remote_device(peer).unwrap();
+    assert_eq!(caps.battery_level, Some(15));
+    assert!(caps.is_charging);
+    assert_eq!(caps.supported_channels, updated.supported_channels);
+
+    let stranger = test_device_id();
+    heartbeat_manager.handle_heartbeat(&ping(stranger)).await;
+    assert!(cap_manager.get_remote_device(stranger).is_none());
+}
+
+#[tokio::test]
+async fn test_group_ciphertext_bound_to_context() {
+    // 群组密文与发送方/群组/纪元绑定，挪用到其他群组或冒用发送方时解密失败
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let member_id = test_device_id();
+    let member_key = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    sdk.register_device_key(member_id, member_key).unwrap();
+    let members = vec![sdk.device_id(), member_id];
+    let group_a = sdk
+        .create_group("A".to_string(), members.clone())
+        .await
+        .unwrap();
+    let group_b = sdk.create_group("B".to_string(), members).await.unwrap();
+
+    let payload = MessagePayload::Text("bound".to_string());
+    let encrypted = sdk.encrypt_group_message(group_a, &payload).unwrap();
+    assert_eq!(
+        sdk.decrypt_group_message(group_a, sdk.device_id(), &encrypted)
+            .unwrap(),
+        payload
+    );
+
+    assert!(sdk
+        .decrypt_group_message(group_b, sdk.device_id(), &encrypted)
+        .is_err());
+    assert!(sdk
+        .decrypt_group_message(group_a, member_id, &encrypted)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_revoked_peer_key_requires_fresh_key() {
+    // 撤销对端公钥后，需重新注册公钥才能再次为其加密
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    let peer_id = test_device_id();
+    let peer_key = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    sdk.register_device_key(peer_id, peer_key).unwrap();
+
+    let peers = sdk.trusted_peers();
+    assert_eq!(peers, vec![(peer_id, peer_key, false)]);
+
+    sdk.revoke_peer_key(peer_id).await.unwrap();
+    assert!(sdk.trusted_peers().is_empty());
+    assert!(sdk
+        .export_audit_logs()
+        .await
+        .unwrap()
+        .iter()
+        .any(|entry| entry.contains(&format!("Revoked peer key for device {}", peer_id))));
+
+    // 没有公钥时无法为该设备建立群组加密
+    let group_manager = sdk.group_manager();
+    assert!(group_manager
+        .create_group("Revoked".to_string(), vec![peer_id])
+        .await
+        .is_err());
+
+    // 重复撤销返回错误
+    assert!(sdk.revoke_peer_key(peer_id).await.is_err());
+
+    // 注册新公钥后恢复
+    let fresh_key = x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::random_from_rng(
+        rand::rngs::OsRng,
+    ));
+    sdk.register_device_key(peer_id, fresh_key).unwrap();
+    assert!(group_manager
+        .create_group("Fresh".to_string(), vec![peer_id])
+        .await
+        .is_ok());
+}
+
 // ==================== Storage Path Validation Tests ====================
 
 #[test]
@@ -288,3 +1787,116 @@ fn test_path_validation() {
     let traversal_path = Path::new("/tmp/xlink/../../../etc");
     assert!(traversal_path.to_string_lossy().contains(".."));
 }
+
+// ==================== Receive Pipeline Tests ====================
+
+/// 处理 `slow_sender` 的消息时阻塞，记录其余消息的处理顺序
+struct SlowSenderHandler {
+    slow_sender: xlink::core::types::DeviceId,
+    handled: tokio::sync::mpsc::UnboundedSender<xlink::core::types::Message>,
+}
+
+#[async_trait::async_trait]
+impl xlink::core::traits::MessageHandler for SlowSenderHandler {
+    async fn handle_message(
+        &self,
+        message: xlink::core::types::Message,
+    ) -> xlink::core::error::Result<()> {
+        if message.sender == self.slow_sender {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+        let _ = self.handled.send(message);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_receive_pool_slow_sender_does_not_block_others() {
+    use xlink::core::receive_pool::ReceivePool;
+    use xlink::core::types::Message;
+
+    let slow = test_device_id();
+    let (tx, mut handled) = tokio::sync::mpsc::unbounded_channel();
+    let pool = ReceivePool::new(
+        4,
+        16,
+        Arc::new(SlowSenderHandler {
+            slow_sender: slow,
+            handled: tx,
+        }),
+    );
+    assert_eq!(pool.size(), 4);
+    // 选一个与慢发送方分到不同工作者的发送方
+    let fast = std::iter::repeat_with(test_device_id)
+        .find(|id| pool.worker_for(id) != pool.worker_for(&slow))
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    pool.dispatch(Message::new(
+        slow,
+        fast,
+        MessagePayload::Text("slow".into()),
+    ))
+    .await
+    .unwrap();
+    for i in 0..5 {
+        pool.dispatch(Message::new(
+            fast,
+            slow,
+            MessagePayload::Text(i.to_string()),
+        ))
+        .await
+        .unwrap();
+    }
+
+    // 快发送方的消息不被阻塞，且保持发送顺序
+    for i in 0..5 {
+        let message = tokio::time::timeout(std::time::Duration::from_millis(500), handled.recv())
+            .await
+            .expect("fast sender blocked by slow sender")
+            .unwrap();
+        assert_eq!(message.sender, fast);
+        assert_eq!(message.payload, MessagePayload::Text(i.to_string()));
+    }
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+
+    let message = handled.recv().await.unwrap();
+    assert_eq!(message.sender, slow);
+}
+
+#[tokio::test]
+async fn test_receive_pipeline_preserves_per_sender_order() {
+    use xlink::core::types::{Message, ReceivePipelineConfig};
+
+    let sdk = TestSdkBuilder::new().build().await.unwrap();
+    assert_eq!(sdk.receive_pipeline_config().workers, 0);
+    sdk.set_receive_pipeline_config(ReceivePipelineConfig {
+        workers: 4,
+        queue_capacity: 8,
+    });
+    let handler = sdk.get_message_handler();
+    let senders = [test_device_id(), test_device_id()];
+    for i in 0..10 {
+        for sender in senders {
+            handler
+                .handle_message(Message::new(
+                    sender,
+                    sdk.device_id(),
+                    MessagePayload::Text(i.to_string()),
+                ))
+                .await
+                .unwrap();
+        }
+    }
+
+    let mut next = HashMap::new();
+    for _ in 0..20 {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(1), sdk.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        let expected = next.entry(message.sender).or_insert(0);
+        assert_eq!(message.payload, MessagePayload::Text(expected.to_string()));
+        *expected += 1;
+    }
+}